@@ -0,0 +1,25 @@
+#!/bin/sh
+# Regenerates the message template, merges it into every translation and
+# compiles the catalogs into locale/, where the app looks for them.
+# Run from the repository root; needs GNU gettext 0.24 or later.
+set -e
+
+# xgettext skips everything inside path-qualified macro calls such as
+# glib::clone!(...), so it reads copies with the path dropped. Line numbers
+# stay the same.
+tmp=$(mktemp -d)
+trap 'rm -rf "$tmp"' EXIT
+mkdir "$tmp/src"
+for file in $(cat po/POTFILES); do
+    sed 's/glib::clone!/clone!/g' "$file" > "$tmp/$file"
+done
+root=$(pwd)
+(cd "$tmp" && xgettext --language=Rust --keyword='tr!' --from-code=UTF-8 \
+    --package-name=rustLMS --add-location=file --sort-by-file \
+    --files-from="$root/po/POTFILES" --output="$root/po/rustLMS.pot")
+
+for lang in $(cat po/LINGUAS); do
+    msgmerge --quiet --update --backup=none "po/$lang.po" po/rustLMS.pot
+    mkdir -p "locale/$lang/LC_MESSAGES"
+    msgfmt --check --output-file="locale/$lang/LC_MESSAGES/rustLMS.mo" "po/$lang.po"
+done
@@ -0,0 +1,289 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::editing::{ItemDetails, NewItem};
+use crate::fines::format_money;
+use crate::i18n::tr;
+use crate::{LiItem, Library};
+
+/// A supplier the library buys from.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Vendor {
+    pub id: u32,
+    pub name: String,
+    // Free text: an email address, phone number or account number
+    pub contact: String,
+}
+
+/// Money set aside for buying stock, e.g. "Children's books 2026".
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Budget {
+    pub id: u32,
+    pub name: String,
+    pub allocated_cents: u64,
+}
+
+/// How a budget stands: what has been spent on received copies and what
+/// open orders will still spend.
+#[derive(Clone, Copy, Default)]
+pub struct BudgetUse {
+    pub spent_cents: u64,
+    pub committed_cents: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum OrderStatus {
+    Open,
+    Received,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn label(self) -> String {
+        match self {
+            OrderStatus::Open => tr!("Open"),
+            OrderStatus::Received => tr!("Received"),
+            OrderStatus::Cancelled => tr!("Cancelled"),
+        }
+    }
+}
+
+/// A title on an order, with how many copies to buy and their unit price.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct OrderLine {
+    pub title: String,
+    pub author: Option<String>,
+    pub isbn: Option<String>,
+    pub format: String,
+    pub quantity: u32,
+    pub price_cents: u64,
+    // Catalog item the copies went to once received
+    pub item_id: Option<u32>,
+}
+
+impl OrderLine {
+    pub fn total_cents(&self) -> u64 {
+        self.quantity as u64 * self.price_cents
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PurchaseOrder {
+    pub id: u32,
+    pub vendor_id: u32,
+    pub budget_id: u32,
+    pub lines: Vec<OrderLine>,
+    pub status: OrderStatus,
+    pub created_at: DateTime<Utc>,
+    pub received_at: Option<DateTime<Utc>>,
+}
+
+impl PurchaseOrder {
+    pub fn total_cents(&self) -> u64 {
+        self.lines.iter().map(OrderLine::total_cents).sum()
+    }
+}
+
+/// Vendors, budgets and the orders placed against them.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Acquisitions {
+    pub vendors: Vec<Vendor>,
+    pub budgets: Vec<Budget>,
+    pub orders: Vec<PurchaseOrder>,
+}
+
+impl Acquisitions {
+    pub fn vendor_name(&self, vendor_id: u32) -> String {
+        self.vendors.iter().find(|v| v.id == vendor_id).map_or_else(|| "?".to_string(), |v| v.name.clone())
+    }
+
+    pub fn budget_name(&self, budget_id: u32) -> String {
+        self.budgets.iter().find(|b| b.id == budget_id).map_or_else(|| "?".to_string(), |b| b.name.clone())
+    }
+
+    pub fn budget_use(&self, budget_id: u32) -> BudgetUse {
+        let mut used = BudgetUse::default();
+        for order in self.orders.iter().filter(|o| o.budget_id == budget_id) {
+            match order.status {
+                OrderStatus::Open => used.committed_cents += order.total_cents(),
+                OrderStatus::Received => used.spent_cents += order.total_cents(),
+                OrderStatus::Cancelled => {}
+            }
+        }
+        used
+    }
+
+    fn open_order(&mut self, order_id: u32) -> Result<&mut PurchaseOrder, String> {
+        let order = self.orders.iter_mut().find(|o| o.id == order_id).ok_or_else(|| tr!("Order not found"))?;
+        if order.status != OrderStatus::Open {
+            return Err(tr!("Order {} is no longer open", order_id));
+        }
+        Ok(order)
+    }
+}
+
+// The catalog item copies on an order line go to: the one with the same ISBN
+fn line_item<'a>(mut items: impl Iterator<Item = &'a mut LiItem>, line: &OrderLine) -> Option<&'a mut LiItem> {
+    let isbn = line.isbn.as_ref()?;
+    items.find(|i| i.isbn.as_ref() == Some(isbn))
+}
+
+impl Library {
+    pub fn add_vendor(&mut self, name: &str, contact: &str) -> Result<u32, String> {
+        self.only_at_server()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(tr!("Enter the vendor's name"));
+        }
+        let vendors = &mut self.acquisitions.vendors;
+        if vendors.iter().any(|v| v.name.eq_ignore_ascii_case(name)) {
+            return Err(tr!("There is already a vendor called {}", name));
+        }
+        let id = vendors.iter().map(|v| v.id).max().unwrap_or(0) + 1;
+        vendors.push(Vendor { id, name: name.to_string(), contact: contact.trim().to_string() });
+        self.dirty = true;
+        Ok(id)
+    }
+
+    pub fn add_budget(&mut self, name: &str, allocated_cents: u64) -> Result<u32, String> {
+        self.only_at_server()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(tr!("Enter the budget's name"));
+        }
+        let budgets = &mut self.acquisitions.budgets;
+        if budgets.iter().any(|b| b.name.eq_ignore_ascii_case(name)) {
+            return Err(tr!("There is already a budget called {}", name));
+        }
+        let id = budgets.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+        budgets.push(Budget { id, name: name.to_string(), allocated_cents });
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Starts an empty order from `vendor_id`, paid from `budget_id`.
+    pub fn create_order(&mut self, vendor_id: u32, budget_id: u32) -> Result<u32, String> {
+        self.only_at_server()?;
+        let acq = &mut self.acquisitions;
+        if !acq.vendors.iter().any(|v| v.id == vendor_id) {
+            return Err(tr!("Vendor not found"));
+        }
+        if !acq.budgets.iter().any(|b| b.id == budget_id) {
+            return Err(tr!("Budget not found"));
+        }
+        let id = acq.orders.iter().map(|o| o.id).max().unwrap_or(0) + 1;
+        acq.orders.push(PurchaseOrder {
+            id,
+            vendor_id,
+            budget_id,
+            lines: Vec::new(),
+            status: OrderStatus::Open,
+            created_at: self.clock.now(),
+            received_at: None,
+        });
+        tracing::info!(order_id = id, vendor_id, budget_id, "Purchase order created");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Adds a title to an open order. The order's budget must have room for
+    /// it after what is already spent and committed.
+    pub fn add_order_line(&mut self, order_id: u32, line: OrderLine) -> Result<(), String> {
+        self.only_at_server()?;
+        if line.title.trim().is_empty() {
+            return Err(tr!("Title cannot be empty"));
+        }
+        if line.format.trim().is_empty() {
+            return Err(tr!("Format cannot be empty"));
+        }
+        if line.quantity == 0 {
+            return Err(tr!("Order at least one copy"));
+        }
+        let acq = &mut self.acquisitions;
+        let budget_id = acq.open_order(order_id)?.budget_id;
+        let allocated = acq.budgets.iter().find(|b| b.id == budget_id).map_or(0, |b| b.allocated_cents);
+        let used = acq.budget_use(budget_id);
+        let left = allocated.saturating_sub(used.spent_cents + used.committed_cents);
+        if line.total_cents() > left {
+            return Err(tr!("The budget only has {} left", format_money(left)));
+        }
+        if let Some(item) = line_item(self.items.values_mut(), &line) {
+            item.copies_ordered(line.quantity);
+            let item_id = item.id;
+            self.item_changed(item_id);
+        }
+        self.acquisitions.open_order(order_id)?.lines.push(line);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn cancel_order(&mut self, order_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let order = self.acquisitions.open_order(order_id)?;
+        order.status = OrderStatus::Cancelled;
+        for line in order.lines.clone() {
+            if let Some(item) = line_item(self.items.values_mut(), &line) {
+                item.order_cancelled(line.quantity);
+                let item_id = item.id;
+                self.item_changed(item_id);
+            }
+        }
+        tracing::info!(order_id, "Purchase order cancelled");
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Books in everything on an open order: each line's copies are added to
+    /// the catalog item with the same ISBN, or to a new item, and wait
+    /// there in processing. Returns the number of copies added.
+    pub fn receive_order(&mut self, order_id: u32) -> Result<u32, String> {
+        self.only_at_server()?;
+        let lines = self.acquisitions.open_order(order_id)?.lines.clone();
+        if lines.is_empty() {
+            return Err(tr!("Order {} has no titles", order_id));
+        }
+
+        let mut item_ids = Vec::with_capacity(lines.len());
+        for line in &lines {
+            let existing = line_item(self.items.values_mut(), line).map(|item| item.id);
+            let item_id = match existing {
+                Some(item_id) => item_id,
+                None => {
+                    let item_id = self.add_item(NewItem {
+                        details: ItemDetails {
+                            title: line.title.clone(),
+                            author: line.author.clone(),
+                            year: 0,
+                            edition: String::new(),
+                            format: line.format.clone(),
+                            desc: String::new(),
+                        },
+                        isbn: line.isbn.clone(),
+                        call_number: String::new(),
+                        tags: Vec::new(),
+                        copies: line.quantity,
+                    })?;
+                    // Received below like copies of an existing item
+                    let item = self.items.get_mut(&item_id).unwrap();
+                    item.copies = 0;
+                    item.avail_copies = 0;
+                    item_id
+                }
+            };
+            self.items.get_mut(&item_id).unwrap().receive_copies(line.quantity);
+            self.item_changed(item_id);
+            item_ids.push(item_id);
+        }
+
+        let now = self.clock.now();
+        let order = self.acquisitions.open_order(order_id)?;
+        for (line, item_id) in order.lines.iter_mut().zip(item_ids) {
+            line.item_id = Some(item_id);
+        }
+        order.status = OrderStatus::Received;
+        order.received_at = Some(now);
+        tracing::info!(order_id, "Purchase order received");
+        self.dirty = true;
+        Ok(lines.iter().map(|l| l.quantity).sum())
+    }
+}
@@ -0,0 +1,125 @@
+use std::fs::File;
+use std::io::ErrorKind;
+
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::migrate;
+
+const USERS_PATH: &str = "users.json";
+
+/// Staff roles, ordered by privilege: each role can do everything the ones
+/// before it can.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum Role {
+    // Issue and return items
+    FrontDesk,
+    // Edit and import the catalog
+    Librarian,
+    // Manage users, settings and backups
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::FrontDesk, Role::Librarian, Role::Admin];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Role::FrontDesk => "Front Desk",
+            Role::Librarian => "Librarian",
+            Role::Admin => "Admin",
+        }
+    }
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct User {
+    pub username: String,
+    password_hash: String,
+    pub role: Role,
+}
+
+pub struct Session {
+    pub username: String,
+    pub role: Role,
+}
+
+#[derive(Default, Deserialize, Serialize)]
+pub struct UserStore {
+    users: Vec<User>,
+}
+
+impl UserStore {
+    /// Reads the staff accounts. Only a missing file counts as a first run
+    /// with no accounts; one that can't be opened or read is an error, so
+    /// it is never taken for a first run and saved over.
+    pub fn load() -> Result<UserStore, String> {
+        let file = match File::open(USERS_PATH) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(UserStore::default()),
+            Err(e) => return Err(tr!("Failed to open {}: {}", USERS_PATH, e)),
+        };
+        migrate::USERS.read(file).map_err(|e| tr!("Failed to read {}: {}", USERS_PATH, e))
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        migrate::USERS.write_pretty(File::create(USERS_PATH)?, self)?;
+        Ok(())
+    }
+
+    pub fn users(&self) -> &[User] {
+        &self.users
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    pub fn add_user(&mut self, username: &str, password: &str, role: Role) -> Result<(), String> {
+        let username = username.trim();
+        if username.is_empty() || password.is_empty() {
+            return Err(tr!("Username and password are required"));
+        }
+        if self.users.iter().any(|u| u.username == username) {
+            return Err(tr!("User \"{}\" already exists", username));
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| e.to_string())?
+            .to_string();
+
+        self.users.push(User {
+            username: username.to_string(),
+            password_hash,
+            role,
+        });
+        Ok(())
+    }
+
+    pub fn remove_user(&mut self, username: &str) -> Result<(), String> {
+        let Some(pos) = self.users.iter().position(|u| u.username == username) else {
+            return Err(tr!("User \"{}\" not found", username));
+        };
+        let admins = self.users.iter().filter(|u| u.role == Role::Admin).count();
+        if self.users[pos].role == Role::Admin && admins == 1 {
+            return Err(tr!("Cannot remove the last admin"));
+        }
+        self.users.remove(pos);
+        Ok(())
+    }
+
+    pub fn authenticate(&self, username: &str, password: &str) -> Option<Session> {
+        let user = self.users.iter().find(|u| u.username == username.trim())?;
+        let hash = PasswordHash::new(&user.password_hash).ok()?;
+        Argon2::default().verify_password(password.as_bytes(), &hash).ok()?;
+        Some(Session {
+            username: user.username.clone(),
+            role: user.role,
+        })
+    }
+}
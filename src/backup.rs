@@ -0,0 +1,61 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::config::Config;
+use crate::migrate;
+use crate::Library;
+
+const PREFIX: &str = "library-";
+const SUFFIX: &str = ".json.gz";
+
+/// Writes a gzip-compressed snapshot of the library into the backup
+/// directory and drops the oldest snapshots beyond the retention limit.
+pub fn backup_now(library: &Library, config: &Config) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    fs::create_dir_all(&config.backup_dir)?;
+    let name = format!("{}{}{}", PREFIX, Utc::now().format("%Y%m%d-%H%M%S"), SUFFIX);
+    let path = Path::new(&config.backup_dir).join(name);
+
+    let mut encoder = GzEncoder::new(BufWriter::new(File::create(&path)?), Compression::default());
+    migrate::LIBRARY.write(&mut encoder, library)?;
+    encoder.finish()?.flush()?;
+
+    for old in list_backups(config).iter().skip(config.backup_retention.max(1)) {
+        if let Err(e) = fs::remove_file(old) {
+            tracing::warn!("Failed to remove old backup {}: {}", old.display(), e);
+        }
+    }
+
+    tracing::info!(path = %path.display(), "Library backed up");
+    Ok(path)
+}
+
+/// Available snapshots, newest first. The timestamped names sort
+/// chronologically.
+pub fn list_backups(config: &Config) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(&config.backup_dir) else { return Vec::new() };
+    let mut backups: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(SUFFIX))
+        })
+        .collect();
+    backups.sort();
+    backups.reverse();
+    backups
+}
+
+pub fn restore(path: &Path) -> Result<Library, Box<dyn std::error::Error>> {
+    let decoder = GzDecoder::new(BufReader::new(File::open(path)?));
+    let mut library: Library = migrate::LIBRARY.read(decoder)?;
+    // Not saved to the state file yet
+    library.dirty = true;
+    Ok(library)
+}
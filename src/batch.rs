@@ -0,0 +1,112 @@
+use crate::copies::CopyStatus;
+use crate::editing::{Edit, ItemField};
+use crate::i18n::tr;
+use crate::Library;
+
+/// A change made to many catalog items at once.
+#[derive(Clone)]
+pub enum BatchChange {
+    AddGenre(String),
+    SetFormat(String),
+    // Copies added to each item, or taken away if negative
+    AdjustCopies(i32),
+    SetShelfLocation(String),
+    // Withdraws every copy on the shelf
+    Withdraw,
+}
+
+impl BatchChange {
+    /// The question to confirm the change for `count` items with.
+    pub fn confirm_text(&self, count: usize) -> String {
+        match self {
+            BatchChange::AddGenre(genre) => tr!("Add the genre \"{}\" to {} items?", genre, count),
+            BatchChange::SetFormat(format) => tr!("Change the format of {} items to \"{}\"?", count, format),
+            BatchChange::AdjustCopies(delta) if *delta >= 0 => tr!("Add {} copies to each of {} items?", delta, count),
+            BatchChange::AdjustCopies(delta) => tr!("Remove {} copies from each of {} items?", delta.unsigned_abs(), count),
+            BatchChange::SetShelfLocation(location) => tr!("Move {} items to the shelf location \"{}\"?", count, location),
+            BatchChange::Withdraw => tr!("Withdraw the copies on the shelf of {} items?", count),
+        }
+    }
+}
+
+/// What a batch edit did to each item.
+#[derive(Default)]
+pub struct BatchReport {
+    pub changed: Vec<u32>,
+    pub failed: Vec<(u32, String)>,
+}
+
+impl Library {
+    /// Makes `change` to each item in turn. Items it can't be made to are
+    /// left as they are and reported; the others are changed, and undoing
+    /// the edit reverts all of them.
+    pub fn batch_edit(&mut self, item_ids: &[u32], change: &BatchChange) -> BatchReport {
+        let mut report = BatchReport::default();
+        if let Err(e) = self.only_at_server() {
+            report.failed = item_ids.iter().map(|&id| (id, e.clone())).collect();
+            return report;
+        }
+        let mut edits = Vec::new();
+        for &item_id in item_ids {
+            match self.apply_batch_change(item_id, change) {
+                Ok(edit) => {
+                    edits.push(edit);
+                    report.changed.push(item_id);
+                }
+                Err(e) => report.failed.push((item_id, e)),
+            }
+        }
+        if !edits.is_empty() {
+            self.push_undo(Edit::Batch(edits));
+        }
+        tracing::info!(changed = report.changed.len(), failed = report.failed.len(), "Batch edit");
+        report
+    }
+
+    fn apply_batch_change(&mut self, item_id: u32, change: &BatchChange) -> Result<Edit, String> {
+        let field = |field: ItemField, old: String| Edit::Field(item_id, field, old);
+        match change {
+            BatchChange::AddGenre(genre) => {
+                let genre = genre.trim();
+                if genre.is_empty() {
+                    return Err(tr!("Enter a genre"));
+                }
+                let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                if item.tags.iter().any(|t| t.eq_ignore_ascii_case(genre)) {
+                    return Err(tr!("Already has the genre {}", genre));
+                }
+                let old = item.tags.clone();
+                let mut tags = old.clone();
+                tags.push(genre.to_string());
+                self.set_item_tags(item_id, tags)?;
+                Ok(Edit::Tags(item_id, old))
+            }
+            BatchChange::SetFormat(format) => {
+                self.apply_field(item_id, ItemField::Format, format).map(|old| field(ItemField::Format, old))
+            }
+            BatchChange::SetShelfLocation(location) => self
+                .apply_field(item_id, ItemField::ShelfLocation, location)
+                .map(|old| field(ItemField::ShelfLocation, old)),
+            BatchChange::AdjustCopies(delta) => {
+                let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                let copies = item.copies as i64 + *delta as i64;
+                if copies < 0 {
+                    return Err(tr!("Only has {} copies", item.copies));
+                }
+                self.apply_field(item_id, ItemField::Copies, &copies.to_string()).map(|old| field(ItemField::Copies, old))
+            }
+            BatchChange::Withdraw => {
+                let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                let count = item.avail_copies;
+                if count == 0 {
+                    return Err(tr!("No copies are on the shelf"));
+                }
+                item.move_copies(CopyStatus::Available, CopyStatus::Withdrawn, count)?;
+                tracing::info!(item_id, count, "Copies withdrawn");
+                self.dirty = true;
+                self.item_changed(item_id);
+                Ok(Edit::Withdrawn(item_id, count))
+            }
+        }
+    }
+}
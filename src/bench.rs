@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+use crate::i18n::tr;
+use crate::import::CsvSchema;
+use crate::query::{self, Query};
+use crate::roundtrip::TempFile;
+use crate::search;
+use crate::seed::SeedOptions;
+use crate::Library;
+
+// How many entries the items map used to reserve up front
+const OLD_PREALLOCATION: usize = 3_000_000;
+// Loans per member, below every membership's loan limit
+const LOANS_PER_MEMBER: u32 = 5;
+// Imports timed per way of parsing; the fastest counts
+const PARSE_RUNS: u32 = 3;
+// Searches timed per kind of search; the average counts
+const SEARCH_RUNS: u32 = 5;
+
+/// Catalog sizes `catalog` measures when not given one.
+pub const CATALOG_SIZES: [u32; 3] = [10_000, 100_000, 1_000_000];
+
+/// How big a catalog to measure with, from `--items` and `--loans`.
+pub struct BenchOptions {
+    pub items: u32,
+    pub loans: u32,
+}
+
+impl Default for BenchOptions {
+    fn default() -> BenchOptions {
+        BenchOptions { items: 100_000, loans: 50_000 }
+    }
+}
+
+/// The peak resident set size of this process so far, in KiB, where the
+/// system reports it.
+pub fn peak_rss_kib() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let line = status.lines().find(|l| l.starts_with("VmHWM:"))?;
+    line.split_whitespace().nth(1)?.parse().ok()
+}
+
+// Writes a made-up catalog of `items` titles, the same every run
+fn write_catalog(path: &Path, items: u32) -> Result<(), Box<dyn std::error::Error>> {
+    let mut library = Library::new();
+    library.seed(&SeedOptions { items, members: 0, loans: 0, seed: Some(1) });
+    library.export_catalog_csv(path)
+}
+
+/// Loads the catalog at `path` and lends `loans` of its items, the way the
+/// desk would, and prints the peak RSS. With `old_layout` it first
+/// reserves the items map the way the library used to and keeps a copy of
+/// each loan's title, as loans used to have, to compare against. Run in a
+/// process of its own so the peak is this load's alone.
+pub fn measure_memory(path: &Path, loans: u32, old_layout: bool) -> i32 {
+    let mut library = Library::new();
+    if old_layout {
+        library.items.reserve(OLD_PREALLOCATION);
+    }
+    if let Err(e) = library.initialize_lib(&path.to_string_lossy()) {
+        eprintln!("{}", tr!("Failed to load the catalog: {}", e));
+        return 1;
+    }
+
+    let mut item_ids: Vec<u32> = library.items.keys().copied().collect();
+    item_ids.sort();
+    let mut member_id = None;
+    let mut title_copies: Vec<String> = Vec::new();
+    let mut lent = 0;
+    for &item_id in item_ids.iter().take(loans as usize) {
+        if lent % LOANS_PER_MEMBER == 0 {
+            member_id = library.register_member("Bench").ok();
+        }
+        let Some(member_id) = member_id else { break };
+        if library.book_issue(item_id, member_id.to_string()).is_ok() {
+            lent += 1;
+            if old_layout {
+                title_copies.push(library.items[&item_id].title.to_string());
+            }
+        }
+    }
+    let title_bytes: usize = title_copies.iter().map(String::len).sum();
+    println!("{}\t{}\t{}\t{}", library.items.len(), lent, peak_rss_kib().unwrap_or(0), title_bytes);
+    0
+}
+
+// Runs `measure_memory` in a new process of this program and reads back
+// what it printed
+fn memory_in_child(path: &Path, loans: u32, old_layout: bool) -> Result<Vec<u64>, String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let mut command = Command::new(exe);
+    command.args(["bench", "memory-child", &path.to_string_lossy(), &loans.to_string()]);
+    if old_layout {
+        command.arg("--old-layout");
+    }
+    let output = command.output().map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .split('\t')
+        .map(|n| n.parse::<u64>().map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Compares the peak RSS of loading a generated catalog and lending part of
+/// it, as the library is laid out now and as it was before titles were
+/// shared and the items map stopped reserving three million entries.
+/// Prints one line per layout and the difference.
+pub fn memory(options: &BenchOptions) -> i32 {
+    if peak_rss_kib().is_none() {
+        eprintln!("{}", tr!("Peak memory use can only be measured on Linux"));
+        return 1;
+    }
+    let catalog = TempFile::new("bench-catalog.csv");
+    if let Err(e) = write_catalog(&catalog.0, options.items) {
+        eprintln!("{}", tr!("Failed to write the catalog: {}", e));
+        return 1;
+    }
+
+    let mut peaks = HashMap::new();
+    for old_layout in [true, false] {
+        let label = if old_layout { tr!("Before") } else { tr!("Now") };
+        match memory_in_child(&catalog.0, options.loans, old_layout) {
+            Ok(values) if values.len() == 4 => {
+                println!(
+                    "{}",
+                    tr!(
+                        "{}: {} items, {} loans, peak RSS {} KiB, {} KiB of copied titles",
+                        label,
+                        values[0],
+                        values[1],
+                        values[2],
+                        values[3] / 1024
+                    )
+                );
+                peaks.insert(old_layout, values[2]);
+            }
+            Ok(_) => {
+                eprintln!("{}", tr!("Unexpected output from the measurement"));
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("{}", tr!("Measurement failed: {}", e));
+                return 1;
+            }
+        }
+    }
+    let (before, now) = (peaks[&true], peaks[&false]);
+    let saved = before.saturating_sub(now);
+    let percent = if before > 0 { saved * 100 / before } else { 0 };
+    println!("{}", tr!("Peak RSS down by {} KiB ({}%)", saved, percent));
+    0
+}
+
+// The fastest of `PARSE_RUNS` imports of the catalog at `path` on `pool`
+fn time_import(path: &Path, pool: &rayon::ThreadPool) -> Result<(Duration, usize), String> {
+    let mut best: Option<(Duration, usize)> = None;
+    for _ in 0..PARSE_RUNS {
+        let mut library = Library::new();
+        let start = Instant::now();
+        // Errors aren't Send, so only their text leaves the pool
+        let loaded = pool.install(|| {
+            library.import_csv(&path.to_string_lossy(), &CsvSchema::default()).map(|r| r.loaded).map_err(|e| e.to_string())
+        })?;
+        let elapsed = start.elapsed();
+        if best.is_none_or(|(fastest, _)| elapsed < fastest) {
+            best = Some((elapsed, loaded));
+        }
+    }
+    Ok(best.unwrap_or_default())
+}
+
+/// Times importing a generated catalog with the records deserialized on
+/// one thread and on all of them, and prints both and the speedup.
+pub fn parse(options: &BenchOptions) -> i32 {
+    let catalog = TempFile::new("bench-parse.csv");
+    if let Err(e) = write_catalog(&catalog.0, options.items) {
+        eprintln!("{}", tr!("Failed to write the catalog: {}", e));
+        return 1;
+    }
+
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut times = Vec::new();
+    for threads in [1, threads] {
+        let pool = match rayon::ThreadPoolBuilder::new().num_threads(threads).build() {
+            Ok(pool) => pool,
+            Err(e) => {
+                eprintln!("{}", tr!("Measurement failed: {}", e));
+                return 1;
+            }
+        };
+        match time_import(&catalog.0, &pool) {
+            Ok((elapsed, rows)) => {
+                let per_second = rows as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+                println!(
+                    "{}",
+                    tr!("{} thread(s): {} rows in {} ms, {} rows/s", threads, rows, elapsed.as_millis(), per_second as u64)
+                );
+                times.push(elapsed);
+            }
+            Err(e) => {
+                eprintln!("{}", tr!("Measurement failed: {}", e));
+                return 1;
+            }
+        }
+    }
+    let speedup = times[0].as_secs_f64() / times[1].as_secs_f64().max(f64::EPSILON);
+    println!("{}", tr!("Parallel parsing is {}x as fast", format!("{:.1}", speedup)));
+    0
+}
+
+fn average(runs: u32, mut run: impl FnMut() -> usize) -> (Duration, usize) {
+    let start = Instant::now();
+    let mut found = 0;
+    for _ in 0..runs {
+        found = run();
+    }
+    (start.elapsed() / runs, found)
+}
+
+fn per_second(count: usize, elapsed: Duration) -> u64 {
+    (count as f64 / elapsed.as_secs_f64().max(f64::EPSILON)) as u64
+}
+
+// Times the search box, exactly and with a typo, and a field query, all
+// for words of the item in the middle of the catalog
+fn time_searches(library: &Library) -> Option<[(Duration, usize); 3]> {
+    let middle = library.items.keys().copied().max()? / 2;
+    let item = library.items.get(&middle).or_else(|| library.items.values().next())?;
+    let exact = item.title.to_string();
+    // One letter changed, so only the fuzzy matching finds it
+    let mut typo: Vec<char> = exact.chars().collect();
+    let n = typo.len() / 2;
+    typo[n] = if typo[n] == 'x' { 'y' } else { 'x' };
+    let typo: String = typo.into_iter().collect();
+    let author = item.author.as_deref()?.split(' ').next_back()?;
+    let query = Query::parse(&format!("author:{}", author)).ok()?;
+
+    Some([
+        average(SEARCH_RUNS, || search::search(library.items.values(), &exact).ids.len()),
+        average(SEARCH_RUNS, || search::search(library.items.values(), &typo).ids.len()),
+        average(SEARCH_RUNS, || query::run(library.items.values(), &query).len()),
+    ])
+}
+
+// Lends `loans` items and takes them all back, timing each half. Members
+// are signed up beforehand, so only the loans are timed.
+fn time_circulation(library: &mut Library, loans: u32) -> Result<(Duration, Duration, usize), String> {
+    let mut item_ids: Vec<u32> = library.items.keys().copied().collect();
+    item_ids.sort();
+    item_ids.truncate(loans as usize);
+    let members = (item_ids.len() as u32).div_ceil(LOANS_PER_MEMBER);
+    let member_ids = (0..members).map(|_| library.register_member("Bench")).collect::<Result<Vec<u32>, String>>()?;
+    let loans: Vec<(u32, u32)> =
+        item_ids.iter().enumerate().map(|(n, &item_id)| (item_id, member_ids[n / LOANS_PER_MEMBER as usize])).collect();
+
+    let start = Instant::now();
+    for &(item_id, member_id) in &loans {
+        library.book_issue(item_id, member_id.to_string())?;
+    }
+    let issued = start.elapsed();
+    let start = Instant::now();
+    for &(item_id, member_id) in &loans {
+        library.book_return(item_id, member_id)?;
+    }
+    Ok((issued, start.elapsed(), loans.len()))
+}
+
+/// Times, for a generated catalog of each of `sizes` items, importing it
+/// from CSV, searching it, and lending and taking back `loans` of its
+/// items, and prints a line per measurement.
+pub fn catalog(sizes: &[u32], loans: u32) -> i32 {
+    let pool = match rayon::ThreadPoolBuilder::new().build() {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("{}", tr!("Measurement failed: {}", e));
+            return 1;
+        }
+    };
+    for &items in sizes {
+        let catalog = TempFile::new("bench-catalog-size.csv");
+        if let Err(e) = write_catalog(&catalog.0, items) {
+            eprintln!("{}", tr!("Failed to write the catalog: {}", e));
+            return 1;
+        }
+        match time_import(&catalog.0, &pool) {
+            Ok((elapsed, rows)) => println!(
+                "{}",
+                tr!("{} items: import {} ms, {} rows/s", items, elapsed.as_millis(), per_second(rows, elapsed))
+            ),
+            Err(e) => {
+                eprintln!("{}", tr!("Measurement failed: {}", e));
+                return 1;
+            }
+        }
+
+        let mut library = Library::new();
+        if let Err(e) = library.initialize_lib(&catalog.0.to_string_lossy()) {
+            eprintln!("{}", tr!("Failed to load the catalog: {}", e));
+            return 1;
+        }
+        match time_searches(&library) {
+            Some([(exact, exact_found), (typo, typo_found), (field, field_found)]) => println!(
+                "{}",
+                tr!(
+                    "{} items: search {} ms ({} found), with a typo {} ms ({} found), by author {} ms ({} found)",
+                    items,
+                    exact.as_millis(),
+                    exact_found,
+                    typo.as_millis(),
+                    typo_found,
+                    field.as_millis(),
+                    field_found
+                )
+            ),
+            None => {
+                eprintln!("{}", tr!("Unexpected output from the measurement"));
+                return 1;
+            }
+        }
+        match time_circulation(&mut library, loans) {
+            Ok((issued, returned, count)) => println!(
+                "{}",
+                tr!(
+                    "{} items: {} issues/s, {} returns/s over {} loans",
+                    items,
+                    per_second(count, issued),
+                    per_second(count, returned),
+                    count
+                )
+            ),
+            Err(e) => {
+                eprintln!("{}", tr!("Measurement failed: {}", e));
+                return 1;
+            }
+        }
+    }
+    0
+}
@@ -0,0 +1,78 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use gtk::glib;
+use gtk::prelude::*;
+
+use crate::changes::LibraryChange;
+use crate::Library;
+
+// How soon pages hear about a change
+const PUBLISH_INTERVAL: Duration = Duration::from_millis(250);
+
+type Subscriber = (glib::WeakRef<gtk::Widget>, Rc<dyn Fn(&[LibraryChange])>);
+
+/// Tells the pages what changed in the library, whichever page or desk
+/// changed it. Mutations record their changes in the library's
+/// `ChangeLog`; the bus hands every subscriber the new ones a moment later.
+pub struct EventBus {
+    library: Arc<RwLock<Library>>,
+    // The revision already handed out
+    seen: Cell<u64>,
+    subscribers: RefCell<Vec<Subscriber>>,
+}
+
+impl EventBus {
+    pub fn start(library: Arc<RwLock<Library>>) -> Rc<EventBus> {
+        let seen = library.read().unwrap().changes.revision();
+        let bus = Rc::new(EventBus { library, seen: Cell::new(seen), subscribers: RefCell::new(Vec::new()) });
+        // The timer keeps the bus for as long as the application runs
+        let timer_bus = bus.clone();
+        glib::timeout_add_local(PUBLISH_INTERVAL, move || {
+            timer_bus.publish();
+            glib::ControlFlow::Continue
+        });
+        bus
+    }
+
+    /// Calls `f` with each batch of changes for as long as `owner`, usually
+    /// the page, exists. Hidden pages are told too, so they are up to date
+    /// when shown.
+    pub fn subscribe(&self, owner: &impl IsA<gtk::Widget>, f: impl Fn(&[LibraryChange]) + 'static) {
+        let owner = owner.upcast_ref::<gtk::Widget>().downgrade();
+        self.subscribers.borrow_mut().push((owner, Rc::new(f)));
+    }
+
+    /// Runs `fill` after each batch of changes while `page` is shown. Pages
+    /// like this refill whenever they are shown anyway, so hidden ones can
+    /// wait until then.
+    pub fn refill_when_shown(&self, page: &impl IsA<gtk::Widget>, fill: Rc<dyn Fn()>) {
+        let weak_page = page.upcast_ref::<gtk::Widget>().downgrade();
+        self.subscribe(page, move |_| {
+            if weak_page.upgrade().is_some_and(|page| page.is_mapped()) {
+                fill();
+            }
+        });
+    }
+
+    /// Hands out the changes made since the last call, if any.
+    pub fn publish(&self) {
+        let changes = {
+            let library = self.library.read().unwrap();
+            let revision = library.changes.revision();
+            if revision == self.seen.get() {
+                return;
+            }
+            library.changes.since(self.seen.replace(revision))
+        };
+        // Subscribers may subscribe or publish again, so none are borrowed
+        // while they run
+        self.subscribers.borrow_mut().retain(|(owner, _)| owner.upgrade().is_some());
+        let subscribers: Vec<_> = self.subscribers.borrow().iter().map(|(_, f)| f.clone()).collect();
+        for f in subscribers {
+            f(&changes);
+        }
+    }
+}
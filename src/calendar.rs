@@ -0,0 +1,46 @@
+use std::collections::BTreeSet;
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// Days the library is closed: the same weekdays every week plus individual
+/// holidays. Loans never fall due on a closed day and closed days don't
+/// count towards fines.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct ClosureCalendar {
+    pub closed_weekdays: Vec<Weekday>,
+    pub holidays: BTreeSet<NaiveDate>,
+}
+
+impl ClosureCalendar {
+    pub fn is_open(&self, date: NaiveDate) -> bool {
+        !self.closed_weekdays.contains(&date.weekday()) && !self.holidays.contains(&date)
+    }
+
+    pub fn next_open(&self, at: DateTime<Utc>) -> DateTime<Utc> {
+        let mut day = at;
+        // A calendar with every day closed would never end
+        for _ in 0..366 {
+            if self.is_open(day.date_naive()) {
+                return day;
+            }
+            day = day + Days::new(1);
+        }
+        at
+    }
+
+    /// The open days after `from`, up to and including `to`.
+    pub fn open_days(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> impl Iterator<Item = NaiveDate> + '_ {
+        let to = to.date_naive();
+        from.date_naive()
+            .iter_days()
+            .skip(1)
+            .take_while(move |day| *day <= to)
+            .filter(|day| self.is_open(*day))
+    }
+
+    /// Number of open days after `from`, up to and including `to`.
+    pub fn open_days_between(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> i64 {
+        self.open_days(from, to).count() as i64
+    }
+}
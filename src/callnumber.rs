@@ -0,0 +1,100 @@
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::i18n::tr;
+use crate::{Library, LiItem};
+
+// A piece of a call number. Whole numbers compare by value, decimal parts
+// and cutter numbers digit by digit, letters alphabetically. Numbers file
+// before letters.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum Part {
+    Number(u64),
+    Decimal(String),
+    Letters(String),
+}
+
+// Splits a Dewey ("823.914 T65") or Library of Congress ("PR6039.O32 H6
+// 1937") call number into parts. Digits after a point, or right after the
+// letters of a cutter, are decimals: .2 files before .914, and H58 before
+// H6.
+fn parts(call_number: &str) -> Vec<Part> {
+    let mut parts = Vec::new();
+    let mut chars = call_number.chars().peekable();
+    let mut after_point = false;
+    while let Some(&c) = chars.peek() {
+        if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                digits.push(d);
+            }
+            // The class letters at the very start are followed by a whole
+            // number, as in QA76
+            let in_cutter = parts.len() > 1 && matches!(parts.last(), Some(Part::Letters(_)));
+            if after_point || in_cutter {
+                parts.push(Part::Decimal(digits));
+            } else {
+                parts.push(Part::Number(digits.parse().unwrap_or(u64::MAX)));
+            }
+        } else if c.is_alphabetic() {
+            let mut letters = String::new();
+            while let Some(l) = chars.next_if(|l| l.is_alphabetic()) {
+                letters.extend(l.to_uppercase());
+            }
+            parts.push(Part::Letters(letters));
+        } else {
+            after_point = c == '.' || (after_point && !c.is_whitespace());
+            chars.next();
+        }
+    }
+    parts
+}
+
+/// Shelf order for call numbers. Blank ones go last.
+pub fn compare(a: &str, b: &str) -> Ordering {
+    match (a.trim().is_empty(), b.trim().is_empty()) {
+        (true, true) => Ordering::Equal,
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (false, false) => parts(a).cmp(&parts(b)).then_with(|| a.cmp(b)),
+    }
+}
+
+impl Library {
+    /// The catalog by shelf location, each location in call number order.
+    /// Items without a location are listed under "".
+    pub fn shelf_list(&self) -> BTreeMap<String, Vec<&LiItem>> {
+        let mut locations: BTreeMap<String, Vec<&LiItem>> = BTreeMap::new();
+        for item in self.items.values() {
+            locations.entry(item.shelf_location.trim().to_string()).or_default().push(item);
+        }
+        for items in locations.values_mut() {
+            items.sort_by(|a, b| compare(&a.call_number, &b.call_number).then(a.id.cmp(&b.id)));
+        }
+        locations
+    }
+
+    /// Writes the shelf list to `path`, one row per item, for checking the
+    /// shelves location by location.
+    pub fn export_shelf_list_csv(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record(["shelf_location", "call_number", "id", "title", "author", "copies", "avail_copies"])?;
+        for (location, items) in self.shelf_list() {
+            let location = if location.is_empty() { tr!("(no location)") } else { location };
+            for item in items {
+                wtr.write_record([
+                    location.clone(),
+                    item.call_number.clone(),
+                    item.id.to_string(),
+                    item.title.to_string(),
+                    item.author.clone().unwrap_or_default(),
+                    item.copies.to_string(),
+                    item.avail_copies.to_string(),
+                ])?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::fines::format_money;
+use crate::i18n::tr;
+use crate::reports::text_table;
+use crate::Library;
+
+/// A fine paid while a cash session was open.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CashPayment {
+    pub at: DateTime<Utc>,
+    pub member_id: u32,
+    pub fine_id: u32,
+    pub amount_cents: u64,
+    pub user: String,
+}
+
+/// A shift at the cash drawer, from the float counted in at the start to
+/// the cash counted out at the end.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct CashSession {
+    pub id: u32,
+    pub opened_at: DateTime<Utc>,
+    pub opened_by: String,
+    pub opening_float_cents: u64,
+    pub payments: Vec<CashPayment>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub closed_by: Option<String>,
+    pub counted_cents: Option<u64>,
+}
+
+impl CashSession {
+    pub fn is_open(&self) -> bool {
+        self.closed_at.is_none()
+    }
+
+    pub fn taken_cents(&self) -> u64 {
+        self.payments.iter().map(|p| p.amount_cents).sum()
+    }
+
+    /// What should be in the drawer: the float plus every payment taken.
+    pub fn expected_cents(&self) -> u64 {
+        self.opening_float_cents + self.taken_cents()
+    }
+
+    /// Counted minus expected; negative when cash is missing.
+    pub fn difference_cents(&self) -> Option<i64> {
+        self.counted_cents.map(|counted| counted as i64 - self.expected_cents() as i64)
+    }
+
+    /// The reconciliation report printed or saved when the session closes.
+    pub fn reconciliation_text(&self) -> String {
+        let local = |at: DateTime<Utc>| at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string();
+        let mut text = tr!("Cash session {} reconciliation", self.id);
+        text.push_str("\n\n");
+        text.push_str(&tr!("Opened: {} by {}", local(self.opened_at), self.opened_by));
+        text.push('\n');
+        if let (Some(closed_at), Some(closed_by)) = (self.closed_at, &self.closed_by) {
+            text.push_str(&tr!("Closed: {} by {}", local(closed_at), closed_by));
+            text.push('\n');
+        }
+        text.push('\n');
+
+        let headers = [tr!("Time"), tr!("Member"), tr!("Fine"), tr!("Amount"), tr!("Staff")];
+        let rows: Vec<Vec<String>> = self
+            .payments
+            .iter()
+            .map(|p| {
+                vec![local(p.at), p.member_id.to_string(), p.fine_id.to_string(), format_money(p.amount_cents), p.user.clone()]
+            })
+            .collect();
+        if rows.is_empty() {
+            text.push_str(&tr!("No payments were taken."));
+            text.push('\n');
+        } else {
+            text.push_str(&text_table(&headers, &rows));
+        }
+        text.push('\n');
+
+        text.push_str(&tr!("Opening float: {}", format_money(self.opening_float_cents)));
+        text.push('\n');
+        text.push_str(&tr!("Payments taken: {} ({})", format_money(self.taken_cents()), self.payments.len()));
+        text.push('\n');
+        text.push_str(&tr!("Expected in drawer: {}", format_money(self.expected_cents())));
+        text.push('\n');
+        if let (Some(counted), Some(difference)) = (self.counted_cents, self.difference_cents()) {
+            text.push_str(&tr!("Counted: {}", format_money(counted)));
+            text.push('\n');
+            text.push_str(&match difference {
+                0 => tr!("Balanced"),
+                d if d > 0 => tr!("Over by {}", format_money(d as u64)),
+                d => tr!("Short by {}", format_money(d.unsigned_abs())),
+            });
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// Writes a closed session's reconciliation report to `dir`, creating it if
+/// needed. Returns the file written.
+pub fn save_reconciliation(session: &CashSession, dir: &str) -> std::io::Result<PathBuf> {
+    fs::create_dir_all(dir)?;
+    let date = session.closed_at.unwrap_or(session.opened_at).with_timezone(&Local).format("%Y-%m-%d");
+    let path = Path::new(dir).join(format!("cash-session-{}-{}.txt", session.id, date));
+    fs::write(&path, session.reconciliation_text())?;
+    tracing::info!(session_id = session.id, path = %path.display(), "Cash reconciliation saved");
+    Ok(path)
+}
+
+impl Library {
+    pub fn open_cash_session(&self) -> Option<&CashSession> {
+        self.cash_sessions.iter().find(|s| s.is_open())
+    }
+
+    /// Starts a shift with `float_cents` in the drawer. Only one session can
+    /// be open at a time.
+    pub fn start_cash_session(&mut self, float_cents: u64) -> Result<u32, String> {
+        if let Some(open) = self.open_cash_session() {
+            return Err(tr!("Cash session {} is still open; close it first", open.id));
+        }
+        let id = self.cash_sessions.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        self.cash_sessions.push(CashSession {
+            id,
+            opened_at: self.clock.now(),
+            opened_by: self.current_user.clone(),
+            opening_float_cents: float_cents,
+            payments: Vec::new(),
+            closed_at: None,
+            closed_by: None,
+            counted_cents: None,
+        });
+        tracing::info!(session_id = id, float_cents, user = %self.current_user, "Cash session opened");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Takes payment of a fine in cash, recording it in the open session.
+    /// Payments replayed from other desks go into their own drawers, so
+    /// they only settle the fine.
+    pub fn take_payment(&mut self, member_id: u32, fine_id: u32) -> Result<u64, String> {
+        if self.open_cash_session().is_none() {
+            return Err(tr!("Open a cash session before taking payments"));
+        }
+        let amount_cents = self.settle_fine(member_id, fine_id)?;
+        let (at, user) = (self.clock.now(), self.current_user.clone());
+        let session = self.cash_sessions.iter_mut().find(|s| s.is_open()).unwrap();
+        session.payments.push(CashPayment { at, member_id, fine_id, amount_cents, user });
+        self.dirty = true;
+        Ok(amount_cents)
+    }
+
+    /// Ends the open session with the cash counted in the drawer and
+    /// returns it for the reconciliation report.
+    pub fn close_cash_session(&mut self, counted_cents: u64) -> Result<CashSession, String> {
+        let (now, user) = (self.clock.now(), self.current_user.clone());
+        let session = self.cash_sessions.iter_mut().find(|s| s.is_open()).ok_or_else(|| tr!("No cash session is open"))?;
+        session.closed_at = Some(now);
+        session.closed_by = Some(user);
+        session.counted_cents = Some(counted_cents);
+        tracing::info!(
+            session_id = session.id,
+            expected_cents = session.expected_cents(),
+            counted_cents,
+            "Cash session closed"
+        );
+        let session = session.clone();
+        self.dirty = true;
+        Ok(session)
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{Datelike, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::Library;
+
+// Places shown on the kiosk leaderboard
+const LEADERBOARD_SIZE: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Badge {
+    FirstBook,
+    FiveBooks,
+    TenBooks,
+    TwentyFiveBooks,
+    FiftyBooks,
+    HundredBooks,
+    GoalReached,
+}
+
+impl Badge {
+    // Badges for the number of books read, whatever the goal
+    const MILESTONES: [(u32, Badge); 6] = [
+        (1, Badge::FirstBook),
+        (5, Badge::FiveBooks),
+        (10, Badge::TenBooks),
+        (25, Badge::TwentyFiveBooks),
+        (50, Badge::FiftyBooks),
+        (100, Badge::HundredBooks),
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Badge::FirstBook => tr!("First Book"),
+            Badge::FiveBooks => tr!("5 Books"),
+            Badge::TenBooks => tr!("10 Books"),
+            Badge::TwentyFiveBooks => tr!("25 Books"),
+            Badge::FiftyBooks => tr!("50 Books"),
+            Badge::HundredBooks => tr!("100 Books"),
+            Badge::GoalReached => tr!("Goal Reached"),
+        }
+    }
+}
+
+/// A member's reading goal for one calendar year. Every item they return
+/// that year counts as a book read.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ReadingChallenge {
+    pub year: i32,
+    pub goal: u32,
+    pub completed: u32,
+    pub badges: Vec<Badge>,
+    // Whether the member's first name may be shown on the kiosk leaderboard
+    pub on_leaderboard: bool,
+}
+
+impl ReadingChallenge {
+    pub fn progress_text(&self) -> String {
+        tr!("{}: {} of {} books", self.year, self.completed, self.goal)
+    }
+
+    pub fn badges_text(&self) -> String {
+        if self.badges.is_empty() {
+            return tr!("No badges yet");
+        }
+        self.badges.iter().map(|b| b.label()).collect::<Vec<_>>().join(", ")
+    }
+}
+
+/// A line of the kiosk leaderboard.
+#[derive(Clone)]
+pub struct LeaderboardEntry {
+    pub rank: usize,
+    pub first_name: String,
+    pub completed: u32,
+    pub goal: u32,
+}
+
+impl Library {
+    fn challenge_year(&self) -> i32 {
+        self.clock.now().with_timezone(&Local).year()
+    }
+
+    /// Sets a member's goal for this year, keeping what they have read so
+    /// far if they already had one. A goal of zero ends their challenge.
+    pub fn set_reading_challenge(&mut self, member_id: u32, goal: u32, on_leaderboard: bool) -> Result<(), String> {
+        self.only_at_server()?;
+        let year = self.challenge_year();
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        if goal == 0 {
+            member.challenge = None;
+        } else {
+            match member.challenge.as_mut().filter(|c| c.year == year) {
+                Some(challenge) => {
+                    challenge.goal = goal;
+                    challenge.on_leaderboard = on_leaderboard;
+                    if challenge.completed >= goal && !challenge.badges.contains(&Badge::GoalReached) {
+                        challenge.badges.push(Badge::GoalReached);
+                    }
+                }
+                None => {
+                    member.challenge = Some(ReadingChallenge { year, goal, completed: 0, badges: Vec::new(), on_leaderboard });
+                }
+            }
+        }
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(())
+    }
+
+    // Counts a return towards the member's challenge, if they have one for
+    // this year. Returns the badges it earned.
+    pub(crate) fn count_towards_challenge(&mut self, member_id: u32) -> Vec<Badge> {
+        let year = self.challenge_year();
+        let Some(challenge) = self.members.get_mut(&member_id).and_then(|m| m.challenge.as_mut()) else {
+            return Vec::new();
+        };
+        if challenge.year != year {
+            return Vec::new();
+        }
+        challenge.completed += 1;
+        let mut earned: Vec<Badge> = Badge::MILESTONES
+            .iter()
+            .filter(|(count, _)| *count == challenge.completed)
+            .map(|(_, badge)| *badge)
+            .collect();
+        if challenge.completed == challenge.goal {
+            earned.push(Badge::GoalReached);
+        }
+        earned.retain(|b| !challenge.badges.contains(b));
+        challenge.badges.extend(&earned);
+        if !earned.is_empty() {
+            tracing::info!(member_id, ?earned, "Reading challenge badges earned");
+        }
+        earned
+    }
+
+    /// This year's most-read members among those who agreed to be shown,
+    /// by first name only.
+    pub fn leaderboard(&self) -> Vec<LeaderboardEntry> {
+        let year = self.challenge_year();
+        let mut entries: Vec<(String, u32, u32)> = self
+            .members
+            .values()
+            .filter(|m| m.anonymized_at.is_none())
+            .filter_map(|m| {
+                let challenge = m.challenge.as_ref().filter(|c| c.year == year && c.on_leaderboard)?;
+                let first_name = m.name.split_whitespace().next().unwrap_or_default().to_string();
+                Some((first_name, challenge.completed, challenge.goal))
+            })
+            .collect();
+        entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        entries
+            .into_iter()
+            .take(LEADERBOARD_SIZE)
+            .enumerate()
+            .map(|(i, (first_name, completed, goal))| LeaderboardEntry { rank: i + 1, first_name, completed, goal })
+            .collect()
+    }
+}
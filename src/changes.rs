@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+
+use crate::{clock, Library};
+
+// Changes remembered for pages that haven't caught up yet. A page further
+// behind than this is told everything was reloaded.
+const KEEP_CHANGES: usize = 1000;
+
+/// What a mutation of the library touched. Pages get these through the
+/// `EventBus`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LibraryChange {
+    ItemAdded(u32),
+    ItemChanged(u32),
+    ItemRemoved(u32),
+    MemberAdded(u32),
+    MemberChanged(u32),
+    MemberRemoved(u32),
+    LoanCreated { item_id: u32, member_id: u32 },
+    LoanEnded { item_id: u32, member_id: u32 },
+    // Changed in bulk, e.g. by an import or a restore; anything may differ
+    Reloaded,
+}
+
+impl LibraryChange {
+    /// The item whose row needs redrawing, if any.
+    pub fn item_id(self) -> Option<u32> {
+        match self {
+            LibraryChange::ItemChanged(id) => Some(id),
+            LibraryChange::LoanCreated { item_id, .. } | LibraryChange::LoanEnded { item_id, .. } => Some(item_id),
+            _ => None,
+        }
+    }
+
+    /// The member whose row needs redrawing, if any.
+    pub fn member_id(self) -> Option<u32> {
+        match self {
+            LibraryChange::MemberChanged(id) => Some(id),
+            LibraryChange::LoanCreated { member_id, .. } | LibraryChange::LoanEnded { member_id, .. } => Some(member_id),
+            _ => None,
+        }
+    }
+
+    /// Whether a list of items has to be rebuilt rather than redrawn.
+    pub fn changes_item_list(self) -> bool {
+        matches!(self, LibraryChange::ItemAdded(_) | LibraryChange::ItemRemoved(_) | LibraryChange::Reloaded)
+    }
+
+    /// Whether a list of members has to be rebuilt rather than redrawn.
+    pub fn changes_member_list(self) -> bool {
+        matches!(self, LibraryChange::MemberAdded(_) | LibraryChange::MemberRemoved(_) | LibraryChange::Reloaded)
+    }
+}
+
+/// The recent changes to the library, numbered by revision, so the event
+/// bus can tell what happened since it last looked.
+#[derive(Default)]
+pub struct ChangeLog {
+    revision: u64,
+    recent: VecDeque<(u64, LibraryChange)>,
+}
+
+impl ChangeLog {
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    fn push(&mut self, change: LibraryChange) {
+        self.revision += 1;
+        self.recent.push_back((self.revision, change));
+        if self.recent.len() > KEEP_CHANGES {
+            self.recent.pop_front();
+        }
+    }
+
+    /// The changes after `revision`. If some of them have been forgotten
+    /// that is a single `Reloaded`.
+    pub fn since(&self, revision: u64) -> Vec<LibraryChange> {
+        let oldest = self.recent.front().map_or(self.revision + 1, |(r, _)| *r);
+        if revision + 1 < oldest {
+            return vec![LibraryChange::Reloaded];
+        }
+        self.recent.iter().filter(|(r, _)| *r > revision).map(|(_, c)| *c).collect()
+    }
+}
+
+impl Library {
+    pub(crate) fn changed(&mut self, change: LibraryChange) {
+        self.changes.push(change);
+    }
+
+    pub(crate) fn item_changed(&mut self, item_id: u32) {
+        self.changed(LibraryChange::ItemChanged(item_id));
+    }
+
+    pub(crate) fn member_changed(&mut self, member_id: u32) {
+        self.changed(LibraryChange::MemberChanged(member_id));
+    }
+
+    pub(crate) fn reloaded(&mut self) {
+        self.changed(LibraryChange::Reloaded);
+    }
+
+    /// Swaps in `loaded`, e.g. a restored backup, keeping what belongs to
+    /// this session rather than the saved state: who is logged in, the
+    /// clock, sync, plugins, webhooks and the change log open views follow.
+    /// Undo history is dropped, as it refers to the old state.
+    pub(crate) fn replace_state(&mut self, mut loaded: Library) {
+        loaded.current_user = std::mem::take(&mut self.current_user);
+        loaded.clock = std::mem::replace(&mut self.clock, clock::system_clock());
+        loaded.sync_enabled = self.sync_enabled;
+        loaded.sync_client = self.sync_client;
+        loaded.plugins = self.plugins.take();
+        loaded.webhooks = self.webhooks.take();
+        loaded.pending_webhooks = std::mem::take(&mut self.pending_webhooks);
+        loaded.replaying = self.replaying;
+        loaded.changes = std::mem::take(&mut self.changes);
+        loaded.dirty = true;
+        *self = loaded;
+        self.reloaded();
+    }
+}
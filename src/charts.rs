@@ -0,0 +1,364 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::path::Path;
+use std::rc::Rc;
+
+use chrono::{Datelike, Days, NaiveDate};
+use gtk::cairo;
+use gtk::prelude::*;
+use gtk::{glib, Box as GtkBox, Button, Label};
+
+use crate::dialogs;
+use crate::i18n::tr;
+
+// Room for the value axis on the left and the labels underneath
+const MARGIN_LEFT: f64 = 36.0;
+const MARGIN_RIGHT: f64 = 8.0;
+const MARGIN_TOP: f64 = 8.0;
+const MARGIN_BOTTOM: f64 = 22.0;
+const FONT_SIZE: f64 = 10.0;
+// At most this many labels under the chart, so they don't overlap
+const MAX_LABELS: usize = 8;
+// Size of exported images, in pixels
+const PNG_WIDTH: i32 = 800;
+const PNG_HEIGHT: i32 = 400;
+const PNG_TITLE_HEIGHT: f64 = 28.0;
+// Size of a day in the heatmap and the space between days, in pixels
+const HEATMAP_CELL: f64 = 8.0;
+const HEATMAP_GAP: f64 = 2.0;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ChartKind {
+    Line,
+    Bar,
+}
+
+#[derive(Clone)]
+struct ChartData {
+    kind: ChartKind,
+    title: String,
+    points: Vec<(String, f64)>,
+    // Point under the mouse pointer
+    hovered: Option<usize>,
+}
+
+impl ChartData {
+    // The point whose slot contains `x`, in a chart `width` wide
+    fn index_at(&self, width: f64, x: f64) -> Option<usize> {
+        let slot = (width - MARGIN_LEFT - MARGIN_RIGHT) / self.points.len() as f64;
+        let index = ((x - MARGIN_LEFT) / slot).floor();
+        (index >= 0.0 && (index as usize) < self.points.len()).then_some(index as usize)
+    }
+
+    fn draw(&self, cr: &cairo::Context, width: f64, height: f64) -> Result<(), cairo::Error> {
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(FONT_SIZE);
+        cr.set_line_width(1.0);
+
+        let plot_width = width - MARGIN_LEFT - MARGIN_RIGHT;
+        let plot_height = height - MARGIN_TOP - MARGIN_BOTTOM;
+        let bottom = MARGIN_TOP + plot_height;
+        let max = self.points.iter().map(|(_, v)| *v).fold(0.0, f64::max).max(1.0).ceil();
+        let y_of = |value: f64| bottom - value / max * plot_height;
+
+        // Grey reads on both light and dark themes
+        cr.set_source_rgb(0.5, 0.5, 0.5);
+        for value in [0.0, (max / 2.0).round(), max] {
+            let y = y_of(value);
+            cr.move_to(MARGIN_LEFT, y);
+            cr.line_to(width - MARGIN_RIGHT, y);
+            let text = value.to_string();
+            let extents = cr.text_extents(&text)?;
+            cr.move_to(MARGIN_LEFT - extents.width() - 4.0, y + extents.height() / 2.0);
+            cr.show_text(&text)?;
+        }
+        cr.stroke()?;
+
+        if self.points.is_empty() {
+            let text = tr!("No data");
+            let extents = cr.text_extents(&text)?;
+            cr.move_to(MARGIN_LEFT + (plot_width - extents.width()) / 2.0, MARGIN_TOP + plot_height / 2.0);
+            return cr.show_text(&text);
+        }
+
+        let slot = plot_width / self.points.len() as f64;
+        let step = self.points.len().div_ceil(MAX_LABELS);
+        for (i, (label, _)) in self.points.iter().enumerate().step_by(step) {
+            let extents = cr.text_extents(label)?;
+            let x = MARGIN_LEFT + (i as f64 + 0.5) * slot - extents.width() / 2.0;
+            cr.move_to(x.max(0.0), height - 6.0);
+            cr.show_text(label)?;
+        }
+
+        cr.set_source_rgb(0.21, 0.52, 0.89);
+        match self.kind {
+            ChartKind::Bar => {
+                for (i, (_, value)) in self.points.iter().enumerate() {
+                    if self.hovered == Some(i) {
+                        cr.set_source_rgb(0.10, 0.37, 0.71);
+                    }
+                    let top = y_of(*value);
+                    cr.rectangle(MARGIN_LEFT + i as f64 * slot + slot * 0.1, top, slot * 0.8, bottom - top);
+                    cr.fill()?;
+                    if self.hovered == Some(i) {
+                        cr.set_source_rgb(0.21, 0.52, 0.89);
+                    }
+                }
+            }
+            ChartKind::Line => {
+                cr.set_line_width(2.0);
+                for (i, (_, value)) in self.points.iter().enumerate() {
+                    cr.line_to(MARGIN_LEFT + (i as f64 + 0.5) * slot, y_of(*value));
+                }
+                cr.stroke()?;
+                if let Some(i) = self.hovered {
+                    cr.arc(MARGIN_LEFT + (i as f64 + 0.5) * slot, y_of(self.points[i].1), 4.0, 0.0, std::f64::consts::TAU);
+                    cr.fill()?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    // Drawn on white with the title on top, as the theme isn't known to
+    // whoever opens the file
+    fn write_png(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let surface = cairo::ImageSurface::create(cairo::Format::ARgb32, PNG_WIDTH, PNG_HEIGHT)?;
+        let cr = cairo::Context::new(&surface)?;
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        cr.paint()?;
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+        cr.set_font_size(14.0);
+        cr.move_to(MARGIN_LEFT, 20.0);
+        cr.show_text(&self.title)?;
+        cr.translate(0.0, PNG_TITLE_HEIGHT);
+
+        let plain = ChartData { hovered: None, ..self.clone() };
+        plain.draw(&cr, PNG_WIDTH as f64, PNG_HEIGHT as f64 - PNG_TITLE_HEIGHT)?;
+        drop(cr);
+        surface.write_to_png(&mut File::create(path)?)?;
+        Ok(())
+    }
+}
+
+/// A titled line or bar chart with a tooltip for the value under the
+/// pointer and a button to save it as a PNG image.
+pub struct Chart {
+    pub widget: GtkBox,
+    area: gtk::DrawingArea,
+    data: Rc<RefCell<ChartData>>,
+}
+
+impl Chart {
+    pub fn new(kind: ChartKind, title: &str, file_name: &str) -> Chart {
+        let data = Rc::new(RefCell::new(ChartData {
+            kind,
+            title: title.to_string(),
+            points: Vec::new(),
+            hovered: None,
+        }));
+
+        let area = gtk::DrawingArea::new();
+        area.set_content_width(320);
+        area.set_content_height(180);
+        area.set_hexpand(true);
+        area.set_draw_func(glib::clone!(
+            #[strong]
+            data,
+            move |_, cr, width, height| {
+                if let Err(e) = data.borrow().draw(cr, width as f64, height as f64) {
+                    tracing::warn!("Failed to draw chart: {}", e);
+                }
+            }
+        ));
+
+        let motion = gtk::EventControllerMotion::new();
+        motion.connect_motion(glib::clone!(
+            #[strong]
+            data,
+            #[weak]
+            area,
+            move |_, x, _| {
+                let index = data.borrow().index_at(area.width() as f64, x);
+                if data.borrow().hovered != index {
+                    data.borrow_mut().hovered = index;
+                    area.queue_draw();
+                }
+            }
+        ));
+        motion.connect_leave(glib::clone!(
+            #[strong]
+            data,
+            #[weak]
+            area,
+            move |_| {
+                data.borrow_mut().hovered = None;
+                area.queue_draw();
+            }
+        ));
+        area.add_controller(motion);
+
+        area.set_has_tooltip(true);
+        area.connect_query_tooltip(glib::clone!(
+            #[strong]
+            data,
+            move |area, x, _, _, tooltip| {
+                let data = data.borrow();
+                let Some(index) = data.index_at(area.width() as f64, x as f64) else { return false };
+                let (label, value) = &data.points[index];
+                tooltip.set_text(Some(&format!("{}: {}", label, value)));
+                true
+            }
+        ));
+
+        let title_label = Label::new(Some(title));
+        title_label.set_xalign(0.0);
+        let export_button = Button::with_label(&tr!("Export PNG..."));
+        export_button.set_halign(gtk::Align::End);
+        let file_name = file_name.to_string();
+        export_button.connect_clicked(glib::clone!(
+            #[strong]
+            data,
+            move |button| {
+                let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+                dialogs::choose_save_path(&window, &tr!("Export Chart"), &file_name, glib::clone!(
+                    #[strong]
+                    data,
+                    #[weak]
+                    window,
+                    move |path| {
+                        if let Err(e) = data.borrow().write_png(&path) {
+                            dialogs::show_error(&window, &tr!("Failed to export the chart"), &e.to_string());
+                        }
+                    }
+                ));
+            }
+        ));
+
+        let widget = GtkBox::new(gtk::Orientation::Vertical, 5);
+        widget.append(&title_label);
+        widget.append(&area);
+        widget.append(&export_button);
+
+        Chart { widget, area, data }
+    }
+
+    /// Replaces what the chart shows, as (label, value) pairs from left to
+    /// right.
+    pub fn set_points(&self, points: Vec<(String, f64)>) {
+        let mut data = self.data.borrow_mut();
+        data.points = points;
+        data.hovered = None;
+        drop(data);
+        self.area.queue_draw();
+    }
+}
+
+#[derive(Default)]
+struct HeatmapData {
+    first: NaiveDate,
+    // One count per day, starting with `first`
+    days: Vec<u32>,
+}
+
+impl HeatmapData {
+    // Days before `first` in its week, which starts on Monday
+    fn lead(&self) -> usize {
+        self.first.weekday().num_days_from_monday() as usize
+    }
+
+    // Weeks are columns and weekdays rows, Monday on top
+    fn index_at(&self, x: f64, y: f64) -> Option<usize> {
+        let step = HEATMAP_CELL + HEATMAP_GAP;
+        let (column, row) = ((x / step).floor(), (y / step).floor());
+        if column < 0.0 || !(0.0..7.0).contains(&row) {
+            return None;
+        }
+        let index = (column as usize * 7 + row as usize).checked_sub(self.lead())?;
+        (index < self.days.len()).then_some(index)
+    }
+
+    fn draw(&self, cr: &cairo::Context) -> Result<(), cairo::Error> {
+        let step = HEATMAP_CELL + HEATMAP_GAP;
+        let max = self.days.iter().copied().max().unwrap_or(0).max(1) as f64;
+        for (i, &count) in self.days.iter().enumerate() {
+            let slot = self.lead() + i;
+            // Same blue as the charts, deeper with more checkouts; grey reads
+            // on both light and dark themes for days without any
+            if count == 0 {
+                cr.set_source_rgba(0.5, 0.5, 0.5, 0.2);
+            } else {
+                cr.set_source_rgba(0.21, 0.52, 0.89, 0.3 + 0.7 * count as f64 / max);
+            }
+            cr.rectangle((slot / 7) as f64 * step, (slot % 7) as f64 * step, HEATMAP_CELL, HEATMAP_CELL);
+            cr.fill()?;
+        }
+        Ok(())
+    }
+}
+
+/// A calendar of daily counts, a column per week, shaded by how many there
+/// were on each day, with the count in a tooltip.
+pub struct Heatmap {
+    pub widget: GtkBox,
+    area: gtk::DrawingArea,
+    summary_label: Label,
+    data: Rc<RefCell<HeatmapData>>,
+}
+
+impl Heatmap {
+    pub fn new(title: &str) -> Heatmap {
+        let data = Rc::new(RefCell::new(HeatmapData::default()));
+
+        let area = gtk::DrawingArea::new();
+        area.set_content_height((7.0 * (HEATMAP_CELL + HEATMAP_GAP)) as i32);
+        area.set_draw_func(glib::clone!(
+            #[strong]
+            data,
+            move |_, cr, _, _| {
+                if let Err(e) = data.borrow().draw(cr) {
+                    tracing::warn!("Failed to draw heatmap: {}", e);
+                }
+            }
+        ));
+        area.set_has_tooltip(true);
+        area.connect_query_tooltip(glib::clone!(
+            #[strong]
+            data,
+            move |_, x, y, _, tooltip| {
+                let data = data.borrow();
+                let Some(index) = data.index_at(x as f64, y as f64) else { return false };
+                let date = data.first + Days::new(index as u64);
+                tooltip.set_text(Some(&tr!("{}: {} checkouts", date.format("%Y-%m-%d"), data.days[index])));
+                true
+            }
+        ));
+
+        let title_label = Label::new(Some(title));
+        title_label.set_xalign(0.0);
+        let summary_label = Label::new(None);
+        summary_label.set_xalign(0.0);
+
+        let widget = GtkBox::new(gtk::Orientation::Vertical, 5);
+        widget.append(&title_label);
+        widget.append(&area);
+        widget.append(&summary_label);
+
+        Heatmap { widget, area, summary_label, data }
+    }
+
+    /// Replaces the counts shown, one per day starting with `first`.
+    pub fn set_days(&self, first: NaiveDate, days: Vec<u32>) {
+        let total: u32 = days.iter().sum();
+        let busiest = days.iter().copied().max().unwrap_or(0);
+        self.summary_label.set_text(&tr!("{} checkouts in total, at most {} on one day", total, busiest));
+        let mut data = self.data.borrow_mut();
+        data.first = first;
+        data.days = days;
+        let weeks = (data.lead() + data.days.len()).div_ceil(7);
+        drop(data);
+        self.area.set_content_width((weeks as f64 * (HEATMAP_CELL + HEATMAP_GAP)) as i32);
+        self.area.queue_draw();
+    }
+}
@@ -0,0 +1,154 @@
+use std::path::Path;
+
+use crate::bench::{self, BenchOptions};
+use crate::i18n::tr;
+use crate::persist;
+use crate::query::{self, Query};
+use crate::seed::SeedOptions;
+
+/// Runs a subcommand given on the command line, e.g. `rustlms search
+/// author:tolkien`, without starting the GUI. Returns None when the
+/// arguments are for the GUI, otherwise the exit code.
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.get(1).map(String::as_str) {
+        Some("search") => Some(search(&args[2..].join(" "))),
+        Some("seed") => Some(seed(&args[2..])),
+        Some("self-test") => Some(self_test()),
+        Some("bench") => Some(bench(&args[2..])),
+        _ => None,
+    }
+}
+
+// Prints the catalog items matching `text`, one tab-separated line each
+fn search(text: &str) -> i32 {
+    let query = match Query::parse(text) {
+        Ok(query) => query,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let (library, import_result) = crate::load_library(None);
+    if let Some(Err(e)) = import_result {
+        eprintln!("{}", tr!("Failed to load the library: {}", e));
+        return 1;
+    }
+
+    let ids = query::run(library.items.values(), &query);
+    for item in ids.iter().filter_map(|id| library.items.get(id)) {
+        println!(
+            "{}\t{}\t{}\t{}\t{}/{}",
+            item.id,
+            item.title,
+            item.author.as_deref().unwrap_or(""),
+            item.year,
+            item.avail_copies,
+            item.copies
+        );
+    }
+    eprintln!("{}", tr!("{} items found", ids.len()));
+    0
+}
+
+// Adds demo data to the saved library, e.g.
+// `rustlms seed --items 10000 --members 200 --loans 500`
+fn seed(args: &[String]) -> i32 {
+    let mut options = SeedOptions::default();
+    let mut args = args.iter();
+    while let Some(flag) = args.next() {
+        let value = args.next().and_then(|v| v.parse::<u64>().ok());
+        match (flag.as_str(), value) {
+            ("--items", Some(n)) => options.items = n as u32,
+            ("--members", Some(n)) => options.members = n as u32,
+            ("--loans", Some(n)) => options.loans = n as u32,
+            ("--seed", Some(n)) => options.seed = Some(n),
+            _ => {
+                eprintln!("{}", tr!("Usage: rustlms seed [--items N] [--members N] [--loans N] [--seed N]"));
+                return 2;
+            }
+        }
+    }
+    let state_path = Path::new(persist::STATE_PATH);
+    let (mut library, import_result) = crate::load_library(None);
+    if let Some(Err(e)) = import_result {
+        // Without a catalog to start from, the demo data is the catalog
+        if state_path.exists() {
+            eprintln!("{}", tr!("Failed to load the library: {}", e));
+            return 1;
+        }
+    }
+
+    let report = library.seed(&options);
+    if let Err(e) = library.save(state_path) {
+        eprintln!("{}", tr!("Failed to save the library: {}", e));
+        return 1;
+    }
+    eprintln!(
+        "{}",
+        tr!(
+            "Added {} items, {} members and {} loans ({} still out, {} refused)",
+            report.items,
+            report.members,
+            report.loans,
+            report.on_loan,
+            report.refused
+        )
+    );
+    0
+}
+
+// Exports the saved library in each format and reads it back, printing a
+// line per format; fails if any of them changed, so it can run from a
+// script or a scheduled job
+fn self_test() -> i32 {
+    let (library, import_result) = crate::load_library(None);
+    if let Some(Err(e)) = import_result {
+        eprintln!("{}", tr!("Failed to load the library: {}", e));
+        return 1;
+    }
+
+    let results = library.self_test();
+    for result in &results {
+        println!("{}", result.describe());
+    }
+    if results.iter().all(|r| r.passed()) { 0 } else { 1 }
+}
+
+// Measures memory use, parsing speed, or import, search and circulation
+// speed on a generated catalog, e.g.
+// `rustlms bench memory --items 100000 --loans 50000 > bench_output.txt`.
+// `bench catalog` measures 10k, 100k and 1M items unless given `--items`.
+fn bench(args: &[String]) -> i32 {
+    // Started by `bench::memory` for each layout it compares
+    if args.first().map(String::as_str) == Some("memory-child") {
+        let (Some(path), Some(loans)) = (args.get(1), args.get(2).and_then(|n| n.parse().ok())) else { return 2 };
+        let old_layout = args.get(3).map(String::as_str) == Some("--old-layout");
+        return bench::measure_memory(Path::new(path), loans, old_layout);
+    }
+
+    let usage = || {
+        eprintln!("{}", tr!("Usage: rustlms bench memory|parse|catalog [--items N] [--loans N]"));
+        2
+    };
+    let Some(what) = args.first() else { return usage() };
+    let mut options = BenchOptions::default();
+    let mut sizes = bench::CATALOG_SIZES.to_vec();
+    let mut flags = args[1..].iter();
+    while let Some(flag) = flags.next() {
+        let value = flags.next().and_then(|v| v.parse::<u32>().ok());
+        match (flag.as_str(), value) {
+            ("--items", Some(n)) => {
+                options.items = n;
+                sizes = vec![n];
+            }
+            ("--loans", Some(n)) => options.loans = n,
+            _ => return usage(),
+        }
+    }
+    match what.as_str() {
+        "memory" => bench::memory(&options),
+        "parse" => bench::parse(&options),
+        "catalog" => bench::catalog(&sizes, options.loans),
+        _ => usage(),
+    }
+}
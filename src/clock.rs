@@ -0,0 +1,189 @@
+use chrono::{DateTime, Utc};
+
+/// Source of the current time for due dates, fines and the transaction log,
+/// so time-dependent behavior can be driven by a fixed clock.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Always reports the same moment, e.g. to replay a change as of when it
+/// was made.
+pub struct FixedClock(pub DateTime<Utc>);
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        self.0
+    }
+}
+
+pub fn system_clock() -> Box<dyn Clock> {
+    Box::new(SystemClock)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Months, TimeZone, Weekday};
+
+    use super::*;
+    use crate::editing::{ItemDetails, NewItem};
+    use crate::Library;
+
+    // A Monday
+    fn start() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap()
+    }
+
+    fn library_at(at: DateTime<Utc>) -> Library {
+        Library::with_clock(Box::new(FixedClock(at)))
+    }
+
+    fn add(library: &mut Library, format: &str) -> u32 {
+        library
+            .add_item(NewItem {
+                details: ItemDetails {
+                    title: format!("A {}", format),
+                    author: None,
+                    year: 2020,
+                    edition: String::new(),
+                    format: format.to_string(),
+                    desc: String::new(),
+                },
+                isbn: None,
+                call_number: String::new(),
+                tags: Vec::new(),
+                copies: 1,
+            })
+            .unwrap()
+    }
+
+    // Issues a new item of `format` to a new adult member, returning both
+    fn lend(library: &mut Library, format: &str) -> (u32, u32) {
+        let item_id = add(library, format);
+        let member_id = library.register_member("Ada").unwrap();
+        library.book_issue(item_id, member_id.to_string()).unwrap();
+        (item_id, member_id)
+    }
+
+    fn due_date(library: &Library, item_id: u32, member_id: u32) -> DateTime<Utc> {
+        library.members[&member_id].items[&item_id].due_date
+    }
+
+    #[test]
+    fn books_are_due_after_the_loan_period() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Book");
+        assert_eq!(due_date(&library, item_id, member_id), start() + Duration::days(30));
+        assert_eq!(library.members[&member_id].items[&item_id].issued_at, start());
+    }
+
+    #[test]
+    fn movies_get_a_longer_first_loan() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Movie");
+        assert_eq!(due_date(&library, item_id, member_id), start() + Duration::days(60));
+    }
+
+    #[test]
+    fn loans_are_not_due_on_closed_days() {
+        let mut library = library_at(start());
+        // 30 days after the start is a Wednesday
+        library.calendar.closed_weekdays = vec![Weekday::Wed];
+        let (item_id, member_id) = lend(&mut library, "Book");
+        assert_eq!(due_date(&library, item_id, member_id), start() + Duration::days(31));
+    }
+
+    #[test]
+    fn hourly_formats_are_due_in_hours() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Laptop");
+        assert_eq!(due_date(&library, item_id, member_id), start() + Duration::hours(4));
+    }
+
+    #[test]
+    fn loans_become_overdue_once_past_due() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Book");
+        let due = due_date(&library, item_id, member_id);
+        assert_eq!(library.overdue_count(), 0);
+        assert!(library.due_loans(0).is_empty());
+
+        library.clock = Box::new(FixedClock(due));
+        assert_eq!(library.overdue_count(), 0);
+        let due_soon = library.due_loans(0);
+        assert_eq!(due_soon.len(), 1);
+        assert!(!due_soon[0].overdue);
+
+        library.clock = Box::new(FixedClock(due + Duration::minutes(1)));
+        assert_eq!(library.overdue_count(), 1);
+        let overdue = library.due_loans(0);
+        assert_eq!(overdue.len(), 1);
+        assert!(overdue[0].overdue);
+        assert_eq!(overdue[0].member_id, member_id);
+    }
+
+    #[test]
+    fn renewing_extends_by_the_format_period() {
+        let mut library = library_at(start());
+        let (book_id, member_id) = lend(&mut library, "Book");
+        let book_due = due_date(&library, book_id, member_id);
+        let movie_id = add(&mut library, "Movie");
+        library.book_issue(movie_id, member_id.to_string()).unwrap();
+        let movie_due = due_date(&library, movie_id, member_id);
+
+        let renewed = library.renew_loan(book_id, member_id).unwrap();
+        assert_eq!(renewed, book_due + Months::new(1));
+        assert_eq!(due_date(&library, book_id, member_id), renewed);
+        assert_eq!(library.members[&member_id].items[&book_id].renewed_count, 1);
+
+        library.renew_loan(movie_id, member_id).unwrap();
+        assert_eq!(due_date(&library, movie_id, member_id), movie_due + Months::new(2));
+    }
+
+    #[test]
+    fn other_formats_renew_like_books() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Magazine");
+        let due = due_date(&library, item_id, member_id);
+        assert_eq!(due, start() + Duration::days(30));
+        let renewed = library.renew_loan(item_id, member_id).unwrap();
+        assert_eq!(renewed, due + Months::new(1));
+        assert_eq!(due_date(&library, item_id, member_id), renewed);
+    }
+
+    #[test]
+    fn hourly_loans_renew_by_the_hour() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Laptop");
+        let renewed = library.renew_loan(item_id, member_id).unwrap();
+        assert_eq!(renewed, start() + Duration::hours(8));
+    }
+
+    #[test]
+    fn renewing_brings_an_overdue_loan_back_in_time() {
+        let mut library = library_at(start());
+        let (item_id, member_id) = lend(&mut library, "Book");
+        let due = due_date(&library, item_id, member_id);
+        library.clock = Box::new(FixedClock(due + Duration::days(1)));
+        assert_eq!(library.overdue_count(), 1);
+        library.renew_loan(item_id, member_id).unwrap();
+        assert_eq!(library.overdue_count(), 0);
+    }
+
+    #[test]
+    fn expired_members_cannot_borrow() {
+        let mut library = library_at(start());
+        let item_id = add(&mut library, "Book");
+        let member_id = library.register_member("Ada").unwrap();
+        library.clock = Box::new(FixedClock(start() + Months::new(13)));
+        assert!(library.book_issue(item_id, member_id.to_string()).is_err());
+        assert!(library.members[&member_id].items.is_empty());
+    }
+}
@@ -0,0 +1,160 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::{LiItem, Library};
+
+/// The physical state of a copy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum Condition {
+    New,
+    Good,
+    Worn,
+    Damaged,
+}
+
+impl Condition {
+    pub const ALL: [Condition; 4] = [Condition::New, Condition::Good, Condition::Worn, Condition::Damaged];
+
+    pub fn label(self) -> String {
+        match self {
+            Condition::New => tr!("New"),
+            Condition::Good => tr!("Good"),
+            Condition::Worn => tr!("Worn"),
+            Condition::Damaged => tr!("Damaged"),
+        }
+    }
+
+    /// Whether copies in this condition need looking at.
+    pub fn is_poor(self) -> bool {
+        matches!(self, Condition::Worn | Condition::Damaged)
+    }
+}
+
+/// A change to a copy's condition, or a remark about it, e.g. made when
+/// it came back from a loan.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct ConditionNote {
+    pub at: DateTime<Utc>,
+    pub from: Condition,
+    pub to: Condition,
+    pub text: String,
+    pub by: String,
+}
+
+impl LiItem {
+    /// How many copies are in `condition`. Copies not recorded otherwise are
+    /// in good condition; when there are fewer copies than recorded, the
+    /// ones gone are taken to be the worst.
+    pub fn copies_in_condition(&self, condition: Condition) -> u32 {
+        let mut left = self.copies;
+        for c in [Condition::New, Condition::Worn, Condition::Damaged] {
+            let n = self.conditions.get(&c).copied().unwrap_or(0).min(left);
+            if c == condition {
+                return n;
+            }
+            left -= n;
+        }
+        left
+    }
+
+    /// E.g. "Good (2), Worn (1)".
+    pub fn condition_text(&self) -> String {
+        Condition::ALL
+            .iter()
+            .map(|&c| (c, self.copies_in_condition(c)))
+            .filter(|(_, n)| *n > 0)
+            .map(|(c, n)| format!("{} ({})", c.label(), n))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// An item with copies in poor condition.
+#[derive(Clone)]
+pub struct MaintenanceRow {
+    pub item_id: u32,
+    pub title: Arc<str>,
+    pub call_number: String,
+    pub shelf_location: String,
+    pub worn: u32,
+    pub damaged: u32,
+    pub last_note: Option<ConditionNote>,
+}
+
+impl Library {
+    /// Records that one copy of an item went from one condition to another,
+    /// with an optional note. A note alone can be recorded by keeping the
+    /// condition the same.
+    pub fn change_condition(&mut self, item_id: u32, from: Condition, to: Condition, note: &str) -> Result<(), String> {
+        self.only_at_server()?;
+        let note = note.trim();
+        if from == to && note.is_empty() {
+            return Err(tr!("Choose a new condition or enter a note"));
+        }
+        let at = self.clock.now();
+        let by = self.current_user.clone();
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        if item.copies_in_condition(from) == 0 {
+            return Err(tr!("No copies are in the condition {}", from.label()));
+        }
+        if from != to {
+            // Stored as counted, so copies gone since drop out of the map
+            item.conditions = [Condition::New, Condition::Worn, Condition::Damaged]
+                .into_iter()
+                .map(|c| (c, item.copies_in_condition(c) + (c == to) as u32 - (c == from) as u32))
+                .filter(|(_, n)| *n > 0)
+                .collect();
+        }
+        item.condition_notes.push(ConditionNote { at, from, to, text: note.to_string(), by });
+        tracing::info!(item_id, "Copy condition changed from {:?} to {:?}", from, to);
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+
+    /// Items with worn or damaged copies, the most damaged first.
+    pub fn maintenance_report(&self) -> Vec<MaintenanceRow> {
+        let mut rows: Vec<MaintenanceRow> = self
+            .items
+            .values()
+            .filter(|item| Condition::ALL.iter().any(|&c| c.is_poor() && item.copies_in_condition(c) > 0))
+            .map(|item| MaintenanceRow {
+                item_id: item.id,
+                title: item.title.clone(),
+                call_number: item.call_number.clone(),
+                shelf_location: item.shelf_location.clone(),
+                worn: item.copies_in_condition(Condition::Worn),
+                damaged: item.copies_in_condition(Condition::Damaged),
+                last_note: item.condition_notes.last().cloned(),
+            })
+            .collect();
+        rows.sort_by(|a, b| b.damaged.cmp(&a.damaged).then(b.worn.cmp(&a.worn)).then(a.item_id.cmp(&b.item_id)));
+        rows
+    }
+}
+
+pub fn maintenance_report_headers() -> Vec<String> {
+    vec![tr!("Item"), tr!("Title"), tr!("Call Number"), tr!("Shelf Location"), tr!("Worn"), tr!("Damaged"), tr!("Last Note")]
+}
+
+pub fn maintenance_report_rows(rows: &[MaintenanceRow]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|r| {
+            vec![
+                r.item_id.to_string(),
+                r.title.to_string(),
+                r.call_number.clone(),
+                r.shelf_location.clone(),
+                r.worn.to_string(),
+                r.damaged.to_string(),
+                r.last_note.as_ref().map_or(String::new(), |note| {
+                    let date = note.at.with_timezone(&Local).format("%Y-%m-%d");
+                    if note.text.is_empty() { format!("{} {}", date, note.to.label()) } else { format!("{} {}", date, note.text) }
+                }),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,160 @@
+use std::fs::File;
+
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::migrate;
+use crate::sync::SyncMode;
+use crate::webhooks::WebhookEvent;
+
+const CONFIG_PATH: &str = "config.json";
+
+/// The colour scheme the window uses.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum Appearance {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl Appearance {
+    pub const ALL: [Appearance; 3] = [Appearance::System, Appearance::Light, Appearance::Dark];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Appearance::System => "Follow System",
+            Appearance::Light => "Light",
+            Appearance::Dark => "Dark",
+        }
+    }
+}
+
+/// Application settings that aren't part of the library state.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Config {
+    pub backup_dir: String,
+    // Zero disables the periodic backups
+    pub backup_interval_minutes: u32,
+    pub backup_retention: usize,
+    // Where reports saved automatically, e.g. cash reconciliations, go
+    pub reports_dir: String,
+    // Members inactive this many years are anonymized; zero disables it
+    pub anonymize_after_years: u32,
+    // Minutes without input before the main window locks; zero never locks
+    pub idle_lock_minutes: u32,
+    pub appearance: Appearance,
+    // A code from i18n::LANGUAGES; empty follows the system locale
+    pub language: String,
+    // Outgoing mail server; an empty host turns email off
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    // Stored as entered, so keep config.json private
+    pub smtp_password: String,
+    pub mail_from: String,
+    // Addresses the weekly circulation report is sent to; none turns it off
+    pub report_recipients: Vec<String>,
+    pub report_weekday: Weekday,
+    // Local hour of the day from which the report is due
+    pub report_hour: u32,
+    // So the report isn't sent twice when the app is restarted that day
+    pub report_last_sent: Option<NaiveDate>,
+    // Weekly digest emails to members about their loans, holds and fines
+    pub digest_enabled: bool,
+    pub digest_weekday: Weekday,
+    pub digest_hour: u32,
+    pub digest_last_sent: Option<NaiveDate>,
+    // Day the nightly plugin hooks last ran, for the same reason
+    pub plugins_last_nightly: Option<NaiveDate>,
+    pub sync_mode: SyncMode,
+    // host:port the server listens on or the clients connect to
+    pub sync_address: String,
+    // Shared by a server and its clients, which must send it before they
+    // are sent the library or may change it; sync won't start without it
+    pub sync_secret: String,
+    // SIP2 server for self-check machines (with the sip2 feature); zero
+    // turns it off
+    pub sip2_port: u16,
+    // Sent as AO in every SIP2 response
+    pub sip2_institution: String,
+    // Login terminals must send; the server won't start without both
+    pub sip2_username: String,
+    pub sip2_password: String,
+    // Offers the catalog and issuing to other applications on the session
+    // bus; any program run by the same desktop user can use it
+    pub dbus_service: bool,
+    // SRU endpoint searched for records to copy when adding items
+    pub sru_url: String,
+    // Where the exported catalog website is published, for links in the
+    // new arrivals feed
+    pub website_url: String,
+    // The catalog CSV read on first start, and watched for changes made by
+    // other systems; empty turns the watching off
+    pub catalog_csv: String,
+    // Circulation events are posted to each of these as JSON; none turns
+    // the webhooks off
+    pub webhook_urls: Vec<String>,
+    pub webhook_events: Vec<WebhookEvent>,
+    // Loans that fell due up to here have had their overdue webhook
+    pub webhook_overdue_checked: Option<DateTime<Utc>>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            backup_dir: "backups".to_string(),
+            backup_interval_minutes: 15,
+            backup_retention: 20,
+            reports_dir: "reports".to_string(),
+            anonymize_after_years: 0,
+            idle_lock_minutes: 10,
+            appearance: Appearance::System,
+            language: String::new(),
+            smtp_host: String::new(),
+            smtp_port: 587,
+            smtp_username: String::new(),
+            smtp_password: String::new(),
+            mail_from: String::new(),
+            report_recipients: Vec::new(),
+            report_weekday: Weekday::Fri,
+            report_hour: 18,
+            report_last_sent: None,
+            digest_enabled: false,
+            digest_weekday: Weekday::Mon,
+            digest_hour: 9,
+            digest_last_sent: None,
+            plugins_last_nightly: None,
+            sync_mode: SyncMode::Off,
+            sync_address: String::new(),
+            sync_secret: String::new(),
+            sip2_port: 0,
+            sip2_institution: String::new(),
+            sip2_username: String::new(),
+            sip2_password: String::new(),
+            dbus_service: false,
+            sru_url: "http://lx2.loc.gov:210/LCDB".to_string(),
+            website_url: String::new(),
+            catalog_csv: "output.csv".to_string(),
+            webhook_urls: Vec::new(),
+            webhook_events: WebhookEvent::ALL.to_vec(),
+            webhook_overdue_checked: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn load() -> Config {
+        let Ok(file) = File::open(CONFIG_PATH) else { return Config::default() };
+        migrate::CONFIG.read(file).unwrap_or_else(|e| {
+            tracing::warn!("Failed to read {}, using the defaults: {}", CONFIG_PATH, e);
+            Config::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), Box<dyn std::error::Error>> {
+        migrate::CONFIG.write_pretty(File::create(CONFIG_PATH)?, self)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::transactions::TransactionKind;
+use crate::{LiItem, Library};
+
+/// Where a copy of an item is in its life, from being ordered to being
+/// withdrawn from stock.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum CopyStatus {
+    OnOrder,
+    Processing,
+    Available,
+    OnLoan,
+    OnHoldShelf,
+    InTransit,
+    InRepair,
+    Lost,
+    Withdrawn,
+}
+
+impl CopyStatus {
+    pub const ALL: [CopyStatus; 9] = [
+        CopyStatus::OnOrder,
+        CopyStatus::Processing,
+        CopyStatus::Available,
+        CopyStatus::OnLoan,
+        CopyStatus::OnHoldShelf,
+        CopyStatus::InTransit,
+        CopyStatus::InRepair,
+        CopyStatus::Lost,
+        CopyStatus::Withdrawn,
+    ];
+
+    // The ones staff set by hand; the others follow from orders, loans,
+    // holds and transfers
+    pub const MANUAL: [CopyStatus; 5] =
+        [CopyStatus::Processing, CopyStatus::Available, CopyStatus::InRepair, CopyStatus::Lost, CopyStatus::Withdrawn];
+
+    pub fn label(self) -> String {
+        match self {
+            CopyStatus::OnOrder => tr!("On order"),
+            CopyStatus::Processing => tr!("Processing"),
+            CopyStatus::Available => tr!("Available"),
+            CopyStatus::OnLoan => tr!("On loan"),
+            CopyStatus::OnHoldShelf => tr!("On hold shelf"),
+            CopyStatus::InTransit => tr!("In transit"),
+            CopyStatus::InRepair => tr!("In repair"),
+            CopyStatus::Lost => tr!("Lost"),
+            CopyStatus::Withdrawn => tr!("Withdrawn"),
+        }
+    }
+
+    /// Whether a copy may go straight from this status to `to`.
+    pub fn can_become(self, to: CopyStatus) -> bool {
+        use CopyStatus::*;
+        matches!(
+            (self, to),
+            (OnOrder, Processing)
+                | (Processing, Available | InRepair | Withdrawn)
+                | (Available, Processing | OnLoan | OnHoldShelf | InTransit | InRepair | Lost | Withdrawn)
+                | (OnLoan, Available | Lost)
+                | (OnHoldShelf, Available | OnLoan)
+                | (InTransit, Available | Lost)
+                | (InRepair, Available | Withdrawn)
+                | (Lost, Available | Withdrawn)
+        )
+    }
+
+    // Copies on order haven't arrived and withdrawn ones are gone, so they
+    // aren't part of an item's `copies`
+    fn in_stock(self) -> bool {
+        !matches!(self, CopyStatus::OnOrder | CopyStatus::Withdrawn)
+    }
+}
+
+impl LiItem {
+    /// How many copies have `status`. Loans are whatever copies are in
+    /// stock but accounted for by no other status.
+    pub fn copies_in(&self, status: CopyStatus) -> u32 {
+        match status {
+            CopyStatus::Available => self.avail_copies,
+            CopyStatus::Lost => self.lost_copies,
+            CopyStatus::OnLoan => self.copies.saturating_sub(self.avail_copies + self.lost_copies + self.set_aside()),
+            _ => self.copy_statuses.get(&status).copied().unwrap_or(0),
+        }
+    }
+
+    /// Copies in stock that are neither available, on loan nor lost.
+    pub(crate) fn set_aside(&self) -> u32 {
+        self.copy_statuses.iter().filter(|(s, _)| s.in_stock()).map(|(_, n)| n).sum()
+    }
+
+    /// The statuses the item's copies have, with how many copies have each.
+    pub fn statuses(&self) -> Vec<(CopyStatus, u32)> {
+        CopyStatus::ALL.iter().map(|&s| (s, self.copies_in(s))).filter(|(_, n)| *n > 0).collect()
+    }
+
+    /// The status that matters most at the desk: available if any copy is,
+    /// otherwise when one might be.
+    pub fn status(&self) -> Option<CopyStatus> {
+        use CopyStatus::*;
+        [Available, OnHoldShelf, OnLoan, InTransit, InRepair, Processing, OnOrder, Lost, Withdrawn]
+            .into_iter()
+            .find(|&s| self.copies_in(s) > 0)
+    }
+
+    /// E.g. "Available (2), On loan (1)".
+    pub fn status_text(&self) -> String {
+        self.statuses().iter().map(|(s, n)| format!("{} ({})", s.label(), n)).collect::<Vec<_>>().join(", ")
+    }
+
+    fn take_copies(&mut self, status: CopyStatus, count: u32) {
+        match status {
+            CopyStatus::Available => self.avail_copies -= count,
+            CopyStatus::Lost => self.lost_copies -= count,
+            // Follows from the other counts
+            CopyStatus::OnLoan => {}
+            _ => {
+                let left = self.copies_in(status) - count;
+                if left == 0 {
+                    self.copy_statuses.remove(&status);
+                } else {
+                    self.copy_statuses.insert(status, left);
+                }
+            }
+        }
+        if status.in_stock() {
+            self.copies -= count;
+        }
+    }
+
+    fn put_copies(&mut self, status: CopyStatus, count: u32) {
+        if status.in_stock() {
+            self.copies += count;
+        }
+        match status {
+            CopyStatus::Available => self.avail_copies += count,
+            CopyStatus::Lost => self.lost_copies += count,
+            CopyStatus::OnLoan => {}
+            _ => *self.copy_statuses.entry(status).or_insert(0) += count,
+        }
+    }
+
+    /// Moves `count` copies from one status to another, if the lifecycle
+    /// allows it and there are that many.
+    pub(crate) fn move_copies(&mut self, from: CopyStatus, to: CopyStatus, count: u32) -> Result<(), String> {
+        if !from.can_become(to) {
+            return Err(tr!("Copies can't go from {} to {}", from.label(), to.label()));
+        }
+        let have = self.copies_in(from);
+        if have < count {
+            return Err(tr!("Only {} copies have the status {}", have, from.label()));
+        }
+        self.take_copies(from, count);
+        self.put_copies(to, count);
+        Ok(())
+    }
+
+    pub(crate) fn copies_ordered(&mut self, count: u32) {
+        self.put_copies(CopyStatus::OnOrder, count);
+    }
+
+    pub(crate) fn order_cancelled(&mut self, count: u32) {
+        let count = count.min(self.copies_in(CopyStatus::OnOrder));
+        self.take_copies(CopyStatus::OnOrder, count);
+    }
+
+    // For undoing a withdrawal: puts the copies back on the shelf
+    pub(crate) fn undo_withdrawal(&mut self, count: u32) {
+        let count = count.min(self.copies_in(CopyStatus::Withdrawn));
+        self.take_copies(CopyStatus::Withdrawn, count);
+        self.put_copies(CopyStatus::Available, count);
+    }
+
+    /// Books in copies that arrived from a supplier. They need processing
+    /// before they can go on the shelf; copies ordered for the item are
+    /// counted off first.
+    pub(crate) fn receive_copies(&mut self, count: u32) {
+        let ordered = count.min(self.copies_in(CopyStatus::OnOrder));
+        self.take_copies(CopyStatus::OnOrder, ordered);
+        self.put_copies(CopyStatus::Processing, count);
+    }
+}
+
+impl Library {
+    /// Changes the status of `count` copies of an item by hand, e.g. to send
+    /// them for repair or withdraw them. Loans, holds, transfers and orders
+    /// change their copies' status themselves.
+    pub fn change_copy_status(&mut self, item_id: u32, from: CopyStatus, to: CopyStatus, count: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if count == 0 {
+            return Err(tr!("Change at least one copy"));
+        }
+        if let Some(status) = [from, to].into_iter().find(|s| !CopyStatus::MANUAL.contains(s)) {
+            return Err(tr!("The status {} is set by loans, holds, transfers and orders, not by hand", status.label()));
+        }
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        item.move_copies(from, to, count)?;
+        tracing::info!(item_id, count, "Copies changed from {:?} to {:?}", from, to);
+        if to == CopyStatus::Lost {
+            self.log_transaction(TransactionKind::CopyLost, item_id, 0);
+        } else if from == CopyStatus::Lost && to == CopyStatus::Available {
+            self.log_transaction(TransactionKind::CopyFound, item_id, 0);
+        }
+        self.dirty = true;
+        self.item_changed(item_id);
+        // A copy back on the shelf may be what a member is waiting for
+        if to == CopyStatus::Available {
+            self.fill_holds(item_id);
+        }
+        Ok(())
+    }
+}
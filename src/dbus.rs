@@ -0,0 +1,126 @@
+use std::cell::Cell;
+use std::sync::{Arc, RwLock};
+
+use gtk::prelude::*;
+use gtk::{gio, glib};
+
+use crate::search;
+use crate::Library;
+
+pub const BUS_NAME: &str = "org.rustlms.Library";
+const OBJECT_PATH: &str = "/org/rustlms/Library";
+const INTERFACE: &str = "org.rustlms.Library";
+const ERROR_NAME: &str = "org.rustlms.Library.Error.Failed";
+// Most results SearchCatalog returns
+const SEARCH_LIMIT: usize = 50;
+// How often the overdue count is checked for OverdueCountChanged
+const OVERDUE_CHECK_SECONDS: u32 = 60;
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.rustlms.Library">
+    <method name="SearchCatalog">
+      <arg name="query" type="s" direction="in"/>
+      <!-- item ID, title, author, copies on the shelf -->
+      <arg name="items" type="a(ussu)" direction="out"/>
+    </method>
+    <method name="IssueItem">
+      <arg name="item_id" type="u" direction="in"/>
+      <arg name="member_id" type="u" direction="in"/>
+    </method>
+    <method name="GetOverdueCount">
+      <arg name="count" type="u" direction="out"/>
+    </method>
+    <signal name="OverdueCountChanged">
+      <arg name="count" type="u"/>
+    </signal>
+  </interface>
+</node>
+"#;
+
+impl Library {
+    /// Loans past their due date, over all members.
+    pub fn overdue_count(&self) -> u32 {
+        let now = self.clock.now();
+        self.members.values().flat_map(|m| m.items.values()).filter(|inst| inst.due_date < now).count() as u32
+    }
+}
+
+fn call(library: &Arc<RwLock<Library>>, sender: Option<&str>, method: &str, params: &glib::Variant) -> Result<Option<glib::Variant>, String> {
+    match method {
+        "SearchCatalog" => {
+            let (query,) = params.get::<(String,)>().ok_or("Expected (s)")?;
+            let lib = library.read().unwrap();
+            let found: Vec<(u32, String, String, u32)> = search::search(lib.items.values(), &query)
+                .ids
+                .iter()
+                .filter_map(|id| lib.items.get(id))
+                .take(SEARCH_LIMIT)
+                .map(|i| (i.id, i.title.to_string(), i.author.clone().unwrap_or_default(), i.avail_copies))
+                .collect();
+            Ok(Some((found,).to_variant()))
+        }
+        "IssueItem" => {
+            let (item_id, member_id) = params.get::<(u32, u32)>().ok_or("Expected (uu)")?;
+            library.write().unwrap().book_issue(item_id, member_id.to_string())?;
+            tracing::info!(item_id, member_id, sender, "Item issued over D-Bus");
+            Ok(None)
+        }
+        "GetOverdueCount" => Ok(Some((library.read().unwrap().overdue_count(),).to_variant())),
+        _ => Err(format!("Unknown method {}", method)),
+    }
+}
+
+/// Offers the library on the session bus as `org.rustlms.Library`, for
+/// scripts and desktop tools: searching the catalog, issuing items (logged
+/// as the staff member signed in) and following the number of overdue
+/// loans.
+pub fn start(app: &adw::Application, library: Arc<RwLock<Library>>) {
+    let Some(connection) = app.dbus_connection() else {
+        tracing::warn!("No D-Bus session bus; the D-Bus interface is off");
+        return;
+    };
+    let interface = match gio::DBusNodeInfo::for_xml(INTROSPECTION).map(|node| node.lookup_interface(INTERFACE)) {
+        Ok(Some(interface)) => interface,
+        Ok(None) => return,
+        Err(e) => {
+            tracing::error!("Bad D-Bus introspection data: {}", e);
+            return;
+        }
+    };
+
+    let registered = connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(glib::clone!(
+            #[strong]
+            library,
+            move |_, sender, _, _, method, params, invocation| match call(&library, sender, method, &params) {
+                Ok(value) => invocation.return_value(value.as_ref()),
+                Err(e) => invocation.return_dbus_error(ERROR_NAME, &e),
+            }
+        ))
+        .build();
+    if let Err(e) = registered {
+        tracing::error!("Failed to register the D-Bus interface: {}", e);
+        return;
+    }
+    gio::bus_own_name_on_connection(
+        &connection,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        |_, name| tracing::info!("D-Bus name {} acquired", name),
+        |_, name| tracing::warn!("D-Bus name {} is taken by another process", name),
+    );
+
+    let last_count = Cell::new(library.read().unwrap().overdue_count());
+    glib::timeout_add_seconds_local(OVERDUE_CHECK_SECONDS, move || {
+        let count = library.read().unwrap().overdue_count();
+        if count != last_count.replace(count) {
+            let result = connection.emit_signal(None, OBJECT_PATH, INTERFACE, "OverdueCountChanged", Some(&(count,).to_variant()));
+            if let Err(e) = result {
+                tracing::warn!("Failed to emit OverdueCountChanged: {}", e);
+            }
+        }
+        glib::ControlFlow::Continue
+    });
+}
@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::i18n::tr;
+use crate::search::normalize;
+use crate::transfers::TransferStatus;
+use crate::{Library, LiItem};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DuplicateReason {
+    Isbn,
+    TitleAuthorYearFormat,
+}
+
+impl DuplicateReason {
+    pub fn describe(self) -> String {
+        match self {
+            DuplicateReason::Isbn => tr!("Same ISBN"),
+            DuplicateReason::TitleAuthorYearFormat => tr!("Same title, author, year and format"),
+        }
+    }
+}
+
+/// Catalog entries that probably describe the same title, lowest ID first.
+pub struct DuplicateGroup {
+    pub ids: Vec<u32>,
+    pub reason: DuplicateReason,
+}
+
+// Only the digits and a trailing check character count, so "0-14-044913-8"
+// and "0140449138" match
+fn isbn_key(item: &LiItem) -> Option<String> {
+    let key: String = item
+        .isbn
+        .as_deref()?
+        .chars()
+        .filter(|c| c.is_ascii_digit() || *c == 'X' || *c == 'x')
+        .map(|c| c.to_ascii_uppercase())
+        .collect();
+    (!key.is_empty()).then_some(key)
+}
+
+impl Library {
+    /// Groups of items sharing an ISBN, or a normalized title, author, year
+    /// and format. Items with different ISBNs are different editions, so a title
+    /// group is only reported when at most one ISBN appears in it.
+    pub fn find_duplicates(&self) -> Vec<DuplicateGroup> {
+        let mut by_isbn: BTreeMap<String, Vec<u32>> = BTreeMap::new();
+        let mut by_title: BTreeMap<(String, String, u32, String), Vec<u32>> = BTreeMap::new();
+        let mut ids: Vec<u32> = self.items.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let item = &self.items[&id];
+            if let Some(isbn) = isbn_key(item) {
+                by_isbn.entry(isbn).or_default().push(id);
+            }
+            let author = normalize(item.author.as_deref().unwrap_or(""));
+            let key = (normalize(&item.title), author, item.year, item.format.to_lowercase());
+            by_title.entry(key).or_default().push(id);
+        }
+
+        let mut groups: Vec<DuplicateGroup> = by_isbn
+            .into_values()
+            .filter(|ids| ids.len() > 1)
+            .map(|ids| DuplicateGroup { ids, reason: DuplicateReason::Isbn })
+            .collect();
+        for ids in by_title.into_values().filter(|ids| ids.len() > 1) {
+            let isbns: BTreeSet<String> = ids.iter().filter_map(|id| isbn_key(&self.items[id])).collect();
+            if isbns.len() > 1 || groups.iter().any(|g| g.ids == ids) {
+                continue;
+            }
+            groups.push(DuplicateGroup { ids, reason: DuplicateReason::TitleAuthorYearFormat });
+        }
+        groups.sort_by_key(|g| g.ids[0]);
+        groups
+    }
+
+    /// Folds item `merge` into `keep`: its copies, loans, reviews and tags
+    /// move over, and fines, transactions, transfers and reading history
+    /// that mention it are changed to `keep`. Then `merge` is deleted.
+    pub fn merge_items(&mut self, keep: u32, merge: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if keep == merge {
+            return Err(tr!("Pick two different items"));
+        }
+        let (Some(kept), Some(merged)) = (self.items.get(&keep), self.items.get(&merge)) else {
+            return Err(tr!("Item not found"));
+        };
+        if kept.branch != merged.branch {
+            return Err(tr!("The items are held at different branches; transfer them to one branch first"));
+        }
+        if self.transfers.iter().any(|t| (t.item_id == keep || t.item_id == merge) && t.status != TransferStatus::Received) {
+            return Err(tr!("Item has an open transfer"));
+        }
+        if let Some(member) = self.members.values().find(|m| m.items.contains_key(&keep) && m.items.contains_key(&merge)) {
+            return Err(tr!("Member {} has both items on loan", member.id));
+        }
+
+        let merged = self.items.remove(&merge).unwrap();
+        let kept = self.items.get_mut(&keep).unwrap();
+        kept.copies += merged.copies;
+        kept.avail_copies += merged.avail_copies;
+        kept.lost_copies += merged.lost_copies;
+        for (status, count) in merged.copy_statuses {
+            *kept.copy_statuses.entry(status).or_insert(0) += count;
+        }
+        for (condition, count) in merged.conditions {
+            *kept.conditions.entry(condition).or_insert(0) += count;
+        }
+        kept.condition_notes.extend(merged.condition_notes);
+        kept.condition_notes.sort_by_key(|note| note.at);
+        kept.reviews.extend(merged.reviews);
+        for tag in merged.tags {
+            if !kept.tags.contains(&tag) {
+                kept.tags.push(tag);
+            }
+        }
+        if kept.isbn.is_none() {
+            kept.isbn = merged.isbn;
+        }
+        if kept.desc.trim().is_empty() {
+            kept.desc = merged.desc;
+        }
+        let title = kept.title.clone();
+
+        for member in self.members.values_mut() {
+            if let Some(mut inst) = member.items.remove(&merge) {
+                inst.id = keep;
+                inst.title = title.clone();
+                member.items.insert(keep, inst);
+            }
+            for fine in member.fines.iter_mut().filter(|f| f.item_id == merge) {
+                fine.item_id = keep;
+            }
+            for loan in member.history.iter_mut().filter(|l| l.item_id == merge) {
+                loan.item_id = keep;
+            }
+        }
+        for tx in self.transactions.iter_mut().filter(|tx| tx.item_id == merge) {
+            tx.item_id = keep;
+        }
+        for transfer in self.transfers.iter_mut().filter(|t| t.item_id == merge) {
+            transfer.item_id = keep;
+        }
+        self.undo_stack = std::mem::take(&mut self.undo_stack).into_iter().filter_map(|e| e.without_item(merge)).collect();
+
+        tracing::info!(keep, merge, "Items merged");
+        self.dirty = true;
+        self.reloaded();
+        Ok(())
+    }
+}
@@ -0,0 +1,2949 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, NaiveDate, Utc};
+use gtk::prelude::*;
+use gtk::{glib, Box as GtkBox, DropDown, Entry, Label, ScrolledWindow};
+
+use crate::acquisitions::OrderLine;
+use crate::auth::{Role, Session, UserStore};
+use crate::charts::Heatmap;
+use crate::backup;
+use crate::batch::BatchChange;
+use crate::condition::Condition;
+use crate::config::Config;
+use crate::copies::CopyStatus;
+use crate::editing::{EditError, EditableDetails, FieldDiff, ItemDetails, MemberDetails, NewItem};
+use crate::events::{self, NewEvent, Room};
+use crate::facets;
+use crate::fines::{parse_money, WaiverReason};
+use crate::i18n::tr;
+use crate::import::{self, CsvSchema, ImportKind, ImportReport};
+use crate::pii;
+use crate::serials::{Frequency, NewSubscription};
+use crate::sets::{ItemLink, VolumeSet};
+use crate::sru::{self, MarcRecord};
+use crate::table::Table;
+use crate::weeding::{WeedingCandidate, DEFAULT_WEEDING_YEARS};
+use crate::Library;
+
+pub fn show_error(parent: &impl IsA<gtk::Window>, text: &str, detail: &str) {
+    tracing::error!(detail, "{}", text);
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(gtk::MessageType::Error)
+        .buttons(gtk::ButtonsType::Close)
+        .text(text)
+        .secondary_text(detail)
+        .build();
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+pub fn show_info(parent: &impl IsA<gtk::Window>, text: &str, detail: &str) {
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(gtk::MessageType::Info)
+        .buttons(gtk::ButtonsType::Close)
+        .text(text)
+        .secondary_text(detail)
+        .build();
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Asks a yes/no question; `on_confirmed` runs only if the user agrees.
+pub fn confirm(parent: &impl IsA<gtk::Window>, text: &str, detail: &str, on_confirmed: impl Fn() + 'static) {
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(gtk::MessageType::Warning)
+        .buttons(gtk::ButtonsType::YesNo)
+        .text(text)
+        .secondary_text(detail)
+        .build();
+    dialog.connect_response(move |dialog, response| {
+        dialog.close();
+        if response == gtk::ResponseType::Yes {
+            on_confirmed();
+        }
+    });
+    dialog.show();
+}
+
+// GTK doesn't keep native dialogs alive while they are shown, so the chooser
+// holds on to itself until it gets a response.
+fn run_chooser(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    action: gtk::FileChooserAction,
+    default_name: Option<&str>,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    let accept_label = match action {
+        gtk::FileChooserAction::Save => tr!("Save"),
+        gtk::FileChooserAction::SelectFolder => tr!("Select"),
+        _ => tr!("Open"),
+    };
+    let chooser = gtk::FileChooserNative::new(
+        Some(title),
+        Some(parent),
+        action,
+        Some(&accept_label),
+        Some(&tr!("Cancel")),
+    );
+    if let Some(name) = default_name {
+        chooser.set_current_name(name);
+    }
+
+    let holder = Rc::new(RefCell::new(Some(chooser.clone())));
+    chooser.connect_response(move |chooser, response| {
+        if response == gtk::ResponseType::Accept {
+            if let Some(path) = chooser.file().and_then(|f| f.path()) {
+                on_chosen(path);
+            }
+        }
+        holder.borrow_mut().take();
+    });
+    chooser.show();
+}
+
+pub fn choose_save_path(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    default_name: &str,
+    on_chosen: impl Fn(PathBuf) + 'static,
+) {
+    run_chooser(parent, title, gtk::FileChooserAction::Save, Some(default_name), on_chosen);
+}
+
+/// Asks for a passphrase to encrypt an export of member data with, then
+/// where to save it. Without `allow_plain` there is no way to skip the
+/// passphrase. `on_chosen` gets the path and the passphrase, or None when
+/// the export is to be left unencrypted.
+pub fn choose_personal_export_path(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    default_name: &str,
+    allow_plain: bool,
+    on_chosen: impl Fn(PathBuf, Option<String>) + 'static,
+) {
+    let mut buttons = vec![(tr!("Cancel"), gtk::ResponseType::Cancel)];
+    if allow_plain {
+        buttons.push((tr!("Export Unencrypted"), gtk::ResponseType::Reject));
+    }
+    buttons.push((tr!("Continue"), gtk::ResponseType::Accept));
+    let buttons: Vec<(&str, gtk::ResponseType)> = buttons.iter().map(|(label, response)| (label.as_str(), *response)).collect();
+    let dialog = gtk::Dialog::with_buttons(
+        Some(title),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &buttons,
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let explanation = Label::new(Some(&tr!(
+        "This export holds members' personal data, so it is encrypted with a passphrase. Give the passphrase to the recipient separately; any age tool opens the file with it."
+    )));
+    explanation.set_wrap(true);
+    explanation.set_max_width_chars(60);
+    content.append(&explanation);
+    let passphrase_entry = gtk::PasswordEntry::new();
+    passphrase_entry.set_show_peek_icon(true);
+    let repeat_entry = gtk::PasswordEntry::new();
+    repeat_entry.set_activates_default(true);
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::PasswordEntry); 2] = [(tr!("Passphrase:"), &passphrase_entry), (tr!("Repeat:"), &repeat_entry)];
+    for (row, (name, entry)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        entry.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*entry, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    let parent = parent.clone().upcast::<gtk::Window>();
+    let (title, default_name) = (title.to_string(), default_name.to_string());
+    let on_chosen = Rc::new(on_chosen);
+    dialog.connect_response(move |dialog, response| {
+        let passphrase = match response {
+            gtk::ResponseType::Accept => {
+                let passphrase = passphrase_entry.text().to_string();
+                if passphrase.chars().count() < pii::MIN_PASSPHRASE_LEN {
+                    show_error(dialog, &tr!("Passphrase too short"), &tr!("Use at least {} characters.", pii::MIN_PASSPHRASE_LEN));
+                    return;
+                }
+                if passphrase != repeat_entry.text().as_str() {
+                    show_error(dialog, &tr!("Passphrases don't match"), &tr!("Enter the same passphrase twice."));
+                    repeat_entry.set_text("");
+                    return;
+                }
+                Some(passphrase)
+            }
+            gtk::ResponseType::Reject => None,
+            _ => {
+                dialog.close();
+                return;
+            }
+        };
+        dialog.close();
+        let name = match passphrase {
+            Some(_) => format!("{}.{}", default_name, pii::ENCRYPTED_EXTENSION),
+            None => default_name.clone(),
+        };
+        let on_chosen = on_chosen.clone();
+        choose_save_path(&parent, &title, &name, move |path| on_chosen(path, passphrase.clone()));
+    });
+    dialog.show();
+}
+
+pub fn choose_open_path(parent: &impl IsA<gtk::Window>, title: &str, on_chosen: impl Fn(PathBuf) + 'static) {
+    run_chooser(parent, title, gtk::FileChooserAction::Open, None, on_chosen);
+}
+
+pub fn choose_folder(parent: &impl IsA<gtk::Window>, title: &str, on_chosen: impl Fn(PathBuf) + 'static) {
+    run_chooser(parent, title, gtk::FileChooserAction::SelectFolder, None, on_chosen);
+}
+
+pub fn show_import_report(parent: &impl IsA<gtk::Window>, report: ImportReport) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Import Report")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Export Bad Rows...").as_str(), gtk::ResponseType::Apply),
+            (tr!("Close").as_str(), gtk::ResponseType::Close),
+        ],
+    );
+    dialog.set_default_width(500);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    content.append(&Label::new(Some(&tr!(
+        "Loaded {} rows, skipped {} rows.",
+        report.loaded, report.skipped
+    ))));
+
+    let errors = report
+        .errors
+        .iter()
+        .map(|e| tr!("Line {}: {}", e.line, e.message))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let error_label = Label::new(Some(&errors));
+    error_label.set_selectable(true);
+    error_label.set_xalign(0.0);
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(200);
+    scrolled_window.set_child(Some(&error_label));
+
+    let expander = gtk::Expander::new(Some(&tr!("Show errors")));
+    expander.set_child(Some(&scrolled_window));
+    content.append(&expander);
+
+    let report = Rc::new(report);
+    dialog.connect_response(move |dialog, response| {
+        if response == gtk::ResponseType::Apply {
+            let report = report.clone();
+            let parent = dialog.clone();
+            choose_save_path(dialog, &tr!("Export Bad Rows"), "bad_rows.csv", move |path| {
+                if let Err(e) = report.export_bad_rows(&path) {
+                    show_error(&parent, &tr!("Failed to export rows"), &e.to_string());
+                }
+            });
+        } else {
+            dialog.close();
+        }
+    });
+    dialog.show();
+}
+
+const PREVIEW_ROWS: usize = 5;
+
+fn parse_char(text: &str, default: u8) -> u8 {
+    match text {
+        "" => default,
+        "\\t" | "tab" => b'\t',
+        _ => text.as_bytes()[0],
+    }
+}
+
+struct ImportWizard {
+    path: PathBuf,
+    kind: ImportKind,
+    delimiter_entry: Entry,
+    quote_entry: Entry,
+    header_list: gtk::StringList,
+    dropdowns: Vec<(&'static str, DropDown)>,
+    preview_label: Label,
+}
+
+impl ImportWizard {
+    fn schema(&self) -> CsvSchema {
+        let mut columns = HashMap::new();
+        for (field, dropdown) in &self.dropdowns {
+            // Entry 0 is "(not mapped)"
+            let selected = dropdown.selected();
+            if selected != gtk::INVALID_LIST_POSITION && selected > 0 {
+                columns.insert(field.to_string(), selected as usize - 1);
+            }
+        }
+        CsvSchema {
+            delimiter: parse_char(&self.delimiter_entry.text(), b','),
+            quote: parse_char(&self.quote_entry.text(), b'"'),
+            columns,
+        }
+    }
+
+    // Re-reads the header and preview rows. Without explicit columns the
+    // mapping is detected from the header names.
+    fn reload(&self, columns: Option<&HashMap<String, usize>>) {
+        let schema = self.schema();
+        let (headers, rows) = match import::preview(&self.path, &schema, PREVIEW_ROWS) {
+            Ok(preview) => preview,
+            Err(e) => {
+                self.preview_label.set_text(&tr!("Cannot read file: {}", e));
+                return;
+            }
+        };
+
+        let not_mapped = tr!("(not mapped)");
+        let mut choices = vec![not_mapped.as_str()];
+        choices.extend(headers.iter().map(String::as_str));
+        self.header_list.splice(0, self.header_list.n_items(), &choices);
+
+        let detected;
+        let columns = match columns {
+            Some(columns) => columns,
+            None => {
+                detected = CsvSchema::detect(self.kind, &headers);
+                &detected
+            }
+        };
+        for (field, dropdown) in &self.dropdowns {
+            let selected = columns.get(*field).filter(|&&i| i < headers.len()).map_or(0, |i| i + 1);
+            dropdown.set_selected(selected as u32);
+        }
+
+        let mut preview = headers.join(" | ");
+        for row in rows {
+            preview.push('\n');
+            preview.push_str(&row.join(" | "));
+        }
+        self.preview_label.set_text(&preview);
+    }
+}
+
+/// Lets the user map the columns of `path` onto catalog or member fields
+/// before importing it. `on_imported` runs after a successful import.
+pub fn show_import_wizard(
+    parent: &impl IsA<gtk::Window>,
+    library: Arc<RwLock<Library>>,
+    path: PathBuf,
+    kind: ImportKind,
+    on_imported: impl Fn() + 'static,
+) {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let saved = import::saved_schema(&file_name);
+    let initial = saved.clone().unwrap_or_default();
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Import {}", file_name)),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Import").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(600);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let delimiter_entry = Entry::new();
+    delimiter_entry.set_text(&(initial.delimiter as char).to_string().replace('\t', "\\t"));
+    delimiter_entry.set_max_width_chars(4);
+    let quote_entry = Entry::new();
+    quote_entry.set_text(&(initial.quote as char).to_string());
+    quote_entry.set_max_width_chars(4);
+
+    let options_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    options_box.append(&Label::new(Some(&tr!("Delimiter:"))));
+    options_box.append(&delimiter_entry);
+    options_box.append(&Label::new(Some(&tr!("Quote:"))));
+    options_box.append(&quote_entry);
+    content.append(&options_box);
+
+    let header_list = gtk::StringList::new(&[]);
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let mut dropdowns = Vec::new();
+    for (row, (field, _)) in kind.fields().iter().enumerate() {
+        let label = Label::new(Some(*field));
+        label.set_xalign(0.0);
+        let dropdown = DropDown::new(Some(header_list.clone()), None::<gtk::Expression>);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(&dropdown, 1, row as i32, 1, 1);
+        dropdowns.push((*field, dropdown));
+    }
+    content.append(&grid);
+
+    let preview_label = Label::new(None);
+    preview_label.set_xalign(0.0);
+    preview_label.add_css_class("monospace");
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(120);
+    scrolled_window.set_child(Some(&preview_label));
+    content.append(&scrolled_window);
+
+    let remember_check = gtk::CheckButton::with_label(&tr!("Remember this mapping for files matching:"));
+    remember_check.set_active(saved.is_some());
+    let pattern_entry = Entry::new();
+    pattern_entry.set_text(&import::default_pattern(&file_name));
+    let remember_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    remember_box.append(&remember_check);
+    remember_box.append(&pattern_entry);
+    content.append(&remember_box);
+
+    let wizard = Rc::new(ImportWizard {
+        path,
+        kind,
+        delimiter_entry: delimiter_entry.clone(),
+        quote_entry: quote_entry.clone(),
+        header_list,
+        dropdowns,
+        preview_label,
+    });
+    wizard.reload(saved.as_ref().map(|s| &s.columns));
+
+    for entry in [&delimiter_entry, &quote_entry] {
+        entry.connect_changed(glib::clone!(
+            #[strong]
+            wizard,
+            move |_| wizard.reload(None)
+        ));
+    }
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+
+        let schema = wizard.schema();
+        if remember_check.is_active() {
+            if let Err(e) = import::remember_schema(&pattern_entry.text(), &schema) {
+                show_error(dialog, &tr!("Failed to save the column mapping"), &e.to_string());
+            }
+        }
+
+        let path = wizard.path.to_string_lossy();
+        let result = match kind {
+            ImportKind::Items => library.write().unwrap().import_csv(&path, &schema),
+            ImportKind::Members => library.write().unwrap().import_members_csv(&path, &schema),
+        };
+        let parent = dialog.transient_for();
+        dialog.close();
+        match (result, parent) {
+            (Ok(report), Some(parent)) => {
+                on_imported();
+                show_import_report(&parent, report);
+            }
+            (Ok(_), None) => on_imported(),
+            (Err(e), Some(parent)) => show_error(&parent, &tr!("Import failed"), &e.to_string()),
+            (Err(e), None) => tracing::error!("Import failed: {}", e),
+        }
+    });
+    dialog.show();
+}
+
+/// Lists the records read from a MARC file and adds them to the catalog
+/// with the number of copies picked.
+pub fn show_import_marc(parent: &impl IsA<gtk::Window>, library: Arc<RwLock<Library>>, records: Vec<MarcRecord>) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Import MARC Records")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Import").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(600);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    content.append(&Label::new(Some(&tr!("{} record(s) will be added to the catalog:", records.len()))));
+    let summaries = records.iter().map(MarcRecord::summary).collect::<Vec<_>>().join("\n");
+    let records_label = Label::new(Some(&summaries));
+    records_label.set_xalign(0.0);
+    records_label.set_wrap(true);
+    records_label.set_selectable(true);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(250);
+    scrolled_window.set_child(Some(&records_label));
+    content.append(&scrolled_window);
+
+    let copies_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    copies_box.append(&Label::new(Some(&tr!("Copies of each:"))));
+    let copies_spin = gtk::SpinButton::with_range(1.0, 999.0, 1.0);
+    copies_box.append(&copies_spin);
+    content.append(&copies_box);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let report = library.write().unwrap().import_marc(&records, copies_spin.value_as_int() as u32);
+        let parent = dialog.transient_for();
+        dialog.close();
+        if let Some(parent) = parent {
+            show_import_report(&parent, report);
+        }
+    });
+    dialog.show();
+}
+
+/// Lists the available backups and replaces the library state with the
+/// selected one. `on_restored` runs after the state has been swapped.
+pub fn show_restore_backup(
+    parent: &impl IsA<gtk::Window>,
+    library: Arc<RwLock<Library>>,
+    config: &Config,
+    on_restored: impl Fn() + 'static,
+) {
+    let backups = backup::list_backups(config);
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Restore from Backup")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Restore").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let list_box = gtk::ListBox::new();
+    for path in &backups {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let label = Label::new(Some(&name));
+        label.set_xalign(0.0);
+        list_box.append(&label);
+    }
+    if backups.is_empty() {
+        content.append(&Label::new(Some(&tr!("No backups found in \"{}\".", config.backup_dir))));
+    }
+    content.append(&Label::new(Some(&tr!("Restoring replaces all current catalog and loan data."))));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(250);
+    scrolled_window.set_child(Some(&list_box));
+    content.append(&scrolled_window);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let Some(row) = list_box.selected_row() else { return };
+        let path = &backups[row.index() as usize];
+        match backup::restore(path) {
+            Ok(restored) => {
+                library.write().unwrap().replace_state(restored);
+                on_restored();
+                dialog.close();
+            }
+            Err(e) => show_error(dialog, &tr!("Failed to restore backup"), &e.to_string()),
+        }
+    });
+    dialog.show();
+}
+
+/// Lists the problems `Library::verify_integrity` finds, with a Fix button
+/// on those that can be repaired safely. `on_repaired` runs after every
+/// change to the library.
+pub fn show_check_database(parent: &impl IsA<gtk::Window>, library: Arc<RwLock<Library>>, on_repaired: impl Fn() + 'static) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Check Database")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Close").as_str(), gtk::ResponseType::Close),
+            (tr!("Fix All").as_str(), gtk::ResponseType::Apply),
+        ],
+    );
+    dialog.set_default_width(500);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let summary_label = Label::new(None);
+    summary_label.set_xalign(0.0);
+    let list_box = gtk::ListBox::new();
+    list_box.set_selection_mode(gtk::SelectionMode::None);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(250);
+    scrolled_window.set_child(Some(&list_box));
+    content.append(&summary_label);
+    content.append(&scrolled_window);
+
+    let on_repaired = Rc::new(on_repaired);
+    // Reruns the check and lists what is left. Boxed so the Fix buttons it
+    // creates can call it again.
+    let fill: Rc<RefCell<Box<dyn Fn()>>> = Rc::new(RefCell::new(Box::new(|| {})));
+    let weak_fill = Rc::downgrade(&fill);
+    *fill.borrow_mut() = Box::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        on_repaired,
+        #[weak]
+        list_box,
+        #[weak]
+        summary_label,
+        #[weak]
+        dialog,
+        move || {
+            while let Some(child) = list_box.first_child() {
+                list_box.remove(&child);
+            }
+            let problems = library.read().unwrap().verify_integrity();
+            let fixable = problems.iter().filter(|p| p.fixable()).count();
+            summary_label.set_text(&if problems.is_empty() {
+                tr!("No problems found.")
+            } else {
+                tr!("{} problem(s) found, {} can be fixed automatically.", problems.len(), fixable)
+            });
+            dialog.set_response_sensitive(gtk::ResponseType::Apply, fixable > 0);
+
+            for problem in problems {
+                let row = GtkBox::new(gtk::Orientation::Horizontal, 10);
+                let label = Label::new(Some(&problem.describe()));
+                label.set_xalign(0.0);
+                label.set_wrap(true);
+                label.set_hexpand(true);
+                row.append(&label);
+                if problem.fixable() {
+                    let fix_button = gtk::Button::with_label(&tr!("Fix"));
+                    let library = library.clone();
+                    let on_repaired = on_repaired.clone();
+                    let weak_fill = weak_fill.clone();
+                    fix_button.connect_clicked(move |button| {
+                        let result = library.write().unwrap().repair(&problem);
+                        if let Err(e) = result {
+                            if let Some(window) = button.root().and_downcast::<gtk::Window>() {
+                                show_error(&window, &tr!("Cannot fix this problem"), &e);
+                            }
+                        }
+                        on_repaired();
+                        if let Some(fill) = weak_fill.upgrade() {
+                            fill.borrow()();
+                        }
+                    });
+                    row.append(&fix_button);
+                } else {
+                    let manual_label = Label::new(Some(&tr!("Fix by hand")));
+                    manual_label.add_css_class("dim-label");
+                    row.append(&manual_label);
+                }
+                list_box.append(&row);
+            }
+        }
+    ));
+    fill.borrow()();
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Apply {
+            dialog.close();
+            return;
+        }
+        library.write().unwrap().repair_all();
+        on_repaired();
+        fill.borrow()();
+    });
+    dialog.show();
+}
+
+/// Lists probable duplicate catalog entries. For each group the user picks
+/// the record to keep and merges the others into it. `on_merged` runs after
+/// every merge.
+pub fn show_find_duplicates(parent: &impl IsA<gtk::Window>, library: Arc<RwLock<Library>>, on_merged: impl Fn() + 'static) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Find Duplicates")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[(tr!("Close").as_str(), gtk::ResponseType::Close)],
+    );
+    dialog.set_default_width(550);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let summary_label = Label::new(None);
+    summary_label.set_xalign(0.0);
+    let list_box = gtk::ListBox::new();
+    list_box.set_selection_mode(gtk::SelectionMode::None);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(300);
+    scrolled_window.set_child(Some(&list_box));
+    content.append(&summary_label);
+    content.append(&scrolled_window);
+    content.append(&Label::new(Some(&tr!(
+        "Merging moves copies, loans, reviews and tags to the kept record and deletes the others."
+    ))));
+
+    let on_merged = Rc::new(on_merged);
+    // Boxed so the Merge buttons it creates can call it again. The buttons
+    // belong to the list, which it only holds weakly.
+    let fill: Rc<RefCell<Box<dyn Fn()>>> = Rc::new(RefCell::new(Box::new(|| {})));
+    let weak_fill = Rc::downgrade(&fill);
+    *fill.borrow_mut() = Box::new(glib::clone!(
+        #[strong]
+        library,
+        #[weak]
+        list_box,
+        #[weak]
+        summary_label,
+        move || {
+            while let Some(child) = list_box.first_child() {
+                list_box.remove(&child);
+            }
+            let lib = library.read().unwrap();
+            let groups = lib.find_duplicates();
+            summary_label.set_text(&if groups.is_empty() {
+                tr!("No duplicates found.")
+            } else {
+                tr!("{} group(s) of probable duplicates.", groups.len())
+            });
+
+            for group in groups {
+                let names: Vec<String> = group
+                    .ids
+                    .iter()
+                    .map(|id| {
+                        let item = &lib.items[id];
+                        tr!("{}: {} ({}, {} copies)", id, item.title, item.year, item.copies)
+                    })
+                    .collect();
+                let keep_dropdown = DropDown::from_strings(&names.iter().map(String::as_str).collect::<Vec<_>>());
+                keep_dropdown.set_hexpand(true);
+                let merge_button = gtk::Button::with_label(&tr!("Merge"));
+
+                let library = library.clone();
+                let on_merged = on_merged.clone();
+                let fill = weak_fill.upgrade();
+                merge_button.connect_clicked(glib::clone!(
+                    #[weak]
+                    keep_dropdown,
+                    move |button| {
+                        let keep = group.ids[keep_dropdown.selected() as usize];
+                        let result = {
+                            let mut lib = library.write().unwrap();
+                            group.ids.iter().filter(|&&id| id != keep).try_for_each(|&id| lib.merge_items(keep, id))
+                        };
+                        if let Err(e) = result {
+                            if let Some(window) = button.root().and_downcast::<gtk::Window>() {
+                                show_error(&window, &tr!("Cannot merge items"), &e);
+                            }
+                        }
+                        on_merged();
+                        if let Some(fill) = &fill {
+                            fill.borrow()();
+                        }
+                    }
+                ));
+
+                let heading = Label::new(Some(&group.reason.describe()));
+                heading.set_xalign(0.0);
+                heading.add_css_class("heading");
+                let keep_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+                keep_box.append(&Label::new(Some(&tr!("Keep:"))));
+                keep_box.append(&keep_dropdown);
+                keep_box.append(&merge_button);
+                let row = GtkBox::new(gtk::Orientation::Vertical, 4);
+                row.set_margin_top(6);
+                row.set_margin_bottom(6);
+                row.append(&heading);
+                row.append(&keep_box);
+                list_box.append(&row);
+            }
+        }
+    ));
+    fill.borrow()();
+
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Shows what the running stocktake found so far. Finishing either leaves
+/// the catalog alone or, if `can_update`, can also mark missing copies lost
+/// and found ones available. `on_finished` runs once the stocktake ended.
+pub fn show_stocktake_report(
+    parent: &impl IsA<gtk::Window>,
+    library: Arc<RwLock<Library>>,
+    can_update: bool,
+    on_finished: impl Fn() + 'static,
+) {
+    let report = match library.read().unwrap().stocktake_report() {
+        Ok(report) => report,
+        Err(e) => {
+            show_error(parent, &tr!("No report available"), &e);
+            return;
+        }
+    };
+
+    let mut buttons = vec![
+        (tr!("Close"), gtk::ResponseType::Close),
+        (tr!("Finish Without Changes"), gtk::ResponseType::Reject),
+    ];
+    if can_update {
+        buttons.push((tr!("Finish and Update Statuses"), gtk::ResponseType::Accept));
+    }
+    let buttons: Vec<(&str, gtk::ResponseType)> = buttons.iter().map(|(label, response)| (label.as_str(), *response)).collect();
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Stocktake Report")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &buttons,
+    );
+    dialog.set_default_width(550);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let lib = library.read().unwrap();
+    let title = |id: u32| lib.items.get(&id).map_or_else(|| "?".to_string(), |i| i.title.to_string());
+    let copies = |entries: &[(u32, u32)]| -> Vec<String> {
+        entries.iter().map(|&(id, n)| tr!("  {} ({}): {} copies", title(id), id, n)).collect()
+    };
+    let misshelved = report
+        .misshelved
+        .iter()
+        .map(|(id, place)| tr!("  {} ({}): belongs in {}", title(*id), id, place))
+        .collect();
+    let sections = [
+        (tr!("Missing (not scanned, not on loan):"), copies(&report.missing)),
+        (tr!("Misshelved:"), misshelved),
+        (tr!("Found, but marked lost:"), copies(&report.found_lost)),
+        (tr!("More copies than expected (check for loans not returned):"), copies(&report.unexpected)),
+    ];
+    drop(lib);
+
+    let mut lines = Vec::new();
+    if report.is_empty() {
+        lines.push(tr!("Everything expected on the shelves was found."));
+    }
+    for (heading, entries) in sections {
+        if !entries.is_empty() {
+            lines.push(heading);
+            lines.extend(entries);
+            lines.push(String::new());
+        }
+    }
+
+    let report_label = Label::new(Some(lines.join("\n").trim_end()));
+    report_label.set_xalign(0.0);
+    report_label.set_yalign(0.0);
+    report_label.set_selectable(true);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(300);
+    scrolled_window.set_child(Some(&report_label));
+    content.append(&scrolled_window);
+    if can_update {
+        content.append(&Label::new(Some(&tr!(
+            "Updating marks missing copies as lost and puts found lost copies back on the shelf."
+        ))));
+    }
+
+    dialog.connect_response(move |dialog, response| {
+        let apply_changes = match response {
+            gtk::ResponseType::Accept => true,
+            gtk::ResponseType::Reject => false,
+            _ => {
+                dialog.close();
+                return;
+            }
+        };
+        let result = library.write().unwrap().finish_stocktake(apply_changes);
+        match result {
+            Ok(_) => {
+                on_finished();
+                dialog.close();
+            }
+            Err(e) => show_error(dialog, &tr!("Cannot finish the stocktake"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Items that haven't been borrowed for a chosen number of years, to help
+/// decide what to withdraw. The list can be exported as CSV.
+pub fn show_weeding_report(parent: &impl IsA<gtk::Window>, library: Arc<RwLock<Library>>) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Weeding Report")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Export CSV...").as_str(), gtk::ResponseType::Apply),
+            (tr!("Close").as_str(), gtk::ResponseType::Close),
+        ],
+    );
+    dialog.set_default_size(750, 500);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let years_spin = gtk::SpinButton::with_range(1.0, 50.0, 1.0);
+    years_spin.set_value(DEFAULT_WEEDING_YEARS as f64);
+    let years_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    years_box.append(&Label::new(Some(&tr!("Not borrowed in the last (years):"))));
+    years_box.append(&years_spin);
+    let count_label = Label::new(None);
+    count_label.set_xalign(0.0);
+
+    let now = library.read().unwrap().clock.now();
+    let table = Rc::new(Table::<WeedingCandidate>::new());
+    table.add_column(&tr!("Item ID"), |c| c.item_id.to_string(), |a, b| a.item_id.cmp(&b.item_id));
+    table.add_column(&tr!("Title"), |c| c.title.to_string(), |a, b| a.title.cmp(&b.title));
+    table.add_column(&tr!("Author"), |c| c.author.clone().unwrap_or_else(|| tr!("Unknown")), |a, b| a.author.cmp(&b.author));
+    table.add_column(&tr!("Year"), |c| c.year.to_string(), |a, b| a.year.cmp(&b.year));
+    table.add_column(&tr!("Age"), move |c| c.age(now).map_or(String::new(), |age| age.to_string()), |a, b| b.year.cmp(&a.year));
+    table.add_column(&tr!("Copies"), |c| c.copies.to_string(), |a, b| a.copies.cmp(&b.copies));
+    table.add_column(&tr!("Loans"), |c| c.loans.to_string(), |a, b| a.loans.cmp(&b.loans));
+    table.add_column(
+        &tr!("Last Borrowed"),
+        |c| c.last_borrowed.map_or_else(|| tr!("Never"), |at| at.format("%Y-%m-%d").to_string()),
+        |a, b| a.last_borrowed.cmp(&b.last_borrowed),
+    );
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    content.append(&years_box);
+    content.append(&count_label);
+    content.append(&scrolled_window);
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[weak]
+        years_spin,
+        #[weak]
+        count_label,
+        move || {
+            let candidates = library.read().unwrap().weeding_candidates(years_spin.value_as_int() as u32);
+            count_label.set_text(&tr!("{} item(s) to review", candidates.len()));
+            table.set_rows(candidates);
+        }
+    ));
+    fill();
+    years_spin.connect_value_changed(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Apply {
+            dialog.close();
+            return;
+        }
+        let years = years_spin.value_as_int() as u32;
+        choose_save_path(dialog, &tr!("Export Weeding Report"), "weeding.csv", glib::clone!(
+            #[strong]
+            library,
+            #[weak]
+            dialog,
+            move |path| {
+                if let Err(e) = library.read().unwrap().export_weeding_csv(years, &path) {
+                    show_error(&dialog, &tr!("Failed to export the report"), &e.to_string());
+                }
+            }
+        ));
+    });
+    dialog.show();
+}
+
+/// Asks for one line of text. The dialog stays open while `on_entered`
+/// fails, showing the error.
+pub fn ask_text(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    prompt: &str,
+    on_entered: impl Fn(String) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(title),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("OK").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let prompt_label = Label::new(Some(prompt));
+    prompt_label.set_xalign(0.0);
+    content.append(&prompt_label);
+    let entry = Entry::new();
+    entry.set_activates_default(true);
+    content.append(&entry);
+
+    let title = title.to_string();
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        match on_entered(entry.text().trim().to_string()) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &title, &e),
+        }
+    });
+    dialog.show();
+}
+
+/// What to do with an edit of a record someone else saved meanwhile.
+#[derive(Clone, Copy)]
+enum ConflictChoice {
+    Merge,
+    Overwrite,
+}
+
+// Shows the fields that differ between the saved record and the edit
+fn show_conflict(parent: &impl IsA<gtk::Window>, diffs: &[FieldDiff], on_choice: impl Fn(ConflictChoice) + 'static) {
+    let mut detail = tr!("Merge keeps their changes to the fields you didn't change. Overwrite saves all of your values.");
+    detail.push('\n');
+    for diff in diffs {
+        detail.push_str(&tr!("\n{}: theirs \"{}\", yours \"{}\"", diff.field, diff.theirs, diff.yours));
+    }
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(parent)
+        .modal(true)
+        .message_type(gtk::MessageType::Warning)
+        .text(tr!("Someone else saved changes while you were editing"))
+        .secondary_text(detail)
+        .build();
+    dialog.add_button(&tr!("Cancel"), gtk::ResponseType::Cancel);
+    dialog.add_button(&tr!("Merge"), gtk::ResponseType::Other(0));
+    dialog.add_button(&tr!("Overwrite"), gtk::ResponseType::Other(1));
+    dialog.connect_response(move |dialog, response| {
+        dialog.close();
+        match response {
+            gtk::ResponseType::Other(0) => on_choice(ConflictChoice::Merge),
+            gtk::ResponseType::Other(1) => on_choice(ConflictChoice::Overwrite),
+            _ => {}
+        }
+    });
+    dialog.show();
+}
+
+// Saves `edited`, an edit of `initial` as of `version`, and closes `dialog`.
+// If someone else saved the record meanwhile, asks whether to merge the two
+// edits or overwrite theirs.
+fn save_edit<T: EditableDetails>(
+    dialog: &gtk::Dialog,
+    failed_text: &str,
+    initial: &T,
+    edited: T,
+    version: u32,
+    on_save: &Rc<dyn Fn(T, u32) -> Result<(), EditError<T>>>,
+) {
+    let (version, current) = match on_save(edited.clone(), version) {
+        Ok(()) => return dialog.close(),
+        Err(EditError::Invalid(e)) => return show_error(dialog, failed_text, &e),
+        Err(EditError::Conflict { version, current }) => (version, current),
+    };
+    let diffs = T::diff(&current, &edited);
+    let merged = T::merge(initial, edited.clone(), &current);
+    let failed_text = failed_text.to_string();
+    let resolve = glib::clone!(
+        #[weak]
+        dialog,
+        #[strong]
+        on_save,
+        move |choice: ConflictChoice| {
+            let details = match choice {
+                ConflictChoice::Merge => merged.clone(),
+                ConflictChoice::Overwrite => edited.clone(),
+            };
+            match on_save(details, version) {
+                Ok(()) => dialog.close(),
+                Err(EditError::Invalid(e)) => show_error(&dialog, &failed_text, &e),
+                Err(EditError::Conflict { .. }) => {
+                    show_error(&dialog, &failed_text, &tr!("It was changed again. Close this window and edit it again."))
+                }
+            }
+        }
+    );
+    // Their version is the same as the edit, so there is nothing to choose
+    if diffs.is_empty() {
+        resolve(ConflictChoice::Overwrite);
+    } else {
+        show_conflict(dialog, &diffs, resolve);
+    }
+}
+
+/// Edits a member's name and contact details, as of `version` of the member.
+pub fn show_edit_member(
+    parent: &impl IsA<gtk::Window>,
+    initial: MemberDetails,
+    version: u32,
+    on_save: impl Fn(MemberDetails, u32) -> Result<(), EditError<MemberDetails>> + 'static,
+) {
+    let on_save: Rc<dyn Fn(MemberDetails, u32) -> Result<(), EditError<MemberDetails>>> = Rc::new(on_save);
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Edit Member")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Save").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let name_entry = Entry::new();
+    name_entry.set_text(&initial.name);
+    let email_entry = Entry::new();
+    email_entry.set_text(initial.email.as_deref().unwrap_or(""));
+    let external_id_entry = Entry::new();
+    external_id_entry.set_text(initial.external_id.as_deref().unwrap_or(""));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    for (row, (name, entry)) in [(tr!("Name:"), &name_entry), (tr!("Email:"), &email_entry), (tr!("External ID:"), &external_id_entry)].iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        entry.set_hexpand(true);
+        entry.set_activates_default(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*entry, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let details = MemberDetails {
+            name: name_entry.text().to_string(),
+            email: Some(email_entry.text().to_string()),
+            external_id: Some(external_id_entry.text().to_string()),
+        };
+        save_edit(dialog, &tr!("Failed to save member"), &initial, details, version, &on_save);
+    });
+    dialog.show();
+}
+
+/// Edits an item's tags as a comma- or semicolon-separated list.
+pub fn show_edit_tags(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    tags: &str,
+    on_save: impl Fn(Vec<String>) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Edit Tags")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Save").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let title_label = Label::new(Some(title));
+    title_label.set_xalign(0.0);
+    title_label.add_css_class("heading");
+    content.append(&title_label);
+
+    let tags_entry = Entry::new();
+    tags_entry.set_text(tags);
+    tags_entry.set_placeholder_text(Some(&tr!("Fantasy, Classics, Young Adult")));
+    tags_entry.set_activates_default(true);
+    content.append(&tags_entry);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        match on_save(facets::parse_tags(&tags_entry.text())) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to save tags"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Lists an item's reviews and lets staff record a new one for a member.
+pub fn show_reviews(
+    parent: &impl IsA<gtk::Window>,
+    library: Arc<RwLock<Library>>,
+    item_id: u32,
+    on_changed: impl Fn() + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Reviews")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[(tr!("Close").as_str(), gtk::ResponseType::Close)],
+    );
+    dialog.set_default_width(450);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let summary_label = Label::new(None);
+    summary_label.set_xalign(0.0);
+    summary_label.add_css_class("heading");
+    content.append(&summary_label);
+
+    let also_label = Label::new(None);
+    also_label.set_xalign(0.0);
+    also_label.set_wrap(true);
+    content.append(&also_label);
+
+    let list_box = gtk::ListBox::new();
+    list_box.set_selection_mode(gtk::SelectionMode::None);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(200);
+    scrolled_window.set_child(Some(&list_box));
+    content.append(&scrolled_window);
+
+    let fill = glib::clone!(
+        #[strong]
+        library,
+        #[weak]
+        summary_label,
+        #[weak]
+        also_label,
+        #[weak]
+        list_box,
+        move || {
+            let library = library.read().unwrap();
+            let Some(item) = library.items.get(&item_id) else { return };
+            summary_label.set_text(&format!("{} - {}", item.title, item.rating_text()));
+            let also = library.also_borrowed(item_id);
+            also_label.set_visible(!also.is_empty());
+            also_label.set_text(&tr!("Members who borrowed this also borrowed: {}", library.titles_of(&also)));
+
+            while let Some(row) = list_box.first_child() {
+                list_box.remove(&row);
+            }
+            for review in item.reviews.iter().rev() {
+                let reviewer = match review.member_id.and_then(|id| library.members.get(&id)) {
+                    Some(member) => member.name.clone(),
+                    None => tr!("Imported rating"),
+                };
+                let mut text = format!(
+                    "{} {} - {}",
+                    "★".repeat(review.stars as usize),
+                    reviewer,
+                    review.date.format("%Y-%m-%d")
+                );
+                if !review.text.is_empty() {
+                    text.push('\n');
+                    text.push_str(&review.text);
+                }
+                let label = Label::new(Some(&text));
+                label.set_xalign(0.0);
+                label.set_wrap(true);
+                list_box.append(&label);
+            }
+        }
+    );
+    fill();
+
+    let member_entry = Entry::new();
+    member_entry.set_placeholder_text(Some(&tr!("Member ID")));
+    let stars_spin = gtk::SpinButton::with_range(1.0, 5.0, 1.0);
+    stars_spin.set_value(5.0);
+    let text_entry = Entry::new();
+    text_entry.set_placeholder_text(Some(&tr!("Review (optional)")));
+    text_entry.set_hexpand(true);
+    let add_button = gtk::Button::with_label(&tr!("Add Review"));
+
+    let form_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    form_box.append(&member_entry);
+    form_box.append(&Label::new(Some(&tr!("Stars:"))));
+    form_box.append(&stars_spin);
+    form_box.append(&text_entry);
+    form_box.append(&add_button);
+    content.append(&form_box);
+
+    add_button.connect_clicked(glib::clone!(
+        #[weak]
+        dialog,
+        move |_| {
+            let Ok(member_id) = member_entry.text().trim().parse::<u32>() else {
+                show_error(&dialog, &tr!("Failed to add review"), &tr!("Enter a valid member ID"));
+                return;
+            };
+            let result = library.write().unwrap().add_review(
+                item_id,
+                member_id,
+                stars_spin.value_as_int() as u8,
+                &text_entry.text(),
+            );
+            match result {
+                Ok(()) => {
+                    text_entry.set_text("");
+                    fill();
+                    on_changed();
+                }
+                Err(e) => show_error(&dialog, &tr!("Failed to add review"), &e),
+            }
+        }
+    ));
+
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Edits an item's descriptive fields.
+pub fn show_edit_item(
+    parent: &impl IsA<gtk::Window>,
+    initial: ItemDetails,
+    version: u32,
+    on_save: impl Fn(ItemDetails, u32) -> Result<(), EditError<ItemDetails>> + 'static,
+) {
+    let on_save: Rc<dyn Fn(ItemDetails, u32) -> Result<(), EditError<ItemDetails>>> = Rc::new(on_save);
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Edit Item")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Save").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(450);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let title_entry = Entry::new();
+    title_entry.set_text(&initial.title);
+    let author_entry = Entry::new();
+    author_entry.set_text(initial.author.as_deref().unwrap_or(""));
+    let year_spin = gtk::SpinButton::with_range(0.0, 9999.0, 1.0);
+    year_spin.set_value(initial.year as f64);
+    let edition_entry = Entry::new();
+    edition_entry.set_text(&initial.edition);
+    let format_entry = Entry::new();
+    format_entry.set_text(&initial.format);
+    let desc_view = gtk::TextView::new();
+    desc_view.set_wrap_mode(gtk::WrapMode::Word);
+    desc_view.buffer().set_text(&initial.desc);
+    let desc_window = ScrolledWindow::new();
+    desc_window.set_min_content_height(100);
+    desc_window.set_child(Some(&desc_view));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 6] = [
+        (tr!("Title:"), title_entry.upcast_ref()),
+        (tr!("Author:"), author_entry.upcast_ref()),
+        (tr!("Year:"), year_spin.upcast_ref()),
+        (tr!("Edition:"), edition_entry.upcast_ref()),
+        (tr!("Format:"), format_entry.upcast_ref()),
+        (tr!("Description:"), desc_window.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let buffer = desc_view.buffer();
+        let details = ItemDetails {
+            title: title_entry.text().to_string(),
+            author: Some(author_entry.text().to_string()),
+            year: year_spin.value_as_int() as u32,
+            edition: edition_entry.text().to_string(),
+            format: format_entry.text().to_string(),
+            desc: buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).to_string(),
+        };
+        save_edit(dialog, &tr!("Failed to save item"), &initial, details, version, &on_save);
+    });
+    dialog.show();
+}
+
+/// Adds a title to the catalog. The fields can be copied from a record
+/// found in another library's catalog through the SRU server at `sru_url`.
+pub fn show_add_item(
+    parent: &impl IsA<gtk::Window>,
+    sru_url: String,
+    on_save: impl Fn(NewItem) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Add Item")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(550);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let search_entry = Entry::new();
+    search_entry.set_placeholder_text(Some(&tr!("ISBN or title")));
+    search_entry.set_hexpand(true);
+    let search_button = gtk::Button::with_label(&tr!("Search"));
+    search_button.set_sensitive(!sru_url.is_empty());
+    let search_row = GtkBox::new(gtk::Orientation::Horizontal, 5);
+    search_row.append(&search_entry);
+    search_row.append(&search_button);
+    let search_status = Label::new(Some(&if sru_url.is_empty() {
+        tr!("Set a catalog server in Settings to search it.")
+    } else {
+        tr!("Searching {}", sru_url)
+    }));
+    search_status.set_xalign(0.0);
+    search_status.set_wrap(true);
+    search_status.add_css_class("dim-label");
+    let results_list = gtk::ListBox::new();
+    let results_window = ScrolledWindow::new();
+    results_window.set_min_content_height(120);
+    results_window.set_child(Some(&results_list));
+    let search_box = GtkBox::new(gtk::Orientation::Vertical, 5);
+    search_box.set_margin_top(5);
+    search_box.set_margin_bottom(5);
+    search_box.set_margin_start(5);
+    search_box.set_margin_end(5);
+    search_box.append(&search_row);
+    search_box.append(&search_status);
+    search_box.append(&results_window);
+    let search_frame = gtk::Frame::new(Some(&tr!("Search External Catalogs")));
+    search_frame.set_child(Some(&search_box));
+    content.append(&search_frame);
+
+    let isbn_entry = Entry::new();
+    let title_entry = Entry::new();
+    let author_entry = Entry::new();
+    let year_spin = gtk::SpinButton::with_range(0.0, 9999.0, 1.0);
+    let edition_entry = Entry::new();
+    let format_entry = Entry::new();
+    format_entry.set_text("Book");
+    let call_number_entry = Entry::new();
+    let tags_entry = Entry::new();
+    tags_entry.set_placeholder_text(Some(&tr!("Fantasy, Classics, Young Adult")));
+    let copies_spin = gtk::SpinButton::with_range(1.0, 999.0, 1.0);
+    copies_spin.set_value(1.0);
+    let desc_view = gtk::TextView::new();
+    desc_view.set_wrap_mode(gtk::WrapMode::Word);
+    let desc_window = ScrolledWindow::new();
+    desc_window.set_min_content_height(80);
+    desc_window.set_child(Some(&desc_view));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 10] = [
+        (tr!("ISBN:"), isbn_entry.upcast_ref()),
+        (tr!("Title:"), title_entry.upcast_ref()),
+        (tr!("Author:"), author_entry.upcast_ref()),
+        (tr!("Year:"), year_spin.upcast_ref()),
+        (tr!("Edition:"), edition_entry.upcast_ref()),
+        (tr!("Format:"), format_entry.upcast_ref()),
+        (tr!("Call number:"), call_number_entry.upcast_ref()),
+        (tr!("Tags:"), tags_entry.upcast_ref()),
+        (tr!("Copies:"), copies_spin.upcast_ref()),
+        (tr!("Description:"), desc_window.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    // Results of the last search, in the order they are listed
+    let records: Rc<RefCell<Vec<MarcRecord>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let search = glib::clone!(
+        #[weak]
+        search_entry,
+        #[weak]
+        search_button,
+        #[weak]
+        search_status,
+        #[weak]
+        results_list,
+        #[strong]
+        records,
+        move || {
+            let text = search_entry.text().trim().to_string();
+            if text.is_empty() {
+                return;
+            }
+            search_button.set_sensitive(false);
+            search_status.set_text(&tr!("Searching..."));
+            let url = sru_url.clone();
+            let handle = gtk::gio::spawn_blocking(move || sru::search(&url, &text).map_err(|e| e.to_string()));
+            glib::spawn_future_local(glib::clone!(
+                #[strong]
+                records,
+                async move {
+                    let result = handle.await.unwrap_or_else(|_| Err(tr!("The search stopped unexpectedly")));
+                    search_button.set_sensitive(true);
+                    results_list.remove_all();
+                    match result {
+                        Ok(found) => {
+                            search_status.set_text(&if found.is_empty() {
+                                tr!("No records found.")
+                            } else {
+                                tr!("{} record(s) found. Pick one to copy it into the form.", found.len())
+                            });
+                            for record in &found {
+                                let label = Label::new(Some(&record.summary()));
+                                label.set_xalign(0.0);
+                                label.set_wrap(true);
+                                results_list.append(&label);
+                            }
+                            *records.borrow_mut() = found;
+                        }
+                        Err(e) => {
+                            tracing::warn!("Catalog search failed: {}", e);
+                            search_status.set_text(&tr!("Search failed: {}", e));
+                            records.borrow_mut().clear();
+                        }
+                    }
+                }
+            ));
+        }
+    );
+    let search = Rc::new(search);
+    search_button.connect_clicked(glib::clone!(
+        #[strong]
+        search,
+        move |_| search()
+    ));
+    search_entry.connect_activate(move |_| search());
+
+    results_list.connect_row_activated(glib::clone!(
+        #[strong]
+        records,
+        #[weak]
+        isbn_entry,
+        #[weak]
+        title_entry,
+        #[weak]
+        author_entry,
+        #[weak]
+        year_spin,
+        #[weak]
+        edition_entry,
+        #[weak]
+        call_number_entry,
+        #[weak]
+        tags_entry,
+        #[weak]
+        desc_view,
+        move |_, row| {
+            let records = records.borrow();
+            let Some(record) = records.get(row.index() as usize) else { return };
+            isbn_entry.set_text(record.isbn.as_deref().unwrap_or(""));
+            title_entry.set_text(&record.title);
+            author_entry.set_text(record.author.as_deref().unwrap_or(""));
+            year_spin.set_value(record.year as f64);
+            edition_entry.set_text(&record.edition);
+            call_number_entry.set_text(&record.call_number);
+            tags_entry.set_text(&record.tags.join(", "));
+            desc_view.buffer().set_text(&record.desc);
+        }
+    ));
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let buffer = desc_view.buffer();
+        let new = NewItem {
+            details: ItemDetails {
+                title: title_entry.text().to_string(),
+                author: Some(author_entry.text().to_string()),
+                year: year_spin.value_as_int() as u32,
+                edition: edition_entry.text().to_string(),
+                format: format_entry.text().to_string(),
+                desc: buffer.text(&buffer.start_iter(), &buffer.end_iter(), false).to_string(),
+            },
+            isbn: Some(isbn_entry.text().to_string()),
+            call_number: call_number_entry.text().to_string(),
+            tags: facets::parse_tags(&tags_entry.text()),
+            copies: copies_spin.value_as_int() as u32,
+        };
+        match on_save(new) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add item"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Asks for a title to add to a purchase order, starting from `title` and
+/// `author` when they are known, e.g. from a patron's suggestion.
+pub fn show_add_order_line(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    author: &str,
+    on_save: impl Fn(OrderLine) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Add Title to Order")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let title_entry = Entry::new();
+    title_entry.set_text(title);
+    let author_entry = Entry::new();
+    author_entry.set_text(author);
+    let isbn_entry = Entry::new();
+    let format_entry = Entry::new();
+    format_entry.set_text("Book");
+    let quantity_spin = gtk::SpinButton::with_range(1.0, 999.0, 1.0);
+    quantity_spin.set_value(1.0);
+    let price_entry = Entry::new();
+    price_entry.set_placeholder_text(Some("12.99"));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 6] = [
+        (tr!("Title:"), title_entry.upcast_ref()),
+        (tr!("Author:"), author_entry.upcast_ref()),
+        (tr!("ISBN:"), isbn_entry.upcast_ref()),
+        (tr!("Format:"), format_entry.upcast_ref()),
+        (tr!("Copies:"), quantity_spin.upcast_ref()),
+        (tr!("Price per copy:"), price_entry.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let price_cents = match parse_money(&price_entry.text()) {
+            Ok(cents) => cents,
+            Err(e) => {
+                show_error(dialog, &tr!("Invalid price"), &e);
+                return;
+            }
+        };
+        let optional = |entry: &Entry| Some(entry.text().trim().to_string()).filter(|t| !t.is_empty());
+        let line = OrderLine {
+            title: title_entry.text().trim().to_string(),
+            author: optional(&author_entry),
+            isbn: optional(&isbn_entry),
+            format: format_entry.text().trim().to_string(),
+            quantity: quantity_spin.value_as_int() as u32,
+            price_cents,
+            item_id: None,
+        };
+        match on_save(line) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add the title"), &e),
+        }
+    });
+    dialog.show();
+}
+
+fn item_details_text(library: &Library, item_id: u32, show_borrowers: bool) -> String {
+    let Some(item) = library.items.get(&item_id) else {
+        return tr!("This item no longer exists.");
+    };
+
+    let on_loan = item.copies_in(CopyStatus::OnLoan);
+    let mut lines = vec![
+        tr!("Title: {}", item.title),
+        tr!("Author: {}", item.author.clone().unwrap_or_else(|| tr!("Unknown"))),
+        tr!("Year: {}", item.year),
+        tr!("Edition: {}", item.edition),
+        tr!("Format: {}", item.format),
+        tr!("ISBN: {}", item.isbn.as_deref().unwrap_or("-")),
+        tr!("Tags: {}", item.tags.join(", ")),
+        tr!("Rating: {}", item.rating_text()),
+        tr!("Branch: {}", item.branch),
+        tr!("Call number: {}", if item.call_number.is_empty() { "-" } else { item.call_number.as_str() }),
+        tr!("Shelf location: {}", if item.shelf_location.is_empty() { "-" } else { item.shelf_location.as_str() }),
+        tr!(
+            "Copies: {} total, {} on the shelf, {} on loan{}",
+            item.copies,
+            item.avail_copies,
+            on_loan,
+            if library.in_transit(item_id) { tr!(" (in transit)") } else { String::new() }
+        ),
+    ];
+    match item.link {
+        Some(ItemLink::Volume { set_id, number }) => {
+            let set_title = library.volume_set(set_id).map_or_else(|| "?".to_string(), |s| s.title.clone());
+            lines.push(tr!("Volume {} of \"{}\" (set {})", number, set_title, set_id));
+            let others: Vec<String> = library
+                .volumes(set_id)
+                .into_iter()
+                .filter(|v| v.id != item_id)
+                .map(|v| tr!("Vol. {}: {} (item {}, {} available)", v.volume_number().unwrap_or(0), v.title, v.id, v.avail_copies))
+                .collect();
+            if !others.is_empty() {
+                lines.push(tr!("Other volumes:\n{}", others.join("\n")));
+            }
+        }
+        Some(ItemLink::BoundWith { host_id, .. }) => {
+            let host_title = library.items.get(&host_id).map_or_else(|| "?".to_string(), |h| h.title.to_string());
+            lines.push(tr!("Bound in item {}, \"{}\"; lent and held as that item", host_id, host_title));
+        }
+        None => {}
+    }
+    let bound: Vec<String> = library.bound_in(item_id).into_iter().map(|b| tr!("{} (item {})", b.title, b.id)).collect();
+    if !bound.is_empty() {
+        lines.push(tr!("Also bound in this volume:\n{}", bound.join("\n")));
+    }
+    if item.lost_copies > 0 {
+        lines.push(tr!("Lost copies: {}", item.lost_copies));
+    }
+    lines.push(tr!("Status: {}", item.status_text()));
+    lines.push(tr!("Condition: {}", item.condition_text()));
+    if let Some(note) = item.condition_notes.iter().rev().find(|n| !n.text.is_empty()) {
+        lines.push(tr!("Condition note ({}): {}", note.at.format("%Y-%m-%d"), note.text));
+    }
+    if let Some(added_at) = item.added_at {
+        lines.push(tr!("Added: {}", added_at.format("%Y-%m-%d")));
+    }
+    lines.push(String::new());
+    lines.push(if item.desc.is_empty() { tr!("No description.") } else { item.desc.clone() });
+
+    if show_borrowers {
+        let mut borrowers: Vec<String> = library
+            .members
+            .values()
+            .filter_map(|m| {
+                let inst = m.items.get(&item_id)?;
+                Some(tr!("{} ({}), due {}", m.name, m.id, inst.due_text()))
+            })
+            .collect();
+        borrowers.sort();
+        lines.push(String::new());
+        lines.push(if borrowers.is_empty() {
+            tr!("Not on loan.")
+        } else {
+            tr!("On loan to:\n{}", borrowers.join("\n"))
+        });
+    }
+
+    let also = library.also_borrowed(item_id);
+    if !also.is_empty() {
+        lines.push(String::new());
+        lines.push(tr!("Members who borrowed this also borrowed: {}", library.titles_of(&also)));
+    }
+    lines.join("\n")
+}
+
+/// Asks what to change on the `count` selected catalog items. The change is
+/// handed to `on_chosen` once the dialog is closed.
+pub fn show_batch_edit(parent: &impl IsA<gtk::Window>, count: usize, on_chosen: impl Fn(BatchChange) + 'static) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Batch Edit")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Continue").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let count_label = Label::new(Some(&tr!("{} items selected", count)));
+    count_label.set_xalign(0.0);
+    content.append(&count_label);
+
+    let changes = [
+        tr!("Add genre"),
+        tr!("Change format"),
+        tr!("Add or remove copies"),
+        tr!("Move to shelf location"),
+        tr!("Withdraw copies on the shelf"),
+    ];
+    let change_dropdown = DropDown::from_strings(&changes.iter().map(String::as_str).collect::<Vec<_>>());
+    let value_entry = Entry::new();
+    value_entry.set_activates_default(true);
+    value_entry.set_tooltip_text(Some(&tr!("The genre, format or shelf location, or the number of copies to add (negative to remove)")));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 2] = [(tr!("Change:"), change_dropdown.upcast_ref()), (tr!("Value:"), value_entry.upcast_ref())];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    // Withdrawing needs no value
+    change_dropdown.connect_selected_notify(glib::clone!(
+        #[weak]
+        value_entry,
+        move |dropdown| value_entry.set_sensitive(dropdown.selected() != 4)
+    ));
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let value = value_entry.text().trim().to_string();
+        let missing = match change_dropdown.selected() {
+            0 if value.is_empty() => Some(tr!("Enter a genre")),
+            1 if value.is_empty() => Some(tr!("Format cannot be empty")),
+            _ => None,
+        };
+        if let Some(e) = missing {
+            show_error(dialog, &tr!("Invalid value"), &e);
+            return;
+        }
+        let change = match change_dropdown.selected() {
+            0 => BatchChange::AddGenre(value),
+            1 => BatchChange::SetFormat(value),
+            2 => match value.trim_start_matches('+').parse() {
+                Ok(delta) => BatchChange::AdjustCopies(delta),
+                Err(_) => {
+                    show_error(dialog, &tr!("Invalid value"), &tr!("\"{}\" is not a number of copies", value));
+                    return;
+                }
+            },
+            3 => BatchChange::SetShelfLocation(value),
+            _ => BatchChange::Withdraw,
+        };
+        dialog.close();
+        on_chosen(change);
+    });
+    dialog.show();
+}
+
+/// Asks which copies of an item change status, e.g. from available to in
+/// repair. `current` describes the statuses the copies have now.
+pub fn show_change_copy_status(
+    parent: &impl IsA<gtk::Window>,
+    current: &str,
+    on_save: impl Fn(CopyStatus, CopyStatus, u32) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Change Copy Status")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Change").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let current_label = Label::new(Some(&tr!("Copies now: {}", current)));
+    current_label.set_xalign(0.0);
+    current_label.set_wrap(true);
+    content.append(&current_label);
+
+    let labels: Vec<String> = CopyStatus::MANUAL.iter().map(|s| s.label()).collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let from_dropdown = DropDown::from_strings(&labels);
+    let to_dropdown = DropDown::from_strings(&labels);
+    // Sending available copies for repair is the usual change
+    from_dropdown.set_selected(1);
+    to_dropdown.set_selected(2);
+    let count_spin = gtk::SpinButton::with_range(1.0, 999.0, 1.0);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 3] = [
+        (tr!("From:"), from_dropdown.upcast_ref()),
+        (tr!("To:"), to_dropdown.upcast_ref()),
+        (tr!("Copies:"), count_spin.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let status = |dropdown: &DropDown| CopyStatus::MANUAL[dropdown.selected() as usize % CopyStatus::MANUAL.len()];
+        match on_save(status(&from_dropdown), status(&to_dropdown), count_spin.value_as_int() as u32) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to change the copies' status"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Asks which set an item is a volume of, an existing one or a new one by
+/// title, and its volume number. `on_save` gets the set's ID, or None and
+/// the new set's title.
+pub fn show_add_to_set(
+    parent: &impl IsA<gtk::Window>,
+    sets: Vec<VolumeSet>,
+    on_save: impl Fn(Option<u32>, String, u32) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Add to Volume Set")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    // The first choice starts a new set
+    let mut labels = vec![tr!("New set")];
+    labels.extend(sets.iter().map(|s| tr!("{} (set {})", s.title, s.id)));
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let set_dropdown = DropDown::from_strings(&labels);
+    let title_entry = Entry::new();
+    title_entry.set_placeholder_text(Some(&tr!("Title of the whole work")));
+    let number_spin = gtk::SpinButton::with_range(1.0, 999.0, 1.0);
+    set_dropdown.connect_selected_notify(glib::clone!(
+        #[weak]
+        title_entry,
+        move |dropdown| title_entry.set_sensitive(dropdown.selected() == 0)
+    ));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 3] = [
+        (tr!("Set:"), set_dropdown.upcast_ref()),
+        (tr!("New set title:"), title_entry.upcast_ref()),
+        (tr!("Volume:"), number_spin.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let set_id = (set_dropdown.selected() as usize).checked_sub(1).and_then(|i| sets.get(i)).map(|s| s.id);
+        match on_save(set_id, title_entry.text().to_string(), number_spin.value_as_int() as u32) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add the item to the set"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Asks which condition one copy went from and to, with an optional note.
+/// `on_save` is only called when something was entered.
+pub fn show_change_condition(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    current: &str,
+    on_save: impl Fn(Condition, Condition, String) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(title),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Save").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let current_label = Label::new(Some(&tr!("Copies now: {}", current)));
+    current_label.set_xalign(0.0);
+    current_label.set_wrap(true);
+    content.append(&current_label);
+
+    let labels: Vec<String> = Condition::ALL.iter().map(|c| c.label()).collect();
+    let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+    let from_dropdown = DropDown::from_strings(&labels);
+    let to_dropdown = DropDown::from_strings(&labels);
+    let good = Condition::ALL.iter().position(|&c| c == Condition::Good).unwrap_or(0) as u32;
+    from_dropdown.set_selected(good);
+    to_dropdown.set_selected(good);
+    let note_entry = Entry::new();
+    note_entry.set_placeholder_text(Some(&tr!("E.g. torn cover, water damage")));
+    note_entry.set_activates_default(true);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 3] = [
+        (tr!("Was:"), from_dropdown.upcast_ref()),
+        (tr!("Now:"), to_dropdown.upcast_ref()),
+        (tr!("Note:"), note_entry.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        let condition = |dropdown: &DropDown| Condition::ALL[dropdown.selected() as usize % Condition::ALL.len()];
+        let (from, to, note) = (condition(&from_dropdown), condition(&to_dropdown), note_entry.text().trim().to_string());
+        // Nothing to record, e.g. a copy returned as it went out
+        if response != gtk::ResponseType::Accept || (from == to && note.is_empty()) {
+            dialog.close();
+            return;
+        }
+        match on_save(from, to, note) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to record the condition"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Everything known about one item. Staff (`role` set) also see who has it
+/// and can issue it; librarians can edit it.
+pub fn show_item_details(
+    parent: &impl IsA<gtk::Window>,
+    library: Arc<RwLock<Library>>,
+    item_id: u32,
+    role: Option<Role>,
+    on_changed: impl Fn() + 'static,
+) {
+    let on_changed = Rc::new(on_changed);
+
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Item Details")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[(tr!("Close").as_str(), gtk::ResponseType::Close)],
+    );
+    dialog.set_default_width(500);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let details_label = Label::new(None);
+    details_label.set_xalign(0.0);
+    details_label.set_wrap(true);
+    details_label.set_selectable(true);
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(300);
+    scrolled_window.set_child(Some(&details_label));
+    content.append(&scrolled_window);
+    // How much the title is borrowed, for deciding on more copies or weeding
+    let heatmap = Rc::new(Heatmap::new(&tr!("Checkouts over the past year")));
+    content.append(&heatmap.widget);
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[weak]
+        details_label,
+        #[strong]
+        heatmap,
+        move || {
+            let lib = library.read().unwrap();
+            details_label.set_text(&item_details_text(&lib, item_id, role.is_some()));
+            let today = lib.clock.now().with_timezone(&chrono::Local).date_naive();
+            let first = today - chrono::Months::new(12) + chrono::Days::new(1);
+            heatmap.set_days(first, lib.item_checkouts_by_day(item_id, first, today));
+        }
+    ));
+    fill();
+
+    if role.is_some() {
+        let member_entry = Entry::new();
+        member_entry.set_placeholder_text(Some(&tr!("Member ID")));
+        member_entry.set_hexpand(true);
+        let issue_button = gtk::Button::with_label(&tr!("Issue"));
+        let status_label = Label::new(None);
+
+        let issue_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+        issue_box.append(&member_entry);
+        issue_box.append(&issue_button);
+        content.append(&issue_box);
+        content.append(&status_label);
+
+        issue_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            member_entry,
+            #[weak]
+            status_label,
+            move |_| {
+                let result = library.write().unwrap().book_issue(item_id, member_entry.text().trim().to_string());
+                match result {
+                    Ok(()) => {
+                        status_label.set_text(&tr!("Item issued"));
+                        member_entry.set_text("");
+                        fill();
+                        on_changed();
+                    }
+                    Err(e) => status_label.set_text(&tr!("Error: {}", e)),
+                }
+            }
+        ));
+    }
+
+    if role >= Some(Role::Librarian) {
+        let edit_button = gtk::Button::with_label(&tr!("Edit..."));
+        let tags_button = gtk::Button::with_label(&tr!("Edit Tags..."));
+        let reviews_button = gtk::Button::with_label(&tr!("Reviews..."));
+        let status_button = gtk::Button::with_label(&tr!("Copy Status..."));
+        let condition_button = gtk::Button::with_label(&tr!("Condition..."));
+        let actions_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+        actions_box.append(&edit_button);
+        actions_box.append(&tags_button);
+        actions_box.append(&reviews_button);
+        actions_box.append(&status_button);
+        actions_box.append(&condition_button);
+        content.append(&actions_box);
+
+        condition_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            dialog,
+            move |_| {
+                let Some(current) = library.read().unwrap().items.get(&item_id).map(|i| i.condition_text()) else { return };
+                show_change_condition(&dialog, &tr!("Copy Condition"), &current, glib::clone!(
+                    #[strong]
+                    library,
+                    #[strong]
+                    fill,
+                    #[strong]
+                    on_changed,
+                    move |from, to, note| {
+                        library.write().unwrap().change_condition(item_id, from, to, &note)?;
+                        fill();
+                        on_changed();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+
+        status_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            dialog,
+            move |_| {
+                let Some(status) = library.read().unwrap().items.get(&item_id).map(|i| i.status_text()) else { return };
+                show_change_copy_status(&dialog, &status, glib::clone!(
+                    #[strong]
+                    library,
+                    #[strong]
+                    fill,
+                    #[strong]
+                    on_changed,
+                    move |from, to, count| {
+                        library.write().unwrap().change_copy_status(item_id, from, to, count)?;
+                        fill();
+                        on_changed();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+
+        edit_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            dialog,
+            move |_| {
+                let (initial, version) = {
+                    let lib = library.read().unwrap();
+                    let Some(item) = lib.items.get(&item_id) else { return };
+                    (ItemDetails::of(item), item.version)
+                };
+                show_edit_item(&dialog, initial, version, glib::clone!(
+                    #[strong]
+                    library,
+                    #[strong]
+                    fill,
+                    #[strong]
+                    on_changed,
+                    move |details, version| {
+                        library.write().unwrap().edit_item(item_id, version, details)?;
+                        fill();
+                        on_changed();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+
+        tags_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            dialog,
+            move |_| {
+                let Some((title, tags)) = library.read().unwrap().items.get(&item_id).map(|i| (i.title.clone(), i.tags.join(", "))) else { return };
+                show_edit_tags(&dialog, &title, &tags, glib::clone!(
+                    #[strong]
+                    library,
+                    #[strong]
+                    fill,
+                    #[strong]
+                    on_changed,
+                    move |tags| {
+                        library.write().unwrap().set_item_tags(item_id, tags)?;
+                        fill();
+                        on_changed();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+
+        reviews_button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[strong]
+            on_changed,
+            #[weak]
+            dialog,
+            move |_| {
+                show_reviews(&dialog, library.clone(), item_id, glib::clone!(
+                    #[strong]
+                    fill,
+                    #[strong]
+                    on_changed,
+                    move || {
+                        fill();
+                        on_changed();
+                    }
+                ));
+            }
+        ));
+    }
+
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Tells why the application can't start, in place of the login dialog.
+/// Closing it quits the application.
+pub fn show_startup_error(app: &gtk::Application, text: &str, detail: &str) {
+    tracing::error!(detail, "{}", text);
+    let dialog = gtk::MessageDialog::builder()
+        .message_type(gtk::MessageType::Error)
+        .buttons(gtk::ButtonsType::Close)
+        .text(text)
+        .secondary_text(tr!("{}\n\nThe file has been left as it is. Fix it or restore it from a backup, then start again.", detail))
+        .build();
+    dialog.set_application(Some(app));
+    dialog.connect_response(|dialog, _| dialog.close());
+    dialog.show();
+}
+
+/// Asks for staff credentials before anything else is shown. When no users
+/// exist yet the dialog creates the first admin account instead. Closing the
+/// dialog without logging in quits the application.
+pub fn show_login(app: &gtk::Application, users: Rc<RefCell<UserStore>>, on_login: impl FnOnce(Session) + 'static) {
+    let first_run = users.borrow().is_empty();
+
+    let dialog = gtk::Dialog::new();
+    dialog.set_application(Some(app));
+    dialog.set_title(Some(&tr!("Library Management System - Log In")));
+    dialog.add_button(&if first_run { tr!("Create Admin") } else { tr!("Log In") }, gtk::ResponseType::Accept);
+    dialog.set_default_response(gtk::ResponseType::Accept);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    if first_run {
+        content.append(&Label::new(Some(&tr!("No staff accounts exist yet. Create the administrator account:"))));
+    }
+    let username_entry = Entry::new();
+    username_entry.set_placeholder_text(Some(&tr!("Username")));
+    username_entry.set_activates_default(true);
+    let password_entry = gtk::PasswordEntry::new();
+    password_entry.set_activates_default(true);
+    let error_label = Label::new(None);
+
+    content.append(&Label::new(Some(&tr!("Username:"))));
+    content.append(&username_entry);
+    content.append(&Label::new(Some(&tr!("Password:"))));
+    content.append(&password_entry);
+    content.append(&error_label);
+
+    let on_login = RefCell::new(Some(on_login));
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+
+        let username = username_entry.text();
+        let password = password_entry.text();
+        let session = if first_run {
+            let mut store = users.borrow_mut();
+            let created = store
+                .add_user(&username, &password, Role::Admin)
+                .and_then(|_| store.save().map_err(|e| e.to_string()));
+            match created {
+                Ok(()) => store.authenticate(&username, &password),
+                Err(e) => {
+                    error_label.set_text(&e);
+                    return;
+                }
+            }
+        } else {
+            users.borrow().authenticate(&username, &password)
+        };
+
+        match session {
+            Some(session) => {
+                tracing::info!(user = %session.username, role = session.role.name(), "Logged in");
+                // Build the main window before closing the dialog so the
+                // application never runs out of windows.
+                if let Some(on_login) = on_login.borrow_mut().take() {
+                    on_login(session);
+                }
+                dialog.close();
+            }
+            None => {
+                tracing::warn!(user = %username, "Failed login");
+                error_label.set_text(&tr!("Invalid username or password"));
+                password_entry.set_text("");
+            }
+        }
+    });
+    dialog.show();
+}
+
+pub fn show_add_subscription(parent: &impl IsA<gtk::Window>, on_save: impl Fn(NewSubscription) -> Result<(), String> + 'static) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Add Subscription")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let title_entry = Entry::new();
+    let issn_entry = Entry::new();
+    let supplier_entry = Entry::new();
+    let frequency_labels: Vec<String> = Frequency::ALL.iter().map(|f| f.label()).collect();
+    let frequency_dropdown = DropDown::from_strings(&frequency_labels.iter().map(String::as_str).collect::<Vec<_>>());
+    frequency_dropdown.set_selected(Frequency::ALL.iter().position(|&f| f == Frequency::Monthly).unwrap_or(0) as u32);
+    let first_issue_entry = Entry::new();
+    first_issue_entry.set_placeholder_text(Some("YYYY-MM-DD"));
+    let call_number_entry = Entry::new();
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 6] = [
+        (tr!("Title:"), title_entry.upcast_ref()),
+        (tr!("ISSN:"), issn_entry.upcast_ref()),
+        (tr!("Supplier:"), supplier_entry.upcast_ref()),
+        (tr!("Frequency:"), frequency_dropdown.upcast_ref()),
+        (tr!("First issue expected:"), first_issue_entry.upcast_ref()),
+        (tr!("Call Number:"), call_number_entry.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let first_issue = match NaiveDate::parse_from_str(first_issue_entry.text().trim(), "%Y-%m-%d") {
+            Ok(date) => date,
+            Err(_) => {
+                show_error(dialog, &tr!("Invalid date"), &tr!("Enter the first issue's date as YYYY-MM-DD."));
+                return;
+            }
+        };
+        let issn = issn_entry.text().trim().to_string();
+        let new = NewSubscription {
+            title: title_entry.text().to_string(),
+            issn: Some(issn).filter(|i| !i.is_empty()),
+            supplier: supplier_entry.text().to_string(),
+            frequency: Frequency::ALL[frequency_dropdown.selected() as usize % Frequency::ALL.len()],
+            first_issue,
+            call_number: call_number_entry.text().to_string(),
+        };
+        match on_save(new) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add the subscription"), &e),
+        }
+    });
+    dialog.show();
+}
+
+pub fn show_new_ill_request(
+    parent: &impl IsA<gtk::Window>,
+    direction: IllDirection,
+    on_save: impl Fn(NewIllRequest) -> Result<(), String> + 'static,
+) {
+    let title = match direction {
+        IllDirection::Outgoing => tr!("Borrow from Another Library"),
+        IllDirection::Incoming => tr!("Lend to Another Library"),
+    };
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&title),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let partner_entry = Entry::new();
+    let id_entry = Entry::new();
+    let title_entry = Entry::new();
+    let author_entry = Entry::new();
+    let isbn_entry = Entry::new();
+    let note_entry = Entry::new();
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    // Lending requests take the title from our item
+    let rows: Vec<(String, &gtk::Widget)> = match direction {
+        IllDirection::Outgoing => vec![
+            (tr!("Lending Library:"), partner_entry.upcast_ref()),
+            (tr!("Member ID:"), id_entry.upcast_ref()),
+            (tr!("Title:"), title_entry.upcast_ref()),
+            (tr!("Author:"), author_entry.upcast_ref()),
+            (tr!("ISBN:"), isbn_entry.upcast_ref()),
+            (tr!("Note:"), note_entry.upcast_ref()),
+        ],
+        IllDirection::Incoming => vec![
+            (tr!("Borrowing Library:"), partner_entry.upcast_ref()),
+            (tr!("Item ID:"), id_entry.upcast_ref()),
+            (tr!("Note:"), note_entry.upcast_ref()),
+        ],
+    };
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let Ok(id) = id_entry.text().trim().parse::<u32>() else {
+            let message = match direction {
+                IllDirection::Outgoing => tr!("Invalid Member ID!"),
+                IllDirection::Incoming => tr!("Invalid Item ID!"),
+            };
+            show_error(dialog, &message, "");
+            return;
+        };
+        let optional = |entry: &Entry| Some(entry.text().trim().to_string()).filter(|t| !t.is_empty());
+        let new = NewIllRequest {
+            direction,
+            partner: partner_entry.text().to_string(),
+            title: title_entry.text().to_string(),
+            author: optional(&author_entry),
+            isbn: optional(&isbn_entry),
+            member_id: Some(id).filter(|_| direction == IllDirection::Outgoing),
+            item_id: Some(id).filter(|_| direction == IllDirection::Incoming),
+            note: note_entry.text().to_string(),
+        };
+        match on_save(new) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add the request"), &e),
+        }
+    });
+    dialog.show();
+}
+
+// A dropdown of rooms, with "No room" first when a room is optional.
+// Returns the room IDs in dropdown order.
+fn rooms_dropdown(rooms: &[Room], optional: bool) -> (DropDown, Vec<Option<u32>>) {
+    let mut names = Vec::new();
+    let mut ids = Vec::new();
+    if optional {
+        names.push(tr!("No room"));
+        ids.push(None);
+    }
+    for room in rooms {
+        names.push(if room.capacity > 0 { tr!("{} (holds {})", room.name, room.capacity) } else { room.name.clone() });
+        ids.push(Some(room.id));
+    }
+    (DropDown::from_strings(&names.iter().map(String::as_str).collect::<Vec<_>>()), ids)
+}
+
+pub fn show_new_event(
+    parent: &impl IsA<gtk::Window>,
+    date: NaiveDate,
+    rooms: &[Room],
+    on_save: impl Fn(NewEvent) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("New Event on {}", date)),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Add").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let title_entry = Entry::new();
+    title_entry.set_placeholder_text(Some(&tr!("e.g. Storytime, Book club")));
+    let start_entry = Entry::new();
+    start_entry.set_placeholder_text(Some("HH:MM"));
+    let end_entry = Entry::new();
+    end_entry.set_placeholder_text(Some("HH:MM"));
+    let (room_dropdown, room_ids) = rooms_dropdown(rooms, true);
+    let capacity_spin = gtk::SpinButton::with_range(1.0, 9999.0, 1.0);
+    capacity_spin.set_value(20.0);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 5] = [
+        (tr!("Title:"), title_entry.upcast_ref()),
+        (tr!("Starts at:"), start_entry.upcast_ref()),
+        (tr!("Ends at:"), end_entry.upcast_ref()),
+        (tr!("Room:"), room_dropdown.upcast_ref()),
+        (tr!("Places:"), capacity_spin.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let result = events::local_span(date, &start_entry.text(), &end_entry.text()).and_then(|(start, end)| {
+            on_save(NewEvent {
+                title: title_entry.text().to_string(),
+                room_id: room_ids.get(room_dropdown.selected() as usize).copied().flatten(),
+                start,
+                end,
+                capacity: capacity_spin.value_as_int() as u32,
+            })
+        });
+        match result {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to add the event"), &e),
+        }
+    });
+    dialog.show();
+}
+
+pub fn show_book_room(
+    parent: &impl IsA<gtk::Window>,
+    date: NaiveDate,
+    rooms: &[Room],
+    on_save: impl Fn(u32, String, DateTime<Utc>, DateTime<Utc>) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Book a Room on {}", date)),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Book").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let (room_dropdown, room_ids) = rooms_dropdown(rooms, false);
+    let booked_by_entry = Entry::new();
+    booked_by_entry.set_placeholder_text(Some(&tr!("Name or group")));
+    let start_entry = Entry::new();
+    start_entry.set_placeholder_text(Some("HH:MM"));
+    let end_entry = Entry::new();
+    end_entry.set_placeholder_text(Some("HH:MM"));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &gtk::Widget); 4] = [
+        (tr!("Room:"), room_dropdown.upcast_ref()),
+        (tr!("Booked by:"), booked_by_entry.upcast_ref()),
+        (tr!("Starts at:"), start_entry.upcast_ref()),
+        (tr!("Ends at:"), end_entry.upcast_ref()),
+    ];
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let Some(room_id) = room_ids.get(room_dropdown.selected() as usize).copied().flatten() else {
+            show_error(dialog, &tr!("Add a room first"), "");
+            return;
+        };
+        let result = events::local_span(date, &start_entry.text(), &end_entry.text())
+            .and_then(|(start, end)| on_save(room_id, booked_by_entry.text().to_string(), start, end));
+        match result {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to book the room"), &e),
+        }
+    });
+    dialog.show();
+}
+
+/// Asks why a fine is being waived. With `needs_approval`, an
+/// administrator also has to enter their username and password; their
+/// name is passed on as the approver.
+pub fn show_waive_fine(
+    parent: &impl IsA<gtk::Window>,
+    amount: &str,
+    needs_approval: bool,
+    users: Rc<RefCell<UserStore>>,
+    on_waive: impl Fn(WaiverReason, Option<String>) -> Result<(), String> + 'static,
+) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Waive Fine")),
+        Some(parent),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[
+            (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+            (tr!("Waive").as_str(), gtk::ResponseType::Accept),
+        ],
+    );
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    content.append(&Label::new(Some(&tr!("Waive a fine of {}", amount))));
+    let reason_labels: Vec<String> = WaiverReason::ALL.iter().map(|r| r.label()).collect();
+    let reason_dropdown = DropDown::from_strings(&reason_labels.iter().map(String::as_str).collect::<Vec<_>>());
+    let username_entry = Entry::new();
+    username_entry.set_placeholder_text(Some(&tr!("Username")));
+    let password_entry = gtk::PasswordEntry::new();
+    password_entry.set_activates_default(true);
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let mut rows: Vec<(String, &gtk::Widget)> = vec![(tr!("Reason:"), reason_dropdown.upcast_ref())];
+    if needs_approval {
+        rows.push((tr!("Administrator:"), username_entry.upcast_ref()));
+        rows.push((tr!("Password:"), password_entry.upcast_ref()));
+    }
+    for (row, (name, widget)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        widget.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*widget, 1, row as i32, 1, 1);
+    }
+    if needs_approval {
+        content.append(&Label::new(Some(&tr!("Fines this large can only be waived with an administrator's approval."))));
+    }
+    content.append(&grid);
+
+    dialog.connect_response(move |dialog, response| {
+        if response != gtk::ResponseType::Accept {
+            dialog.close();
+            return;
+        }
+        let reason = WaiverReason::ALL[reason_dropdown.selected() as usize % WaiverReason::ALL.len()];
+        let approved_by = if needs_approval {
+            let session = users.borrow().authenticate(&username_entry.text(), &password_entry.text());
+            match session {
+                Some(session) if session.role >= Role::Admin => Some(session.username),
+                Some(session) => {
+                    tracing::warn!(user = %session.username, "Fine waiver approval refused: not an administrator");
+                    show_error(dialog, &tr!("Approval refused"), &tr!("{} is not an administrator.", session.username));
+                    return;
+                }
+                None => {
+                    tracing::warn!(user = %username_entry.text(), "Fine waiver approval refused: wrong credentials");
+                    show_error(dialog, &tr!("Approval refused"), &tr!("Wrong username or password."));
+                    password_entry.set_text("");
+                    return;
+                }
+            }
+        } else {
+            None
+        };
+        match on_waive(reason, approved_by) {
+            Ok(()) => dialog.close(),
+            Err(e) => show_error(dialog, &tr!("Failed to waive the fine"), &e),
+        }
+    });
+    dialog.show();
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use gtk::prelude::*;
+use gtk::{gdk, gio, glib};
+
+use crate::i18n::tr;
+use crate::import::{self, ImportKind};
+use crate::{dialogs, marc, Library};
+
+/// Lets a file be dropped onto `window` to import it. CSV and JSON files
+/// open the import wizard: for members while the member page is shown, for
+/// the catalog otherwise. MARC files are imported as catalog items.
+pub fn install(window: &gtk::Window, stack: &adw::ViewStack, library: Arc<RwLock<Library>>) {
+    let target = gtk::DropTarget::new(gio::File::static_type(), gdk::DragAction::COPY);
+    target.connect_drop(glib::clone!(
+        #[weak]
+        window,
+        #[weak]
+        stack,
+        #[upgrade_or]
+        false,
+        move |_, value, _, _| {
+            let Some(path) = value.get::<gio::File>().ok().and_then(|file| file.path()) else { return false };
+            let kind = match stack.visible_child_name().as_deref() {
+                Some("members") => ImportKind::Members,
+                _ => ImportKind::Items,
+            };
+            import_file(&window, library.clone(), path, kind);
+            true
+        }
+    ));
+    window.add_controller(target);
+}
+
+/// Imports a dropped or opened file: through the import wizard for CSV and
+/// JSON, or straight into the catalog for MARC.
+pub fn import_file(window: &gtk::Window, library: Arc<RwLock<Library>>, path: PathBuf, kind: ImportKind) {
+    let extension = path.extension().map(|e| e.to_string_lossy().to_lowercase()).unwrap_or_default();
+    tracing::info!(path = %path.display(), "File opened for import");
+    match extension.as_str() {
+        "csv" | "tsv" | "txt" => dialogs::show_import_wizard(window, library, path, kind, || {}),
+        "json" => match import::json_to_csv(&path) {
+            Ok(csv_path) => dialogs::show_import_wizard(window, library, csv_path, kind, || {}),
+            Err(e) => dialogs::show_error(window, &tr!("Failed to read {}", path.display()), &e.to_string()),
+        },
+        "mrc" | "marc" | "xml" => match marc::read_file(&path) {
+            Ok(records) => dialogs::show_import_marc(window, library, records),
+            Err(e) => dialogs::show_error(window, &tr!("Failed to read {}", path.display()), &e.to_string()),
+        },
+        _ => dialogs::show_error(
+            window,
+            &tr!("Can't import {}", path.display()),
+            &tr!("Drop a CSV, JSON or MARC file (.csv, .tsv, .json, .mrc or .xml)."),
+        ),
+    }
+}
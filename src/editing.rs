@@ -0,0 +1,418 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::changes::LibraryChange;
+use crate::i18n::tr;
+use crate::memberships::MembershipType;
+use crate::sync::LibraryCommand;
+use crate::transfers;
+use crate::{Library, LiItem, Member};
+
+/// The descriptive fields of an item that staff can change by hand.
+#[derive(Clone)]
+pub struct ItemDetails {
+    pub title: String,
+    pub author: Option<String>,
+    pub year: u32,
+    pub edition: String,
+    pub format: String,
+    pub desc: String,
+}
+
+impl ItemDetails {
+    pub fn of(item: &LiItem) -> ItemDetails {
+        ItemDetails {
+            title: item.title.to_string(),
+            author: item.author.clone(),
+            year: item.year,
+            edition: item.edition.clone(),
+            format: item.format.clone(),
+            desc: item.desc.clone(),
+        }
+    }
+}
+
+/// Why an edit of a whole record wasn't saved.
+pub enum EditError<T> {
+    Invalid(String),
+    // Someone else saved the record after the edit started; `current` is
+    // what they saved, at `version`
+    Conflict { version: u32, current: T },
+}
+
+impl<T> From<String> for EditError<T> {
+    fn from(e: String) -> EditError<T> {
+        EditError::Invalid(e)
+    }
+}
+
+/// A field that differs between the saved record and an edit of it.
+pub struct FieldDiff {
+    pub field: String,
+    pub theirs: String,
+    pub yours: String,
+}
+
+/// Details edited as a whole in a dialog, so an edit of an older version can
+/// be compared with the current one and merged into it.
+pub trait EditableDetails: Clone + 'static {
+    // Each field's label and value, in the order the dialog shows them
+    fn fields(&self) -> Vec<(String, String)>;
+
+    // `yours` with the fields it left as they were in `base` taken from
+    // `theirs`
+    fn merge(base: &Self, yours: Self, theirs: &Self) -> Self;
+
+    fn diff(theirs: &Self, yours: &Self) -> Vec<FieldDiff> {
+        theirs
+            .fields()
+            .into_iter()
+            .zip(yours.fields())
+            .filter(|((_, a), (_, b))| a != b)
+            .map(|((field, theirs), (_, yours))| FieldDiff { field, theirs, yours })
+            .collect()
+    }
+}
+
+// Your value if you changed it, otherwise theirs
+fn pick<T: PartialEq + Clone>(base: &T, yours: T, theirs: &T) -> T {
+    if yours == *base { theirs.clone() } else { yours }
+}
+
+impl EditableDetails for ItemDetails {
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            (tr!("Title"), self.title.clone()),
+            (tr!("Author"), self.author.clone().unwrap_or_default()),
+            (tr!("Year"), self.year.to_string()),
+            (tr!("Edition"), self.edition.clone()),
+            (tr!("Format"), self.format.clone()),
+            (tr!("Description"), self.desc.clone()),
+        ]
+    }
+
+    fn merge(base: &ItemDetails, yours: ItemDetails, theirs: &ItemDetails) -> ItemDetails {
+        ItemDetails {
+            title: pick(&base.title, yours.title, &theirs.title),
+            author: pick(&base.author, yours.author, &theirs.author),
+            year: pick(&base.year, yours.year, &theirs.year),
+            edition: pick(&base.edition, yours.edition, &theirs.edition),
+            format: pick(&base.format, yours.format, &theirs.format),
+            desc: pick(&base.desc, yours.desc, &theirs.desc),
+        }
+    }
+}
+
+/// A title being added to the catalog by hand, typed in or copied from
+/// another library's record.
+pub struct NewItem {
+    pub details: ItemDetails,
+    pub isbn: Option<String>,
+    pub call_number: String,
+    pub tags: Vec<String>,
+    pub copies: u32,
+}
+
+/// Catalog columns that can be edited in place.
+#[derive(Clone, Copy)]
+pub enum ItemField {
+    CallNumber,
+    Copies,
+    Format,
+    ShelfLocation,
+    Year,
+}
+
+// What it takes to undo one edit: the item and its previous values.
+pub(crate) enum Edit {
+    Field(u32, ItemField, String),
+    Details(u32, ItemDetails),
+    Tags(u32, Vec<String>),
+    // Copies withdrawn from the shelf
+    Withdrawn(u32, u32),
+    // A batch edit, undone as a whole
+    Batch(Vec<Edit>),
+}
+
+impl Edit {
+    // The edit without its parts for `item_id`, e.g. once the item is gone
+    pub(crate) fn without_item(self, item_id: u32) -> Option<Edit> {
+        match self {
+            Edit::Batch(edits) => {
+                let edits: Vec<Edit> = edits.into_iter().filter_map(|e| e.without_item(item_id)).collect();
+                (!edits.is_empty()).then_some(Edit::Batch(edits))
+            }
+            Edit::Field(id, _, _) | Edit::Details(id, _) | Edit::Tags(id, _) | Edit::Withdrawn(id, _) if id == item_id => None,
+            edit => Some(edit),
+        }
+    }
+}
+
+// Undo only goes back this many edits
+const UNDO_LIMIT: usize = 100;
+
+impl Library {
+    pub(crate) fn push_undo(&mut self, edit: Edit) {
+        if self.undo_stack.len() == UNDO_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.undo_stack.push(edit);
+    }
+
+    /// Saves an edit made to `version` of the item. If the item has been
+    /// saved since, nothing changes and the error says what it is now.
+    pub fn edit_item(&mut self, item_id: u32, version: u32, details: ItemDetails) -> Result<(), EditError<ItemDetails>> {
+        self.only_at_server()?;
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        if item.version != version {
+            return Err(EditError::Conflict { version: item.version, current: ItemDetails::of(item) });
+        }
+        let old = self.apply_details(item_id, details)?;
+        self.push_undo(Edit::Details(item_id, old));
+        Ok(())
+    }
+
+    /// Sets one field from the text typed into the catalog. The copy count
+    /// can't go below the number of copies on loan.
+    pub fn set_item_field(&mut self, item_id: u32, field: ItemField, text: &str) -> Result<(), String> {
+        self.only_at_server()?;
+        let old = self.apply_field(item_id, field, text)?;
+        self.push_undo(Edit::Field(item_id, field, old));
+        Ok(())
+    }
+
+    /// Reverts the most recent edit. Returns the ID of an item it changed.
+    pub fn undo_edit(&mut self) -> Result<u32, String> {
+        self.only_at_server()?;
+        let edit = self.undo_stack.pop().ok_or_else(|| tr!("Nothing to undo"))?;
+        self.undo(edit)
+    }
+
+    fn undo(&mut self, edit: Edit) -> Result<u32, String> {
+        match edit {
+            Edit::Field(item_id, field, old) => self.apply_field(item_id, field, &old).map(|_| item_id),
+            Edit::Details(item_id, old) => self.apply_details(item_id, old).map(|_| item_id),
+            Edit::Tags(item_id, old) => self.set_item_tags(item_id, old).map(|_| item_id),
+            Edit::Withdrawn(item_id, count) => {
+                let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                item.undo_withdrawal(count);
+                self.dirty = true;
+                self.item_changed(item_id);
+                Ok(item_id)
+            }
+            // Later items are undone first; one that can't be undone
+            // doesn't stop the others
+            Edit::Batch(edits) => {
+                let (mut undone, mut error) = (None, None);
+                for edit in edits.into_iter().rev() {
+                    match self.undo(edit) {
+                        Ok(item_id) => undone = Some(item_id),
+                        Err(e) => error = Some(e),
+                    }
+                }
+                match (error, undone) {
+                    (Some(e), _) => Err(e),
+                    (None, Some(item_id)) => Ok(item_id),
+                    (None, None) => Err(tr!("Nothing to undo")),
+                }
+            }
+        }
+    }
+
+    // Returns the details the item had before.
+    fn apply_details(&mut self, item_id: u32, details: ItemDetails) -> Result<ItemDetails, String> {
+        let title = details.title.trim();
+        if title.is_empty() {
+            return Err(tr!("Title cannot be empty"));
+        }
+        let format = details.format.trim();
+        if format.is_empty() {
+            return Err(tr!("Format cannot be empty"));
+        }
+
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        let old = ItemDetails::of(item);
+        item.title = title.into();
+        item.author = details.author.map(|a| a.trim().to_string()).filter(|a| !a.is_empty());
+        item.year = details.year;
+        item.edition = details.edition.trim().to_string();
+        item.format = format.to_string();
+        item.desc = details.desc.trim().to_string();
+        item.version += 1;
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(old)
+    }
+
+    // Returns the field's previous value as text.
+    pub(crate) fn apply_field(&mut self, item_id: u32, field: ItemField, text: &str) -> Result<String, String> {
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        let text = text.trim();
+        let old = match field {
+            ItemField::CallNumber => std::mem::replace(&mut item.call_number, text.to_string()),
+            ItemField::Copies => {
+                let copies: u32 = text.parse().map_err(|_| tr!("\"{}\" is not a number of copies", text))?;
+                let on_loan = item.copies.saturating_sub(item.avail_copies);
+                if copies < on_loan {
+                    return Err(tr!("{} copies are on loan, so there must be at least that many", on_loan));
+                }
+                let old = item.copies.to_string();
+                item.copies = copies;
+                item.avail_copies = copies - on_loan;
+                old
+            }
+            ItemField::Format => {
+                if text.is_empty() {
+                    return Err(tr!("Format cannot be empty"));
+                }
+                std::mem::replace(&mut item.format, text.to_string())
+            }
+            ItemField::ShelfLocation => std::mem::replace(&mut item.shelf_location, text.to_string()),
+            ItemField::Year => {
+                let year: u32 = text.parse().map_err(|_| tr!("\"{}\" is not a year", text))?;
+                let old = item.year.to_string();
+                item.year = year;
+                old
+            }
+        };
+        item.version += 1;
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(old)
+    }
+}
+
+/// The contact details of a member that staff can change by hand.
+#[derive(Clone)]
+pub struct MemberDetails {
+    pub name: String,
+    pub email: Option<String>,
+    pub external_id: Option<String>,
+}
+
+impl MemberDetails {
+    pub fn of(member: &Member) -> MemberDetails {
+        MemberDetails { name: member.name.clone(), email: member.email.clone(), external_id: member.external_id.clone() }
+    }
+}
+
+impl EditableDetails for MemberDetails {
+    fn fields(&self) -> Vec<(String, String)> {
+        vec![
+            (tr!("Name"), self.name.clone()),
+            (tr!("Email"), self.email.clone().unwrap_or_default()),
+            (tr!("External ID"), self.external_id.clone().unwrap_or_default()),
+        ]
+    }
+
+    fn merge(base: &MemberDetails, yours: MemberDetails, theirs: &MemberDetails) -> MemberDetails {
+        MemberDetails {
+            name: pick(&base.name, yours.name, &theirs.name),
+            email: pick(&base.email, yours.email, &theirs.email),
+            external_id: pick(&base.external_id, yours.external_id, &theirs.external_id),
+        }
+    }
+}
+
+fn non_empty(text: Option<String>) -> Option<String> {
+    text.map(|t| t.trim().to_string()).filter(|t| !t.is_empty())
+}
+
+impl Library {
+    /// Adds a title to the catalog with all its copies on the shelf.
+    /// Returns the new item's ID.
+    pub fn add_item(&mut self, new: NewItem) -> Result<u32, String> {
+        self.only_at_server()?;
+        if new.copies == 0 {
+            return Err(tr!("An item needs at least one copy"));
+        }
+        let id = self.next_item_id();
+        self.items.insert(id, LiItem {
+            title: "".into(),
+            author: None,
+            year: 0,
+            edition: String::new(),
+            desc: String::new(),
+            format: String::new(),
+            id,
+            copies: new.copies,
+            avail_copies: new.copies,
+            ratings: 0,
+            isbn: non_empty(new.isbn),
+            tags: new.tags,
+            reviews: Vec::new(),
+            added_at: Some(self.clock.now()),
+            branch: transfers::default_branch(),
+            call_number: new.call_number.trim().to_string(),
+            shelf_location: String::new(),
+            lost_copies: 0,
+            copy_statuses: BTreeMap::new(),
+            conditions: BTreeMap::new(),
+            condition_notes: Vec::new(),
+            link: None,
+            version: 0,
+        });
+        // Checked the same way as an edit, so nothing half-filled stays behind
+        if let Err(e) = self.apply_details(id, new.details) {
+            self.items.remove(&id);
+            return Err(e);
+        }
+        self.changed(LibraryChange::ItemAdded(id));
+        Ok(id)
+    }
+
+    /// Saves an edit made to `version` of the member, like `edit_item`.
+    pub fn edit_member(&mut self, member_id: u32, version: u32, details: MemberDetails) -> Result<(), EditError<MemberDetails>> {
+        self.only_at_server()?;
+        let name = details.name.trim();
+        if name.is_empty() {
+            return Err(tr!("Name cannot be empty").into());
+        }
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        if member.anonymized_at.is_some() {
+            return Err(tr!("Anonymized members can't be edited").into());
+        }
+        if member.version != version {
+            return Err(EditError::Conflict { version: member.version, current: MemberDetails::of(member) });
+        }
+        member.name = name.to_string();
+        member.email = non_empty(details.email);
+        member.external_id = non_empty(details.external_id);
+        member.version += 1;
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(())
+    }
+
+    /// Signs up a new member with the default membership and returns their
+    /// ID.
+    pub fn register_member(&mut self, name: &str) -> Result<u32, String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(tr!("Name cannot be empty"));
+        }
+        let id = self.next_member_id();
+        self.members.insert(id, Member {
+            id,
+            name: name.to_string(),
+            items: HashMap::new(),
+            fines: Vec::new(),
+            membership: MembershipType::default(),
+            expires_at: self.new_membership_expiry(),
+            email: None,
+            external_id: None,
+            anonymized_at: None,
+            keep_history: false,
+            history: Vec::new(),
+            joined_at: Some(self.clock.now()),
+            challenge: None,
+            receipts: Vec::new(),
+            version: 0,
+            digest_opt_out: false,
+        });
+        tracing::info!(member_id = id, "Member registered");
+        self.record_command(LibraryCommand::RegisterMember { name: name.to_string(), member_id: Some(id) });
+        self.dirty = true;
+        self.changed(LibraryChange::MemberAdded(id));
+        Ok(id)
+    }
+}
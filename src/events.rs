@@ -0,0 +1,280 @@
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::Library;
+
+/// A room that events can be held in and groups can book.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Room {
+    pub id: u32,
+    pub name: String,
+    pub capacity: u32,
+}
+
+/// A room booked for something other than a library event, e.g. a study
+/// group or a meeting.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct RoomBooking {
+    pub id: u32,
+    pub room_id: u32,
+    pub booked_by: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+}
+
+/// A library event, e.g. a storytime or a book club meeting, with members
+/// registered to attend.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct LibraryEvent {
+    pub id: u32,
+    pub title: String,
+    pub room_id: Option<u32>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub capacity: u32,
+    pub attendees: Vec<u32>,
+}
+
+/// What the new event dialog asks for.
+pub struct NewEvent {
+    pub title: String,
+    pub room_id: Option<u32>,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub capacity: u32,
+}
+
+/// Rooms, room bookings and events.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct Events {
+    pub rooms: Vec<Room>,
+    pub bookings: Vec<RoomBooking>,
+    pub events: Vec<LibraryEvent>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Scheduled {
+    Event(u32),
+    Booking(u32),
+}
+
+/// A line of a day's schedule.
+#[derive(Clone)]
+pub struct ScheduleRow {
+    pub what: Scheduled,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub room: String,
+    pub title: String,
+    // "3 / 12" for events; empty for bookings
+    pub attendance: String,
+}
+
+/// The span from `start` to `end`, both "HH:MM" local times on `date`.
+pub fn local_span(date: NaiveDate, start: &str, end: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let at = |text: &str| -> Result<DateTime<Utc>, String> {
+        let time = NaiveTime::parse_from_str(text.trim(), "%H:%M").map_err(|_| tr!("\"{}\" is not a time; use HH:MM", text.trim()))?;
+        Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+            .ok_or_else(|| tr!("{} does not exist on {}", text.trim(), date))
+    };
+    let (start, end) = (at(start)?, at(end)?);
+    if end <= start {
+        return Err(tr!("The end time must be after the start time"));
+    }
+    Ok((start, end))
+}
+
+fn overlaps(a: (DateTime<Utc>, DateTime<Utc>), b: (DateTime<Utc>, DateTime<Utc>)) -> bool {
+    a.0 < b.1 && b.0 < a.1
+}
+
+fn local_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local).format("%H:%M").to_string()
+}
+
+impl Events {
+    pub fn room_name(&self, room_id: u32) -> String {
+        self.rooms.iter().find(|r| r.id == room_id).map_or_else(|| "?".to_string(), |r| r.name.clone())
+    }
+
+    // What already has the room in that time, so the staff know who to talk
+    // to
+    fn room_conflict(&self, room_id: u32, span: (DateTime<Utc>, DateTime<Utc>)) -> Option<String> {
+        let booking = self.bookings.iter().find(|b| b.room_id == room_id && overlaps((b.start, b.end), span));
+        if let Some(b) = booking {
+            return Some(tr!("{} is booked by {} from {} to {}", self.room_name(room_id), b.booked_by, local_time(b.start), local_time(b.end)));
+        }
+        let event = self.events.iter().find(|e| e.room_id == Some(room_id) && overlaps((e.start, e.end), span));
+        event.map(|e| tr!("{} is in use for {} from {} to {}", self.room_name(room_id), e.title, local_time(e.start), local_time(e.end)))
+    }
+}
+
+impl Library {
+    pub fn add_room(&mut self, name: &str, capacity: u32) -> Result<u32, String> {
+        self.only_at_server()?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(tr!("Enter the room's name"));
+        }
+        let rooms = &mut self.events.rooms;
+        if rooms.iter().any(|r| r.name.eq_ignore_ascii_case(name)) {
+            return Err(tr!("There is already a room called {}", name));
+        }
+        let id = rooms.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        rooms.push(Room { id, name: name.to_string(), capacity });
+        self.dirty = true;
+        Ok(id)
+    }
+
+    pub fn book_room(&mut self, room_id: u32, booked_by: &str, start: DateTime<Utc>, end: DateTime<Utc>) -> Result<u32, String> {
+        self.only_at_server()?;
+        let booked_by = booked_by.trim();
+        if booked_by.is_empty() {
+            return Err(tr!("Enter who the room is booked for"));
+        }
+        let events = &mut self.events;
+        if !events.rooms.iter().any(|r| r.id == room_id) {
+            return Err(tr!("Room not found"));
+        }
+        if let Some(conflict) = events.room_conflict(room_id, (start, end)) {
+            return Err(conflict);
+        }
+        let id = events.bookings.iter().map(|b| b.id).max().unwrap_or(0) + 1;
+        events.bookings.push(RoomBooking { id, room_id, booked_by: booked_by.to_string(), start, end });
+        tracing::info!(booking_id = id, room_id, "Room booked");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    pub fn create_event(&mut self, new: NewEvent) -> Result<u32, String> {
+        self.only_at_server()?;
+        let title = new.title.trim();
+        if title.is_empty() {
+            return Err(tr!("Title cannot be empty"));
+        }
+        if new.capacity == 0 {
+            return Err(tr!("An event needs room for at least one attendee"));
+        }
+        let events = &mut self.events;
+        if let Some(room_id) = new.room_id {
+            let room = events.rooms.iter().find(|r| r.id == room_id).ok_or_else(|| tr!("Room not found"))?;
+            if room.capacity > 0 && new.capacity > room.capacity {
+                return Err(tr!("{} only holds {} people", room.name, room.capacity));
+            }
+            if let Some(conflict) = events.room_conflict(room_id, (new.start, new.end)) {
+                return Err(conflict);
+            }
+        }
+        let id = events.events.iter().map(|e| e.id).max().unwrap_or(0) + 1;
+        events.events.push(LibraryEvent {
+            id,
+            title: title.to_string(),
+            room_id: new.room_id,
+            start: new.start,
+            end: new.end,
+            capacity: new.capacity,
+            attendees: Vec::new(),
+        });
+        tracing::info!(event_id = id, "Event created");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Cancels an event or a room booking, freeing its room.
+    pub fn cancel_scheduled(&mut self, what: Scheduled) -> Result<(), String> {
+        self.only_at_server()?;
+        let events = &mut self.events;
+        match what {
+            Scheduled::Event(id) => {
+                let before = events.events.len();
+                events.events.retain(|e| e.id != id);
+                if events.events.len() == before {
+                    return Err(tr!("Event not found"));
+                }
+            }
+            Scheduled::Booking(id) => {
+                let before = events.bookings.len();
+                events.bookings.retain(|b| b.id != id);
+                if events.bookings.len() == before {
+                    return Err(tr!("Booking not found"));
+                }
+            }
+        }
+        tracing::info!(?what, "Cancelled");
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn register_attendee(&mut self, event_id: u32, member_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if !self.members.contains_key(&member_id) {
+            return Err(tr!("Member not found"));
+        }
+        let event = self.events.events.iter_mut().find(|e| e.id == event_id).ok_or_else(|| tr!("Event not found"))?;
+        if event.attendees.contains(&member_id) {
+            return Err(tr!("Member is already registered"));
+        }
+        if event.attendees.len() as u32 >= event.capacity {
+            return Err(tr!("{} is full", event.title));
+        }
+        event.attendees.push(member_id);
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn unregister_attendee(&mut self, event_id: u32, member_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let event = self.events.events.iter_mut().find(|e| e.id == event_id).ok_or_else(|| tr!("Event not found"))?;
+        let index = event.attendees.iter().position(|&id| id == member_id).ok_or_else(|| tr!("Member is not registered"))?;
+        event.attendees.remove(index);
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Events and room bookings starting on `date`, in order.
+    pub fn day_schedule(&self, date: NaiveDate) -> Vec<ScheduleRow> {
+        let on_day = |at: DateTime<Utc>| at.with_timezone(&Local).date_naive() == date;
+        let events = &self.events;
+        let mut rows: Vec<ScheduleRow> = events
+            .events
+            .iter()
+            .filter(|e| on_day(e.start))
+            .map(|e| ScheduleRow {
+                what: Scheduled::Event(e.id),
+                start: e.start,
+                end: e.end,
+                room: e.room_id.map_or(String::new(), |id| events.room_name(id)),
+                title: e.title.clone(),
+                attendance: format!("{} / {}", e.attendees.len(), e.capacity),
+            })
+            .chain(events.bookings.iter().filter(|b| on_day(b.start)).map(|b| ScheduleRow {
+                what: Scheduled::Booking(b.id),
+                start: b.start,
+                end: b.end,
+                room: events.room_name(b.room_id),
+                title: tr!("Booked by {}", b.booked_by),
+                attendance: String::new(),
+            }))
+            .collect();
+        rows.sort_by_key(|r| r.start);
+        rows
+    }
+
+    /// Days of `year`/`month` with something scheduled, for marking the
+    /// calendar.
+    pub fn busy_days(&self, year: i32, month: u32) -> Vec<u32> {
+        let starts = self.events.events.iter().map(|e| e.start).chain(self.events.bookings.iter().map(|b| b.start));
+        let mut days: Vec<u32> = starts
+            .map(|at| at.with_timezone(&Local).date_naive())
+            .filter(|d| d.year() == year && d.month() == month)
+            .map(|d| d.day())
+            .collect();
+        days.sort_unstable();
+        days.dedup();
+        days
+    }
+}
@@ -0,0 +1,161 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+use serde::de::{self, Deserializer, SeqAccess, Visitor};
+
+use crate::i18n::tr;
+use crate::{LiItem, Library};
+
+/// Splits a free-text tag list ("Fantasy; classics, YA") into tags. Case
+/// variants of a tag already seen are dropped; order is kept.
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split([';', ',', '|']) {
+        let tag = tag.trim();
+        if !tag.is_empty() && !tags.iter().any(|t| t.eq_ignore_ascii_case(tag)) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// Tags are a list in library.json but a single delimited column in CSV files,
+// where the csv crate may also hand over a number ("1984") or a bool.
+pub fn deserialize_tags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    struct TagsVisitor;
+
+    impl<'de> Visitor<'de> for TagsVisitor {
+        type Value = Vec<String>;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str("a list of tags or a delimited string")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            Ok(parse_tags(v))
+        }
+
+        fn visit_bool<E: de::Error>(self, v: bool) -> Result<Self::Value, E> {
+            Ok(vec![v.to_string()])
+        }
+
+        fn visit_i64<E: de::Error>(self, v: i64) -> Result<Self::Value, E> {
+            Ok(vec![v.to_string()])
+        }
+
+        fn visit_u64<E: de::Error>(self, v: u64) -> Result<Self::Value, E> {
+            Ok(vec![v.to_string()])
+        }
+
+        fn visit_f64<E: de::Error>(self, v: f64) -> Result<Self::Value, E> {
+            Ok(vec![v.to_string()])
+        }
+
+        fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+            Ok(Vec::new())
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            let mut tags = Vec::new();
+            while let Some(tag) = seq.next_element::<String>()? {
+                tags.push(tag);
+            }
+            Ok(tags)
+        }
+    }
+
+    deserializer.deserialize_any(TagsVisitor)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum FacetKind {
+    Genre,
+    Format,
+    Decade,
+    // Any copy's status
+    Status,
+}
+
+impl FacetKind {
+    pub const ALL: [FacetKind; 4] = [FacetKind::Genre, FacetKind::Format, FacetKind::Decade, FacetKind::Status];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            FacetKind::Genre => "Genre",
+            FacetKind::Format => "Format",
+            FacetKind::Decade => "Decade",
+            FacetKind::Status => "Status",
+        }
+    }
+
+    fn values(self, item: &LiItem) -> Vec<String> {
+        match self {
+            FacetKind::Genre => item.tags.clone(),
+            FacetKind::Format => vec![item.format.clone()],
+            FacetKind::Decade if item.year == 0 => vec!["Unknown".to_string()],
+            FacetKind::Decade => vec![format!("{}s", item.year / 10 * 10)],
+            FacetKind::Status => item.statuses().into_iter().map(|(status, _)| status.label()).collect(),
+        }
+    }
+}
+
+/// The facet values picked in the catalog sidebar. An item is shown only if
+/// it matches every picked value.
+#[derive(Default)]
+pub struct FacetFilter {
+    selected: HashMap<FacetKind, String>,
+}
+
+impl FacetFilter {
+    pub fn selected(&self, kind: FacetKind) -> Option<&str> {
+        self.selected.get(&kind).map(String::as_str)
+    }
+
+    /// Picks `value` for `kind`, or clears it if it was already picked.
+    pub fn toggle(&mut self, kind: FacetKind, value: &str) {
+        if self.selected(kind) == Some(value) {
+            self.selected.remove(&kind);
+        } else {
+            self.selected.insert(kind, value.to_string());
+        }
+    }
+
+    pub fn matches(&self, item: &LiItem) -> bool {
+        self.selected.iter().all(|(kind, value)| kind.values(item).contains(value))
+    }
+
+    /// Counts items per value of `kind`. Picks on the other facets still
+    /// narrow the counts, but a pick on `kind` itself doesn't, so its
+    /// alternatives stay visible.
+    pub fn counts(&self, items: &[&LiItem], kind: FacetKind) -> BTreeMap<String, usize> {
+        let mut counts = BTreeMap::new();
+        for item in items {
+            let others_match = self
+                .selected
+                .iter()
+                .filter(|(k, _)| **k != kind)
+                .all(|(k, value)| k.values(item).contains(value));
+            if others_match {
+                for value in kind.values(item) {
+                    *counts.entry(value).or_insert(0) += 1;
+                }
+            }
+        }
+        counts
+    }
+}
+
+impl Library {
+    pub fn set_item_tags(&mut self, item_id: u32, tags: Vec<String>) -> Result<(), String> {
+        self.only_at_server()?;
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        item.tags = tags;
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,274 @@
+use chrono::{DateTime, Local, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::sync::LibraryCommand;
+use crate::transactions::TransactionKind;
+use crate::{Library, LiItemInstance};
+
+// Daily fine for adult members; see MembershipPolicy for the other types
+pub const FINE_PER_DAY_CENTS: u64 = 30;
+
+// Waivers above this need an administrator's approval, unless changed in
+// Settings
+pub const DEFAULT_WAIVER_APPROVAL_CENTS: u64 = 2000;
+
+pub fn default_waiver_approval_cents() -> u64 {
+    DEFAULT_WAIVER_APPROVAL_CENTS
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum FineStatus {
+    Outstanding,
+    Paid,
+    Waived,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WaiverReason {
+    // The fine shouldn't have been charged, e.g. a missed check-in
+    StaffError,
+    Amnesty,
+    Hardship,
+}
+
+impl WaiverReason {
+    pub const ALL: [WaiverReason; 3] = [WaiverReason::StaffError, WaiverReason::Amnesty, WaiverReason::Hardship];
+
+    pub fn label(self) -> String {
+        match self {
+            WaiverReason::StaffError => tr!("Staff error"),
+            WaiverReason::Amnesty => tr!("Amnesty"),
+            WaiverReason::Hardship => tr!("Hardship"),
+        }
+    }
+}
+
+/// Dates on which late days aren't fined, e.g. to win back members who
+/// stay away over old fines.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Amnesty {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+impl Amnesty {
+    pub fn covers(&self, day: NaiveDate) -> bool {
+        (self.from..=self.to).contains(&day)
+    }
+}
+
+/// Why and by whom a fine was waived.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Waiver {
+    pub reason: WaiverReason,
+    pub at: DateTime<Utc>,
+    pub by: String,
+    // The administrator who approved a waiver above the approval amount
+    pub approved_by: Option<String>,
+}
+
+/// An entry in a member's fine ledger.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Fine {
+    pub id: u32,
+    pub item_id: u32,
+    pub days_late: i64,
+    pub amount_cents: u64,
+    pub assessed_at: DateTime<Utc>,
+    pub status: FineStatus,
+    // Set for fines waived since waivers needed a reason
+    #[serde(default)]
+    pub waiver: Option<Waiver>,
+}
+
+/// A line of the waiver report.
+#[derive(Clone)]
+pub struct WaiverRow {
+    pub member_id: u32,
+    pub member_name: String,
+    pub item_id: u32,
+    pub amount_cents: u64,
+    pub waiver: Waiver,
+}
+
+pub fn format_money(cents: u64) -> String {
+    format!("${}.{:02}", cents / 100, cents % 100)
+}
+
+/// Reads an amount typed as "12", "12.5" or "$12.50" into cents.
+pub fn parse_money(text: &str) -> Result<u64, String> {
+    let invalid = || tr!("\"{}\" is not an amount", text.trim());
+    let digits = text.trim().trim_start_matches('$');
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    if fraction.len() > 2 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let whole: u64 = if whole.is_empty() && !fraction.is_empty() { 0 } else { whole.parse().map_err(|_| invalid())? };
+    let cents: u64 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    whole.checked_mul(100).and_then(|c| c.checked_add(cents)).ok_or_else(invalid)
+}
+
+impl Library {
+    // Adds a fine to the member's ledger if the loan is returned after its
+    // due date. The first `fine_grace_days` late days and days during an
+    // amnesty aren't fined, and no fine is above `max_fine_cents`; what
+    // that lets off is logged as forgiven. Plugins have the last word.
+    pub fn assess_fine(&mut self, member_id: u32, inst: &LiItemInstance) -> Option<Fine> {
+        let now = self.clock.now();
+        // Hourly loans are late from the minute they are due, closed or not,
+        // and all their late days count as the day they came back
+        let late: Vec<NaiveDate> = if inst.is_hourly() {
+            vec![now.date_naive(); inst.days_late_hourly(now).max(0) as usize]
+        } else {
+            self.calendar.open_days(inst.due_date, now).collect()
+        };
+        if late.is_empty() {
+            return None;
+        }
+
+        let membership = self.members.get(&member_id)?.membership;
+        let rate = self.policy(membership).fine_per_day_cents;
+        if rate == 0 {
+            return None;
+        }
+        let fined_days = late
+            .iter()
+            .skip(self.fine_grace_days as usize)
+            .filter(|day| !self.amnesty.is_some_and(|a| a.covers(**day)))
+            .count() as u64;
+        let mut amount_cents = fined_days * rate;
+        if self.max_fine_cents > 0 {
+            amount_cents = amount_cents.min(self.max_fine_cents);
+        }
+        amount_cents = self.plugins_fine(member_id, inst, late.len() as i64, amount_cents);
+        let forgiven_cents = (late.len() as u64 * rate).saturating_sub(amount_cents);
+        if forgiven_cents > 0 {
+            self.log_fine(TransactionKind::FineForgiven, inst.id, member_id, forgiven_cents);
+            self.dirty = true;
+        }
+        if amount_cents == 0 {
+            return None;
+        }
+
+        let member = self.members.get_mut(&member_id)?;
+        let fine = Fine {
+            id: member.fines.iter().map(|f| f.id).max().unwrap_or(0) + 1,
+            item_id: inst.id,
+            days_late: late.len() as i64,
+            amount_cents,
+            assessed_at: now,
+            status: FineStatus::Outstanding,
+            waiver: None,
+        };
+        member.fines.push(fine.clone());
+        self.dirty = true;
+        Some(fine)
+    }
+
+    fn outstanding_fine(&mut self, member_id: u32, fine_id: u32) -> Result<&mut Fine, String> {
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let fine = member
+            .fines
+            .iter_mut()
+            .find(|f| f.id == fine_id)
+            .ok_or_else(|| tr!("Fine not found"))?;
+        if fine.status != FineStatus::Outstanding {
+            return Err(tr!("This fine has already been settled"));
+        }
+        Ok(fine)
+    }
+
+    /// Marks an outstanding fine as paid and records it in the transaction
+    /// log. Returns the amount paid. Waivers go through `waive_fine`, which
+    /// asks for a reason and, above the approval amount, an administrator.
+    pub fn settle_fine(&mut self, member_id: u32, fine_id: u32) -> Result<u64, String> {
+        let fine = self.outstanding_fine(member_id, fine_id)?;
+        fine.status = FineStatus::Paid;
+        let (item_id, amount) = (fine.item_id, fine.amount_cents);
+        self.log_fine(TransactionKind::FinePaid, item_id, member_id, amount);
+        self.record_command(LibraryCommand::SettleFine { member_id, fine_id, status: FineStatus::Paid });
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(amount)
+    }
+
+    /// Whether waiving `amount_cents` needs an administrator's approval.
+    pub fn waiver_needs_approval(&self, amount_cents: u64) -> bool {
+        amount_cents > self.waiver_approval_cents
+    }
+
+    /// Waives an outstanding fine for `reason`. Fines above the approval
+    /// amount need the name of the administrator who approved it. The
+    /// waiver is kept on the fine and written to the transaction and
+    /// application logs. Returns the amount waived.
+    pub fn waive_fine(&mut self, member_id: u32, fine_id: u32, reason: WaiverReason, approved_by: Option<String>) -> Result<u64, String> {
+        let amount = self.outstanding_fine(member_id, fine_id)?.amount_cents;
+        if self.waiver_needs_approval(amount) && approved_by.is_none() {
+            return Err(tr!("Waivers over {} need an administrator's approval", format_money(self.waiver_approval_cents)));
+        }
+
+        let waiver = Waiver { reason, at: self.clock.now(), by: self.current_user.clone(), approved_by };
+        tracing::info!(
+            member_id,
+            fine_id,
+            amount_cents = amount,
+            ?reason,
+            user = %waiver.by,
+            approved_by = waiver.approved_by.as_deref().unwrap_or(""),
+            "Fine waived"
+        );
+        let fine = self.outstanding_fine(member_id, fine_id)?;
+        fine.status = FineStatus::Waived;
+        fine.waiver = Some(waiver.clone());
+        let item_id = fine.item_id;
+        self.log_fine(TransactionKind::FineWaived, item_id, member_id, amount);
+        self.record_command(LibraryCommand::WaiveFine { member_id, fine_id, reason, approved_by: waiver.approved_by });
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(amount)
+    }
+
+    /// Fines waived from `from` to `to`, oldest first.
+    pub fn waiver_report(&self, from: NaiveDate, to: NaiveDate) -> Vec<WaiverRow> {
+        let mut rows: Vec<WaiverRow> = self
+            .members
+            .values()
+            .flat_map(|m| {
+                m.fines.iter().filter_map(move |f| {
+                    let waiver = f.waiver.clone()?;
+                    Some(WaiverRow {
+                        member_id: m.id,
+                        member_name: m.name.clone(),
+                        item_id: f.item_id,
+                        amount_cents: f.amount_cents,
+                        waiver,
+                    })
+                })
+            })
+            .filter(|r| (from..=to).contains(&r.waiver.at.with_timezone(&Local).date_naive()))
+            .collect();
+        rows.sort_by_key(|r| r.waiver.at);
+        rows
+    }
+}
+
+pub fn waiver_report_headers() -> Vec<String> {
+    vec![tr!("Date"), tr!("Member"), tr!("Item"), tr!("Amount"), tr!("Reason"), tr!("Waived by"), tr!("Approved by")]
+}
+
+pub fn waiver_report_rows(rows: &[WaiverRow]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|r| {
+            vec![
+                r.waiver.at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+                format!("{} ({})", r.member_name, r.member_id),
+                r.item_id.to_string(),
+                format_money(r.amount_cents),
+                r.waiver.reason.label(),
+                r.waiver.by.clone(),
+                r.waiver.approved_by.clone().unwrap_or_default(),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,193 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::editing::{ItemDetails, NewItem};
+use crate::history::PastLoan;
+use crate::i18n::tr;
+use crate::import::ImportReport;
+use crate::pii;
+use crate::reviews::Review;
+use crate::Library;
+
+// Goodreads puts every book on one of these; they say nothing about genre
+const EXCLUSIVE_SHELVES: [&str; 3] = ["read", "currently-reading", "to-read"];
+
+// A row of a Goodreads library export. The aliases are the StoryGraph
+// export's names for the same columns.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct GoodreadsRow {
+    #[serde(rename = "Title")]
+    title: String,
+    #[serde(rename = "Author", alias = "Authors")]
+    author: String,
+    #[serde(rename = "ISBN", alias = "ISBN/UID")]
+    isbn: String,
+    #[serde(rename = "ISBN13")]
+    isbn13: String,
+    #[serde(rename = "My Rating", alias = "Star Rating")]
+    rating: String,
+    #[serde(rename = "Year Published")]
+    year: String,
+    #[serde(rename = "Original Publication Year")]
+    original_year: String,
+    #[serde(rename = "Binding", alias = "Format")]
+    binding: String,
+    #[serde(rename = "Bookshelves", alias = "Tags")]
+    shelves: String,
+    #[serde(rename = "Owned Copies")]
+    owned_copies: String,
+}
+
+// Goodreads writes ISBNs as spreadsheet formulas, ="0441013597", so
+// leading zeros survive
+fn unquote_isbn(text: &str) -> Option<String> {
+    let isbn = text.trim().trim_start_matches('=').trim_matches('"').trim();
+    (!isbn.is_empty()).then(|| isbn.to_string())
+}
+
+impl GoodreadsRow {
+    fn isbn(&self) -> Option<String> {
+        unquote_isbn(&self.isbn13).or_else(|| unquote_isbn(&self.isbn))
+    }
+
+    // Zero for unrated books; StoryGraph allows quarter stars
+    fn stars(&self) -> u8 {
+        self.rating.trim().parse::<f32>().map_or(0, |r| r.round().clamp(0.0, 5.0) as u8)
+    }
+
+    fn year(&self) -> u32 {
+        self.year.trim().parse().or_else(|_| self.original_year.trim().parse()).unwrap_or(0)
+    }
+
+    fn tags(&self) -> Vec<String> {
+        self.shelves
+            .split(',')
+            .map(str::trim)
+            .filter(|shelf| !shelf.is_empty() && !EXCLUSIVE_SHELVES.contains(shelf))
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Library {
+    /// Adds the books of a Goodreads (or StoryGraph) library export to the
+    /// catalog. Shelves become tags and the owner's rating an anonymous
+    /// review. Books whose ISBN is already in the catalog are skipped and
+    /// listed in the report.
+    pub fn import_goodreads_csv(&mut self, path: &Path) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        self.only_at_server()?;
+        let mut rdr = csv::ReaderBuilder::new().flexible(true).from_reader(File::open(path)?);
+        let headers = rdr.byte_headers()?.clone();
+        let mut report = ImportReport::new(&headers);
+
+        for record in rdr.byte_records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map_or(0, |p| p.line());
+                    report.add_error(line, &e, None);
+                    continue;
+                }
+            };
+            let line = record.position().map_or(0, |p| p.line());
+            let row: GoodreadsRow = match record.deserialize(Some(&headers)) {
+                Ok(row) => row,
+                Err(e) => {
+                    report.add_error(line, &e, Some(&record));
+                    continue;
+                }
+            };
+
+            let isbn = row.isbn();
+            if let Some(existing) = isbn.as_ref().and_then(|isbn| self.items.values().find(|i| i.isbn.as_ref() == Some(isbn))) {
+                report.add_problem(line, tr!("Already in the catalog as item {}", existing.id), Some(&record));
+                continue;
+            }
+            let new = NewItem {
+                details: ItemDetails {
+                    title: row.title.clone(),
+                    author: Some(row.author.clone()),
+                    year: row.year(),
+                    edition: String::new(),
+                    format: if row.binding.trim().is_empty() { "Book".to_string() } else { row.binding.clone() },
+                    desc: String::new(),
+                },
+                isbn,
+                call_number: String::new(),
+                tags: row.tags(),
+                copies: row.owned_copies.trim().parse::<u32>().unwrap_or(0).max(1),
+            };
+            let id = match self.add_item(new) {
+                Ok(id) => id,
+                Err(e) => {
+                    report.add_problem(line, e, Some(&record));
+                    continue;
+                }
+            };
+            let stars = row.stars();
+            if stars > 0 {
+                let now = self.clock.now();
+                self.items.get_mut(&id).unwrap().reviews.push(Review {
+                    member_id: None,
+                    stars,
+                    text: String::new(),
+                    date: now,
+                });
+            }
+            report.loaded += 1;
+        }
+
+        tracing::info!(path = %path.display(), loaded = report.loaded, skipped = report.skipped, "Goodreads library imported");
+        Ok(report)
+    }
+
+    /// Writes a member's reading history in the CSV layout Goodreads
+    /// imports, one row per title with the last time it was read and the
+    /// member's rating and review, encrypted with `passphrase` if given.
+    pub fn export_history_goodreads_csv(&self, member_id: u32, path: &Path, passphrase: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let member = self.members.get(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let mut latest: BTreeMap<u32, &PastLoan> = BTreeMap::new();
+        for loan in &member.history {
+            let entry = latest.entry(loan.item_id).or_insert(loan);
+            if loan.returned_at > entry.returned_at {
+                *entry = loan;
+            }
+        }
+
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record([
+            "Title",
+            "Author",
+            "ISBN",
+            "My Rating",
+            "Year Published",
+            "Date Read",
+            "Date Added",
+            "Bookshelves",
+            "Exclusive Shelf",
+            "My Review",
+        ])?;
+        for loan in latest.values() {
+            // Items deleted since still have their title in the history
+            let item = self.item_or_trashed(loan.item_id);
+            let review = item.and_then(|i| i.reviews.iter().find(|r| r.member_id == Some(member_id)));
+            wtr.write_record([
+                loan.title.to_string(),
+                item.and_then(|i| i.author.clone()).unwrap_or_default(),
+                item.and_then(|i| i.isbn.clone()).unwrap_or_default(),
+                review.map_or(0, |r| r.stars).to_string(),
+                item.map_or(String::new(), |i| if i.year > 0 { i.year.to_string() } else { String::new() }),
+                loan.returned_at.format("%Y/%m/%d").to_string(),
+                loan.issued_at.format("%Y/%m/%d").to_string(),
+                item.map_or(String::new(), |i| i.tags.join(", ")),
+                "read".to_string(),
+                review.map_or(String::new(), |r| r.text.clone()),
+            ])?;
+        }
+        pii::write_export(path, &wtr.into_inner().map_err(|e| e.into_error())?, passphrase)
+    }
+}
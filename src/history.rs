@@ -0,0 +1,61 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::pii;
+use crate::{Library, LiItemInstance};
+
+/// A completed loan, kept only for members who opted in.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct PastLoan {
+    pub item_id: u32,
+    pub title: Arc<str>,
+    pub issued_at: DateTime<Utc>,
+    pub returned_at: DateTime<Utc>,
+}
+
+impl Library {
+    pub(crate) fn record_history(&mut self, member_id: u32, inst: &LiItemInstance) {
+        let now = self.clock.now();
+        if let Some(member) = self.members.get_mut(&member_id).filter(|m| m.keep_history) {
+            member.history.push(PastLoan {
+                item_id: inst.id,
+                title: inst.title.clone(),
+                issued_at: inst.issued_at,
+                returned_at: now,
+            });
+        }
+    }
+
+    /// Turns a member's reading history on or off. Turning it off also
+    /// forgets what has been kept so far.
+    pub fn set_keep_history(&mut self, member_id: u32, keep: bool) -> Result<(), String> {
+        self.only_at_server()?;
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        member.keep_history = keep;
+        if !keep {
+            member.history.clear();
+        }
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(())
+    }
+
+    pub fn export_history_csv(&self, member_id: u32, path: &Path, passphrase: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let member = self.members.get(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record(["item_id", "title", "issued", "returned"])?;
+        for loan in &member.history {
+            wtr.write_record([
+                loan.item_id.to_string(),
+                loan.title.to_string(),
+                loan.issued_at.format("%Y-%m-%d").to_string(),
+                loan.returned_at.format("%Y-%m-%d").to_string(),
+            ])?;
+        }
+        pii::write_export(path, &wtr.into_inner().map_err(|e| e.into_error())?, passphrase)
+    }
+}
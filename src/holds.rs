@@ -0,0 +1,332 @@
+use chrono::{DateTime, Days, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::copies::CopyStatus;
+use crate::i18n::tr;
+use crate::search::normalize;
+use crate::sync::LibraryCommand;
+use crate::webhooks::WebhookEvent;
+use crate::{Library, LiItem};
+
+// How long a member has to collect a held item, unless changed in Settings
+pub const DEFAULT_HOLD_PICKUP_DAYS: u32 = 7;
+
+pub fn default_hold_pickup_days() -> u32 {
+    DEFAULT_HOLD_PICKUP_DAYS
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum HoldStatus {
+    // In the queue for the next free copy
+    Waiting,
+    // A copy is on the hold shelf for the member
+    OnShelf,
+    Collected,
+    // Not collected in time
+    Expired,
+    Cancelled,
+}
+
+impl HoldStatus {
+    pub fn label(self) -> String {
+        match self {
+            HoldStatus::Waiting => tr!("Waiting"),
+            HoldStatus::OnShelf => tr!("On hold shelf"),
+            HoldStatus::Collected => tr!("Collected"),
+            HoldStatus::Expired => tr!("Expired"),
+            HoldStatus::Cancelled => tr!("Cancelled"),
+        }
+    }
+}
+
+/// What a hold asks for: a copy of the very item it was placed on, e.g. one
+/// volume of a set or one edition, or the next copy of the title that comes
+/// free, whichever catalog entry it belongs to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum HoldLevel {
+    // Holds placed before there was a choice were all on one item
+    #[default]
+    Copy,
+    Title,
+}
+
+impl HoldLevel {
+    pub const ALL: [HoldLevel; 2] = [HoldLevel::Title, HoldLevel::Copy];
+
+    pub fn label(self) -> String {
+        match self {
+            HoldLevel::Copy => tr!("Specific copy"),
+            HoldLevel::Title => tr!("Any copy of the title"),
+        }
+    }
+}
+
+// Catalog entries with the same title, author and format are copies of the
+// same title, whatever their edition or branch. Volumes of a set only stand
+// in for the same volume.
+fn title_key(item: &LiItem) -> (String, String, String, Option<u32>) {
+    (normalize(&item.title), normalize(item.author.as_deref().unwrap_or("")), item.format.to_lowercase(), item.volume_number())
+}
+
+/// A member's request for the next free copy of an item. Holds are filled
+/// in the order they were placed; a filled hold keeps a copy on the hold
+/// shelf, out of circulation, until the member collects it or the pickup
+/// deadline passes.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Hold {
+    pub id: u32,
+    pub item_id: u32,
+    pub member_id: u32,
+    #[serde(default)]
+    pub level: HoldLevel,
+    pub placed_at: DateTime<Utc>,
+    pub status: HoldStatus,
+    pub shelved_at: Option<DateTime<Utc>>,
+    pub pickup_by: Option<DateTime<Utc>>,
+    // Whether the member has been emailed that the item is ready
+    pub notified: bool,
+}
+
+/// An email telling a member their hold is ready to collect.
+pub struct PickupNotice {
+    pub hold_id: u32,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+impl Hold {
+    pub fn is_active(&self) -> bool {
+        matches!(self.status, HoldStatus::Waiting | HoldStatus::OnShelf)
+    }
+}
+
+impl Library {
+    /// Items that are copies of the same title as `item_id`, including
+    /// itself, lowest ID first.
+    pub fn same_title(&self, item_id: u32) -> Vec<u32> {
+        let Some(key) = self.items.get(&item_id).map(title_key) else { return Vec::new() };
+        let mut ids: Vec<u32> = self.items.values().filter(|i| title_key(i) == key).map(|i| i.id).collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    // Whether `hold`, while waiting, may be filled with a copy of `item_id`
+    fn hold_wants(&self, hold: &Hold, item_id: u32) -> bool {
+        match hold.level {
+            HoldLevel::Copy => hold.item_id == item_id,
+            HoldLevel::Title => {
+                hold.item_id == item_id
+                    || self.items.get(&hold.item_id).zip(self.items.get(&item_id)).is_some_and(|(a, b)| title_key(a) == title_key(b))
+            }
+        }
+    }
+
+    pub(crate) fn next_hold_id(&self) -> u32 {
+        self.holds.iter().map(|h| h.id).max().unwrap_or(0) + 1
+    }
+
+    pub fn place_hold(&mut self, item_id: u32, member_id: u32, level: HoldLevel) -> Result<u32, String> {
+        // A title bound with others is held as its host volume
+        let item_id = self.physical_unit(item_id);
+        if !self.items.contains_key(&item_id) {
+            return Err(tr!("Item not found"));
+        }
+        let member = self.members.get(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        if member.items.contains_key(&item_id) {
+            return Err(tr!("This member already has this item"));
+        }
+        let wanted = match level {
+            HoldLevel::Copy => vec![item_id],
+            HoldLevel::Title => self.same_title(item_id),
+        };
+        if self.holds.iter().any(|h| h.member_id == member_id && h.is_active() && wanted.iter().any(|&id| self.hold_wants(h, id))) {
+            return Err(tr!("Member already has a hold on this item"));
+        }
+        let id = self.next_hold_id();
+        self.holds.push(Hold {
+            id,
+            item_id,
+            member_id,
+            level,
+            placed_at: self.clock.now(),
+            status: HoldStatus::Waiting,
+            shelved_at: None,
+            pickup_by: None,
+            notified: false,
+        });
+        tracing::info!(hold_id = id, item_id, member_id, ?level, "Hold placed");
+        self.record_command(LibraryCommand::PlaceHold { item_id, member_id, level, hold_id: id });
+        self.dirty = true;
+        // A copy on the shelf now goes straight to the hold shelf
+        for item_id in wanted {
+            self.fill_holds(item_id);
+        }
+        Ok(id)
+    }
+
+    /// Moves free copies of `item_id` to the hold shelf for the members
+    /// waiting longest, whether they asked for this item or for any copy of
+    /// its title.
+    pub(crate) fn fill_holds(&mut self, item_id: u32) {
+        let now = self.clock.now();
+        let pickup_by = self.calendar.next_open(now + Days::new(self.hold_pickup_days as u64));
+        loop {
+            if !self.items.get(&item_id).is_some_and(|i| i.avail_copies > 0) {
+                return;
+            }
+            let Some(hold_id) = self
+                .holds
+                .iter()
+                .filter(|h| h.status == HoldStatus::Waiting && self.hold_wants(h, item_id))
+                // Not for a member who already has this very item out
+                .filter(|h| !self.members.get(&h.member_id).is_some_and(|m| m.items.contains_key(&item_id)))
+                .min_by_key(|h| h.placed_at)
+                .map(|h| h.id)
+            else {
+                return;
+            };
+            let item = self.items.get_mut(&item_id).unwrap();
+            if item.move_copies(CopyStatus::Available, CopyStatus::OnHoldShelf, 1).is_err() {
+                return;
+            }
+            let hold = self.holds.iter_mut().find(|h| h.id == hold_id).unwrap();
+            // A title hold now waits for this particular copy
+            hold.item_id = item_id;
+            hold.status = HoldStatus::OnShelf;
+            hold.shelved_at = Some(now);
+            hold.pickup_by = Some(pickup_by);
+            hold.notified = false;
+            let member_id = hold.member_id;
+            tracing::info!(hold_id, item_id, member_id, "Hold filled");
+            self.dirty = true;
+            self.item_changed(item_id);
+            self.webhook(WebhookEvent::HoldReady, item_id, member_id);
+        }
+    }
+
+    /// The hold keeping a copy of `item_id` on the shelf for `member_id`.
+    pub(crate) fn shelved_hold(&self, item_id: u32, member_id: u32) -> Option<u32> {
+        self.holds
+            .iter()
+            .find(|h| h.item_id == item_id && h.member_id == member_id && h.status == HoldStatus::OnShelf)
+            .map(|h| h.id)
+    }
+
+    // Marks a hold collected and puts its copy back in circulation, just
+    // before it is issued to the member
+    pub(crate) fn collect_hold(&mut self, hold_id: u32) {
+        let Some(hold) = self.holds.iter_mut().find(|h| h.id == hold_id && h.status == HoldStatus::OnShelf) else { return };
+        hold.status = HoldStatus::Collected;
+        let item_id = hold.item_id;
+        if let Some(item) = self.items.get_mut(&item_id) {
+            let _ = item.move_copies(CopyStatus::OnHoldShelf, CopyStatus::Available, 1);
+        }
+        self.dirty = true;
+        self.item_changed(item_id);
+    }
+
+    /// Holds on the hold shelf for `item_id`, the most recently shelved
+    /// last.
+    pub fn on_hold_shelf(&self, item_id: u32) -> Vec<&Hold> {
+        let mut holds: Vec<&Hold> =
+            self.holds.iter().filter(|h| h.item_id == item_id && h.status == HoldStatus::OnShelf).collect();
+        holds.sort_by_key(|h| h.shelved_at);
+        holds
+    }
+
+    // Ends a hold that had a copy on the shelf and passes the copy on to the
+    // next member waiting, if any
+    fn release_shelved(&mut self, hold_id: u32, status: HoldStatus) -> Result<(), String> {
+        let hold = self.holds.iter_mut().find(|h| h.id == hold_id).ok_or_else(|| tr!("Hold not found"))?;
+        let was_shelved = hold.status == HoldStatus::OnShelf;
+        hold.status = status;
+        let item_id = hold.item_id;
+        if was_shelved {
+            if let Some(item) = self.items.get_mut(&item_id) {
+                let _ = item.move_copies(CopyStatus::OnHoldShelf, CopyStatus::Available, 1);
+            }
+            self.item_changed(item_id);
+            self.fill_holds(item_id);
+        }
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn cancel_hold(&mut self, hold_id: u32) -> Result<(), String> {
+        let hold = self.holds.iter().find(|h| h.id == hold_id).ok_or_else(|| tr!("Hold not found"))?;
+        if !hold.is_active() {
+            return Err(tr!("Hold is no longer active"));
+        }
+        self.release_shelved(hold_id, HoldStatus::Cancelled)?;
+        tracing::info!(hold_id, "Hold cancelled");
+        self.record_command(LibraryCommand::CancelHold { hold_id });
+        Ok(())
+    }
+
+    // Cancels the holds on an item leaving the catalog. Copies it had on the
+    // hold shelf go back on the shelf rather than to the next member, as
+    // they are leaving too.
+    pub(crate) fn cancel_item_holds(&mut self, item_id: u32) {
+        let mut shelved = 0;
+        for hold in self.holds.iter_mut().filter(|h| h.item_id == item_id && h.is_active()) {
+            shelved += u32::from(hold.status == HoldStatus::OnShelf);
+            hold.status = HoldStatus::Cancelled;
+            tracing::info!(hold_id = hold.id, item_id, "Hold cancelled");
+        }
+        if let Some(item) = self.items.get_mut(&item_id) {
+            let _ = item.move_copies(CopyStatus::OnHoldShelf, CopyStatus::Available, shelved);
+        }
+    }
+
+    /// Expires holds not collected by their pickup deadline. Their copies
+    /// go to the next member waiting or back into circulation. Returns the
+    /// number expired.
+    pub fn expire_holds(&mut self) -> usize {
+        let now = self.clock.now();
+        let expired: Vec<u32> = self
+            .holds
+            .iter()
+            .filter(|h| h.status == HoldStatus::OnShelf && h.pickup_by.is_some_and(|by| by < now))
+            .map(|h| h.id)
+            .collect();
+        for &hold_id in &expired {
+            let _ = self.release_shelved(hold_id, HoldStatus::Expired);
+            tracing::info!(hold_id, "Hold expired");
+        }
+        expired.len()
+    }
+
+    /// Pickup emails for holds on the shelf whose member hasn't been told
+    /// yet. Members without an email address are left out.
+    pub fn pending_pickup_notices(&self) -> Vec<PickupNotice> {
+        self.holds
+            .iter()
+            .filter(|h| h.status == HoldStatus::OnShelf && !h.notified)
+            .filter_map(|h| {
+                let member = self.members.get(&h.member_id)?;
+                let to = member.email.clone().filter(|e| !e.trim().is_empty())?;
+                let title = self.items.get(&h.item_id).map_or_else(|| "?".to_string(), |i| i.title.to_string());
+                let pickup_by = h.pickup_by.map_or(String::new(), |by| by.format("%Y-%m-%d").to_string());
+                Some(PickupNotice {
+                    hold_id: h.id,
+                    to,
+                    subject: tr!("Your hold is ready: {}", title),
+                    body: tr!(
+                        "Dear {},\n\nThe item you placed a hold on is waiting for you at the library:\n  {}\n\nPlease collect it by {}. After that it goes to the next reader.\n",
+                        member.name,
+                        title,
+                        pickup_by
+                    ),
+                })
+            })
+            .collect()
+    }
+
+    pub fn mark_notified(&mut self, hold_ids: &[u32]) {
+        for hold in self.holds.iter_mut().filter(|h| hold_ids.contains(&h.id)) {
+            hold.notified = true;
+        }
+        self.dirty = true;
+    }
+}
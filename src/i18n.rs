@@ -0,0 +1,83 @@
+use std::fmt::{Display, Write};
+use std::io;
+
+use gettextrs::{bind_textdomain_codeset, bindtextdomain, setlocale, textdomain, LocaleCategory};
+
+const DOMAIN: &str = "rustLMS";
+// Compiled catalogs, as locale/<code>/LC_MESSAGES/rustLMS.mo (see po/update.sh)
+const LOCALE_DIR: &str = "locale";
+
+/// The languages there is a catalog for, as (code, name). English is the
+/// language of the source strings.
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("de", "Deutsch")];
+
+/// Translates a message. Further arguments fill its `{}` placeholders in
+/// order; translations may use `{0}`, `{1}`... to reorder them.
+macro_rules! tr {
+    ($msgid:literal) => {
+        gettextrs::gettext($msgid)
+    };
+    ($msgid:literal, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill(&gettextrs::gettext($msgid), &[$(&$arg as &dyn std::fmt::Display),+])
+    };
+}
+pub(crate) use tr;
+
+/// Sets up gettext for the messages. A non-empty `language` code overrides
+/// the system's choice, although glibc still ignores it under the C locale.
+/// Has to run before GTK starts or any other thread exists.
+pub fn init(language: &str) {
+    if !language.is_empty() {
+        // SAFETY: called first thing in main, while there is only one thread
+        unsafe { std::env::set_var("LANGUAGE", language) };
+    }
+    setlocale(LocaleCategory::LcAll, "");
+    if let Err(e) = bind_domain() {
+        tracing::warn!("Translations unavailable: {}", e);
+    }
+}
+
+fn bind_domain() -> io::Result<()> {
+    bindtextdomain(DOMAIN, LOCALE_DIR)?;
+    bind_textdomain_codeset(DOMAIN, "UTF-8")?;
+    textdomain(DOMAIN)?;
+    Ok(())
+}
+
+// The runtime half of `tr!`. `{{` and `}}` are literal braces; a placeholder
+// without a matching argument is left as it is.
+pub fn fill(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(start) = rest.find(['{', '}']) {
+        out.push_str(&rest[..start]);
+        let brace = &rest[start..];
+        if brace.starts_with("{{") || brace.starts_with("}}") {
+            out.push_str(&brace[..1]);
+            rest = &brace[2..];
+            continue;
+        }
+        let Some(end) = brace.find('}').filter(|_| brace.starts_with('{')) else {
+            out.push_str(&brace[..1]);
+            rest = &brace[1..];
+            continue;
+        };
+        let name = &brace[1..end];
+        let index = if name.is_empty() {
+            next += 1;
+            Some(next - 1)
+        } else {
+            name.parse::<usize>().ok()
+        };
+        match index.and_then(|i| args.get(i)) {
+            Some(arg) => {
+                let _ = write!(out, "{}", arg);
+            }
+            None => out.push_str(&brace[..=end]),
+        }
+        rest = &brace[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,190 @@
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use gtk::prelude::*;
+use gtk::{gio, glib, Box as GtkBox, Button, Label};
+
+use crate::auth::UserStore;
+use crate::i18n::tr;
+
+// How often the time since the last input is checked
+const IDLE_CHECK_SECONDS: u32 = 15;
+
+struct Lock {
+    window: glib::WeakRef<adw::ApplicationWindow>,
+    stack: gtk::Stack,
+    password_entry: gtk::PasswordEntry,
+    error_label: Label,
+    last_input: Cell<Instant>,
+    // Window actions turned off while locked, so shortcuts and the command
+    // palette can't reach the pages behind the lock screen
+    disabled: RefCell<Vec<gio::SimpleAction>>,
+}
+
+impl Lock {
+    fn is_locked(&self) -> bool {
+        self.stack.visible_child_name().as_deref() == Some("locked")
+    }
+
+    fn lock(&self) {
+        let Some(window) = self.window.upgrade() else { return };
+        if self.is_locked() {
+            return;
+        }
+        // Dialogs may show member details too
+        for toplevel in gtk::Window::list_toplevels() {
+            if let Ok(dialog) = toplevel.downcast::<gtk::Window>() {
+                if dialog.transient_for().as_ref() == Some(window.upcast_ref()) {
+                    dialog.close();
+                }
+            }
+        }
+        let mut disabled = self.disabled.borrow_mut();
+        for name in window.list_actions() {
+            if let Some(action) = window.lookup_action(&name).and_downcast::<gio::SimpleAction>() {
+                if action.is_enabled() {
+                    action.set_enabled(false);
+                    disabled.push(action);
+                }
+            }
+        }
+        self.error_label.set_text("");
+        self.password_entry.set_text("");
+        self.stack.set_visible_child_name("locked");
+        self.password_entry.grab_focus();
+        tracing::info!("Screen locked");
+    }
+
+    fn unlock(&self) {
+        for action in self.disabled.borrow_mut().drain(..) {
+            action.set_enabled(true);
+        }
+        self.password_entry.set_text("");
+        self.last_input.set(Instant::now());
+        self.stack.set_visible_child_name("unlocked");
+        tracing::info!("Screen unlocked");
+    }
+}
+
+/// Shows `content` in `window` behind a lock screen that covers it after
+/// `minutes` without keyboard or mouse input (zero turns that off), or when
+/// the lock action is used. `username`'s password unlocks it again.
+pub fn install(
+    app: &adw::Application,
+    window: &adw::ApplicationWindow,
+    content: &impl IsA<gtk::Widget>,
+    users: Rc<RefCell<UserStore>>,
+    username: String,
+    minutes: u32,
+) {
+    let lock_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    lock_box.set_valign(gtk::Align::Center);
+    lock_box.set_halign(gtk::Align::Center);
+    let icon = gtk::Image::from_icon_name("system-lock-screen-symbolic");
+    icon.set_pixel_size(64);
+    lock_box.append(&icon);
+    let title_label = Label::new(Some(&tr!("Locked")));
+    title_label.add_css_class("title-1");
+    lock_box.append(&title_label);
+    lock_box.append(&Label::new(Some(&tr!("Enter the password of {} to continue.", username))));
+    let password_entry = gtk::PasswordEntry::new();
+    lock_box.append(&password_entry);
+    let error_label = Label::new(None);
+    lock_box.append(&error_label);
+    let unlock_button = Button::with_label(&tr!("Unlock"));
+    unlock_button.add_css_class("suggested-action");
+    lock_box.append(&unlock_button);
+
+    let lock_view = adw::ToolbarView::new();
+    lock_view.add_top_bar(&adw::HeaderBar::new());
+    lock_view.set_content(Some(&lock_box));
+
+    let stack = gtk::Stack::new();
+    stack.set_transition_type(gtk::StackTransitionType::Crossfade);
+    stack.add_named(content, Some("unlocked"));
+    stack.add_named(&lock_view, Some("locked"));
+    stack.set_visible_child_name("unlocked");
+    window.set_content(Some(&stack));
+
+    let lock = Rc::new(Lock {
+        window: window.downgrade(),
+        stack,
+        password_entry,
+        error_label,
+        last_input: Cell::new(Instant::now()),
+        disabled: RefCell::new(Vec::new()),
+    });
+
+    let try_unlock = glib::clone!(
+        #[strong]
+        lock,
+        move || {
+            if users.borrow().authenticate(&username, &lock.password_entry.text()).is_some() {
+                lock.unlock();
+            } else {
+                tracing::warn!(user = %username, "Failed unlock");
+                lock.error_label.set_text(&tr!("Invalid password"));
+                lock.password_entry.set_text("");
+            }
+        }
+    );
+    let try_unlock = Rc::new(try_unlock);
+    lock.password_entry.connect_activate(glib::clone!(
+        #[strong]
+        try_unlock,
+        move |_| try_unlock()
+    ));
+    unlock_button.connect_clicked(move |_| try_unlock());
+
+    // Any key press, pointer movement or click counts as someone at the desk
+    let key = gtk::EventControllerKey::new();
+    key.set_propagation_phase(gtk::PropagationPhase::Capture);
+    key.connect_key_pressed(glib::clone!(
+        #[strong]
+        lock,
+        move |_, _, _, _| {
+            lock.last_input.set(Instant::now());
+            glib::Propagation::Proceed
+        }
+    ));
+    window.add_controller(key);
+    let motion = gtk::EventControllerMotion::new();
+    motion.set_propagation_phase(gtk::PropagationPhase::Capture);
+    motion.connect_motion(glib::clone!(
+        #[strong]
+        lock,
+        move |_, _, _| lock.last_input.set(Instant::now())
+    ));
+    window.add_controller(motion);
+    let click = gtk::GestureClick::new();
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    click.connect_pressed(glib::clone!(
+        #[strong]
+        lock,
+        move |_, _, _, _| lock.last_input.set(Instant::now())
+    ));
+    window.add_controller(click);
+
+    let lock_action = gio::SimpleAction::new("lock", None);
+    lock_action.connect_activate(glib::clone!(
+        #[strong]
+        lock,
+        move |_, _| lock.lock()
+    ));
+    window.add_action(&lock_action);
+    app.set_accels_for_action("win.lock", &["<Control>l"]);
+
+    if minutes > 0 {
+        let timeout = Duration::from_secs(minutes as u64 * 60);
+        glib::timeout_add_seconds_local(IDLE_CHECK_SECONDS, move || {
+            if lock.window.upgrade().is_none() {
+                return glib::ControlFlow::Break;
+            }
+            if lock.last_input.get().elapsed() >= timeout {
+                lock.lock();
+            }
+            glib::ControlFlow::Continue
+        });
+    }
+}
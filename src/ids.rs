@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Library;
+
+/// Hands out item and member IDs. The counters only go up and are saved with
+/// the library, so an ID is never given to a second record, even after the
+/// first one was deleted.
+#[derive(Clone, Default, Deserialize, Serialize)]
+pub struct IdAllocator {
+    // The next IDs to hand out; zero until first needed, e.g. in libraries
+    // saved before IDs were allocated
+    next_item: u32,
+    next_member: u32,
+}
+
+impl IdAllocator {
+    /// Keeps new item IDs clear of one that came from elsewhere, such as a
+    /// catalog CSV's ID column.
+    pub fn item_id_used(&mut self, id: u32) {
+        if self.next_item != 0 {
+            self.next_item = self.next_item.max(id + 1);
+        }
+    }
+}
+
+// The counter's next ID; a counter not started yet starts after the IDs in use
+fn peek(next: u32, highest_used: impl FnOnce() -> Option<u32>) -> u32 {
+    if next == 0 { highest_used().map_or(1, |id| id + 1) } else { next }
+}
+
+fn take(next: &mut u32, highest_used: impl FnOnce() -> Option<u32>) -> u32 {
+    let id = peek(*next, highest_used);
+    *next = id + 1;
+    id
+}
+
+impl Library {
+    pub(crate) fn next_item_id(&mut self) -> u32 {
+        let (items, trash) = (&self.items, &self.trash.items);
+        take(&mut self.ids.next_item, || items.keys().chain(trash.keys()).max().copied())
+    }
+
+    pub(crate) fn next_member_id(&mut self) -> u32 {
+        let (members, trash) = (&self.members, &self.trash.members);
+        take(&mut self.ids.next_member, || members.keys().chain(trash.keys()).max().copied())
+    }
+
+    // The ID `next_member_id` would hand out, without using it up
+    pub(crate) fn peek_member_id(&self) -> u32 {
+        peek(self.ids.next_member, || self.members.keys().chain(self.trash.members.keys()).max().copied())
+    }
+}
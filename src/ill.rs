@@ -0,0 +1,210 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::Library;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum IllDirection {
+    // Borrowing from another library for one of our members
+    Outgoing,
+    // Lending one of our items to another library
+    Incoming,
+}
+
+impl IllDirection {
+    pub fn label(self) -> String {
+        match self {
+            IllDirection::Outgoing => tr!("Borrowing"),
+            IllDirection::Incoming => tr!("Lending"),
+        }
+    }
+}
+
+/// Where an interlibrary loan stands. Borrowing goes Requested, Received,
+/// Returned; lending goes Requested, Shipped, Returned.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum IllStatus {
+    Requested,
+    Shipped,
+    Received,
+    Returned,
+    Cancelled,
+}
+
+impl IllStatus {
+    pub fn label(self) -> String {
+        match self {
+            IllStatus::Requested => tr!("Requested"),
+            IllStatus::Shipped => tr!("Shipped"),
+            IllStatus::Received => tr!("Received"),
+            IllStatus::Returned => tr!("Returned"),
+            IllStatus::Cancelled => tr!("Cancelled"),
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        matches!(self, IllStatus::Returned | IllStatus::Cancelled)
+    }
+}
+
+/// An interlibrary loan request, in either direction. The due date is
+/// always the lending library's: the one they give us when we borrow, and
+/// the one we set when we lend.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct IllRequest {
+    pub id: u32,
+    pub direction: IllDirection,
+    // The other library
+    pub partner: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub isbn: Option<String>,
+    // Our member the item is borrowed for
+    pub member_id: Option<u32>,
+    // Our item being lent
+    pub item_id: Option<u32>,
+    pub status: IllStatus,
+    pub due_date: Option<NaiveDate>,
+    pub note: String,
+    pub requested_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl IllRequest {
+    /// Out with the borrower past its due date.
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        matches!(self.status, IllStatus::Shipped | IllStatus::Received) && self.due_date.is_some_and(|due| due < today)
+    }
+}
+
+/// What the new request dialog asks for. Borrowing requests name a member
+/// and the title wanted; lending requests name our item.
+pub struct NewIllRequest {
+    pub direction: IllDirection,
+    pub partner: String,
+    pub title: String,
+    pub author: Option<String>,
+    pub isbn: Option<String>,
+    pub member_id: Option<u32>,
+    pub item_id: Option<u32>,
+    pub note: String,
+}
+
+impl Library {
+    pub fn request_ill(&mut self, new: NewIllRequest) -> Result<u32, String> {
+        self.only_at_server()?;
+        let partner = new.partner.trim();
+        if partner.is_empty() {
+            return Err(tr!("Enter the other library"));
+        }
+        let (title, author, isbn) = match new.direction {
+            IllDirection::Outgoing => {
+                let member_id = new.member_id.ok_or_else(|| tr!("Enter the member the item is for"))?;
+                if !self.members.contains_key(&member_id) {
+                    return Err(tr!("Member not found"));
+                }
+                if new.title.trim().is_empty() {
+                    return Err(tr!("Title cannot be empty"));
+                }
+                (new.title.trim().to_string(), new.author, new.isbn)
+            }
+            IllDirection::Incoming => {
+                let item_id = new.item_id.ok_or_else(|| tr!("Enter the item to lend"))?;
+                let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                (item.title.to_string(), item.author.clone(), item.isbn.clone())
+            }
+        };
+
+        let now = self.clock.now();
+        let id = self.ill.iter().map(|r| r.id).max().unwrap_or(0) + 1;
+        self.ill.push(IllRequest {
+            id,
+            direction: new.direction,
+            partner: partner.to_string(),
+            title,
+            author,
+            isbn,
+            member_id: new.member_id.filter(|_| new.direction == IllDirection::Outgoing),
+            item_id: new.item_id.filter(|_| new.direction == IllDirection::Incoming),
+            status: IllStatus::Requested,
+            due_date: None,
+            note: new.note.trim().to_string(),
+            requested_at: now,
+            updated_at: now,
+        });
+        tracing::info!(ill_id = id, direction = ?new.direction, partner, "Interlibrary loan requested");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Moves a request to its next status. Receiving a borrowed item and
+    /// shipping one we lend both need the due date; shipping takes a copy
+    /// off our shelf until it comes back.
+    pub fn advance_ill(&mut self, id: u32, due_date: Option<NaiveDate>) -> Result<IllStatus, String> {
+        self.only_at_server()?;
+        let now = self.clock.now();
+        let request = self.ill.iter().find(|r| r.id == id).ok_or_else(|| tr!("Interlibrary loan not found"))?;
+        let (direction, item_id) = (request.direction, request.item_id);
+        let status = match (direction, request.status) {
+            (IllDirection::Outgoing, IllStatus::Requested) => IllStatus::Received,
+            (IllDirection::Incoming, IllStatus::Requested) => IllStatus::Shipped,
+            (_, IllStatus::Received | IllStatus::Shipped) => IllStatus::Returned,
+            (_, IllStatus::Returned | IllStatus::Cancelled) => return Err(tr!("Interlibrary loan {} is already finished", id)),
+        };
+        if matches!(status, IllStatus::Received | IllStatus::Shipped) && due_date.is_none() {
+            return Err(tr!("Enter the due date"));
+        }
+
+        if direction == IllDirection::Incoming {
+            let item = item_id.and_then(|id| self.items.get_mut(&id)).ok_or_else(|| tr!("Item not found"))?;
+            match status {
+                IllStatus::Shipped if item.avail_copies == 0 => return Err(tr!("No copies available!")),
+                IllStatus::Shipped => item.avail_copies -= 1,
+                _ => item.avail_copies = (item.avail_copies + 1).min(item.copies),
+            }
+        }
+
+        let request = self.ill.iter_mut().find(|r| r.id == id).unwrap();
+        request.status = status;
+        if due_date.is_some() && status != IllStatus::Returned {
+            request.due_date = due_date;
+        }
+        request.updated_at = now;
+        tracing::info!(ill_id = id, "Interlibrary loan {:?}", status);
+        self.dirty = true;
+        if let Some(item_id) = item_id.filter(|_| direction == IllDirection::Incoming) {
+            self.item_changed(item_id);
+        }
+        Ok(status)
+    }
+
+    /// Changes the due date of an item out on interlibrary loan, for
+    /// renewals the lending library agreed to.
+    pub fn set_ill_due_date(&mut self, id: u32, due_date: NaiveDate) -> Result<(), String> {
+        self.only_at_server()?;
+        let now = self.clock.now();
+        let request = self.ill.iter_mut().find(|r| r.id == id).ok_or_else(|| tr!("Interlibrary loan not found"))?;
+        if !matches!(request.status, IllStatus::Shipped | IllStatus::Received) {
+            return Err(tr!("Only items out on loan have a due date"));
+        }
+        request.due_date = Some(due_date);
+        request.updated_at = now;
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn cancel_ill(&mut self, id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let now = self.clock.now();
+        let request = self.ill.iter_mut().find(|r| r.id == id).ok_or_else(|| tr!("Interlibrary loan not found"))?;
+        if request.status != IllStatus::Requested {
+            return Err(tr!("Only requests not yet filled can be cancelled"));
+        }
+        request.status = IllStatus::Cancelled;
+        request.updated_at = now;
+        tracing::info!(ill_id = id, "Interlibrary loan cancelled");
+        self.dirty = true;
+        Ok(())
+    }
+}
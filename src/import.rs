@@ -0,0 +1,313 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::i18n::tr;
+
+const MAPPINGS_PATH: &str = "import_mappings.json";
+
+// The LiItem fields an import can fill, with the (normalized) header names
+// they are detected from.
+pub const FIELDS: &[(&str, &[&str])] = &[
+    ("title", &["title", "name", "booktitle"]),
+    ("author", &["author", "authors", "creator", "writer"]),
+    ("year", &["year", "yearpublished", "publicationyear", "pubyear", "originalpublicationyear"]),
+    ("edition", &["edition", "ed"]),
+    ("desc", &["desc", "description", "summary", "notes"]),
+    ("format", &["format", "type", "mediatype", "material", "binding"]),
+    ("id", &["id", "itemid", "recordid", "barcode"]),
+    ("copies", &["copies", "totalcopies", "quantity", "qty"]),
+    ("avail_copies", &["availcopies", "available", "availablecopies"]),
+    ("ratings", &["ratings", "rating", "stars", "myrating"]),
+    ("isbn", &["isbn", "isbn13", "isbn10", "ean"]),
+    ("tags", &["tags", "genre", "genres", "subjects", "bookshelves"]),
+    ("branch", &["branch", "location", "homebranch"]),
+    ("call_number", &["callnumber", "callno", "classmark", "shelfmark", "classification"]),
+    ("shelf_location", &["shelflocation", "shelf", "collection", "section"]),
+];
+
+// The Member fields a member import can fill.
+pub const MEMBER_FIELDS: &[(&str, &[&str])] = &[
+    ("name", &["name", "fullname", "membername", "student", "studentname"]),
+    ("email", &["email", "emailaddress", "mail"]),
+    ("external_id", &["externalid", "studentid", "patronid", "cardnumber", "barcode"]),
+    ("membership", &["membership", "membershiptype", "patrontype", "category", "type"]),
+    ("expires_at", &["expiresat", "expires", "expiry", "expirydate"]),
+];
+
+/// What a CSV file is imported into.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    Items,
+    Members,
+}
+
+impl ImportKind {
+    pub fn fields(self) -> &'static [(&'static str, &'static [&'static str])] {
+        match self {
+            ImportKind::Items => FIELDS,
+            ImportKind::Members => MEMBER_FIELDS,
+        }
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header.chars().filter(|c| c.is_alphanumeric()).flat_map(|c| c.to_lowercase()).collect()
+}
+
+// Value used for a field that isn't mapped to any column.
+fn default_value<'a>(field: &str, record: &'a csv::ByteRecord) -> &'a [u8] {
+    match field {
+        "id" | "year" | "ratings" => b"0",
+        "copies" => b"1",
+        // Everything is on the shelf unless the file says otherwise
+        "avail_copies" => FIELDS
+            .iter()
+            .position(|(f, _)| *f == "copies")
+            .and_then(|i| record.get(i))
+            .unwrap_or(b"1"),
+        "format" => b"Book",
+        "branch" => b"Main",
+        _ => b"",
+    }
+}
+
+/// How the columns of a CSV file map onto `LiItem` or `Member` fields.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CsvSchema {
+    pub delimiter: u8,
+    pub quote: u8,
+    // Field name -> source column index. Left empty, the mapping is
+    // detected from the file's header.
+    pub columns: HashMap<String, usize>,
+}
+
+impl Default for CsvSchema {
+    fn default() -> Self {
+        CsvSchema {
+            delimiter: b',',
+            quote: b'"',
+            columns: HashMap::new(),
+        }
+    }
+}
+
+impl CsvSchema {
+    pub fn detect(kind: ImportKind, headers: &[String]) -> HashMap<String, usize> {
+        let mut columns = HashMap::new();
+        for (i, header) in headers.iter().enumerate() {
+            let header = normalize_header(header);
+            for (field, names) in kind.fields() {
+                if !columns.contains_key(*field) && names.contains(&header.as_str()) {
+                    columns.insert(field.to_string(), i);
+                    break;
+                }
+            }
+        }
+        columns
+    }
+
+    pub fn reader(&self, file: File) -> csv::Reader<File> {
+        csv::ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .quote(self.quote)
+            .from_reader(file)
+    }
+
+    pub fn field_headers(kind: ImportKind) -> csv::ByteRecord {
+        kind.fields().iter().map(|(field, _)| *field).collect()
+    }
+
+    /// Rearranges a source record into `field_headers` order.
+    pub fn remap(&self, kind: ImportKind, record: &csv::ByteRecord) -> csv::ByteRecord {
+        let mut out = csv::ByteRecord::new();
+        for (field, _) in kind.fields() {
+            match self.columns.get(*field).and_then(|&i| record.get(i)) {
+                Some(value) => out.push_field(value),
+                None => {
+                    let value = default_value(field, &out).to_vec();
+                    out.push_field(&value);
+                }
+            }
+        }
+        out.set_position(record.position().cloned());
+        out
+    }
+}
+
+/// Reads the header and the first `rows` records of a file for previewing.
+pub fn preview(path: &Path, schema: &CsvSchema, rows: usize) -> Result<(Vec<String>, Vec<Vec<String>>), Box<dyn std::error::Error>> {
+    let mut rdr = schema.reader(File::open(path)?);
+    let headers = rdr.headers()?.iter().map(String::from).collect();
+    let mut records = Vec::new();
+    for record in rdr.records().take(rows) {
+        records.push(record?.iter().map(String::from).collect());
+    }
+    Ok((headers, records))
+}
+
+#[derive(Serialize, Deserialize)]
+struct SavedMapping {
+    pattern: String,
+    schema: CsvSchema,
+}
+
+fn load_mappings() -> Vec<SavedMapping> {
+    File::open(MAPPINGS_PATH)
+        .ok()
+        .and_then(|file| serde_json::from_reader(file).ok())
+        .unwrap_or_default()
+}
+
+// Matches a file name against a pattern where `*` stands for any run of
+// characters.
+fn glob_match(pattern: &str, name: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == name,
+        Some((prefix, rest)) => {
+            let Some(name) = name.strip_prefix(prefix) else { return false };
+            (0..=name.len())
+                .filter(|&i| name.is_char_boundary(i))
+                .any(|i| glob_match(rest, &name[i..]))
+        }
+    }
+}
+
+/// Suggests a pattern for remembering a mapping: digit runs (dates, batch
+/// numbers) in the file name become wildcards.
+pub fn default_pattern(file_name: &str) -> String {
+    let mut pattern = String::new();
+    for c in file_name.chars() {
+        if c.is_ascii_digit() {
+            if !pattern.ends_with('*') {
+                pattern.push('*');
+            }
+        } else {
+            pattern.push(c);
+        }
+    }
+    pattern
+}
+
+pub fn saved_schema(file_name: &str) -> Option<CsvSchema> {
+    load_mappings()
+        .into_iter()
+        .find(|m| glob_match(&m.pattern, file_name))
+        .map(|m| m.schema)
+}
+
+pub fn remember_schema(pattern: &str, schema: &CsvSchema) -> Result<(), Box<dyn std::error::Error>> {
+    let mut mappings = load_mappings();
+    mappings.retain(|m| m.pattern != pattern);
+    mappings.insert(0, SavedMapping {
+        pattern: pattern.to_string(),
+        schema: schema.clone(),
+    });
+    serde_json::to_writer_pretty(File::create(MAPPINGS_PATH)?, &mappings)?;
+    Ok(())
+}
+
+// A JSON value as the text of one CSV cell
+fn json_cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        Value::Array(values) => values.iter().map(json_cell).collect::<Vec<_>>().join("; "),
+        other => other.to_string(),
+    }
+}
+
+/// Writes a JSON file holding a list of flat records out as a CSV file in
+/// the temporary directory, for the import wizard to read. The records'
+/// keys become the columns; a list becomes one cell with its values
+/// separated by semicolons, like tags in a CSV file.
+pub fn json_to_csv(path: &Path) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let value: Value = serde_json::from_reader(BufReader::new(File::open(path)?))?;
+    // A bare list, or a list inside an object such as {"items": [...]}
+    let rows = match &value {
+        Value::Array(rows) => Some(rows),
+        Value::Object(object) => object.values().find_map(Value::as_array),
+        _ => None,
+    };
+    let rows = rows.ok_or_else(|| tr!("The JSON file has no list of records"))?;
+
+    let mut headers: Vec<&String> = Vec::new();
+    for row in rows {
+        let record = row.as_object().ok_or_else(|| tr!("The JSON file has no list of records"))?;
+        for key in record.keys() {
+            if !headers.contains(&key) {
+                headers.push(key);
+            }
+        }
+    }
+
+    let stem = path.file_stem().map_or_else(|| "import".into(), |s| s.to_string_lossy());
+    let csv_path = std::env::temp_dir().join(format!("{}.csv", stem));
+    let mut wtr = csv::Writer::from_path(&csv_path)?;
+    wtr.write_record(&headers)?;
+    for row in rows {
+        wtr.write_record(headers.iter().map(|h| row.get(h.as_str()).map(json_cell).unwrap_or_default()))?;
+    }
+    wtr.flush()?;
+    Ok(csv_path)
+}
+
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+    // The raw fields of the offending record, empty when the record itself
+    // could not be read.
+    pub fields: Vec<String>,
+}
+
+pub struct ImportReport {
+    pub loaded: usize,
+    pub skipped: usize,
+    pub headers: Vec<String>,
+    pub errors: Vec<RowError>,
+}
+
+impl ImportReport {
+    pub fn new(headers: &csv::ByteRecord) -> ImportReport {
+        ImportReport {
+            loaded: 0,
+            skipped: 0,
+            headers: headers.iter().map(|h| String::from_utf8_lossy(h).into_owned()).collect(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn add_error(&mut self, line: u64, error: &csv::Error, record: Option<&csv::ByteRecord>) {
+        self.add_problem(line, error.to_string(), record);
+    }
+
+    // A row that was read fine but rejected, e.g. as a duplicate.
+    pub fn add_problem(&mut self, line: u64, message: String, record: Option<&csv::ByteRecord>) {
+        self.skipped += 1;
+        self.errors.push(RowError {
+            line,
+            message,
+            fields: record.map_or(Vec::new(), |r| {
+                r.iter().map(|f| String::from_utf8_lossy(f).into_owned()).collect()
+            }),
+        });
+    }
+
+    /// Writes the header and every rejected row to `path` so the rows can be
+    /// fixed up and imported again.
+    pub fn export_bad_rows(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::WriterBuilder::new().flexible(true).from_path(path)?;
+        wtr.write_record(&self.headers)?;
+        for error in &self.errors {
+            if !error.fields.is_empty() {
+                wtr.write_record(&error.fields)?;
+            }
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, HashSet};
+
+use crate::i18n::tr;
+use crate::transfers::TransferStatus;
+use crate::{Library, LiItemInstance};
+
+/// An inconsistency in the library data, as found by `verify_integrity`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Problem {
+    // The catalog stores the item under a different ID than its own
+    ItemIdMismatch { key: u32, id: u32 },
+    MemberIdMismatch { key: u32, id: u32 },
+    LoanIdMismatch { member_id: u32, key: u32, id: u32 },
+    LoanOfMissingItem { member_id: u32, item_id: u32 },
+    // More loans than the item has copies
+    OverLoaned { item_id: u32, on_loan: u32, copies: u32 },
+    // The available count doesn't equal copies minus loans, lost copies
+    // and copies set aside, e.g. on the hold shelf
+    AvailableCopies { item_id: u32, recorded: u32, expected: u32 },
+    DuplicateFineId { member_id: u32, fine_id: u32 },
+    DuplicateTransferId { transfer_id: u32 },
+    TransferOfMissingItem { transfer_id: u32, item_id: u32 },
+}
+
+impl Problem {
+    pub fn describe(&self) -> String {
+        match *self {
+            Problem::ItemIdMismatch { key, id } => tr!("Item {} is stored under ID {}", id, key),
+            Problem::MemberIdMismatch { key, id } => tr!("Member {} is stored under ID {}", id, key),
+            Problem::LoanIdMismatch { member_id, key, id } => {
+                tr!("Member {} has a loan of item {} stored under item {}", member_id, id, key)
+            }
+            Problem::LoanOfMissingItem { member_id, item_id } => {
+                tr!("Member {} has a loan of item {}, which doesn't exist", member_id, item_id)
+            }
+            Problem::OverLoaned { item_id, on_loan, copies } => {
+                tr!("Item {} has {} loans but only {} copies", item_id, on_loan, copies)
+            }
+            Problem::AvailableCopies { item_id, recorded, expected } if recorded > expected => {
+                tr!("Item {} shows {} copies available, but only {} are on the shelf", item_id, recorded, expected)
+            }
+            Problem::AvailableCopies { item_id, recorded, expected } => {
+                tr!("Item {} shows {} copies available, but {} are on the shelf", item_id, recorded, expected)
+            }
+            Problem::DuplicateFineId { member_id, fine_id } => {
+                tr!("Member {} has several fines with ID {}", member_id, fine_id)
+            }
+            Problem::DuplicateTransferId { transfer_id } => tr!("Several transfers have ID {}", transfer_id),
+            Problem::TransferOfMissingItem { transfer_id, item_id } => {
+                tr!("Transfer {} is for item {}, which doesn't exist", transfer_id, item_id)
+            }
+        }
+    }
+
+    /// Whether `Library::repair` can fix this without guessing at what the
+    /// data should have been. The rest need a person to look at them.
+    pub fn fixable(&self) -> bool {
+        !matches!(self, Problem::OverLoaned { .. } | Problem::TransferOfMissingItem { .. })
+    }
+}
+
+impl Library {
+    // How many copies of each item are on loan
+    fn loans_per_item(&self) -> BTreeMap<u32, u32> {
+        let mut on_loan = BTreeMap::new();
+        for member in self.members.values() {
+            for &item_id in member.items.keys() {
+                *on_loan.entry(item_id).or_insert(0) += 1;
+            }
+        }
+        on_loan
+    }
+
+    /// Looks for data that contradicts itself. Problems are listed by kind,
+    /// then by ID.
+    pub fn verify_integrity(&self) -> Vec<Problem> {
+        let mut problems = Vec::new();
+
+        let mut item_ids: Vec<u32> = self.items.keys().copied().collect();
+        item_ids.sort_unstable();
+        let mut member_ids: Vec<u32> = self.members.keys().copied().collect();
+        member_ids.sort_unstable();
+
+        for &key in &item_ids {
+            let id = self.items[&key].id;
+            if id != key {
+                problems.push(Problem::ItemIdMismatch { key, id });
+            }
+        }
+        for &key in &member_ids {
+            let id = self.members[&key].id;
+            if id != key {
+                problems.push(Problem::MemberIdMismatch { key, id });
+            }
+        }
+
+        for &member_id in &member_ids {
+            let mut loans: Vec<(&u32, &LiItemInstance)> = self.members[&member_id].items.iter().collect();
+            loans.sort_unstable_by_key(|(key, _)| **key);
+            for (&key, inst) in loans {
+                if inst.id != key {
+                    problems.push(Problem::LoanIdMismatch { member_id, key, id: inst.id });
+                }
+                if !self.items.contains_key(&key) {
+                    problems.push(Problem::LoanOfMissingItem { member_id, item_id: key });
+                }
+            }
+        }
+
+        let on_loan = self.loans_per_item();
+        for &item_id in &item_ids {
+            let item = &self.items[&item_id];
+            let loans = on_loan.get(&item_id).copied().unwrap_or(0);
+            if loans > item.copies {
+                problems.push(Problem::OverLoaned { item_id, on_loan: loans, copies: item.copies });
+            } else if item.avail_copies != item.copies.saturating_sub(loans + item.lost_copies + item.set_aside()) {
+                problems.push(Problem::AvailableCopies {
+                    item_id,
+                    recorded: item.avail_copies,
+                    expected: item.copies.saturating_sub(loans + item.lost_copies + item.set_aside()),
+                });
+            }
+        }
+
+        for &member_id in &member_ids {
+            let (mut seen, mut reported) = (HashSet::new(), HashSet::new());
+            for fine in &self.members[&member_id].fines {
+                if !seen.insert(fine.id) && reported.insert(fine.id) {
+                    problems.push(Problem::DuplicateFineId { member_id, fine_id: fine.id });
+                }
+            }
+        }
+
+        let (mut seen, mut reported) = (HashSet::new(), HashSet::new());
+        for transfer in &self.transfers {
+            if !seen.insert(transfer.id) && reported.insert(transfer.id) {
+                problems.push(Problem::DuplicateTransferId { transfer_id: transfer.id });
+            }
+        }
+        for transfer in &self.transfers {
+            if transfer.status != TransferStatus::Received && !self.items.contains_key(&transfer.item_id) {
+                problems.push(Problem::TransferOfMissingItem {
+                    transfer_id: transfer.id,
+                    item_id: transfer.item_id,
+                });
+            }
+        }
+
+        problems
+    }
+
+    /// Fixes a problem found by `verify_integrity`. The map key wins over
+    /// the ID stored in the record, as that is what loans and lookups go
+    /// by; duplicate fine and transfer IDs are renumbered after the highest.
+    pub fn repair(&mut self, problem: &Problem) -> Result<(), String> {
+        self.only_at_server()?;
+        match *problem {
+            Problem::ItemIdMismatch { key, .. } => {
+                let item = self.items.get_mut(&key).ok_or_else(|| tr!("Item not found"))?;
+                item.id = key;
+            }
+            Problem::MemberIdMismatch { key, .. } => {
+                let member = self.members.get_mut(&key).ok_or_else(|| tr!("Member not found"))?;
+                member.id = key;
+            }
+            Problem::LoanIdMismatch { member_id, key, .. } => {
+                let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+                let inst = member.items.get_mut(&key).ok_or_else(|| tr!("This book was not checked out by this member"))?;
+                inst.id = key;
+            }
+            Problem::LoanOfMissingItem { member_id, item_id } => {
+                if self.items.contains_key(&item_id) {
+                    return Err(tr!("The item exists again"));
+                }
+                let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+                member.items.remove(&item_id);
+            }
+            Problem::AvailableCopies { item_id, .. } => {
+                let loans = self.loans_per_item().get(&item_id).copied().unwrap_or(0);
+                let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+                if loans > item.copies {
+                    return Err(tr!("Item {} has {} loans but only {} copies", item_id, loans, item.copies));
+                }
+                item.avail_copies = item.copies.saturating_sub(loans + item.lost_copies + item.set_aside());
+            }
+            Problem::DuplicateFineId { member_id, fine_id } => {
+                let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+                let mut next_id = member.fines.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+                // The first fine keeps the ID
+                for fine in member.fines.iter_mut().filter(|f| f.id == fine_id).skip(1) {
+                    fine.id = next_id;
+                    next_id += 1;
+                }
+            }
+            Problem::DuplicateTransferId { transfer_id } => {
+                let mut next_id = self.transfers.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+                for transfer in self.transfers.iter_mut().filter(|t| t.id == transfer_id).skip(1) {
+                    transfer.id = next_id;
+                    next_id += 1;
+                }
+            }
+            Problem::OverLoaned { .. } | Problem::TransferOfMissingItem { .. } => {
+                return Err(tr!("This problem has to be fixed by hand"));
+            }
+        }
+        tracing::info!("Repaired: {}", problem.describe());
+        self.dirty = true;
+        self.reloaded();
+        Ok(())
+    }
+
+    /// Repairs every fixable problem. Returns how many were fixed.
+    pub fn repair_all(&mut self) -> usize {
+        self.verify_integrity()
+            .iter()
+            .filter(|p| p.fixable())
+            .filter(|p| self.repair(p).is_ok())
+            .count()
+    }
+}
@@ -0,0 +1,282 @@
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use gtk::prelude::*;
+use gtk::{glib, Application, ApplicationWindow, Box as GtkBox, Button, Entry, Label, ScrolledWindow};
+
+use crate::bus::EventBus;
+use crate::callnumber;
+use crate::challenge::LeaderboardEntry;
+use crate::i18n::tr;
+use crate::suggestions::{self, NewSuggestion};
+use crate::table::Table;
+use crate::{create_catalog_page, Library, LiItem, LiItemInstance};
+
+// Seconds before a patron's loans are cleared from the screen
+const LOANS_DISPLAY_SECONDS: u32 = 60;
+
+/// The patron-facing window started with `--kiosk`: catalog search, a
+/// patron's own loans and purchase suggestions, with no way to change
+/// anything else or leave fullscreen.
+pub fn build_kiosk_window(app: &Application, library: Arc<RwLock<Library>>) {
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title(tr!("Library Catalog"))
+        .build();
+
+    let main_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    main_box.set_margin_top(10);
+    main_box.set_margin_bottom(10);
+    main_box.set_margin_start(10);
+    main_box.set_margin_end(10);
+
+    // The catalog page follows changes through a bus, though the kiosk
+    // makes none itself
+    let bus = EventBus::start(library.clone());
+    let notebook = gtk::Notebook::new();
+    notebook.append_page(
+        &create_catalog_page(library.clone(), &bus, None),
+        Some(&Label::new(Some(&tr!("Search Catalog")))),
+    );
+    notebook.append_page(
+        &create_shelf_page(library.clone(), Library::new_arrivals),
+        Some(&Label::new(Some(&tr!("New Arrivals")))),
+    );
+    notebook.append_page(
+        &create_shelf_page(library.clone(), Library::just_returned),
+        Some(&Label::new(Some(&tr!("Just Returned")))),
+    );
+    notebook.append_page(
+        &create_patron_loans_page(library.clone()),
+        Some(&Label::new(Some(&tr!("My Loans")))),
+    );
+    notebook.append_page(
+        &create_suggestion_page(library.clone()),
+        Some(&Label::new(Some(&tr!("Suggest a Purchase")))),
+    );
+    notebook.append_page(
+        &create_leaderboard_page(library),
+        Some(&Label::new(Some(&tr!("Reading Challenge")))),
+    );
+
+    main_box.append(&notebook);
+    window.set_child(Some(&main_box));
+
+    window.connect_close_request(|_| glib::Propagation::Stop);
+    window.connect_fullscreened_notify(|window| {
+        if !window.is_fullscreen() {
+            window.fullscreen();
+        }
+    });
+    window.fullscreen();
+    window.show();
+}
+
+fn create_patron_loans_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let loans_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let member_id_label = Label::new(Some(&tr!("Member ID:")));
+    let member_id_entry = Entry::new();
+    let show_button = Button::with_label(&tr!("Show My Loans"));
+    let status_label = Label::new(None);
+
+    let table = Rc::new(Table::<LiItemInstance>::new());
+    table.add_column(&tr!("Title"), |i| i.title.to_string(), |a, b| a.title.cmp(&b.title));
+    table.add_column(&tr!("Due Date"), |i| i.due_text(), |a, b| a.due_date.cmp(&b.due_date));
+
+    let show_loans = glib::clone!(
+        #[weak]
+        member_id_entry,
+        #[weak]
+        status_label,
+        #[strong]
+        table,
+        move || {
+            let lib = library.read().unwrap();
+            let member = member_id_entry
+                .text()
+                .trim()
+                .parse::<u32>()
+                .ok()
+                .and_then(|id| lib.members.get(&id));
+            member_id_entry.set_text("");
+            let Some(member) = member else {
+                status_label.set_text(&tr!("Member not found"));
+                table.set_rows(Vec::new());
+                return;
+            };
+
+            let mut status = tr!("{} item(s) on loan", member.items.len());
+            if let Some(challenge) = &member.challenge {
+                status.push_str(&format!("\n{}", tr!("Reading challenge: {}", challenge.progress_text())));
+            }
+            status_label.set_text(&status);
+            table.set_rows(member.items.values().cloned().collect::<Vec<_>>());
+
+            // Don't leave a patron's loans on a public screen
+            glib::timeout_add_seconds_local_once(LOANS_DISPLAY_SECONDS, glib::clone!(
+                #[weak]
+                status_label,
+                #[strong]
+                table,
+                move || {
+                    status_label.set_text("");
+                    table.set_rows(Vec::new());
+                }
+            ));
+        }
+    );
+    let show_loans = Rc::new(show_loans);
+
+    show_button.connect_clicked(glib::clone!(
+        #[strong]
+        show_loans,
+        move |_| show_loans()
+    ));
+    member_id_entry.connect_activate(move |_| show_loans());
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    loans_box.append(&member_id_label);
+    loans_box.append(&member_id_entry);
+    loans_box.append(&show_button);
+    loans_box.append(&status_label);
+    loans_box.append(&scrolled_window);
+
+    loans_box
+}
+
+// A form for asking the library to buy a title. Suggestions go to the
+// inbox file, for the staff desk to take in.
+fn create_suggestion_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let suggestion_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let title_entry = Entry::new();
+    let author_entry = Entry::new();
+    let notes_entry = Entry::new();
+    notes_entry.set_placeholder_text(Some(&tr!("E.g. the edition, or why it would be a good fit")));
+    let member_id_entry = Entry::new();
+    member_id_entry.set_placeholder_text(Some(&tr!("Optional, to hear back about it")));
+    let submit_button = Button::with_label(&tr!("Send Suggestion"));
+    let status_label = Label::new(None);
+
+    submit_button.connect_clicked(glib::clone!(
+        #[weak]
+        title_entry,
+        #[weak]
+        author_entry,
+        #[weak]
+        notes_entry,
+        #[weak]
+        member_id_entry,
+        #[weak]
+        status_label,
+        move |_| {
+            let member_id = match member_id_entry.text().trim() {
+                "" => None,
+                text => match text.parse::<u32>().ok().filter(|id| library.read().unwrap().members.contains_key(id)) {
+                    Some(id) => Some(id),
+                    None => {
+                        status_label.set_text(&tr!("Member not found"));
+                        return;
+                    }
+                },
+            };
+            let author = author_entry.text().trim().to_string();
+            let suggestion = NewSuggestion {
+                title: title_entry.text().trim().to_string(),
+                author: Some(author).filter(|a| !a.is_empty()),
+                notes: notes_entry.text().trim().to_string(),
+                member_id,
+                at: library.read().unwrap().clock.now(),
+            };
+            match suggestions::submit(Path::new(suggestions::INBOX_PATH), &suggestion) {
+                Ok(()) => {
+                    status_label.set_text(&tr!("Thank you! Staff will look at your suggestion."));
+                    for entry in [&title_entry, &author_entry, &notes_entry, &member_id_entry] {
+                        entry.set_text("");
+                    }
+                }
+                Err(e) => status_label.set_text(&e.to_string()),
+            }
+        }
+    ));
+
+    let grid = gtk::Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+    let rows: [(String, &Entry); 4] = [
+        (tr!("Title:"), &title_entry),
+        (tr!("Author:"), &author_entry),
+        (tr!("Notes:"), &notes_entry),
+        (tr!("Member ID:"), &member_id_entry),
+    ];
+    for (row, (name, entry)) in rows.iter().enumerate() {
+        let label = Label::new(Some(name.as_str()));
+        label.set_xalign(0.0);
+        entry.set_hexpand(true);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(*entry, 1, row as i32, 1, 1);
+    }
+
+    suggestion_box.append(&Label::new(Some(&tr!("Can't find what you're looking for? Ask us to buy it."))));
+    suggestion_box.append(&grid);
+    suggestion_box.append(&submit_button);
+    suggestion_box.append(&status_label);
+
+    suggestion_box
+}
+
+// A read-only list of items, refilled from `items` every time the page is
+// shown.
+fn create_shelf_page(library: Arc<RwLock<Library>>, items: fn(&Library) -> Vec<LiItem>) -> GtkBox {
+    let shelf_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let table = Rc::new(Table::<LiItem>::new());
+    table.add_column(&tr!("Title"), |i| i.title.to_string(), |a, b| a.title.cmp(&b.title));
+    table.add_column(&tr!("Author"), |i| i.author.clone().unwrap_or_else(|| "Unknown".to_string()), |a, b| a.author.cmp(&b.author));
+    table.add_column(&tr!("Format"), |i| i.format.clone(), |a, b| a.format.cmp(&b.format));
+    table.add_column(&tr!("Available Copies"), |i| i.avail_copies.to_string(), |a, b| a.avail_copies.cmp(&b.avail_copies));
+    table.add_column(&tr!("Shelf Location"), |i| i.shelf_location.clone(), |a, b| a.shelf_location.cmp(&b.shelf_location));
+    table.add_column(&tr!("Call Number"), |i| i.call_number.clone(), |a, b| callnumber::compare(&a.call_number, &b.call_number));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+    shelf_box.append(&scrolled_window);
+
+    shelf_box.connect_map(move |_| {
+        let rows = items(&library.read().unwrap());
+        table.set_rows(rows);
+    });
+
+    shelf_box
+}
+
+// This year's reading challenge leaders, for members who agreed to be
+// listed
+fn create_leaderboard_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let leaderboard_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let table = Rc::new(Table::<LeaderboardEntry>::new());
+    table.add_column(&tr!("Rank"), |e| e.rank.to_string(), |a, b| a.rank.cmp(&b.rank));
+    table.add_column(&tr!("Name"), |e| e.first_name.clone(), |a, b| a.first_name.cmp(&b.first_name));
+    table.add_column(&tr!("Books Read"), |e| e.completed.to_string(), |a, b| a.completed.cmp(&b.completed));
+    table.add_column(&tr!("Goal"), |e| e.goal.to_string(), |a, b| a.goal.cmp(&b.goal));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+    leaderboard_box.append(&Label::new(Some(&tr!("Ask at the desk to join this year's reading challenge."))));
+    leaderboard_box.append(&scrolled_window);
+
+    leaderboard_box.connect_map(move |_| {
+        let rows = library.read().unwrap().leaderboard();
+        table.set_rows(rows);
+    });
+
+    leaderboard_box
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, Utc};
+
+use crate::i18n::tr;
+use crate::{Library, LiItem, LiItemInstance};
+
+/// Loans due within this many days count as due soon.
+pub const DUE_SOON_DAYS: i64 = 3;
+
+/// A loan that is overdue or due soon, for the Overdue page.
+#[derive(Clone)]
+pub struct DueLoan {
+    pub loan: LiItemInstance,
+    pub member_id: u32,
+    pub member_name: String,
+    pub overdue: bool,
+}
+
+/// Formats lent by the hour out of the box, with their loan length in
+/// hours.
+pub fn default_hourly_formats() -> BTreeMap<String, u32> {
+    BTreeMap::from([
+        ("laptop".to_string(), 4),
+        ("hotspot".to_string(), 72),
+        ("room key".to_string(), 2),
+    ])
+}
+
+/// Reads "laptop=4, hotspot=72" as format and hours pairs. Formats are
+/// matched ignoring case.
+pub fn parse_hourly_formats(text: &str) -> Result<BTreeMap<String, u32>, String> {
+    let mut formats = BTreeMap::new();
+    for entry in text.split([',', ';']).map(str::trim).filter(|e| !e.is_empty()) {
+        let (format, hours) = entry
+            .split_once('=')
+            .ok_or_else(|| tr!("\"{}\" should look like laptop=4", entry))?;
+        let hours: u32 = hours
+            .trim()
+            .parse()
+            .ok()
+            .filter(|&h| h > 0)
+            .ok_or_else(|| tr!("\"{}\" is not a number of hours", hours.trim()))?;
+        formats.insert(format.trim().to_lowercase(), hours);
+    }
+    Ok(formats)
+}
+
+pub fn format_hourly_formats(formats: &BTreeMap<String, u32>) -> String {
+    formats.iter().map(|(format, hours)| format!("{}={}", format, hours)).collect::<Vec<_>>().join(", ")
+}
+
+impl LiItemInstance {
+    pub fn is_hourly(&self) -> bool {
+        self.loan_hours > 0
+    }
+
+    /// The due date for display, with the time for hourly loans.
+    pub fn due_text(&self) -> String {
+        if self.is_hourly() {
+            self.due_date.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string()
+        } else {
+            self.due_date.format("%Y-%m-%d").to_string()
+        }
+    }
+
+    /// Whole days late at `now`, counting any part of a day. Only for
+    /// hourly loans; the rest are counted in open days.
+    pub(crate) fn days_late_hourly(&self, now: DateTime<Utc>) -> i64 {
+        let minutes = (now - self.due_date).num_minutes();
+        if minutes <= 0 { 0 } else { (minutes + 24 * 60 - 1) / (24 * 60) }
+    }
+}
+
+impl Library {
+    /// Loans past their due date or due within `days`, earliest due first.
+    pub fn due_loans(&self, days: i64) -> Vec<DueLoan> {
+        let now = self.clock.now();
+        let soon = now + Duration::days(days);
+        let mut loans: Vec<DueLoan> = self
+            .members
+            .values()
+            .flat_map(|m| m.items.values().map(move |inst| (m, inst)))
+            .filter(|(_, inst)| inst.due_date <= soon)
+            .map(|(m, inst)| DueLoan { loan: inst.clone(), member_id: m.id, member_name: m.name.clone(), overdue: inst.due_date < now })
+            .collect();
+        loans.sort_by_key(|l| l.loan.due_date);
+        loans
+    }
+
+    /// Hours a copy of `item` is lent for, if its format is lent by the
+    /// hour.
+    pub fn hourly_loan(&self, item: &LiItem) -> Option<u32> {
+        self.hourly_formats.get(&item.format.to_lowercase()).copied()
+    }
+
+    // Sets the due date of a new loan for course reserves and formats lent
+    // by the hour, which don't follow the membership's loan days
+    pub(crate) fn apply_loan_rules(&self, inst: &mut LiItemInstance, now: DateTime<Utc>) {
+        if let Some(reserve) = self.reserve(inst.id) {
+            if let Some(due_date) = self.reserve_due(inst.id, now) {
+                inst.due_date = due_date;
+            }
+            inst.loan_hours = reserve.loan.hours().unwrap_or(0);
+        } else if let Some(hours) = self.items.get(&inst.id).and_then(|item| self.hourly_loan(item)) {
+            inst.loan_hours = hours;
+            inst.due_date = now + Duration::hours(hours as i64);
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tracing::Level;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, EnvFilter};
+
+// One file per day, as logs/rustLMS.<date>.log
+const LOG_DIR: &str = "logs";
+const PREFIX: &str = "rustLMS";
+const SUFFIX: &str = "log";
+// Daily files kept before the oldest is deleted
+const KEEP_FILES: usize = 14;
+
+/// A line of the log file as shown on the Logs page.
+#[derive(Clone)]
+pub struct LogEntry {
+    pub time: String,
+    pub level: Level,
+    pub target: String,
+    // The event's message followed by its other fields as key=value
+    pub message: String,
+}
+
+// The JSON that tracing-subscriber writes for each event
+#[derive(Deserialize)]
+struct RawEntry {
+    timestamp: String,
+    level: String,
+    target: String,
+    #[serde(default)]
+    fields: Map<String, Value>,
+}
+
+/// Sends events to a rotating JSON log file and a readable copy to stderr.
+/// The level defaults to info and can be changed with `RUST_LOG`. Events
+/// still queued for the file are written when the returned guard drops, so
+/// it has to live until the end of main.
+pub fn init() -> Option<WorkerGuard> {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let stderr_layer = fmt::layer().with_writer(std::io::stderr);
+
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(PREFIX)
+        .filename_suffix(SUFFIX)
+        .max_log_files(KEEP_FILES)
+        .build(LOG_DIR);
+    match appender {
+        Ok(appender) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let file_layer = fmt::layer().json().with_writer(writer);
+            tracing_subscriber::registry().with(filter).with(stderr_layer).with(file_layer).init();
+            Some(guard)
+        }
+        Err(e) => {
+            tracing_subscriber::registry().with(filter).with(stderr_layer).init();
+            tracing::warn!("Logging to stderr only, the log file can't be opened: {}", e);
+            None
+        }
+    }
+}
+
+// The file being written to now. The dated names sort chronologically.
+fn current_file() -> Option<PathBuf> {
+    fs::read_dir(LOG_DIR)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(PREFIX) && n.ends_with(SUFFIX))
+        })
+        .max()
+}
+
+/// The last `limit` entries of the current log file at `level` or more
+/// severe, newest first. Lines that don't parse are skipped.
+pub fn recent(limit: usize, level: Level) -> Vec<LogEntry> {
+    let Some(path) = current_file() else { return Vec::new() };
+    read_entries(&path)
+        .into_iter()
+        .rev()
+        .filter(|entry| entry.level <= level)
+        .take(limit)
+        .collect()
+}
+
+fn read_entries(path: &Path) -> Vec<LogEntry> {
+    let Ok(text) = fs::read_to_string(path) else { return Vec::new() };
+    text.lines().filter_map(parse_line).collect()
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let mut raw: RawEntry = serde_json::from_str(line).ok()?;
+    let time = DateTime::parse_from_rfc3339(&raw.timestamp)
+        .map(|t| t.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or(raw.timestamp);
+
+    let mut message = match raw.fields.remove("message") {
+        Some(Value::String(text)) => text,
+        _ => String::new(),
+    };
+    for (key, value) in raw.fields {
+        let value = match value {
+            Value::String(text) => text,
+            other => other.to_string(),
+        };
+        message.push_str(&format!(" {}={}", key, value));
+    }
+
+    Some(LogEntry {
+        time,
+        level: raw.level.parse().ok()?,
+        target: raw.target,
+        message,
+    })
+}
@@ -0,0 +1,55 @@
+use lettre::message::header::ContentType;
+use lettre::message::{Attachment, Mailbox, MultiPart, SinglePart};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+use crate::config::Config;
+use crate::i18n::tr;
+
+/// A file sent along with an email.
+pub struct MailAttachment {
+    pub file_name: String,
+    pub content_type: &'static str,
+    pub data: Vec<u8>,
+}
+
+/// Whether enough is configured to send mail at all.
+pub fn is_configured(config: &Config) -> bool {
+    !config.smtp_host.trim().is_empty() && !config.mail_from.trim().is_empty()
+}
+
+/// Sends a plain text email through the configured SMTP server, using
+/// STARTTLS. This blocks until the server has accepted the message, so call
+/// it off the main thread.
+pub fn send(
+    config: &Config,
+    to: &[String],
+    subject: &str,
+    body: &str,
+    attachment: Option<MailAttachment>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if !is_configured(config) {
+        return Err(tr!("No mail server is configured").into());
+    }
+    if to.is_empty() {
+        return Err(tr!("No recipients").into());
+    }
+
+    let mut builder = Message::builder().from(config.mail_from.trim().parse::<Mailbox>()?).subject(subject);
+    for address in to {
+        builder = builder.to(address.trim().parse::<Mailbox>()?);
+    }
+    let mut parts = MultiPart::mixed().singlepart(SinglePart::plain(body.to_string()));
+    if let Some(attachment) = attachment {
+        let content_type = ContentType::parse(attachment.content_type)?;
+        parts = parts.singlepart(Attachment::new(attachment.file_name).body(attachment.data, content_type));
+    }
+    let message = builder.multipart(parts)?;
+
+    let mut transport = SmtpTransport::starttls_relay(config.smtp_host.trim())?.port(config.smtp_port);
+    if !config.smtp_username.is_empty() {
+        transport = transport.credentials(Credentials::new(config.smtp_username.clone(), config.smtp_password.clone()));
+    }
+    transport.build().send(&message)?;
+    Ok(())
+}
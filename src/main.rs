@@ -1,37 +1,181 @@
 use glib::clone::Downgrade;
 use glib::property::PropertyGet;
 use gtk::prelude::*;
-use gtk::{Application, ApplicationWindow, Box as GtkBox, Button, Entry, Label, ListStore, ScrolledWindow, TreeView, TreeViewColumn, CellRendererText};
+use gtk::gio;
+use gtk::{ApplicationWindow, Box as GtkBox, Button, Entry, EntryCompletion, Label, ListStore, ScrolledWindow};
 use std::ops::Deref;
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::fs::File;
-use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::{Arc, Mutex, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use csv;
-use chrono::{self, DateTime, Months, Utc};
+use chrono::{self, DateTime, Datelike, Months, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
+use rayon::prelude::*;
 
+mod acquisitions;
+mod auth;
+mod backup;
+mod batch;
+mod bench;
+mod calendar;
+mod callnumber;
+mod cash;
+mod challenge;
+mod changes;
+mod bus;
+mod charts;
+mod cli;
+mod clock;
+mod condition;
+mod config;
+mod copies;
+mod dbus;
+mod dedup;
+mod dialogs;
+mod dnd;
+mod editing;
+mod events;
+mod facets;
+mod fines;
+mod goodreads;
+mod history;
+mod holds;
+mod i18n;
+mod idle;
+mod ids;
+mod ill;
+mod integrity;
+mod import;
+mod kiosk;
+mod loans;
+mod logging;
+mod mail;
+mod marc;
+mod member_import;
+mod memberships;
+mod migrate;
+mod notices;
+mod opac;
+mod pattern;
+mod persist;
+mod picklist;
+mod pii;
+mod plugins;
+mod print;
+mod privacy;
+mod profile;
+mod query;
+mod recommend;
+mod receipts;
+mod reports;
+mod reserves;
+mod reviews;
+mod roundtrip;
+mod search;
+mod seed;
+mod selfcheck;
+mod serials;
+mod sets;
+mod shelves;
+#[cfg(feature = "sip2")]
+mod sip2;
+mod shortcuts;
+mod sql;
+mod sru;
+mod suggestions;
+mod status;
+mod stocktake;
+mod sync;
+mod table;
+mod transfers;
+mod trash;
+mod transactions;
+mod watch;
+mod webhooks;
+mod weeding;
 
-#[derive(Clone)]
+use acquisitions::{Acquisitions, Budget, BudgetUse, OrderLine, OrderStatus, PurchaseOrder, Vendor};
+use auth::{Role, Session, User, UserStore};
+use calendar::ClosureCalendar;
+use bus::EventBus;
+use cash::{CashPayment, CashSession};
+use challenge::ReadingChallenge;
+use changes::{ChangeLog, LibraryChange};
+use charts::{Chart, ChartKind};
+use clock::Clock;
+use condition::{Condition, ConditionNote};
+use config::{Appearance, Config};
+use copies::CopyStatus;
+use facets::{FacetFilter, FacetKind};
+use fines::{format_money, Amnesty, Fine, FineStatus, WaiverReason};
+use history::PastLoan;
+use holds::{Hold, HoldLevel, HoldStatus};
+use i18n::tr;
+use ids::IdAllocator;
+use ill::{IllDirection, IllRequest};
+use editing::{Edit, ItemDetails, ItemField, MemberDetails, NewItem};
+use events::{Events, Room, ScheduleRow, Scheduled};
+use import::{CsvSchema, ImportKind, ImportReport};
+use loans::DueLoan;
+use memberships::{MembershipPolicy, MembershipType, EXPIRY_WARNING_DAYS};
+use pattern::{Pattern, SearchMode};
+use plugins::Plugins;
+use query::Query;
+use receipts::Receipt;
+use reports::{Period, PeriodSummary};
+use reserves::{Reserve, ReserveLoan};
+use serials::{IssueRow, Subscription};
+use sets::{ItemLink, VolumeSet};
+use reviews::Review;
+use stocktake::Stocktake;
+use suggestions::{RejectReason, Suggestion, SuggestionStatus};
+use sync::{LibraryCommand, SyncCommand, SyncConflict, SyncMode};
+use table::Table;
+use transactions::{Transaction, TransactionKind};
+use transfers::{Transfer, TransferStatus};
+use trash::{Trash, Trashed};
+use webhooks::{WebhookEvent, Webhooks};
+
+#[derive(Clone, Deserialize, Serialize)]
 struct LiItemInstance {
-    title: String,
+    title: Arc<str>,
     id: u32,
     renew_factor: u32,
     due_date: DateTime<Utc>,
-    notice: bool
+    notice: bool,
+    #[serde(default)]
+    issued_at: DateTime<Utc>,
+    #[serde(default)]
+    renewed_count: u32,
+    // Length of an hourly loan; zero for loans counted in days
+    #[serde(default)]
+    loan_hours: u32,
 }
 
 impl LiItemInstance {
-    fn renew(&mut self) {
-        self.due_date = self.due_date + Months::new(1*self.renew_factor);
+    fn extend_due_date(&mut self, calendar: &ClosureCalendar) {
+        if self.is_hourly() {
+            self.due_date += chrono::Duration::hours(self.loan_hours as i64);
+            return;
+        }
+        // Formats without a renewal period of their own renew for a month,
+        // as their first loan is as long as a book's
+        self.due_date = calendar.next_open(self.due_date + Months::new(self.renew_factor.max(1)));
+    }
+
+    fn renew(&mut self, calendar: &ClosureCalendar) {
+        self.extend_due_date(calendar);
+        self.renewed_count += 1;
     }
 }
 
 #[derive(Clone, Deserialize, Serialize)]
 struct LiItem {
-    title: String,
-    author: Option<Box<String>>,
+    title: Arc<str>,
+    author: Option<String>,
     year: u32,
     edition: String,
     desc: String,
@@ -39,11 +183,49 @@ struct LiItem {
     id: u32,
     copies: u32,
     avail_copies: u32,
+    // Only read, to migrate old catalogs into `reviews`
+    #[serde(default, skip_serializing)]
     ratings: u32,
+    #[serde(default)]
+    isbn: Option<String>,
+    #[serde(default, deserialize_with = "facets::deserialize_tags")]
+    tags: Vec<String>,
+    #[serde(default)]
+    reviews: Vec<Review>,
+    // None for items from the initial catalog or saved before this was tracked
+    #[serde(default)]
+    added_at: Option<DateTime<Utc>>,
+    #[serde(default = "transfers::default_branch")]
+    branch: String,
+    // Dewey or LC class mark, e.g. "823.914 TOL"
+    #[serde(default)]
+    call_number: String,
+    // Where in the branch the item is shelved, e.g. "Adult Fiction"
+    #[serde(default)]
+    shelf_location: String,
+    // Copies a stocktake couldn't find: neither on the shelf nor on loan
+    #[serde(default)]
+    lost_copies: u32,
+    // Copies neither available, on loan nor lost, by status, e.g. on the
+    // hold shelf or in repair
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    copy_statuses: BTreeMap<CopyStatus, u32>,
+    // Copies not in good condition, by condition
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    conditions: BTreeMap<Condition, u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    condition_notes: Vec<ConditionNote>,
+    // A volume of a set, or a title bound into another item
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    link: Option<ItemLink>,
+    // Counts the edits made by hand, so an edit made to an older version
+    // isn't saved over a newer one
+    #[serde(default)]
+    version: u32,
 }
 
 impl LiItem {
-    fn create_instance(&mut self) -> LiItemInstance {
+    fn create_instance(&mut self, now: DateTime<Utc>, loan_days: u32, calendar: &ClosureCalendar) -> LiItemInstance {
         self.avail_copies -= 1;
 
         let mut inst = LiItemInstance{
@@ -54,429 +236,6917 @@ impl LiItem {
                 "movie" => 2,
                 _ => 0
             },
-            due_date: DateTime::default(),
-            notice: false
+            due_date: now,
+            notice: false,
+            issued_at: now,
+            renewed_count: 0,
+            loan_hours: 0,
         };
 
-        inst.renew();
+        // Formats with a longer renewal period get a longer first loan too
+        let days = loan_days * inst.renew_factor.max(1);
+        inst.due_date = calendar.next_open(now + chrono::Duration::days(days as i64));
 
         return inst;
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Deserialize, Serialize)]
 struct Member {
     id: u32,
+    name: String,
     items: HashMap<u32, LiItemInstance>,
+    #[serde(default)]
+    fines: Vec<Fine>,
+    #[serde(default)]
+    membership: MembershipType,
+    // None for members that never expire, including those saved before
+    // expiry was tracked
+    #[serde(default)]
+    expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    email: Option<String>,
+    // ID from an outside system, e.g. a school's student number
+    #[serde(default)]
+    external_id: Option<String>,
+    #[serde(default)]
+    anonymized_at: Option<DateTime<Utc>>,
+    // Completed loans are only kept for members who opted in
+    #[serde(default)]
+    keep_history: bool,
+    #[serde(default)]
+    history: Vec<PastLoan>,
+    // None for members added before sign-up dates were recorded
+    #[serde(default)]
+    joined_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    challenge: Option<ReadingChallenge>,
+    // Checkout, return and payment receipts, for printing again
+    #[serde(default)]
+    receipts: Vec<Receipt>,
+    // Counts the edits made by hand, like `LiItem::version`
+    #[serde(default)]
+    version: u32,
+    // Set for members who asked not to get the weekly digest email
+    #[serde(default)]
+    digest_opt_out: bool,
 }
 
+const PARSE_CHUNK_SIZE: usize = 65536;
+
+#[derive(Deserialize, Serialize)]
 struct Library {
     items: HashMap<u32, LiItem>,
     members: HashMap<u32, Member>,
+    #[serde(default)]
+    transactions: Vec<Transaction>,
+    #[serde(default)]
+    calendar: ClosureCalendar,
+    #[serde(default)]
+    transfers: Vec<Transfer>,
+    // Types without an entry use MembershipPolicy::default_for
+    #[serde(default)]
+    policies: HashMap<MembershipType, MembershipPolicy>,
+    #[serde(default)]
+    stocktake: Option<Stocktake>,
+    #[serde(default)]
+    reserves: Vec<Reserve>,
+    #[serde(default)]
+    acquisitions: Acquisitions,
+    // Purchase suggestions taken in from the kiosk
+    #[serde(default)]
+    suggestions: Vec<Suggestion>,
+    #[serde(default)]
+    serials: Vec<Subscription>,
+    #[serde(default)]
+    ill: Vec<IllRequest>,
+    #[serde(default)]
+    events: Events,
+    #[serde(default)]
+    holds: Vec<Hold>,
+    #[serde(default)]
+    cash_sessions: Vec<CashSession>,
+    // Fine waivers above this need an administrator's approval
+    #[serde(default = "fines::default_waiver_approval_cents")]
+    waiver_approval_cents: u64,
+    // Lets staff skip the passphrase on exports holding member data
+    #[serde(default)]
+    allow_plain_pii_export: bool,
+    // Open days an item may be late before it is fined
+    #[serde(default)]
+    fine_grace_days: u32,
+    // Most one late return is fined; zero for no cap
+    #[serde(default)]
+    max_fine_cents: u64,
+    #[serde(default)]
+    amnesty: Option<Amnesty>,
+    // Days a member has to collect a filled hold
+    #[serde(default = "holds::default_hold_pickup_days")]
+    hold_pickup_days: u32,
+    // Formats lent by the hour (lowercase), with their loan length in hours
+    #[serde(default = "loans::default_hourly_formats")]
+    hourly_formats: BTreeMap<String, u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    volume_sets: Vec<VolumeSet>,
+    // Set by every mutation, cleared once the state has been saved
+    #[serde(skip)]
+    dirty: bool,
+    // Staff member recorded in the transaction log
+    #[serde(skip)]
+    current_user: String,
+    // Catalog edits that can still be undone, most recent last
+    #[serde(skip)]
+    undo_stack: Vec<Edit>,
+    #[serde(skip, default = "clock::system_clock")]
+    clock: Box<dyn Clock>,
+    // Set while this desk shares its library with others
+    #[serde(skip)]
+    sync_enabled: bool,
+    // Set while this desk is a sync client, whose library the server's
+    // replaces; changes that aren't sent to the server are refused
+    #[serde(skip)]
+    sync_client: bool,
+    // Changes not yet sent to the other desks, kept across restarts so
+    // those made while a client is offline still go out once it is back
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    outbox: Vec<SyncCommand>,
+    // Changes from here the sync server refused, for staff to sort out
+    #[serde(default)]
+    sync_conflicts: Vec<SyncConflict>,
+    // Scripts from the plugins directory, if there are any
+    #[serde(skip)]
+    plugins: Option<Arc<Plugins>>,
+    // Where circulation events are posted, if anywhere
+    #[serde(skip)]
+    webhooks: Option<Arc<Webhooks>>,
+    // Events of the changes made so far, posted by `flush_webhooks`
+    #[serde(skip)]
+    pending_webhooks: Vec<String>,
+    // Set while a change from another desk is replayed
+    #[serde(skip)]
+    replaying: bool,
+    // What changed, for views to redraw just those rows
+    #[serde(skip)]
+    changes: ChangeLog,
+    #[serde(default)]
+    ids: IdAllocator,
+    #[serde(default)]
+    trash: Trash,
 }
 
 impl Library {
     fn new() -> Library {
+        Library::with_clock(clock::system_clock())
+    }
+
+    fn with_clock(clock: Box<dyn Clock>) -> Library {
         Library {
-            items: HashMap::with_capacity(3000000),
-            members: HashMap::with_capacity(10),
+            items: HashMap::new(),
+            members: HashMap::new(),
+            transactions: Vec::new(),
+            calendar: ClosureCalendar::default(),
+            transfers: Vec::new(),
+            policies: HashMap::new(),
+            stocktake: None,
+            reserves: Vec::new(),
+            acquisitions: Acquisitions::default(),
+            suggestions: Vec::new(),
+            serials: Vec::new(),
+            ill: Vec::new(),
+            events: Events::default(),
+            holds: Vec::new(),
+            cash_sessions: Vec::new(),
+            waiver_approval_cents: fines::DEFAULT_WAIVER_APPROVAL_CENTS,
+            allow_plain_pii_export: false,
+            fine_grace_days: 0,
+            max_fine_cents: 0,
+            amnesty: None,
+            hold_pickup_days: holds::DEFAULT_HOLD_PICKUP_DAYS,
+            hourly_formats: loans::default_hourly_formats(),
+            volume_sets: Vec::new(),
+            dirty: false,
+            current_user: String::new(),
+            undo_stack: Vec::new(),
+            clock,
+            sync_enabled: false,
+            sync_client: false,
+            outbox: Vec::new(),
+            sync_conflicts: Vec::new(),
+            plugins: None,
+            webhooks: None,
+            pending_webhooks: Vec::new(),
+            replaying: false,
+            changes: ChangeLog::default(),
+            ids: IdAllocator::default(),
+            trash: Trash::default(),
+        }
+    }
+
+    fn initialize_lib(&mut self, csv_path: &str) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        let report = self.import_csv(csv_path, &CsvSchema::default())?;
+        // The starting catalog isn't new stock
+        for item in self.items.values_mut() {
+            item.added_at = None;
         }
+        // Nothing to save yet, the catalog is still on disk
+        self.dirty = false;
+        Ok(report)
     }
 
-    fn initialize_lib(&mut self, csv_path: &str) -> Result<(), Box<dyn std::error::Error>> {
+    fn import_csv(&mut self, csv_path: &str, schema: &CsvSchema) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        self.only_at_server()?;
         let file = File::open(csv_path).map_err(|e| {
-            eprintln!("Failed to open file: {}", e);
-            println!("Attempted to open file: {}", csv_path);
+            tracing::error!(path = csv_path, "Failed to open file: {}", e);
             e
         })?;
-        let mut rdr = csv::Reader::from_reader(file);
-        let mut count = 0;
-        for (i, result) in rdr.deserialize().enumerate() {
-            match result {
-                Ok(item) => {
-                    let item: LiItem = item;
-                    self.items.insert(item.id, item);
-                    count += 1;
+        let mut rdr = schema.reader(file);
+        let mut report = ImportReport::new(rdr.byte_headers()?);
+        let mut records = rdr.into_byte_records();
+
+        let mut schema = schema.clone();
+        if schema.columns.is_empty() {
+            schema.columns = CsvSchema::detect(ImportKind::Items, &report.headers);
+        }
+        let headers = CsvSchema::field_headers(ImportKind::Items);
+        // Files without an ID column get new IDs
+        let assign_ids = !schema.columns.contains_key("id");
+        // IDs already read from this file; a second row with the same ID
+        // would silently replace the first
+        let mut seen_ids = HashSet::new();
+
+        // Records are read sequentially (cheap) and deserialized in parallel
+        // one chunk at a time, so memory stays bounded on very large files.
+        let mut chunk = Vec::with_capacity(PARSE_CHUNK_SIZE);
+        let mut done = false;
+        while !done {
+            chunk.clear();
+            while chunk.len() < PARSE_CHUNK_SIZE {
+                match records.next() {
+                    Some(Ok(record)) => chunk.push(record),
+                    Some(Err(e)) => {
+                        let line = e.position().map_or(0, |p| p.line());
+                        report.add_error(line, &e, None);
+                    }
+                    None => {
+                        done = true;
+                        break;
+                    }
                 }
-                Err(e) => {
-                    eprintln!("Failed to parse row {}: {}", i + 2, e);
-                    // Print details about the row
-                    println!("Failed row details: {:?}", e);
+            }
+
+            let parsed: Vec<Result<LiItem, csv::Error>> = chunk
+                .par_iter()
+                .map(|record| schema.remap(ImportKind::Items, record).deserialize(Some(&headers)))
+                .collect();
+
+            for (record, result) in chunk.iter().zip(parsed) {
+                match result {
+                    Ok(mut item) => {
+                        if assign_ids {
+                            item.id = self.next_item_id();
+                        } else if !seen_ids.insert(item.id) {
+                            let line = record.position().map_or(0, |p| p.line());
+                            report.add_problem(line, tr!("Duplicate item ID {}", item.id), Some(record));
+                            continue;
+                        } else {
+                            self.ids.item_id_used(item.id);
+                        }
+                        item.migrate_ratings(self.clock.now());
+                        (item.added_at, item.version) = match self.items.get(&item.id) {
+                            // Replacing the item counts as an edit
+                            Some(existing) => (existing.added_at, existing.version + 1),
+                            None => (Some(self.clock.now()), 0),
+                        };
+                        self.items.insert(item.id, item);
+                        report.loaded += 1;
+                    }
+                    Err(e) => {
+                        let line = record.position().map_or(0, |p| p.line());
+                        report.add_error(line, &e, Some(record));
+                    }
                 }
             }
         }
-        println!("Loaded {} items into library", count);
-        if count == 0 {
-            return Err("No items loaded from CSV".into());
+        tracing::info!(path = csv_path, loaded = report.loaded, skipped = report.skipped, "Catalog imported");
+        if report.loaded > 0 {
+            self.dirty = true;
+            self.reloaded();
         }
-        Ok(())
+        Ok(report)
     }
     
     fn book_issue(&mut self, title_id: u32, member_id_text: String) -> Result<(), String> {
+        let title_id = self.physical_unit(title_id);
+        if self.in_transit(title_id) {
+            return Err(tr!("Item is in transit between branches"));
+        }
         if let Ok(member_id) = member_id_text.parse::<u32>() {
-            if let Some(member) = self.members.get_mut(&member_id) {
-                if let Some(item) = self.items.get_mut(&title_id) {
-                    if item.avail_copies > 0 {
-                        member.items.insert(title_id, item.create_instance());
+            if let Some(member) = self.members.get(&member_id) {
+                if member.items.contains_key(&title_id) {
+                    return Err(tr!("This member already has this item"));
+                }
+                if let Some(item) = self.items.get(&title_id) {
+                    let policy = self.check_policy(member, item)?;
+                    self.plugins_allow_issue(title_id, member_id)?;
+                    // A copy waiting on the hold shelf for this member
+                    let held = self.shelved_hold(title_id, member_id);
+                    if item.avail_copies > 0 || held.is_some() {
+                        if let Some(hold_id) = held {
+                            self.collect_hold(hold_id);
+                        }
+                        let now = self.clock.now();
+                        let mut inst = self.items.get_mut(&title_id).unwrap().create_instance(now, policy.loan_days, &self.calendar);
+                        self.apply_loan_rules(&mut inst, now);
+                        self.members.get_mut(&member_id).unwrap().items.insert(title_id, inst);
+                        self.log_transaction(TransactionKind::Issue, title_id, member_id);
+                        self.record_command(LibraryCommand::Issue { item_id: title_id, member: member_id_text });
+                        self.dirty = true;
+                        self.changed(LibraryChange::LoanCreated { item_id: title_id, member_id });
+                        self.webhook(WebhookEvent::Issue, title_id, member_id);
                         Ok(())
                     } else {
-                        Err("No available copies left!".to_string())
+                        Err(tr!("No available copies left!"))
                     }
                 } else {
-                    Err("Invalid Item ID!".to_string())
+                    Err(tr!("Invalid Item ID!"))
                 }
             } else {
-                Err("Invalid Member ID!".to_string())
+                Err(tr!("Invalid Member ID!"))
             }
         } else {
-            let member_id = self.members.len() as u32 + 1;
-            if let Some(item) = self.items.get_mut(&title_id) {
-                if item.avail_copies > 0 {
-                    let mut member = Member {
-                        id: member_id,
-                        items: HashMap::new(),
-                    };
-
-                    member.items.insert(title_id, item.create_instance());
-
-                    self.members.insert(member_id, member);
-                    Ok(())
-                } else {
-                    Err("No available copies left!".to_string())
-                }
-            } else {
-                Err("Invalid Item ID!".to_string())
-            }
+            // New members are registered first, see `register_member`
+            Err(tr!("Invalid Member ID!"))
         }
     }
 
 
-    fn book_return(&mut self, title_id: u32, member_id: u32) -> Result<&mut LiItem, String>{
+    fn book_return(&mut self, title_id: u32, member_id: u32) -> Result<(&mut LiItem, Option<Fine>), String>{
+        let title_id = self.physical_unit(title_id);
         if self.members.contains_key(&member_id) {
             if let Some(inst) = self.members.get_mut(&member_id).unwrap().items.remove(&title_id) {
-                if let Some(item) = self.items.get_mut(&title_id) {
-                    item.avail_copies += 1;
+                if self.items.contains_key(&title_id) {
+                    let fine = self.assess_fine(member_id, &inst);
+                    self.record_history(member_id, &inst);
+                    self.plugins_on_return(member_id, &inst);
+                    self.count_towards_challenge(member_id);
                     drop(inst);
-                    Ok(item)
+                    self.log_transaction(TransactionKind::Return, title_id, member_id);
+                    self.webhook(WebhookEvent::Return, title_id, member_id);
+                    self.record_command(LibraryCommand::Return { item_id: title_id, member_id });
+                    self.dirty = true;
+                    self.items.get_mut(&title_id).unwrap().avail_copies += 1;
+                    // Goes to the hold shelf if someone is waiting for it
+                    self.fill_holds(title_id);
+                    self.changed(LibraryChange::LoanEnded { item_id: title_id, member_id });
+                    let item = self.items.get_mut(&title_id).unwrap();
+                    Ok((item, fine))
                 } else {
-                    Err("Book not found in library items".to_string())
+                    Err(tr!("Book not found in library items"))
                 }
             } else {
-                Err("This book was not checked out by this member".to_string())
+                Err(tr!("This book was not checked out by this member"))
             }
         } else {
-            Err("Member not found".to_string())
+            Err(tr!("Member not found"))
         }
     }
-}
 
-fn create_library_gui() -> Application {
-    let app = Application::builder()
-        .application_id("com.example.rustLMS")
-        .build();
+    // Issues several items to one member. Either every item is issued or, if
+    // one of them fails, the items and member are put back as they were.
+    fn book_issue_batch(&mut self, item_ids: &[u32], member_id_text: String) -> Result<(), String> {
+        if item_ids.is_empty() {
+            return Err(tr!("No items to issue"));
+        }
 
-    app.connect_activate(|app| {
-        // Shared library state
-        let library = Arc::new(RwLock::new(Library::new()));
+        let member_id = member_id_text.trim().parse::<u32>().map_err(|_| tr!("Invalid Member ID!"))?;
+        let saved_member = self.members.get(&member_id).cloned().ok_or_else(|| tr!("Invalid Member ID!"))?;
+        // Bound-with titles are issued as their host volume, which is what
+        // changes
+        let units: BTreeSet<u32> = item_ids.iter().map(|&id| self.physical_unit(id)).collect();
+        let saved_items: Vec<LiItem> = units.iter().filter_map(|id| self.items.get(id).cloned()).collect();
+        let saved_holds = self.holds.clone();
+        let log_len = self.transactions.len();
+        let outbox_len = self.outbox.len();
+        let webhooks_len = self.pending_webhooks.len();
 
-        // Initialize library
-        {
-            let mut lib = library.write().unwrap();
-            match lib.initialize_lib("output.csv") {
-                Ok(_) => println!("Library initialized successfully"),
-                Err(e) => {
-                    eprintln!("Failed to initialize library: {}", e);
-                    if let Ok(path) = std::env::current_dir() {
-                        println!("Current working directory: {}", path.display());
-                    }
+        for &item_id in item_ids {
+            if let Err(e) = self.book_issue(item_id, member_id.to_string()) {
+                for item in saved_items {
+                    self.items.insert(item.id, item);
                 }
+                self.members.insert(member_id, saved_member);
+                self.holds = saved_holds;
+                self.transactions.truncate(log_len);
+                self.outbox.truncate(outbox_len);
+                self.pending_webhooks.truncate(webhooks_len);
+                self.reloaded();
+                tracing::warn!(item_id, "Batch issue rolled back: {}", e);
+                return Err(tr!("Item {}: {}", item_id, e));
             }
         }
+        Ok(())
+    }
+
+    fn renew_loan(&mut self, item_id: u32, member_id: u32) -> Result<DateTime<Utc>, String> {
+        let item_id = self.physical_unit(item_id);
+        if let Some(reserve) = self.reserve(item_id) {
+            return Err(tr!("Items on reserve for {} can't be renewed", reserve.course));
+        }
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let inst = member.items.get_mut(&item_id).ok_or_else(|| tr!("This book was not checked out by this member"))?;
+        inst.renew(&self.calendar);
+        let due_date = inst.due_date;
+        self.log_transaction(TransactionKind::Renew, item_id, member_id);
+        self.record_command(LibraryCommand::Renew { item_id, member_id });
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(due_date)
+    }
 
-        let window = ApplicationWindow::builder()
-            .application(app)
-            .title("Library Management System")
-            .default_width(800)
-            .default_height(600)
-            .build();
+    fn return_all(&mut self, member_id: u32) -> Result<Vec<ReturnOutcome>, String> {
+        let Some(member) = self.members.get(&member_id) else {
+            return Err(tr!("Member not found"));
+        };
+        let loans: Vec<(u32, Arc<str>)> = member.items.values().map(|i| (i.id, i.title.clone())).collect();
 
-        let main_box = GtkBox::new(gtk::Orientation::Vertical, 10);
-        main_box.set_margin_top(10);
-        main_box.set_margin_bottom(10);
-        main_box.set_margin_start(10);
-        main_box.set_margin_end(10);
+        let outcomes = loans
+            .into_iter()
+            .map(|(item_id, title)| ReturnOutcome {
+                item_id,
+                title,
+                result: self.book_return(item_id, member_id).map(|(_, fine)| fine),
+            })
+            .collect();
+        Ok(outcomes)
+    }
+}
 
-        let notebook = gtk::Notebook::new();
+struct ReturnOutcome {
+    item_id: u32,
+    title: Arc<str>,
+    result: Result<Option<Fine>, String>,
+}
 
-        // Pass the Arc<RwLock<Library>> to each page
-        notebook.append_page(
-            &create_issue_page(library.clone()),
-            Some(&Label::new(Some("Issue Books"))),
-        );
-        notebook.append_page(
-            &create_return_page(library.clone()),
-            Some(&Label::new(Some("Return Books"))),
-        );
-        notebook.append_page(
-            &create_member_details_page(library.clone()),
-            Some(&Label::new(Some("Member Details"))),
-        );
-        notebook.append_page(
-            &create_catalog_page(library.clone()),
-            Some(&Label::new(Some("Library Catalog"))),
-        );
+// Restores the saved state, or starts from the catalog CSV on first run. A
+// catalog given on the command line is used instead of the configured one,
+// and updates the saved state's catalog when there is one.
+fn load_library(catalog: Option<&Path>) -> (Library, Option<Result<ImportReport, Box<dyn std::error::Error>>>) {
+    let state_path = Path::new(persist::STATE_PATH);
+    let mut lib = Library::new();
+    let import_result = if state_path.exists() {
+        match Library::load(state_path) {
+            Ok(saved) => {
+                lib = saved;
+                catalog.map(|path| lib.reload_catalog_csv(&path.to_string_lossy()))
+            }
+            Err(e) => Some(Err(e)),
+        }
+    } else {
+        let csv_path = catalog.map_or_else(|| Config::load().catalog_csv, |path| path.to_string_lossy().into_owned());
+        Some(lib.initialize_lib(if csv_path.is_empty() { "output.csv" } else { &csv_path }))
+    };
+    (lib, import_result)
+}
 
-        main_box.append(&notebook);
-        window.set_child(Some(&main_box));
-        window.show();
+fn apply_appearance(appearance: Appearance) {
+    adw::StyleManager::default().set_color_scheme(match appearance {
+        Appearance::System => adw::ColorScheme::Default,
+        Appearance::Light => adw::ColorScheme::ForceLight,
+        Appearance::Dark => adw::ColorScheme::ForceDark,
     });
+}
 
-    app
+// The staff window and its library, once someone has logged in
+type MainWindow = Option<(glib::WeakRef<adw::ApplicationWindow>, Arc<RwLock<Library>>)>;
+
+/// Which window the application opens, chosen on the command line.
+#[derive(Clone, Copy, PartialEq)]
+enum Station {
+    Staff,
+    // `--kiosk`
+    Kiosk,
+    // `--self-checkout`
+    SelfCheckout,
 }
-fn create_issue_page(library: Arc<RwLock<Library>>) -> GtkBox {
-    let issue_box = GtkBox::new(gtk::Orientation::Vertical, 10);
 
-    let item_id_label = Label::new(Some("Item ID:"));
-    let item_id_entry = Entry::new();
-    let member_id_label = Label::new(Some("Member ID:"));
-    let member_id_entry = Entry::new();
-    let status_label = Label::new(None);
+impl Station {
+    fn from_args() -> Station {
+        let args: Vec<String> = std::env::args().collect();
+        if args.iter().any(|arg| arg == "--self-checkout") {
+            Station::SelfCheckout
+        } else if args.iter().any(|arg| arg == "--kiosk") {
+            Station::Kiosk
+        } else {
+            Station::Staff
+        }
+    }
 
-    let issue_button = Button::with_label("Issue Book");
-    issue_button.connect_clicked(glib::clone!(
-        #[weak] item_id_entry,
-        #[weak] member_id_entry,
-        #[weak] status_label,
-        #[strong] library,  // Keep the Arc alive
-        move |_| {
-            let item_id_text = item_id_entry.text().to_string();
-            let member_id_text = member_id_entry.text().to_string();
-            if let Ok(item_id) = item_id_text.parse::<u32>() {
-                let mut lib = library.write().unwrap(); // Lock for writing here
-                match lib.book_issue(item_id, member_id_text) {
-                    Ok(_) => {
-                        status_label.set_text("Book issued successfully!");
-                        item_id_entry.set_text("");
-                        member_id_entry.set_text("");
+    fn application_id(self) -> &'static str {
+        match self {
+            Station::Staff => "com.example.rustLMS",
+            Station::Kiosk => "com.example.rustLMS.Kiosk",
+            Station::SelfCheckout => "com.example.rustLMS.SelfCheckout",
+        }
+    }
+}
+
+fn create_library_gui(station: Station) -> adw::Application {
+    // The patron stations run as applications of their own, so they and a
+    // staff window on one machine don't take each other's place
+    let app = adw::Application::builder()
+        .application_id(station.application_id())
+        .flags(gio::ApplicationFlags::HANDLES_OPEN)
+        .build();
+
+    let main_window: Rc<RefCell<MainWindow>> = Rc::new(RefCell::new(None));
+    // A catalog CSV given when the application starts, e.g.
+    // `rustLMS catalog.csv`
+    let catalog: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+    // Files opened before anyone logged in, imported right after
+    let pending_files: Rc<RefCell<Vec<PathBuf>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // Launching the application again opens the files in this instance
+    // instead, so two libraries never save over each other
+    app.connect_open(glib::clone!(
+        #[strong]
+        main_window,
+        #[strong]
+        pending_files,
+        #[strong]
+        catalog,
+        move |app, files, _| {
+            let mut paths: Vec<PathBuf> = files.iter().filter_map(|file| file.path()).collect();
+            let open = main_window.borrow().as_ref().and_then(|(window, library)| Some((window.upgrade()?, library.clone())));
+            match open {
+                Some((window, library)) if station == Station::Staff => {
+                    window.present();
+                    for path in paths {
+                        dnd::import_file(window.upcast_ref(), library.clone(), path, ImportKind::Items);
                     }
-                    Err(e) => status_label.set_text(&format!("Error: {}", e)),
                 }
-            } else {
-                status_label.set_text("Invalid Item ID");
+                _ => {
+                    // Before the library is loaded, the first CSV is taken
+                    // as the catalog to load
+                    let is_csv = |path: &PathBuf| path.extension().is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+                    if app.active_window().is_none() && catalog.borrow().is_none() {
+                        if let Some(n) = paths.iter().position(is_csv) {
+                            *catalog.borrow_mut() = Some(paths.remove(n));
+                        }
+                    }
+                    if station == Station::Staff {
+                        pending_files.borrow_mut().extend(paths);
+                    }
+                    app.activate();
+                }
             }
         }
     ));
 
-    issue_box.append(&item_id_label);
-    issue_box.append(&item_id_entry);
-    issue_box.append(&member_id_label);
-    issue_box.append(&member_id_entry);
-    issue_box.append(&issue_button);
-    issue_box.append(&status_label);
+    app.connect_activate(move |app| {
+        // Only the first activation loads the library; later launches just
+        // bring the window that is already open to the front
+        if let Some(window) = app.active_window() {
+            window.present();
+            return;
+        }
+        let catalog = catalog.borrow().clone();
+        let (lib, import_result) = load_library(catalog.as_deref());
 
-    issue_box
-}
+        // Shared library state
+        let library = Arc::new(RwLock::new(lib));
 
-fn create_return_page(library: Arc<RwLock<Library>>) -> GtkBox {
-    let return_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+        let config = Rc::new(RefCell::new(Config::load()));
+        apply_appearance(config.borrow().appearance);
 
-    let item_id_label = Label::new(Some("Item ID:"));
-    let item_id_entry = Entry::new();
-    let member_id_label = Label::new(Some("Member ID:"));
-    let member_id_entry = Entry::new();
-    let status_label = Label::new(None);
-    let book_details_label = Label::new(None);
+        if station != Station::Staff {
+            if let Some(Err(e)) = import_result {
+                tracing::error!("Failed to initialize library: {}", e);
+            }
+            if station == Station::Kiosk {
+                kiosk::build_kiosk_window(app.upcast_ref(), library);
+            } else {
+                selfcheck::build_self_checkout_window(app.upcast_ref(), library, config);
+            }
+            return;
+        }
 
-    let return_button = Button::with_label("Return Book");
-    return_button.connect_clicked(glib::clone!(
-        #[weak] item_id_entry,
-        #[weak] member_id_entry,
-        #[weak] status_label,
-        #[weak] book_details_label,
-        #[strong] library,
-        move |_| {
-            let item_id_text = item_id_entry.text().to_string();
-            let member_id_text = member_id_entry.text().to_string();
+        let users = match UserStore::load() {
+            Ok(users) => Rc::new(RefCell::new(users)),
+            Err(e) => {
+                dialogs::show_startup_error(app.upcast_ref(), &tr!("The staff accounts could not be loaded"), &e);
+                return;
+            }
+        };
 
-            if let Ok(item_id) = item_id_text.parse::<u32>() {
-                if let Ok(member_id) = member_id_text.parse::<u32>() {
-                    let mut lib = library.write().unwrap(); // Lock for writing
-                    match lib.book_return(item_id, member_id) {
-                        Ok(book) => {
-                            status_label.set_text("Book returned successfully!");
-                            book_details_label.set_text(&format!(
-                                "Returned Book: {} (ID: {})",
-                                book.title, book.id
-                            ));
-                            item_id_entry.set_text("");
-                            member_id_entry.set_text("");
-                        }
-                        Err(e) => {
-                            status_label.set_text(&format!("Error: {}", e));
-                            book_details_label.set_text("");
-                        }
-                    }
-                } else {
-                    status_label.set_text("Invalid Member ID");
+        dialogs::show_login(app.upcast_ref(), users.clone(), glib::clone!(
+            #[weak]
+            app,
+            #[strong]
+            main_window,
+            #[strong]
+            pending_files,
+            move |session| {
+                let window = build_main_window(&app, library.clone(), config, users, session, catalog, import_result);
+                *main_window.borrow_mut() = Some((window.downgrade(), library.clone()));
+                for path in pending_files.take() {
+                    dnd::import_file(window.upcast_ref(), library.clone(), path, ImportKind::Items);
                 }
-            } else {
-                status_label.set_text("Invalid Item ID");
             }
+        ));
+    });
+
+    app
+}
+
+fn build_main_window(
+    app: &adw::Application,
+    library: Arc<RwLock<Library>>,
+    config: Rc<RefCell<Config>>,
+    users: Rc<RefCell<UserStore>>,
+    session: Session,
+    catalog: Option<PathBuf>,
+    import_result: Option<Result<ImportReport, Box<dyn std::error::Error>>>,
+) -> adw::ApplicationWindow {
+    library.write().unwrap().current_user = session.username.clone();
+
+    // Where the library comes from: the catalog given on the command line,
+    // or the saved state
+    let data_file = catalog.clone().unwrap_or_else(|| PathBuf::from(persist::STATE_PATH));
+    let data_file = std::path::absolute(&data_file).unwrap_or(data_file);
+
+    let window = adw::ApplicationWindow::builder()
+        .application(app)
+        .title(tr!("Library Management System - {} ({}) - {}", session.username, session.role.name(), data_file.display()))
+        .default_width(800)
+        .default_height(600)
+        .build();
+    window.set_size_request(360, 400);
+
+    let main_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    main_box.set_margin_top(10);
+    main_box.set_margin_bottom(10);
+    main_box.set_margin_start(10);
+    main_box.set_margin_end(10);
+
+    let stack = adw::ViewStack::new();
+    // Keeps the pages up to date with each other and the other desks
+    let bus = EventBus::start(library.clone());
+
+    // Pass the Arc<RwLock<Library>> to each page
+    stack.add_titled_with_icon(&create_dashboard_page(library.clone(), &bus), Some("dashboard"), &tr!("Dashboard"), "go-home-symbolic");
+    let issue_page = create_issue_page(library.clone());
+    stack.add_titled_with_icon(&issue_page, Some("issue"), &tr!("Issue Books"), "document-send-symbolic");
+    let return_page = create_return_page(library.clone(), users.clone(), session.role);
+    stack.add_titled_with_icon(&return_page, Some("return"), &tr!("Return Books"), "edit-undo-symbolic");
+    stack.add_titled_with_icon(
+        &create_member_details_page(library.clone(), &bus, session.role),
+        Some("members"),
+        &tr!("Member Details"),
+        "system-users-symbolic",
+    );
+    let catalog_page = create_catalog_page(library.clone(), &bus, Some(session.role));
+    stack.add_titled_with_icon(&catalog_page, Some("catalog"), &tr!("Library Catalog"), "view-list-symbolic");
+    stack.add_titled_with_icon(&create_overdue_page(library.clone(), &bus), Some("overdue"), &tr!("Overdue"), "alarm-symbolic");
+    stack.add_titled_with_icon(
+        &create_transfers_page(library.clone()),
+        Some("transfers"),
+        &tr!("Transfers"),
+        "mail-send-receive-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_ill_page(library.clone()),
+        Some("ill"),
+        &tr!("Interlibrary Loans"),
+        "network-workgroup-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_hold_shelf_page(library.clone(), &bus, config.clone()),
+        Some("holds"),
+        &tr!("Hold Shelf"),
+        "view-pin-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_reserves_page(library.clone(), &bus, session.role),
+        Some("reserves"),
+        &tr!("Course Reserves"),
+        "x-office-address-book-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_serials_page(library.clone(), session.role),
+        Some("serials"),
+        &tr!("Serials"),
+        "x-office-document-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_events_page(library.clone()),
+        Some("events"),
+        &tr!("Events & Rooms"),
+        "x-office-calendar-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_stocktake_page(library.clone(), session.role),
+        Some("stocktake"),
+        &tr!("Stocktake"),
+        "edit-find-symbolic",
+    );
+    stack.add_titled_with_icon(
+        &create_cash_page(library.clone(), &bus, config.clone()),
+        Some("cash"),
+        &tr!("Cash Drawer"),
+        "accessories-calculator-symbolic",
+    );
+    stack.add_titled_with_icon(&create_reports_page(library.clone(), &bus), Some("reports"), &tr!("Reports"), "x-office-spreadsheet-symbolic");
+    if session.role >= Role::Librarian {
+        stack.add_titled_with_icon(
+            &create_acquisitions_page(library.clone()),
+            Some("acquisitions"),
+            &tr!("Acquisitions"),
+            "emblem-documents-symbolic",
+        );
+        stack.add_titled_with_icon(
+            &create_trash_page(library.clone(), &bus, session.role),
+            Some("trash"),
+            &tr!("Trash"),
+            "user-trash-symbolic",
+        );
+    }
+    if config.borrow().sync_mode == SyncMode::Client {
+        stack.add_titled_with_icon(
+            &create_sync_conflicts_page(library.clone(), &bus),
+            Some("sync-conflicts"),
+            &tr!("Sync Conflicts"),
+            "dialog-warning-symbolic",
+        );
+    }
+    if session.role >= Role::Admin {
+        stack.add_titled_with_icon(
+            &create_users_page(users.clone(), session.username.clone()),
+            Some("users"),
+            &tr!("Users"),
+            "avatar-default-symbolic",
+        );
+        stack.add_titled_with_icon(
+            &create_settings_page(library.clone(), config.clone()),
+            Some("settings"),
+            &tr!("Settings"),
+            "emblem-system-symbolic",
+        );
+        stack.add_titled_with_icon(&create_logs_page(), Some("logs"), &tr!("Logs"), "utilities-terminal-symbolic");
+        stack.add_titled_with_icon(&create_sql_page(library.clone()), Some("sql"), &tr!("SQL Console"), "system-search-symbolic");
+    }
+
+    stack.set_vexpand(true);
+    main_box.append(&stack);
+    main_box.append(&status::create_status_bar(window.upcast_ref()));
+
+    let menu = gio::Menu::new();
+    menu.append(Some(&tr!("Command Palette")), Some("win.command-palette"));
+    menu.append(Some(&tr!("Save")), Some("win.save"));
+    menu.append(Some(&tr!("Restore from Backup...")), Some("win.restore-backup"));
+    menu.append(Some(&tr!("Check Database...")), Some("win.check-database"));
+    menu.append(Some(&tr!("Run Self-Test...")), Some("win.self-test"));
+    menu.append(Some(&tr!("Lock Screen")), Some("win.lock"));
+    if config.borrow().sync_mode == SyncMode::Client {
+        menu.append(Some(&tr!("Reload from Sync Server")), Some("win.resync"));
+    }
+    if cfg!(debug_assertions) && session.role >= Role::Admin {
+        menu.append(Some(&tr!("Generate Demo Data...")), Some("win.seed"));
+    }
+    let menu_button = gtk::MenuButton::new();
+    menu_button.set_icon_name("open-menu-symbolic");
+    menu_button.set_menu_model(Some(&menu));
+
+    let switcher = adw::ViewSwitcher::new();
+    switcher.set_stack(Some(&stack));
+    switcher.set_policy(adw::ViewSwitcherPolicy::Wide);
+    let header_bar = adw::HeaderBar::new();
+    header_bar.set_title_widget(Some(&switcher));
+    header_bar.pack_end(&menu_button);
+    let quick_stats = create_quick_stats(library.clone(), &bus);
+    header_bar.pack_start(&quick_stats);
+    let switcher_bar = adw::ViewSwitcherBar::new();
+    switcher_bar.set_stack(Some(&stack));
+
+    let toolbar_view = adw::ToolbarView::new();
+    toolbar_view.add_top_bar(&header_bar);
+    toolbar_view.add_top_bar(&create_due_banner(&library.read().unwrap(), &stack));
+    toolbar_view.set_content(Some(&main_box));
+    toolbar_view.add_bottom_bar(&switcher_bar);
+    idle::install(app, &window, &toolbar_view, users, session.username.clone(), config.borrow().idle_lock_minutes);
+
+    // Narrow windows get the page switcher along the bottom instead
+    let breakpoint = adw::Breakpoint::new(adw::BreakpointCondition::new_length(
+        adw::BreakpointConditionLengthType::MaxWidth,
+        700.0,
+        adw::LengthUnit::Sp,
+    ));
+    breakpoint.connect_apply(glib::clone!(
+        #[weak]
+        switcher,
+        #[weak]
+        switcher_bar,
+        #[weak]
+        quick_stats,
+        move |_| {
+            switcher.set_visible(false);
+            switcher_bar.set_reveal(true);
+            quick_stats.set_visible(false);
+        }
+    ));
+    breakpoint.connect_unapply(glib::clone!(
+        #[weak]
+        switcher,
+        #[weak]
+        switcher_bar,
+        #[weak]
+        quick_stats,
+        move |_| {
+            switcher.set_visible(true);
+            switcher_bar.set_reveal(false);
+            quick_stats.set_visible(true);
         }
     ));
+    window.add_breakpoint(breakpoint);
 
-    return_box.append(&item_id_label);
-    return_box.append(&item_id_entry);
-    return_box.append(&member_id_label);
-    return_box.append(&member_id_entry);
-    return_box.append(&return_button);
-    return_box.append(&status_label);
-    return_box.append(&book_details_label);
+    let restore_action = gio::SimpleAction::new("restore-backup", None);
+    restore_action.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        #[strong]
+        library,
+        #[strong]
+        config,
+        move |_, _| {
+            dialogs::show_restore_backup(&window, library.clone(), &config.borrow(), glib::clone!(
+                #[weak]
+                window,
+                move || status::success(&window, &tr!("Library restored from backup"))
+            ));
+        }
+    ));
+    restore_action.set_enabled(session.role >= Role::Admin);
+    window.add_action(&restore_action);
 
-    return_box
-}
+    let check_action = gio::SimpleAction::new("check-database", None);
+    check_action.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        #[strong]
+        library,
+        move |_, _| {
+            dialogs::show_check_database(&window, library.clone(), glib::clone!(
+                #[weak]
+                window,
+                move || status::success(&window, &tr!("Library data repaired"))
+            ));
+        }
+    ));
+    check_action.set_enabled(session.role >= Role::Admin);
+    window.add_action(&check_action);
 
-fn create_member_details_page(library: Arc<RwLock<Library>>) -> GtkBox {
-    let member_box = GtkBox::new(gtk::Orientation::Vertical, 10);
-    
-    // Create a list store for members
-    let list_store = ListStore::new(&[
-        u32::static_type(),     // Member ID
-        String::static_type(),  // Book Title
-    ]);
-    
-    // Create TreeView
-    let tree_view = TreeView::with_model(&list_store);
-    // Create columns
-    let columns = [
-        ("Member ID", 0),
-        ("Item Titles", 1),
-    ];
-    
-    for (title, column_id) in columns.iter() {
-        let renderer = CellRendererText::new();
-        let column = TreeViewColumn::new();
-        column.set_title(title);
-        column.pack_start(&renderer, true);
-        column.add_attribute(&renderer, "text", *column_id);
-        tree_view.append_column(&column);
-    }
-    
-    // Refresh Button
-    let refresh_button = Button::with_label("Refresh Members");
-    refresh_button.connect_clicked(glib::clone!(
+    let self_test_action = gio::SimpleAction::new("self-test", None);
+    self_test_action.connect_activate(glib::clone!(
         #[weak]
-        list_store,
+        window,
+        #[strong]
+        library,
+        move |_, _| {
+            let results = library.read().unwrap().self_test();
+            let detail = results.iter().map(|r| r.describe()).collect::<Vec<_>>().join("\n");
+            if results.iter().all(|r| r.passed()) {
+                dialogs::show_info(&window, &tr!("Exports read back unchanged"), &detail);
+            } else {
+                dialogs::show_error(&window, &tr!("Exports don't read back unchanged"), &detail);
+            }
+        }
+    ));
+    self_test_action.set_enabled(session.role >= Role::Admin);
+    window.add_action(&self_test_action);
+
+    // Only offered in debug builds, for trying out pages on a full catalog
+    let seed_action = gio::SimpleAction::new("seed", None);
+    seed_action.connect_activate(glib::clone!(
         #[weak]
+        window,
+        #[strong]
         library,
-        move |_| {
-            list_store.clear();
-            for member in library.read().unwrap().members.values() {
-                let mut titles = String::new();
-                for inst in member.items.values() {
-                    titles += &(inst.title.as_str().to_owned() + " (" + &inst.id.to_string() +  "),  ");
+        move |_, _| {
+            let options = seed::SeedOptions::default();
+            let detail = tr!("{} items, {} members and {} loans will be added to the library.", options.items, options.members, options.loans);
+            dialogs::confirm(&window, &tr!("Generate demo data?"), &detail, glib::clone!(
+                #[weak]
+                window,
+                #[strong]
+                library,
+                move || {
+                    let report = library.write().unwrap().seed(&options);
+                    status::success(&window, &tr!("Added {} items, {} members and {} loans ({} still out, {} refused)", report.items, report.members, report.loans, report.on_loan, report.refused));
                 }
-                list_store.insert_with_values(None, &[
-                    (0, &member.id),
-                    (1, &titles),
-                ]);
-            }
+            ));
         }
     ));
-    
-    // Scrolled Window for TreeView
-    let scrolled_window = ScrolledWindow::new();
-    scrolled_window.set_child(Some(&tree_view));
-    scrolled_window.set_vexpand(true);
-    
-    // Add widgets to box
-    member_box.append(&refresh_button);
-    member_box.append(&scrolled_window);
-    
-    member_box
-}
-fn create_catalog_page(library: Arc<RwLock<Library>>) -> GtkBox {
-    let catalog_box = GtkBox::new(gtk::Orientation::Vertical, 10);
-    
-    let list_store = ListStore::new(&[
-        u32::static_type(),     // Item ID
-        String::static_type(),  // Title
-        String::static_type(),  // Author
-        u32::static_type(),     // Year
-        String::static_type(),  // Format
-        u32::static_type(),     // Total Copies
-        u32::static_type(),     // Available Copies
-        u32::static_type(),     // Ratings
-    ]);
-    
-    let tree_view = TreeView::with_model(&list_store);
-
-    let columns = [
-        ("Item ID", 0),
-        ("Title", 1),
-        ("Author", 2),
-        ("Year", 3),
-        ("Format", 4),
-        ("Total Copies", 5),
-        ("Available Copies", 6),
-        ("Ratings", 7)
-    ];
+    seed_action.set_enabled(cfg!(debug_assertions) && session.role >= Role::Admin);
+    window.add_action(&seed_action);
 
-    for (title, column_id) in columns.iter() {
-        let renderer = CellRendererText::new();
-        let column = TreeViewColumn::new();
-        column.set_title(title);
-        column.pack_start(&renderer, true);
-        column.add_attribute(&renderer, "text", *column_id);
-        tree_view.append_column(&column);
+    #[cfg(feature = "sip2")]
+    sip2::start(library.clone(), &config.borrow());
+    if config.borrow().dbus_service {
+        dbus::start(app, library.clone());
     }
+    plugins::start(library.clone(), config.clone());
+    webhooks::start(library.clone(), config.clone());
 
-    let refresh_button = Button::with_label("Refresh Catalog");
-    
-    let refresh_catalog = |list_store: &ListStore, library: &Library| {
-        list_store.clear();
-        for (_, item) in &library.items {
-            list_store.insert_with_values(None, &[
-                (0, &item.id),
-                (1, &item.title),
-                (2, &item.author.as_ref().map_or("Unknown".to_string(), |a| a.to_string())),
-                (3, &item.year),
-                (4, &item.format),
-                (5, &item.copies),
-                (6, &item.avail_copies),
-                (7, &item.ratings),
-            ]);
-        }
-    };
+    let catalog_csv = catalog.map_or_else(|| config.borrow().catalog_csv.clone(), |path| path.to_string_lossy().into_owned());
+    watch::watch_catalog_csv(window.upcast_ref(), library.clone(), &catalog_csv);
+    if session.role >= Role::Librarian {
+        dnd::install(window.upcast_ref(), &stack, library.clone());
+    }
 
-    // Populate catalog on startup
-    {
-        refresh_catalog(&list_store, &library.read().unwrap());
+    if let Some(sync_handle) = sync::start(&window, library.clone(), &config.borrow()) {
+        let resync_action = gio::SimpleAction::new("resync", None);
+        resync_action.connect_activate(move |_, _| sync_handle.request_snapshot());
+        window.add_action(&resync_action);
     }
 
+    shortcuts::install(app.upcast_ref(), window.upcast_ref(), &stack, library.clone(), shortcuts::ShortcutPages {
+        issue: issue_page,
+        returns: return_page,
+        catalog: catalog_page,
+    });
 
-    refresh_button.connect_clicked(glib::clone!(
+    let interval = config.borrow().backup_interval_minutes;
+    if interval > 0 {
+        glib::timeout_add_seconds_local(interval * 60, glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            config,
+            move || {
+                if let Err(e) = backup::backup_now(&library.read().unwrap(), &config.borrow()) {
+                    tracing::error!("Automatic backup failed: {}", e);
+                }
+                glib::ControlFlow::Continue
+            }
+        ));
+    }
+
+    glib::timeout_add_seconds_local(REPORT_CHECK_SECONDS, glib::clone!(
         #[weak]
-        list_store,
+        window,
         #[strong]
         library,
-        move |_| {
-            refresh_catalog(&list_store, &library.read().unwrap());
+        #[strong]
+        config,
+        #[upgrade_or]
+        glib::ControlFlow::Break,
+        move || {
+            let now = library.read().unwrap().clock.now().with_timezone(&chrono::Local);
+            if reports::weekly_report_due(&config.borrow(), now) {
+                // Marked before sending, so a failing server isn't retried
+                // every minute
+                config.borrow_mut().report_last_sent = Some(now.date_naive());
+                if let Err(e) = config.borrow().save() {
+                    tracing::error!("Failed to save settings: {}", e);
+                }
+                send_weekly_report(&window, &library, &config.borrow());
+            }
+            if notices::digest_due(&config.borrow(), now) {
+                config.borrow_mut().digest_last_sent = Some(now.date_naive());
+                if let Err(e) = config.borrow().save() {
+                    tracing::error!("Failed to save settings: {}", e);
+                }
+                send_member_digests(&window, &library, &config.borrow());
+            }
+            glib::ControlFlow::Continue
         }
     ));
 
-    let scrolled_window = ScrolledWindow::new();
-    scrolled_window.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
-    scrolled_window.set_child(Some(&tree_view));
-    scrolled_window.set_vexpand(true);
-
-    catalog_box.append(&refresh_button);
-    catalog_box.append(&scrolled_window);
-
-    catalog_box
+    glib::timeout_add_seconds_local(HOLD_EXPIRY_CHECK_SECONDS, glib::clone!(
+        #[strong]
+        library,
+        move || {
+            let expired = library.write().unwrap().expire_holds();
+            if expired > 0 {
+                tracing::info!(expired, "Uncollected holds expired");
+            }
+            glib::ControlFlow::Continue
+        }
+    ));
+
+    window.connect_destroy(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        config,
+        move |_| {
+            if let Err(e) = backup::backup_now(&library.read().unwrap(), &config.borrow()) {
+                tracing::error!("Backup on shutdown failed: {}", e);
+            }
+        }
+    ));
+
+    window.connect_close_request(glib::clone!(
+        #[strong]
+        library,
+        move |window| {
+            if !library.read().unwrap().dirty {
+                return glib::Propagation::Proceed;
+            }
+            confirm_close(window.upcast_ref(), library.clone());
+            glib::Propagation::Stop
+        }
+    ));
+
+    window.show();
+
+    match import_result {
+        None => {}
+        Some(Ok(report)) => {
+            if report.loaded == 0 || !report.errors.is_empty() {
+                dialogs::show_import_report(&window, report);
+            }
+        }
+        Some(Err(e)) => {
+            tracing::error!("Failed to initialize library: {}", e);
+            let cwd = std::env::current_dir().map(|p| p.display().to_string()).unwrap_or_default();
+            dialogs::show_error(
+                &window,
+                &tr!("Failed to load the library catalog"),
+                &tr!("{}\n\nCurrent working directory: {}", e, cwd),
+            );
+        }
+    }
+    window
+}
+
+// How often the weekly report schedule is checked
+const REPORT_CHECK_SECONDS: u32 = 60;
+
+// How often holds past their pickup deadline are looked for
+const HOLD_EXPIRY_CHECK_SECONDS: u32 = 300;
+
+// Mails the circulation report for the week ending today from a worker
+// thread, so a slow mail server doesn't hold up the window. The outcome is
+// shown in the status bar.
+fn send_weekly_report(widget: &impl IsA<gtk::Widget>, library: &Arc<RwLock<Library>>, config: &Config) {
+    let report = {
+        let lib = library.read().unwrap();
+        lib.weekly_report(lib.clock.now().with_timezone(&chrono::Local).date_naive())
+    };
+    let report = match report {
+        Ok(report) => report,
+        Err(e) => {
+            status::error(widget, &tr!("Failed to email the weekly report: {}", e));
+            return;
+        }
+    };
+    let config = config.clone();
+    let handle = gio::spawn_blocking(move || {
+        mail::send(&config, &config.report_recipients, &report.subject, &report.body, Some(report.attachment))
+            .map(|()| config.report_recipients.len())
+            .map_err(|e| e.to_string())
+    });
+    let widget = widget.clone().upcast::<gtk::Widget>();
+    glib::spawn_future_local(async move {
+        match handle.await {
+            Ok(Ok(recipients)) => {
+                tracing::info!(recipients, "Weekly report emailed");
+                status::success(&widget, &tr!("Weekly report emailed to {} recipient(s)", recipients));
+            }
+            Ok(Err(e)) => status::error(&widget, &tr!("Failed to email the weekly report: {}", e)),
+            Err(_) => status::error(&widget, &tr!("Failed to email the weekly report")),
+        }
+    });
+}
+
+// Mails each member their weekly digest from a worker thread, like the
+// weekly report. A failed address doesn't stop the others; the outcome is
+// shown in the status bar.
+fn send_member_digests(widget: &impl IsA<gtk::Widget>, library: &Arc<RwLock<Library>>, config: &Config) {
+    let digests = library.read().unwrap().member_digests();
+    if digests.is_empty() {
+        status::info(widget, &tr!("No member digests to send"));
+        return;
+    }
+    let config = config.clone();
+    let handle = gio::spawn_blocking(move || {
+        let mut sent = 0;
+        let mut errors = Vec::new();
+        for digest in digests {
+            match mail::send(&config, &[digest.to.clone()], &digest.subject, &digest.body, None) {
+                Ok(()) => sent += 1,
+                Err(e) => {
+                    tracing::warn!(member_id = digest.member_id, "Digest to {} failed: {}", digest.to, e);
+                    errors.push(format!("{}: {}", digest.to, e));
+                }
+            }
+        }
+        (sent, errors)
+    });
+    let widget = widget.clone().upcast::<gtk::Widget>();
+    glib::spawn_future_local(async move {
+        match handle.await {
+            Ok((sent, errors)) if errors.is_empty() => {
+                tracing::info!(sent, "Member digests emailed");
+                status::success(&widget, &tr!("Weekly digest emailed to {} member(s)", sent));
+            }
+            Ok((sent, errors)) => status::error(
+                &widget,
+                &tr!("Weekly digest emailed to {} member(s), {} failed: {}", sent, errors.len(), errors.join("; ")),
+            ),
+            Err(_) => status::error(&widget, &tr!("Failed to email the member digests")),
+        }
+    });
+}
+
+// Asks what to do with unsaved changes; the window is only destroyed once
+// they have been saved or explicitly discarded.
+fn confirm_close(window: &ApplicationWindow, library: Arc<RwLock<Library>>) {
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .message_type(gtk::MessageType::Question)
+        .buttons(gtk::ButtonsType::None)
+        .text(tr!("Save changes before closing?"))
+        .secondary_text(tr!("Loans and catalog changes made since the last save will be lost otherwise."))
+        .build();
+    dialog.add_buttons(&[
+        (tr!("Discard").as_str(), gtk::ResponseType::Reject),
+        (tr!("Cancel").as_str(), gtk::ResponseType::Cancel),
+        (tr!("Save").as_str(), gtk::ResponseType::Accept),
+    ]);
+    dialog.connect_response(glib::clone!(
+        #[weak]
+        window,
+        move |dialog, response| {
+            dialog.close();
+            match response {
+                gtk::ResponseType::Accept => {
+                    match library.write().unwrap().save(Path::new(persist::STATE_PATH)) {
+                        Ok(()) => window.destroy(),
+                        Err(e) => dialogs::show_error(&window, &tr!("Failed to save the library"), &e.to_string()),
+                    }
+                }
+                gtk::ResponseType::Reject => window.destroy(),
+                _ => {}
+            }
+        }
+    ));
+    dialog.show();
+}
+fn fill_item_completion(store: &ListStore, library: &Library) {
+    store.clear();
+    for item in library.items.values() {
+        store.insert_with_values(None, &[
+            (0, &format!("{} ({})", item.title, item.id)),
+            (1, &item.id),
+        ]);
+    }
+}
+
+fn fill_member_completion(store: &ListStore, library: &Library) {
+    store.clear();
+    for member in library.members.values() {
+        let label = if member.name.is_empty() {
+            tr!("Member {}", member.id)
+        } else {
+            format!("{} ({})", member.name, member.id)
+        };
+        store.insert_with_values(None, &[
+            (0, &label),
+            (1, &member.id),
+        ]);
+    }
+}
+
+// Suggests titles/member names while typing into an ID entry and replaces the
+// text with the matching ID once a suggestion is picked. The suggestions are
+// rebuilt from the library every time the entry gains focus.
+fn attach_completion(entry: &Entry, library: Arc<RwLock<Library>>, fill: fn(&ListStore, &Library)) {
+    let store = ListStore::new(&[
+        String::static_type(),  // Display text
+        u32::static_type(),     // ID
+    ]);
+
+    let completion = EntryCompletion::new();
+    completion.set_model(Some(&store));
+    completion.set_text_column(0);
+    completion.set_minimum_key_length(2);
+    completion.set_match_func(|completion, key, iter| {
+        let Some(model) = completion.model() else { return false };
+        let text: String = model.get(iter, 0);
+        text.to_lowercase().contains(&key.to_lowercase())
+    });
+    completion.connect_match_selected(glib::clone!(
+        #[weak]
+        entry,
+        #[upgrade_or]
+        glib::Propagation::Proceed,
+        move |_, model, iter| {
+            let id: u32 = model.get(iter, 1);
+            entry.set_text(&id.to_string());
+            entry.set_position(-1);
+            glib::Propagation::Stop
+        }
+    ));
+    entry.set_completion(Some(&completion));
+
+    let focus = gtk::EventControllerFocus::new();
+    focus.connect_enter(glib::clone!(
+        #[weak]
+        store,
+        move |_| {
+            fill(&store, &library.read().unwrap());
+        }
+    ));
+    entry.add_controller(focus);
+}
+
+// Days of circulation covered by the dashboard charts
+const CHART_DAYS: u64 = 90;
+const TOP_GENRES: usize = 10;
+
+// Refilled every time the page is shown
+fn create_dashboard_page(library: Arc<RwLock<Library>>, bus: &EventBus) -> GtkBox {
+    let dashboard_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let daily_chart = Chart::new(
+        ChartKind::Line,
+        &tr!("Checkouts per day, last {} days", CHART_DAYS),
+        "checkouts-per-day.png",
+    );
+    let genre_chart = Chart::new(ChartKind::Bar, &tr!("Top genres"), "top-genres.png");
+    let hour_chart = Chart::new(ChartKind::Bar, &tr!("Busiest hours"), "busiest-hours.png");
+    let charts_box = gtk::FlowBox::new();
+    charts_box.set_selection_mode(gtk::SelectionMode::None);
+    charts_box.set_max_children_per_line(3);
+    charts_box.set_homogeneous(true);
+    charts_box.append(&daily_chart.widget);
+    charts_box.append(&genre_chart.widget);
+    charts_box.append(&hour_chart.widget);
+
+    let expiring_table = Rc::new(Table::<Member>::new());
+    expiring_table.add_column(&tr!("Member ID"), |m| m.id.to_string(), |a, b| a.id.cmp(&b.id));
+    expiring_table.add_column(&tr!("Name"), |m| m.name.clone(), |a, b| a.name.cmp(&b.name));
+    expiring_table.add_column(&tr!("Membership"), |m| m.membership.name().to_string(), |a, b| a.membership.cmp(&b.membership));
+    expiring_table.add_column(&tr!("Expires"), |m| m.expires_at.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()), |a, b| a.expires_at.cmp(&b.expires_at));
+
+    let expiring_window = ScrolledWindow::new();
+    expiring_window.set_child(Some(&expiring_table.view));
+    expiring_window.set_vexpand(true);
+
+    dashboard_box.append(&charts_box);
+    dashboard_box.append(&Label::new(Some(&tr!("Memberships expired or expiring in the next {} days:", EXPIRY_WARNING_DAYS))));
+    dashboard_box.append(&expiring_window);
+
+    let fill = Rc::new(move || {
+        let lib = library.read().unwrap();
+        let members = lib.expiring_members(EXPIRY_WARNING_DAYS);
+        let today = lib.clock.now().with_timezone(&chrono::Local).date_naive();
+        let from = today - chrono::Days::new(CHART_DAYS - 1);
+        let days = lib.circulation_summary(from, today, Period::Day);
+        let genres = lib.top_genres(from, TOP_GENRES);
+        let hours = lib.checkouts_by_hour(from);
+        drop(lib);
+
+        expiring_table.set_rows(members);
+        daily_chart.set_points(days.iter().map(|s| (s.start.format("%m-%d").to_string(), s.checkouts as f64)).collect());
+        genre_chart.set_points(genres.into_iter().map(|(genre, n)| (genre, n as f64)).collect());
+        hour_chart.set_points(hours.iter().enumerate().map(|(hour, &n)| (format!("{:02}:00", hour), n as f64)).collect());
+    });
+    dashboard_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&dashboard_box, fill);
+
+    dashboard_box
+}
+
+// The member to issue to, registering them first if "New member" is ticked.
+// The entry then shows the new ID, so trying again doesn't register twice.
+fn member_for_issue(lib: &mut Library, new_member_check: &gtk::CheckButton, member_id_entry: &Entry) -> Result<(String, Option<u32>), String> {
+    if !new_member_check.is_active() {
+        return Ok((member_id_entry.text().trim().to_string(), None));
+    }
+    let member_id = lib.register_member(&member_id_entry.text())?;
+    member_id_entry.set_text(&member_id.to_string());
+    new_member_check.set_active(false);
+    Ok((member_id.to_string(), Some(member_id)))
+}
+
+fn create_issue_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let issue_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let item_id_label = Label::new(Some(&tr!("Item ID:")));
+    let item_id_entry = Entry::new();
+    let member_id_label = Label::new(Some(&tr!("Member ID:")));
+    let member_id_entry = Entry::new();
+    let new_member_check = gtk::CheckButton::with_label(&tr!("New member"));
+    new_member_check.connect_toggled(glib::clone!(
+        #[weak] member_id_label,
+        move |check| {
+            member_id_label.set_text(&if check.is_active() { tr!("New member's name:") } else { tr!("Member ID:") });
+        }
+    ));
+
+    attach_completion(&item_id_entry, library.clone(), fill_item_completion);
+    attach_completion(&member_id_entry, library.clone(), fill_member_completion);
+
+    // Items collected for a single checkout of several items
+    let pending_items: Rc<RefCell<Vec<u32>>> = Rc::new(RefCell::new(Vec::new()));
+    let pending_label = Label::new(None);
+    pending_label.set_wrap(true);
+    let add_button = Button::with_label(&tr!("Add to Checkout List"));
+    let issue_all_button = Button::with_label(&tr!("Issue All Listed Items"));
+    let clear_button = Button::with_label(&tr!("Clear List"));
+
+    let show_pending = |pending_label: &Label, pending: &[u32], library: &Library| {
+        let titles: Vec<String> = pending
+            .iter()
+            .map(|id| match library.items.get(id) {
+                Some(item) => format!("{} ({})", item.title, id),
+                None => id.to_string(),
+            })
+            .collect();
+        pending_label.set_text(&if titles.is_empty() {
+            String::new()
+        } else {
+            tr!("Checkout list: {}", titles.join(", "))
+        });
+    };
+
+    add_button.connect_clicked(glib::clone!(
+        #[weak] item_id_entry,
+        #[weak] issue_box,
+        #[weak] pending_label,
+        #[strong] pending_items,
+        #[strong] library,
+        move |_| {
+            let Ok(item_id) = item_id_entry.text().parse::<u32>() else {
+                status::error(&issue_box, &tr!("Invalid Item ID"));
+                return;
+            };
+            let lib = library.read().unwrap();
+            let mut pending = pending_items.borrow_mut();
+            match lib.items.get(&item_id) {
+                None => status::error(&issue_box, &tr!("Invalid Item ID!")),
+                Some(_) if pending.contains(&item_id) => status::error(&issue_box, &tr!("Item is already on the list")),
+                Some(item) if item.avail_copies == 0 => status::error(&issue_box, &tr!("No available copies left!")),
+                Some(_) if pending.len() >= lib.max_loan_limit() => {
+                    status::error(&issue_box, &tr!("Loan limit of {} items reached", lib.max_loan_limit()));
+                }
+                Some(_) => {
+                    pending.push(item_id);
+                    item_id_entry.set_text("");
+                }
+            }
+            show_pending(&pending_label, &pending, &lib);
+        }
+    ));
+
+    issue_all_button.connect_clicked(glib::clone!(
+        #[weak] member_id_entry,
+        #[weak] new_member_check,
+        #[weak] issue_box,
+        #[weak] pending_label,
+        #[strong] pending_items,
+        #[strong] library,
+        move |_| {
+            let mut lib = library.write().unwrap();
+            let mut pending = pending_items.borrow_mut();
+            if pending.is_empty() {
+                status::error(&issue_box, &tr!("No items to issue"));
+                return;
+            }
+            let (member_id_text, registered) = match member_for_issue(&mut lib, &new_member_check, &member_id_entry) {
+                Ok(member) => member,
+                Err(e) => {
+                    status::error(&issue_box, &e);
+                    return;
+                }
+            };
+            match lib.book_issue_batch(&pending, member_id_text) {
+                Ok(()) => {
+                    status::success(&issue_box, &match registered {
+                        Some(member_id) => tr!("Member {} registered, {} items issued", member_id, pending.len()),
+                        None => tr!("{} items issued successfully!", pending.len()),
+                    });
+                    pending.clear();
+                    member_id_entry.set_text("");
+                }
+                Err(e) => status::error(&issue_box, &match registered {
+                    Some(member_id) => tr!("Member {} registered, but nothing was issued: {}", member_id, e),
+                    None => tr!("{} (nothing was issued)", e),
+                }),
+            }
+            show_pending(&pending_label, &pending, &lib);
+        }
+    ));
+
+    clear_button.connect_clicked(glib::clone!(
+        #[weak] pending_label,
+        #[strong] pending_items,
+        move |_| {
+            pending_items.borrow_mut().clear();
+            pending_label.set_text("");
+        }
+    ));
+
+    let issue_button = Button::with_label(&tr!("Issue Book"));
+    issue_button.connect_clicked(glib::clone!(
+        #[weak] item_id_entry,
+        #[weak] member_id_entry,
+        #[weak] new_member_check,
+        #[weak] issue_box,
+        #[strong] library,  // Keep the Arc alive
+        move |_| {
+            let item_id_text = item_id_entry.text().to_string();
+            if let Ok(item_id) = item_id_text.parse::<u32>() {
+                let mut lib = library.write().unwrap(); // Lock for writing here
+                let (member_id_text, registered) = match member_for_issue(&mut lib, &new_member_check, &member_id_entry) {
+                    Ok(member) => member,
+                    Err(e) => {
+                        status::error(&issue_box, &e);
+                        return;
+                    }
+                };
+                match lib.book_issue(item_id, member_id_text) {
+                    Ok(_) => {
+                        status::success(&issue_box, &match registered {
+                            Some(member_id) => tr!("Member {} registered, book issued", member_id),
+                            None => tr!("Book issued successfully!"),
+                        });
+                        item_id_entry.set_text("");
+                        member_id_entry.set_text("");
+                    }
+                    Err(e) => status::error(&issue_box, &match registered {
+                        Some(member_id) => tr!("Member {} registered, but the book was not issued: {}", member_id, e),
+                        None => e,
+                    }),
+                }
+            } else {
+                status::error(&issue_box, &tr!("Invalid Item ID"));
+            }
+        }
+    ));
+
+    issue_box.append(&item_id_label);
+    issue_box.append(&item_id_entry);
+    issue_box.append(&member_id_label);
+    issue_box.append(&member_id_entry);
+    issue_box.append(&new_member_check);
+    issue_box.append(&issue_button);
+
+    let batch_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    batch_box.append(&add_button);
+    batch_box.append(&issue_all_button);
+    batch_box.append(&clear_button);
+    issue_box.append(&batch_box);
+    issue_box.append(&pending_label);
+
+    issue_box
+}
+
+fn create_return_page(library: Arc<RwLock<Library>>, users: Rc<RefCell<UserStore>>, role: Role) -> GtkBox {
+    let return_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let item_id_label = Label::new(Some(&tr!("Item ID:")));
+    let item_id_entry = Entry::new();
+    let member_id_label = Label::new(Some(&tr!("Member ID:")));
+    let member_id_entry = Entry::new();
+    let book_details_label = Label::new(None);
+    let fine_label = Label::new(None);
+    let condition_check = gtk::CheckButton::with_label(&tr!("Ask for the condition of returned items"));
+
+    // The fine assessed by the last return, waiting to be collected or waived
+    let pending_fine: Rc<Cell<Option<(u32, u32)>>> = Rc::new(Cell::new(None));
+    let collect_button = Button::with_label(&tr!("Collect Payment"));
+    let waive_button = Button::with_label(&tr!("Waive"));
+    let fine_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    fine_box.append(&collect_button);
+    fine_box.append(&waive_button);
+    fine_box.set_visible(false);
+
+    attach_completion(&item_id_entry, library.clone(), fill_item_completion);
+    attach_completion(&member_id_entry, library.clone(), fill_member_completion);
+
+    collect_button.connect_clicked(glib::clone!(
+        #[weak] return_box,
+        #[weak] fine_label,
+        #[weak] fine_box,
+        #[strong] pending_fine,
+        #[strong] library,
+        move |_| {
+            let Some((member_id, fine_id)) = pending_fine.get() else { return };
+            let result = library.write().unwrap().take_payment(member_id, fine_id);
+            match result {
+                Ok(amount) => {
+                    status::success(&return_box, &tr!("Fine of {} collected", format_money(amount)));
+                    pending_fine.set(None);
+                    fine_label.set_text("");
+                    fine_box.set_visible(false);
+                }
+                // Left on screen, e.g. to take it once a cash session is open
+                Err(e) => status::error(&return_box, &e),
+            }
+        }
+    ));
+
+    waive_button.connect_clicked(glib::clone!(
+        #[weak] return_box,
+        #[weak] fine_label,
+        #[weak] fine_box,
+        #[strong] pending_fine,
+        #[strong] library,
+        #[strong] users,
+        move |button| {
+            let Some((member_id, fine_id)) = pending_fine.get() else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let (amount, needs_approval, current_user) = {
+                let lib = library.read().unwrap();
+                let amount = lib
+                    .members
+                    .get(&member_id)
+                    .and_then(|m| m.fines.iter().find(|f| f.id == fine_id))
+                    .map_or(0, |f| f.amount_cents);
+                (amount, lib.waiver_needs_approval(amount), lib.current_user.clone())
+            };
+            // Administrators approve their own waivers
+            let self_approved = needs_approval && role >= Role::Admin;
+            dialogs::show_waive_fine(&window, &format_money(amount), needs_approval && !self_approved, users.clone(), glib::clone!(
+                #[weak] return_box,
+                #[weak] fine_label,
+                #[weak] fine_box,
+                #[strong] pending_fine,
+                #[strong] library,
+                #[strong] current_user,
+                #[upgrade_or] Ok(()),
+                move |reason: WaiverReason, approved_by| {
+                    let approved_by = approved_by.or_else(|| self_approved.then(|| current_user.clone()));
+                    let amount = library.write().unwrap().waive_fine(member_id, fine_id, reason, approved_by)?;
+                    status::success(&return_box, &tr!("Fine of {} waived ({})", format_money(amount), reason.label()));
+                    pending_fine.set(None);
+                    fine_label.set_text("");
+                    fine_box.set_visible(false);
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    let renew_button = Button::with_label(&tr!("Renew Book"));
+    renew_button.connect_clicked(glib::clone!(
+        #[weak] item_id_entry,
+        #[weak] member_id_entry,
+        #[weak] return_box,
+        #[strong] library,
+        move |_| {
+            let (Ok(item_id), Ok(member_id)) = (
+                item_id_entry.text().parse::<u32>(),
+                member_id_entry.text().parse::<u32>(),
+            ) else {
+                status::error(&return_box, &tr!("Invalid Item or Member ID"));
+                return;
+            };
+            let result = {
+                let mut lib = library.write().unwrap();
+                let item_id = lib.physical_unit(item_id);
+                lib.renew_loan(item_id, member_id).map(|_| lib.members[&member_id].items[&item_id].due_text())
+            };
+            match result {
+                Ok(due) => {
+                    status::success(&return_box, &tr!("Renewed, now due {}", due));
+                    item_id_entry.set_text("");
+                    member_id_entry.set_text("");
+                }
+                Err(e) => status::error(&return_box, &e),
+            }
+        }
+    ));
+
+    let return_button = Button::with_label(&tr!("Return Book"));
+    return_button.connect_clicked(glib::clone!(
+        #[weak] item_id_entry,
+        #[weak] member_id_entry,
+        #[weak] return_box,
+        #[weak] book_details_label,
+        #[weak] fine_label,
+        #[weak] fine_box,
+        #[weak] condition_check,
+        #[strong] pending_fine,
+        #[strong] library,
+        move |_| {
+            let item_id_text = item_id_entry.text().to_string();
+            let member_id_text = member_id_entry.text().to_string();
+
+            if let Ok(item_id) = item_id_text.parse::<u32>() {
+                if let Ok(member_id) = member_id_text.parse::<u32>() {
+                    let mut lib = library.write().unwrap(); // Lock for writing
+                    let item_id = lib.physical_unit(item_id);
+                    let shelved_before = lib.on_hold_shelf(item_id).len();
+                    match lib.book_return(item_id, member_id) {
+                        Ok((book, fine)) => {
+                            book_details_label.set_text(&tr!(
+                                "Returned Book: {} (ID: {})",
+                                book.title, book.id
+                            ));
+                            let shelved = lib.on_hold_shelf(item_id);
+                            match shelved.last().filter(|_| shelved.len() > shelved_before) {
+                                Some(hold) => {
+                                    let name = lib.members.get(&hold.member_id).map_or("?", |m| m.name.as_str());
+                                    status::info(&return_box, &tr!("Book returned. Put this copy on the hold shelf for {}", name));
+                                }
+                                None => status::success(&return_box, &tr!("Book returned successfully!")),
+                            }
+                            match fine {
+                                Some(fine) => {
+                                    fine_label.set_text(&tr!(
+                                        "Returned {} days late \u{2014} fine: {}",
+                                        fine.days_late, format_money(fine.amount_cents)
+                                    ));
+                                    pending_fine.set(Some((member_id, fine.id)));
+                                    fine_box.set_visible(true);
+                                }
+                                None => {
+                                    fine_label.set_text("");
+                                    pending_fine.set(None);
+                                    fine_box.set_visible(false);
+                                }
+                            }
+                            let window = return_box.root().and_downcast::<gtk::Window>();
+                            if let (true, Some(window), Some(item)) = (condition_check.is_active(), window, lib.items.get(&item_id)) {
+                                dialogs::show_change_condition(&window, &tr!("Condition of {}", item.title), &item.condition_text(), glib::clone!(
+                                    #[strong] library,
+                                    move |from, to, note| library.write().unwrap().change_condition(item_id, from, to, &note)
+                                ));
+                            }
+                            item_id_entry.set_text("");
+                            member_id_entry.set_text("");
+                        }
+                        Err(e) => {
+                            status::error(&return_box, &e);
+                            book_details_label.set_text("");
+                        }
+                    }
+                } else {
+                    status::error(&return_box, &tr!("Invalid Member ID"));
+                }
+            } else {
+                status::error(&return_box, &tr!("Invalid Item ID"));
+            }
+        }
+    ));
+
+    return_box.append(&item_id_label);
+    return_box.append(&item_id_entry);
+    return_box.append(&member_id_label);
+    return_box.append(&member_id_entry);
+    return_box.append(&condition_check);
+    return_box.append(&return_button);
+    return_box.append(&renew_button);
+    return_box.append(&book_details_label);
+    return_box.append(&fine_label);
+    return_box.append(&fine_box);
+
+    return_box
+}
+
+// Adds an action to a table's context menu group. The action gets the window
+// the table is shown in.
+fn add_row_action(group: &gio::SimpleActionGroup, name: &str, view: &gtk::ColumnView, activate: impl Fn(&gtk::Window) + 'static) {
+    let action = gio::SimpleAction::new(name, None);
+    let view = view.downgrade();
+    action.connect_activate(move |_, _| {
+        let Some(window) = view.upgrade().and_then(|v| v.root()).and_downcast::<gtk::Window>() else { return };
+        activate(&window);
+    });
+    group.add_action(&action);
+}
+
+// Adds "Copy as CSV", "Copy as TSV" and "Export Selection..." to a
+// table's context menu, for pasting rows into a spreadsheet or an email,
+// and turns on multi-row selection. `table` finds the table in `owner`,
+// which the actions hold weakly like the other row actions. Tables of
+// member data pass the library as `personal`, so exports follow its policy
+// for those.
+fn add_selection_actions<V: 'static, T: 'static>(
+    owner: &Rc<V>,
+    table: fn(&V) -> &Table<T>,
+    actions: &gio::SimpleActionGroup,
+    menu: &gio::Menu,
+    default_name: &'static str,
+    personal: Option<Arc<RwLock<Library>>>,
+) {
+    let view = &table(owner).view;
+    table(owner).set_multi_select();
+    for (name, delimiter) in [("copy-csv", b','), ("copy-tsv", b'\t')] {
+        let weak_owner = Rc::downgrade(owner);
+        add_row_action(actions, name, view, move |window| {
+            let Some(owner) = weak_owner.upgrade() else { return };
+            let table = table(&owner);
+            match table.selection_as_text(delimiter) {
+                Ok(text) => {
+                    table.view.clipboard().set_text(&text);
+                    status::success(window, &tr!("Copied {} row(s)", table.selected_rows(|_| ()).len()));
+                }
+                Err(e) => dialogs::show_error(window, &tr!("Failed to copy the rows"), &e.to_string()),
+            }
+        });
+    }
+    let weak_owner = Rc::downgrade(owner);
+    add_row_action(actions, "export-selection", view, move |window| {
+        let weak_owner = weak_owner.clone();
+        let export = glib::clone!(
+            #[weak]
+            window,
+            move |path: PathBuf, passphrase: Option<String>| {
+                let Some(owner) = weak_owner.upgrade() else { return };
+                // Tab-separated if saved as .tsv
+                let delimiter = if path.extension().is_some_and(|e| e.eq_ignore_ascii_case("tsv")) { b'\t' } else { b',' };
+                let result = table(&owner)
+                    .selection_as_text(delimiter)
+                    .and_then(|text| pii::write_export(&path, text.as_bytes(), passphrase.as_deref()));
+                if let Err(e) = result {
+                    dialogs::show_error(&window, &tr!("Failed to export the selection"), &e.to_string());
+                }
+            }
+        );
+        match &personal {
+            Some(library) => {
+                let allow_plain = library.read().unwrap().allow_plain_pii_export;
+                dialogs::choose_personal_export_path(window, &tr!("Export Selection"), default_name, allow_plain, export);
+            }
+            None => dialogs::choose_save_path(window, &tr!("Export Selection"), default_name, move |path| export(path, None)),
+        }
+    });
+    let section = gio::Menu::new();
+    section.append(Some(&tr!("Copy as CSV")), Some("row.copy-csv"));
+    section.append(Some(&tr!("Copy as TSV")), Some("row.copy-tsv"));
+    section.append(Some(&tr!("Export Selection...")), Some("row.export-selection"));
+    menu.append_section(None, &section);
+}
+
+fn member_summary(library: &Library, member_id: u32) -> String {
+    let Some(member) = library.members.get(&member_id) else {
+        return tr!("Member not found");
+    };
+    let outstanding: u64 = member
+        .fines
+        .iter()
+        .filter(|f| f.status == FineStatus::Outstanding)
+        .map(|f| f.amount_cents)
+        .sum();
+    let mut lines = vec![
+        format!("{} ({})", member.name, member.id),
+        tr!("Membership: {}", member.membership.name()),
+        tr!("Expires: {}", member.expires_at.map_or_else(|| tr!("Never"), |at| at.format("%Y-%m-%d").to_string())),
+        tr!("Email: {}", member.email.as_deref().unwrap_or("-")),
+        tr!("External ID: {}", member.external_id.as_deref().unwrap_or("-")),
+        tr!("Outstanding fines: {}", format_money(outstanding)),
+        tr!("Reading challenge: {}", member.challenge.as_ref().map_or_else(|| "-".to_string(), |c| c.progress_text())),
+        tr!("On loan ({}):", member.items.len()),
+    ];
+    for inst in member.items.values() {
+        lines.push(tr!("  {} ({}), due {}", inst.title, inst.id, inst.due_text()));
+    }
+    lines.join("\n")
+}
+
+fn return_all_of_selected(table: &Table<Member>, library: &Arc<RwLock<Library>>, window: &gtk::Window) {
+    let Some(member_id) = table.selected(|m| m.id) else { return };
+    let mut lib = library.write().unwrap();
+    match lib.return_all(member_id) {
+        Ok(outcomes) => {
+            let summary = outcomes
+                .iter()
+                .map(|o| match &o.result {
+                    Ok(None) => tr!("{} ({}): returned", o.title, o.item_id),
+                    Ok(Some(fine)) => tr!(
+                        "{} ({}): returned {} days late, fine {}",
+                        o.title, o.item_id, fine.days_late, format_money(fine.amount_cents)
+                    ),
+                    Err(e) => format!("{} ({}): {}", o.title, o.item_id, e),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+            dialogs::show_info(window, &tr!("Returned {} item(s) for member {}", outcomes.len(), member_id), &summary);
+            let members: Vec<Member> = lib.members.values().cloned().collect();
+            // Selection handlers read the library again
+            drop(lib);
+            table.set_rows(members);
+        }
+        Err(e) => dialogs::show_error(window, &tr!("Return failed"), &e),
+    }
+}
+
+fn create_member_details_page(library: Arc<RwLock<Library>>, bus: &EventBus, role: Role) -> GtkBox {
+    let member_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    
+    let table = Rc::new(Table::<Member>::new());
+    table.add_column(&tr!("Member ID"), |m| m.id.to_string(), |a, b| a.id.cmp(&b.id));
+    table.add_column(&tr!("Name"), |m| m.name.clone(), |a, b| a.name.cmp(&b.name));
+    table.add_column(&tr!("Email"), |m| m.email.clone().unwrap_or_default(), |a, b| a.email.cmp(&b.email));
+    table.add_column(&tr!("Membership"), |m| m.membership.name().to_string(), |a, b| a.membership.cmp(&b.membership));
+    table.add_column(&tr!("Expires"), |m| m.expires_at.map_or_else(|| tr!("Never"), |at| at.format("%Y-%m-%d").to_string()), |a, b| a.expires_at.cmp(&b.expires_at));
+    table.add_column(&tr!("Item Titles"), |m| {
+        let mut titles = String::new();
+        for inst in m.items.values() {
+            titles += &(inst.title.to_string() + " (" + &inst.id.to_string() +  "),  ");
+        }
+        titles
+    }, |a, b| a.items.len().cmp(&b.items.len()));
+
+    // Loans of the selected member
+    let loans_table = Rc::new(Table::<LiItemInstance>::new());
+    loans_table.add_column(&tr!("Item ID"), |i| i.id.to_string(), |a, b| a.id.cmp(&b.id));
+    loans_table.add_column(&tr!("Title"), |i| i.title.to_string(), |a, b| a.title.cmp(&b.title));
+    loans_table.add_column(&tr!("Issued"), |i| i.issued_at.format("%Y-%m-%d").to_string(), |a, b| a.issued_at.cmp(&b.issued_at));
+    loans_table.add_column(&tr!("Due"), |i| i.due_text(), |a, b| a.due_date.cmp(&b.due_date));
+    loans_table.add_column(&tr!("Renewals"), |i| i.renewed_count.to_string(), |a, b| a.renewed_count.cmp(&b.renewed_count));
+
+    let history_table = Rc::new(Table::<PastLoan>::new());
+    history_table.add_column(&tr!("Item ID"), |l| l.item_id.to_string(), |a, b| a.item_id.cmp(&b.item_id));
+    history_table.add_column(&tr!("Title"), |l| l.title.to_string(), |a, b| a.title.cmp(&b.title));
+    history_table.add_column(&tr!("Issued"), |l| l.issued_at.format("%Y-%m-%d").to_string(), |a, b| a.issued_at.cmp(&b.issued_at));
+    history_table.add_column(&tr!("Returned"), |l| l.returned_at.format("%Y-%m-%d").to_string(), |a, b| a.returned_at.cmp(&b.returned_at));
+    let history_actions = gio::SimpleActionGroup::new();
+    let history_menu = gio::Menu::new();
+    add_selection_actions(&history_table, |t| t, &history_actions, &history_menu, "history.csv", Some(library.clone()));
+    history_table.set_context_menu(&history_menu, &history_actions);
+
+    // Receipts handed to the selected member, newest first
+    let receipts_table = Rc::new(Table::<Receipt>::new());
+    receipts_table.add_column(&tr!("Receipt"), |r| r.id.to_string(), |a, b| a.id.cmp(&b.id));
+    receipts_table.add_column(&tr!("Date"), |r| r.at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(), |a, b| a.at.cmp(&b.at));
+    receipts_table.add_column(&tr!("Kind"), |r| r.kind.label(), |a, b| a.kind.label().cmp(&b.kind.label()));
+    receipts_table.add_column(&tr!("Item ID"), |r| r.item_id.to_string(), |a, b| a.item_id.cmp(&b.item_id));
+    receipts_table.add_column(&tr!("Title"), |r| r.title.to_string(), |a, b| a.title.cmp(&b.title));
+    receipts_table.add_column(&tr!("Details"), |r| r.detail.clone(), |a, b| a.detail.cmp(&b.detail));
+    let reprint_button = Button::with_label(&tr!("Reprint Receipt..."));
+    reprint_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        receipts_table,
+        move |button| {
+            let Some((member_id, name)) = table.selected(|m| (m.id, m.name.clone())) else { return };
+            let Some(receipt) = receipts_table.selected(|r| r.clone()) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let title = tr!("{} Receipt {} - {} ({})", receipt.kind.label(), receipt.id, name, member_id);
+            let result = print::print_table(&window, &title, receipts::receipt_headers(), receipts::receipt_rows(&receipt), None);
+            if let Err(e) = result {
+                dialogs::show_error(&window, &tr!("Failed to print receipt"), &e);
+            }
+        }
+    ));
+
+    let keep_history_check = gtk::CheckButton::with_label(&tr!("Keep borrowing history for this member"));
+    let digest_check = gtk::CheckButton::with_label(&tr!("Weekly digest email"));
+    digest_check.set_tooltip_text(Some(&tr!("Untick for members who don't want the weekly summary of their loans, holds and fines.")));
+    // Set while the check box follows the selection, so that doesn't count
+    // as the member changing their choice
+    let syncing_check = Rc::new(Cell::new(false));
+
+    let recommended_label = Label::new(None);
+    recommended_label.set_xalign(0.0);
+    recommended_label.set_wrap(true);
+
+    table.selection.connect_selected_item_notify(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        loans_table,
+        #[strong]
+        history_table,
+        #[strong]
+        receipts_table,
+        #[strong]
+        library,
+        #[weak]
+        recommended_label,
+        #[weak]
+        keep_history_check,
+        #[weak]
+        digest_check,
+        #[strong]
+        syncing_check,
+        move |_| {
+            let loans = table.selected(|m| m.items.values().cloned().collect::<Vec<_>>());
+            loans_table.set_rows(loans.unwrap_or_default());
+            let history = table.selected(|m| (m.keep_history, m.history.clone()));
+            let (keep, history) = history.unwrap_or_default();
+            history_table.set_rows(history);
+            let receipts = table.selected(|m| m.receipts.iter().rev().cloned().collect::<Vec<_>>());
+            receipts_table.set_rows(receipts.unwrap_or_default());
+            let digest = table.selected(|m| !m.digest_opt_out);
+            syncing_check.set(true);
+            keep_history_check.set_active(keep);
+            digest_check.set_sensitive(digest.is_some());
+            digest_check.set_active(digest.unwrap_or(false));
+            syncing_check.set(false);
+
+            let text = match table.selected(|m| m.id) {
+                Some(member_id) => {
+                    let lib = library.read().unwrap();
+                    match lib.recommend_for(member_id).as_slice() {
+                        [] => tr!("No recommendations yet."),
+                        ids => lib.titles_of(ids),
+                    }
+                }
+                None => String::new(),
+            };
+            recommended_label.set_text(&text);
+        }
+    ));
+    
+    keep_history_check.connect_toggled(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        #[strong]
+        syncing_check,
+        move |check| {
+            if syncing_check.get() {
+                return;
+            }
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let result = library.write().unwrap().set_keep_history(member_id, check.is_active());
+            if let Err(e) = result {
+                tracing::warn!("Failed to change history setting: {}", e);
+                return;
+            }
+            let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+            table.set_rows(members);
+        }
+    ));
+
+    digest_check.connect_toggled(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        #[strong]
+        syncing_check,
+        move |check| {
+            if syncing_check.get() {
+                return;
+            }
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            if let Err(e) = library.write().unwrap().set_digest_opt_out(member_id, !check.is_active()) {
+                tracing::warn!("Failed to change digest setting: {}", e);
+            }
+        }
+    ));
+
+    // Reading challenge of the selected member
+    let challenge_label = Label::new(None);
+    challenge_label.set_xalign(0.0);
+    challenge_label.set_wrap(true);
+    let goal_spin = gtk::SpinButton::with_range(1.0, 1000.0, 1.0);
+    goal_spin.set_value(12.0);
+    let leaderboard_check = gtk::CheckButton::with_label(&tr!("Show first name on the kiosk leaderboard"));
+    let set_goal_button = Button::with_label(&tr!("Set Goal for This Year"));
+    let end_challenge_button = Button::with_label(&tr!("End Challenge"));
+    table.selection.connect_selected_item_notify(glib::clone!(
+        #[strong]
+        table,
+        #[weak]
+        challenge_label,
+        #[weak]
+        goal_spin,
+        #[weak]
+        leaderboard_check,
+        move |_| {
+            let challenge = table.selected(|m| m.challenge.clone()).flatten();
+            let text = match &challenge {
+                Some(c) => format!("{}\n{}", c.progress_text(), tr!("Badges: {}", c.badges_text())),
+                None => tr!("Not taking part in a reading challenge."),
+            };
+            challenge_label.set_text(&text);
+            if let Some(c) = challenge {
+                goal_spin.set_value(c.goal as f64);
+                leaderboard_check.set_active(c.on_leaderboard);
+            }
+        }
+    ));
+    for (button, ends) in [(&set_goal_button, false), (&end_challenge_button, true)] {
+        button.connect_clicked(glib::clone!(
+            #[strong]
+            table,
+            #[strong]
+            library,
+            #[weak]
+            goal_spin,
+            #[weak]
+            leaderboard_check,
+            #[weak]
+            member_box,
+            move |_| {
+                let Some(member_id) = table.selected(|m| m.id) else { return };
+                let goal = if ends { 0 } else { goal_spin.value_as_int() as u32 };
+                let result = library.write().unwrap().set_reading_challenge(member_id, goal, leaderboard_check.is_active());
+                if let Err(e) = result {
+                    status::error(&member_box, &e);
+                    return;
+                }
+                let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                table.set_rows(members);
+            }
+        ));
+    }
+
+    let export_history_button = Button::with_label(&tr!("Export History CSV..."));
+    export_history_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let name = format!("history-{}.csv", member_id);
+            let allow_plain = library.read().unwrap().allow_plain_pii_export;
+            dialogs::choose_personal_export_path(&window, &tr!("Export Borrowing History"), &name, allow_plain, glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path, passphrase| {
+                    if let Err(e) = library.read().unwrap().export_history_csv(member_id, &path, passphrase.as_deref()) {
+                        dialogs::show_error(&window, &tr!("Failed to export history"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    let goodreads_export_button = Button::with_label(&tr!("Export for Goodreads..."));
+    goodreads_export_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let name = format!("goodreads-{}.csv", member_id);
+            let allow_plain = library.read().unwrap().allow_plain_pii_export;
+            dialogs::choose_personal_export_path(&window, &tr!("Export History for Goodreads"), &name, allow_plain, glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path, passphrase| {
+                    if let Err(e) = library.read().unwrap().export_history_goodreads_csv(member_id, &path, passphrase.as_deref()) {
+                        dialogs::show_error(&window, &tr!("Failed to export history"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    let return_all_button = Button::with_label(&tr!("Return All Items of Selected Member"));
+    return_all_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            return_all_of_selected(&table, &library, &window);
+        }
+    ));
+
+    // The action group ends up on the table's view, so the actions only
+    // hold on to the table weakly.
+    let actions = gio::SimpleActionGroup::new();
+    let weak_table = Rc::downgrade(&table);
+    add_row_action(&actions, "details", &table.view, glib::clone!(
+        #[strong]
+        weak_table,
+        #[strong]
+        library,
+        move |window| {
+            let Some(table) = weak_table.upgrade() else { return };
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let summary = member_summary(&library.read().unwrap(), member_id);
+            dialogs::show_info(window, &tr!("Member Details"), &summary);
+        }
+    ));
+    add_row_action(&actions, "return-all", &table.view, glib::clone!(
+        #[strong]
+        weak_table,
+        #[strong]
+        library,
+        move |window| {
+            let Some(table) = weak_table.upgrade() else { return };
+            return_all_of_selected(&table, &library, window);
+        }
+    ));
+    add_row_action(&actions, "send-notice", &table.view, glib::clone!(
+        #[strong]
+        weak_table,
+        #[strong]
+        library,
+        move |window| {
+            let Some(table) = weak_table.upgrade() else { return };
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let result = library.write().unwrap().overdue_notice(member_id);
+            match result {
+                Ok(text) => dialogs::show_info(window, &tr!("Overdue notice (copy and send to the member)"), &text),
+                Err(e) => dialogs::show_error(window, &tr!("No notice sent"), &e),
+            }
+        }
+    ));
+    add_row_action(&actions, "edit", &table.view, glib::clone!(
+        #[strong]
+        weak_table,
+        #[strong]
+        library,
+        move |window| {
+            let Some(table) = weak_table.upgrade() else { return };
+            let Some((member_id, initial, version)) = table.selected(|m| (m.id, MemberDetails::of(m), m.version)) else { return };
+            dialogs::show_edit_member(window, initial, version, glib::clone!(
+                #[strong]
+                weak_table,
+                #[strong]
+                library,
+                move |details, version| {
+                    library.write().unwrap().edit_member(member_id, version, details)?;
+                    if let Some(table) = weak_table.upgrade() {
+                        let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                        table.set_rows(members);
+                    }
+                    Ok(())
+                }
+            ));
+        }
+    ));
+    add_row_action(&actions, "delete", &table.view, glib::clone!(
+        #[strong]
+        weak_table,
+        #[strong]
+        library,
+        move |window| {
+            let Some(table) = weak_table.upgrade() else { return };
+            let Some((member_id, name)) = table.selected(|m| (m.id, m.name.clone())) else { return };
+            dialogs::confirm(window, &tr!("Delete \"{}\"?", name), &tr!("The member is moved to the trash and can be restored from there."), glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move || {
+                    let result = library.write().unwrap().delete_member(member_id);
+                    if let Err(e) = result {
+                        dialogs::show_error(&window, &tr!("Cannot delete member"), &e);
+                    }
+                }
+            ));
+        }
+    ));
+    let menu = gio::Menu::new();
+    menu.append(Some(&tr!("View Details")), Some("row.details"));
+    menu.append(Some(&tr!("Return All Items")), Some("row.return-all"));
+    menu.append(Some(&tr!("Send Overdue Notice")), Some("row.send-notice"));
+    menu.append(Some(&tr!("Edit...")), Some("row.edit"));
+    if role >= Role::Librarian {
+        menu.append(Some(&tr!("Delete...")), Some("row.delete"));
+    }
+    add_selection_actions(&table, |t| t, &actions, &menu, "members.csv", Some(library.clone()));
+    table.set_context_menu(&menu, &actions);
+
+    let type_names: Vec<&str> = MembershipType::ALL.iter().map(|t| t.name()).collect();
+    let type_dropdown = gtk::DropDown::from_strings(&type_names);
+    let set_type_button = Button::with_label(&tr!("Set Membership Type"));
+    set_type_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        #[weak]
+        type_dropdown,
+        move |button| {
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let kind = MembershipType::ALL[type_dropdown.selected() as usize % MembershipType::ALL.len()];
+            let result = library.write().unwrap().set_membership(member_id, kind);
+            match result {
+                Ok(()) => {
+                    let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                    table.set_rows(members);
+                }
+                Err(e) => {
+                    let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+                    dialogs::show_error(&window, &tr!("Failed to change membership type"), &e);
+                }
+            }
+        }
+    ));
+
+    let renew_button = Button::with_label(&tr!("Renew Membership"));
+    renew_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some(member_id) = table.selected(|m| m.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let result = library.write().unwrap().renew_membership(member_id);
+            match result {
+                Ok(expires_at) => {
+                    dialogs::show_info(&window, &tr!("Membership renewed"), &tr!("Now expires on {}", expires_at.format("%Y-%m-%d")));
+                    let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                    table.set_rows(members);
+                }
+                Err(e) => dialogs::show_error(&window, &tr!("Failed to renew membership"), &e),
+            }
+        }
+    ));
+
+    let import_button = Button::with_label(&tr!("Import Members CSV..."));
+    import_button.set_visible(role >= Role::Librarian);
+    import_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_open_path(&window, &tr!("Import Members CSV"), glib::clone!(
+                #[strong]
+                table,
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path| {
+                    dialogs::show_import_wizard(&window, library.clone(), path, ImportKind::Members, glib::clone!(
+                        #[strong]
+                        table,
+                        #[strong]
+                        library,
+                        move || {
+                            let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                            table.set_rows(members);
+                        }
+                    ));
+                }
+            ));
+        }
+    ));
+
+    let export_button = Button::with_label(&tr!("Export Members CSV..."));
+    export_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let allow_plain = library.read().unwrap().allow_plain_pii_export;
+            dialogs::choose_personal_export_path(&window, &tr!("Export Members"), "members.csv", allow_plain, glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path, passphrase| {
+                    if let Err(e) = library.read().unwrap().export_members_csv(&path, passphrase.as_deref()) {
+                        dialogs::show_error(&window, &tr!("Failed to export members"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    let anonymize_button = Button::with_label(&tr!("Anonymize Selected Member..."));
+    anonymize_button.set_visible(role >= Role::Admin);
+    anonymize_button.connect_clicked(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |button| {
+            let Some((member_id, name)) = table.selected(|m| (m.id, m.name.clone())) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::confirm(
+                &window,
+                &tr!("Anonymize {} ({})?", name, member_id),
+                &tr!("Their name, contact details, reading history and review texts are removed for good. This cannot be undone."),
+                glib::clone!(
+                    #[strong]
+                    table,
+                    #[strong]
+                    library,
+                    #[weak]
+                    window,
+                    move || {
+                        let result = library.write().unwrap().anonymize_member(member_id);
+                        match result {
+                            Ok(()) => {
+                                let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+                                table.set_rows(members);
+                            }
+                            Err(e) => dialogs::show_error(&window, &tr!("Failed to anonymize member"), &e),
+                        }
+                    }
+                ),
+            );
+        }
+    ));
+
+    let members: Vec<Member> = library.read().unwrap().members.values().cloned().collect();
+    table.set_rows(members);
+
+    // Members changed elsewhere, e.g. by an issue or a payment, are redrawn
+    // in place; new members refill the table
+    bus.subscribe(&member_box, glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move |changes| {
+            let lib = library.read().unwrap();
+            if changes.iter().any(|c| c.changes_member_list()) {
+                let members: Vec<Member> = lib.members.values().cloned().collect();
+                drop(lib);
+                table.set_rows(members);
+                return;
+            }
+            let changed: HashMap<u32, Member> = changes
+                .iter()
+                .filter_map(|c| c.member_id())
+                .filter_map(|id| lib.members.get(&id))
+                .map(|m| (m.id, m.clone()))
+                .collect();
+            drop(lib);
+            if changed.is_empty() {
+                return;
+            }
+            table.update_rows(|row| changed.get(&row.id).cloned());
+            // The loans and history below follow the selected member
+            if table.selected(|m| changed.contains_key(&m.id)).unwrap_or(false) {
+                table.selection.notify("selected-item");
+            }
+        }
+    ));
+    
+    // Scrolled Window for the member table
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+    
+    // Add widgets to box
+    let loans_window = ScrolledWindow::new();
+    loans_window.set_child(Some(&loans_table.view));
+    loans_window.set_vexpand(true);
+
+    let top_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    top_box.append(&import_button);
+    top_box.append(&export_button);
+
+    member_box.append(&top_box);
+    member_box.append(&scrolled_window);
+    let history_window = ScrolledWindow::new();
+    history_window.set_child(Some(&history_table.view));
+    history_window.set_vexpand(true);
+
+    let history_controls = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    history_controls.append(&keep_history_check);
+    history_controls.append(&export_history_button);
+    history_controls.append(&goodreads_export_button);
+
+    let history_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    history_box.append(&history_window);
+    history_box.append(&history_controls);
+
+    // Loans and history of the selected member
+    let member_notebook = gtk::Notebook::new();
+    member_notebook.append_page(&loans_window, Some(&Label::new(Some(&tr!("Current Loans")))));
+    member_notebook.append_page(&history_box, Some(&Label::new(Some(&tr!("Borrowing History")))));
+    let receipts_window = ScrolledWindow::new();
+    receipts_window.set_child(Some(&receipts_table.view));
+    receipts_window.set_vexpand(true);
+    let receipts_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    receipts_box.append(&receipts_window);
+    receipts_box.append(&reprint_button);
+    member_notebook.append_page(&receipts_box, Some(&Label::new(Some(&tr!("Receipts")))));
+    let goal_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    goal_box.append(&Label::new(Some(&tr!("Books this year:"))));
+    goal_box.append(&goal_spin);
+    goal_box.append(&leaderboard_check);
+    goal_box.append(&set_goal_button);
+    goal_box.append(&end_challenge_button);
+    let challenge_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    challenge_box.append(&challenge_label);
+    challenge_box.append(&goal_box);
+    member_notebook.append_page(&challenge_box, Some(&Label::new(Some(&tr!("Reading Challenge")))));
+
+    member_box.append(&member_notebook);
+    member_box.append(&Label::new(Some(&tr!("Recommended:"))));
+    member_box.append(&recommended_label);
+    let membership_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    membership_box.append(&type_dropdown);
+    membership_box.append(&set_type_button);
+    membership_box.append(&renew_button);
+    membership_box.append(&anonymize_button);
+    membership_box.append(&digest_check);
+
+    member_box.append(&membership_box);
+    member_box.append(&return_all_button);
+    
+    member_box
+}
+fn create_settings_page(library: Arc<RwLock<Library>>, config: Rc<RefCell<Config>>) -> GtkBox {
+    let settings_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let calendar = library.read().unwrap().calendar.clone();
+    let weekdays = [
+        Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+        Weekday::Fri, Weekday::Sat, Weekday::Sun,
+    ];
+    let weekday_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    let weekday_checks: Vec<(Weekday, gtk::CheckButton)> = weekdays
+        .iter()
+        .map(|&day| {
+            let check = gtk::CheckButton::with_label(&day.to_string());
+            check.set_active(calendar.closed_weekdays.contains(&day));
+            weekday_box.append(&check);
+            (day, check)
+        })
+        .collect();
+
+    let holidays_view = gtk::TextView::new();
+    holidays_view.buffer().set_text(
+        &calendar.holidays.iter().map(|d| d.format("%Y-%m-%d").to_string()).collect::<Vec<_>>().join("\n"),
+    );
+    let holidays_window = ScrolledWindow::new();
+    holidays_window.set_min_content_height(120);
+    holidays_window.set_child(Some(&holidays_view));
+
+    let policy_grid = gtk::Grid::new();
+    policy_grid.set_row_spacing(5);
+    policy_grid.set_column_spacing(10);
+    for (column, heading) in [tr!("Membership"), tr!("Loan limit"), tr!("Loan days"), tr!("Fine per day (cents)"), tr!("Restricted formats")].iter().enumerate() {
+        policy_grid.attach(&Label::new(Some(heading.as_str())), column as i32, 0, 1, 1);
+    }
+    let policy_rows: Vec<(MembershipType, gtk::SpinButton, gtk::SpinButton, gtk::SpinButton, Entry)> = {
+        let lib = library.read().unwrap();
+        MembershipType::ALL
+            .iter()
+            .enumerate()
+            .map(|(i, &kind)| {
+                let policy = lib.policy(kind);
+                let limit_spin = gtk::SpinButton::with_range(0.0, 1000.0, 1.0);
+                limit_spin.set_value(policy.loan_limit as f64);
+                let days_spin = gtk::SpinButton::with_range(1.0, 365.0, 1.0);
+                days_spin.set_value(policy.loan_days as f64);
+                let fine_spin = gtk::SpinButton::with_range(0.0, 10000.0, 5.0);
+                fine_spin.set_value(policy.fine_per_day_cents as f64);
+                let formats_entry = Entry::new();
+                formats_entry.set_text(&policy.restricted_formats.join(", "));
+                formats_entry.set_hexpand(true);
+
+                let row = i as i32 + 1;
+                policy_grid.attach(&Label::new(Some(kind.name())), 0, row, 1, 1);
+                policy_grid.attach(&limit_spin, 1, row, 1, 1);
+                policy_grid.attach(&days_spin, 2, row, 1, 1);
+                policy_grid.attach(&fine_spin, 3, row, 1, 1);
+                policy_grid.attach(&formats_entry, 4, row, 1, 1);
+                (kind, limit_spin, days_spin, fine_spin, formats_entry)
+            })
+            .collect()
+    };
+
+    let hourly_entry = Entry::new();
+    hourly_entry.set_text(&loans::format_hourly_formats(&library.read().unwrap().hourly_formats));
+    hourly_entry.set_placeholder_text(Some("laptop=4, hotspot=72"));
+    let pickup_days_spin = gtk::SpinButton::with_range(1.0, 60.0, 1.0);
+    pickup_days_spin.set_value(library.read().unwrap().hold_pickup_days as f64);
+    let waiver_approval_entry = Entry::new();
+    waiver_approval_entry.set_text(&format_money(library.read().unwrap().waiver_approval_cents));
+    let grace_days_spin = gtk::SpinButton::with_range(0.0, 30.0, 1.0);
+    grace_days_spin.set_value(library.read().unwrap().fine_grace_days as f64);
+    let max_fine_entry = Entry::new();
+    max_fine_entry.set_text(&format_money(library.read().unwrap().max_fine_cents));
+    let amnesty = library.read().unwrap().amnesty;
+    let amnesty_check = gtk::CheckButton::with_label(&tr!("Fine amnesty from"));
+    amnesty_check.set_active(amnesty.is_some());
+    let today = library.read().unwrap().clock.now().with_timezone(&chrono::Local).date_naive();
+    let (amnesty_from_button, amnesty_from_calendar) = date_picker(amnesty.map_or(today, |a| a.from));
+    let (amnesty_to_button, amnesty_to_calendar) = date_picker(amnesty.map_or(today, |a| a.to));
+
+    let backup_dir_entry = Entry::new();
+    backup_dir_entry.set_text(&config.borrow().backup_dir);
+    let reports_dir_entry = Entry::new();
+    reports_dir_entry.set_text(&config.borrow().reports_dir);
+    let catalog_csv_entry = Entry::new();
+    catalog_csv_entry.set_text(&config.borrow().catalog_csv);
+    catalog_csv_entry.set_tooltip_text(Some(&tr!("Changes to this file are offered for reloading. Takes effect after a restart.")));
+    let interval_spin = gtk::SpinButton::with_range(0.0, 1440.0, 1.0);
+    interval_spin.set_value(config.borrow().backup_interval_minutes as f64);
+    let retention_spin = gtk::SpinButton::with_range(1.0, 1000.0, 1.0);
+    retention_spin.set_value(config.borrow().backup_retention as f64);
+    let anonymize_spin = gtk::SpinButton::with_range(0.0, 100.0, 1.0);
+    anonymize_spin.set_value(config.borrow().anonymize_after_years as f64);
+    let idle_lock_spin = gtk::SpinButton::with_range(0.0, 240.0, 1.0);
+    idle_lock_spin.set_value(config.borrow().idle_lock_minutes as f64);
+    idle_lock_spin.set_tooltip_text(Some(&tr!("Takes effect after a restart.")));
+    let appearance_names: Vec<&str> = Appearance::ALL.iter().map(|a| a.name()).collect();
+    let appearance_dropdown = gtk::DropDown::from_strings(&appearance_names);
+    let appearance_index = Appearance::ALL.iter().position(|&a| a == config.borrow().appearance).unwrap_or(0);
+    appearance_dropdown.set_selected(appearance_index as u32);
+    let mut language_names = vec![tr!("System Default")];
+    language_names.extend(i18n::LANGUAGES.iter().map(|(_, name)| name.to_string()));
+    let language_dropdown = gtk::DropDown::from_strings(&language_names.iter().map(String::as_str).collect::<Vec<_>>());
+    let language_index = i18n::LANGUAGES.iter().position(|(code, _)| *code == config.borrow().language).map_or(0, |i| i + 1);
+    language_dropdown.set_selected(language_index as u32);
+    let smtp_host_entry = Entry::new();
+    smtp_host_entry.set_text(&config.borrow().smtp_host);
+    smtp_host_entry.set_placeholder_text(Some(&tr!("Leave empty to turn email off")));
+    let smtp_port_spin = gtk::SpinButton::with_range(1.0, 65535.0, 1.0);
+    smtp_port_spin.set_value(config.borrow().smtp_port as f64);
+    let smtp_username_entry = Entry::new();
+    smtp_username_entry.set_text(&config.borrow().smtp_username);
+    let smtp_password_entry = gtk::PasswordEntry::new();
+    smtp_password_entry.set_show_peek_icon(true);
+    smtp_password_entry.set_text(&config.borrow().smtp_password);
+    let mail_from_entry = Entry::new();
+    mail_from_entry.set_text(&config.borrow().mail_from);
+    mail_from_entry.set_placeholder_text(Some("Library <library@example.org>"));
+    let recipients_entry = Entry::new();
+    recipients_entry.set_text(&config.borrow().report_recipients.join(", "));
+    recipients_entry.set_placeholder_text(Some(&tr!("Comma-separated; leave empty to turn the report off")));
+    let report_day_names: Vec<String> = weekdays.iter().map(|d| d.to_string()).collect();
+    let report_day_dropdown = gtk::DropDown::from_strings(&report_day_names.iter().map(String::as_str).collect::<Vec<_>>());
+    report_day_dropdown.set_selected(config.borrow().report_weekday.num_days_from_monday());
+    let report_hour_spin = gtk::SpinButton::with_range(0.0, 23.0, 1.0);
+    report_hour_spin.set_value(config.borrow().report_hour as f64);
+    let digest_check = gtk::CheckButton::with_label(&tr!("Email members a weekly digest of their loans, holds and fines"));
+    digest_check.set_active(config.borrow().digest_enabled);
+    digest_check.set_tooltip_text(Some(&tr!("Members without an email address or who opted out on the Members page are left out.")));
+    let digest_day_dropdown = gtk::DropDown::from_strings(&report_day_names.iter().map(String::as_str).collect::<Vec<_>>());
+    digest_day_dropdown.set_selected(config.borrow().digest_weekday.num_days_from_monday());
+    let digest_hour_spin = gtk::SpinButton::with_range(0.0, 23.0, 1.0);
+    digest_hour_spin.set_value(config.borrow().digest_hour as f64);
+    let sync_names: Vec<&str> = SyncMode::ALL.iter().map(|m| m.name()).collect();
+    let sync_dropdown = gtk::DropDown::from_strings(&sync_names);
+    sync_dropdown.set_selected(SyncMode::ALL.iter().position(|&m| m == config.borrow().sync_mode).unwrap_or(0) as u32);
+    let sync_address_entry = Entry::new();
+    sync_address_entry.set_text(&config.borrow().sync_address);
+    sync_address_entry.set_placeholder_text(Some("host:9001"));
+    sync_address_entry.set_tooltip_text(Some(&tr!("A server listens on 127.0.0.1:9001, this computer only, unless given an address such as 0.0.0.0:9001.")));
+    let sync_secret_entry = gtk::PasswordEntry::new();
+    sync_secret_entry.set_show_peek_icon(true);
+    sync_secret_entry.set_text(&config.borrow().sync_secret);
+    sync_secret_entry.set_tooltip_text(Some(&tr!("The server and every desk connecting to it need the same secret.")));
+    let sru_url_entry = Entry::new();
+    sru_url_entry.set_text(&config.borrow().sru_url);
+    sru_url_entry.set_placeholder_text(Some(&tr!("Leave empty to turn catalog searches off")));
+    let dbus_check = gtk::CheckButton::with_label(&tr!("Offer the catalog to other applications over D-Bus"));
+    dbus_check.set_active(config.borrow().dbus_service);
+    dbus_check.set_tooltip_text(Some(&tr!("Lets scripts and desktop tools search the catalog and issue items as the logged-in user. Takes effect after a restart.")));
+    let website_url_entry = Entry::new();
+    website_url_entry.set_text(&config.borrow().website_url);
+    website_url_entry.set_placeholder_text(Some("https://library.example.org/catalog"));
+    let plain_pii_check = gtk::CheckButton::with_label(&tr!("Allow exporting member data without a passphrase"));
+    plain_pii_check.set_active(library.read().unwrap().allow_plain_pii_export);
+    plain_pii_check.set_tooltip_text(Some(&tr!("Member lists, borrowing histories and query results are otherwise always encrypted when exported.")));
+    let webhook_urls_entry = Entry::new();
+    webhook_urls_entry.set_text(&config.borrow().webhook_urls.join(", "));
+    webhook_urls_entry.set_placeholder_text(Some(&tr!("Comma-separated; leave empty to turn webhooks off")));
+    webhook_urls_entry.set_tooltip_text(Some(&tr!("Takes effect after a restart.")));
+    let webhook_events_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    let webhook_checks: Vec<gtk::CheckButton> = WebhookEvent::ALL
+        .iter()
+        .map(|event| {
+            let check = gtk::CheckButton::with_label(&event.label());
+            check.set_active(config.borrow().webhook_events.contains(event));
+            webhook_events_box.append(&check);
+            check
+        })
+        .collect();
+
+    // Previewed right away, but only kept once the settings are saved
+    appearance_dropdown.connect_selected_notify(|dropdown| {
+        apply_appearance(Appearance::ALL[dropdown.selected() as usize % Appearance::ALL.len()]);
+    });
+
+    let save_button = Button::with_label(&tr!("Save Settings"));
+    save_button.connect_clicked(glib::clone!(
+        #[weak] holidays_view,
+        #[weak] hourly_entry,
+        #[weak] pickup_days_spin,
+        #[weak] waiver_approval_entry,
+        #[weak] grace_days_spin,
+        #[weak] max_fine_entry,
+        #[weak] amnesty_check,
+        #[weak] amnesty_from_calendar,
+        #[weak] amnesty_to_calendar,
+        #[weak] backup_dir_entry,
+        #[weak] reports_dir_entry,
+        #[weak] catalog_csv_entry,
+        #[weak] interval_spin,
+        #[weak] retention_spin,
+        #[weak] anonymize_spin,
+        #[weak] idle_lock_spin,
+        #[weak] appearance_dropdown,
+        #[weak] language_dropdown,
+        #[weak] smtp_host_entry,
+        #[weak] smtp_port_spin,
+        #[weak] smtp_username_entry,
+        #[weak] smtp_password_entry,
+        #[weak] mail_from_entry,
+        #[weak] recipients_entry,
+        #[weak] report_day_dropdown,
+        #[weak] report_hour_spin,
+        #[weak] digest_check,
+        #[weak] digest_day_dropdown,
+        #[weak] digest_hour_spin,
+        #[weak] sync_dropdown,
+        #[weak] sync_address_entry,
+        #[weak] sync_secret_entry,
+        #[weak] sru_url_entry,
+        #[weak] website_url_entry,
+        #[weak] dbus_check,
+        #[weak] plain_pii_check,
+        #[weak] webhook_urls_entry,
+        #[strong] webhook_checks,
+        #[weak] settings_box,
+        #[strong] library,
+        #[strong] config,
+        move |_| {
+            let buffer = holidays_view.buffer();
+            let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            let mut holidays = BTreeSet::new();
+            for line in text.lines().map(str::trim).filter(|l| !l.is_empty()) {
+                match NaiveDate::parse_from_str(line, "%Y-%m-%d") {
+                    Ok(date) => {
+                        holidays.insert(date);
+                    }
+                    Err(_) => {
+                        status::error(&settings_box, &tr!("\"{}\" is not a YYYY-MM-DD date", line));
+                        return;
+                    }
+                }
+            }
+
+            let hourly_formats = match loans::parse_hourly_formats(&hourly_entry.text()) {
+                Ok(formats) => formats,
+                Err(e) => {
+                    status::error(&settings_box, &e);
+                    return;
+                }
+            };
+
+            let waiver_approval_cents = match fines::parse_money(&waiver_approval_entry.text()) {
+                Ok(cents) => cents,
+                Err(e) => {
+                    status::error(&settings_box, &e);
+                    return;
+                }
+            };
+            let max_fine_cents = match fines::parse_money(&max_fine_entry.text()) {
+                Ok(cents) => cents,
+                Err(e) => {
+                    status::error(&settings_box, &e);
+                    return;
+                }
+            };
+            let amnesty = amnesty_check.is_active().then(|| Amnesty {
+                from: calendar_date(&amnesty_from_calendar),
+                to: calendar_date(&amnesty_to_calendar),
+            });
+            if amnesty.is_some_and(|a| a.to < a.from) {
+                status::error(&settings_box, &tr!("The amnesty ends before it starts"));
+                return;
+            }
+
+            // A sync client gets the library's rules from the server with
+            // each snapshot, so only the desk's own settings are saved there
+            let rules_saved = library.read().unwrap().only_at_server().is_ok();
+            if rules_saved {
+                let mut lib = library.write().unwrap();
+                lib.hourly_formats = hourly_formats;
+                lib.waiver_approval_cents = waiver_approval_cents;
+                if lib.allow_plain_pii_export != plain_pii_check.is_active() {
+                    tracing::warn!(allowed = plain_pii_check.is_active(), "Unencrypted member data exports changed");
+                }
+                lib.allow_plain_pii_export = plain_pii_check.is_active();
+                lib.fine_grace_days = grace_days_spin.value_as_int() as u32;
+                lib.max_fine_cents = max_fine_cents;
+                lib.amnesty = amnesty;
+                lib.hold_pickup_days = pickup_days_spin.value_as_int() as u32;
+                lib.calendar = ClosureCalendar {
+                    closed_weekdays: weekday_checks.iter().filter(|(_, c)| c.is_active()).map(|(d, _)| *d).collect(),
+                    holidays,
+                };
+                lib.policies = policy_rows
+                    .iter()
+                    .map(|(kind, limit_spin, days_spin, fine_spin, formats_entry)| {
+                        let policy = MembershipPolicy {
+                            loan_limit: limit_spin.value_as_int() as usize,
+                            loan_days: days_spin.value_as_int() as u32,
+                            fine_per_day_cents: fine_spin.value_as_int() as u64,
+                            restricted_formats: facets::parse_tags(&formats_entry.text()),
+                        };
+                        (*kind, policy)
+                    })
+                    .collect();
+                lib.dirty = true;
+            }
+
+            let mut config = config.borrow_mut();
+            config.backup_dir = backup_dir_entry.text().to_string();
+            config.reports_dir = reports_dir_entry.text().to_string();
+            config.catalog_csv = catalog_csv_entry.text().trim().to_string();
+            config.backup_interval_minutes = interval_spin.value_as_int() as u32;
+            config.backup_retention = retention_spin.value_as_int() as usize;
+            config.anonymize_after_years = anonymize_spin.value_as_int() as u32;
+            config.idle_lock_minutes = idle_lock_spin.value_as_int() as u32;
+            config.appearance = Appearance::ALL[appearance_dropdown.selected() as usize % Appearance::ALL.len()];
+            config.language = match language_dropdown.selected() as usize {
+                0 => String::new(),
+                i => i18n::LANGUAGES.get(i - 1).map_or(String::new(), |(code, _)| code.to_string()),
+            };
+            config.smtp_host = smtp_host_entry.text().trim().to_string();
+            config.smtp_port = smtp_port_spin.value_as_int() as u16;
+            config.smtp_username = smtp_username_entry.text().trim().to_string();
+            config.smtp_password = smtp_password_entry.text().to_string();
+            config.mail_from = mail_from_entry.text().trim().to_string();
+            config.report_recipients = recipients_entry
+                .text()
+                .split(',')
+                .map(str::trim)
+                .filter(|a| !a.is_empty())
+                .map(str::to_string)
+                .collect();
+            config.report_weekday = weekdays[report_day_dropdown.selected() as usize % weekdays.len()];
+            config.report_hour = report_hour_spin.value_as_int() as u32;
+            config.digest_enabled = digest_check.is_active();
+            config.digest_weekday = weekdays[digest_day_dropdown.selected() as usize % weekdays.len()];
+            config.digest_hour = digest_hour_spin.value_as_int() as u32;
+            config.sync_mode = SyncMode::ALL[sync_dropdown.selected() as usize % SyncMode::ALL.len()];
+            config.sync_address = sync_address_entry.text().trim().to_string();
+            config.sync_secret = sync_secret_entry.text().to_string();
+            config.sru_url = sru_url_entry.text().trim().to_string();
+            config.website_url = website_url_entry.text().trim().to_string();
+            config.dbus_service = dbus_check.is_active();
+            config.webhook_urls = webhook_urls_entry
+                .text()
+                .split(',')
+                .map(str::trim)
+                .filter(|u| !u.is_empty())
+                .map(str::to_string)
+                .collect();
+            config.webhook_events =
+                WebhookEvent::ALL.iter().zip(&webhook_checks).filter(|(_, check)| check.is_active()).map(|(&event, _)| event).collect();
+            match config.save() {
+                Ok(()) if !rules_saved => status::info(
+                    &settings_box,
+                    &tr!("Settings saved; the library's rules come from the sync server, so change them there"),
+                ),
+                Ok(()) => status::success(&settings_box, &tr!("Settings saved")),
+                Err(e) => status::error(&settings_box, &e.to_string()),
+            }
+        }
+    ));
+
+    let backup_grid = gtk::Grid::new();
+    backup_grid.set_row_spacing(5);
+    backup_grid.set_column_spacing(10);
+    backup_grid.attach(&Label::new(Some(&tr!("Backup directory:"))), 0, 0, 1, 1);
+    backup_grid.attach(&backup_dir_entry, 1, 0, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Backup every (minutes, 0 = off):"))), 0, 1, 1, 1);
+    backup_grid.attach(&interval_spin, 1, 1, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Backups to keep:"))), 0, 2, 1, 1);
+    backup_grid.attach(&retention_spin, 1, 2, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Anonymize members inactive for (years, 0 = never):"))), 0, 3, 1, 1);
+    backup_grid.attach(&anonymize_spin, 1, 3, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Lock the screen when idle for (minutes, 0 = never):"))), 0, 4, 1, 1);
+    backup_grid.attach(&idle_lock_spin, 1, 4, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Appearance:"))), 0, 5, 1, 1);
+    backup_grid.attach(&appearance_dropdown, 1, 5, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Language:"))), 0, 6, 1, 1);
+    backup_grid.attach(&language_dropdown, 1, 6, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Mail server (SMTP):"))), 0, 7, 1, 1);
+    backup_grid.attach(&smtp_host_entry, 1, 7, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Port:"))), 0, 8, 1, 1);
+    backup_grid.attach(&smtp_port_spin, 1, 8, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Username:"))), 0, 9, 1, 1);
+    backup_grid.attach(&smtp_username_entry, 1, 9, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Password:"))), 0, 10, 1, 1);
+    backup_grid.attach(&smtp_password_entry, 1, 10, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Send mail as:"))), 0, 11, 1, 1);
+    backup_grid.attach(&mail_from_entry, 1, 11, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Email the weekly report to:"))), 0, 12, 1, 1);
+    backup_grid.attach(&recipients_entry, 1, 12, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Send it every:"))), 0, 13, 1, 1);
+    backup_grid.attach(&report_day_dropdown, 1, 13, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("From (hour):"))), 0, 14, 1, 1);
+    backup_grid.attach(&report_hour_spin, 1, 14, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Share the library with other desks:"))), 0, 15, 1, 1);
+    backup_grid.attach(&sync_dropdown, 1, 15, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Sync address:"))), 0, 16, 1, 1);
+    backup_grid.attach(&sync_address_entry, 1, 16, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Sync secret:"))), 0, 17, 1, 1);
+    backup_grid.attach(&sync_secret_entry, 1, 17, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("External catalog (SRU):"))), 0, 18, 1, 1);
+    backup_grid.attach(&sru_url_entry, 1, 18, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Catalog website address:"))), 0, 19, 1, 1);
+    backup_grid.attach(&website_url_entry, 1, 19, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Reports directory:"))), 0, 20, 1, 1);
+    backup_grid.attach(&reports_dir_entry, 1, 20, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Catalog CSV:"))), 0, 21, 1, 1);
+    backup_grid.attach(&catalog_csv_entry, 1, 21, 1, 1);
+    backup_grid.attach(&dbus_check, 0, 22, 2, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Post circulation events to:"))), 0, 23, 1, 1);
+    backup_grid.attach(&webhook_urls_entry, 1, 23, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Events to post:"))), 0, 24, 1, 1);
+    backup_grid.attach(&webhook_events_box, 1, 24, 1, 1);
+    backup_grid.attach(&plain_pii_check, 0, 25, 2, 1);
+    backup_grid.attach(&digest_check, 0, 26, 2, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("Send digests every:"))), 0, 27, 1, 1);
+    backup_grid.attach(&digest_day_dropdown, 1, 27, 1, 1);
+    backup_grid.attach(&Label::new(Some(&tr!("From (hour):"))), 0, 28, 1, 1);
+    backup_grid.attach(&digest_hour_spin, 1, 28, 1, 1);
+
+    // Goes out with the saved settings, to try them
+    let send_report_button = Button::with_label(&tr!("Send Weekly Report Now"));
+    send_report_button.connect_clicked(glib::clone!(
+        #[strong] library,
+        #[strong] config,
+        #[weak] settings_box,
+        move |_| {
+            let config = config.borrow();
+            if !mail::is_configured(&config) || config.report_recipients.is_empty() {
+                status::error(&settings_box, &tr!("Save a mail server, sender and recipients first"));
+                return;
+            }
+            status::info(&settings_box, &tr!("Sending the weekly report..."));
+            send_weekly_report(&settings_box, &library, &config);
+        }
+    ));
+
+    let send_digests_button = Button::with_label(&tr!("Send Member Digests Now"));
+    send_digests_button.connect_clicked(glib::clone!(
+        #[strong] library,
+        #[strong] config,
+        #[weak] settings_box,
+        move |_| {
+            let config = config.borrow();
+            if !mail::is_configured(&config) {
+                status::error(&settings_box, &tr!("Save a mail server and sender first"));
+                return;
+            }
+            status::info(&settings_box, &tr!("Sending the member digests..."));
+            send_member_digests(&settings_box, &library, &config);
+        }
+    ));
+
+    let retention_button = Button::with_label(&tr!("Apply Retention Policy Now..."));
+    retention_button.connect_clicked(glib::clone!(
+        #[strong] library,
+        #[strong] config,
+        #[weak] settings_box,
+        move |button| {
+            let years = config.borrow().anonymize_after_years;
+            if years == 0 {
+                status::info(&settings_box, &tr!("The retention policy is off"));
+                return;
+            }
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let count = library.read().unwrap().inactive_members(years).len();
+            if count == 0 {
+                status::info(&settings_box, &tr!("No members have been inactive for {} years", years));
+                return;
+            }
+            dialogs::confirm(
+                &window,
+                &tr!("Anonymize {} inactive member(s)?", count),
+                &tr!("Members without activity for {} years lose their personal data for good.", years),
+                glib::clone!(
+                    #[strong] library,
+                    #[weak] settings_box,
+                    move || {
+                        let count = library.write().unwrap().anonymize_inactive(years);
+                        status::success(&settings_box, &tr!("Anonymized {} member(s)", count));
+                    }
+                ),
+            );
+        }
+    ));
+
+    let export_profile_button = Button::with_label(&tr!("Export Profile..."));
+    export_profile_button.connect_clicked(glib::clone!(
+        #[strong] library,
+        #[strong] config,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_save_path(&window, &tr!("Export Configuration Profile"), "profile.json", glib::clone!(
+                #[strong] library,
+                #[strong] config,
+                #[weak] window,
+                move |path| {
+                    if let Err(e) = library.read().unwrap().export_profile(&config.borrow(), &path) {
+                        dialogs::show_error(&window, &tr!("Failed to export the profile"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    let import_profile_button = Button::with_label(&tr!("Import Profile..."));
+    import_profile_button.connect_clicked(glib::clone!(
+        #[strong] library,
+        #[strong] config,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_open_path(&window, &tr!("Import Configuration Profile"), glib::clone!(
+                #[strong] library,
+                #[strong] config,
+                #[weak] window,
+                move |path| {
+                    dialogs::confirm(
+                        &window,
+                        &tr!("Import the profile {}?", path.display()),
+                        &tr!("Loan policies, opening days, fine rules and the shared settings are replaced by those in the profile. Paths, sync and the mail password stay as they are."),
+                        glib::clone!(
+                            #[strong] library,
+                            #[strong] config,
+                            #[weak] window,
+                            move || {
+                                let result = library.write().unwrap().import_profile(&mut config.borrow_mut(), &path);
+                                match result {
+                                    Ok(()) => dialogs::show_info(&window, &tr!("Profile imported"), &tr!("This page shows the new settings after a restart.")),
+                                    Err(e) => dialogs::show_error(&window, &tr!("Failed to import the profile"), &e.to_string()),
+                                }
+                            }
+                        ),
+                    );
+                }
+            ));
+        }
+    ));
+
+    settings_box.append(&Label::new(Some(&tr!("Closed every week on:"))));
+    settings_box.append(&weekday_box);
+    settings_box.append(&Label::new(Some(&tr!("Holidays (one YYYY-MM-DD date per line):"))));
+    settings_box.append(&holidays_window);
+    settings_box.append(&Label::new(Some(&tr!("Membership types:"))));
+    settings_box.append(&policy_grid);
+    settings_box.append(&Label::new(Some(&tr!("Formats lent by the hour (format=hours):"))));
+    settings_box.append(&hourly_entry);
+    let pickup_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    pickup_box.append(&Label::new(Some(&tr!("Days to collect a hold:"))));
+    pickup_box.append(&pickup_days_spin);
+    settings_box.append(&pickup_box);
+    let waiver_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    waiver_box.append(&Label::new(Some(&tr!("Fine waivers above this need an administrator:"))));
+    waiver_box.append(&waiver_approval_entry);
+    settings_box.append(&waiver_box);
+    let grace_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    grace_box.append(&Label::new(Some(&tr!("Open days late before fines start:"))));
+    grace_box.append(&grace_days_spin);
+    settings_box.append(&grace_box);
+    let max_fine_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    max_fine_box.append(&Label::new(Some(&tr!("Most one late return is fined (0 for no cap):"))));
+    max_fine_box.append(&max_fine_entry);
+    settings_box.append(&max_fine_box);
+    let amnesty_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    amnesty_box.append(&amnesty_check);
+    amnesty_box.append(&amnesty_from_button);
+    amnesty_box.append(&Label::new(Some(&tr!("to"))));
+    amnesty_box.append(&amnesty_to_button);
+    settings_box.append(&amnesty_box);
+    settings_box.append(&backup_grid);
+    settings_box.append(&Label::new(Some(&tr!("Backup interval, language and sync changes apply after a restart."))));
+    settings_box.append(&save_button);
+    settings_box.append(&retention_button);
+    let profile_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    profile_box.append(&export_profile_button);
+    profile_box.append(&import_profile_button);
+    settings_box.append(&profile_box);
+    let mail_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    mail_box.append(&send_report_button);
+    mail_box.append(&send_digests_button);
+    settings_box.append(&mail_box);
+
+    settings_box
+}
+
+fn create_users_page(users: Rc<RefCell<UserStore>>, current_user: String) -> GtkBox {
+    let users_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let table = Rc::new(Table::<User>::new());
+    table.add_column(&tr!("Username"), |u| u.username.clone(), |a, b| a.username.cmp(&b.username));
+    table.add_column(&tr!("Role"), |u| u.role.name().to_string(), |a, b| a.role.cmp(&b.role));
+    table.set_rows(users.borrow().users().to_vec());
+
+    let username_entry = Entry::new();
+    username_entry.set_placeholder_text(Some(&tr!("Username")));
+    let password_entry = gtk::PasswordEntry::new();
+    password_entry.set_show_peek_icon(true);
+    let role_names: Vec<&str> = Role::ALL.iter().map(|r| r.name()).collect();
+    let role_dropdown = gtk::DropDown::from_strings(&role_names);
+    let add_button = Button::with_label(&tr!("Add User"));
+    let remove_button = Button::with_label(&tr!("Remove Selected User"));
+
+    add_button.connect_clicked(glib::clone!(
+        #[strong]
+        users,
+        #[strong]
+        table,
+        #[weak]
+        username_entry,
+        #[weak]
+        password_entry,
+        #[weak]
+        role_dropdown,
+        #[weak]
+        users_box,
+        move |_| {
+            let role = Role::ALL[role_dropdown.selected() as usize % Role::ALL.len()];
+            let mut store = users.borrow_mut();
+            let result = store
+                .add_user(&username_entry.text(), &password_entry.text(), role)
+                .and_then(|_| store.save().map_err(|e| e.to_string()));
+            match result {
+                Ok(()) => {
+                    status::success(&users_box, &tr!("User added"));
+                    username_entry.set_text("");
+                    password_entry.set_text("");
+                    table.set_rows(store.users().to_vec());
+                }
+                Err(e) => status::error(&users_box, &e),
+            }
+        }
+    ));
+
+    remove_button.connect_clicked(glib::clone!(
+        #[strong]
+        users,
+        #[strong]
+        table,
+        #[weak]
+        users_box,
+        move |_| {
+            let Some(username) = table.selected(|u| u.username.clone()) else {
+                status::error(&users_box, &tr!("Select a user first"));
+                return;
+            };
+            if username == current_user {
+                status::error(&users_box, &tr!("You cannot remove yourself"));
+                return;
+            }
+            let mut store = users.borrow_mut();
+            let result = store
+                .remove_user(&username)
+                .and_then(|_| store.save().map_err(|e| e.to_string()));
+            match result {
+                Ok(()) => {
+                    status::success(&users_box, &tr!("User removed"));
+                    table.set_rows(store.users().to_vec());
+                }
+                Err(e) => status::error(&users_box, &e),
+            }
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    let form_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    form_box.append(&username_entry);
+    form_box.append(&password_entry);
+    form_box.append(&role_dropdown);
+    form_box.append(&add_button);
+
+    users_box.append(&scrolled_window);
+    users_box.append(&form_box);
+    users_box.append(&remove_button);
+
+    users_box
+}
+
+fn create_transfers_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let transfers_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let item_id_label = Label::new(Some(&tr!("Item ID:")));
+    let item_id_entry = Entry::new();
+    let to_label = Label::new(Some(&tr!("Destination Branch:")));
+    let to_entry = Entry::new();
+    let request_button = Button::with_label(&tr!("Request Transfer"));
+
+    attach_completion(&item_id_entry, library.clone(), fill_item_completion);
+
+    let table = Rc::new(Table::<(Transfer, Arc<str>)>::new());
+    table.add_column(&tr!("ID"), |(t, _)| t.id.to_string(), |a, b| a.0.id.cmp(&b.0.id));
+    table.add_column(&tr!("Item ID"), |(t, _)| t.item_id.to_string(), |a, b| a.0.item_id.cmp(&b.0.item_id));
+    table.add_column(&tr!("Title"), |(_, title)| title.to_string(), |a, b| a.1.cmp(&b.1));
+    table.add_column(&tr!("From"), |(t, _)| t.from.clone(), |a, b| a.0.from.cmp(&b.0.from));
+    table.add_column(&tr!("To"), |(t, _)| t.to.clone(), |a, b| a.0.to.cmp(&b.0.to));
+    table.add_column(&tr!("Status"), |(t, _)| format!("{:?}", t.status), |a, b| format!("{:?}", a.0.status).cmp(&format!("{:?}", b.0.status)));
+    table.add_column(&tr!("Updated"), |(t, _)| t.updated_at.format("%Y-%m-%d %H:%M").to_string(), |a, b| a.0.updated_at.cmp(&b.0.updated_at));
+
+    let fill = glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move || {
+            let lib = library.read().unwrap();
+            let rows: Vec<(Transfer, Arc<str>)> = lib
+                .transfers
+                .iter()
+                .map(|t| {
+                    let title = lib.item_or_trashed(t.item_id).map_or_else(|| Arc::from("?"), |i| i.title.clone());
+                    (t.clone(), title)
+                })
+                .collect();
+            drop(lib);
+            table.set_rows(rows);
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+
+    request_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        item_id_entry,
+        #[weak]
+        to_entry,
+        #[weak]
+        transfers_box,
+        move |_| {
+            let Ok(item_id) = item_id_entry.text().trim().parse::<u32>() else {
+                status::error(&transfers_box, &tr!("Invalid Item ID!"));
+                return;
+            };
+            let result = {
+                let mut lib = library.write().unwrap();
+                let from = lib.items.get(&item_id).map(|i| i.branch.clone()).unwrap_or_default();
+                lib.request_transfer(item_id, &from, &to_entry.text())
+            };
+            match result {
+                Ok(id) => {
+                    status::success(&transfers_box, &tr!("Transfer {} requested", id));
+                    item_id_entry.set_text("");
+                    to_entry.set_text("");
+                    fill();
+                }
+                Err(e) => status::error(&transfers_box, &e),
+            }
+        }
+    ));
+
+    let advance_button = Button::with_label(&tr!("Mark Sent / Received"));
+    advance_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        transfers_box,
+        move |_| {
+            let Some(id) = table.selected(|(t, _)| t.id) else { return };
+            let result = library.write().unwrap().advance_transfer(id);
+            match result {
+                Ok(TransferStatus::InTransit) => status::success(&transfers_box, &tr!("Transfer {} sent", id)),
+                Ok(_) => status::success(&transfers_box, &tr!("Transfer {} received", id)),
+                Err(e) => status::error(&transfers_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    transfers_box.append(&item_id_label);
+    transfers_box.append(&item_id_entry);
+    transfers_box.append(&to_label);
+    transfers_box.append(&to_entry);
+    transfers_box.append(&request_button);
+    transfers_box.append(&scrolled_window);
+    transfers_box.append(&advance_button);
+
+    transfers_box
+}
+
+fn create_ill_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let ill_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let borrow_button = Button::with_label(&tr!("Borrow from Another Library..."));
+    borrow_button.set_tooltip_text(Some(&tr!("A member wants an item we don't own")));
+    let lend_button = Button::with_label(&tr!("Lend to Another Library..."));
+    lend_button.set_tooltip_text(Some(&tr!("Another library wants to borrow one of our items")));
+    let finished_check = gtk::CheckButton::with_label(&tr!("Show returned and cancelled"));
+    let top_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    top_box.append(&borrow_button);
+    top_box.append(&lend_button);
+    top_box.append(&finished_check);
+
+    // Rows carry who the loan is for and whether it is overdue
+    let table = Rc::new(Table::<(IllRequest, String, bool)>::new());
+    table.add_column(&tr!("ID"), |(r, _, _)| r.id.to_string(), |a, b| a.0.id.cmp(&b.0.id));
+    table.add_column(&tr!("Type"), |(r, _, _)| r.direction.label(), |a, b| a.0.direction.label().cmp(&b.0.direction.label()));
+    table.add_column(&tr!("Library"), |(r, _, _)| r.partner.clone(), |a, b| a.0.partner.cmp(&b.0.partner));
+    table.add_column(&tr!("Title"), |(r, _, _)| r.title.clone(), |a, b| a.0.title.cmp(&b.0.title));
+    table.add_column(&tr!("For"), |(_, whom, _)| whom.clone(), |a, b| a.1.cmp(&b.1));
+    table.add_column(&tr!("Status"), |(r, _, _)| r.status.label(), |a, b| a.0.status.label().cmp(&b.0.status.label()));
+    table.add_column(&tr!("Requested"), |(r, _, _)| r.requested_at.format("%Y-%m-%d").to_string(), |a, b| a.0.requested_at.cmp(&b.0.requested_at));
+    table.add_column(
+        &tr!("Due Date"),
+        |(r, _, overdue)| match r.due_date {
+            Some(due) if *overdue => tr!("{} (overdue)", due),
+            Some(due) => due.to_string(),
+            None => String::new(),
+        },
+        |a, b| a.0.due_date.cmp(&b.0.due_date),
+    );
+    table.add_column(&tr!("Note"), |(r, _, _)| r.note.clone(), |a, b| a.0.note.cmp(&b.0.note));
+
+    let due_entry = Entry::new();
+    due_entry.set_placeholder_text(Some(&tr!("Due date (YYYY-MM-DD)")));
+    let advance_button = Button::with_label(&tr!("Mark Received / Shipped / Returned"));
+    let due_button = Button::with_label(&tr!("Change Due Date"));
+    let cancel_button = Button::with_label(&tr!("Cancel Request"));
+    let action_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    action_box.append(&due_entry);
+    action_box.append(&advance_button);
+    action_box.append(&due_button);
+    action_box.append(&cancel_button);
+
+    let fill = glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        #[weak]
+        finished_check,
+        move || {
+            let show_finished = finished_check.is_active();
+            let lib = library.read().unwrap();
+            let today = lib.today();
+            let rows: Vec<(IllRequest, String, bool)> = lib
+                .ill
+                .iter()
+                .filter(|r| show_finished || !r.status.is_finished())
+                .map(|r| {
+                    let whom = match r.direction {
+                        IllDirection::Outgoing => r
+                            .member_id
+                            .and_then(|id| lib.members.get(&id))
+                            .map_or_else(|| "?".to_string(), |m| format!("{} ({})", m.name, m.id)),
+                        IllDirection::Incoming => r.item_id.map_or(String::new(), |id| tr!("Item {}", id)),
+                    };
+                    (r.clone(), whom, r.is_overdue(today))
+                })
+                .collect();
+            drop(lib);
+            table.set_rows(rows);
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    // Loans fall overdue while the page is hidden
+    ill_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    finished_check.connect_toggled(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+
+    for (button, direction) in [(&borrow_button, IllDirection::Outgoing), (&lend_button, IllDirection::Incoming)] {
+        button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            fill,
+            #[weak]
+            ill_box,
+            move |button| {
+                let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+                dialogs::show_new_ill_request(&window, direction, glib::clone!(
+                    #[strong]
+                    library,
+                    #[strong]
+                    fill,
+                    #[weak]
+                    ill_box,
+                    #[upgrade_or]
+                    Ok(()),
+                    move |new| {
+                        let id = library.write().unwrap().request_ill(new)?;
+                        status::success(&ill_box, &tr!("Interlibrary loan {} requested", id));
+                        fill();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+    }
+
+    // The due date typed in, if any
+    let parse_due = |entry: &Entry| -> Result<Option<NaiveDate>, String> {
+        let text = entry.text();
+        if text.trim().is_empty() {
+            return Ok(None);
+        }
+        NaiveDate::parse_from_str(text.trim(), "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| tr!("\"{}\" is not a date; use YYYY-MM-DD", text.trim()))
+    };
+
+    advance_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        due_entry,
+        #[weak]
+        ill_box,
+        move |_| {
+            let Some(id) = table.selected(|(r, _, _)| r.id) else { return };
+            let result = parse_due(&due_entry).and_then(|due| library.write().unwrap().advance_ill(id, due));
+            match result {
+                Ok(new_status) => {
+                    status::success(&ill_box, &tr!("Interlibrary loan {} marked {}", id, new_status.label()));
+                    due_entry.set_text("");
+                }
+                Err(e) => status::error(&ill_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    due_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        due_entry,
+        #[weak]
+        ill_box,
+        move |_| {
+            let Some(id) = table.selected(|(r, _, _)| r.id) else { return };
+            let result = parse_due(&due_entry)
+                .and_then(|due| due.ok_or_else(|| tr!("Enter the due date")))
+                .and_then(|due| library.write().unwrap().set_ill_due_date(id, due).map(|()| due));
+            match result {
+                Ok(due) => {
+                    status::success(&ill_box, &tr!("Interlibrary loan {} now due {}", id, due));
+                    due_entry.set_text("");
+                }
+                Err(e) => status::error(&ill_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    cancel_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        ill_box,
+        move |_| {
+            let Some(id) = table.selected(|(r, _, _)| r.id) else { return };
+            let result = library.write().unwrap().cancel_ill(id);
+            match result {
+                Ok(()) => status::success(&ill_box, &tr!("Interlibrary loan {} cancelled", id)),
+                Err(e) => status::error(&ill_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    ill_box.append(&top_box);
+    ill_box.append(&scrolled_window);
+    ill_box.append(&action_box);
+
+    ill_box
+}
+
+fn create_hold_shelf_page(library: Arc<RwLock<Library>>, bus: &EventBus, config: Rc<RefCell<Config>>) -> GtkBox {
+    let holds_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let item_id_entry = Entry::new();
+    item_id_entry.set_placeholder_text(Some(&tr!("Item ID")));
+    attach_completion(&item_id_entry, library.clone(), fill_item_completion);
+    let member_id_entry = Entry::new();
+    member_id_entry.set_placeholder_text(Some(&tr!("Member ID")));
+    attach_completion(&member_id_entry, library.clone(), fill_member_completion);
+    let level_names: Vec<String> = HoldLevel::ALL.iter().map(|l| l.label()).collect();
+    let level_dropdown = gtk::DropDown::from_strings(&level_names.iter().map(String::as_str).collect::<Vec<_>>());
+    level_dropdown.set_tooltip_text(Some(&tr!("A specific copy is this item only, e.g. one volume of a set; any copy of the title may be filled from another edition or branch")));
+    let place_button = Button::with_label(&tr!("Place Hold"));
+    let place_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    place_box.append(&item_id_entry);
+    place_box.append(&member_id_entry);
+    place_box.append(&level_dropdown);
+    place_box.append(&place_button);
+
+    // Rows carry the item's title and the member's name
+    let shelf_table = Rc::new(Table::<(Hold, String, String)>::new());
+    shelf_table.add_column(&tr!("Item"), |(h, _, _)| h.item_id.to_string(), |a, b| a.0.item_id.cmp(&b.0.item_id));
+    shelf_table.add_column(&tr!("Title"), |(_, title, _)| title.clone(), |a, b| a.1.cmp(&b.1));
+    shelf_table.add_column(&tr!("Member"), |(_, name, _)| name.clone(), |a, b| a.2.cmp(&b.2));
+    shelf_table.add_column(&tr!("Type"), |(h, _, _)| h.level.label(), |a, b| a.0.level.cmp(&b.0.level));
+    shelf_table.add_column(
+        &tr!("Shelved"),
+        |(h, _, _)| h.shelved_at.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+        |a, b| a.0.shelved_at.cmp(&b.0.shelved_at),
+    );
+    shelf_table.add_column(
+        &tr!("Pick up by"),
+        |(h, _, _)| h.pickup_by.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+        |a, b| a.0.pickup_by.cmp(&b.0.pickup_by),
+    );
+    shelf_table.add_column(
+        &tr!("Notified"),
+        |(h, _, _)| if h.notified { tr!("Yes") } else { tr!("No") },
+        |a, b| a.0.notified.cmp(&b.0.notified),
+    );
+
+    let queue_table = Rc::new(Table::<(Hold, String, String)>::new());
+    queue_table.add_column(&tr!("Item"), |(h, _, _)| h.item_id.to_string(), |a, b| a.0.item_id.cmp(&b.0.item_id));
+    queue_table.add_column(&tr!("Title"), |(_, title, _)| title.clone(), |a, b| a.1.cmp(&b.1));
+    queue_table.add_column(&tr!("Member"), |(_, name, _)| name.clone(), |a, b| a.2.cmp(&b.2));
+    queue_table.add_column(&tr!("Type"), |(h, _, _)| h.level.label(), |a, b| a.0.level.cmp(&b.0.level));
+    queue_table.add_column(
+        &tr!("Placed"),
+        |(h, _, _)| h.placed_at.format("%Y-%m-%d").to_string(),
+        |a, b| a.0.placed_at.cmp(&b.0.placed_at),
+    );
+
+    let cancel_shelved_button = Button::with_label(&tr!("Cancel Hold"));
+    let notify_button = Button::with_label(&tr!("Send Pickup Notices"));
+    notify_button.set_tooltip_text(Some(&tr!("Email members whose holds are ready and who haven't been told yet")));
+    let pick_list_button = Button::with_label(&tr!("Print Pick List..."));
+    pick_list_button.set_tooltip_text(Some(&tr!("Print the copies to fetch from the shelves today for holds and transfers")));
+    let pick_list_pdf_button = Button::with_label(&tr!("Export Pick List PDF..."));
+    let shelf_actions = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    shelf_actions.append(&cancel_shelved_button);
+    shelf_actions.append(&notify_button);
+    shelf_actions.append(&pick_list_button);
+    shelf_actions.append(&pick_list_pdf_button);
+    let cancel_waiting_button = Button::with_label(&tr!("Cancel Hold"));
+
+    let fill = glib::clone!(
+        #[strong]
+        shelf_table,
+        #[strong]
+        queue_table,
+        #[strong]
+        library,
+        move || {
+            let lib = library.read().unwrap();
+            let row = |h: &Hold| {
+                let title = lib.items.get(&h.item_id).map_or_else(|| "?".to_string(), |i| i.title.to_string());
+                let name = lib.members.get(&h.member_id).map_or_else(|| "?".to_string(), |m| format!("{} ({})", m.name, m.id));
+                (h.clone(), title, name)
+            };
+            let shelved: Vec<_> = lib.holds.iter().filter(|h| h.status == HoldStatus::OnShelf).map(row).collect();
+            let waiting: Vec<_> = lib.holds.iter().filter(|h| h.status == HoldStatus::Waiting).map(row).collect();
+            drop(lib);
+            shelf_table.set_rows(shelved);
+            queue_table.set_rows(waiting);
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    // Deadlines may have passed while the page was hidden
+    holds_box.connect_map(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        move |_| {
+            library.write().unwrap().expire_holds();
+            fill();
+        }
+    ));
+    // Returns put copies on the shelf
+    bus.refill_when_shown(&holds_box, fill.clone());
+
+    place_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        item_id_entry,
+        #[weak]
+        member_id_entry,
+        #[weak]
+        level_dropdown,
+        #[weak]
+        holds_box,
+        move |_| {
+            let Ok(item_id) = item_id_entry.text().trim().parse::<u32>() else {
+                status::error(&holds_box, &tr!("Invalid Item ID"));
+                return;
+            };
+            let Ok(member_id) = member_id_entry.text().trim().parse::<u32>() else {
+                status::error(&holds_box, &tr!("Invalid Member ID"));
+                return;
+            };
+            let level = HoldLevel::ALL[level_dropdown.selected() as usize % HoldLevel::ALL.len()];
+            let result = library.write().unwrap().place_hold(item_id, member_id, level);
+            match result {
+                Ok(id) => {
+                    // A title hold may be filled from another item
+                    let shelved_item = library
+                        .read()
+                        .unwrap()
+                        .holds
+                        .iter()
+                        .find(|h| h.id == id && h.status == HoldStatus::OnShelf)
+                        .map(|h| h.item_id);
+                    if let Some(shelved_item) = shelved_item {
+                        status::info(&holds_box, &tr!("A copy of item {} is free: put it on the hold shelf for member {}", shelved_item, member_id));
+                    } else {
+                        status::success(&holds_box, &tr!("Hold placed for member {}", member_id));
+                    }
+                    item_id_entry.set_text("");
+                    member_id_entry.set_text("");
+                }
+                Err(e) => status::error(&holds_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    for (button, table) in [(&cancel_shelved_button, &shelf_table), (&cancel_waiting_button, &queue_table)] {
+        button.connect_clicked(glib::clone!(
+            #[strong]
+            library,
+            #[strong]
+            table,
+            #[strong]
+            fill,
+            #[weak]
+            holds_box,
+            move |_| {
+                let Some(id) = table.selected(|(h, _, _)| h.id) else { return };
+                let result = library.write().unwrap().cancel_hold(id);
+                match result {
+                    Ok(()) => status::success(&holds_box, &tr!("Hold cancelled")),
+                    Err(e) => status::error(&holds_box, &e),
+                }
+                fill();
+            }
+        ));
+    }
+
+    notify_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        config,
+        #[strong]
+        fill,
+        #[weak]
+        holds_box,
+        move |button| {
+            let (notices, unreachable) = {
+                let lib = library.read().unwrap();
+                let notices = lib.pending_pickup_notices();
+                let unreachable = lib
+                    .holds
+                    .iter()
+                    .filter(|h| h.status == HoldStatus::OnShelf && !h.notified)
+                    .filter(|h| !notices.iter().any(|n| n.hold_id == h.id))
+                    .count();
+                (notices, unreachable)
+            };
+            if notices.is_empty() {
+                status::info(&holds_box, &tr!("No pickup notices to send"));
+                return;
+            }
+            button.set_sensitive(false);
+            let config = config.borrow().clone();
+            let handle = gio::spawn_blocking(move || {
+                let mut sent = Vec::new();
+                let mut errors = Vec::new();
+                for notice in notices {
+                    match mail::send(&config, &[notice.to.clone()], &notice.subject, &notice.body, None) {
+                        Ok(()) => sent.push(notice.hold_id),
+                        Err(e) => errors.push(format!("{}: {}", notice.to, e)),
+                    }
+                }
+                (sent, errors)
+            });
+            glib::spawn_future_local(glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                button,
+                #[weak]
+                holds_box,
+                async move {
+                    button.set_sensitive(true);
+                    let Ok((sent, errors)) = handle.await else {
+                        status::error(&holds_box, &tr!("Failed to send pickup notices"));
+                        return;
+                    };
+                    library.write().unwrap().mark_notified(&sent);
+                    tracing::info!(sent = sent.len(), failed = errors.len(), "Pickup notices sent");
+                    fill();
+                    if let Some(first) = errors.first() {
+                        status::error(&holds_box, &tr!("Sent {} pickup notice(s); {} failed: {}", sent.len(), errors.len(), first));
+                    } else if unreachable > 0 {
+                        status::info(&holds_box, &tr!("Sent {} pickup notice(s); {} member(s) have no email address", sent.len(), unreachable));
+                    } else {
+                        status::success(&holds_box, &tr!("Sent {} pickup notice(s)", sent.len()));
+                    }
+                }
+            ));
+        }
+    ));
+
+    // Sends today's pick list to a PDF file, or to the print dialog without one
+    let print_pick_list = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        move |window: &gtk::Window, pdf_path: Option<std::path::PathBuf>| {
+            let today = chrono::Local::now().date_naive();
+            let rows = library.read().unwrap().pick_list(today);
+            if rows.is_empty() {
+                status::info(window, &tr!("Nothing to fetch from the shelves today"));
+                return;
+            }
+            let title = tr!("Pick list, {}", today.format("%Y-%m-%d"));
+            let result = print::print_table(window, &title, picklist::pick_list_headers(), picklist::pick_list_rows(&rows), pdf_path);
+            if let Err(e) = result {
+                dialogs::show_error(window, &tr!("Failed to print the pick list"), &e);
+            }
+        }
+    ));
+    pick_list_button.connect_clicked(glib::clone!(
+        #[strong]
+        print_pick_list,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            print_pick_list(&window, None);
+        }
+    ));
+    pick_list_pdf_button.connect_clicked(move |button| {
+        let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+        let name = format!("pick-list-{}.pdf", chrono::Local::now().format("%Y-%m-%d"));
+        dialogs::choose_save_path(&window, &tr!("Export Pick List"), &name, glib::clone!(
+            #[strong]
+            print_pick_list,
+            #[weak]
+            window,
+            move |path| print_pick_list(&window, Some(path))
+        ));
+    });
+
+    let shelf_window = ScrolledWindow::new();
+    shelf_window.set_child(Some(&shelf_table.view));
+    shelf_window.set_vexpand(true);
+    let queue_window = ScrolledWindow::new();
+    queue_window.set_child(Some(&queue_table.view));
+    queue_window.set_vexpand(true);
+
+    holds_box.append(&place_box);
+    holds_box.append(&Label::new(Some(&tr!("Awaiting pickup:"))));
+    holds_box.append(&shelf_window);
+    holds_box.append(&shelf_actions);
+    holds_box.append(&Label::new(Some(&tr!("Waiting for a copy:"))));
+    holds_box.append(&queue_window);
+    holds_box.append(&cancel_waiting_button);
+
+    holds_box
+}
+
+// Deleted items and members, refilled every time the page is shown
+fn create_trash_page(library: Arc<RwLock<Library>>, bus: &EventBus, role: Role) -> GtkBox {
+    let trash_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let items_table = Rc::new(Table::<Trashed<LiItem>>::new());
+    items_table.add_column(&tr!("ID"), |t| t.record.id.to_string(), |a, b| a.record.id.cmp(&b.record.id));
+    items_table.add_column(&tr!("Title"), |t| t.record.title.to_string(), |a, b| a.record.title.cmp(&b.record.title));
+    items_table.add_column(&tr!("Author"), |t| t.record.author.clone().unwrap_or_default(), |a, b| a.record.author.cmp(&b.record.author));
+    items_table.add_column(&tr!("Deleted"), |t| t.deleted_at.format("%Y-%m-%d %H:%M").to_string(), |a, b| a.deleted_at.cmp(&b.deleted_at));
+    items_table.add_column(&tr!("Deleted by"), |t| t.deleted_by.clone(), |a, b| a.deleted_by.cmp(&b.deleted_by));
+
+    let members_table = Rc::new(Table::<Trashed<Member>>::new());
+    members_table.add_column(&tr!("ID"), |t| t.record.id.to_string(), |a, b| a.record.id.cmp(&b.record.id));
+    members_table.add_column(&tr!("Name"), |t| t.record.name.clone(), |a, b| a.record.name.cmp(&b.record.name));
+    members_table.add_column(&tr!("Deleted"), |t| t.deleted_at.format("%Y-%m-%d %H:%M").to_string(), |a, b| a.deleted_at.cmp(&b.deleted_at));
+    members_table.add_column(&tr!("Deleted by"), |t| t.deleted_by.clone(), |a, b| a.deleted_by.cmp(&b.deleted_by));
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        items_table,
+        #[strong]
+        members_table,
+        #[strong]
+        library,
+        move || {
+            let lib = library.read().unwrap();
+            let items: Vec<_> = lib.trash.items.values().cloned().collect();
+            let members: Vec<_> = lib.trash.members.values().cloned().collect();
+            drop(lib);
+            items_table.set_rows(items);
+            members_table.set_rows(members);
+        }
+    ));
+    trash_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&trash_box, fill.clone());
+
+    let restore_item_button = Button::with_label(&tr!("Restore Item"));
+    restore_item_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        items_table,
+        #[strong]
+        fill,
+        #[weak]
+        trash_box,
+        move |_| {
+            let Some(id) = items_table.selected(|t| t.record.id) else { return };
+            let result = library.write().unwrap().restore_item(id);
+            match result {
+                Ok(()) => status::success(&trash_box, &tr!("Item {} restored to the catalog", id)),
+                Err(e) => status::error(&trash_box, &e),
+            }
+            fill();
+        }
+    ));
+    let restore_member_button = Button::with_label(&tr!("Restore Member"));
+    restore_member_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        members_table,
+        #[strong]
+        fill,
+        #[weak]
+        trash_box,
+        move |_| {
+            let Some(id) = members_table.selected(|t| t.record.id) else { return };
+            let result = library.write().unwrap().restore_member(id);
+            match result {
+                Ok(()) => status::success(&trash_box, &tr!("Member {} restored", id)),
+                Err(e) => status::error(&trash_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    // Only administrators can delete records for good
+    let purge_item_button = Button::with_label(&tr!("Delete Permanently"));
+    purge_item_button.set_visible(role >= Role::Admin);
+    purge_item_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        items_table,
+        #[strong]
+        fill,
+        #[weak]
+        trash_box,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let Some((id, title)) = items_table.selected(|t| (t.record.id, t.record.title.clone())) else { return };
+            dialogs::confirm(&window, &tr!("Delete \"{}\" permanently?", title), &tr!("Past transactions will no longer show its title. This cannot be undone."), glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                trash_box,
+                move || {
+                    let result = library.write().unwrap().purge_item(id);
+                    match result {
+                        Ok(()) => status::success(&trash_box, &tr!("Item {} deleted permanently", id)),
+                        Err(e) => status::error(&trash_box, &e),
+                    }
+                    fill();
+                }
+            ));
+        }
+    ));
+    let purge_member_button = Button::with_label(&tr!("Delete Permanently"));
+    purge_member_button.set_visible(role >= Role::Admin);
+    purge_member_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        members_table,
+        #[strong]
+        fill,
+        #[weak]
+        trash_box,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let Some((id, name)) = members_table.selected(|t| (t.record.id, t.record.name.clone())) else { return };
+            dialogs::confirm(&window, &tr!("Delete \"{}\" permanently?", name), &tr!("The member's record and reading history are removed. This cannot be undone."), glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                trash_box,
+                move || {
+                    let result = library.write().unwrap().purge_member(id);
+                    match result {
+                        Ok(()) => status::success(&trash_box, &tr!("Member {} deleted permanently", id)),
+                        Err(e) => status::error(&trash_box, &e),
+                    }
+                    fill();
+                }
+            ));
+        }
+    ));
+
+    let items_window = ScrolledWindow::new();
+    items_window.set_child(Some(&items_table.view));
+    items_window.set_vexpand(true);
+    let members_window = ScrolledWindow::new();
+    members_window.set_child(Some(&members_table.view));
+    members_window.set_vexpand(true);
+    let item_actions = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    item_actions.append(&restore_item_button);
+    item_actions.append(&purge_item_button);
+    let member_actions = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    member_actions.append(&restore_member_button);
+    member_actions.append(&purge_member_button);
+
+    trash_box.append(&Label::new(Some(&tr!("Deleted items:"))));
+    trash_box.append(&items_window);
+    trash_box.append(&item_actions);
+    trash_box.append(&Label::new(Some(&tr!("Deleted members:"))));
+    trash_box.append(&members_window);
+    trash_box.append(&member_actions);
+
+    trash_box
+}
+
+// How often the header counters are recounted without any change, for
+// loans becoming overdue as time passes
+const QUICK_STATS_SECONDS: u32 = 60;
+
+// Counters for the header bar, recounted after every change
+fn create_quick_stats(library: Arc<RwLock<Library>>, bus: &EventBus) -> GtkBox {
+    let stats_box = GtkBox::new(gtk::Orientation::Horizontal, 12);
+    let counter = |icon: &str, tooltip: String| {
+        let label = Label::new(None);
+        let counter_box = GtkBox::new(gtk::Orientation::Horizontal, 4);
+        counter_box.append(&gtk::Image::from_icon_name(icon));
+        counter_box.append(&label);
+        counter_box.set_tooltip_text(Some(&tooltip));
+        stats_box.append(&counter_box);
+        label
+    };
+    let available = counter("view-list-symbolic", tr!("Copies available"));
+    let on_loan = counter("document-send-symbolic", tr!("Copies on loan"));
+    let overdue = counter("alarm-symbolic", tr!("Loans overdue"));
+    let awaiting_pickup = counter("view-pin-symbolic", tr!("Holds awaiting pickup"));
+
+    let fill = move || {
+        let stats = library.read().unwrap().quick_stats();
+        available.set_text(&stats.available.to_string());
+        on_loan.set_text(&stats.on_loan.to_string());
+        overdue.set_text(&stats.overdue.to_string());
+        awaiting_pickup.set_text(&stats.awaiting_pickup.to_string());
+        if stats.overdue > 0 {
+            overdue.add_css_class("error");
+        } else {
+            overdue.remove_css_class("error");
+        }
+    };
+    fill();
+    let fill = Rc::new(fill);
+    bus.subscribe(&stats_box, glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    let weak_box = stats_box.downgrade();
+    glib::timeout_add_seconds_local(QUICK_STATS_SECONDS, move || {
+        if weak_box.upgrade().is_none() {
+            return glib::ControlFlow::Break;
+        }
+        fill();
+        glib::ControlFlow::Continue
+    });
+
+    stats_box
+}
+
+// Shown on startup when loans are overdue or due soon, so the day's
+// reminders are the first thing staff see
+fn create_due_banner(lib: &Library, stack: &adw::ViewStack) -> adw::Banner {
+    let loans = lib.due_loans(loans::DUE_SOON_DAYS);
+    let overdue = loans.iter().filter(|l| l.overdue).count();
+    let due_soon = loans.len() - overdue;
+
+    let banner = adw::Banner::new(&tr!("{} loans overdue, {} due in the next {} days", overdue, due_soon, loans::DUE_SOON_DAYS));
+    banner.set_button_label(Some(&tr!("Show Loans")));
+    banner.set_revealed(!loans.is_empty());
+    banner.connect_button_clicked(glib::clone!(
+        #[weak]
+        stack,
+        move |banner| {
+            stack.set_visible_child_name("overdue");
+            banner.set_revealed(false);
+        }
+    ));
+    banner
+}
+
+fn create_overdue_page(library: Arc<RwLock<Library>>, bus: &EventBus) -> GtkBox {
+    let overdue_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let table = Rc::new(Table::<DueLoan>::new());
+    table.add_column(&tr!("Due"), |l| l.loan.due_text(), |a, b| a.loan.due_date.cmp(&b.loan.due_date));
+    table.add_column(
+        &tr!("Status"),
+        |l| if l.overdue { tr!("Overdue") } else { tr!("Due soon") },
+        |a, b| a.overdue.cmp(&b.overdue),
+    );
+    table.add_column(&tr!("Item"), |l| l.loan.id.to_string(), |a, b| a.loan.id.cmp(&b.loan.id));
+    table.add_column(&tr!("Title"), |l| l.loan.title.to_string(), |a, b| a.loan.title.cmp(&b.loan.title));
+    table.add_column(&tr!("Member"), |l| format!("{} ({})", l.member_name, l.member_id), |a, b| a.member_name.cmp(&b.member_name));
+    table.add_column(&tr!("Notice Sent"), |l| if l.loan.notice { tr!("Yes") } else { tr!("No") }, |a, b| a.loan.notice.cmp(&b.loan.notice));
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move || {
+            let loans = library.read().unwrap().due_loans(loans::DUE_SOON_DAYS);
+            table.set_rows(loans);
+        }
+    ));
+    overdue_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&overdue_box, fill.clone());
+
+    let notice_button = Button::with_label(&tr!("Send Overdue Notice"));
+    notice_button.set_tooltip_text(Some(&tr!("Composes a notice listing all of the selected member's overdue items")));
+    notice_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let Some(member_id) = table.selected(|l| l.member_id) else { return };
+            let result = library.write().unwrap().overdue_notice(member_id);
+            match result {
+                Ok(text) => dialogs::show_info(&window, &tr!("Overdue notice (copy and send to the member)"), &text),
+                Err(e) => dialogs::show_error(&window, &tr!("No notice sent"), &e),
+            }
+            fill();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    overdue_box.append(&Label::new(Some(&tr!("Loans overdue or due in the next {} days:", loans::DUE_SOON_DAYS))));
+    overdue_box.append(&scrolled_window);
+    overdue_box.append(&notice_button);
+
+    overdue_box
+}
+
+fn create_sync_conflicts_page(library: Arc<RwLock<Library>>, bus: &EventBus) -> GtkBox {
+    let conflicts_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let table = Rc::new(Table::<SyncConflict>::new());
+    table.add_column(&tr!("ID"), |c| c.id.to_string(), |a, b| a.id.cmp(&b.id));
+    table.add_column(&tr!("Made"), |c| c.command.at.format("%Y-%m-%d %H:%M").to_string(), |a, b| a.command.at.cmp(&b.command.at));
+    table.add_column(&tr!("By"), |c| c.command.user.clone(), |a, b| a.command.user.cmp(&b.command.user));
+    table.add_column(&tr!("Change"), |c| c.command.command.describe(), |a, b| a.command.command.describe().cmp(&b.command.command.describe()));
+    table.add_column(&tr!("Refused Because"), |c| c.reason.clone(), |a, b| a.reason.cmp(&b.reason));
+    table.add_column(&tr!("Status"), |c| c.status_text(), |a, b| a.status_text().cmp(&b.status_text()));
+    table.add_column(&tr!("Resolved by"), |c| c.resolved_by.clone().unwrap_or_default(), |a, b| a.resolved_by.cmp(&b.resolved_by));
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move || {
+            // Open ones first, newest first
+            let mut rows = library.read().unwrap().sync_conflicts.clone();
+            rows.sort_by(|a, b| a.resolved_by.is_some().cmp(&b.resolved_by.is_some()).then(b.id.cmp(&a.id)));
+            table.set_rows(rows);
+        }
+    ));
+    conflicts_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&conflicts_box, fill.clone());
+
+    let retry_button = Button::with_label(&tr!("Retry"));
+    retry_button.set_tooltip_text(Some(&tr!("Makes the change again here, as of when it was first made, and sends it to the server")));
+    retry_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        conflicts_box,
+        move |_| {
+            let Some(id) = table.selected(|c| c.id) else { return };
+            let result = library.write().unwrap().retry_conflict(id);
+            match result {
+                Ok(()) => status::success(&conflicts_box, &tr!("Conflict {} sent again", id)),
+                Err(e) => status::error(&conflicts_box, &e),
+            }
+            fill();
+        }
+    ));
+    let resolve_button = Button::with_label(&tr!("Mark Resolved"));
+    resolve_button.set_tooltip_text(Some(&tr!("Keeps the library as the server has it")));
+    resolve_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        conflicts_box,
+        move |_| {
+            let Some(id) = table.selected(|c| c.id) else { return };
+            let result = library.write().unwrap().resolve_conflict(id);
+            match result {
+                Ok(()) => status::success(&conflicts_box, &tr!("Conflict {} resolved", id)),
+                Err(e) => status::error(&conflicts_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+    let actions = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    actions.append(&retry_button);
+    actions.append(&resolve_button);
+
+    conflicts_box.append(&Label::new(Some(&tr!("Changes made here that the sync server refused, usually because another desk got there first:"))));
+    conflicts_box.append(&scrolled_window);
+    conflicts_box.append(&actions);
+
+    conflicts_box
+}
+
+fn create_reserves_page(library: Arc<RwLock<Library>>, bus: &EventBus, role: Role) -> GtkBox {
+    let can_edit = role >= Role::Librarian;
+    let reserves_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let item_id_entry = Entry::new();
+    item_id_entry.set_placeholder_text(Some(&tr!("Item ID")));
+    attach_completion(&item_id_entry, library.clone(), fill_item_completion);
+    let course_entry = Entry::new();
+    course_entry.set_placeholder_text(Some(&tr!("Course, e.g. BIO 101")));
+    let teacher_entry = Entry::new();
+    teacher_entry.set_placeholder_text(Some(&tr!("Teacher")));
+    let loan_labels: Vec<String> = ReserveLoan::ALL.iter().map(|l| l.label()).collect();
+    let loan_dropdown = gtk::DropDown::from_strings(&loan_labels.iter().map(String::as_str).collect::<Vec<_>>());
+    let place_button = Button::with_label(&tr!("Place on Reserve"));
+    let form_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    form_box.append(&item_id_entry);
+    form_box.append(&course_entry);
+    form_box.append(&teacher_entry);
+    form_box.append(&loan_dropdown);
+    form_box.append(&place_button);
+    form_box.set_visible(can_edit);
+
+    let table = Rc::new(Table::<(Reserve, Arc<str>, String)>::new());
+    table.add_column(&tr!("Item ID"), |(r, _, _)| r.item_id.to_string(), |a, b| a.0.item_id.cmp(&b.0.item_id));
+    table.add_column(&tr!("Title"), |(_, title, _)| title.to_string(), |a, b| a.1.cmp(&b.1));
+    table.add_column(&tr!("Course"), |(r, _, _)| r.course.clone(), |a, b| a.0.course.cmp(&b.0.course));
+    table.add_column(&tr!("Teacher"), |(r, _, _)| r.teacher.clone(), |a, b| a.0.teacher.cmp(&b.0.teacher));
+    table.add_column(&tr!("Loan Period"), |(r, _, _)| r.loan.label(), |a, b| a.0.loan.label().cmp(&b.0.loan.label()));
+    table.add_column(&tr!("Available Copies"), |(_, _, avail)| avail.clone(), |a, b| a.2.cmp(&b.2));
+    table.add_column(&tr!("Since"), |(r, _, _)| r.placed_at.format("%Y-%m-%d").to_string(), |a, b| a.0.placed_at.cmp(&b.0.placed_at));
+
+    let fill = glib::clone!(
+        #[strong]
+        table,
+        #[strong]
+        library,
+        move || {
+            let lib = library.read().unwrap();
+            let mut rows: Vec<(Reserve, Arc<str>, String)> = lib
+                .reserves
+                .iter()
+                .map(|r| match lib.items.get(&r.item_id) {
+                    Some(item) => (r.clone(), item.title.clone(), format!("{} / {}", item.avail_copies, item.copies)),
+                    None => (r.clone(), Arc::from("?"), String::new()),
+                })
+                .collect();
+            drop(lib);
+            rows.sort_by(|a, b| a.0.course.cmp(&b.0.course).then_with(|| a.1.cmp(&b.1)));
+            table.set_rows(rows);
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    // Loans change the available copies while the page is hidden
+    reserves_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&reserves_box, fill.clone());
+
+    place_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        item_id_entry,
+        #[weak]
+        course_entry,
+        #[weak]
+        teacher_entry,
+        #[weak]
+        loan_dropdown,
+        #[weak]
+        reserves_box,
+        move |_| {
+            let Ok(item_id) = item_id_entry.text().trim().parse::<u32>() else {
+                status::error(&reserves_box, &tr!("Invalid Item ID!"));
+                return;
+            };
+            let loan = ReserveLoan::ALL[loan_dropdown.selected() as usize % ReserveLoan::ALL.len()];
+            let result = library.write().unwrap().place_on_reserve(item_id, &course_entry.text(), &teacher_entry.text(), loan);
+            match result {
+                Ok(()) => {
+                    status::success(&reserves_box, &tr!("Item {} placed on reserve", item_id));
+                    item_id_entry.set_text("");
+                    fill();
+                }
+                Err(e) => status::error(&reserves_box, &e),
+            }
+        }
+    ));
+
+    let remove_button = Button::with_label(&tr!("Remove from Reserve"));
+    remove_button.set_visible(can_edit);
+    remove_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        fill,
+        #[weak]
+        reserves_box,
+        move |_| {
+            let Some(item_id) = table.selected(|(r, _, _)| r.item_id) else { return };
+            let result = library.write().unwrap().remove_from_reserve(item_id);
+            match result {
+                Ok(()) => status::success(&reserves_box, &tr!("Item {} removed from reserve", item_id)),
+                Err(e) => status::error(&reserves_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    reserves_box.append(&form_box);
+    reserves_box.append(&scrolled_window);
+    reserves_box.append(&remove_button);
+
+    reserves_box
+}
+
+fn create_serials_page(library: Arc<RwLock<Library>>, role: Role) -> GtkBox {
+    let serials_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let add_button = Button::with_label(&tr!("Add Subscription..."));
+    let remove_button = Button::with_label(&tr!("End Subscription"));
+    let claims_button = Button::with_label(&tr!("Copy Claims List"));
+    claims_button.set_tooltip_text(Some(&tr!("Copies every missing issue, grouped by supplier, to paste into claim emails")));
+    let subscription_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    subscription_buttons.append(&add_button);
+    subscription_buttons.append(&remove_button);
+    subscription_buttons.append(&claims_button);
+    add_button.set_visible(role >= Role::Librarian);
+    remove_button.set_visible(role >= Role::Librarian);
+
+    // Rows carry the next expected issue and the number missing, worked
+    // out for the day the page was filled
+    let subscriptions_table = Rc::new(Table::<(Subscription, Option<NaiveDate>, usize)>::new());
+    subscriptions_table.add_column(&tr!("Title"), |(s, _, _)| s.title.clone(), |a, b| a.0.title.cmp(&b.0.title));
+    subscriptions_table.add_column(&tr!("ISSN"), |(s, _, _)| s.issn.clone().unwrap_or_default(), |a, b| a.0.issn.cmp(&b.0.issn));
+    subscriptions_table.add_column(&tr!("Supplier"), |(s, _, _)| s.supplier.clone(), |a, b| a.0.supplier.cmp(&b.0.supplier));
+    subscriptions_table.add_column(&tr!("Frequency"), |(s, _, _)| s.frequency.label(), |a, b| a.0.frequency.label().cmp(&b.0.frequency.label()));
+    subscriptions_table.add_column(
+        &tr!("Next Issue"),
+        |(s, next, _)| next.map_or(String::new(), |d| format!("{} ({})", s.frequency.issue_label(d), d)),
+        |a, b| a.1.cmp(&b.1),
+    );
+    subscriptions_table.add_column(&tr!("Missing"), |(_, _, missing)| missing.to_string(), |a, b| a.2.cmp(&b.2));
+
+    let issues_table = Rc::new(Table::<IssueRow>::new());
+    issues_table.add_column(&tr!("Issue"), |r| r.label.clone(), |a, b| a.expected.cmp(&b.expected));
+    issues_table.add_column(&tr!("Expected"), |r| r.expected.to_string(), |a, b| a.expected.cmp(&b.expected));
+    issues_table.add_column(&tr!("Status"), |r| r.status.label(), |a, b| a.status.label().cmp(&b.status.label()));
+    issues_table.add_column(
+        &tr!("Received"),
+        |r| r.issue.as_ref().and_then(|i| i.received_at).map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+        |a, b| a.issue.as_ref().and_then(|i| i.received_at).cmp(&b.issue.as_ref().and_then(|i| i.received_at)),
+    );
+    issues_table.add_column(
+        &tr!("Last Claimed"),
+        |r| r.issue.as_ref().and_then(|i| i.claimed_at.last()).map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+        |a, b| a.issue.as_ref().and_then(|i| i.claimed_at.last()).cmp(&b.issue.as_ref().and_then(|i| i.claimed_at.last())),
+    );
+    issues_table.add_column(
+        &tr!("Item ID"),
+        |r| r.issue.as_ref().and_then(|i| i.item_id).map_or(String::new(), |id| id.to_string()),
+        |a, b| a.issue.as_ref().and_then(|i| i.item_id).cmp(&b.issue.as_ref().and_then(|i| i.item_id)),
+    );
+
+    let check_in_button = Button::with_label(&tr!("Check In"));
+    check_in_button.set_tooltip_text(Some(&tr!("Records the issue's arrival and adds it to the catalog as a lendable copy")));
+    let claim_button = Button::with_label(&tr!("Claim"));
+    claim_button.set_tooltip_text(Some(&tr!("Records that the missing issue was claimed from the supplier")));
+    let issue_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    issue_buttons.append(&check_in_button);
+    issue_buttons.append(&claim_button);
+
+    let subscriptions_window = ScrolledWindow::new();
+    subscriptions_window.set_child(Some(&subscriptions_table.view));
+    subscriptions_window.set_vexpand(true);
+    let issues_window = ScrolledWindow::new();
+    issues_window.set_child(Some(&issues_table.view));
+    issues_window.set_vexpand(true);
+    serials_box.append(&subscription_buttons);
+    serials_box.append(&subscriptions_window);
+    serials_box.append(&Label::new(Some(&tr!("Issues of the selected subscription:"))));
+    serials_box.append(&issues_window);
+    serials_box.append(&issue_buttons);
+
+    let fill_issues = glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        subscriptions_table,
+        #[strong]
+        issues_table,
+        move || {
+            let lib = library.read().unwrap();
+            let today = lib.today();
+            let rows = subscriptions_table
+                .selected(|(s, _, _)| s.id)
+                .and_then(|id| lib.serials.iter().find(|s| s.id == id))
+                .map(|s| s.issue_rows(today));
+            drop(lib);
+            issues_table.set_rows(rows.unwrap_or_default());
+        }
+    );
+    let fill_issues = Rc::new(fill_issues);
+    subscriptions_table.selection.connect_selected_notify(glib::clone!(
+        #[strong]
+        fill_issues,
+        move |_| fill_issues()
+    ));
+
+    let fill = glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        subscriptions_table,
+        #[strong]
+        fill_issues,
+        move || {
+            let lib = library.read().unwrap();
+            let today = lib.today();
+            let rows: Vec<(Subscription, Option<NaiveDate>, usize)> =
+                lib.serials.iter().map(|s| (s.clone(), s.next_expected(today), s.missing(today))).collect();
+            drop(lib);
+            subscriptions_table.set_rows(rows);
+            fill_issues();
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    // Issues fall late with the passing days
+    serials_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+
+    add_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_add_subscription(&window, glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                move |new| {
+                    library.write().unwrap().add_subscription(new)?;
+                    fill();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    remove_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        subscriptions_table,
+        #[weak]
+        serials_box,
+        move |button| {
+            let Some((id, title)) = subscriptions_table.selected(|(s, _, _)| (s.id, s.title.clone())) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::confirm(&window, &tr!("End the subscription to {}?", title), &tr!("Issues already checked in stay in the catalog."), glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                serials_box,
+                move || {
+                    let result = library.write().unwrap().remove_subscription(id);
+                    match result {
+                        Ok(()) => status::success(&serials_box, &tr!("Subscription to {} ended", title)),
+                        Err(e) => status::error(&serials_box, &e),
+                    }
+                    fill();
+                }
+            ));
+        }
+    ));
+
+    claims_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[weak]
+        serials_box,
+        move |button| {
+            let text = library.read().unwrap().claims_text();
+            if text.is_empty() {
+                status::info(&serials_box, &tr!("No issues are missing"));
+                return;
+            }
+            button.clipboard().set_text(&text);
+            status::success(&serials_box, &tr!("Claims list copied to the clipboard"));
+        }
+    ));
+
+    check_in_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        subscriptions_table,
+        #[strong]
+        issues_table,
+        #[weak]
+        serials_box,
+        move |_| {
+            let Some(id) = subscriptions_table.selected(|(s, _, _)| s.id) else { return };
+            let Some((expected, label)) = issues_table.selected(|r| (r.expected, r.label.clone())) else {
+                status::error(&serials_box, &tr!("Select an issue first"));
+                return;
+            };
+            let result = library.write().unwrap().check_in_issue(id, expected);
+            match result {
+                Ok(item_id) => status::success(&serials_box, &tr!("Issue {} checked in as item {}", label, item_id)),
+                Err(e) => status::error(&serials_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    claim_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        subscriptions_table,
+        #[strong]
+        issues_table,
+        #[weak]
+        serials_box,
+        move |_| {
+            let Some(id) = subscriptions_table.selected(|(s, _, _)| s.id) else { return };
+            let Some((expected, label)) = issues_table.selected(|r| (r.expected, r.label.clone())) else {
+                status::error(&serials_box, &tr!("Select an issue first"));
+                return;
+            };
+            let result = library.write().unwrap().claim_issue(id, expected);
+            match result {
+                Ok(()) => status::success(&serials_box, &tr!("Issue {} marked as claimed", label)),
+                Err(e) => status::error(&serials_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    serials_box
+}
+
+// Points a dropdown at `names`, keeping the selection where it can
+fn set_dropdown_names(dropdown: &gtk::DropDown, names: &[String]) {
+    let selected = dropdown.selected();
+    dropdown.set_model(Some(&gtk::StringList::new(&names.iter().map(String::as_str).collect::<Vec<_>>())));
+    if (selected as usize) < names.len() {
+        dropdown.set_selected(selected);
+    }
+}
+
+fn create_acquisitions_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let acquisitions_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    let notebook = gtk::Notebook::new();
+    notebook.set_vexpand(true);
+
+    // Orders
+    let vendor_dropdown = gtk::DropDown::from_strings(&[]);
+    let budget_dropdown = gtk::DropDown::from_strings(&[]);
+    // IDs behind the dropdown entries, in the same order
+    let vendor_ids: Rc<RefCell<Vec<u32>>> = Rc::new(RefCell::new(Vec::new()));
+    let budget_ids: Rc<RefCell<Vec<u32>>> = Rc::new(RefCell::new(Vec::new()));
+    let new_order_button = Button::with_label(&tr!("New Order"));
+    let new_order_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    new_order_box.append(&Label::new(Some(&tr!("Vendor:"))));
+    new_order_box.append(&vendor_dropdown);
+    new_order_box.append(&Label::new(Some(&tr!("Budget:"))));
+    new_order_box.append(&budget_dropdown);
+    new_order_box.append(&new_order_button);
+
+    let orders_table = Rc::new(Table::<(PurchaseOrder, String, String)>::new());
+    orders_table.add_column(&tr!("Order"), |(o, _, _)| o.id.to_string(), |a, b| a.0.id.cmp(&b.0.id));
+    orders_table.add_column(&tr!("Vendor"), |(_, vendor, _)| vendor.clone(), |a, b| a.1.cmp(&b.1));
+    orders_table.add_column(&tr!("Budget"), |(_, _, budget)| budget.clone(), |a, b| a.2.cmp(&b.2));
+    orders_table.add_column(&tr!("Titles"), |(o, _, _)| o.lines.len().to_string(), |a, b| a.0.lines.len().cmp(&b.0.lines.len()));
+    orders_table.add_column(&tr!("Total"), |(o, _, _)| format_money(o.total_cents()), |a, b| a.0.total_cents().cmp(&b.0.total_cents()));
+    orders_table.add_column(&tr!("Status"), |(o, _, _)| o.status.label(), |a, b| a.0.status.label().cmp(&b.0.status.label()));
+    orders_table.add_column(&tr!("Created"), |(o, _, _)| o.created_at.format("%Y-%m-%d").to_string(), |a, b| a.0.created_at.cmp(&b.0.created_at));
+
+    let lines_table = Rc::new(Table::<OrderLine>::new());
+    lines_table.add_column(&tr!("Title"), |l| l.title.clone(), |a, b| a.title.cmp(&b.title));
+    lines_table.add_column(&tr!("Author"), |l| l.author.clone().unwrap_or_default(), |a, b| a.author.cmp(&b.author));
+    lines_table.add_column(&tr!("ISBN"), |l| l.isbn.clone().unwrap_or_default(), |a, b| a.isbn.cmp(&b.isbn));
+    lines_table.add_column(&tr!("Format"), |l| l.format.clone(), |a, b| a.format.cmp(&b.format));
+    lines_table.add_column(&tr!("Copies"), |l| l.quantity.to_string(), |a, b| a.quantity.cmp(&b.quantity));
+    lines_table.add_column(&tr!("Price"), |l| format_money(l.price_cents), |a, b| a.price_cents.cmp(&b.price_cents));
+    lines_table.add_column(&tr!("Total"), |l| format_money(l.total_cents()), |a, b| a.total_cents().cmp(&b.total_cents()));
+    lines_table.add_column(&tr!("Item ID"), |l| l.item_id.map_or(String::new(), |id| id.to_string()), |a, b| a.item_id.cmp(&b.item_id));
+
+    let add_line_button = Button::with_label(&tr!("Add Title..."));
+    let receive_button = Button::with_label(&tr!("Receive Order"));
+    let cancel_button = Button::with_label(&tr!("Cancel Order"));
+    let order_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    order_buttons.append(&add_line_button);
+    order_buttons.append(&receive_button);
+    order_buttons.append(&cancel_button);
+
+    let orders_window = ScrolledWindow::new();
+    orders_window.set_child(Some(&orders_table.view));
+    orders_window.set_vexpand(true);
+    let lines_window = ScrolledWindow::new();
+    lines_window.set_child(Some(&lines_table.view));
+    lines_window.set_vexpand(true);
+    let orders_page = GtkBox::new(gtk::Orientation::Vertical, 10);
+    orders_page.append(&new_order_box);
+    orders_page.append(&orders_window);
+    orders_page.append(&Label::new(Some(&tr!("Titles on the selected order:"))));
+    orders_page.append(&lines_window);
+    orders_page.append(&order_buttons);
+    notebook.append_page(&orders_page, Some(&Label::new(Some(&tr!("Orders")))));
+
+    // Vendors
+    let vendor_name_entry = Entry::new();
+    vendor_name_entry.set_placeholder_text(Some(&tr!("Name")));
+    let vendor_contact_entry = Entry::new();
+    vendor_contact_entry.set_placeholder_text(Some(&tr!("Contact (email, phone or account number)")));
+    vendor_contact_entry.set_hexpand(true);
+    let add_vendor_button = Button::with_label(&tr!("Add Vendor"));
+    let vendor_form = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    vendor_form.append(&vendor_name_entry);
+    vendor_form.append(&vendor_contact_entry);
+    vendor_form.append(&add_vendor_button);
+    let vendors_table = Rc::new(Table::<Vendor>::new());
+    vendors_table.add_column(&tr!("ID"), |v| v.id.to_string(), |a, b| a.id.cmp(&b.id));
+    vendors_table.add_column(&tr!("Name"), |v| v.name.clone(), |a, b| a.name.cmp(&b.name));
+    vendors_table.add_column(&tr!("Contact"), |v| v.contact.clone(), |a, b| a.contact.cmp(&b.contact));
+    let vendors_window = ScrolledWindow::new();
+    vendors_window.set_child(Some(&vendors_table.view));
+    vendors_window.set_vexpand(true);
+    let vendors_page = GtkBox::new(gtk::Orientation::Vertical, 10);
+    vendors_page.append(&vendor_form);
+    vendors_page.append(&vendors_window);
+    notebook.append_page(&vendors_page, Some(&Label::new(Some(&tr!("Vendors")))));
+
+    // Budgets
+    let budget_name_entry = Entry::new();
+    budget_name_entry.set_placeholder_text(Some(&tr!("Name, e.g. Children's books 2026")));
+    budget_name_entry.set_hexpand(true);
+    let budget_amount_entry = Entry::new();
+    budget_amount_entry.set_placeholder_text(Some(&tr!("Amount")));
+    let add_budget_button = Button::with_label(&tr!("Add Budget"));
+    let budget_form = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    budget_form.append(&budget_name_entry);
+    budget_form.append(&budget_amount_entry);
+    budget_form.append(&add_budget_button);
+    let budgets_table = Rc::new(Table::<(Budget, BudgetUse)>::new());
+    budgets_table.add_column(&tr!("Name"), |(b, _)| b.name.clone(), |a, b| a.0.name.cmp(&b.0.name));
+    budgets_table.add_column(&tr!("Allocated"), |(b, _)| format_money(b.allocated_cents), |a, b| a.0.allocated_cents.cmp(&b.0.allocated_cents));
+    budgets_table.add_column(&tr!("Spent"), |(_, u)| format_money(u.spent_cents), |a, b| a.1.spent_cents.cmp(&b.1.spent_cents));
+    budgets_table.add_column(&tr!("On Order"), |(_, u)| format_money(u.committed_cents), |a, b| a.1.committed_cents.cmp(&b.1.committed_cents));
+    let remaining = |(b, u): &(Budget, BudgetUse)| b.allocated_cents.saturating_sub(u.spent_cents + u.committed_cents);
+    budgets_table.add_column(&tr!("Remaining"), move |row| format_money(remaining(row)), move |a, b| remaining(a).cmp(&remaining(b)));
+    let budgets_window = ScrolledWindow::new();
+    budgets_window.set_child(Some(&budgets_table.view));
+    budgets_window.set_vexpand(true);
+    let budgets_page = GtkBox::new(gtk::Orientation::Vertical, 10);
+    budgets_page.append(&budget_form);
+    budgets_page.append(&budgets_window);
+    notebook.append_page(&budgets_page, Some(&Label::new(Some(&tr!("Budgets")))));
+
+    // Suggestions patrons made at the kiosk
+    let suggestions_table = Rc::new(Table::<Suggestion>::new());
+    suggestions_table.add_column(&tr!("ID"), |s| s.id.to_string(), |a, b| a.id.cmp(&b.id));
+    suggestions_table.add_column(&tr!("Date"), |s| s.at.format("%Y-%m-%d").to_string(), |a, b| a.at.cmp(&b.at));
+    suggestions_table.add_column(&tr!("Title"), |s| s.title.clone(), |a, b| a.title.cmp(&b.title));
+    suggestions_table.add_column(&tr!("Author"), |s| s.author.clone().unwrap_or_default(), |a, b| a.author.cmp(&b.author));
+    suggestions_table.add_column(&tr!("Notes"), |s| s.notes.clone(), |a, b| a.notes.cmp(&b.notes));
+    suggestions_table.add_column(&tr!("Member ID"), |s| s.member_id.map_or(String::new(), |id| id.to_string()), |a, b| a.member_id.cmp(&b.member_id));
+    suggestions_table.add_column(&tr!("Status"), |s| s.status.label(), |a, b| a.status.label().cmp(&b.status.label()));
+    suggestions_table.add_column(&tr!("Decision"), |s| s.decision_text(), |a, b| a.decision_text().cmp(&b.decision_text()));
+    let suggestion_order_dropdown = gtk::DropDown::from_strings(&[]);
+    // IDs of the open orders behind the dropdown entries
+    let open_order_ids: Rc<RefCell<Vec<u32>>> = Rc::new(RefCell::new(Vec::new()));
+    let approve_button = Button::with_label(&tr!("Approve..."));
+    let reason_names: Vec<String> = RejectReason::ALL.iter().map(|r| r.label()).collect();
+    let reason_dropdown = gtk::DropDown::from_strings(&reason_names.iter().map(String::as_str).collect::<Vec<_>>());
+    let reject_button = Button::with_label(&tr!("Reject"));
+    let suggestion_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    suggestion_buttons.append(&Label::new(Some(&tr!("Add to order:"))));
+    suggestion_buttons.append(&suggestion_order_dropdown);
+    suggestion_buttons.append(&approve_button);
+    suggestion_buttons.append(&Label::new(Some(&tr!("Reason:"))));
+    suggestion_buttons.append(&reason_dropdown);
+    suggestion_buttons.append(&reject_button);
+    let suggestions_window = ScrolledWindow::new();
+    suggestions_window.set_child(Some(&suggestions_table.view));
+    suggestions_window.set_vexpand(true);
+    let suggestions_page = GtkBox::new(gtk::Orientation::Vertical, 10);
+    suggestions_page.append(&suggestions_window);
+    suggestions_page.append(&suggestion_buttons);
+    notebook.append_page(&suggestions_page, Some(&Label::new(Some(&tr!("Suggestions")))));
+
+    let fill_lines = glib::clone!(
+        #[strong]
+        orders_table,
+        #[strong]
+        lines_table,
+        move || {
+            let lines = orders_table.selected(|(o, _, _)| o.lines.clone()).unwrap_or_default();
+            lines_table.set_rows(lines);
+        }
+    );
+    let fill_lines = Rc::new(fill_lines);
+    orders_table.selection.connect_selected_notify(glib::clone!(
+        #[strong]
+        fill_lines,
+        move |_| fill_lines()
+    ));
+
+    let fill = glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        orders_table,
+        #[strong]
+        vendors_table,
+        #[strong]
+        budgets_table,
+        #[strong]
+        vendor_ids,
+        #[strong]
+        budget_ids,
+        #[weak]
+        vendor_dropdown,
+        #[weak]
+        budget_dropdown,
+        #[strong]
+        fill_lines,
+        #[strong]
+        suggestions_table,
+        #[strong]
+        open_order_ids,
+        #[weak]
+        suggestion_order_dropdown,
+        move || {
+            if let Err(e) = library.write().unwrap().take_suggestions(Path::new(suggestions::INBOX_PATH)) {
+                tracing::error!("Failed to take in purchase suggestions: {}", e);
+            }
+            let lib = library.read().unwrap();
+            let acq = &lib.acquisitions;
+            let orders: Vec<(PurchaseOrder, String, String)> = acq
+                .orders
+                .iter()
+                .map(|o| (o.clone(), acq.vendor_name(o.vendor_id), acq.budget_name(o.budget_id)))
+                .collect();
+            let vendors = acq.vendors.clone();
+            let budgets: Vec<(Budget, BudgetUse)> = acq.budgets.iter().map(|b| (b.clone(), acq.budget_use(b.id))).collect();
+            let open_orders: Vec<(u32, String)> = acq
+                .orders
+                .iter()
+                .filter(|o| o.status == OrderStatus::Open)
+                .map(|o| (o.id, tr!("Order {} ({})", o.id, acq.vendor_name(o.vendor_id))))
+                .collect();
+            // Undecided ones first, newest first
+            let mut suggestions = lib.suggestions.clone();
+            suggestions.sort_by(|a, b| (b.status == SuggestionStatus::New).cmp(&(a.status == SuggestionStatus::New)).then(b.id.cmp(&a.id)));
+            drop(lib);
+
+            *vendor_ids.borrow_mut() = vendors.iter().map(|v| v.id).collect();
+            set_dropdown_names(&vendor_dropdown, &vendors.iter().map(|v| v.name.clone()).collect::<Vec<_>>());
+            *budget_ids.borrow_mut() = budgets.iter().map(|(b, _)| b.id).collect();
+            set_dropdown_names(&budget_dropdown, &budgets.iter().map(|(b, _)| b.name.clone()).collect::<Vec<_>>());
+            orders_table.set_rows(orders);
+            vendors_table.set_rows(vendors);
+            budgets_table.set_rows(budgets);
+            *open_order_ids.borrow_mut() = open_orders.iter().map(|(id, _)| *id).collect();
+            set_dropdown_names(&suggestion_order_dropdown, &open_orders.into_iter().map(|(_, name)| name).collect::<Vec<_>>());
+            suggestions_table.set_rows(suggestions);
+            fill_lines();
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    acquisitions_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+
+    new_order_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        vendor_ids,
+        #[strong]
+        budget_ids,
+        #[weak]
+        vendor_dropdown,
+        #[weak]
+        budget_dropdown,
+        #[weak]
+        acquisitions_box,
+        move |_| {
+            let vendor_id = vendor_ids.borrow().get(vendor_dropdown.selected() as usize).copied();
+            let budget_id = budget_ids.borrow().get(budget_dropdown.selected() as usize).copied();
+            let (Some(vendor_id), Some(budget_id)) = (vendor_id, budget_id) else {
+                status::error(&acquisitions_box, &tr!("Add a vendor and a budget first"));
+                return;
+            };
+            let result = library.write().unwrap().create_order(vendor_id, budget_id);
+            match result {
+                Ok(id) => status::success(&acquisitions_box, &tr!("Order {} created", id)),
+                Err(e) => status::error(&acquisitions_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    add_line_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        orders_table,
+        move |button| {
+            let Some(order_id) = orders_table.selected(|(o, _, _)| o.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_add_order_line(&window, "", "", glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                move |line| {
+                    library.write().unwrap().add_order_line(order_id, line)?;
+                    fill();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    receive_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        orders_table,
+        #[weak]
+        acquisitions_box,
+        move |_| {
+            let Some(order_id) = orders_table.selected(|(o, _, _)| o.id) else { return };
+            let result = library.write().unwrap().receive_order(order_id);
+            match result {
+                Ok(copies) => status::success(&acquisitions_box, &tr!("Order {} received: {} copies added to the catalog", order_id, copies)),
+                Err(e) => status::error(&acquisitions_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    cancel_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        orders_table,
+        #[weak]
+        acquisitions_box,
+        move |button| {
+            let Some(order_id) = orders_table.selected(|(o, _, _)| o.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::confirm(&window, &tr!("Cancel order {}?", order_id), &tr!("Its titles will no longer count against the budget."), glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                acquisitions_box,
+                move || {
+                    let result = library.write().unwrap().cancel_order(order_id);
+                    match result {
+                        Ok(()) => status::success(&acquisitions_box, &tr!("Order {} cancelled", order_id)),
+                        Err(e) => status::error(&acquisitions_box, &e),
+                    }
+                    fill();
+                }
+            ));
+        }
+    ));
+
+    add_vendor_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        vendor_name_entry,
+        #[weak]
+        vendor_contact_entry,
+        #[weak]
+        acquisitions_box,
+        move |_| {
+            let result = library.write().unwrap().add_vendor(&vendor_name_entry.text(), &vendor_contact_entry.text());
+            match result {
+                Ok(_) => {
+                    status::success(&acquisitions_box, &tr!("Vendor {} added", vendor_name_entry.text().trim()));
+                    vendor_name_entry.set_text("");
+                    vendor_contact_entry.set_text("");
+                    fill();
+                }
+                Err(e) => status::error(&acquisitions_box, &e),
+            }
+        }
+    ));
+
+    add_budget_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        budget_name_entry,
+        #[weak]
+        budget_amount_entry,
+        #[weak]
+        acquisitions_box,
+        move |_| {
+            let result = fines::parse_money(&budget_amount_entry.text())
+                .and_then(|cents| library.write().unwrap().add_budget(&budget_name_entry.text(), cents));
+            match result {
+                Ok(_) => {
+                    status::success(&acquisitions_box, &tr!("Budget {} added", budget_name_entry.text().trim()));
+                    budget_name_entry.set_text("");
+                    budget_amount_entry.set_text("");
+                    fill();
+                }
+                Err(e) => status::error(&acquisitions_box, &e),
+            }
+        }
+    ));
+
+    approve_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        suggestions_table,
+        #[strong]
+        open_order_ids,
+        #[weak]
+        suggestion_order_dropdown,
+        #[weak]
+        acquisitions_box,
+        move |button| {
+            let Some((id, title, author)) = suggestions_table.selected(|s| (s.id, s.title.clone(), s.author.clone().unwrap_or_default())) else { return };
+            let Some(order_id) = open_order_ids.borrow().get(suggestion_order_dropdown.selected() as usize).copied() else {
+                status::error(&acquisitions_box, &tr!("Create an order to add the title to first"));
+                return;
+            };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_add_order_line(&window, &title, &author, glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                acquisitions_box,
+                #[upgrade_or]
+                Ok(()),
+                move |line| {
+                    library.write().unwrap().approve_suggestion(id, order_id, line)?;
+                    status::success(&acquisitions_box, &tr!("Suggestion {} added to order {}", id, order_id));
+                    fill();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    reject_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        suggestions_table,
+        #[weak]
+        reason_dropdown,
+        #[weak]
+        acquisitions_box,
+        move |_| {
+            let Some(id) = suggestions_table.selected(|s| s.id) else { return };
+            let reason = RejectReason::ALL[reason_dropdown.selected() as usize % RejectReason::ALL.len()];
+            let result = library.write().unwrap().reject_suggestion(id, reason);
+            match result {
+                Ok(()) => status::success(&acquisitions_box, &tr!("Suggestion {} rejected", id)),
+                Err(e) => status::error(&acquisitions_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    acquisitions_box.append(&notebook);
+    acquisitions_box
+}
+
+// Scans listed on the stocktake page, most recent first
+const RECENT_SCANS: usize = 50;
+
+fn create_stocktake_page(library: Arc<RwLock<Library>>, role: Role) -> GtkBox {
+    let stocktake_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    // Shown while no stocktake is running
+    let setup_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    let branch_entry = Entry::new();
+    branch_entry.set_text(&transfers::default_branch());
+    let location_entry = Entry::new();
+    location_entry.set_placeholder_text(Some(&tr!("All locations")));
+    let start_button = Button::with_label(&tr!("Start Stocktake"));
+    setup_box.append(&Label::new(Some(&tr!("Branch:"))));
+    setup_box.append(&branch_entry);
+    setup_box.append(&Label::new(Some(&tr!("Shelf location:"))));
+    setup_box.append(&location_entry);
+    setup_box.append(&start_button);
+
+    // Shown while one is
+    let session_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    let session_label = Label::new(None);
+    let barcode_entry = Entry::new();
+    barcode_entry.set_placeholder_text(Some(&tr!("Scan an item barcode")));
+    let undo_button = Button::with_label(&tr!("Undo Last Scan"));
+    let report_button = Button::with_label(&tr!("Show Report..."));
+
+    let table = Rc::new(Table::<LiItem>::new());
+    table.add_column(&tr!("Item ID"), |i| i.id.to_string(), |a, b| a.id.cmp(&b.id));
+    table.add_column(&tr!("Title"), |i| i.title.to_string(), |a, b| a.title.cmp(&b.title));
+    table.add_column(&tr!("Call Number"), |i| i.call_number.clone(), |a, b| callnumber::compare(&a.call_number, &b.call_number));
+    table.add_column(&tr!("Shelf Location"), |i| i.shelf_location.clone(), |a, b| a.shelf_location.cmp(&b.shelf_location));
+    table.add_column(&tr!("Branch"), |i| i.branch.clone(), |a, b| a.branch.cmp(&b.branch));
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    let button_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    button_box.append(&undo_button);
+    button_box.append(&report_button);
+    session_box.append(&session_label);
+    session_box.append(&barcode_entry);
+    session_box.append(&button_box);
+    session_box.append(&scrolled_window);
+
+    let update = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[weak]
+        setup_box,
+        #[weak]
+        session_box,
+        #[weak]
+        session_label,
+        move || {
+            let lib = library.read().unwrap();
+            let (summary, rows) = match &lib.stocktake {
+                Some(stocktake) => {
+                    let place = if stocktake.shelf_location.is_empty() {
+                        stocktake.branch.clone()
+                    } else {
+                        format!("{}, {}", stocktake.branch, stocktake.shelf_location)
+                    };
+                    let summary = tr!(
+                        "Counting {} since {}: {} copies scanned",
+                        place,
+                        stocktake.started_at.format("%Y-%m-%d"),
+                        stocktake.scans.len()
+                    );
+                    let rows: Vec<LiItem> = stocktake
+                        .scans
+                        .iter()
+                        .rev()
+                        .take(RECENT_SCANS)
+                        .filter_map(|id| lib.items.get(id).cloned())
+                        .collect();
+                    (Some(summary), rows)
+                }
+                None => (None, Vec::new()),
+            };
+            drop(lib);
+            setup_box.set_visible(summary.is_none());
+            session_box.set_visible(summary.is_some());
+            session_label.set_text(&summary.unwrap_or_default());
+            table.set_rows(rows);
+        }
+    ));
+    update();
+
+    start_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        update,
+        #[weak]
+        branch_entry,
+        #[weak]
+        location_entry,
+        #[weak]
+        barcode_entry,
+        #[weak]
+        stocktake_box,
+        move |_| {
+            let result = library.write().unwrap().start_stocktake(&branch_entry.text(), &location_entry.text());
+            match result {
+                Ok(()) => {
+                    update();
+                    barcode_entry.grab_focus();
+                }
+                Err(e) => status::error(&stocktake_box, &e),
+            }
+        }
+    ));
+
+    // Barcode scanners type the number and press Enter
+    barcode_entry.connect_activate(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        update,
+        #[weak]
+        stocktake_box,
+        move |entry| {
+            let Ok(item_id) = entry.text().trim().parse::<u32>() else {
+                status::error(&stocktake_box, &tr!("Invalid Item ID!"));
+                entry.set_text("");
+                return;
+            };
+            entry.set_text("");
+            let result = library.write().unwrap().record_scan(item_id);
+            match result {
+                Ok((title, None)) => status::success(&stocktake_box, &tr!("Scanned {}", title)),
+                Ok((title, Some(home))) => status::error(&stocktake_box, &tr!("{} is misshelved; it belongs in {}", title, home)),
+                Err(e) => status::error(&stocktake_box, &e),
+            }
+            update();
+        }
+    ));
+
+    undo_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        update,
+        #[weak]
+        stocktake_box,
+        move |_| {
+            let result = library.write().unwrap().undo_scan();
+            match result {
+                Ok(item_id) => status::info(&stocktake_box, &tr!("Removed the last scan of item {}", item_id)),
+                Err(e) => status::error(&stocktake_box, &e),
+            }
+            update();
+        }
+    ));
+
+    report_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        update,
+        #[weak]
+        stocktake_box,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_stocktake_report(&window, library.clone(), role >= Role::Librarian, glib::clone!(
+                #[strong]
+                update,
+                #[weak]
+                stocktake_box,
+                move || {
+                    update();
+                    status::success(&stocktake_box, &tr!("Stocktake finished"));
+                }
+            ));
+        }
+    ));
+
+    stocktake_box.append(&setup_box);
+    stocktake_box.append(&session_box);
+
+    stocktake_box
+}
+
+fn create_events_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let events_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+
+    // Calendar and rooms
+    let calendar = gtk::Calendar::new();
+    let rooms_table = Rc::new(Table::<Room>::new());
+    rooms_table.add_column(&tr!("Room"), |r| r.name.clone(), |a, b| a.name.cmp(&b.name));
+    rooms_table.add_column(&tr!("Holds"), |r| r.capacity.to_string(), |a, b| a.capacity.cmp(&b.capacity));
+    let rooms_window = ScrolledWindow::new();
+    rooms_window.set_child(Some(&rooms_table.view));
+    rooms_window.set_vexpand(true);
+    let room_name_entry = Entry::new();
+    room_name_entry.set_placeholder_text(Some(&tr!("Room name")));
+    let room_capacity_spin = gtk::SpinButton::with_range(0.0, 9999.0, 1.0);
+    room_capacity_spin.set_tooltip_text(Some(&tr!("How many people the room holds; 0 if it doesn't matter")));
+    let add_room_button = Button::with_label(&tr!("Add Room"));
+    let room_form = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    room_form.append(&room_name_entry);
+    room_form.append(&room_capacity_spin);
+    room_form.append(&add_room_button);
+    let side_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    side_box.append(&calendar);
+    side_box.append(&rooms_window);
+    side_box.append(&room_form);
+
+    // The selected day
+    let day_label = Label::new(None);
+    day_label.set_xalign(0.0);
+    let schedule_table = Rc::new(Table::<ScheduleRow>::new());
+    let local_time = |at: &DateTime<Utc>| at.with_timezone(&chrono::Local).format("%H:%M").to_string();
+    schedule_table.add_column(&tr!("From"), move |r| local_time(&r.start), |a, b| a.start.cmp(&b.start));
+    schedule_table.add_column(&tr!("To"), move |r| local_time(&r.end), |a, b| a.end.cmp(&b.end));
+    schedule_table.add_column(&tr!("Room"), |r| r.room.clone(), |a, b| a.room.cmp(&b.room));
+    schedule_table.add_column(&tr!("What"), |r| r.title.clone(), |a, b| a.title.cmp(&b.title));
+    schedule_table.add_column(&tr!("Registered"), |r| r.attendance.clone(), |a, b| a.attendance.cmp(&b.attendance));
+    let schedule_window = ScrolledWindow::new();
+    schedule_window.set_child(Some(&schedule_table.view));
+    schedule_window.set_vexpand(true);
+    let new_event_button = Button::with_label(&tr!("New Event..."));
+    let book_room_button = Button::with_label(&tr!("Book a Room..."));
+    let cancel_button = Button::with_label(&tr!("Cancel Selected"));
+    let schedule_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    schedule_buttons.append(&new_event_button);
+    schedule_buttons.append(&book_room_button);
+    schedule_buttons.append(&cancel_button);
+
+    let attendees_table = Rc::new(Table::<(u32, String)>::new());
+    attendees_table.add_column(&tr!("Member ID"), |(id, _)| id.to_string(), |a, b| a.0.cmp(&b.0));
+    attendees_table.add_column(&tr!("Name"), |(_, name)| name.clone(), |a, b| a.1.cmp(&b.1));
+    let attendees_window = ScrolledWindow::new();
+    attendees_window.set_child(Some(&attendees_table.view));
+    attendees_window.set_vexpand(true);
+    let member_id_entry = Entry::new();
+    member_id_entry.set_placeholder_text(Some(&tr!("Member ID")));
+    attach_completion(&member_id_entry, library.clone(), fill_member_completion);
+    let register_button = Button::with_label(&tr!("Register"));
+    let unregister_button = Button::with_label(&tr!("Remove Attendee"));
+    let attendee_buttons = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    attendee_buttons.append(&member_id_entry);
+    attendee_buttons.append(&register_button);
+    attendee_buttons.append(&unregister_button);
+
+    let day_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    day_box.set_hexpand(true);
+    day_box.append(&day_label);
+    day_box.append(&schedule_window);
+    day_box.append(&schedule_buttons);
+    day_box.append(&Label::new(Some(&tr!("Attendees of the selected event:"))));
+    day_box.append(&attendees_window);
+    day_box.append(&attendee_buttons);
+
+    events_box.append(&side_box);
+    events_box.append(&day_box);
+
+    let selected_event = glib::clone!(
+        #[strong]
+        schedule_table,
+        move || match schedule_table.selected(|r| r.what) {
+            Some(Scheduled::Event(id)) => Some(id),
+            _ => None,
+        }
+    );
+    let selected_event = Rc::new(selected_event);
+
+    let fill_attendees = glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        attendees_table,
+        #[strong]
+        selected_event,
+        move || {
+            let lib = library.read().unwrap();
+            let rows: Vec<(u32, String)> = selected_event()
+                .and_then(|id| lib.events.events.iter().find(|e| e.id == id))
+                .map(|e| {
+                    e.attendees
+                        .iter()
+                        .map(|id| (*id, lib.members.get(id).map_or_else(|| "?".to_string(), |m| m.name.clone())))
+                        .collect()
+                })
+                .unwrap_or_default();
+            drop(lib);
+            attendees_table.set_rows(rows);
+        }
+    );
+    let fill_attendees = Rc::new(fill_attendees);
+    schedule_table.selection.connect_selected_notify(glib::clone!(
+        #[strong]
+        fill_attendees,
+        move |_| fill_attendees()
+    ));
+
+    let fill = glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        rooms_table,
+        #[strong]
+        schedule_table,
+        #[strong]
+        fill_attendees,
+        #[weak]
+        calendar,
+        #[weak]
+        day_label,
+        move || {
+            let date = calendar_date(&calendar);
+            let lib = library.read().unwrap();
+            let rooms = lib.events.rooms.clone();
+            let schedule = lib.day_schedule(date);
+            let busy = lib.busy_days(date.year(), date.month());
+            drop(lib);
+
+            calendar.clear_marks();
+            for day in busy {
+                calendar.mark_day(day);
+            }
+            day_label.set_markup(&format!("<b>{}</b>", glib::markup_escape_text(&date.format("%Y-%m-%d").to_string())));
+            rooms_table.set_rows(rooms);
+            schedule_table.set_rows(schedule);
+            fill_attendees();
+        }
+    );
+    let fill = Rc::new(fill);
+    fill();
+    events_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    calendar.connect_day_selected(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    // Marks go by day of the month, so they change with the month shown
+    calendar.connect_month_notify(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+
+    add_room_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        room_name_entry,
+        #[weak]
+        room_capacity_spin,
+        #[weak]
+        events_box,
+        move |_| {
+            let result = library.write().unwrap().add_room(&room_name_entry.text(), room_capacity_spin.value_as_int() as u32);
+            match result {
+                Ok(_) => {
+                    status::success(&events_box, &tr!("Room {} added", room_name_entry.text().trim()));
+                    room_name_entry.set_text("");
+                    fill();
+                }
+                Err(e) => status::error(&events_box, &e),
+            }
+        }
+    ));
+
+    new_event_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        calendar,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let rooms = library.read().unwrap().events.rooms.clone();
+            dialogs::show_new_event(&window, calendar_date(&calendar), &rooms, glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                move |new| {
+                    library.write().unwrap().create_event(new)?;
+                    fill();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    book_room_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        calendar,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let rooms = library.read().unwrap().events.rooms.clone();
+            dialogs::show_book_room(&window, calendar_date(&calendar), &rooms, glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                move |room_id, booked_by, start, end| {
+                    library.write().unwrap().book_room(room_id, &booked_by, start, end)?;
+                    fill();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    cancel_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        schedule_table,
+        #[weak]
+        events_box,
+        move |button| {
+            let Some((what, title)) = schedule_table.selected(|r| (r.what, r.title.clone())) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let detail = match what {
+                Scheduled::Event(_) => tr!("Registered members are not told; let them know yourself."),
+                Scheduled::Booking(_) => tr!("The room becomes free for others to book."),
+            };
+            dialogs::confirm(&window, &tr!("Cancel {}?", title), &detail, glib::clone!(
+                #[strong]
+                library,
+                #[strong]
+                fill,
+                #[weak]
+                events_box,
+                move || {
+                    let result = library.write().unwrap().cancel_scheduled(what);
+                    match result {
+                        Ok(()) => status::success(&events_box, &tr!("{} cancelled", title)),
+                        Err(e) => status::error(&events_box, &e),
+                    }
+                    fill();
+                }
+            ));
+        }
+    ));
+
+    register_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        selected_event,
+        #[weak]
+        member_id_entry,
+        #[weak]
+        events_box,
+        move |_| {
+            let Some(event_id) = selected_event() else {
+                status::error(&events_box, &tr!("Select an event first"));
+                return;
+            };
+            let Ok(member_id) = member_id_entry.text().trim().parse::<u32>() else {
+                status::error(&events_box, &tr!("Invalid Member ID"));
+                return;
+            };
+            let result = library.write().unwrap().register_attendee(event_id, member_id);
+            match result {
+                Ok(()) => {
+                    status::success(&events_box, &tr!("Member {} registered", member_id));
+                    member_id_entry.set_text("");
+                }
+                Err(e) => status::error(&events_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    unregister_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[strong]
+        selected_event,
+        #[strong]
+        attendees_table,
+        #[weak]
+        events_box,
+        move |_| {
+            let Some(event_id) = selected_event() else { return };
+            let Some(member_id) = attendees_table.selected(|(id, _)| *id) else { return };
+            let result = library.write().unwrap().unregister_attendee(event_id, member_id);
+            match result {
+                Ok(()) => status::success(&events_box, &tr!("Member {} no longer registered", member_id)),
+                Err(e) => status::error(&events_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    events_box
+}
+
+// A button showing a date that opens a calendar to change it
+fn date_picker(initial: NaiveDate) -> (gtk::MenuButton, gtk::Calendar) {
+    let calendar = gtk::Calendar::new();
+    if let Ok(date) = glib::DateTime::from_local(initial.year(), initial.month() as i32, initial.day() as i32, 0, 0, 0.0) {
+        calendar.select_day(&date);
+    }
+    let popover = gtk::Popover::new();
+    popover.set_child(Some(&calendar));
+    let button = gtk::MenuButton::new();
+    button.set_label(&initial.format("%Y-%m-%d").to_string());
+    button.set_popover(Some(&popover));
+
+    calendar.connect_day_selected(glib::clone!(
+        #[weak]
+        button,
+        #[weak]
+        popover,
+        move |calendar| {
+            button.set_label(&calendar_date(calendar).format("%Y-%m-%d").to_string());
+            popover.popdown();
+        }
+    ));
+    (button, calendar)
+}
+
+fn calendar_date(calendar: &gtk::Calendar) -> NaiveDate {
+    let date = calendar.date();
+    NaiveDate::from_ymd_opt(date.year(), date.month() as u32, date.day_of_month() as u32).unwrap_or_default()
+}
+
+fn create_cash_page(library: Arc<RwLock<Library>>, bus: &EventBus, config: Rc<RefCell<Config>>) -> GtkBox {
+    let cash_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let session_label = Label::new(None);
+    session_label.set_xalign(0.0);
+
+    let float_entry = Entry::new();
+    float_entry.set_placeholder_text(Some(&tr!("Opening float, e.g. 50.00")));
+    let open_button = Button::with_label(&tr!("Open Session"));
+    let open_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    open_box.append(&float_entry);
+    open_box.append(&open_button);
+
+    let payments_table = Rc::new(Table::<CashPayment>::new());
+    payments_table.add_column(
+        &tr!("Time"),
+        |p| p.at.with_timezone(&chrono::Local).format("%H:%M").to_string(),
+        |a, b| a.at.cmp(&b.at),
+    );
+    payments_table.add_column(&tr!("Member"), |p| p.member_id.to_string(), |a, b| a.member_id.cmp(&b.member_id));
+    payments_table.add_column(&tr!("Fine"), |p| p.fine_id.to_string(), |a, b| a.fine_id.cmp(&b.fine_id));
+    payments_table.add_column(&tr!("Amount"), |p| format_money(p.amount_cents), |a, b| a.amount_cents.cmp(&b.amount_cents));
+    payments_table.add_column(&tr!("Staff"), |p| p.user.clone(), |a, b| a.user.cmp(&b.user));
+
+    let totals_label = Label::new(None);
+    totals_label.set_xalign(0.0);
+
+    let counted_entry = Entry::new();
+    counted_entry.set_placeholder_text(Some(&tr!("Cash counted in the drawer")));
+    let close_button = Button::with_label(&tr!("Close Session"));
+    let close_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    close_box.append(&counted_entry);
+    close_box.append(&close_button);
+
+    let history_table = Rc::new(Table::<CashSession>::new());
+    history_table.add_column(&tr!("ID"), |s| s.id.to_string(), |a, b| a.id.cmp(&b.id));
+    history_table.add_column(
+        &tr!("Opened"),
+        |s| s.opened_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M").to_string(),
+        |a, b| a.opened_at.cmp(&b.opened_at),
+    );
+    history_table.add_column(&tr!("Staff"), |s| s.opened_by.clone(), |a, b| a.opened_by.cmp(&b.opened_by));
+    history_table.add_column(&tr!("Expected"), |s| format_money(s.expected_cents()), |a, b| a.expected_cents().cmp(&b.expected_cents()));
+    history_table.add_column(
+        &tr!("Counted"),
+        |s| s.counted_cents.map_or(String::new(), format_money),
+        |a, b| a.counted_cents.cmp(&b.counted_cents),
+    );
+    history_table.add_column(
+        &tr!("Difference"),
+        |s| match s.difference_cents() {
+            Some(d) if d < 0 => format!("-{}", format_money(d.unsigned_abs())),
+            Some(d) => format_money(d as u64),
+            None => String::new(),
+        },
+        |a, b| a.difference_cents().cmp(&b.difference_cents()),
+    );
+    let save_again_button = Button::with_label(&tr!("Save Report Again"));
+    save_again_button.set_tooltip_text(Some(&tr!("Write the selected session's reconciliation to the reports directory")));
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        payments_table,
+        #[strong]
+        history_table,
+        #[weak]
+        session_label,
+        #[weak]
+        totals_label,
+        #[weak]
+        open_box,
+        #[weak]
+        close_box,
+        move || {
+            let lib = library.read().unwrap();
+            let open = lib.open_cash_session().cloned();
+            let closed: Vec<CashSession> = lib.cash_sessions.iter().filter(|s| !s.is_open()).rev().cloned().collect();
+            drop(lib);
+            match &open {
+                Some(session) => {
+                    session_label.set_text(&tr!(
+                        "Cash session {} open since {} ({})",
+                        session.id,
+                        session.opened_at.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"),
+                        session.opened_by
+                    ));
+                    totals_label.set_text(&tr!(
+                        "Float {} + payments {} = {} expected in the drawer",
+                        format_money(session.opening_float_cents),
+                        format_money(session.taken_cents()),
+                        format_money(session.expected_cents())
+                    ));
+                }
+                None => {
+                    session_label.set_text(&tr!("No cash session is open. Open one before taking fine payments."));
+                    totals_label.set_text("");
+                }
+            }
+            open_box.set_visible(open.is_none());
+            close_box.set_visible(open.is_some());
+            payments_table.set_rows(open.map(|s| s.payments).unwrap_or_default());
+            history_table.set_rows(closed);
+        }
+    ));
+    fill();
+    // Payments are taken on the Return page
+    cash_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&cash_box, fill.clone());
+
+    open_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        fill,
+        #[weak]
+        float_entry,
+        #[weak]
+        cash_box,
+        move |_| {
+            let text = float_entry.text();
+            let float = if text.trim().is_empty() { Ok(0) } else { fines::parse_money(&text) };
+            let result = float.and_then(|float| library.write().unwrap().start_cash_session(float));
+            match result {
+                Ok(id) => {
+                    status::success(&cash_box, &tr!("Cash session {} opened", id));
+                    float_entry.set_text("");
+                }
+                Err(e) => status::error(&cash_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    // Saves the report and shows it, so it can be checked against the
+    // drawer straight away
+    let save_report = move |window: &gtk::Window, session: &CashSession, dir: &str| match cash::save_reconciliation(session, dir) {
+        Ok(path) => {
+            dialogs::show_info(window, &tr!("Reconciliation saved to {}", path.display()), &session.reconciliation_text());
+        }
+        Err(e) => dialogs::show_error(window, &tr!("Failed to save the reconciliation report"), &e.to_string()),
+    };
+
+    close_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        config,
+        #[strong]
+        fill,
+        #[weak]
+        counted_entry,
+        #[weak]
+        cash_box,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            if counted_entry.text().trim().is_empty() {
+                status::error(&cash_box, &tr!("Count the cash in the drawer and enter the total"));
+                return;
+            }
+            let result = fines::parse_money(&counted_entry.text()).and_then(|counted| library.write().unwrap().close_cash_session(counted));
+            match result {
+                Ok(session) => {
+                    counted_entry.set_text("");
+                    status::success(&cash_box, &tr!("Cash session {} closed", session.id));
+                    save_report(&window, &session, &config.borrow().reports_dir);
+                }
+                Err(e) => status::error(&cash_box, &e),
+            }
+            fill();
+        }
+    ));
+
+    save_again_button.connect_clicked(glib::clone!(
+        #[strong]
+        history_table,
+        #[strong]
+        config,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let Some(session) = history_table.selected(|s| s.clone()) else { return };
+            save_report(&window, &session, &config.borrow().reports_dir);
+        }
+    ));
+
+    let payments_window = ScrolledWindow::new();
+    payments_window.set_child(Some(&payments_table.view));
+    payments_window.set_vexpand(true);
+    let history_window = ScrolledWindow::new();
+    history_window.set_child(Some(&history_table.view));
+    history_window.set_vexpand(true);
+
+    cash_box.append(&session_label);
+    cash_box.append(&open_box);
+    cash_box.append(&payments_window);
+    cash_box.append(&totals_label);
+    cash_box.append(&close_box);
+    cash_box.append(&Label::new(Some(&tr!("Closed sessions:"))));
+    cash_box.append(&history_window);
+    cash_box.append(&save_again_button);
+
+    cash_box
+}
+
+fn create_reports_page(library: Arc<RwLock<Library>>, bus: &EventBus) -> GtkBox {
+    let reports_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let today = chrono::Local::now().date_naive();
+    let (from_button, from_calendar) = date_picker(today.with_day(1).unwrap());
+    let (to_button, to_calendar) = date_picker(today);
+    let period_names: Vec<String> = Period::ALL.iter().map(|p| p.label()).collect();
+    let period_dropdown = gtk::DropDown::from_strings(&period_names.iter().map(String::as_str).collect::<Vec<_>>());
+    let today_button = Button::with_label(&tr!("Today"));
+
+    let controls = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    controls.append(&Label::new(Some(&tr!("From:"))));
+    controls.append(&from_button);
+    controls.append(&Label::new(Some(&tr!("To:"))));
+    controls.append(&to_button);
+    controls.append(&period_dropdown);
+    controls.append(&today_button);
+
+    let table = Rc::new(Table::<PeriodSummary>::new());
+    table.add_column(&tr!("Period"), |s| s.start.format("%Y-%m-%d").to_string(), |a, b| a.start.cmp(&b.start));
+    table.add_column(&tr!("Checkouts"), |s| s.checkouts.to_string(), |a, b| a.checkouts.cmp(&b.checkouts));
+    table.add_column(&tr!("Returns"), |s| s.returns.to_string(), |a, b| a.returns.cmp(&b.returns));
+    table.add_column(&tr!("Renewals"), |s| s.renewals.to_string(), |a, b| a.renewals.cmp(&b.renewals));
+    table.add_column(&tr!("New Members"), |s| s.new_members.to_string(), |a, b| a.new_members.cmp(&b.new_members));
+    table.add_column(
+        &tr!("Fines Collected"),
+        |s| format_money(s.fines_collected_cents),
+        |a, b| a.fines_collected_cents.cmp(&b.fines_collected_cents),
+    );
+    table.add_column(
+        &tr!("Fines Waived"),
+        |s| format_money(s.fines_waived_cents),
+        |a, b| a.fines_waived_cents.cmp(&b.fines_waived_cents),
+    );
+    table.add_column(
+        &tr!("Fines Forgiven"),
+        |s| format_money(s.fines_forgiven_cents),
+        |a, b| a.fines_forgiven_cents.cmp(&b.fines_forgiven_cents),
+    );
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    let totals_label = Label::new(None);
+    totals_label.set_xalign(0.0);
+
+    let csv_button = Button::with_label(&tr!("Export CSV..."));
+    let pdf_button = Button::with_label(&tr!("Export PDF..."));
+    let print_button = Button::with_label(&tr!("Print..."));
+    let button_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    button_box.append(&csv_button);
+    button_box.append(&pdf_button);
+    button_box.append(&print_button);
+    let waivers_button = Button::with_label(&tr!("Waiver Report..."));
+    waivers_button.set_tooltip_text(Some(&tr!("Print every fine waived in the chosen range, with the reason and who approved it")));
+    button_box.append(&waivers_button);
+
+    // The chosen range and grouping
+    let selection = Rc::new(glib::clone!(
+        #[weak]
+        from_calendar,
+        #[weak]
+        to_calendar,
+        #[weak]
+        period_dropdown,
+        #[upgrade_or]
+        (NaiveDate::default(), NaiveDate::default(), Period::Day),
+        move || {
+            let period = Period::ALL[period_dropdown.selected() as usize];
+            (calendar_date(&from_calendar), calendar_date(&to_calendar), period)
+        }
+    ));
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        table,
+        #[strong]
+        selection,
+        #[weak]
+        totals_label,
+        move || {
+            let (from, to, period) = selection();
+            let summaries = library.read().unwrap().circulation_summary(from, to, period);
+            let total = reports::total(&summaries);
+            totals_label.set_text(&tr!(
+                "Total: {} checkouts, {} returns, {} renewals, {} new members, {} fines collected, {} waived, {} forgiven",
+                total.checkouts,
+                total.returns,
+                total.renewals,
+                total.new_members,
+                format_money(total.fines_collected_cents),
+                format_money(total.fines_waived_cents),
+                format_money(total.fines_forgiven_cents)
+            ));
+            table.set_rows(summaries);
+        }
+    ));
+    fill();
+
+    from_calendar.connect_day_selected(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    to_calendar.connect_day_selected(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    period_dropdown.connect_selected_notify(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    // End-of-day summary
+    today_button.connect_clicked(glib::clone!(
+        #[weak]
+        from_calendar,
+        #[weak]
+        to_calendar,
+        #[weak]
+        period_dropdown,
+        move |_| {
+            let now = glib::DateTime::now_local().unwrap();
+            from_calendar.select_day(&now);
+            to_calendar.select_day(&now);
+            period_dropdown.set_selected(0);
+        }
+    ));
+    // Catches the day's transactions when coming back to the page
+    reports_box.connect_map(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    bus.refill_when_shown(&reports_box, fill.clone());
+
+    csv_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        selection,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let (from, to, period) = selection();
+            dialogs::choose_save_path(&window, &tr!("Export Report"), "circulation.csv", glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path| {
+                    if let Err(e) = library.read().unwrap().export_circulation_csv(from, to, period, &path) {
+                        dialogs::show_error(&window, &tr!("Failed to export the report"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    // Sends the report to a PDF file, or to the print dialog without one
+    let print_report = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        selection,
+        move |window: &gtk::Window, pdf_path: Option<std::path::PathBuf>| {
+            let (from, to, period) = selection();
+            let summaries = library.read().unwrap().circulation_summary(from, to, period);
+            let title = tr!(
+                "{} circulation report, {} to {}",
+                period.label(),
+                from.format("%Y-%m-%d"),
+                to.format("%Y-%m-%d")
+            );
+            let result = print::print_table(window, &title, reports::report_headers(), reports::report_rows(&summaries), pdf_path);
+            if let Err(e) = result {
+                dialogs::show_error(window, &tr!("Failed to print the report"), &e);
+            }
+        }
+    ));
+    pdf_button.connect_clicked(glib::clone!(
+        #[strong]
+        print_report,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_save_path(&window, &tr!("Export Report"), "circulation.pdf", glib::clone!(
+                #[strong]
+                print_report,
+                #[weak]
+                window,
+                move |path| print_report(&window, Some(path))
+            ));
+        }
+    ));
+    print_button.connect_clicked(glib::clone!(
+        #[strong]
+        print_report,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            print_report(&window, None);
+        }
+    ));
+
+    waivers_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        #[strong]
+        selection,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let (from, to, _) = selection();
+            let rows = library.read().unwrap().waiver_report(from, to);
+            if rows.is_empty() {
+                status::info(button, &tr!("No fines were waived in this range"));
+                return;
+            }
+            let title = tr!("Fine waivers, {} to {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+            let result = print::print_table(&window, &title, fines::waiver_report_headers(), fines::waiver_report_rows(&rows), None);
+            if let Err(e) = result {
+                dialogs::show_error(&window, &tr!("Failed to print the report"), &e);
+            }
+        }
+    ));
+
+    reports_box.append(&controls);
+    reports_box.append(&scrolled_window);
+    reports_box.append(&totals_label);
+    reports_box.append(&button_box);
+
+    reports_box
+}
+
+// Entries shown on the logs page
+const LOG_TAIL: usize = 500;
+// How often the logs page rereads the log file while it is on screen
+const LOG_REFRESH_SECONDS: u32 = 2;
+
+fn create_logs_page() -> GtkBox {
+    let logs_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let levels = [
+        (tracing::Level::ERROR, tr!("Errors")),
+        (tracing::Level::WARN, tr!("Warnings")),
+        (tracing::Level::INFO, tr!("Info")),
+        (tracing::Level::DEBUG, tr!("Debug")),
+        (tracing::Level::TRACE, tr!("Trace")),
+    ];
+    let level_names: Vec<&str> = levels.iter().map(|(_, name)| name.as_str()).collect();
+    let level_dropdown = gtk::DropDown::from_strings(&level_names);
+    level_dropdown.set_selected(2);
+    let refresh_button = Button::with_label(&tr!("Refresh"));
+
+    let controls = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    controls.append(&Label::new(Some(&tr!("Show:"))));
+    controls.append(&level_dropdown);
+    controls.append(&refresh_button);
+
+    let table = Rc::new(Table::<logging::LogEntry>::new());
+    table.add_column(&tr!("Time"), |e| e.time.clone(), |a, b| a.time.cmp(&b.time));
+    table.add_column(&tr!("Level"), |e| e.level.to_string(), |a, b| a.level.cmp(&b.level));
+    table.add_column(&tr!("Source"), |e| e.target.clone(), |a, b| a.target.cmp(&b.target));
+    table.add_column(&tr!("Message"), |e| e.message.clone(), |a, b| a.message.cmp(&b.message)).set_expand(true);
+
+    // Shows the chosen level and everything more severe
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        table,
+        #[weak]
+        level_dropdown,
+        move || {
+            let level = levels[level_dropdown.selected() as usize].0;
+            table.set_rows(logging::recent(LOG_TAIL, level));
+        }
+    ));
+    fill();
+
+    level_dropdown.connect_selected_notify(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    refresh_button.connect_clicked(glib::clone!(
+        #[strong]
+        fill,
+        move |_| fill()
+    ));
+    glib::timeout_add_seconds_local(LOG_REFRESH_SECONDS, glib::clone!(
+        #[weak]
+        logs_box,
+        #[upgrade_or]
+        glib::ControlFlow::Break,
+        move || {
+            if logs_box.is_mapped() {
+                fill();
+            }
+            glib::ControlFlow::Continue
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_child(Some(&table.view));
+    scrolled_window.set_vexpand(true);
+
+    logs_box.append(&controls);
+    logs_box.append(&scrolled_window);
+
+    logs_box
+}
+
+// Read-only queries over a copy of the library, for reports the other
+// pages don't offer. The tables are listed above the query box.
+fn create_sql_page(library: Arc<RwLock<Library>>) -> GtkBox {
+    let sql_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+
+    let tables_label = Label::new(Some(&tr!(
+        "Tables: items, members, loans, fines, holds, transactions. Dates are UTC, e.g. WHERE due_date < '2026-01-01'."
+    )));
+    tables_label.set_xalign(0.0);
+    tables_label.set_wrap(true);
+    let query_view = gtk::TextView::new();
+    query_view.set_monospace(true);
+    query_view.buffer().set_text("SELECT format, COUNT(*) FROM items GROUP BY format ORDER BY 2 DESC");
+    let query_window = ScrolledWindow::new();
+    query_window.set_min_content_height(100);
+    query_window.set_child(Some(&query_view));
+    let run_button = Button::with_label(&tr!("Run Query"));
+    run_button.add_css_class("suggested-action");
+    let export_button = Button::with_label(&tr!("Export CSV..."));
+    let controls = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    controls.append(&run_button);
+    controls.append(&export_button);
+    let summary_label = Label::new(None);
+    summary_label.set_xalign(0.0);
+
+    let results_window = ScrolledWindow::new();
+    results_window.set_vexpand(true);
+    let last_result: Rc<RefCell<sql::QueryResult>> = Rc::new(RefCell::new(sql::QueryResult::default()));
+    let results_table: Rc<RefCell<Option<Table<Vec<String>>>>> = Rc::new(RefCell::new(None));
+
+    let run = Rc::new(glib::clone!(
+        #[strong]
+        library,
+        #[weak]
+        query_view,
+        #[weak]
+        results_window,
+        #[weak]
+        summary_label,
+        #[weak]
+        sql_box,
+        #[strong]
+        last_result,
+        #[strong]
+        results_table,
+        move || {
+            let buffer = query_view.buffer();
+            let query = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+            let result = library.read().unwrap().run_query(&query);
+            let result = match result {
+                Ok(result) => result,
+                Err(e) => {
+                    status::error(&sql_box, &e);
+                    return;
+                }
+            };
+            // Columns differ from query to query, so each gets a new table
+            let table = Table::<Vec<String>>::new();
+            for (i, column) in result.columns.iter().enumerate() {
+                table.add_column(column, move |row| row[i].clone(), move |a, b| a[i].cmp(&b[i]));
+            }
+            table.set_rows(result.rows.clone());
+            results_window.set_child(Some(&table.view));
+            summary_label.set_text(&if result.truncated {
+                tr!("Showing the first {} rows", sql::MAX_ROWS)
+            } else {
+                tr!("{} row(s)", result.rows.len())
+            });
+            *last_result.borrow_mut() = result;
+            *results_table.borrow_mut() = Some(table);
+        }
+    ));
+    run_button.connect_clicked(glib::clone!(
+        #[strong]
+        run,
+        move |_| run()
+    ));
+    // Ctrl+Enter runs the query without leaving the keyboard
+    let keys = gtk::EventControllerKey::new();
+    keys.connect_key_pressed(move |_, key, _, modifiers| {
+        if modifiers.contains(gtk::gdk::ModifierType::CONTROL_MASK) && matches!(key, gtk::gdk::Key::Return | gtk::gdk::Key::KP_Enter) {
+            run();
+            return glib::Propagation::Stop;
+        }
+        glib::Propagation::Proceed
+    });
+    query_view.add_controller(keys);
+
+    export_button.connect_clicked(glib::clone!(
+        #[strong]
+        last_result,
+        move |button| {
+            if last_result.borrow().columns.is_empty() {
+                return;
+            }
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let allow_plain = library.read().unwrap().allow_plain_pii_export;
+            dialogs::choose_personal_export_path(&window, &tr!("Export Query Results"), "query.csv", allow_plain, glib::clone!(
+                #[strong]
+                last_result,
+                #[weak]
+                window,
+                move |path, passphrase| {
+                    if let Err(e) = last_result.borrow().export_csv(&path, passphrase.as_deref()) {
+                        dialogs::show_error(&window, &tr!("Failed to export the results"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    sql_box.append(&tables_label);
+    sql_box.append(&query_window);
+    sql_box.append(&controls);
+    sql_box.append(&summary_label);
+    sql_box.append(&results_window);
+
+    sql_box
+}
+
+const CATALOG_PAGE_SIZE: usize = 100;
+
+// Only one page of search results is put into the model at a time, so large
+// catalogs don't have to be fully materialized in the list model.
+struct CatalogView {
+    library: Arc<RwLock<Library>>,
+    table: Table<LiItem>,
+    search_entry: Entry,
+    search_mode: gtk::DropDown,
+    // The wildcard or regex pattern last searched for, to highlight in the
+    // results
+    highlight: Rc<RefCell<Option<Pattern>>>,
+    suggestion_label: Label,
+    page_label: Label,
+    page: Cell<usize>,
+    facets: RefCell<FacetFilter>,
+    facet_box: GtkBox,
+}
+
+impl CatalogView {
+    // Runs the search for the current text and mode. Spelling suggestions,
+    // bad queries and patterns, and timeouts are shown under the search bar.
+    fn find(&self, library: &Library) -> Vec<u32> {
+        let text = self.search_entry.text();
+        let mode = SearchMode::ALL[self.search_mode.selected() as usize % SearchMode::ALL.len()];
+        *self.highlight.borrow_mut() = None;
+
+        let (ids, message) = if mode != SearchMode::Words && !text.trim().is_empty() {
+            match Pattern::new(mode, &text) {
+                Ok(pattern) => {
+                    let result = pattern::search(library.items.values(), &pattern, pattern::MATCH_TIMEOUT);
+                    *self.highlight.borrow_mut() = Some(pattern);
+                    let message = result.timed_out.then(|| tr!("The pattern took too long; showing the first {} matches", result.ids.len()));
+                    (result.ids, message)
+                }
+                Err(e) => (Vec::new(), Some(e)),
+            }
+        } else if query::is_query(&text) {
+            // Plain words go to the fuzzy title search, which can suggest a
+            // spelling; anything using the query language is parsed instead
+            match Query::parse(&text) {
+                Ok(query) => (query::run(library.items.values(), &query), None),
+                Err(e) => (Vec::new(), Some(e)),
+            }
+        } else {
+            let result = search::search(library.items.values(), &text);
+            (result.ids, result.suggestion.map(|title| tr!("Did you mean \"{}\"?", title)))
+        };
+
+        match message {
+            Some(message) => {
+                self.suggestion_label.set_text(&message);
+                self.suggestion_label.set_visible(true);
+            }
+            None => self.suggestion_label.set_visible(false),
+        }
+        ids
+    }
+
+    fn refresh(self: &Rc<Self>) {
+        let library = self.library.read().unwrap();
+        let ids = self.find(&library);
+
+        let found: Vec<&LiItem> = ids.iter().filter_map(|id| library.items.get(id)).collect();
+        self.rebuild_facets(&found);
+        let facets = self.facets.borrow();
+        let visible: Vec<&LiItem> = found.into_iter().filter(|item| facets.matches(item)).collect();
+
+        let pages = visible.len().div_ceil(CATALOG_PAGE_SIZE).max(1);
+        let page = self.page.get().min(pages - 1);
+        self.page.set(page);
+        self.page_label.set_text(&tr!("Page {} of {} ({} items)", page + 1, pages, visible.len()));
+
+        self.table.set_rows(
+            visible.iter()
+                .skip(page * CATALOG_PAGE_SIZE)
+                .take(CATALOG_PAGE_SIZE)
+                .map(|item| (*item).clone())
+        );
+    }
+
+    // Follows changes made on other pages or at other desks. Changed items
+    // on this page are redrawn in place; items added or removed refill it.
+    fn apply(self: &Rc<Self>, changes: &[LibraryChange]) {
+        if changes.iter().any(|c| c.changes_item_list()) {
+            self.refresh();
+            return;
+        }
+        let library = self.library.read().unwrap();
+        let items: HashMap<u32, LiItem> = changes
+            .iter()
+            .filter_map(|c| c.item_id())
+            .filter_map(|id| library.items.get(&id))
+            .map(|item| (item.id, item.clone()))
+            .collect();
+        drop(library);
+        if !items.is_empty() {
+            self.table.update_rows(|row| items.get(&row.id).cloned());
+        }
+    }
+
+    fn rebuild_facets(self: &Rc<Self>, items: &[&LiItem]) {
+        while let Some(child) = self.facet_box.first_child() {
+            self.facet_box.remove(&child);
+        }
+
+        let facets = self.facets.borrow();
+        for kind in FacetKind::ALL {
+            let heading = Label::new(Some(kind.name()));
+            heading.set_xalign(0.0);
+            heading.add_css_class("heading");
+            self.facet_box.append(&heading);
+
+            let selected = facets.selected(kind);
+            for (value, count) in facets.counts(items, kind) {
+                let button = gtk::ToggleButton::with_label(&format!("{} ({})", value, count));
+                button.set_active(selected == Some(value.as_str()));
+                button.add_css_class("flat");
+                let view = Rc::downgrade(self);
+                button.connect_clicked(move |_| {
+                    let Some(view) = view.upgrade() else { return };
+                    view.facets.borrow_mut().toggle(kind, &value);
+                    view.page.set(0);
+                    view.refresh();
+                });
+                self.facet_box.append(&button);
+            }
+        }
+    }
+}
+
+fn create_catalog_page(library: Arc<RwLock<Library>>, bus: &EventBus, role: Option<Role>) -> GtkBox {
+    let can_edit = role >= Some(Role::Librarian);
+    let catalog_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    
+    let table = Table::<LiItem>::new();
+    let column_view = table.view.clone();
+
+    let add_button = Button::with_label(&tr!("Add Item..."));
+    add_button.set_visible(can_edit);
+    let goodreads_button = Button::with_label(&tr!("Import Goodreads CSV..."));
+    goodreads_button.set_visible(can_edit);
+    let import_button = Button::with_label(&tr!("Import CSV..."));
+    import_button.set_visible(can_edit);
+    let tags_button = Button::with_label(&tr!("Edit Tags..."));
+    tags_button.set_visible(can_edit);
+    let reviews_button = Button::with_label(&tr!("Reviews..."));
+    reviews_button.set_visible(can_edit);
+    let undo_button = Button::with_label(&tr!("Undo Edit"));
+    undo_button.set_visible(can_edit);
+    let duplicates_button = Button::with_label(&tr!("Find Duplicates..."));
+    duplicates_button.set_visible(can_edit);
+    let shelf_list_button = Button::with_label(&tr!("Export Shelf List..."));
+    shelf_list_button.set_visible(role.is_some());
+    let weeding_button = Button::with_label(&tr!("Weeding Report..."));
+    weeding_button.set_visible(can_edit);
+    let maintenance_button = Button::with_label(&tr!("Maintenance Report..."));
+    maintenance_button.set_tooltip_text(Some(&tr!("Print the items with worn or damaged copies")));
+    maintenance_button.set_visible(can_edit);
+    let website_button = Button::with_label(&tr!("Export Website..."));
+    website_button.set_visible(can_edit);
+    let feed_button = Button::with_label(&tr!("Export New Arrivals Feed..."));
+    feed_button.set_visible(can_edit);
+
+    let search_entry = Entry::new();
+    search_entry.set_placeholder_text(Some(&tr!("Search titles, or e.g. author:tolkien AND year:1950..1960")));
+    search_entry.set_tooltip_text(Some(&tr!(
+        "Plain words search titles. Narrow by field with field:word, field=exact, ranges like year:1950..1960 and comparisons like avail>0; combine with AND, OR, NOT and parentheses.\nFields: {}",
+        query::field_names()
+    )));
+    let mode_labels: Vec<String> = SearchMode::ALL.iter().map(|m| m.label()).collect();
+    let search_mode = gtk::DropDown::from_strings(&mode_labels.iter().map(String::as_str).collect::<Vec<_>>());
+    search_mode.set_tooltip_text(Some(&tr!("Wildcard and Regex match titles, authors and descriptions")));
+    let suggestion_label = Label::new(None);
+    suggestion_label.set_visible(false);
+
+    let prev_button = Button::with_label(&tr!("< Previous"));
+    let next_button = Button::with_label(&tr!("Next >"));
+    let page_label = Label::new(None);
+    page_label.set_hexpand(true);
+
+    let facet_box = GtkBox::new(gtk::Orientation::Vertical, 2);
+
+    let view = Rc::new(CatalogView {
+        library: library.clone(),
+        table,
+        search_entry: search_entry.clone(),
+        search_mode: search_mode.clone(),
+        highlight: Rc::new(RefCell::new(None)),
+        suggestion_label: suggestion_label.clone(),
+        page_label: page_label.clone(),
+        page: Cell::new(0),
+        facets: RefCell::new(FacetFilter::default()),
+        facet_box: facet_box.clone(),
+    });
+
+    // Editing a cell writes straight to the library. The table belongs to
+    // the view, so the handlers only hold on to it weakly.
+    let edit_field = |field: ItemField| {
+        let weak_view = Rc::downgrade(&view);
+        move |item: &LiItem, text: &str| -> Result<(), String> {
+            let Some(view) = weak_view.upgrade() else { return Err(tr!("Catalog closed")) };
+            let result = view.library.write().unwrap().set_item_field(item.id, field, text);
+            match &result {
+                // Not while GTK is still finishing the edit
+                Ok(()) => {
+                    glib::idle_add_local_once(move || view.refresh());
+                }
+                Err(e) => {
+                    if let Some(window) = view.table.view.root().and_downcast::<gtk::Window>() {
+                        dialogs::show_error(&window, &tr!("Invalid value"), e);
+                    }
+                }
+            }
+            result
+        }
+    };
+    let add_column = |title: &str, field: Option<ItemField>, text: fn(&LiItem) -> String, cmp: fn(&LiItem, &LiItem) -> std::cmp::Ordering| {
+        match field.filter(|_| can_edit) {
+            Some(field) => view.table.add_editable_column(title, text, cmp, edit_field(field)),
+            None => view.table.add_column(title, text, cmp),
+        };
+    };
+    add_column(&tr!("Item ID"), None, |i| i.id.to_string(), |a, b| a.id.cmp(&b.id));
+    // Title and author show where a wildcard or regex search matched
+    let highlighted = |text: fn(&LiItem) -> String| {
+        let highlight = view.highlight.clone();
+        move |item: &LiItem| match highlight.borrow().as_ref() {
+            Some(pattern) => pattern.highlight(&text(item)),
+            None => glib::markup_escape_text(&text(item)).to_string(),
+        }
+    };
+    let title_text: fn(&LiItem) -> String = |i| i.title.to_string();
+    let author_text: fn(&LiItem) -> String = |i| i.author.clone().unwrap_or_else(|| tr!("Unknown"));
+    view.table.add_markup_column(&tr!("Title"), title_text, highlighted(title_text), |a, b| a.title.cmp(&b.title));
+    view.table.add_markup_column(&tr!("Author"), author_text, highlighted(author_text), |a, b| a.author.cmp(&b.author));
+    add_column(&tr!("Year"), Some(ItemField::Year), |i| i.year.to_string(), |a, b| a.year.cmp(&b.year));
+    add_column(&tr!("Format"), Some(ItemField::Format), |i| i.format.clone(), |a, b| a.format.cmp(&b.format));
+    add_column(&tr!("Tags"), None, |i| i.tags.join(", "), |a, b| a.tags.cmp(&b.tags));
+    add_column(&tr!("Branch"), None, |i| i.branch.clone(), |a, b| a.branch.cmp(&b.branch));
+    add_column(&tr!("Call Number"), Some(ItemField::CallNumber), |i| i.call_number.clone(), |a, b| callnumber::compare(&a.call_number, &b.call_number));
+    add_column(&tr!("Shelf Location"), Some(ItemField::ShelfLocation), |i| i.shelf_location.clone(), |a, b| a.shelf_location.cmp(&b.shelf_location));
+    add_column(&tr!("Part Of"), None, |i| i.link_text(), |a, b| a.link.cmp(&b.link));
+    add_column(&tr!("Total Copies"), Some(ItemField::Copies), |i| i.copies.to_string(), |a, b| a.copies.cmp(&b.copies));
+    add_column(&tr!("Available Copies"), None, |i| i.avail_copies.to_string(), |a, b| a.avail_copies.cmp(&b.avail_copies));
+    add_column(&tr!("Status"), None, |i| i.status_text(), |a, b| a.status().cmp(&b.status()));
+    add_column(&tr!("Rating"), None, |i| i.rating_text(), |a, b| a.average_stars().unwrap_or(0.0).total_cmp(&b.average_stars().unwrap_or(0.0)));
+
+    // Populate catalog on startup
+    view.refresh();
+
+    add_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            // Read now so a server changed in Settings is used straight away
+            let sru_url = Config::load().sru_url;
+            dialogs::show_add_item(&window, sru_url, glib::clone!(
+                #[strong]
+                view,
+                #[weak]
+                window,
+                #[upgrade_or]
+                Ok(()),
+                move |new| {
+                    let id = view.library.write().unwrap().add_item(new)?;
+                    view.refresh();
+                    status::success(&window, &tr!("Added item {}", id));
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    import_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_open_path(&window, &tr!("Import Catalog CSV"), glib::clone!(
+                #[strong]
+                view,
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path| {
+                    dialogs::show_import_wizard(&window, library.clone(), path, ImportKind::Items, glib::clone!(
+                        #[strong]
+                        view,
+                        move || view.refresh()
+                    ));
+                }
+            ));
+        }
+    ));
+
+    goodreads_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_open_path(&window, &tr!("Import Goodreads Library"), glib::clone!(
+                #[strong]
+                view,
+                #[weak]
+                window,
+                move |path| {
+                    let result = view.library.write().unwrap().import_goodreads_csv(&path);
+                    match result {
+                        Ok(report) => {
+                            view.refresh();
+                            dialogs::show_import_report(&window, report);
+                        }
+                        Err(e) => dialogs::show_error(&window, &tr!("Failed to import the Goodreads library"), &e.to_string()),
+                    }
+                }
+            ));
+        }
+    ));
+
+    tags_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let Some((id, title, tags)) = view.table.selected(|i| (i.id, i.title.clone(), i.tags.join(", "))) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_edit_tags(&window, &title, &tags, glib::clone!(
+                #[strong]
+                view,
+                move |tags| {
+                    view.library.write().unwrap().set_item_tags(id, tags)?;
+                    view.refresh();
+                    Ok(())
+                }
+            ));
+        }
+    ));
+
+    duplicates_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_find_duplicates(&window, view.library.clone(), glib::clone!(
+                #[strong]
+                view,
+                move || view.refresh()
+            ));
+        }
+    ));
+
+    shelf_list_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_save_path(&window, &tr!("Export Shelf List"), "shelf-list.csv", glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path| {
+                    if let Err(e) = library.read().unwrap().export_shelf_list_csv(&path) {
+                        dialogs::show_error(&window, &tr!("Failed to export the shelf list"), &e.to_string());
+                    }
+                }
+            ));
+        }
+    ));
+
+    website_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_folder(&window, &tr!("Export Catalog Website"), glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |dir| {
+                    match library.read().unwrap().export_opac_html(&dir) {
+                        Ok(count) => status::success(&window, &tr!("Exported {} items to {}", count, dir.display())),
+                        Err(e) => dialogs::show_error(&window, &tr!("Failed to export the catalog website"), &e.to_string()),
+                    }
+                }
+            ));
+        }
+    ));
+
+    feed_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::choose_save_path(&window, &tr!("Export New Arrivals Feed"), "new-arrivals.xml", glib::clone!(
+                #[strong]
+                library,
+                #[weak]
+                window,
+                move |path| {
+                    let website_url = Config::load().website_url;
+                    match library.read().unwrap().export_new_arrivals_rss(&path, &website_url) {
+                        Ok(count) => status::success(&window, &tr!("Exported {} new arrivals", count)),
+                        Err(e) => dialogs::show_error(&window, &tr!("Failed to export the feed"), &e.to_string()),
+                    }
+                }
+            ));
+        }
+    ));
+
+    weeding_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_weeding_report(&window, library.clone());
+        }
+    ));
+
+    maintenance_button.connect_clicked(glib::clone!(
+        #[strong]
+        library,
+        move |button| {
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            let rows = library.read().unwrap().maintenance_report();
+            if rows.is_empty() {
+                status::info(button, &tr!("No copies are worn or damaged"));
+                return;
+            }
+            let title = tr!("Maintenance report, {}", chrono::Local::now().format("%Y-%m-%d"));
+            let result = print::print_table(&window, &title, condition::maintenance_report_headers(), condition::maintenance_report_rows(&rows), None);
+            if let Err(e) = result {
+                dialogs::show_error(&window, &tr!("Failed to print the report"), &e);
+            }
+        }
+    ));
+
+    // The table belongs to the view, so the handler must not keep it alive
+    let weak_view = Rc::downgrade(&view);
+    undo_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let result = view.library.write().unwrap().undo_edit();
+            match result {
+                Ok(_) => view.refresh(),
+                Err(e) => {
+                    let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+                    dialogs::show_error(&window, &tr!("Cannot undo"), &e);
+                }
+            }
+        }
+    ));
+
+    view.table.connect_row_activated(glib::clone!(
+        #[weak]
+        column_view,
+        move |item| {
+            let Some(view) = weak_view.upgrade() else { return };
+            let Some(window) = column_view.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_item_details(&window, view.library.clone(), item.id, role, move || view.refresh());
+        }
+    ));
+
+    // Actions for the rows' context menu. Like the cell edits they hold on
+    // to the view weakly, as the action group is owned by the table.
+    let weak_view = Rc::downgrade(&view);
+    let actions = gio::SimpleActionGroup::new();
+    let menu = gio::Menu::new();
+    if role.is_some() {
+        add_row_action(&actions, "issue", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some((id, title)) = view.table.selected(|i| (i.id, i.title.clone())) else { return };
+                dialogs::ask_text(window, &tr!("Issue \"{}\"", title), &tr!("Member ID:"), glib::clone!(
+                    #[strong]
+                    weak_view,
+                    move |member| {
+                        let Some(view) = weak_view.upgrade() else { return Ok(()) };
+                        view.library.write().unwrap().book_issue(id, member)?;
+                        view.refresh();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+        menu.append(Some(&tr!("Issue to Member...")), Some("row.issue"));
+    }
+    add_row_action(&actions, "details", &column_view, glib::clone!(
+        #[strong]
+        weak_view,
+        move |window| {
+            let Some(view) = weak_view.upgrade() else { return };
+            let Some(id) = view.table.selected(|i| i.id) else { return };
+            let weak_view = Rc::downgrade(&view);
+            dialogs::show_item_details(window, view.library.clone(), id, role, move || {
+                if let Some(view) = weak_view.upgrade() {
+                    view.refresh();
+                }
+            });
+        }
+    ));
+    menu.append(Some(&tr!("Details")), Some("row.details"));
+    if can_edit {
+        add_row_action(&actions, "edit", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some((id, initial, version)) = view.table.selected(|item| (item.id, ItemDetails::of(item), item.version)) else { return };
+                dialogs::show_edit_item(window, initial, version, glib::clone!(
+                    #[strong]
+                    weak_view,
+                    move |details, version| {
+                        let Some(view) = weak_view.upgrade() else { return Ok(()) };
+                        view.library.write().unwrap().edit_item(id, version, details)?;
+                        view.refresh();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+        add_row_action(&actions, "delete", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some((id, title)) = view.table.selected(|i| (i.id, i.title.clone())) else { return };
+                dialogs::confirm(window, &tr!("Delete \"{}\"?", title), &tr!("The item is moved to the trash and can be restored from there."), glib::clone!(
+                    #[strong]
+                    weak_view,
+                    #[weak]
+                    window,
+                    move || {
+                        let Some(view) = weak_view.upgrade() else { return };
+                        let result = view.library.write().unwrap().delete_item(id);
+                        match result {
+                            Ok(_) => view.refresh(),
+                            Err(e) => dialogs::show_error(&window, &tr!("Cannot delete item"), &e),
+                        }
+                    }
+                ));
+            }
+        ));
+        add_row_action(&actions, "batch-edit", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let ids = view.table.selected_rows(|i| i.id);
+                if ids.is_empty() {
+                    return;
+                }
+                dialogs::show_batch_edit(window, ids.len(), glib::clone!(
+                    #[strong]
+                    weak_view,
+                    #[weak]
+                    window,
+                    move |change| {
+                        let detail = tr!("Items the change can't be made to are left as they are. Undo Edit reverts the whole change.");
+                        dialogs::confirm(&window, &change.confirm_text(ids.len()), &detail, glib::clone!(
+                            #[strong]
+                            weak_view,
+                            #[strong]
+                            ids,
+                            #[weak]
+                            window,
+                            move || {
+                                let Some(view) = weak_view.upgrade() else { return };
+                                let report = view.library.write().unwrap().batch_edit(&ids, &change);
+                                view.refresh();
+                                let failures: Vec<String> = report.failed.iter().map(|(id, e)| tr!("Item {}: {}", id, e)).collect();
+                                let detail = if failures.is_empty() { tr!("All items were changed.") } else { failures.join("\n") };
+                                dialogs::show_info(&window, &tr!("Changed {} of {} items", report.changed.len(), ids.len()), &detail);
+                            }
+                        ));
+                    }
+                ));
+            }
+        ));
+        add_row_action(&actions, "add-to-set", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some(id) = view.table.selected(|i| i.id) else { return };
+                let sets = view.library.read().unwrap().volume_sets.clone();
+                dialogs::show_add_to_set(window, sets, glib::clone!(
+                    #[strong]
+                    weak_view,
+                    move |set_id, title, number| {
+                        let Some(view) = weak_view.upgrade() else { return Ok(()) };
+                        let mut lib = view.library.write().unwrap();
+                        let created = set_id.is_none();
+                        let set_id = match set_id {
+                            Some(set_id) => set_id,
+                            None => lib.create_volume_set(&title)?,
+                        };
+                        if let Err(e) = lib.add_to_set(id, set_id, number) {
+                            // No empty set is left behind
+                            if created {
+                                lib.volume_sets.retain(|s| s.id != set_id);
+                            }
+                            return Err(e);
+                        }
+                        drop(lib);
+                        view.refresh();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+        add_row_action(&actions, "bind-with", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some((id, title)) = view.table.selected(|i| (i.id, i.title.clone())) else { return };
+                dialogs::ask_text(window, &tr!("Bind \"{}\" With", title), &tr!("ID of the item it is bound into:"), glib::clone!(
+                    #[strong]
+                    weak_view,
+                    move |text| {
+                        let Some(view) = weak_view.upgrade() else { return Ok(()) };
+                        let host_id = text.trim().parse::<u32>().map_err(|_| tr!("Invalid Item ID"))?;
+                        view.library.write().unwrap().bind_with(id, host_id)?;
+                        view.refresh();
+                        Ok(())
+                    }
+                ));
+            }
+        ));
+        add_row_action(&actions, "unlink", &column_view, glib::clone!(
+            #[strong]
+            weak_view,
+            move |window| {
+                let Some(view) = weak_view.upgrade() else { return };
+                let Some(id) = view.table.selected(|i| i.id) else { return };
+                let result = view.library.write().unwrap().unlink_item(id);
+                match result {
+                    Ok(()) => view.refresh(),
+                    Err(e) => dialogs::show_error(window, &tr!("Cannot unlink item"), &e),
+                }
+            }
+        ));
+        menu.append(Some(&tr!("Edit...")), Some("row.edit"));
+        menu.append(Some(&tr!("Batch Edit...")), Some("row.batch-edit"));
+        menu.append(Some(&tr!("Add to Volume Set...")), Some("row.add-to-set"));
+        menu.append(Some(&tr!("Bind With...")), Some("row.bind-with"));
+        menu.append(Some(&tr!("Remove from Set or Binding")), Some("row.unlink"));
+        menu.append(Some(&tr!("Delete...")), Some("row.delete"));
+    }
+    add_row_action(&actions, "copy-id", &column_view, glib::clone!(
+        #[strong]
+        weak_view,
+        move |_| {
+            let Some(view) = weak_view.upgrade() else { return };
+            let Some(id) = view.table.selected(|i| i.id) else { return };
+            view.table.view.clipboard().set_text(&id.to_string());
+        }
+    ));
+    menu.append(Some(&tr!("Copy ID")), Some("row.copy-id"));
+    add_selection_actions(&view, |v| &v.table, &actions, &menu, "catalog.csv", None);
+    view.table.set_context_menu(&menu, &actions);
+
+    reviews_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |button| {
+            let Some(id) = view.table.selected(|i| i.id) else { return };
+            let Some(window) = button.root().and_downcast::<gtk::Window>() else { return };
+            dialogs::show_reviews(&window, view.library.clone(), id, glib::clone!(
+                #[strong]
+                view,
+                move || view.refresh()
+            ));
+        }
+    ));
+
+    search_entry.connect_changed(glib::clone!(
+        #[strong]
+        view,
+        move |_| {
+            view.page.set(0);
+            view.refresh();
+        }
+    ));
+
+    search_mode.connect_selected_notify(glib::clone!(
+        #[strong]
+        view,
+        move |_| {
+            view.page.set(0);
+            view.refresh();
+        }
+    ));
+
+    let weak_view = Rc::downgrade(&view);
+    bus.subscribe(&catalog_box, move |changes| {
+        if let Some(view) = weak_view.upgrade() {
+            view.apply(changes);
+        }
+    });
+
+    prev_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |_| {
+            view.page.set(view.page.get().saturating_sub(1));
+            view.refresh();
+        }
+    ));
+
+    next_button.connect_clicked(glib::clone!(
+        #[strong]
+        view,
+        move |_| {
+            view.page.set(view.page.get() + 1);
+            view.refresh();
+        }
+    ));
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+    scrolled_window.set_child(Some(&column_view));
+    scrolled_window.set_vexpand(true);
+    scrolled_window.set_hexpand(true);
+
+    let facet_window = ScrolledWindow::new();
+    facet_window.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    facet_window.set_child(Some(&facet_box));
+    facet_window.set_size_request(180, -1);
+
+    let browse_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    browse_box.append(&facet_window);
+    browse_box.append(&scrolled_window);
+
+    let pager_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    pager_box.append(&prev_button);
+    pager_box.append(&page_label);
+    pager_box.append(&next_button);
+
+    let button_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    button_box.append(&add_button);
+    button_box.append(&import_button);
+    button_box.append(&goodreads_button);
+    button_box.append(&tags_button);
+    button_box.append(&reviews_button);
+    button_box.append(&undo_button);
+    button_box.append(&duplicates_button);
+    button_box.append(&shelf_list_button);
+    button_box.append(&weeding_button);
+    button_box.append(&maintenance_button);
+    button_box.append(&website_button);
+    button_box.append(&feed_button);
+
+    search_entry.set_hexpand(true);
+    let search_box = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    search_box.append(&search_entry);
+    search_box.append(&search_mode);
+
+    catalog_box.append(&search_box);
+    catalog_box.append(&button_box);
+    catalog_box.append(&suggestion_label);
+    catalog_box.append(&browse_box);
+    catalog_box.append(&pager_box);
+
+    catalog_box
 }
 
 fn main() {
-    let app = create_library_gui();
-    app.run();
+    // GTK would reject the unknown options, so they are taken out of the
+    // arguments before they are handed over
+    let station = Station::from_args();
+    let args: Vec<String> = std::env::args().filter(|arg| arg != "--kiosk" && arg != "--self-checkout").collect();
+
+    let _log_guard = logging::init();
+    i18n::init(&Config::load().language);
+    if let Some(code) = cli::run(&args) {
+        // exit skips destructors, so the log is flushed first
+        drop(_log_guard);
+        std::process::exit(code);
+    }
+    let app = create_library_gui(station);
+    app.run_with_args(&args);
 }
\ No newline at end of file
@@ -0,0 +1,110 @@
+use std::fs;
+use std::path::Path;
+
+use crate::editing::{ItemDetails, NewItem};
+use crate::i18n::tr;
+use crate::import::ImportReport;
+use crate::sru::{self, MarcRecord};
+use crate::Library;
+
+// ISO 2709 separators
+const RECORD_END: u8 = 0x1d;
+const FIELD_END: u8 = 0x1e;
+const SUBFIELD_START: u8 = 0x1f;
+
+/// Reads the bibliographic records in a MARC file, either MARCXML or
+/// binary MARC 21 (ISO 2709, usually `.mrc`).
+pub fn read_file(path: &Path) -> Result<Vec<MarcRecord>, Box<dyn std::error::Error + Send + Sync>> {
+    let bytes = fs::read(path)?;
+    let text = if bytes.trim_ascii_start().starts_with(b"<") {
+        String::from_utf8(bytes)?
+    } else {
+        binary_to_xml(&bytes)?
+    };
+    let records = sru::parse_marcxml(&text)?;
+    if records.is_empty() {
+        return Err(tr!("The file has no MARC records").into());
+    }
+    Ok(records)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+// Rewrites binary records as MARCXML, so both kinds of file share one
+// field mapping
+fn binary_to_xml(bytes: &[u8]) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+    let mut xml = format!("<collection xmlns=\"{}\">", sru::MARC_NS);
+    for (n, record) in bytes.split(|&b| b == RECORD_END).enumerate() {
+        let record = record.trim_ascii();
+        if record.is_empty() {
+            continue;
+        }
+        let bad = || tr!("MARC record {} is damaged", n + 1);
+        // A 24-byte leader gives where the data starts; the directory
+        // between them has 12 bytes per field: tag, length and offset
+        let base: usize = record.get(12..17).and_then(|b| std::str::from_utf8(b).ok()).and_then(|b| b.parse().ok()).ok_or_else(bad)?;
+        let directory = record.get(24..base.saturating_sub(1)).ok_or_else(bad)?;
+        xml.push_str("<record>");
+        for entry in directory.chunks_exact(12) {
+            let number = |range: std::ops::Range<usize>| -> Option<usize> { std::str::from_utf8(&entry[range]).ok()?.parse().ok() };
+            let tag = String::from_utf8_lossy(&entry[0..3]).into_owned();
+            let (Some(length), Some(start)) = (number(3..7), number(7..12)) else { return Err(bad().into()) };
+            let data = record.get(base + start..base + start + length).ok_or_else(bad)?;
+            let data = String::from_utf8_lossy(data.strip_suffix(&[FIELD_END]).unwrap_or(data)).into_owned();
+            // Control fields (001 to 009) have no subfields and nothing
+            // the catalog keeps
+            if tag.as_str() < "010" {
+                continue;
+            }
+            xml.push_str(&format!("<datafield tag=\"{}\">", escape(&tag)));
+            for subfield in data.split(SUBFIELD_START as char).skip(1) {
+                let mut chars = subfield.chars();
+                let Some(code) = chars.next() else { continue };
+                xml.push_str(&format!("<subfield code=\"{}\">{}</subfield>", escape(&code.to_string()), escape(chars.as_str())));
+            }
+            xml.push_str("</datafield>");
+        }
+        xml.push_str("</record>");
+    }
+    xml.push_str("</collection>");
+    Ok(xml)
+}
+
+impl Library {
+    /// Adds a catalog item with `copies` copies for each record. Records
+    /// with the ISBN of an item already in the catalog are skipped.
+    pub fn import_marc(&mut self, records: &[MarcRecord], copies: u32) -> ImportReport {
+        let mut report = ImportReport::new(&csv::ByteRecord::new());
+        for (n, record) in records.iter().enumerate() {
+            let line = n as u64 + 1;
+            if let Some(isbn) = &record.isbn {
+                if let Some(item) = self.items.values().find(|i| i.isbn.as_ref() == Some(isbn)) {
+                    report.add_problem(line, tr!("ISBN {} is already in the catalog as item {}", isbn, item.id), None);
+                    continue;
+                }
+            }
+            let new = NewItem {
+                details: ItemDetails {
+                    title: record.title.clone(),
+                    author: record.author.clone(),
+                    year: record.year,
+                    edition: record.edition.clone(),
+                    format: "Book".to_string(),
+                    desc: record.desc.clone(),
+                },
+                isbn: record.isbn.clone(),
+                call_number: record.call_number.clone(),
+                tags: record.tags.clone(),
+                copies,
+            };
+            match self.add_item(new) {
+                Ok(_) => report.loaded += 1,
+                Err(e) => report.add_problem(line, e, None),
+            }
+        }
+        tracing::info!(loaded = report.loaded, skipped = report.skipped, "MARC records imported");
+        report
+    }
+}
@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::Path;
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use serde::Deserialize;
+
+use crate::i18n::tr;
+use crate::import::{CsvSchema, ImportKind, ImportReport};
+use crate::memberships::MembershipType;
+use crate::pii;
+use crate::{Library, Member};
+
+// A member row after remapping to `MEMBER_FIELDS` order.
+#[derive(Deserialize)]
+struct MemberRow {
+    name: String,
+    email: String,
+    external_id: String,
+    membership: String,
+    expires_at: String,
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+impl Library {
+    // An existing member with the same email (ignoring case) or external ID.
+    fn find_duplicate(&self, email: Option<&str>, external_id: Option<&str>) -> Option<(u32, String)> {
+        self.members.values().find_map(|m| {
+            if email.is_some_and(|e| m.email.as_deref().is_some_and(|me| me.eq_ignore_ascii_case(e))) {
+                Some((m.id, tr!("email")))
+            } else if external_id.is_some() && m.external_id.as_deref() == external_id {
+                Some((m.id, tr!("external ID")))
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Adds a member for every row of a CSV file. Rows matching an existing
+    /// member (or an earlier row) by email or external ID are skipped and
+    /// listed in the report.
+    pub fn import_members_csv(&mut self, csv_path: &str, schema: &CsvSchema) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        self.only_at_server()?;
+        let mut rdr = schema.reader(File::open(csv_path)?);
+        let mut report = ImportReport::new(rdr.byte_headers()?);
+
+        let mut schema = schema.clone();
+        if schema.columns.is_empty() {
+            schema.columns = CsvSchema::detect(ImportKind::Members, &report.headers);
+        }
+        let headers = CsvSchema::field_headers(ImportKind::Members);
+        let now = self.clock.now();
+
+        for record in rdr.byte_records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map_or(0, |p| p.line());
+                    report.add_error(line, &e, None);
+                    continue;
+                }
+            };
+            let line = record.position().map_or(0, |p| p.line());
+            let row: MemberRow = match schema.remap(ImportKind::Members, &record).deserialize(Some(&headers)) {
+                Ok(row) => row,
+                Err(e) => {
+                    report.add_error(line, &e, Some(&record));
+                    continue;
+                }
+            };
+
+            let Some(name) = non_empty(&row.name) else {
+                report.add_problem(line, tr!("Missing member name"), Some(&record));
+                continue;
+            };
+            let email = non_empty(&row.email);
+            let external_id = non_empty(&row.external_id);
+            if let Some((id, field)) = self.find_duplicate(email.as_deref(), external_id.as_deref()) {
+                report.add_problem(line, tr!("Duplicate of member {} (same {})", id, field), Some(&record));
+                continue;
+            }
+
+            let membership = match non_empty(&row.membership) {
+                None => MembershipType::default(),
+                Some(text) => match MembershipType::ALL.iter().find(|t| t.name().eq_ignore_ascii_case(&text)) {
+                    Some(&kind) => kind,
+                    None => {
+                        report.add_problem(line, tr!("Unknown membership type \"{}\"", text), Some(&record));
+                        continue;
+                    }
+                },
+            };
+            let expires_at = match non_empty(&row.expires_at) {
+                None => self.new_membership_expiry(),
+                Some(text) => match NaiveDate::parse_from_str(&text, "%Y-%m-%d") {
+                    Ok(date) => Some(Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())),
+                    Err(_) => {
+                        report.add_problem(line, tr!("\"{}\" is not a YYYY-MM-DD date", text), Some(&record));
+                        continue;
+                    }
+                },
+            };
+
+            let id = self.next_member_id();
+            self.members.insert(id, Member {
+                id,
+                name,
+                items: HashMap::new(),
+                fines: Vec::new(),
+                membership,
+                expires_at,
+                email,
+                external_id,
+                anonymized_at: None,
+                keep_history: false,
+                history: Vec::new(),
+                joined_at: Some(now),
+                challenge: None,
+                receipts: Vec::new(),
+                version: 0,
+                digest_opt_out: false,
+            });
+            report.loaded += 1;
+        }
+
+        tracing::info!(path = csv_path, loaded = report.loaded, skipped = report.skipped, "Members imported");
+        if report.loaded > 0 {
+            self.dirty = true;
+            self.reloaded();
+        }
+        Ok(report)
+    }
+
+    /// Writes every member to `path` in the column layout the member import
+    /// detects, encrypted with `passphrase` if given.
+    pub fn export_members_csv(&self, path: &Path, passphrase: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record(["id", "name", "email", "external_id", "membership", "expires_at"])?;
+
+        let mut members: Vec<&Member> = self.members.values().collect();
+        members.sort_by_key(|m| m.id);
+        for m in members {
+            wtr.write_record([
+                m.id.to_string(),
+                m.name.clone(),
+                m.email.clone().unwrap_or_default(),
+                m.external_id.clone().unwrap_or_default(),
+                m.membership.name().to_string(),
+                m.expires_at.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+            ])?;
+        }
+        pii::write_export(path, &wtr.into_inner().map_err(|e| e.into_error())?, passphrase)
+    }
+}
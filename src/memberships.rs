@@ -0,0 +1,146 @@
+use chrono::{DateTime, Duration, Months, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::fines::FINE_PER_DAY_CENTS;
+use crate::i18n::tr;
+use crate::sync::LibraryCommand;
+use crate::{Library, LiItem, Member};
+
+pub const MEMBERSHIP_MONTHS: u32 = 12;
+// How far ahead the dashboard warns about expiring memberships
+pub const EXPIRY_WARNING_DAYS: i64 = 30;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
+pub enum MembershipType {
+    #[default]
+    Adult,
+    Child,
+    Student,
+    Staff,
+    Institutional,
+}
+
+impl MembershipType {
+    pub const ALL: [MembershipType; 5] = [
+        MembershipType::Adult,
+        MembershipType::Child,
+        MembershipType::Student,
+        MembershipType::Staff,
+        MembershipType::Institutional,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MembershipType::Adult => "Adult",
+            MembershipType::Child => "Child",
+            MembershipType::Student => "Student",
+            MembershipType::Staff => "Staff",
+            MembershipType::Institutional => "Institutional",
+        }
+    }
+}
+
+/// What members of one membership type may borrow, and on what terms.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct MembershipPolicy {
+    pub loan_limit: usize,
+    // Base loan period; formats with a longer renewal period get multiples
+    pub loan_days: u32,
+    pub fine_per_day_cents: u64,
+    // Formats this type may not borrow, compared case-insensitively
+    pub restricted_formats: Vec<String>,
+}
+
+impl MembershipPolicy {
+    pub fn default_for(kind: MembershipType) -> MembershipPolicy {
+        let (loan_limit, loan_days, fine_per_day_cents) = match kind {
+            MembershipType::Adult => (10, 30, FINE_PER_DAY_CENTS),
+            MembershipType::Child => (5, 21, 10),
+            MembershipType::Student => (15, 30, 20),
+            MembershipType::Staff => (25, 60, 0),
+            MembershipType::Institutional => (50, 90, FINE_PER_DAY_CENTS),
+        };
+        MembershipPolicy {
+            loan_limit,
+            loan_days,
+            fine_per_day_cents,
+            restricted_formats: Vec::new(),
+        }
+    }
+
+    pub fn allows(&self, item: &LiItem) -> bool {
+        !self.restricted_formats.iter().any(|f| f.eq_ignore_ascii_case(&item.format))
+    }
+}
+
+impl Library {
+    pub fn policy(&self, kind: MembershipType) -> MembershipPolicy {
+        self.policies.get(&kind).cloned().unwrap_or_else(|| MembershipPolicy::default_for(kind))
+    }
+
+    /// The most any member can have on loan, for checking a checkout list
+    /// before the member is known.
+    pub fn max_loan_limit(&self) -> usize {
+        MembershipType::ALL.iter().map(|&kind| self.policy(kind).loan_limit).max().unwrap_or(0)
+    }
+
+    pub(crate) fn check_policy(&self, member: &Member, item: &LiItem) -> Result<MembershipPolicy, String> {
+        if let Some(expires_at) = member.expires_at.filter(|&at| at <= self.clock.now()) {
+            return Err(tr!(
+                "Membership expired on {}; renew it before borrowing",
+                expires_at.format("%Y-%m-%d")
+            ));
+        }
+        let policy = self.policy(member.membership);
+        if member.items.len() >= policy.loan_limit {
+            return Err(tr!("Loan limit of {} items reached", policy.loan_limit));
+        }
+        if !policy.allows(item) {
+            return Err(tr!("{} members may not borrow {} items", member.membership.name(), item.format));
+        }
+        Ok(policy)
+    }
+
+    pub fn new_membership_expiry(&self) -> Option<DateTime<Utc>> {
+        self.clock.now().checked_add_months(Months::new(MEMBERSHIP_MONTHS))
+    }
+
+    /// Extends a membership by `MEMBERSHIP_MONTHS`, counted from today if it
+    /// has already expired. Returns the new expiry date.
+    pub fn renew_membership(&mut self, member_id: u32) -> Result<DateTime<Utc>, String> {
+        let now = self.clock.now();
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let from = member.expires_at.map_or(now, |at| at.max(now));
+        let expires_at = from
+            .checked_add_months(Months::new(MEMBERSHIP_MONTHS))
+            .ok_or_else(|| tr!("Expiry date out of range"))?;
+        member.expires_at = Some(expires_at);
+        self.record_command(LibraryCommand::RenewMembership { member_id });
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(expires_at)
+    }
+
+    /// Members whose membership has expired or expires within `days`, soonest
+    /// first.
+    pub fn expiring_members(&self, days: i64) -> Vec<Member> {
+        let until = self.clock.now() + Duration::days(days);
+        let mut members: Vec<Member> = self
+            .members
+            .values()
+            .filter(|m| m.expires_at.is_some_and(|at| at <= until))
+            .cloned()
+            .collect();
+        members.sort_by_key(|m| m.expires_at);
+        members
+    }
+
+    pub fn set_membership(&mut self, member_id: u32, kind: MembershipType) -> Result<(), String> {
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        member.membership = kind;
+        self.record_command(LibraryCommand::SetMembership { member_id, kind });
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+use std::io::{Read, Write};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use serde_json::{json, Value};
+
+use crate::clock::{Clock, SystemClock};
+use crate::i18n::tr;
+
+// Upgrades a file from one version of its format to the next. The clock
+// dates anything a step has to make up.
+type Step = fn(&mut Value, &dyn Clock);
+
+/// A JSON file format and how to bring files saved by older versions of the
+/// application up to date. Fields added with a serde default need no step;
+/// anything else (a renamed field, a value moved elsewhere) bumps the
+/// version with a step that rewrites the old shape.
+pub struct Format {
+    name: &'static str,
+    // `steps[n]` upgrades version n to n + 1, so the current version is
+    // `steps.len()`. Files from before versioning are version 0.
+    steps: &'static [Step],
+}
+
+/// The library state, backups and sync snapshots.
+pub const LIBRARY: Format = Format { name: "library", steps: &[ratings_to_reviews, count_shelved_holds] };
+pub const CONFIG: Format = Format { name: "config", steps: &[unchanged] };
+pub const USERS: Format = Format { name: "users", steps: &[unchanged] };
+/// Configuration profiles shared between branches.
+pub const PROFILE: Format = Format { name: "profile", steps: &[] };
+
+// Written next to the data, and left alone by the types reading it
+#[derive(Serialize)]
+struct Versioned<'a, T> {
+    schema_version: u32,
+    #[serde(flatten)]
+    data: &'a T,
+}
+
+impl Format {
+    pub fn version(&self) -> u32 {
+        self.steps.len() as u32
+    }
+
+    /// Brings `value` up to the current version of the format.
+    pub fn upgrade(&self, value: &mut Value, clock: &dyn Clock) -> Result<(), String> {
+        let Some(object) = value.as_object() else {
+            return Err(tr!("The {} file is not a JSON object", self.name));
+        };
+        let from = object.get("schema_version").and_then(Value::as_u64).unwrap_or(0);
+        if from > self.version() as u64 {
+            return Err(tr!(
+                "The {} file was saved by a newer version of rustLMS (format {}); this version reads up to format {}",
+                self.name,
+                from,
+                self.version()
+            ));
+        }
+        for (n, step) in self.steps.iter().enumerate().skip(from as usize) {
+            step(value, clock);
+            tracing::info!(format = self.name, "Upgraded from format {} to {}", n, n + 1);
+        }
+        value["schema_version"] = self.version().into();
+        Ok(())
+    }
+
+    /// Reads a file in this format saved by this or an older version.
+    pub fn read<T: DeserializeOwned>(&self, reader: impl Read) -> Result<T, Box<dyn std::error::Error>> {
+        let mut value: Value = serde_json::from_reader(reader)?;
+        self.upgrade(&mut value, &SystemClock)?;
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn write<T: Serialize>(&self, writer: impl Write, data: &T) -> Result<(), serde_json::Error> {
+        serde_json::to_writer(writer, &Versioned { schema_version: self.version(), data })
+    }
+
+    pub fn write_pretty<T: Serialize>(&self, writer: impl Write, data: &T) -> Result<(), serde_json::Error> {
+        serde_json::to_writer_pretty(writer, &Versioned { schema_version: self.version(), data })
+    }
+
+    pub fn to_string<T: Serialize>(&self, data: &T) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&Versioned { schema_version: self.version(), data })
+    }
+}
+
+// For the first versioned format, which only added the version
+fn unchanged(_: &mut Value, _: &dyn Clock) {}
+
+// Library 0 to 1: an item's single `ratings` number (1 to 5 stars, 0 for
+// none) becomes an anonymous review
+fn ratings_to_reviews(library: &mut Value, clock: &dyn Clock) {
+    let now = clock.now();
+    let Some(items) = library.get_mut("items").and_then(Value::as_object_mut) else { return };
+    for item in items.values_mut() {
+        let stars = item.get("ratings").and_then(Value::as_u64).unwrap_or(0);
+        let Some(item) = item.as_object_mut() else { continue };
+        item.remove("ratings");
+        if (1..=5).contains(&stars) {
+            let review = json!({ "member_id": null, "stars": stars, "text": "", "date": now });
+            match item.get_mut("reviews").and_then(Value::as_array_mut) {
+                Some(reviews) => reviews.push(review),
+                None => {
+                    item.insert("reviews".to_string(), json!([review]));
+                }
+            }
+        }
+    }
+}
+
+// Library 1 to 2: copies on the hold shelf are counted on the item, where
+// before they were only missing from the available copies
+fn count_shelved_holds(library: &mut Value, _: &dyn Clock) {
+    let shelved: Vec<u64> = library
+        .get("holds")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter(|hold| hold.get("status").and_then(Value::as_str) == Some("OnShelf"))
+        .filter_map(|hold| hold.get("item_id").and_then(Value::as_u64))
+        .collect();
+    let Some(items) = library.get_mut("items").and_then(Value::as_object_mut) else { return };
+    for item_id in shelved {
+        let Some(item) = items.get_mut(&item_id.to_string()).and_then(Value::as_object_mut) else { continue };
+        let statuses = item.entry("copy_statuses").or_insert_with(|| json!({}));
+        let count = statuses.get("OnHoldShelf").and_then(Value::as_u64).unwrap_or(0);
+        statuses["OnHoldShelf"] = (count + 1).into();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::auth::UserStore;
+    use crate::clock::FixedClock;
+    use crate::config::Config;
+    use crate::copies::CopyStatus;
+    use crate::Library;
+
+    fn clock() -> FixedClock {
+        FixedClock(Utc.with_ymd_and_hms(2024, 3, 1, 12, 0, 0).unwrap())
+    }
+
+    fn item(id: u32, extra: Value) -> Value {
+        let mut item = json!({
+            "title": format!("Title {}", id),
+            "author": null,
+            "year": 2001,
+            "edition": "",
+            "desc": "",
+            "format": "Book",
+            "id": id,
+            "copies": 2,
+            "avail_copies": 1,
+        });
+        item.as_object_mut().unwrap().extend(extra.as_object().unwrap().clone());
+        item
+    }
+
+    // Upgrades an old library file and reads it as the library
+    fn upgrade_library(mut value: Value) -> Library {
+        LIBRARY.upgrade(&mut value, &clock()).unwrap();
+        assert_eq!(value["schema_version"], LIBRARY.version());
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn library_0_to_1_turns_ratings_into_reviews() {
+        let old = json!({
+            "items": {
+                "1": item(1, json!({ "ratings": 4 })),
+                "2": item(2, json!({ "ratings": 0 })),
+                "3": item(3, json!({ "ratings": 9 })),
+            },
+            "members": {},
+        });
+        let library = upgrade_library(old);
+        let reviews = &library.items[&1].reviews;
+        assert_eq!(reviews.len(), 1);
+        assert_eq!(reviews[0].stars, 4);
+        assert_eq!(reviews[0].member_id, None);
+        assert_eq!(reviews[0].date, clock().0);
+        // No rating, and one out of range, give no review
+        assert!(library.items[&2].reviews.is_empty());
+        assert!(library.items[&3].reviews.is_empty());
+    }
+
+    #[test]
+    fn library_0_to_1_keeps_existing_reviews() {
+        let review = json!({ "member_id": 7, "stars": 2, "text": "Slow", "date": "2020-01-01T00:00:00Z" });
+        let old = json!({
+            "items": { "1": item(1, json!({ "ratings": 5, "reviews": [review] })) },
+            "members": {},
+        });
+        let library = upgrade_library(old);
+        let stars: Vec<u8> = library.items[&1].reviews.iter().map(|r| r.stars).collect();
+        assert_eq!(stars, [2, 5]);
+    }
+
+    #[test]
+    fn library_1_to_2_counts_shelved_holds() {
+        let hold = |id: u32, item_id: u32, status: &str| {
+            json!({
+                "id": id,
+                "item_id": item_id,
+                "member_id": 1,
+                "placed_at": "2024-02-01T00:00:00Z",
+                "status": status,
+                "shelved_at": null,
+                "pickup_by": null,
+                "notified": false,
+            })
+        };
+        let old = json!({
+            "schema_version": 1,
+            "items": {
+                "1": item(1, json!({})),
+                "2": item(2, json!({ "copy_statuses": { "OnHoldShelf": 1 } })),
+            },
+            "members": {},
+            "holds": [hold(1, 1, "OnShelf"), hold(2, 1, "Waiting"), hold(3, 2, "OnShelf")],
+        });
+        let library = upgrade_library(old);
+        assert_eq!(library.items[&1].copies_in(CopyStatus::OnHoldShelf), 1);
+        assert_eq!(library.items[&2].copies_in(CopyStatus::OnHoldShelf), 2);
+    }
+
+    #[test]
+    fn library_1_to_2_skips_holds_on_missing_items() {
+        let old = json!({
+            "schema_version": 1,
+            "items": {},
+            "members": {},
+            "holds": [{
+                "id": 1,
+                "item_id": 9,
+                "member_id": 1,
+                "placed_at": "2024-02-01T00:00:00Z",
+                "status": "OnShelf",
+                "shelved_at": null,
+                "pickup_by": null,
+                "notified": false,
+            }],
+        });
+        let library = upgrade_library(old);
+        assert!(library.items.is_empty());
+    }
+
+    #[test]
+    fn current_library_is_left_alone() {
+        let current = json!({
+            "schema_version": LIBRARY.version(),
+            "items": { "1": item(1, json!({})) },
+            "members": {},
+        });
+        let mut upgraded = current.clone();
+        LIBRARY.upgrade(&mut upgraded, &clock()).unwrap();
+        assert_eq!(upgraded, current);
+    }
+
+    #[test]
+    fn newer_files_are_refused() {
+        let mut value = json!({ "schema_version": LIBRARY.version() + 1, "items": {}, "members": {} });
+        assert!(LIBRARY.upgrade(&mut value, &clock()).is_err());
+        assert!(LIBRARY.upgrade(&mut json!([]), &clock()).is_err());
+    }
+
+    #[test]
+    fn config_0_to_1_reads_as_before() {
+        let old = json!({ "smtp_host": "mail.example.org", "report_hour": 7 });
+        let config: Config = CONFIG.read(old.to_string().as_bytes()).unwrap();
+        assert_eq!(config.smtp_host, "mail.example.org");
+        assert_eq!(config.report_hour, 7);
+    }
+
+    #[test]
+    fn users_0_to_1_reads_as_before() {
+        let old = json!({ "users": [{ "username": "ada", "password_hash": "x", "role": "Admin" }] });
+        let users: UserStore = USERS.read(old.to_string().as_bytes()).unwrap();
+        assert_eq!(users.users().len(), 1);
+        assert_eq!(users.users()[0].username, "ada");
+    }
+
+    #[test]
+    fn written_files_read_back() {
+        let config = Config::default();
+        let text = CONFIG.to_string(&config).unwrap();
+        let value: Value = serde_json::from_str(&text).unwrap();
+        assert_eq!(value["schema_version"], CONFIG.version());
+        let read: Config = CONFIG.read(text.as_bytes()).unwrap();
+        assert_eq!(read.report_hour, config.report_hour);
+    }
+}
@@ -0,0 +1,121 @@
+use chrono::{DateTime, Datelike, Local, Timelike};
+
+use crate::config::Config;
+use crate::fines::{format_money, FineStatus};
+use crate::holds::HoldStatus;
+use crate::i18n::tr;
+use crate::{mail, Library, Member};
+
+impl Library {
+    /// Composes an overdue notice for a member and marks the overdue loans
+    /// as noticed. Fails if nothing is overdue.
+    pub fn overdue_notice(&mut self, member_id: u32) -> Result<String, String> {
+        let now = self.clock.now();
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        let mut overdue: Vec<_> = member.items.values_mut().filter(|inst| inst.due_date < now).collect();
+        if overdue.is_empty() {
+            return Err(tr!("Member has no overdue items"));
+        }
+        overdue.sort_by_key(|inst| inst.due_date);
+
+        let mut text = tr!("Dear {},\n\nThe following items are overdue:\n", member.name);
+        for inst in overdue {
+            text.push_str(&tr!("  - {} (due {})\n", inst.title, inst.due_text()));
+            inst.notice = true;
+        }
+        text.push_str(&tr!("\nPlease return or renew them as soon as possible.\n"));
+        self.dirty = true;
+        Ok(text)
+    }
+}
+
+/// A member's weekly digest email.
+pub struct MemberDigest {
+    pub member_id: u32,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Whether the member digests should go out at `now`: they are turned on,
+/// mail is set up, it is the configured day and hour or later, and they
+/// haven't been sent yet today.
+pub fn digest_due(config: &Config, now: DateTime<Local>) -> bool {
+    config.digest_enabled
+        && mail::is_configured(config)
+        && now.weekday() == config.digest_weekday
+        && now.hour() >= config.digest_hour
+        && config.digest_last_sent != Some(now.date_naive())
+}
+
+impl Library {
+    /// One digest for each member with an email address who hasn't opted
+    /// out and has something to be told about: what they have out and when
+    /// it is due, holds waiting for them and fines still owed.
+    pub fn member_digests(&self) -> Vec<MemberDigest> {
+        let now = self.clock.now();
+        let mut members: Vec<&Member> = self.members.values().filter(|m| !m.digest_opt_out && m.anonymized_at.is_none()).collect();
+        members.sort_by_key(|m| m.id);
+        members
+            .into_iter()
+            .filter_map(|member| {
+                let to = member.email.clone().filter(|e| !e.trim().is_empty())?;
+                let mut loans: Vec<_> = member.items.values().collect();
+                loans.sort_by_key(|inst| (inst.due_date, inst.id));
+                let mut ready: Vec<_> = self
+                    .holds
+                    .iter()
+                    .filter(|h| h.member_id == member.id && h.status == HoldStatus::OnShelf)
+                    .collect();
+                ready.sort_by_key(|h| (h.pickup_by, h.id));
+                let fines: Vec<_> = member.fines.iter().filter(|f| f.status == FineStatus::Outstanding).collect();
+                if loans.is_empty() && ready.is_empty() && fines.is_empty() {
+                    return None;
+                }
+
+                let mut body = tr!("Dear {},\n\nHere is your weekly summary from the library.\n", member.name);
+                if !loans.is_empty() {
+                    body.push_str(&tr!("\nOn loan to you:\n"));
+                    for inst in loans {
+                        if inst.due_date < now {
+                            body.push_str(&tr!("  - {} (overdue since {})\n", inst.title, inst.due_text()));
+                        } else {
+                            body.push_str(&tr!("  - {} (due {})\n", inst.title, inst.due_text()));
+                        }
+                    }
+                }
+                if !ready.is_empty() {
+                    body.push_str(&tr!("\nWaiting for you on the hold shelf:\n"));
+                    for hold in ready {
+                        let title = self.items.get(&hold.item_id).map_or_else(|| "?".to_string(), |i| i.title.to_string());
+                        match hold.pickup_by {
+                            Some(by) => body.push_str(&tr!("  - {} (collect by {})\n", title, by.format("%Y-%m-%d"))),
+                            None => body.push_str(&format!("  - {}\n", title)),
+                        }
+                    }
+                }
+                if !fines.is_empty() {
+                    let owed: u64 = fines.iter().map(|f| f.amount_cents).sum();
+                    body.push_str(&tr!("\nFines owed: {}\n", format_money(owed)));
+                }
+                body.push_str(&tr!("\nTo stop these weekly emails, ask at the library desk.\n"));
+                Some(MemberDigest {
+                    member_id: member.id,
+                    to,
+                    subject: tr!("Your library account this week"),
+                    body,
+                })
+            })
+            .collect()
+    }
+
+    /// Stops or resumes a member's weekly digest emails.
+    pub fn set_digest_opt_out(&mut self, member_id: u32, opt_out: bool) -> Result<(), String> {
+        self.only_at_server()?;
+        let member = self.members.get_mut(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        member.digest_opt_out = opt_out;
+        self.dirty = true;
+        self.member_changed(member_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,217 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::i18n::tr;
+use crate::shelves::NEW_ARRIVAL_DAYS;
+use crate::{Library, LiItem};
+
+// Folder under the export directory with one page per item
+const ITEMS_DIR: &str = "items";
+
+const STYLE: &str = "body { font-family: sans-serif; max-width: 50em; margin: 2em auto; padding: 0 1em; line-height: 1.4; }
+nav a { margin-right: 1em; }
+h2 { border-bottom: 1px solid #ccc; }
+ul { list-style: none; padding-left: 0; }
+li { margin: 0.3em 0; }
+.letters a { margin-right: 0.5em; }
+.meta { color: #555; }
+.available { color: #1a7f37; }
+.unavailable { color: #b35900; }
+dt { font-weight: bold; }
+dd { margin: 0 0 0.5em 0; }
+footer { margin-top: 3em; color: #777; font-size: small; }
+";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Anchor for a heading, so the letter and genre links can jump to it
+fn anchor(text: &str) -> String {
+    let id: String = text
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect();
+    format!("s-{}", id)
+}
+
+fn author_of(item: &LiItem) -> String {
+    item.author.clone().unwrap_or_else(|| tr!("Unknown"))
+}
+
+// A whole HTML document; `root` leads from the page back to the export
+// directory
+fn page(root: &str, title: &str, body: &str, exported: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<meta name=\"viewport\" content=\"width=device-width\">\n\
+         <title>{title}</title>\n<link rel=\"stylesheet\" href=\"{root}style.css\">\n</head>\n<body>\n\
+         <nav><a href=\"{root}index.html\">{by_title}</a><a href=\"{root}authors.html\">{by_author}</a><a href=\"{root}genres.html\">{by_genre}</a></nav>\n\
+         <h1>{title}</h1>\n{body}<footer>{exported}</footer>\n</body>\n</html>\n",
+        title = escape(title),
+        by_title = escape(&tr!("Titles")),
+        by_author = escape(&tr!("Authors")),
+        by_genre = escape(&tr!("Genres")),
+        exported = escape(exported),
+    )
+}
+
+fn item_entry(item: &LiItem) -> String {
+    let mut meta = author_of(item);
+    if item.year > 0 {
+        meta.push_str(&format!(", {}", item.year));
+    }
+    format!(
+        "<li><a href=\"{}/{}.html\">{}</a> <span class=\"meta\">{}</span></li>\n",
+        ITEMS_DIR,
+        item.id,
+        escape(&item.title),
+        escape(&meta)
+    )
+}
+
+// Sections with a heading each and links to jump between them
+fn grouped(groups: &BTreeMap<String, Vec<&LiItem>>) -> String {
+    let mut html = String::from("<p class=\"letters\">");
+    for heading in groups.keys() {
+        html.push_str(&format!("<a href=\"#{}\">{}</a> ", anchor(heading), escape(heading)));
+    }
+    html.push_str("</p>\n");
+    for (heading, items) in groups {
+        html.push_str(&format!("<h2 id=\"{}\">{}</h2>\n<ul>\n", anchor(heading), escape(heading)));
+        for item in items {
+            html.push_str(&item_entry(item));
+        }
+        html.push_str("</ul>\n");
+    }
+    html
+}
+
+fn sorted_by_title(mut items: Vec<&LiItem>) -> Vec<&LiItem> {
+    items.sort_by_cached_key(|item| (item.title.to_lowercase(), item.id));
+    items
+}
+
+impl Library {
+    fn item_page(&self, item: &LiItem, exported: &str) -> String {
+        let available = item.avail_copies > 0 && !self.in_transit(item.id);
+        let mut body = format!(
+            "<p class=\"{}\">{}</p>\n<dl>\n",
+            if available { "available" } else { "unavailable" },
+            escape(&tr!("{} of {} copies available", if available { item.avail_copies } else { 0 }, item.copies))
+        );
+        let mut field = |name: String, value: &str| {
+            if !value.is_empty() {
+                body.push_str(&format!("<dt>{}</dt><dd>{}</dd>\n", escape(&name), escape(value)));
+            }
+        };
+        field(tr!("Author"), &author_of(item));
+        field(tr!("Year"), &if item.year > 0 { item.year.to_string() } else { String::new() });
+        field(tr!("Edition"), &item.edition);
+        field(tr!("Format"), &item.format);
+        field(tr!("ISBN"), item.isbn.as_deref().unwrap_or(""));
+        field(tr!("Genres"), &item.tags.join(", "));
+        field(tr!("Branch"), &item.branch);
+        field(tr!("Call Number"), &item.call_number);
+        field(tr!("Shelf Location"), &item.shelf_location);
+        body.push_str("</dl>\n");
+        if !item.desc.is_empty() {
+            body.push_str(&format!("<p>{}</p>\n", escape(&item.desc)));
+        }
+        page("../", &item.title, &body, exported)
+    }
+
+    /// Writes the catalog to `dir` as a static website: an index by title,
+    /// pages by author and by genre, and a page per item showing how many
+    /// copies were on the shelf at the time of the export. The folder can
+    /// be copied to any web server as it is. Returns the number of items.
+    pub fn export_opac_html(&self, dir: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+        let items_dir = dir.join(ITEMS_DIR);
+        fs::create_dir_all(&items_dir)?;
+        // Pages of items deleted since the last export would stay reachable
+        for entry in fs::read_dir(&items_dir)? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|e| e == "html") {
+                fs::remove_file(path)?;
+            }
+        }
+
+        let exported = tr!("Availability as of {}", self.clock.now().with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"));
+        let items = sorted_by_title(self.items.values().collect());
+
+        let mut by_letter: BTreeMap<String, Vec<&LiItem>> = BTreeMap::new();
+        let mut by_author: BTreeMap<String, Vec<&LiItem>> = BTreeMap::new();
+        let mut by_genre: BTreeMap<String, Vec<&LiItem>> = BTreeMap::new();
+        for &item in &items {
+            let letter = match item.title.chars().next() {
+                Some(c) if c.is_alphabetic() => c.to_uppercase().to_string(),
+                _ => "#".to_string(),
+            };
+            by_letter.entry(letter).or_default().push(item);
+            by_author.entry(author_of(item)).or_default().push(item);
+            for tag in &item.tags {
+                by_genre.entry(tag.clone()).or_default().push(item);
+            }
+            fs::write(items_dir.join(format!("{}.html", item.id)), self.item_page(item, &exported))?;
+        }
+
+        fs::write(dir.join("style.css"), STYLE)?;
+        fs::write(dir.join("index.html"), page("", &tr!("Catalog"), &grouped(&by_letter), &exported))?;
+        fs::write(dir.join("authors.html"), page("", &tr!("Authors"), &grouped(&by_author), &exported))?;
+        fs::write(dir.join("genres.html"), page("", &tr!("Genres"), &grouped(&by_genre), &exported))?;
+        Ok(items.len())
+    }
+
+    /// Writes the new arrivals (items added in the last `NEW_ARRIVAL_DAYS`)
+    /// to `path` as an RSS 2.0 feed patrons can subscribe to. With the
+    /// address the catalog website is published at, each entry links to the
+    /// item's page there.
+    pub fn export_new_arrivals_rss(&self, path: &Path, website_url: &str) -> Result<usize, Box<dyn std::error::Error>> {
+        let website_url = website_url.trim_end_matches('/');
+        let items = self.new_arrivals();
+        let mut rss = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<rss version=\"2.0\">\n<channel>\n");
+        rss.push_str(&format!("<title>{}</title>\n", escape(&tr!("New Arrivals"))));
+        rss.push_str(&format!("<link>{}</link>\n", escape(website_url)));
+        rss.push_str(&format!(
+            "<description>{}</description>\n",
+            escape(&tr!("Items added to the catalog in the last {} days", NEW_ARRIVAL_DAYS))
+        ));
+        rss.push_str(&format!("<lastBuildDate>{}</lastBuildDate>\n", self.clock.now().to_rfc2822()));
+        for item in &items {
+            let mut desc = author_of(item);
+            if item.year > 0 {
+                desc.push_str(&format!(", {}", item.year));
+            }
+            if !item.desc.is_empty() {
+                desc.push_str(&format!("\n\n{}", item.desc));
+            }
+            rss.push_str("<item>\n");
+            rss.push_str(&format!("<title>{}</title>\n", escape(&item.title)));
+            rss.push_str(&format!("<description>{}</description>\n", escape(&desc)));
+            if !website_url.is_empty() {
+                rss.push_str(&format!("<link>{}/{}/{}.html</link>\n", escape(website_url), ITEMS_DIR, item.id));
+            }
+            // Stable across exports, so readers don't show an item twice
+            rss.push_str(&format!("<guid isPermaLink=\"false\">item-{}</guid>\n", item.id));
+            if let Some(added_at) = item.added_at {
+                rss.push_str(&format!("<pubDate>{}</pubDate>\n", added_at.to_rfc2822()));
+            }
+            rss.push_str("</item>\n");
+        }
+        rss.push_str("</channel>\n</rss>\n");
+        fs::write(path, rss)?;
+        Ok(items.len())
+    }
+}
@@ -0,0 +1,125 @@
+use std::time::{Duration, Instant};
+
+use gtk::glib;
+use regex::{Regex, RegexBuilder};
+
+use crate::i18n::tr;
+use crate::LiItem;
+
+// How long a pattern search may take before it stops with what it has
+pub const MATCH_TIMEOUT: Duration = Duration::from_millis(300);
+// Upper bound on the compiled pattern, so a pattern like `(a{100}){100}`
+// is rejected instead of taking all the memory
+const SIZE_LIMIT: usize = 1 << 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchMode {
+    // The fuzzy title search, or the query language
+    Words,
+    // `*` for any text, `?` for one character
+    Wildcard,
+    Regex,
+}
+
+impl SearchMode {
+    pub const ALL: [SearchMode; 3] = [SearchMode::Words, SearchMode::Wildcard, SearchMode::Regex];
+
+    pub fn label(self) -> String {
+        match self {
+            SearchMode::Words => tr!("Words"),
+            SearchMode::Wildcard => tr!("Wildcard"),
+            SearchMode::Regex => tr!("Regex"),
+        }
+    }
+}
+
+/// A wildcard or regex pattern matched, ignoring case, against an item's
+/// title, author and description.
+#[derive(Clone)]
+pub struct Pattern {
+    regex: Regex,
+}
+
+/// The result of a pattern search. When it took longer than the timeout
+/// the search stopped early and `ids` are only the matches found so far.
+pub struct PatternResult {
+    pub ids: Vec<u32>,
+    pub timed_out: bool,
+}
+
+fn wildcard_to_regex(text: &str) -> String {
+    let mut source = String::new();
+    for c in text.chars() {
+        match c {
+            '*' => source.push_str(".*"),
+            '?' => source.push('.'),
+            c => source.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    source
+}
+
+impl Pattern {
+    pub fn new(mode: SearchMode, text: &str) -> Result<Pattern, String> {
+        let source = match mode {
+            SearchMode::Words => regex::escape(text.trim()),
+            SearchMode::Wildcard => wildcard_to_regex(text.trim()),
+            SearchMode::Regex => text.to_string(),
+        };
+        let regex = RegexBuilder::new(&source)
+            .case_insensitive(true)
+            .size_limit(SIZE_LIMIT)
+            .dfa_size_limit(SIZE_LIMIT)
+            .build()
+            .map_err(|e| match e {
+                regex::Error::CompiledTooBig(_) => tr!("The pattern is too complex"),
+                e => tr!("Invalid pattern: {}", e),
+            })?;
+        Ok(Pattern { regex })
+    }
+
+    pub fn matches(&self, item: &LiItem) -> bool {
+        self.regex.is_match(&item.title)
+            || item.author.as_deref().is_some_and(|a| self.regex.is_match(a))
+            || self.regex.is_match(&item.desc)
+    }
+
+    /// `text` as Pango markup with the matches highlighted.
+    pub fn highlight(&self, text: &str) -> String {
+        let mut markup = String::new();
+        let mut last = 0;
+        for m in self.regex.find_iter(text).filter(|m| !m.is_empty()) {
+            markup.push_str(&glib::markup_escape_text(&text[last..m.start()]));
+            markup.push_str("<span background=\"#f6d32d\" foreground=\"black\">");
+            markup.push_str(&glib::markup_escape_text(m.as_str()));
+            markup.push_str("</span>");
+            last = m.end();
+        }
+        markup.push_str(&glib::markup_escape_text(&text[last..]));
+        markup
+    }
+}
+
+/// IDs of the items matching `pattern`, in order. The regex engine runs in
+/// linear time, so no single match can hang, but a broad pattern over long
+/// descriptions can still be slow on a big catalog; the search gives up
+/// after `timeout`.
+pub fn search<'a>(items: impl Iterator<Item = &'a LiItem>, pattern: &Pattern, timeout: Duration) -> PatternResult {
+    let start = Instant::now();
+    let mut ids = Vec::new();
+    let mut timed_out = false;
+    for item in items {
+        if start.elapsed() > timeout {
+            timed_out = true;
+            break;
+        }
+        if pattern.matches(item) {
+            ids.push(item.id);
+        }
+    }
+    if timed_out {
+        tracing::warn!(found = ids.len(), "Pattern search stopped after {:?}", timeout);
+    }
+    ids.sort_unstable();
+    PatternResult { ids, timed_out }
+}
@@ -0,0 +1,27 @@
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::path::Path;
+
+use crate::migrate;
+use crate::Library;
+
+pub const STATE_PATH: &str = "library.json";
+
+impl Library {
+    pub fn load(path: &Path) -> Result<Library, Box<dyn std::error::Error>> {
+        let file = File::open(path)?;
+        migrate::LIBRARY.read(BufReader::new(file))
+    }
+
+    // Writes to a temporary file first so a failed save never leaves a
+    // truncated state file behind.
+    pub fn save(&mut self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let tmp_path = path.with_extension("json.tmp");
+        let mut writer = BufWriter::new(File::create(&tmp_path)?);
+        migrate::LIBRARY.write(&mut writer, &*self)?;
+        writer.flush()?;
+        fs::rename(&tmp_path, path)?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,91 @@
+use std::sync::Arc;
+
+use chrono::{Local, NaiveDate};
+
+use crate::callnumber;
+use crate::holds::HoldStatus;
+use crate::i18n::tr;
+use crate::transfers::TransferStatus;
+use crate::Library;
+
+/// Copies to fetch from the shelves, and why.
+#[derive(Clone)]
+pub struct PickRow {
+    pub item_id: u32,
+    pub title: Arc<str>,
+    pub branch: String,
+    pub shelf_location: String,
+    pub call_number: String,
+    pub copies: u32,
+    // E.g. "Hold for Jane Doe (12)"
+    pub reason: String,
+}
+
+impl Library {
+    /// What has to be pulled from the shelves: a copy for each hold filled
+    /// on `date` and still waiting on the hold shelf, and the copies on the
+    /// shelf of items with a transfer requested but not yet sent. Sorted by
+    /// branch, shelf location and call number, in the order they are walked
+    /// past.
+    pub fn pick_list(&self, date: NaiveDate) -> Vec<PickRow> {
+        let row = |item_id: u32, copies: u32, reason: String| {
+            let item = self.items.get(&item_id)?;
+            Some(PickRow {
+                item_id,
+                title: item.title.clone(),
+                branch: item.branch.clone(),
+                shelf_location: item.shelf_location.clone(),
+                call_number: item.call_number.clone(),
+                copies,
+                reason,
+            })
+        };
+
+        let holds = self
+            .holds
+            .iter()
+            .filter(|h| h.status == HoldStatus::OnShelf)
+            .filter(|h| h.shelved_at.is_some_and(|at| at.with_timezone(&Local).date_naive() == date))
+            .filter_map(|h| {
+                let name = self.members.get(&h.member_id).map_or("?", |m| m.name.as_str());
+                row(h.item_id, 1, tr!("Hold for {} ({})", name, h.member_id))
+            });
+        let transfers = self.transfers.iter().filter(|t| t.status == TransferStatus::Requested).filter_map(|t| {
+            let copies = self.items.get(&t.item_id)?.avail_copies;
+            if copies == 0 {
+                return None;
+            }
+            row(t.item_id, copies, tr!("Transfer to {}", t.to))
+        });
+
+        let mut rows: Vec<PickRow> = holds.chain(transfers).collect();
+        rows.sort_by(|a, b| {
+            a.branch
+                .cmp(&b.branch)
+                .then_with(|| a.shelf_location.cmp(&b.shelf_location))
+                .then_with(|| callnumber::compare(&a.call_number, &b.call_number))
+                .then(a.item_id.cmp(&b.item_id))
+        });
+        rows
+    }
+}
+
+pub fn pick_list_headers() -> Vec<String> {
+    vec![tr!("Branch"), tr!("Shelf Location"), tr!("Call Number"), tr!("Item"), tr!("Title"), tr!("Copies"), tr!("For")]
+}
+
+pub fn pick_list_rows(rows: &[PickRow]) -> Vec<Vec<String>> {
+    rows.iter()
+        .map(|r| {
+            vec![
+                r.branch.clone(),
+                r.shelf_location.clone(),
+                r.call_number.clone(),
+                r.item_id.to_string(),
+                r.title.to_string(),
+                r.copies.to_string(),
+                r.reason.clone(),
+            ]
+        })
+        .collect()
+}
@@ -0,0 +1,34 @@
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use age::secrecy::Secret;
+
+use crate::i18n::tr;
+
+// Shortest passphrase an export is encrypted with
+pub const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Added to the name of an encrypted export.
+pub const ENCRYPTED_EXTENSION: &str = "age";
+
+/// Writes an export holding member data to `path`. With a passphrase it is
+/// encrypted in the age format, which `age -d` or any age tool can open
+/// with the same passphrase; without one it is written as is, which callers
+/// only offer when the library allows it.
+pub fn write_export(path: &Path, data: &[u8], passphrase: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(passphrase) = passphrase else {
+        std::fs::write(path, data)?;
+        tracing::warn!(path = %path.display(), "Member data exported unencrypted");
+        return Ok(());
+    };
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(tr!("The passphrase must be at least {} characters long", MIN_PASSPHRASE_LEN).into());
+    }
+    let encryptor = age::Encryptor::with_user_passphrase(Secret::new(passphrase.to_string()));
+    let mut writer = encryptor.wrap_output(File::create(path)?)?;
+    writer.write_all(data)?;
+    writer.finish()?;
+    tracing::info!(path = %path.display(), "Member data exported encrypted");
+    Ok(())
+}
@@ -0,0 +1,237 @@
+use std::cell::RefCell;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use gtk::glib;
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::config::Config;
+use crate::fines::FineStatus;
+use crate::{LiItem, LiItemInstance, Library, Member};
+
+pub const PLUGINS_DIR: &str = "plugins";
+// How often the nightly hook checks whether the day has changed
+const NIGHTLY_CHECK_SECONDS: u32 = 60;
+// Keeps a runaway script from hanging the desk
+const MAX_OPERATIONS: u64 = 1_000_000;
+const HTTP_TIMEOUT_SECONDS: u64 = 10;
+
+struct Script {
+    name: String,
+    ast: AST,
+}
+
+/// The Rhai scripts in the plugins directory, and the hooks they define:
+///
+/// - `on_issue(item, member)`: return a message to refuse the loan
+/// - `on_return(loan)`: after an item comes back
+/// - `fine(loan, days_late, cents)`: return a different fine in cents
+/// - `on_overdue(loan)`: for each overdue loan, once a day
+/// - `nightly()`: once a day
+///
+/// Scripts get plain maps, not the library itself, so they can't change
+/// it except through what a hook returns. A script that fails is logged
+/// and otherwise ignored.
+pub struct Plugins {
+    engine: Engine,
+    scripts: Vec<Script>,
+}
+
+fn new_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1_000_000);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+    engine.on_print(|text| tracing::info!(target: "plugins", "{}", text));
+    engine.on_debug(|text, source, pos| tracing::debug!(target: "plugins", "{} {:?} {}", source.unwrap_or(""), pos, text));
+    // Sent from a thread of its own, so a slow server doesn't hold up the
+    // desk; failures are only logged
+    engine.register_fn("http_post", |url: &str, body: &str| {
+        let (url, body) = (url.to_string(), body.to_string());
+        std::thread::spawn(move || {
+            let result = ureq::post(&url)
+                .timeout(Duration::from_secs(HTTP_TIMEOUT_SECONDS))
+                .set("Content-Type", "application/json")
+                .send_string(&body);
+            if let Err(e) = result {
+                tracing::warn!(target: "plugins", "http_post to {} failed: {}", url, e);
+            }
+        });
+    });
+    engine
+}
+
+impl Plugins {
+    /// Compiles every `.rhai` file in `dir`. Scripts that don't compile are
+    /// logged and left out.
+    pub fn load(dir: &Path) -> Plugins {
+        let engine = new_engine();
+        let mut paths: Vec<_> = match fs::read_dir(dir) {
+            Ok(entries) => entries.filter_map(Result::ok).map(|e| e.path()).filter(|p| p.extension().is_some_and(|e| e == "rhai")).collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        let mut scripts = Vec::new();
+        for path in paths {
+            let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|src| engine.compile(src).map_err(|e| e.to_string())) {
+                Ok(ast) => {
+                    tracing::info!("Plugin {} loaded", name);
+                    scripts.push(Script { name, ast });
+                }
+                Err(e) => tracing::error!("Plugin {} not loaded: {}", name, e),
+            }
+        }
+        Plugins { engine, scripts }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scripts.is_empty()
+    }
+
+    // Calls `hook` in every script that defines it, in file name order,
+    // and returns what each one returned
+    fn call(&self, hook: &str, args: impl Fn() -> Vec<Dynamic>) -> Vec<(&str, Dynamic)> {
+        let mut results = Vec::new();
+        for script in &self.scripts {
+            if !script.ast.iter_functions().any(|f| f.name == hook) {
+                continue;
+            }
+            let result = self.engine.call_fn::<Dynamic>(&mut Scope::new(), &script.ast, hook, args());
+            match result {
+                Ok(value) => results.push((script.name.as_str(), value)),
+                Err(e) => tracing::error!("Plugin {} failed in {}: {}", script.name, hook, e),
+            }
+        }
+        results
+    }
+}
+
+fn item_map(item: &LiItem) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), (item.id as i64).into());
+    map.insert("title".into(), item.title.to_string().into());
+    map.insert("author".into(), item.author.clone().unwrap_or_default().into());
+    map.insert("format".into(), item.format.clone().into());
+    map.insert("branch".into(), item.branch.clone().into());
+    map.insert("call_number".into(), item.call_number.clone().into());
+    map.insert("available".into(), (item.avail_copies as i64).into());
+    map
+}
+
+fn member_map(member: &Member) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), (member.id as i64).into());
+    map.insert("name".into(), member.name.clone().into());
+    map.insert("membership".into(), member.membership.name().to_string().into());
+    map.insert("loans".into(), (member.items.len() as i64).into());
+    let owed: u64 = member.fines.iter().filter(|f| f.status == FineStatus::Outstanding).map(|f| f.amount_cents).sum();
+    map.insert("fines_owed_cents".into(), (owed as i64).into());
+    map
+}
+
+fn loan_map(member_id: u32, inst: &LiItemInstance) -> Map {
+    let mut map = Map::new();
+    map.insert("item_id".into(), (inst.id as i64).into());
+    map.insert("member_id".into(), (member_id as i64).into());
+    map.insert("title".into(), inst.title.to_string().into());
+    map.insert("issued_at".into(), inst.issued_at.to_rfc3339().into());
+    map.insert("due_date".into(), inst.due_date.to_rfc3339().into());
+    map.insert("renewals".into(), (inst.renewed_count as i64).into());
+    map
+}
+
+impl Library {
+    /// Asks the `on_issue` hooks about a loan. The first script that
+    /// returns a message refuses it with that message.
+    pub(crate) fn plugins_allow_issue(&self, item_id: u32, member_id: u32) -> Result<(), String> {
+        let Some(plugins) = &self.plugins else { return Ok(()) };
+        let (Some(item), Some(member)) = (self.items.get(&item_id), self.members.get(&member_id)) else { return Ok(()) };
+        let (item, member) = (item_map(item), member_map(member));
+        for (name, value) in plugins.call("on_issue", || vec![item.clone().into(), member.clone().into()]) {
+            if let Ok(message) = value.into_string() {
+                if !message.is_empty() {
+                    tracing::info!(item_id, member_id, "Issue refused by plugin {}: {}", name, message);
+                    return Err(message);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn plugins_on_return(&self, member_id: u32, inst: &LiItemInstance) {
+        let Some(plugins) = &self.plugins else { return };
+        let loan = loan_map(member_id, inst);
+        plugins.call("on_return", || vec![loan.clone().into()]);
+    }
+
+    /// The fine the `fine` hooks settle on for a late loan, starting from
+    /// `cents` worked out by the usual rules. Each script gets what the
+    /// one before it returned.
+    pub(crate) fn plugins_fine(&self, member_id: u32, inst: &LiItemInstance, days_late: i64, cents: u64) -> u64 {
+        let Some(plugins) = &self.plugins else { return cents };
+        let loan = loan_map(member_id, inst);
+        let mut cents = cents;
+        for script in &plugins.scripts {
+            if !script.ast.iter_functions().any(|f| f.name == "fine") {
+                continue;
+            }
+            let args = (loan.clone(), days_late, cents as i64);
+            match plugins.engine.call_fn::<Dynamic>(&mut Scope::new(), &script.ast, "fine", args).map(|v| v.as_int()) {
+                Ok(Ok(new)) if new >= 0 => cents = new as u64,
+                Ok(_) => tracing::error!("Plugin {} returned no fine in cents from fine()", script.name),
+                Err(e) => tracing::error!("Plugin {} failed in fine: {}", script.name, e),
+            }
+        }
+        cents
+    }
+
+    // The once-a-day hooks: `on_overdue` for every loan past its due date,
+    // then `nightly`
+    fn run_nightly_plugins(&self) {
+        let Some(plugins) = &self.plugins else { return };
+        let now = self.clock.now();
+        for member in self.members.values() {
+            for inst in member.items.values().filter(|inst| inst.due_date < now) {
+                let loan = loan_map(member.id, inst);
+                plugins.call("on_overdue", || vec![loan.clone().into()]);
+            }
+        }
+        plugins.call("nightly", Vec::new);
+        tracing::info!("Nightly plugins run");
+    }
+}
+
+/// Loads the plugins into the library and runs the daily hooks once each
+/// day the desk is open, the first time it is checked after midnight.
+pub fn start(library: Arc<RwLock<Library>>, config: Rc<RefCell<Config>>) {
+    let plugins = Plugins::load(Path::new(PLUGINS_DIR));
+    if plugins.is_empty() {
+        return;
+    }
+    library.write().unwrap().plugins = Some(Arc::new(plugins));
+
+    let check = move || {
+        let lib = library.read().unwrap();
+        let today = lib.clock.now().with_timezone(&chrono::Local).date_naive();
+        if config.borrow().plugins_last_nightly == Some(today) {
+            return;
+        }
+        config.borrow_mut().plugins_last_nightly = Some(today);
+        if let Err(e) = config.borrow().save() {
+            tracing::error!("Failed to save settings: {}", e);
+        }
+        lib.run_nightly_plugins();
+    };
+    check();
+    glib::timeout_add_seconds_local(NIGHTLY_CHECK_SECONDS, move || {
+        check();
+        glib::ControlFlow::Continue
+    });
+}
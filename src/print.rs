@@ -0,0 +1,121 @@
+use std::cell::Cell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use gtk::cairo;
+use gtk::prelude::*;
+
+use crate::i18n::tr;
+
+const FONT_SIZE: f64 = 10.0;
+const LINE_HEIGHT: f64 = 16.0;
+const TITLE_SIZE: f64 = 14.0;
+// Space taken by the title and column headings at the top of every page
+const HEADER_HEIGHT: f64 = 3.0 * LINE_HEIGHT;
+const FOOTER_HEIGHT: f64 = LINE_HEIGHT;
+// Gap between columns
+const PADDING: f64 = 6.0;
+
+struct Sheet {
+    title: String,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    rows_per_page: Cell<usize>,
+}
+
+// Text clipped to the column so long values don't run into the next one
+fn cell(cr: &cairo::Context, text: &str, x: f64, y: f64, width: f64) -> Result<(), cairo::Error> {
+    cr.save()?;
+    cr.rectangle(x, y - LINE_HEIGHT, width - PADDING, LINE_HEIGHT * 1.5);
+    cr.clip();
+    cr.move_to(x, y);
+    cr.show_text(text)?;
+    cr.restore()
+}
+
+fn draw_page(sheet: &Sheet, context: &gtk::PrintContext, page: usize) -> Result<(), cairo::Error> {
+    let cr = context.cairo_context();
+    let width = context.width();
+    let column_width = width / sheet.headers.len().max(1) as f64;
+    cr.set_source_rgb(0.0, 0.0, 0.0);
+
+    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Bold);
+    cr.set_font_size(TITLE_SIZE);
+    cr.move_to(0.0, LINE_HEIGHT);
+    cr.show_text(&sheet.title)?;
+
+    cr.set_font_size(FONT_SIZE);
+    let mut y = HEADER_HEIGHT - LINE_HEIGHT / 2.0;
+    for (i, header) in sheet.headers.iter().enumerate() {
+        cell(&cr, header, i as f64 * column_width, y, column_width)?;
+    }
+    cr.set_line_width(0.5);
+    cr.move_to(0.0, y + 4.0);
+    cr.line_to(width, y + 4.0);
+    cr.stroke()?;
+
+    cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+    let per_page = sheet.rows_per_page.get();
+    for row in sheet.rows.iter().skip(page * per_page).take(per_page) {
+        y += LINE_HEIGHT;
+        for (i, text) in row.iter().enumerate() {
+            cell(&cr, text, i as f64 * column_width, y, column_width)?;
+        }
+    }
+
+    let pages = sheet.rows.len().div_ceil(per_page).max(1);
+    cr.move_to(0.0, context.height() - FOOTER_HEIGHT / 4.0);
+    cr.show_text(&tr!("Page {} of {}", page + 1, pages))
+}
+
+/// Lays a table out over as many pages as it needs, with the title and
+/// column headings repeated on each. With `pdf_path` the pages are written
+/// to that file; otherwise the print dialog is shown.
+pub fn print_table(
+    parent: &impl IsA<gtk::Window>,
+    title: &str,
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+    pdf_path: Option<PathBuf>,
+) -> Result<(), String> {
+    let sheet = Rc::new(Sheet {
+        title: title.to_string(),
+        headers,
+        rows,
+        rows_per_page: Cell::new(1),
+    });
+
+    let operation = gtk::PrintOperation::new();
+    operation.set_job_name(title);
+    operation.set_unit(gtk::Unit::Points);
+    operation.set_embed_page_setup(true);
+
+    operation.connect_begin_print(glib::clone!(
+        #[strong]
+        sheet,
+        move |operation, context| {
+            let usable = context.height() - HEADER_HEIGHT - FOOTER_HEIGHT;
+            let per_page = ((usable / LINE_HEIGHT) as usize).max(1);
+            sheet.rows_per_page.set(per_page);
+            operation.set_n_pages(sheet.rows.len().div_ceil(per_page).max(1) as i32);
+        }
+    ));
+    operation.connect_draw_page(glib::clone!(
+        #[strong]
+        sheet,
+        move |_, context, page| {
+            if let Err(e) = draw_page(&sheet, context, page as usize) {
+                tracing::error!("Failed to draw page {}: {}", page + 1, e);
+            }
+        }
+    ));
+
+    let action = match pdf_path {
+        Some(path) => {
+            operation.set_export_filename(path);
+            gtk::PrintOperationAction::Export
+        }
+        None => gtk::PrintOperationAction::PrintDialog,
+    };
+    operation.run(action, Some(parent)).map(|_| ()).map_err(|e| e.to_string())
+}
@@ -0,0 +1,84 @@
+use chrono::{DateTime, Months, Utc};
+
+use crate::i18n::tr;
+use crate::sync::LibraryCommand;
+use crate::transactions::TransactionKind;
+use crate::Library;
+
+impl Library {
+    /// Irreversibly removes a member's personal data: name, contact details,
+    /// external ID, reading history and review texts. The member ID stays,
+    /// so transactions, fines and star ratings still count towards
+    /// statistics. Deleted members are anonymized in the trash.
+    pub fn anonymize_member(&mut self, member_id: u32) -> Result<(), String> {
+        let now = self.clock.now();
+        let member = self.member_or_trashed_mut(member_id).ok_or_else(|| tr!("Member not found"))?;
+        if member.anonymized_at.is_some() {
+            return Err(tr!("Member has already been anonymized"));
+        }
+        if !member.items.is_empty() {
+            return Err(tr!("Member still has items on loan"));
+        }
+
+        member.name = format!("Anonymized member {}", member_id);
+        member.email = None;
+        member.external_id = None;
+        member.keep_history = false;
+        member.history.clear();
+        member.receipts.clear();
+        member.challenge = None;
+        member.anonymized_at = Some(now);
+        for item in self.items.values_mut() {
+            for review in item.reviews.iter_mut().filter(|r| r.member_id == Some(member_id)) {
+                review.text.clear();
+            }
+        }
+        let open_holds: Vec<u32> = self.holds.iter().filter(|h| h.member_id == member_id && h.is_active()).map(|h| h.id).collect();
+        for hold_id in open_holds {
+            let _ = self.cancel_hold(hold_id);
+        }
+
+        self.log_transaction(TransactionKind::MemberAnonymized, 0, member_id);
+        self.record_command(LibraryCommand::AnonymizeMember { member_id });
+        self.dirty = true;
+        // Reviews on any item may have lost their text
+        self.reloaded();
+        Ok(())
+    }
+
+    fn last_activity(&self, member_id: u32) -> Option<DateTime<Utc>> {
+        self.transactions
+            .iter()
+            .rev()
+            .find(|tx| tx.member_id == member_id)
+            .map(|tx| tx.at)
+    }
+
+    /// Members with no loans and no transactions in the last `years` years,
+    /// deleted ones included. Members without any transactions are left
+    /// alone, as there is no way to tell how long they have been inactive.
+    pub fn inactive_members(&self, years: u32) -> Vec<u32> {
+        let Some(cutoff) = self.clock.now().checked_sub_months(Months::new(years * 12)) else {
+            return Vec::new();
+        };
+        let mut ids: Vec<u32> = self
+            .members
+            .values()
+            .chain(self.trash.members.values().map(|t| &t.record))
+            .filter(|m| m.anonymized_at.is_none() && m.items.is_empty())
+            .filter(|m| self.last_activity(m.id).is_some_and(|at| at < cutoff))
+            .map(|m| m.id)
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// The retention policy: anonymizes every member inactive for `years`
+    /// years. Returns how many were anonymized.
+    pub fn anonymize_inactive(&mut self, years: u32) -> usize {
+        self.inactive_members(years)
+            .into_iter()
+            .filter(|&id| self.anonymize_member(id).is_ok())
+            .count()
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use chrono::{DateTime, Utc, Weekday};
+use serde::{Deserialize, Serialize};
+
+use crate::calendar::ClosureCalendar;
+use crate::config::Config;
+use crate::fines::Amnesty;
+use crate::memberships::{MembershipPolicy, MembershipType};
+use crate::migrate;
+use crate::Library;
+
+/// The settings of `Config` that are the same on every desk of a library.
+/// Paths, sync and servers are left out as they belong to one machine, and
+/// so is the mail password.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SharedConfig {
+    pub backup_interval_minutes: u32,
+    pub backup_retention: usize,
+    pub anonymize_after_years: u32,
+    pub idle_lock_minutes: u32,
+    pub smtp_host: String,
+    pub smtp_port: u16,
+    pub smtp_username: String,
+    pub mail_from: String,
+    pub report_recipients: Vec<String>,
+    pub report_weekday: Weekday,
+    pub report_hour: u32,
+    pub sru_url: String,
+    pub website_url: String,
+}
+
+impl SharedConfig {
+    fn of(config: &Config) -> SharedConfig {
+        SharedConfig {
+            backup_interval_minutes: config.backup_interval_minutes,
+            backup_retention: config.backup_retention,
+            anonymize_after_years: config.anonymize_after_years,
+            idle_lock_minutes: config.idle_lock_minutes,
+            smtp_host: config.smtp_host.clone(),
+            smtp_port: config.smtp_port,
+            smtp_username: config.smtp_username.clone(),
+            mail_from: config.mail_from.clone(),
+            report_recipients: config.report_recipients.clone(),
+            report_weekday: config.report_weekday,
+            report_hour: config.report_hour,
+            sru_url: config.sru_url.clone(),
+            website_url: config.website_url.clone(),
+        }
+    }
+
+    fn apply(self, config: &mut Config) {
+        config.backup_interval_minutes = self.backup_interval_minutes;
+        config.backup_retention = self.backup_retention;
+        config.anonymize_after_years = self.anonymize_after_years;
+        config.idle_lock_minutes = self.idle_lock_minutes;
+        config.smtp_host = self.smtp_host;
+        config.smtp_port = self.smtp_port;
+        config.smtp_username = self.smtp_username;
+        config.mail_from = self.mail_from;
+        config.report_recipients = self.report_recipients;
+        config.report_weekday = self.report_weekday;
+        config.report_hour = self.report_hour;
+        config.sru_url = self.sru_url;
+        config.website_url = self.website_url;
+    }
+}
+
+/// Loan policies, opening days, fine rules and shared settings, written to
+/// one file so every branch of a library can lend on the same terms.
+#[derive(Deserialize, Serialize)]
+pub struct Profile {
+    pub exported_at: DateTime<Utc>,
+    pub exported_by: String,
+    pub policies: HashMap<MembershipType, MembershipPolicy>,
+    pub calendar: ClosureCalendar,
+    pub hourly_formats: BTreeMap<String, u32>,
+    pub hold_pickup_days: u32,
+    pub waiver_approval_cents: u64,
+    pub fine_grace_days: u32,
+    pub max_fine_cents: u64,
+    pub amnesty: Option<Amnesty>,
+    pub config: SharedConfig,
+}
+
+impl Library {
+    pub fn export_profile(&self, config: &Config, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let profile = Profile {
+            exported_at: self.clock.now(),
+            exported_by: self.current_user.clone(),
+            policies: self.policies.clone(),
+            calendar: self.calendar.clone(),
+            hourly_formats: self.hourly_formats.clone(),
+            hold_pickup_days: self.hold_pickup_days,
+            waiver_approval_cents: self.waiver_approval_cents,
+            fine_grace_days: self.fine_grace_days,
+            max_fine_cents: self.max_fine_cents,
+            amnesty: self.amnesty,
+            config: SharedConfig::of(config),
+        };
+        migrate::PROFILE.write_pretty(File::create(path)?, &profile)?;
+        tracing::info!("Configuration profile exported to {}", path.display());
+        Ok(())
+    }
+
+    /// Replaces the policies and fine rules with those of the profile at
+    /// `path`, and its shared settings in `config`, which is saved.
+    pub fn import_profile(&mut self, config: &mut Config, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        self.only_at_server()?;
+        let profile: Profile = migrate::PROFILE.read(BufReader::new(File::open(path)?))?;
+        self.policies = profile.policies;
+        self.calendar = profile.calendar;
+        self.hourly_formats = profile.hourly_formats;
+        self.hold_pickup_days = profile.hold_pickup_days;
+        self.waiver_approval_cents = profile.waiver_approval_cents;
+        self.fine_grace_days = profile.fine_grace_days;
+        self.max_fine_cents = profile.max_fine_cents;
+        self.amnesty = profile.amnesty;
+        self.dirty = true;
+        profile.config.apply(config);
+        config.save()?;
+        tracing::info!(
+            exported_by = %profile.exported_by,
+            "Configuration profile from {} imported from {}",
+            profile.exported_at,
+            path.display()
+        );
+        Ok(())
+    }
+}
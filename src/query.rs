@@ -0,0 +1,357 @@
+use crate::i18n::tr;
+use crate::search::normalize;
+use crate::LiItem;
+
+/// Fields a query term can be scoped to, e.g. `author:tolkien`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Field {
+    Title,
+    Author,
+    Year,
+    Format,
+    Isbn,
+    Tag,
+    Description,
+    Branch,
+    CallNumber,
+    Copies,
+    Available,
+    Id,
+}
+
+impl Field {
+    // Every name a field is written as; the first is the one shown in help
+    const NAMES: [(&'static str, Field); 15] = [
+        ("title", Field::Title),
+        ("author", Field::Author),
+        ("year", Field::Year),
+        ("format", Field::Format),
+        ("isbn", Field::Isbn),
+        ("tag", Field::Tag),
+        ("tags", Field::Tag),
+        ("desc", Field::Description),
+        ("description", Field::Description),
+        ("branch", Field::Branch),
+        ("call", Field::CallNumber),
+        ("copies", Field::Copies),
+        ("avail", Field::Available),
+        ("available", Field::Available),
+        ("id", Field::Id),
+    ];
+
+    fn from_name(name: &str) -> Option<Field> {
+        Field::NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, f)| *f)
+    }
+
+    fn is_numeric(self) -> bool {
+        matches!(self, Field::Year | Field::Copies | Field::Available | Field::Id)
+    }
+
+    fn number(self, item: &LiItem) -> u32 {
+        match self {
+            Field::Year => item.year,
+            Field::Copies => item.copies,
+            Field::Available => item.avail_copies,
+            _ => item.id,
+        }
+    }
+
+    // The field's text; tags are matched one by one
+    fn texts(self, item: &LiItem) -> Vec<String> {
+        match self {
+            Field::Title => vec![item.title.to_string()],
+            Field::Author => vec![item.author.clone().unwrap_or_default()],
+            Field::Format => vec![item.format.clone()],
+            Field::Isbn => vec![item.isbn.clone().unwrap_or_default()],
+            Field::Tag => item.tags.clone(),
+            Field::Description => vec![item.desc.clone()],
+            Field::Branch => vec![item.branch.clone()],
+            Field::CallNumber => vec![item.call_number.clone()],
+            _ => vec![self.number(item).to_string()],
+        }
+    }
+}
+
+/// Field names for the search help, e.g. in a tooltip.
+pub fn field_names() -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for (name, field) in Field::NAMES {
+        if !Field::NAMES.iter().any(|(n, f)| *f == field && names.contains(n)) {
+            names.push(name);
+        }
+    }
+    names.join(", ")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+}
+
+impl Op {
+    fn holds(self, a: u32, b: u32) -> bool {
+        match self {
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Eq => a == b,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Test {
+    // Normalized text the field has to contain
+    Contains(String),
+    // Normalized text the field has to equal
+    Equals(String),
+    Compare(Op, u32),
+    // Either end may be open, as in `year:..1900`
+    Range(Option<u32>, Option<u32>),
+}
+
+/// A parsed catalog query, e.g. `author:tolkien AND year:1950..1960 AND
+/// avail>0`. Terms next to each other must all match; `OR`, `NOT` and
+/// parentheses work as usual. A term without a field matches the title,
+/// author or tags.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    Term(Option<Field>, Test),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                // Quotes are kept so terms can tell a quoted phrase from a
+                // field name
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if !quoted && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+                    if c == '"' {
+                        quoted = !quoted;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                if quoted {
+                    return Err(tr!("Missing closing quote in {}", word));
+                }
+                tokens.push(match word.as_str() {
+                    "AND" | "&&" => Token::And,
+                    "OR" | "||" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+fn parse_number(field: &str, text: &str) -> Result<u32, String> {
+    text.trim().parse().map_err(|_| tr!("{} needs a number, not \"{}\"", field, text.trim()))
+}
+
+fn parse_term(word: &str) -> Result<Query, String> {
+    // A leading minus negates the term, as in `-tag:horror`
+    if let Some(rest) = word.strip_prefix('-').filter(|r| !r.is_empty()) {
+        return Ok(Query::Not(Box::new(parse_term(rest)?)));
+    }
+    let unquote = |text: &str| text.replace('"', "");
+
+    let split = word
+        .char_indices()
+        .take_while(|(_, c)| *c != '"')
+        .find(|(_, c)| matches!(c, ':' | '<' | '>' | '='))
+        .map(|(i, _)| i);
+    let Some(at) = split.filter(|&i| i > 0) else {
+        return Ok(Query::Term(None, Test::Contains(normalize(&unquote(word)))));
+    };
+    let (name, rest) = word.split_at(at);
+    let field = Field::from_name(name).ok_or_else(|| tr!("Unknown field \"{}\"; use one of {}", name, field_names()))?;
+
+    let (op, value) = if let Some(v) = rest.strip_prefix(">=") {
+        (Some(Op::Ge), v)
+    } else if let Some(v) = rest.strip_prefix("<=") {
+        (Some(Op::Le), v)
+    } else if let Some(v) = rest.strip_prefix('>') {
+        (Some(Op::Gt), v)
+    } else if let Some(v) = rest.strip_prefix('<') {
+        (Some(Op::Lt), v)
+    } else if let Some(v) = rest.strip_prefix('=') {
+        (Some(Op::Eq), v)
+    } else {
+        (None, &rest[1..])
+    };
+    let value = unquote(value);
+    if value.trim().is_empty() {
+        return Err(tr!("Nothing to search for after {}", word));
+    }
+
+    let test = match (op, field.is_numeric()) {
+        (Some(op), true) => Test::Compare(op, parse_number(name, &value)?),
+        (Some(Op::Eq), false) => Test::Equals(normalize(&value)),
+        (Some(_), false) => return Err(tr!("{} can't be compared with < or >", name)),
+        (None, true) => match value.split_once("..") {
+            Some((from, to)) => {
+                let bound = |text: &str| if text.trim().is_empty() { Ok(None) } else { parse_number(name, text).map(Some) };
+                Test::Range(bound(from)?, bound(to)?)
+            }
+            None => Test::Compare(Op::Eq, parse_number(name, &value)?),
+        },
+        (None, false) => Test::Contains(normalize(&value)),
+    };
+    Ok(Query::Term(Some(field), test))
+}
+
+// Recursive descent over the tokens: OR binds loosest, then AND (written
+// or implied), then NOT
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query, String> {
+        let mut query = self.and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, String> {
+        let mut query = self.not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Word(_)) => {}
+                _ => return Ok(query),
+            }
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Query, String> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => Err(tr!("Missing closing parenthesis")),
+                }
+            }
+            Some(Token::Word(word)) => parse_term(&word),
+            Some(Token::Close) => Err(tr!("Unexpected closing parenthesis")),
+            Some(Token::And | Token::Or) => Err(tr!("AND and OR need a term on both sides")),
+            Some(Token::Not) => unreachable!(),
+            None => Err(tr!("The query ends too early")),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(text: &str) -> Result<Query, String> {
+        let tokens = tokenize(text)?;
+        if tokens.is_empty() {
+            return Err(tr!("Enter something to search for"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.or()?;
+        match parser.peek() {
+            None => Ok(query),
+            Some(Token::Close) => Err(tr!("Unexpected closing parenthesis")),
+            Some(_) => Err(tr!("AND and OR need a term on both sides")),
+        }
+    }
+
+    pub fn matches(&self, item: &LiItem) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(item) && b.matches(item),
+            Query::Or(a, b) => a.matches(item) || b.matches(item),
+            Query::Not(q) => !q.matches(item),
+            Query::Term(None, test) => {
+                [Field::Title, Field::Author, Field::Tag].iter().any(|f| Query::test(*f, test, item))
+            }
+            Query::Term(Some(field), test) => Query::test(*field, test, item),
+        }
+    }
+
+    fn test(field: Field, test: &Test, item: &LiItem) -> bool {
+        match test {
+            Test::Contains(text) => field.texts(item).iter().any(|t| normalize(t).contains(text.as_str())),
+            Test::Equals(text) => field.texts(item).iter().any(|t| normalize(t) == *text),
+            Test::Compare(op, n) => op.holds(field.number(item), *n),
+            Test::Range(from, to) => {
+                let n = field.number(item);
+                from.is_none_or(|from| n >= from) && to.is_none_or(|to| n <= to)
+            }
+        }
+    }
+}
+
+/// Whether `text` uses the query language, rather than being plain words
+/// for the fuzzy title search. Operators have to be upper case, so "war and
+/// peace" stays a title search.
+pub fn is_query(text: &str) -> bool {
+    text.contains([':', '<', '>', '=', '(']) || text.split_whitespace().any(|w| matches!(w, "AND" | "OR" | "NOT"))
+}
+
+/// IDs of the items matching `query`, in order.
+pub fn run<'a>(items: impl Iterator<Item = &'a LiItem>, query: &Query) -> Vec<u32> {
+    let mut ids: Vec<u32> = items.filter(|item| query.matches(item)).map(|item| item.id).collect();
+    ids.sort_unstable();
+    ids
+}
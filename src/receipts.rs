@@ -0,0 +1,82 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::fines::format_money;
+use crate::i18n::tr;
+use crate::transactions::TransactionKind;
+use crate::Library;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReceiptKind {
+    Checkout,
+    Return,
+    Payment,
+}
+
+impl ReceiptKind {
+    pub fn label(self) -> String {
+        match self {
+            ReceiptKind::Checkout => tr!("Checkout"),
+            ReceiptKind::Return => tr!("Return"),
+            ReceiptKind::Payment => tr!("Payment"),
+        }
+    }
+}
+
+/// What a member was handed at the desk, kept as it was then so it can be
+/// printed again later even if the item has since been renamed.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Receipt {
+    // Counts up per member
+    pub id: u32,
+    pub at: DateTime<Utc>,
+    pub kind: ReceiptKind,
+    pub item_id: u32,
+    pub title: Arc<str>,
+    // E.g. "Due 2024-05-01" or "Paid 2.50"
+    pub detail: String,
+    pub by: String,
+}
+
+impl Library {
+    /// Keeps a receipt for checkouts, returns and fine payments, called as
+    /// they are logged. Other transactions don't get one.
+    pub(crate) fn record_receipt(&mut self, kind: TransactionKind, item_id: u32, member_id: u32, amount_cents: u64) {
+        let title = self.items.get(&item_id).map_or_else(|| Arc::from(""), |item| item.title.clone());
+        let Some(member) = self.members.get_mut(&member_id) else { return };
+        let (kind, detail) = match kind {
+            TransactionKind::Issue => {
+                let due = member.items.get(&item_id).map(|inst| inst.due_text()).unwrap_or_default();
+                (ReceiptKind::Checkout, tr!("Due {}", due))
+            }
+            TransactionKind::Return => (ReceiptKind::Return, tr!("Returned")),
+            TransactionKind::FinePaid => (ReceiptKind::Payment, tr!("Fine paid: {}", format_money(amount_cents))),
+            _ => return,
+        };
+        member.receipts.push(Receipt {
+            id: member.receipts.last().map_or(1, |r| r.id + 1),
+            at: self.clock.now(),
+            kind,
+            item_id,
+            title,
+            detail,
+            by: self.current_user.clone(),
+        });
+    }
+}
+
+pub fn receipt_headers() -> Vec<String> {
+    vec![tr!("Date"), tr!("Item"), tr!("Title"), tr!("Details"), tr!("Staff")]
+}
+
+pub fn receipt_rows(receipt: &Receipt) -> Vec<Vec<String>> {
+    vec![vec![
+        receipt.at.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        receipt.item_id.to_string(),
+        receipt.title.to_string(),
+        receipt.detail.clone(),
+        receipt.by.clone(),
+    ]]
+}
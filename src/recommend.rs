@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::transactions::TransactionKind;
+use crate::Library;
+
+const MAX_RECOMMENDATIONS: usize = 10;
+// What one shared tag is worth relative to one co-borrowing member
+const GENRE_WEIGHT: f32 = 0.5;
+
+fn top<S: Copy + PartialOrd>(scores: HashMap<u32, S>) -> Vec<u32> {
+    let mut ranked: Vec<(u32, S)> = scores.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    ranked.into_iter().take(MAX_RECOMMENDATIONS).map(|(id, _)| id).collect()
+}
+
+impl Library {
+    // Every item each member has ever borrowed, from the issue log.
+    fn borrowing_history(&self) -> HashMap<u32, HashSet<u32>> {
+        let mut history: HashMap<u32, HashSet<u32>> = HashMap::new();
+        for tx in self.transactions.iter().filter(|tx| tx.kind == TransactionKind::Issue) {
+            history.entry(tx.member_id).or_default().insert(tx.item_id);
+        }
+        history
+    }
+
+    /// "Members who borrowed this also borrowed": other items ranked by how
+    /// many members borrowed both.
+    pub fn also_borrowed(&self, item_id: u32) -> Vec<u32> {
+        let mut scores: HashMap<u32, u32> = HashMap::new();
+        for items in self.borrowing_history().values().filter(|items| items.contains(&item_id)) {
+            for &other in items.iter().filter(|&&other| other != item_id) {
+                *scores.entry(other).or_default() += 1;
+            }
+        }
+        scores.retain(|id, _| self.items.contains_key(id));
+        top(scores)
+    }
+
+    /// Items a member hasn't borrowed yet, scored by the members whose
+    /// history overlaps theirs (weighted by the size of the overlap) plus
+    /// how often the item's tags appear in what they have borrowed.
+    pub fn recommend_for(&self, member_id: u32) -> Vec<u32> {
+        let history = self.borrowing_history();
+        let Some(own) = history.get(&member_id) else { return Vec::new() };
+
+        let mut scores: HashMap<u32, f32> = HashMap::new();
+        for (_, items) in history.iter().filter(|(id, _)| **id != member_id) {
+            let overlap = items.intersection(own).count();
+            if overlap == 0 {
+                continue;
+            }
+            for &item in items.difference(own) {
+                *scores.entry(item).or_default() += overlap as f32;
+            }
+        }
+
+        let mut tag_counts: HashMap<&str, usize> = HashMap::new();
+        for item in own.iter().filter_map(|id| self.items.get(id)) {
+            for tag in &item.tags {
+                *tag_counts.entry(tag).or_default() += 1;
+            }
+        }
+        if !tag_counts.is_empty() {
+            for item in self.items.values().filter(|item| !own.contains(&item.id)) {
+                let affinity: f32 = item
+                    .tags
+                    .iter()
+                    .filter_map(|tag| tag_counts.get(tag.as_str()))
+                    .map(|&count| count as f32 / own.len() as f32)
+                    .sum();
+                if affinity > 0.0 {
+                    *scores.entry(item.id).or_default() += affinity * GENRE_WEIGHT;
+                }
+            }
+        }
+
+        scores.retain(|id, _| self.items.contains_key(id));
+        top(scores)
+    }
+
+    pub fn titles_of(&self, ids: &[u32]) -> String {
+        ids.iter()
+            .filter_map(|id| self.items.get(id))
+            .map(|item| format!("{} ({})", item.title, item.id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, Timelike, Utc};
+
+use crate::config::Config;
+use crate::holds::HoldStatus;
+use crate::fines::format_money;
+use crate::i18n::tr;
+use crate::mail::{self, MailAttachment};
+use crate::transactions::TransactionKind;
+use crate::Library;
+
+/// How circulation figures are grouped in a report.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Period {
+    Day,
+    Week,
+    Month,
+}
+
+impl Period {
+    pub const ALL: [Period; 3] = [Period::Day, Period::Week, Period::Month];
+
+    pub fn label(self) -> String {
+        match self {
+            Period::Day => tr!("Daily"),
+            Period::Week => tr!("Weekly"),
+            Period::Month => tr!("Monthly"),
+        }
+    }
+
+    // First day of the period containing `date`; weeks start on Monday
+    fn start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => date,
+            Period::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Period::Month => date.with_day(1).unwrap(),
+        }
+    }
+
+    fn next(self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Period::Day => start + Days::new(1),
+            Period::Week => start + Days::new(7),
+            Period::Month => start + Months::new(1),
+        }
+    }
+}
+
+/// Circulation in one day, week or month.
+#[derive(Clone, Default)]
+pub struct PeriodSummary {
+    pub start: NaiveDate,
+    pub checkouts: u32,
+    pub returns: u32,
+    pub renewals: u32,
+    pub new_members: u32,
+    pub fines_collected_cents: u64,
+    pub fines_waived_cents: u64,
+    // Not fined thanks to the grace period, an amnesty or the fine cap
+    pub fines_forgiven_cents: u64,
+}
+
+/// All the summaries added up. The start is left at its default.
+pub fn total(summaries: &[PeriodSummary]) -> PeriodSummary {
+    let mut total = PeriodSummary::default();
+    for s in summaries {
+        total.checkouts += s.checkouts;
+        total.returns += s.returns;
+        total.renewals += s.renewals;
+        total.new_members += s.new_members;
+        total.fines_collected_cents += s.fines_collected_cents;
+        total.fines_waived_cents += s.fines_waived_cents;
+        total.fines_forgiven_cents += s.fines_forgiven_cents;
+    }
+    total
+}
+
+/// Column headings for `report_rows`.
+pub fn report_headers() -> Vec<String> {
+    vec![
+        tr!("Period"),
+        tr!("Checkouts"),
+        tr!("Returns"),
+        tr!("Renewals"),
+        tr!("New Members"),
+        tr!("Fines Collected"),
+        tr!("Fines Waived"),
+        tr!("Fines Forgiven"),
+    ]
+}
+
+/// The summaries as text, one row per period and a total at the end, for
+/// printing.
+pub fn report_rows(summaries: &[PeriodSummary]) -> Vec<Vec<String>> {
+    let mut rows: Vec<Vec<String>> =
+        summaries.iter().map(|s| row(s.start.format("%Y-%m-%d").to_string(), s)).collect();
+    rows.push(row(tr!("Total"), &total(summaries)));
+    rows
+}
+
+fn row(period: String, summary: &PeriodSummary) -> Vec<String> {
+    vec![
+        period,
+        summary.checkouts.to_string(),
+        summary.returns.to_string(),
+        summary.renewals.to_string(),
+        summary.new_members.to_string(),
+        format_money(summary.fines_collected_cents),
+        format_money(summary.fines_waived_cents),
+        format_money(summary.fines_forgiven_cents),
+    ]
+}
+
+// Columns padded to line up in a monospaced font, numbers to the right
+pub(crate) fn text_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let widths: Vec<usize> = (0..headers.len())
+        .map(|i| rows.iter().map(|r| r[i].chars().count()).chain([headers[i].chars().count()]).max().unwrap_or(0))
+        .collect();
+    let line = |cells: &[String]| {
+        let mut text = format!("{:<1$}", cells[0], widths[0]);
+        for (cell, width) in cells.iter().zip(&widths).skip(1) {
+            text.push_str(&format!("  {:>1$}", cell, width));
+        }
+        text.push('\n');
+        text
+    };
+    let mut text = line(headers);
+    for row in rows {
+        text.push_str(&line(row));
+    }
+    text
+}
+
+/// A report ready to be mailed.
+pub struct EmailReport {
+    pub subject: String,
+    pub body: String,
+    pub attachment: MailAttachment,
+}
+
+/// Counts for the header bar, at a glance.
+#[derive(Clone, Copy)]
+pub struct QuickStats {
+    pub available: u32,
+    pub on_loan: u32,
+    pub overdue: u32,
+    pub awaiting_pickup: u32,
+}
+
+/// Whether the weekly report should go out at `now`: mail is set up, there
+/// are recipients, it is the configured day and hour or later, and it
+/// hasn't been sent yet today.
+pub fn weekly_report_due(config: &Config, now: DateTime<Local>) -> bool {
+    mail::is_configured(config)
+        && !config.report_recipients.is_empty()
+        && now.weekday() == config.report_weekday
+        && now.hour() >= config.report_hour
+        && config.report_last_sent != Some(now.date_naive())
+}
+
+// Reports go by the library's own calendar days, not UTC ones
+fn local_date(at: DateTime<Utc>) -> NaiveDate {
+    at.with_timezone(&Local).date_naive()
+}
+
+impl Library {
+    /// Copies on the shelf, copies on loan, loans overdue and holds
+    /// waiting on the hold shelf, right now.
+    pub fn quick_stats(&self) -> QuickStats {
+        QuickStats {
+            available: self.items.values().map(|i| i.avail_copies).sum(),
+            on_loan: self.members.values().map(|m| m.items.len() as u32).sum(),
+            overdue: self.overdue_count(),
+            awaiting_pickup: self.holds.iter().filter(|h| h.status == HoldStatus::OnShelf).count() as u32,
+        }
+    }
+
+    /// Circulation from `from` to `to` (both included), one summary per
+    /// period and oldest first. Periods without any activity are included,
+    /// so the report has no gaps. New members are only counted for members
+    /// whose sign-up date is known.
+    pub fn circulation_summary(&self, from: NaiveDate, to: NaiveDate, period: Period) -> Vec<PeriodSummary> {
+        let mut buckets: BTreeMap<NaiveDate, PeriodSummary> = BTreeMap::new();
+        let mut start = period.start(from);
+        while start <= to {
+            buckets.insert(start, PeriodSummary { start, ..PeriodSummary::default() });
+            start = period.next(start);
+        }
+
+        let key = |at: DateTime<Utc>| {
+            let date = local_date(at);
+            (from..=to).contains(&date).then(|| period.start(date))
+        };
+        for tx in &self.transactions {
+            let Some(summary) = key(tx.at).and_then(|k| buckets.get_mut(&k)) else { continue };
+            match tx.kind {
+                TransactionKind::Issue => summary.checkouts += 1,
+                TransactionKind::Return => summary.returns += 1,
+                TransactionKind::Renew => summary.renewals += 1,
+                TransactionKind::FinePaid => summary.fines_collected_cents += tx.amount_cents,
+                TransactionKind::FineWaived => summary.fines_waived_cents += tx.amount_cents,
+                TransactionKind::FineForgiven => summary.fines_forgiven_cents += tx.amount_cents,
+                _ => {}
+            }
+        }
+        for joined_at in self.members.values().filter_map(|m| m.joined_at) {
+            if let Some(summary) = key(joined_at).and_then(|k| buckets.get_mut(&k)) {
+                summary.new_members += 1;
+            }
+        }
+
+        buckets.into_values().collect()
+    }
+
+    /// Checkouts of `item_id` on each day from `from` to `to` (both
+    /// included), oldest first.
+    pub fn item_checkouts_by_day(&self, item_id: u32, from: NaiveDate, to: NaiveDate) -> Vec<u32> {
+        let mut days = vec![0; ((to - from).num_days() + 1).max(0) as usize];
+        for tx in self.transactions.iter().filter(|tx| tx.kind == TransactionKind::Issue && tx.item_id == item_id) {
+            let date = local_date(tx.at);
+            if (from..=to).contains(&date) {
+                days[(date - from).num_days() as usize] += 1;
+            }
+        }
+        days
+    }
+
+    /// Checkouts since `from` by the local hour of day they happened in.
+    pub fn checkouts_by_hour(&self, from: NaiveDate) -> [u32; 24] {
+        let mut hours = [0; 24];
+        for tx in self.transactions.iter().filter(|tx| tx.kind == TransactionKind::Issue) {
+            let at = tx.at.with_timezone(&Local);
+            if at.date_naive() >= from {
+                hours[at.hour() as usize] += 1;
+            }
+        }
+        hours
+    }
+
+    /// The `limit` genres (tags) with the most checkouts since `from`, most
+    /// borrowed first. An item with several tags counts for each of them.
+    pub fn top_genres(&self, from: NaiveDate, limit: usize) -> Vec<(String, u32)> {
+        let mut counts: BTreeMap<&str, u32> = BTreeMap::new();
+        for tx in self.transactions.iter().filter(|tx| tx.kind == TransactionKind::Issue && local_date(tx.at) >= from) {
+            let Some(item) = self.item_or_trashed(tx.item_id) else { continue };
+            for tag in &item.tags {
+                *counts.entry(tag).or_insert(0) += 1;
+            }
+        }
+        let mut genres: Vec<(String, u32)> = counts.into_iter().map(|(tag, n)| (tag.to_string(), n)).collect();
+        genres.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        genres.truncate(limit);
+        genres
+    }
+
+    pub fn export_circulation_csv(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        period: Period,
+        path: &Path,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        self.write_circulation_csv(from, to, period, File::create(path)?)?;
+        Ok(())
+    }
+
+    fn write_circulation_csv<W: Write>(
+        &self,
+        from: NaiveDate,
+        to: NaiveDate,
+        period: Period,
+        writer: W,
+    ) -> Result<W, Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_writer(writer);
+        wtr.write_record([
+            "period_start",
+            "checkouts",
+            "returns",
+            "renewals",
+            "new_members",
+            "fines_collected",
+            "fines_waived",
+            "fines_forgiven",
+        ])?;
+        for s in self.circulation_summary(from, to, period) {
+            wtr.write_record([
+                s.start.format("%Y-%m-%d").to_string(),
+                s.checkouts.to_string(),
+                s.returns.to_string(),
+                s.renewals.to_string(),
+                s.new_members.to_string(),
+                format!("{}.{:02}", s.fines_collected_cents / 100, s.fines_collected_cents % 100),
+                format!("{}.{:02}", s.fines_waived_cents / 100, s.fines_waived_cents % 100),
+                format!("{}.{:02}", s.fines_forgiven_cents / 100, s.fines_forgiven_cents % 100),
+            ])?;
+        }
+        Ok(wtr.into_inner().map_err(|e| e.into_error())?)
+    }
+
+    /// The circulation report for the week ending on `to`, day by day, as
+    /// an email with the figures as a CSV attachment.
+    pub fn weekly_report(&self, to: NaiveDate) -> Result<EmailReport, Box<dyn std::error::Error>> {
+        let from = to - Days::new(6);
+        let summaries = self.circulation_summary(from, to, Period::Day);
+        let subject = tr!("Weekly circulation report, {} to {}", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+        let mut body = tr!("Circulation from {} to {}:", from.format("%Y-%m-%d"), to.format("%Y-%m-%d"));
+        body.push_str("\n\n");
+        body.push_str(&text_table(&report_headers(), &report_rows(&summaries)));
+        let csv = self.write_circulation_csv(from, to, Period::Day, Vec::new())?;
+        Ok(EmailReport {
+            subject,
+            body,
+            attachment: MailAttachment {
+                file_name: format!("circulation-{}.csv", to.format("%Y-%m-%d")),
+                content_type: "text/csv",
+                data: csv,
+            },
+        })
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Days, Duration, Local, NaiveTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::Library;
+
+// Reserve loans of a day or more are due back at this local hour
+const RESERVE_DUE_HOUR: u32 = 10;
+
+/// How long an item on course reserve may be borrowed for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ReserveLoan {
+    TwoHours,
+    Overnight,
+    ThreeDays,
+}
+
+impl ReserveLoan {
+    pub const ALL: [ReserveLoan; 3] = [ReserveLoan::TwoHours, ReserveLoan::Overnight, ReserveLoan::ThreeDays];
+
+    pub fn label(self) -> String {
+        match self {
+            ReserveLoan::TwoHours => tr!("2 hours"),
+            ReserveLoan::Overnight => tr!("Overnight"),
+            ReserveLoan::ThreeDays => tr!("3 days"),
+        }
+    }
+
+    /// The loan length for reserves lent by the hour.
+    pub fn hours(self) -> Option<u32> {
+        match self {
+            ReserveLoan::TwoHours => Some(2),
+            ReserveLoan::Overnight | ReserveLoan::ThreeDays => None,
+        }
+    }
+}
+
+/// An item set aside for a course. While on reserve it is only lent for
+/// the reserve's short loan period and can't be renewed.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Reserve {
+    pub item_id: u32,
+    pub course: String,
+    pub teacher: String,
+    pub loan: ReserveLoan,
+    pub placed_at: DateTime<Utc>,
+}
+
+impl Library {
+    pub fn reserve(&self, item_id: u32) -> Option<&Reserve> {
+        self.reserves.iter().find(|r| r.item_id == item_id)
+    }
+
+    pub fn place_on_reserve(&mut self, item_id: u32, course: &str, teacher: &str, loan: ReserveLoan) -> Result<(), String> {
+        self.only_at_server()?;
+        if !self.items.contains_key(&item_id) {
+            return Err(tr!("Item not found"));
+        }
+        let course = course.trim();
+        if course.is_empty() {
+            return Err(tr!("Enter a course"));
+        }
+        if let Some(reserve) = self.reserve(item_id) {
+            return Err(tr!("Item is already on reserve for {}", reserve.course));
+        }
+        self.reserves.push(Reserve {
+            item_id,
+            course: course.to_string(),
+            teacher: teacher.trim().to_string(),
+            loan,
+            placed_at: self.clock.now(),
+        });
+        tracing::info!(item_id, course, "Item placed on reserve");
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Takes an item off reserve. Copies already on loan keep their short
+    /// due dates.
+    pub fn remove_from_reserve(&mut self, item_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let index = self
+            .reserves
+            .iter()
+            .position(|r| r.item_id == item_id)
+            .ok_or_else(|| tr!("Item is not on reserve"))?;
+        self.reserves.remove(index);
+        tracing::info!(item_id, "Item removed from reserve");
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// When a copy of `item_id` issued at `now` is due back, if the item is
+    /// on reserve.
+    pub(crate) fn reserve_due(&self, item_id: u32, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let due = match self.reserve(item_id)?.loan {
+            ReserveLoan::TwoHours => return Some(now + Duration::hours(2)),
+            ReserveLoan::Overnight => now + Days::new(1),
+            ReserveLoan::ThreeDays => now + Days::new(3),
+        };
+        let day = self.calendar.next_open(due).with_timezone(&Local).date_naive();
+        let time = NaiveTime::from_hms_opt(RESERVE_DUE_HOUR, 0, 0).unwrap();
+        Local
+            .from_local_datetime(&day.and_time(time))
+            .earliest()
+            .map(|at| at.with_timezone(&Utc))
+            .or(Some(due))
+    }
+}
@@ -0,0 +1,72 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::{Library, LiItem};
+
+/// A member's star rating of an item, with optional review text.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Review {
+    // None for ratings carried over from the old `ratings` column
+    pub member_id: Option<u32>,
+    pub stars: u8,
+    pub text: String,
+    pub date: DateTime<Utc>,
+}
+
+impl LiItem {
+    pub fn average_stars(&self) -> Option<f32> {
+        if self.reviews.is_empty() {
+            return None;
+        }
+        let total: u32 = self.reviews.iter().map(|r| r.stars as u32).sum();
+        Some(total as f32 / self.reviews.len() as f32)
+    }
+
+    pub fn rating_text(&self) -> String {
+        match self.average_stars() {
+            Some(avg) => format!("{:.1} ★ ({})", avg, self.reviews.len()),
+            None => tr!("No ratings"),
+        }
+    }
+
+    // The old `ratings` column held a bare number. Values that look like a
+    // star rating become an anonymous review; anything else is dropped.
+    pub(crate) fn migrate_ratings(&mut self, now: DateTime<Utc>) {
+        if (1..=5).contains(&self.ratings) {
+            self.reviews.push(Review {
+                member_id: None,
+                stars: self.ratings as u8,
+                text: String::new(),
+                date: now,
+            });
+        }
+        self.ratings = 0;
+    }
+}
+
+impl Library {
+    /// Adds a member's review of an item, replacing any earlier review by
+    /// the same member.
+    pub fn add_review(&mut self, item_id: u32, member_id: u32, stars: u8, text: &str) -> Result<(), String> {
+        self.only_at_server()?;
+        if !(1..=5).contains(&stars) {
+            return Err(tr!("Ratings must be between 1 and 5 stars"));
+        }
+        if !self.members.contains_key(&member_id) {
+            return Err(tr!("Member not found"));
+        }
+        let now = self.clock.now();
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        item.reviews.retain(|r| r.member_id != Some(member_id));
+        item.reviews.push(Review {
+            member_id: Some(member_id),
+            stars,
+            text: text.trim().to_string(),
+            date: now,
+        });
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+}
@@ -0,0 +1,474 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rusqlite::types::ValueRef;
+use rusqlite::Connection;
+use serde::Serialize;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use crate::i18n::tr;
+use crate::import::CsvSchema;
+use crate::sql::date;
+use crate::{migrate, Library};
+
+/// How one export format fared in `Library::self_test`.
+pub struct RoundTrip {
+    pub format: &'static str,
+    // Hashes of what the format carries, before and after the round trip
+    pub before: String,
+    pub after: String,
+    pub error: Option<String>,
+}
+
+impl RoundTrip {
+    pub fn passed(&self) -> bool {
+        self.error.is_none() && self.before == self.after
+    }
+
+    pub fn describe(&self) -> String {
+        match &self.error {
+            Some(e) => tr!("{}: failed: {}", self.format, e),
+            None if self.passed() => tr!("{}: passed ({})", self.format, short(&self.before)),
+            None => tr!("{}: changed ({} became {})", self.format, short(&self.before), short(&self.after)),
+        }
+    }
+}
+
+fn short(hash: &str) -> &str {
+    &hash[..hash.len().min(12)]
+}
+
+// Writes `value` as JSON with the keys of every object sorted, so maps
+// that are HashMaps in memory hash the same whatever their order
+fn write_canonical(value: &Value, out: &mut String) {
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for (n, key) in keys.into_iter().enumerate() {
+                if n > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(values) => {
+            out.push('[');
+            for (n, value) in values.iter().enumerate() {
+                if n > 0 {
+                    out.push(',');
+                }
+                write_canonical(value, out);
+            }
+            out.push(']');
+        }
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+/// SHA-256 of `data` in its normal form, as hex.
+pub fn content_hash(data: &impl Serialize) -> Result<String, serde_json::Error> {
+    let mut text = String::new();
+    write_canonical(&serde_json::to_value(data)?, &mut text);
+    Ok(format!("{:x}", Sha256::digest(text.as_bytes())))
+}
+
+// The item fields the catalog CSV carries
+#[derive(Serialize)]
+struct CatalogRow<'a> {
+    id: u32,
+    title: &'a str,
+    author: &'a Option<String>,
+    year: u32,
+    edition: &'a str,
+    desc: &'a str,
+    format: &'a str,
+    copies: u32,
+    avail_copies: u32,
+    isbn: &'a Option<String>,
+    tags: &'a [String],
+    branch: &'a str,
+    call_number: &'a str,
+    shelf_location: &'a str,
+}
+
+// The member fields the members CSV carries. The import gives members new
+// IDs, in the order of the file, so they are compared in ID order instead.
+#[derive(Serialize)]
+struct MemberRow<'a> {
+    name: &'a str,
+    email: &'a Option<String>,
+    external_id: &'a Option<String>,
+    membership: &'static str,
+    expires_on: Option<String>,
+}
+
+// The rows of a query against the SQLite copy, as JSON values
+fn sqlite_rows(db: &Connection, sql: &str) -> rusqlite::Result<Vec<Vec<Value>>> {
+    let mut stmt = db.prepare(sql)?;
+    let columns = stmt.column_count();
+    let mut rows = stmt.query([])?;
+    let mut result = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut values = Vec::with_capacity(columns);
+        for i in 0..columns {
+            values.push(match row.get_ref(i)? {
+                ValueRef::Null => Value::Null,
+                ValueRef::Integer(n) => json!(n),
+                ValueRef::Real(x) => json!(x),
+                ValueRef::Text(t) => json!(String::from_utf8_lossy(t)),
+                ValueRef::Blob(b) => json!(b),
+            });
+        }
+        result.push(values);
+    }
+    Ok(result)
+}
+
+/// A file in the temporary directory, removed again when dropped, so a
+/// failed check leaves nothing behind.
+pub(crate) struct TempFile(pub(crate) PathBuf);
+
+impl TempFile {
+    pub(crate) fn new(name: &str) -> TempFile {
+        TempFile(std::env::temp_dir().join(format!("rustlms-{}-{}", std::process::id(), name)))
+    }
+}
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+impl Library {
+    /// SHA-256 of everything the library saves, in a normal form.
+    pub fn state_hash(&self) -> Result<String, serde_json::Error> {
+        content_hash(self)
+    }
+
+    fn catalog_hash(&self) -> Result<String, serde_json::Error> {
+        let mut items: Vec<_> = self.items.values().collect();
+        items.sort_by_key(|i| i.id);
+        let rows: Vec<CatalogRow> = items
+            .into_iter()
+            .map(|i| CatalogRow {
+                id: i.id,
+                title: &i.title,
+                author: &i.author,
+                year: i.year,
+                edition: &i.edition,
+                desc: &i.desc,
+                format: &i.format,
+                copies: i.copies,
+                avail_copies: i.avail_copies,
+                isbn: &i.isbn,
+                tags: &i.tags,
+                branch: &i.branch,
+                call_number: &i.call_number,
+                shelf_location: &i.shelf_location,
+            })
+            .collect();
+        content_hash(&rows)
+    }
+
+    fn member_rows(&self) -> Vec<MemberRow<'_>> {
+        let mut members: Vec<_> = self.members.values().collect();
+        members.sort_by_key(|m| m.id);
+        members
+            .into_iter()
+            .map(|m| MemberRow {
+                name: &m.name,
+                email: &m.email,
+                external_id: &m.external_id,
+                membership: m.membership.name(),
+                expires_on: m.expires_at.map(|at| at.format("%Y-%m-%d").to_string()),
+            })
+            .collect()
+    }
+
+    /// Writes the catalog to `path` in the columns the catalog import
+    /// detects, so it can be read back in with every item as it is.
+    pub fn export_catalog_csv(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record([
+            "id",
+            "title",
+            "author",
+            "year",
+            "edition",
+            "desc",
+            "format",
+            "copies",
+            "avail_copies",
+            "isbn",
+            "tags",
+            "branch",
+            "call_number",
+            "shelf_location",
+        ])?;
+        let mut items: Vec<_> = self.items.values().collect();
+        items.sort_by_key(|i| i.id);
+        for i in items {
+            wtr.write_record([
+                i.id.to_string(),
+                i.title.to_string(),
+                i.author.clone().unwrap_or_default(),
+                i.year.to_string(),
+                i.edition.clone(),
+                i.desc.clone(),
+                i.format.clone(),
+                i.copies.to_string(),
+                i.avail_copies.to_string(),
+                i.isbn.clone().unwrap_or_default(),
+                i.tags.join("; "),
+                i.branch.clone(),
+                i.call_number.clone(),
+                i.shelf_location.clone(),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+
+    fn json_round_trip(&self) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let before = self.state_hash()?;
+        let json = migrate::LIBRARY.to_string(self)?;
+        let copy: Library = migrate::LIBRARY.read(json.as_bytes())?;
+        Ok((before, copy.state_hash()?))
+    }
+
+    fn catalog_round_trip(&self) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let file = TempFile::new("catalog.csv");
+        self.export_catalog_csv(&file.0)?;
+        let mut copy = Library::new();
+        copy.import_csv(&file.0.to_string_lossy(), &CsvSchema::default())?;
+        Ok((self.catalog_hash()?, copy.catalog_hash()?))
+    }
+
+    // Written unencrypted, only to the temporary directory, and removed
+    // right after
+    fn members_round_trip(&self) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let file = TempFile::new("members.csv");
+        self.export_members_csv(&file.0, None)?;
+        let mut copy = Library::new();
+        copy.import_members_csv(&file.0.to_string_lossy(), &CsvSchema::default())?;
+        let before = self.member_rows();
+        let mut after = copy.member_rows();
+        // A member without an expiry date is exported with a blank one,
+        // which the import takes to mean the usual term from today
+        for (row, original) in after.iter_mut().zip(&before) {
+            if original.expires_on.is_none() {
+                row.expires_on = None;
+            }
+        }
+        Ok((content_hash(&before)?, content_hash(&after)?))
+    }
+
+    // What the SQLite copy should hold, table by table, in the order the
+    // queries in `sqlite_round_trip` read it back
+    fn sqlite_tables(&self) -> Value {
+        let mut items: Vec<_> = self.items.values().collect();
+        items.sort_by_key(|i| i.id);
+        let mut members: Vec<_> = self.members.values().collect();
+        members.sort_by_key(|m| m.id);
+        let mut holds: Vec<_> = self.holds.iter().collect();
+        holds.sort_by_key(|h| h.id);
+        let mut loans = Vec::new();
+        let mut fines = Vec::new();
+        for m in &members {
+            let mut insts: Vec<_> = m.items.values().collect();
+            insts.sort_by_key(|inst| inst.id);
+            for inst in insts {
+                loans.push(json!([inst.id, m.id, date(inst.issued_at), date(inst.due_date), inst.renewed_count]));
+            }
+            let mut member_fines: Vec<_> = m.fines.iter().collect();
+            member_fines.sort_by_key(|f| f.id);
+            for f in member_fines {
+                fines.push(json!([
+                    m.id,
+                    f.id,
+                    f.item_id,
+                    f.days_late,
+                    f.amount_cents,
+                    date(f.assessed_at),
+                    format!("{:?}", f.status)
+                ]));
+            }
+        }
+        json!({
+            "items": items
+                .iter()
+                .map(|i| json!([
+                    i.id,
+                    &*i.title,
+                    i.author,
+                    i.year,
+                    i.format,
+                    i.isbn,
+                    i.branch,
+                    i.shelf_location,
+                    i.call_number,
+                    i.copies,
+                    i.avail_copies,
+                    i.added_at.map(date)
+                ]))
+                .collect::<Vec<_>>(),
+            "members": members
+                .iter()
+                .map(|m| json!([
+                    m.id,
+                    m.name,
+                    m.email,
+                    m.membership.name(),
+                    m.joined_at.map(date),
+                    m.expires_at.map(date),
+                    m.anonymized_at.map(date)
+                ]))
+                .collect::<Vec<_>>(),
+            "loans": loans,
+            "fines": fines,
+            "holds": holds
+                .iter()
+                .map(|h| json!([
+                    h.id,
+                    h.item_id,
+                    h.member_id,
+                    date(h.placed_at),
+                    format!("{:?}", h.status),
+                    h.shelved_at.map(date),
+                    format!("{:?}", h.level)
+                ]))
+                .collect::<Vec<_>>(),
+            "transactions": self
+                .transactions
+                .iter()
+                .map(|t| json!([date(t.at), format!("{:?}", t.kind), t.item_id, t.member_id, t.user, t.amount_cents]))
+                .collect::<Vec<_>>(),
+        })
+    }
+
+    // The SQLite copy the SQL console queries, read back table by table
+    fn sqlite_round_trip(&self) -> Result<(String, String), Box<dyn std::error::Error>> {
+        let db = self.to_sqlite()?;
+        let copy = json!({
+            "items": sqlite_rows(&db, "SELECT * FROM items ORDER BY id")?,
+            "members": sqlite_rows(&db, "SELECT * FROM members ORDER BY id")?,
+            "loans": sqlite_rows(&db, "SELECT * FROM loans ORDER BY member_id, item_id")?,
+            "fines": sqlite_rows(&db, "SELECT * FROM fines ORDER BY member_id, fine_id")?,
+            "holds": sqlite_rows(&db, "SELECT * FROM holds ORDER BY id")?,
+            "transactions": sqlite_rows(&db, "SELECT * FROM transactions ORDER BY rowid")?,
+        });
+        Ok((content_hash(&self.sqlite_tables())?, content_hash(&copy)?))
+    }
+
+    /// Exports the library in each format it can be read back from, reads
+    /// it back into a new library and compares the content hashes of what
+    /// the format carries. Any difference means an export loses or changes
+    /// data.
+    pub fn self_test(&self) -> Vec<RoundTrip> {
+        type Check = fn(&Library) -> Result<(String, String), Box<dyn std::error::Error>>;
+        let checks: [(&'static str, Check); 4] = [
+            ("JSON", Library::json_round_trip),
+            ("Catalog CSV", Library::catalog_round_trip),
+            ("Members CSV", Library::members_round_trip),
+            ("SQLite", Library::sqlite_round_trip),
+        ];
+        checks
+            .into_iter()
+            .map(|(format, check)| {
+                let result = match check(self) {
+                    Ok((before, after)) => RoundTrip { format, before, after, error: None },
+                    Err(e) => RoundTrip { format, before: String::new(), after: String::new(), error: Some(e.to_string()) },
+                };
+                if result.passed() {
+                    tracing::info!(format, hash = %result.before, "Round trip passed");
+                } else {
+                    tracing::error!(format, "Round trip failed: {}", result.describe());
+                }
+                result
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::holds::HoldLevel;
+    use crate::seed::SeedOptions;
+
+    // Items, members, loans, fines and holds, the same every run
+    fn seeded() -> Library {
+        let mut library = Library::new();
+        library.seed(&SeedOptions { items: 200, members: 40, loans: 400, seed: Some(7) });
+        // Holds on the shelf and waiting; the seeded data has none. Refused
+        // ones, e.g. on a member's own loan, are fine.
+        let mut item_ids: Vec<u32> = library.items.keys().copied().collect();
+        item_ids.sort();
+        let mut member_ids: Vec<u32> = library.members.keys().copied().collect();
+        member_ids.sort();
+        for (&item_id, &member_id) in item_ids.iter().zip(&member_ids).take(10) {
+            let _ = library.place_hold(item_id, member_id, HoldLevel::Copy);
+            let _ = library.place_hold(item_id, member_id + 1, HoldLevel::Title);
+        }
+        library
+    }
+
+    fn assert_round_trip(format: &str, check: fn(&Library) -> Result<(String, String), Box<dyn std::error::Error>>) {
+        let (before, after) = check(&seeded()).unwrap();
+        assert_eq!(before, after, "{} round trip changed the data", format);
+    }
+
+    #[test]
+    fn json_round_trip_keeps_everything() {
+        assert_round_trip("JSON", Library::json_round_trip);
+    }
+
+    #[test]
+    fn catalog_csv_round_trip_keeps_the_catalog() {
+        assert_round_trip("Catalog CSV", Library::catalog_round_trip);
+    }
+
+    #[test]
+    fn members_csv_round_trip_keeps_the_members() {
+        assert_round_trip("Members CSV", Library::members_round_trip);
+    }
+
+    #[test]
+    fn sqlite_round_trip_keeps_every_table() {
+        assert_round_trip("SQLite", Library::sqlite_round_trip);
+    }
+
+    #[test]
+    fn self_test_passes_on_seeded_data() {
+        let results = seeded().self_test();
+        assert_eq!(results.len(), 4);
+        for result in results {
+            assert!(result.passed(), "{}", result.describe());
+        }
+    }
+
+    #[test]
+    fn hashes_ignore_map_order() {
+        let a: HashMap<String, u32> = (0..50).map(|n| (n.to_string(), n)).collect();
+        let b: HashMap<String, u32> = (0..50).rev().map(|n| (n.to_string(), n)).collect();
+        assert_eq!(content_hash(&a).unwrap(), content_hash(&b).unwrap());
+    }
+
+    #[test]
+    fn hashes_see_changes() {
+        let mut library = seeded();
+        let before = library.state_hash().unwrap();
+        let item = library.items.values_mut().next().unwrap();
+        item.avail_copies += 1;
+        assert_ne!(before, library.state_hash().unwrap());
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashSet;
+
+use crate::LiItem;
+
+// Minimum trigram similarity for a title to count as a fuzzy match when the
+// per-word comparison fails.
+const FUZZY_THRESHOLD: f32 = 0.45;
+
+pub struct SearchResult {
+    pub ids: Vec<u32>,
+    pub suggestion: Option<String>,
+}
+
+pub fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        curr[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            curr[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(curr[j] + 1);
+        }
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    prev[b.len()]
+}
+
+fn trigrams(s: &str) -> HashSet<(char, char, char)> {
+    let padded: Vec<char> = format!("  {} ", s).chars().collect();
+    padded.windows(3).map(|w| (w[0], w[1], w[2])).collect()
+}
+
+pub fn trigram_similarity(a: &str, b: &str) -> f32 {
+    let a = trigrams(a);
+    let b = trigrams(b);
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f32 / union as f32
+}
+
+// Short words have to match exactly (or as a prefix), longer ones tolerate a
+// typo or two.
+fn word_matches(query_word: &str, word: &str) -> bool {
+    let tolerance = match query_word.chars().count() {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    };
+    word.starts_with(query_word) || levenshtein(query_word, word) <= tolerance
+}
+
+/// Matches `query` against item titles. Exact (substring) matches come first,
+/// ordered by ID, followed by fuzzy matches ordered by similarity. When
+/// nothing matches exactly, the closest fuzzy title is offered as a
+/// suggestion.
+pub fn search<'a>(items: impl Iterator<Item = &'a LiItem>, query: &str) -> SearchResult {
+    let query = normalize(query);
+    let mut exact = Vec::new();
+    let mut fuzzy: Vec<(f32, &LiItem)> = Vec::new();
+
+    for item in items {
+        let title = normalize(&item.title);
+        if title.contains(&query) {
+            exact.push(item.id);
+            continue;
+        }
+
+        let words: Vec<&str> = title.split(' ').collect();
+        let all_words = query
+            .split(' ')
+            .all(|q| words.iter().any(|w| word_matches(q, w)));
+        let similarity = trigram_similarity(&query, &title);
+        if all_words || similarity >= FUZZY_THRESHOLD {
+            fuzzy.push((similarity, item));
+        }
+    }
+
+    exact.sort_unstable();
+    fuzzy.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+
+    let suggestion = if exact.is_empty() {
+        fuzzy.first().map(|(_, item)| item.title.to_string())
+    } else {
+        None
+    };
+
+    let mut ids = exact;
+    ids.extend(fuzzy.iter().map(|(_, item)| item.id));
+
+    SearchResult { ids, suggestion }
+}
@@ -0,0 +1,231 @@
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::{DateTime, Duration, Utc};
+
+use crate::clock::FixedClock;
+use crate::memberships::MembershipType;
+use crate::{transfers, LiItem, Library, Member};
+
+// Loans are spread over this many days before today
+const HISTORY_DAYS: i64 = 365;
+// Share of loans, in percent, that have been returned again
+const RETURNED_PERCENT: u64 = 80;
+
+const ADJECTIVES: &[&str] = &[
+    "Silent", "Hidden", "Last", "Broken", "Golden", "Forgotten", "Distant", "Burning", "Quiet", "Endless", "Secret",
+    "Northern", "Crimson", "Lost", "Wild", "Glass",
+];
+const NOUNS: &[&str] = &[
+    "River", "Garden", "Kingdom", "Winter", "Machine", "Harbor", "Letters", "Mountain", "Library", "Storm", "Orchard",
+    "Empire", "Island", "Frontier", "Lighthouse", "Algorithm",
+];
+const SUBJECTS: &[&str] = &[
+    "history", "cooking", "programming", "astronomy", "gardening", "philosophy", "travel", "economics", "poetry",
+    "biology", "music", "architecture",
+];
+const FIRST_NAMES: &[&str] = &[
+    "Anna", "Ben", "Chiara", "David", "Emma", "Farid", "Greta", "Hiro", "Ines", "Jonas", "Kemal", "Lena", "Mateo",
+    "Nora", "Oskar", "Priya", "Quinn", "Rosa", "Samuel", "Tanja", "Umar", "Vera", "Wei", "Yusuf", "Zoe",
+];
+const LAST_NAMES: &[&str] = &[
+    "Schmidt", "Garcia", "Nguyen", "Okafor", "Rossi", "Kowalski", "Jensen", "Tanaka", "Silva", "Dubois", "Novak",
+    "Haddad", "Larsen", "Moreau", "Petrov", "Walsh",
+];
+// Weighted towards books, as a real catalog is
+const FORMATS: &[&str] = &["Book", "Book", "Book", "Book", "Book", "Book", "Movie", "Audiobook", "Magazine", "Music"];
+const EDITIONS: &[&str] = &["1st", "1st", "1st", "2nd", "3rd", "Revised"];
+
+/// How much demo data to generate.
+pub struct SeedOptions {
+    pub items: u32,
+    pub members: u32,
+    pub loans: u32,
+    // The same seed gives the same data; None picks one from the time
+    pub seed: Option<u64>,
+}
+
+impl Default for SeedOptions {
+    fn default() -> SeedOptions {
+        SeedOptions { items: 1000, members: 100, loans: 300, seed: None }
+    }
+}
+
+/// What `Library::seed` added.
+pub struct SeedReport {
+    pub items: u32,
+    pub members: u32,
+    // Loans issued, and of those the ones still out
+    pub loans: u32,
+    pub on_loan: u32,
+    // Loans a policy refused, e.g. a loan limit or an expired membership
+    pub refused: u32,
+}
+
+// Small xorshift generator: good enough for fake data and keeps seeding
+// reproducible without another dependency
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Rng {
+        // Zero would stay zero forever
+        Rng(seed.max(1))
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    // A number in `0..n`
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 { 0 } else { self.next() % n }
+    }
+
+    fn pick<'a>(&mut self, list: &[&'a str]) -> &'a str {
+        list[self.below(list.len() as u64) as usize]
+    }
+
+    fn percent(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+// A random ISBN-13 with a correct check digit
+fn fake_isbn(rng: &mut Rng) -> String {
+    let mut digits: Vec<u32> = vec![9, 7, 8];
+    digits.extend((0..9).map(|_| rng.below(10) as u32));
+    let sum: u32 = digits.iter().enumerate().map(|(i, d)| if i % 2 == 0 { *d } else { d * 3 }).sum();
+    digits.push((10 - sum % 10) % 10);
+    digits.iter().map(|d| char::from_digit(*d, 10).unwrap()).collect()
+}
+
+enum LoanEvent {
+    Issue { item_id: u32, member_id: u32 },
+    Return { item_id: u32, member_id: u32 },
+}
+
+impl Library {
+    /// Adds made-up items, members and past loans, for trying out the
+    /// application and measuring it on a catalog of a realistic size. New
+    /// records get IDs after the existing ones.
+    pub fn seed(&mut self, options: &SeedOptions) -> SeedReport {
+        let seed = options.seed.unwrap_or_else(|| {
+            SystemTime::now().duration_since(UNIX_EPOCH).map_or(1, |d| d.as_nanos() as u64)
+        });
+        let mut rng = Rng::new(seed);
+        let now = self.clock.now();
+        tracing::info!(items = options.items, members = options.members, loans = options.loans, seed, "Seeding demo data");
+
+        let item_ids: Vec<u32> = (0..options.items).map(|_| self.next_item_id()).collect();
+        for &id in &item_ids {
+            let format = rng.pick(FORMATS);
+            let subject = rng.pick(SUBJECTS);
+            let title = match rng.below(3) {
+                0 => format!("The {} {}", rng.pick(ADJECTIVES), rng.pick(NOUNS)),
+                1 => format!("{} of the {}", rng.pick(NOUNS), rng.pick(NOUNS)),
+                _ => format!("A Short Guide to {}{}", subject[..1].to_uppercase(), &subject[1..]),
+            };
+            let copies = 1 + rng.below(4) as u32;
+            self.items.insert(id, LiItem {
+                title: title.into(),
+                author: Some(format!("{} {}", rng.pick(FIRST_NAMES), rng.pick(LAST_NAMES))),
+                year: 1950 + rng.below(76) as u32,
+                edition: rng.pick(EDITIONS).to_string(),
+                desc: format!("A {} about {}.", format.to_lowercase(), subject),
+                format: format.to_string(),
+                id,
+                copies,
+                avail_copies: copies,
+                ratings: 0,
+                isbn: (format == "Book").then(|| fake_isbn(&mut rng)),
+                tags: vec![subject.to_string()],
+                reviews: Vec::new(),
+                added_at: Some(now - Duration::days(HISTORY_DAYS + rng.below(3 * 365) as i64)),
+                branch: transfers::default_branch(),
+                call_number: format!("{:03}.{} {}", rng.below(1000), rng.below(100), rng.pick(LAST_NAMES)[..3].to_uppercase()),
+                shelf_location: String::new(),
+                lost_copies: 0,
+                copy_statuses: BTreeMap::new(),
+                conditions: BTreeMap::new(),
+                condition_notes: Vec::new(),
+                link: None,
+                version: 0,
+            });
+        }
+
+        let member_ids: Vec<u32> = (0..options.members).map(|_| self.next_member_id()).collect();
+        for &id in &member_ids {
+            let first = rng.pick(FIRST_NAMES);
+            let last = rng.pick(LAST_NAMES);
+            let joined_at = now - Duration::days(HISTORY_DAYS + rng.below(5 * 365) as i64);
+            self.members.insert(id, Member {
+                id,
+                name: format!("{} {}", first, last),
+                items: HashMap::new(),
+                fines: Vec::new(),
+                membership: MembershipType::ALL[rng.below(3) as usize],
+                expires_at: self.new_membership_expiry(),
+                email: Some(format!("{}.{}{}@example.org", first.to_lowercase(), last.to_lowercase(), id)),
+                external_id: None,
+                anonymized_at: None,
+                keep_history: rng.percent(30),
+                history: Vec::new(),
+                joined_at: Some(joined_at),
+                challenge: None,
+                receipts: Vec::new(),
+                version: 0,
+                digest_opt_out: false,
+            });
+        }
+
+        // Issues and returns are replayed in order, each as of its own time,
+        // so due dates, fines and the transaction log come out as they would
+        // have at the desk
+        let mut events: Vec<(DateTime<Utc>, LoanEvent)> = Vec::new();
+        if !item_ids.is_empty() && !member_ids.is_empty() {
+            for _ in 0..options.loans {
+                let item_id = item_ids[rng.below(item_ids.len() as u64) as usize];
+                let member_id = member_ids[rng.below(member_ids.len() as u64) as usize];
+                let issued_at = now - Duration::minutes(rng.below(HISTORY_DAYS as u64 * 24 * 60) as i64);
+                events.push((issued_at, LoanEvent::Issue { item_id, member_id }));
+                if rng.percent(RETURNED_PERCENT) {
+                    let returned_at = issued_at + Duration::hours(1 + rng.below(45 * 24) as i64);
+                    if returned_at < now {
+                        events.push((returned_at, LoanEvent::Return { item_id, member_id }));
+                    }
+                }
+            }
+        }
+        events.sort_by_key(|(at, _)| *at);
+
+        // Made-up loans aren't sent to other desks
+        let sync_enabled = std::mem::replace(&mut self.sync_enabled, false);
+        let mut report =
+            SeedReport { items: options.items, members: options.members, loans: 0, on_loan: 0, refused: 0 };
+        for (at, event) in events {
+            let clock = std::mem::replace(&mut self.clock, Box::new(FixedClock(at)));
+            match event {
+                LoanEvent::Issue { item_id, member_id } => match self.book_issue(item_id, member_id.to_string()) {
+                    Ok(()) => report.loans += 1,
+                    Err(_) => report.refused += 1,
+                },
+                // Fails for loans that were refused, which is fine
+                LoanEvent::Return { item_id, member_id } => {
+                    let _ = self.book_return(item_id, member_id);
+                }
+            }
+            self.clock = clock;
+        }
+        self.sync_enabled = sync_enabled;
+        // IDs only go up, so the new members are the ones from the first on
+        let first_member = member_ids.first().copied().unwrap_or(u32::MAX);
+        report.on_loan = self.members.values().filter(|m| m.id >= first_member).map(|m| m.items.len() as u32).sum();
+
+        self.dirty = true;
+        self.reloaded();
+        report
+    }
+}
@@ -0,0 +1,427 @@
+use std::cell::{Cell, RefCell};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use gtk::prelude::*;
+use gtk::{glib, Application, ApplicationWindow, Box as GtkBox, Button, Entry, Label, ScrolledWindow};
+
+use crate::config::Config;
+use crate::i18n::tr;
+use crate::receipts::{self, Receipt, ReceiptKind};
+use crate::table::Table;
+use crate::{mail, persist, print, sync, Library};
+
+// Recorded as the staff member in the transaction log and on receipts
+const SELF_CHECKOUT_USER: &str = "self-checkout";
+// A checkout left this long without a touch or a scan starts over, so the
+// next patron doesn't find someone else's card in use
+const IDLE_RESET_SECONDS: u64 = 90;
+const IDLE_CHECK_SECONDS: u32 = 5;
+
+// The patron at the screen and what they have scanned so far
+#[derive(Default)]
+struct Checkout {
+    member_id: Option<u32>,
+    items: Vec<(u32, Arc<str>)>,
+    // Handed out once the items are issued
+    receipts: Vec<Receipt>,
+}
+
+struct Screen {
+    window: ApplicationWindow,
+    steps: gtk::Stack,
+    card_entry: Entry,
+    item_entry: Entry,
+    items_table: Rc<Table<(u32, Arc<str>)>>,
+    confirm_label: Label,
+    receipt_label: Label,
+    email_button: Button,
+    message_label: Label,
+    checkout: RefCell<Checkout>,
+    last_input: Cell<Instant>,
+}
+
+impl Screen {
+    fn show_step(&self, name: &str) {
+        self.message_label.set_text("");
+        self.steps.set_visible_child_name(name);
+        match name {
+            "card" => self.card_entry.grab_focus(),
+            "items" => self.item_entry.grab_focus(),
+            _ => false,
+        };
+    }
+
+    fn error(&self, text: &str) {
+        self.message_label.set_text(text);
+    }
+
+    // Back to the first step, with nothing of the last patron left showing
+    fn reset(&self) {
+        *self.checkout.borrow_mut() = Checkout::default();
+        self.card_entry.set_text("");
+        self.item_entry.set_text("");
+        self.items_table.set_rows(Vec::new());
+        self.receipt_label.set_text("");
+        self.show_step("card");
+    }
+}
+
+fn big_button(label: &str) -> Button {
+    let button = Button::with_label(label);
+    button.add_css_class("pill");
+    button.add_css_class("title-2");
+    button.set_size_request(240, 80);
+    button
+}
+
+fn big_entry() -> Entry {
+    let entry = Entry::new();
+    entry.add_css_class("title-2");
+    entry.set_size_request(-1, 64);
+    entry
+}
+
+fn step_box(heading: &str) -> GtkBox {
+    let step = GtkBox::new(gtk::Orientation::Vertical, 20);
+    step.set_margin_top(40);
+    step.set_margin_bottom(40);
+    step.set_margin_start(60);
+    step.set_margin_end(60);
+    let heading = Label::new(Some(heading));
+    heading.add_css_class("title-1");
+    heading.set_wrap(true);
+    step.append(&heading);
+    step
+}
+
+fn button_row(buttons: &[&Button]) -> GtkBox {
+    let row = GtkBox::new(gtk::Orientation::Horizontal, 20);
+    row.set_halign(gtk::Align::Center);
+    for button in buttons {
+        row.append(*button);
+    }
+    row
+}
+
+/// The touchscreen window started with `--self-checkout`, taking a patron
+/// through scanning their card and items, confirming, and a receipt. Loans
+/// are made with `Library::book_issue_batch` and saved right away; when the
+/// settings make this machine a sync client, they also go to the server.
+pub fn build_self_checkout_window(app: &Application, library: Arc<RwLock<Library>>, config: Rc<RefCell<Config>>) {
+    library.write().unwrap().current_user = SELF_CHECKOUT_USER.to_string();
+
+    let window = ApplicationWindow::builder()
+        .application(app)
+        .title(tr!("Self-Checkout"))
+        .build();
+
+    let card_step = step_box(&tr!("Scan your library card"));
+    let card_entry = big_entry();
+    card_entry.set_placeholder_text(Some(&tr!("Member ID")));
+    let start_button = big_button(&tr!("Start"));
+    start_button.add_css_class("suggested-action");
+    card_step.append(&card_entry);
+    card_step.append(&button_row(&[&start_button]));
+
+    let items_step = step_box(&tr!("Scan each item"));
+    let item_entry = big_entry();
+    item_entry.set_placeholder_text(Some(&tr!("Item ID")));
+    let items_table = Rc::new(Table::<(u32, Arc<str>)>::new());
+    items_table.add_column(&tr!("Item ID"), |(id, _)| id.to_string(), |a, b| a.0.cmp(&b.0));
+    items_table.add_column(&tr!("Title"), |(_, title)| title.to_string(), |a, b| a.1.cmp(&b.1));
+    let items_window = ScrolledWindow::new();
+    items_window.set_child(Some(&items_table.view));
+    items_window.set_vexpand(true);
+    let remove_button = big_button(&tr!("Remove Last"));
+    let cancel_button = big_button(&tr!("Cancel"));
+    let next_button = big_button(&tr!("Next"));
+    next_button.add_css_class("suggested-action");
+    items_step.append(&item_entry);
+    items_step.append(&items_window);
+    items_step.append(&button_row(&[&cancel_button, &remove_button, &next_button]));
+
+    let confirm_step = step_box(&tr!("Check out these items?"));
+    let confirm_label = Label::new(None);
+    confirm_label.add_css_class("title-3");
+    confirm_label.set_wrap(true);
+    confirm_label.set_vexpand(true);
+    confirm_label.set_valign(gtk::Align::Start);
+    let back_button = big_button(&tr!("Back"));
+    let check_out_button = big_button(&tr!("Check Out"));
+    check_out_button.add_css_class("suggested-action");
+    confirm_step.append(&confirm_label);
+    confirm_step.append(&button_row(&[&back_button, &check_out_button]));
+
+    let receipt_step = step_box(&tr!("All done! Your items are due back:"));
+    let receipt_label = Label::new(None);
+    receipt_label.add_css_class("title-3");
+    receipt_label.set_wrap(true);
+    receipt_label.set_vexpand(true);
+    receipt_label.set_valign(gtk::Align::Start);
+    let print_button = big_button(&tr!("Print Receipt"));
+    let email_button = big_button(&tr!("Email Receipt"));
+    let done_button = big_button(&tr!("Done"));
+    done_button.add_css_class("suggested-action");
+    receipt_step.append(&receipt_label);
+    receipt_step.append(&button_row(&[&print_button, &email_button, &done_button]));
+
+    let steps = gtk::Stack::new();
+    steps.set_transition_type(gtk::StackTransitionType::SlideLeftRight);
+    steps.set_vexpand(true);
+    steps.add_named(&card_step, Some("card"));
+    steps.add_named(&items_step, Some("items"));
+    steps.add_named(&confirm_step, Some("confirm"));
+    steps.add_named(&receipt_step, Some("receipt"));
+
+    let message_label = Label::new(None);
+    message_label.add_css_class("title-3");
+    message_label.add_css_class("error");
+    message_label.set_wrap(true);
+
+    let main_box = GtkBox::new(gtk::Orientation::Vertical, 10);
+    main_box.append(&steps);
+    main_box.append(&message_label);
+    window.set_child(Some(&main_box));
+
+    let screen = Rc::new(Screen {
+        window: window.clone(),
+        steps,
+        card_entry,
+        item_entry,
+        items_table,
+        confirm_label,
+        receipt_label,
+        email_button,
+        message_label,
+        checkout: RefCell::new(Checkout::default()),
+        last_input: Cell::new(Instant::now()),
+    });
+
+    let start = glib::clone!(
+        #[strong]
+        screen,
+        #[strong]
+        library,
+        move || {
+            let text = screen.card_entry.text().trim().to_string();
+            screen.card_entry.set_text("");
+            let member_id = text.parse::<u32>().ok().filter(|id| library.read().unwrap().members.contains_key(id));
+            let Some(member_id) = member_id else {
+                screen.error(&tr!("Card not recognised. Please ask at the desk."));
+                return;
+            };
+            screen.checkout.borrow_mut().member_id = Some(member_id);
+            screen.show_step("items");
+        }
+    );
+    let start = Rc::new(start);
+    start_button.connect_clicked(glib::clone!(
+        #[strong]
+        start,
+        move |_| start()
+    ));
+    screen.card_entry.connect_activate(move |_| start());
+
+    // Each scan is checked here, so a patron hears about an item that can't
+    // go out while still holding it
+    screen.item_entry.connect_activate(glib::clone!(
+        #[strong]
+        screen,
+        #[strong]
+        library,
+        move |entry| {
+            let text = entry.text().trim().to_string();
+            entry.set_text("");
+            let Ok(item_id) = text.parse::<u32>() else {
+                screen.error(&tr!("Item not recognised. Please ask at the desk."));
+                return;
+            };
+            let lib = library.read().unwrap();
+            // A title bound with others is lent as its host volume
+            let item_id = lib.physical_unit(item_id);
+            let mut checkout = screen.checkout.borrow_mut();
+            if checkout.items.iter().any(|(id, _)| *id == item_id) {
+                screen.error(&tr!("This item has already been scanned"));
+                return;
+            }
+            let Some(item) = lib.items.get(&item_id) else {
+                screen.error(&tr!("Item not recognised. Please ask at the desk."));
+                return;
+            };
+            let held = checkout.member_id.and_then(|member_id| lib.shelved_hold(item_id, member_id));
+            if item.avail_copies == 0 && held.is_none() {
+                screen.error(&tr!("This item can't be checked out here. Please ask at the desk."));
+                return;
+            }
+            checkout.items.push((item_id, item.title.clone()));
+            screen.items_table.set_rows(checkout.items.clone());
+            screen.message_label.set_text("");
+        }
+    ));
+
+    remove_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| {
+            let mut checkout = screen.checkout.borrow_mut();
+            checkout.items.pop();
+            screen.items_table.set_rows(checkout.items.clone());
+            screen.item_entry.grab_focus();
+        }
+    ));
+    cancel_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| screen.reset()
+    ));
+    next_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| {
+            let checkout = screen.checkout.borrow();
+            if checkout.items.is_empty() {
+                screen.error(&tr!("Scan at least one item first"));
+                return;
+            }
+            let titles: Vec<String> = checkout.items.iter().map(|(_, title)| title.to_string()).collect();
+            screen.confirm_label.set_text(&titles.join("\n"));
+            drop(checkout);
+            screen.show_step("confirm");
+        }
+    ));
+    back_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| screen.show_step("items")
+    ));
+
+    check_out_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        #[strong]
+        library,
+        #[strong]
+        config,
+        move |_| {
+            let (member_id, item_ids) = {
+                let checkout = screen.checkout.borrow();
+                let Some(member_id) = checkout.member_id else { return };
+                (member_id, checkout.items.iter().map(|(id, _)| *id).collect::<Vec<_>>())
+            };
+            let mut lib = library.write().unwrap();
+            let receipts_before = lib.members.get(&member_id).map_or(0, |m| m.receipts.len());
+            if let Err(e) = lib.book_issue_batch(&item_ids, member_id.to_string()) {
+                tracing::warn!(member_id, "Self-checkout refused: {}", e);
+                screen.error(&tr!("Nothing was checked out: {}. Please ask at the desk.", e));
+                return;
+            }
+            if let Err(e) = lib.save(Path::new(persist::STATE_PATH)) {
+                tracing::error!("Failed to save after self-checkout: {}", e);
+            }
+            let member = &lib.members[&member_id];
+            let new: Vec<Receipt> =
+                member.receipts[receipts_before..].iter().filter(|r| r.kind == ReceiptKind::Checkout).cloned().collect();
+            let has_email = member.email.as_deref().is_some_and(|e| !e.trim().is_empty());
+            drop(lib);
+            tracing::info!(member_id, count = item_ids.len(), "Self-checkout");
+
+            let lines: Vec<String> = new.iter().map(|r| format!("{} – {}", r.title, r.detail)).collect();
+            screen.receipt_label.set_text(&lines.join("\n"));
+            screen.email_button.set_sensitive(has_email && mail::is_configured(&config.borrow()));
+            screen.checkout.borrow_mut().receipts = new;
+            screen.show_step("receipt");
+        }
+    ));
+
+    print_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| {
+            let checkout = screen.checkout.borrow();
+            let rows: Vec<Vec<String>> = checkout.receipts.iter().flat_map(receipts::receipt_rows).collect();
+            let result = print::print_table(&screen.window, &tr!("Checkout Receipt"), receipts::receipt_headers(), rows, None);
+            if let Err(e) = result {
+                tracing::error!("Failed to print self-checkout receipt: {}", e);
+                screen.error(&tr!("The receipt could not be printed. Please ask at the desk."));
+            }
+        }
+    ));
+
+    // Sent off the main thread; the patron can walk away meanwhile
+    screen.email_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        #[strong]
+        library,
+        #[strong]
+        config,
+        move |button| {
+            let checkout = screen.checkout.borrow();
+            let Some(member_id) = checkout.member_id else { return };
+            let Some(to) = library.read().unwrap().members.get(&member_id).and_then(|m| m.email.clone()) else { return };
+            let lines: Vec<String> = checkout.receipts.iter().map(|r| format!("{} – {}", r.title, r.detail)).collect();
+            let body = tr!("You checked out:\n\n{}\n\nThank you for visiting the library.", lines.join("\n"));
+            let config = config.borrow().clone();
+            button.set_sensitive(false);
+            std::thread::spawn(move || {
+                if let Err(e) = mail::send(&config, &[to], &tr!("Your checkout receipt"), &body, None) {
+                    tracing::error!(member_id, "Failed to email self-checkout receipt: {}", e);
+                }
+            });
+            screen.message_label.set_text(&tr!("The receipt is on its way to your email."));
+        }
+    ));
+    done_button.connect_clicked(glib::clone!(
+        #[strong]
+        screen,
+        move |_| screen.reset()
+    ));
+
+    // Any touch or scan counts as the patron still being there
+    let key = gtk::EventControllerKey::new();
+    key.set_propagation_phase(gtk::PropagationPhase::Capture);
+    key.connect_key_pressed(glib::clone!(
+        #[strong]
+        screen,
+        move |_, _, _, _| {
+            screen.last_input.set(Instant::now());
+            glib::Propagation::Proceed
+        }
+    ));
+    window.add_controller(key);
+    let click = gtk::GestureClick::new();
+    click.set_propagation_phase(gtk::PropagationPhase::Capture);
+    click.connect_pressed(glib::clone!(
+        #[strong]
+        screen,
+        move |_, _, _, _| screen.last_input.set(Instant::now())
+    ));
+    window.add_controller(click);
+    glib::timeout_add_seconds_local(IDLE_CHECK_SECONDS, glib::clone!(
+        #[strong]
+        screen,
+        move || {
+            let idle = screen.last_input.get().elapsed() >= Duration::from_secs(IDLE_RESET_SECONDS);
+            if idle && screen.steps.visible_child_name().as_deref() != Some("card") {
+                screen.reset();
+            }
+            glib::ControlFlow::Continue
+        }
+    ));
+
+    // The sync handle lives as long as its timer, i.e. the application
+    let _ = sync::start(&window, library, &config.borrow());
+
+    window.connect_close_request(|_| glib::Propagation::Stop);
+    window.connect_fullscreened_notify(|window| {
+        if !window.is_fullscreen() {
+            window.fullscreen();
+        }
+    });
+    window.fullscreen();
+    screen.reset();
+    window.show();
+}
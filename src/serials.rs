@@ -0,0 +1,326 @@
+use chrono::{DateTime, Datelike, Days, Local, Months, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::editing::{ItemDetails, NewItem};
+use crate::i18n::tr;
+use crate::Library;
+
+// An issue not in by this many days after its expected date can be claimed
+pub const CLAIM_AFTER_DAYS: u64 = 14;
+
+// Format of the catalog items checked-in issues become
+const ISSUE_FORMAT: &str = "Periodical";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Frequency {
+    Weekly,
+    Fortnightly,
+    Monthly,
+    Bimonthly,
+    Quarterly,
+    Yearly,
+}
+
+impl Frequency {
+    pub const ALL: [Frequency; 6] = [
+        Frequency::Weekly,
+        Frequency::Fortnightly,
+        Frequency::Monthly,
+        Frequency::Bimonthly,
+        Frequency::Quarterly,
+        Frequency::Yearly,
+    ];
+
+    pub fn label(self) -> String {
+        match self {
+            Frequency::Weekly => tr!("Weekly"),
+            Frequency::Fortnightly => tr!("Fortnightly"),
+            Frequency::Monthly => tr!("Monthly"),
+            Frequency::Bimonthly => tr!("Every two months"),
+            Frequency::Quarterly => tr!("Quarterly"),
+            Frequency::Yearly => tr!("Yearly"),
+        }
+    }
+
+    // Date of issue number `n`, counting the first issue as 0. Counted from
+    // the first issue each time so monthly issues due on the 31st don't
+    // drift to the 28th after February.
+    fn nth_issue(self, first: NaiveDate, n: u32) -> Option<NaiveDate> {
+        match self {
+            Frequency::Weekly => first.checked_add_days(Days::new(7 * n as u64)),
+            Frequency::Fortnightly => first.checked_add_days(Days::new(14 * n as u64)),
+            Frequency::Monthly => first.checked_add_months(Months::new(n)),
+            Frequency::Bimonthly => first.checked_add_months(Months::new(2 * n)),
+            Frequency::Quarterly => first.checked_add_months(Months::new(3 * n)),
+            Frequency::Yearly => first.checked_add_months(Months::new(12 * n)),
+        }
+    }
+
+    /// How an issue expected on `date` is named, e.g. "2026-10" for a
+    /// monthly.
+    pub fn issue_label(self, date: NaiveDate) -> String {
+        match self {
+            Frequency::Weekly | Frequency::Fortnightly => date.format("%Y-%m-%d").to_string(),
+            Frequency::Monthly | Frequency::Bimonthly => date.format("%Y-%m").to_string(),
+            Frequency::Quarterly => format!("{} Q{}", date.year(), date.month0() / 3 + 1),
+            Frequency::Yearly => date.year().to_string(),
+        }
+    }
+}
+
+/// An issue that has arrived or been claimed. Issues still only expected
+/// aren't stored; they're predicted from the subscription.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SerialIssue {
+    pub expected: NaiveDate,
+    pub received_at: Option<DateTime<Utc>>,
+    // Catalog item the issue is lent as
+    pub item_id: Option<u32>,
+    pub claimed_at: Vec<DateTime<Utc>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IssueStatus {
+    Expected,
+    Late,
+    Claimed,
+    Received,
+}
+
+impl IssueStatus {
+    pub fn label(self) -> String {
+        match self {
+            IssueStatus::Expected => tr!("Expected"),
+            IssueStatus::Late => tr!("Late"),
+            IssueStatus::Claimed => tr!("Claimed"),
+            IssueStatus::Received => tr!("Received"),
+        }
+    }
+}
+
+/// A row of a subscription's check-in grid.
+#[derive(Clone)]
+pub struct IssueRow {
+    pub expected: NaiveDate,
+    pub label: String,
+    pub status: IssueStatus,
+    pub issue: Option<SerialIssue>,
+}
+
+/// A magazine or journal the library subscribes to.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Subscription {
+    pub id: u32,
+    pub title: String,
+    pub issn: Option<String>,
+    // Publisher or subscription agent, who missing issues are claimed from
+    pub supplier: String,
+    pub frequency: Frequency,
+    pub first_issue: NaiveDate,
+    pub call_number: String,
+    pub issues: Vec<SerialIssue>,
+}
+
+/// What the add-subscription dialog asks for.
+pub struct NewSubscription {
+    pub title: String,
+    pub issn: Option<String>,
+    pub supplier: String,
+    pub frequency: Frequency,
+    pub first_issue: NaiveDate,
+    pub call_number: String,
+}
+
+impl Subscription {
+    fn issue(&self, expected: NaiveDate) -> Option<&SerialIssue> {
+        self.issues.iter().find(|i| i.expected == expected)
+    }
+
+    /// Issue dates from the first issue up to and including the next one
+    /// due after `today`.
+    pub fn expected_dates(&self, today: NaiveDate) -> Vec<NaiveDate> {
+        let mut dates = Vec::new();
+        for n in 0.. {
+            let Some(date) = self.frequency.nth_issue(self.first_issue, n) else { break };
+            dates.push(date);
+            if date > today {
+                break;
+            }
+        }
+        dates
+    }
+
+    pub fn status(&self, expected: NaiveDate, today: NaiveDate) -> IssueStatus {
+        match self.issue(expected) {
+            Some(issue) if issue.received_at.is_some() => IssueStatus::Received,
+            Some(issue) if !issue.claimed_at.is_empty() => IssueStatus::Claimed,
+            _ if expected + Days::new(CLAIM_AFTER_DAYS) < today => IssueStatus::Late,
+            _ => IssueStatus::Expected,
+        }
+    }
+
+    /// The check-in grid, newest issue first.
+    pub fn issue_rows(&self, today: NaiveDate) -> Vec<IssueRow> {
+        self.expected_dates(today)
+            .into_iter()
+            .rev()
+            .map(|expected| IssueRow {
+                expected,
+                label: self.frequency.issue_label(expected),
+                status: self.status(expected, today),
+                issue: self.issue(expected).cloned(),
+            })
+            .collect()
+    }
+
+    /// The first issue due on or after `today` that hasn't arrived yet.
+    pub fn next_expected(&self, today: NaiveDate) -> Option<NaiveDate> {
+        self.expected_dates(today).into_iter().find(|&d| d >= today && self.status(d, today) != IssueStatus::Received)
+    }
+
+    /// Issues late or already claimed and still missing.
+    pub fn missing(&self, today: NaiveDate) -> usize {
+        self.expected_dates(today)
+            .into_iter()
+            .filter(|&d| matches!(self.status(d, today), IssueStatus::Late | IssueStatus::Claimed))
+            .count()
+    }
+}
+
+impl Library {
+    /// The date on the library's own calendar.
+    pub fn today(&self) -> NaiveDate {
+        self.clock.now().with_timezone(&Local).date_naive()
+    }
+
+    pub fn add_subscription(&mut self, new: NewSubscription) -> Result<u32, String> {
+        self.only_at_server()?;
+        let title = new.title.trim();
+        if title.is_empty() {
+            return Err(tr!("Title cannot be empty"));
+        }
+        if self.serials.iter().any(|s| s.title.eq_ignore_ascii_case(title)) {
+            return Err(tr!("There is already a subscription to {}", title));
+        }
+        let id = self.serials.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        self.serials.push(Subscription {
+            id,
+            title: title.to_string(),
+            issn: new.issn,
+            supplier: new.supplier.trim().to_string(),
+            frequency: new.frequency,
+            first_issue: new.first_issue,
+            call_number: new.call_number.trim().to_string(),
+            issues: Vec::new(),
+        });
+        tracing::info!(subscription_id = id, "Subscription added");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Ends a subscription. Issues already checked in stay in the catalog.
+    pub fn remove_subscription(&mut self, id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let index = self.serials.iter().position(|s| s.id == id).ok_or_else(|| tr!("Subscription not found"))?;
+        self.serials.remove(index);
+        tracing::info!(subscription_id = id, "Subscription removed");
+        self.dirty = true;
+        Ok(())
+    }
+
+    // The stored record of an issue, added the first time something
+    // happens to it
+    fn serial_issue(&mut self, id: u32, expected: NaiveDate) -> Result<&mut SerialIssue, String> {
+        let today = self.today();
+        let sub = self.serials.iter_mut().find(|s| s.id == id).ok_or_else(|| tr!("Subscription not found"))?;
+        if !sub.expected_dates(today).contains(&expected) {
+            return Err(tr!("No issue of {} is expected on {}", sub.title, expected));
+        }
+        let index = match sub.issues.iter().position(|i| i.expected == expected) {
+            Some(index) => index,
+            None => {
+                sub.issues.push(SerialIssue { expected, received_at: None, item_id: None, claimed_at: Vec::new() });
+                sub.issues.len() - 1
+            }
+        };
+        Ok(&mut sub.issues[index])
+    }
+
+    /// Records the arrival of an issue and adds it to the catalog as an
+    /// item of its own with one copy, so it can be lent. Returns the item's
+    /// ID.
+    pub fn check_in_issue(&mut self, id: u32, expected: NaiveDate) -> Result<u32, String> {
+        self.only_at_server()?;
+        if self.serial_issue(id, expected)?.received_at.is_some() {
+            return Err(tr!("That issue is already checked in"));
+        }
+        let sub = self.serials.iter().find(|s| s.id == id).unwrap();
+        let label = sub.frequency.issue_label(expected);
+        let new = NewItem {
+            details: ItemDetails {
+                title: format!("{} ({})", sub.title, label),
+                author: None,
+                year: expected.year() as u32,
+                edition: label,
+                format: ISSUE_FORMAT.to_string(),
+                desc: sub.issn.as_ref().map_or(String::new(), |issn| format!("ISSN {}", issn)),
+            },
+            isbn: None,
+            call_number: sub.call_number.clone(),
+            tags: Vec::new(),
+            copies: 1,
+        };
+        let item_id = self.add_item(new)?;
+
+        let now = self.clock.now();
+        let issue = self.serial_issue(id, expected)?;
+        issue.received_at = Some(now);
+        issue.item_id = Some(item_id);
+        tracing::info!(subscription_id = id, %expected, item_id, "Issue checked in");
+        self.dirty = true;
+        Ok(item_id)
+    }
+
+    /// Records that a missing issue was claimed from the supplier. Issues
+    /// can be claimed again if the first claim brings nothing.
+    pub fn claim_issue(&mut self, id: u32, expected: NaiveDate) -> Result<(), String> {
+        self.only_at_server()?;
+        let today = self.today();
+        let now = self.clock.now();
+        let sub = self.serials.iter().find(|s| s.id == id).ok_or_else(|| tr!("Subscription not found"))?;
+        match sub.status(expected, today) {
+            IssueStatus::Late | IssueStatus::Claimed => {}
+            IssueStatus::Received => return Err(tr!("That issue is already checked in")),
+            IssueStatus::Expected => {
+                return Err(tr!("Issues can only be claimed {} days after they were expected", CLAIM_AFTER_DAYS));
+            }
+        }
+        self.serial_issue(id, expected)?.claimed_at.push(now);
+        tracing::info!(subscription_id = id, %expected, "Issue claimed");
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// A list of every late or claimed issue for the claim letters, grouped
+    /// by supplier.
+    pub fn claims_text(&self) -> String {
+        let today = self.today();
+        let mut subs: Vec<&Subscription> = self.serials.iter().filter(|s| s.missing(today) > 0).collect();
+        subs.sort_by(|a, b| a.supplier.cmp(&b.supplier).then_with(|| a.title.cmp(&b.title)));
+        let mut text = String::new();
+        let mut supplier = None;
+        for sub in subs {
+            if supplier != Some(&sub.supplier) {
+                text.push_str(&format!("\n{}\n", if sub.supplier.is_empty() { tr!("Unknown supplier") } else { sub.supplier.clone() }));
+                supplier = Some(&sub.supplier);
+            }
+            for row in sub.issue_rows(today) {
+                if matches!(row.status, IssueStatus::Late | IssueStatus::Claimed) {
+                    let issn = sub.issn.as_ref().map_or(String::new(), |issn| format!(" (ISSN {})", issn));
+                    text.push_str(&format!("  {}{}: {}\n", sub.title, issn, row.label));
+                }
+            }
+        }
+        text.trim_start().to_string()
+    }
+}
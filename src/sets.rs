@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+
+use crate::copies::CopyStatus;
+use crate::i18n::tr;
+use crate::{Library, LiItem};
+
+/// How a catalog entry belongs with others.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+pub enum ItemLink {
+    // One volume of a multi-volume set; each volume is lent and held on
+    // its own
+    Volume { set_id: u32, number: u32 },
+    // A title bound into another item's physical volume. It has no copies
+    // of its own while bound; `copies` is what it had before, given back
+    // when it is unbound.
+    BoundWith { host_id: u32, copies: u32 },
+}
+
+/// A work catalogued as one item per volume.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct VolumeSet {
+    pub id: u32,
+    pub title: String,
+}
+
+impl LiItem {
+    /// Where the item belongs, for the catalog, e.g. "Vol. 2 of set 3".
+    /// Sorting on it keeps the volumes of a set together.
+    pub fn link_text(&self) -> String {
+        match self.link {
+            None => String::new(),
+            Some(ItemLink::Volume { set_id, number }) => tr!("Vol. {} of set {}", number, set_id),
+            Some(ItemLink::BoundWith { host_id, .. }) => tr!("Bound in item {}", host_id),
+        }
+    }
+
+    pub fn volume_number(&self) -> Option<u32> {
+        match self.link {
+            Some(ItemLink::Volume { number, .. }) => Some(number),
+            _ => None,
+        }
+    }
+}
+
+impl Library {
+    /// The item lent, held and returned for `item_id`: the host volume for
+    /// a title bound with others, otherwise the item itself.
+    pub fn physical_unit(&self, item_id: u32) -> u32 {
+        match self.items.get(&item_id).and_then(|i| i.link) {
+            Some(ItemLink::BoundWith { host_id, .. }) => host_id,
+            _ => item_id,
+        }
+    }
+
+    pub fn volume_set(&self, set_id: u32) -> Option<&VolumeSet> {
+        self.volume_sets.iter().find(|s| s.id == set_id)
+    }
+
+    /// The volumes of a set, in volume order.
+    pub fn volumes(&self, set_id: u32) -> Vec<&LiItem> {
+        let mut volumes: Vec<&LiItem> = self
+            .items
+            .values()
+            .filter(|i| matches!(i.link, Some(ItemLink::Volume { set_id: s, .. }) if s == set_id))
+            .collect();
+        volumes.sort_by_key(|i| (i.volume_number(), i.id));
+        volumes
+    }
+
+    /// The titles bound into `host_id`, lowest ID first.
+    pub fn bound_in(&self, host_id: u32) -> Vec<&LiItem> {
+        let mut bound: Vec<&LiItem> = self
+            .items
+            .values()
+            .filter(|i| matches!(i.link, Some(ItemLink::BoundWith { host_id: h, .. }) if h == host_id))
+            .collect();
+        bound.sort_by_key(|i| i.id);
+        bound
+    }
+
+    pub fn create_volume_set(&mut self, title: &str) -> Result<u32, String> {
+        self.only_at_server()?;
+        let title = title.trim();
+        if title.is_empty() {
+            return Err(tr!("A set needs a title"));
+        }
+        let id = self.volume_sets.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        self.volume_sets.push(VolumeSet { id, title: title.to_string() });
+        tracing::info!(set_id = id, "Volume set created");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Makes `item_id` volume `number` of a set. An item can be in one set
+    /// only, and a set has one item per volume number.
+    pub fn add_to_set(&mut self, item_id: u32, set_id: u32, number: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if self.volume_set(set_id).is_none() {
+            return Err(tr!("Set not found"));
+        }
+        if number == 0 {
+            return Err(tr!("Volumes are numbered from 1"));
+        }
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        if matches!(item.link, Some(ItemLink::BoundWith { .. })) {
+            return Err(tr!("Item is bound with another; unbind it first"));
+        }
+        if let Some(other) = self.volumes(set_id).into_iter().find(|i| i.id != item_id && i.volume_number() == Some(number)) {
+            return Err(tr!("Volume {} of the set is already item {}", number, other.id));
+        }
+        self.items.get_mut(&item_id).unwrap().link = Some(ItemLink::Volume { set_id, number });
+        tracing::info!(item_id, set_id, number, "Item added to volume set");
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+
+    /// Records `item_id` as bound into `host_id`'s physical volume, so
+    /// issuing, returning or placing a hold on it acts on the host. Only
+    /// while all its copies are on the shelf, as they become part of the
+    /// host.
+    pub fn bind_with(&mut self, item_id: u32, host_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if item_id == host_id {
+            return Err(tr!("Pick two different items"));
+        }
+        let host = self.items.get(&host_id).ok_or_else(|| tr!("Host item not found"))?;
+        if matches!(host.link, Some(ItemLink::BoundWith { .. })) {
+            return Err(tr!("Item {} is itself bound into another item", host_id));
+        }
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        if item.link.is_some() {
+            return Err(tr!("Item already belongs to a set or another item"));
+        }
+        if !self.bound_in(item_id).is_empty() {
+            return Err(tr!("Other items are bound into this one"));
+        }
+        if item.avail_copies != item.copies || item.copies_in(CopyStatus::Available) != item.copies {
+            return Err(tr!("All copies must be on the shelf"));
+        }
+        if self.holds.iter().any(|h| h.item_id == item_id && h.is_active()) {
+            return Err(tr!("Item has holds"));
+        }
+        if self.in_transit(item_id) {
+            return Err(tr!("Item has an open transfer"));
+        }
+
+        let item = self.items.get_mut(&item_id).unwrap();
+        item.link = Some(ItemLink::BoundWith { host_id, copies: item.copies });
+        item.copies = 0;
+        item.avail_copies = 0;
+        tracing::info!(item_id, host_id, "Item bound with host");
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+
+    /// Takes an item out of its set or host volume. An unbound title gets
+    /// back the copies it had, on the shelf.
+    pub fn unlink_item(&mut self, item_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let item = self.items.get_mut(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        let link = item.link.take().ok_or_else(|| tr!("Item is not part of a set or bound with another"))?;
+        if let ItemLink::BoundWith { copies, .. } = link {
+            item.copies = copies;
+            item.avail_copies = copies;
+        }
+        // A set without volumes is of no use
+        if let ItemLink::Volume { set_id, .. } = link {
+            if self.volumes(set_id).is_empty() {
+                self.volume_sets.retain(|s| s.id != set_id);
+            }
+        }
+        tracing::info!(item_id, "Item unlinked");
+        self.dirty = true;
+        self.item_changed(item_id);
+        Ok(())
+    }
+}
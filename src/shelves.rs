@@ -0,0 +1,42 @@
+use std::collections::HashSet;
+
+use chrono::Duration;
+
+use crate::transactions::TransactionKind;
+use crate::{Library, LiItem};
+
+pub const NEW_ARRIVAL_DAYS: i64 = 30;
+pub const JUST_RETURNED_DAYS: i64 = 3;
+const JUST_RETURNED_LIMIT: usize = 50;
+
+impl Library {
+    /// Items added in the last `NEW_ARRIVAL_DAYS`, newest first.
+    pub fn new_arrivals(&self) -> Vec<LiItem> {
+        let since = self.clock.now() - Duration::days(NEW_ARRIVAL_DAYS);
+        let mut items: Vec<LiItem> = self
+            .items
+            .values()
+            .filter(|item| item.added_at.is_some_and(|at| at >= since))
+            .cloned()
+            .collect();
+        items.sort_by(|a, b| b.added_at.cmp(&a.added_at).then(a.id.cmp(&b.id)));
+        items
+    }
+
+    /// Items returned in the last `JUST_RETURNED_DAYS` that are on the shelf,
+    /// most recently returned first.
+    pub fn just_returned(&self) -> Vec<LiItem> {
+        let since = self.clock.now() - Duration::days(JUST_RETURNED_DAYS);
+        let mut seen = HashSet::new();
+        self.transactions
+            .iter()
+            .rev()
+            .take_while(|tx| tx.at >= since)
+            .filter(|tx| tx.kind == TransactionKind::Return && seen.insert(tx.item_id))
+            .filter_map(|tx| self.items.get(&tx.item_id))
+            .filter(|item| item.avail_copies > 0)
+            .take(JUST_RETURNED_LIMIT)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,275 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::{Arc, RwLock};
+
+use gtk::prelude::*;
+use gtk::{gio, glib, Application, ApplicationWindow, Box as GtkBox, Entry, Label, ScrolledWindow};
+
+use crate::i18n::tr;
+use crate::{dialogs, persist, status, Library};
+
+// A palette entry: what is shown and the detailed action it activates
+struct Command {
+    label: String,
+    action: String,
+}
+
+/// The pages the shortcuts jump to.
+pub struct ShortcutPages {
+    pub issue: GtkBox,
+    pub returns: GtkBox,
+    pub catalog: GtkBox,
+}
+
+// Switches to `page` and focuses its first entry
+fn show_page(stack: &adw::ViewStack, page: &impl IsA<gtk::Widget>) {
+    stack.set_visible_child(page);
+    page.child_focus(gtk::DirectionType::TabForward);
+}
+
+fn nth_page(stack: &adw::ViewStack, number: u32) -> Option<adw::ViewStackPage> {
+    stack.pages().item(number).and_downcast::<adw::ViewStackPage>()
+}
+
+/// Adds the window actions behind the keyboard shortcuts and binds their
+/// accelerators: Ctrl+1..9 switch pages, Ctrl+F searches the catalog,
+/// Ctrl+I and Ctrl+R go to issuing and returning, Ctrl+S saves and Ctrl+K
+/// opens the command palette.
+pub fn install(
+    app: &Application,
+    window: &ApplicationWindow,
+    stack: &adw::ViewStack,
+    library: Arc<RwLock<Library>>,
+    pages: ShortcutPages,
+) {
+    let show_page_action = gio::SimpleAction::new("show-page", Some(glib::VariantTy::INT32));
+    show_page_action.connect_activate(glib::clone!(
+        #[weak]
+        stack,
+        move |_, target| {
+            let Some(number) = target.and_then(|t| t.get::<i32>()) else { return };
+            if let Some(page) = nth_page(&stack, number as u32) {
+                show_page(&stack, &page.child());
+            }
+        }
+    ));
+    window.add_action(&show_page_action);
+    for number in 0..stack.pages().n_items().min(9) {
+        app.set_accels_for_action(&format!("win.show-page({})", number), &[&format!("<Control>{}", number + 1)]);
+    }
+
+    for (name, page, accel) in [
+        ("focus-search", pages.catalog, "<Control>f"),
+        ("issue", pages.issue, "<Control>i"),
+        ("return", pages.returns, "<Control>r"),
+    ] {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate(glib::clone!(
+            #[weak]
+            stack,
+            move |_, _| show_page(&stack, &page)
+        ));
+        window.add_action(&action);
+        app.set_accels_for_action(&format!("win.{}", name), &[accel]);
+    }
+
+    let save_action = gio::SimpleAction::new("save", None);
+    save_action.connect_activate(glib::clone!(
+        #[weak]
+        window,
+        move |_, _| {
+            match library.write().unwrap().save(Path::new(persist::STATE_PATH)) {
+                Ok(()) => status::success(&window, &tr!("Library saved")),
+                Err(e) => dialogs::show_error(&window, &tr!("Failed to save the library"), &e.to_string()),
+            }
+        }
+    ));
+    window.add_action(&save_action);
+    app.set_accels_for_action("win.save", &["<Control>s"]);
+
+    let palette_action = gio::SimpleAction::new("command-palette", None);
+    palette_action.connect_activate(glib::clone!(
+        #[weak]
+        app,
+        #[weak]
+        window,
+        #[weak]
+        stack,
+        move |_, _| show_command_palette(&app, &window, &stack)
+    ));
+    window.add_action(&palette_action);
+    app.set_accels_for_action("win.command-palette", &["<Control>k"]);
+}
+
+// Everything the palette offers: one entry per page, then the other window
+// actions that are currently enabled.
+fn commands(window: &ApplicationWindow, stack: &adw::ViewStack) -> Vec<Command> {
+    let mut commands: Vec<Command> = (0..stack.pages().n_items())
+        .filter_map(|number| {
+            let title = nth_page(stack, number)?.title()?;
+            Some(Command {
+                label: tr!("Go to {}", title),
+                action: format!("win.show-page({})", number),
+            })
+        })
+        .collect();
+    for (name, label) in [
+        ("focus-search", tr!("Search Catalog")),
+        ("issue", tr!("Issue Items")),
+        ("return", tr!("Return Items")),
+        ("save", tr!("Save Library")),
+        ("restore-backup", tr!("Restore from Backup...")),
+        ("check-database", tr!("Check Database...")),
+        ("lock", tr!("Lock Screen")),
+    ] {
+        if window.lookup_action(name).is_some_and(|a| a.is_enabled()) {
+            commands.push(Command {
+                label,
+                action: format!("win.{}", name),
+            });
+        }
+    }
+    commands
+}
+
+// Lower is better. Every query character has to appear in the label in
+// order; each skipped label character costs a point.
+fn fuzzy_score(query: &str, label: &str) -> Option<usize> {
+    let label: Vec<char> = label.to_lowercase().chars().collect();
+    let mut start = 0;
+    let mut score = 0;
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = start + label[start..].iter().position(|&l| l == c)?;
+        score += found - start;
+        start = found + 1;
+    }
+    Some(score)
+}
+
+/// A searchable list of commands. Typing narrows it down; Enter runs the
+/// best match.
+fn show_command_palette(app: &Application, window: &ApplicationWindow, stack: &adw::ViewStack) {
+    let dialog = gtk::Dialog::with_buttons(
+        Some(&tr!("Command Palette")),
+        Some(window),
+        gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+        &[],
+    );
+    dialog.set_default_width(400);
+
+    let content = dialog.content_area();
+    content.set_spacing(10);
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let entry = Entry::new();
+    entry.set_placeholder_text(Some(&tr!("Type a command...")));
+    let list = gtk::ListBox::new();
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_min_content_height(250);
+    scrolled_window.set_child(Some(&list));
+    content.append(&entry);
+    content.append(&scrolled_window);
+
+    let commands = Rc::new(commands(window, stack));
+    // Commands in the order they are listed
+    let shown: Rc<RefCell<Vec<usize>>> = Rc::default();
+
+    let fill = Rc::new(glib::clone!(
+        #[strong]
+        commands,
+        #[strong]
+        shown,
+        #[weak]
+        app,
+        #[weak]
+        list,
+        move |query: &str| {
+            while let Some(child) = list.first_child() {
+                list.remove(&child);
+            }
+            let mut matches: Vec<(usize, usize)> = commands
+                .iter()
+                .enumerate()
+                .filter_map(|(i, c)| fuzzy_score(query, &c.label).map(|score| (score, i)))
+                .collect();
+            matches.sort();
+            *shown.borrow_mut() = matches.iter().map(|&(_, i)| i).collect();
+
+            for &(_, i) in &matches {
+                let command = &commands[i];
+                let label = Label::new(Some(&command.label));
+                label.set_xalign(0.0);
+                label.set_hexpand(true);
+                let accel = app.accels_for_action(&command.action).first().and_then(|accel| {
+                    let (key, mods) = gtk::accelerator_parse(accel)?;
+                    Some(gtk::accelerator_get_label(key, mods))
+                });
+                let accel_label = Label::new(accel.as_deref());
+                accel_label.add_css_class("dim-label");
+                let row = GtkBox::new(gtk::Orientation::Horizontal, 10);
+                row.append(&label);
+                row.append(&accel_label);
+                list.append(&row);
+            }
+            if let Some(first) = list.row_at_index(0) {
+                list.select_row(Some(&first));
+            }
+        }
+    ));
+    fill("");
+
+    let run = Rc::new(glib::clone!(
+        #[strong]
+        commands,
+        #[weak]
+        window,
+        #[weak]
+        dialog,
+        move |index: usize| {
+            dialog.close();
+            let Ok((name, target)) = gio::Action::parse_detailed_name(&commands[index].action) else { return };
+            if let Err(e) = window.activate_action(&name, target.as_ref()) {
+                tracing::warn!("Command failed: {}", e);
+            }
+        }
+    ));
+
+    entry.connect_changed(glib::clone!(
+        #[strong]
+        fill,
+        move |entry| fill(&entry.text())
+    ));
+    entry.connect_activate(glib::clone!(
+        #[strong]
+        shown,
+        #[strong]
+        run,
+        #[weak]
+        list,
+        move |_| {
+            let position = list.selected_row().map_or(0, |row| row.index() as usize);
+            let index = shown.borrow().get(position).copied();
+            if let Some(index) = index {
+                run(index);
+            }
+        }
+    ));
+    list.connect_row_activated(glib::clone!(
+        #[strong]
+        shown,
+        #[strong]
+        run,
+        move |_, row| {
+            let index = shown.borrow().get(row.index() as usize).copied();
+            if let Some(index) = index {
+                run(index);
+            }
+        }
+    ));
+
+    dialog.show();
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+use chrono::{DateTime, Local, Utc};
+
+use crate::config::Config;
+use crate::fines::FineStatus;
+use crate::Library;
+
+// Every SIP2 message ends with a carriage return
+const TERMINATOR: u8 = b'\r';
+
+// Length of the fixed fields, message code included, before the variable
+// "XXvalue|" fields start
+fn fixed_length(code: &str) -> Option<usize> {
+    match code {
+        "93" => Some(4),
+        "99" => Some(10),
+        "23" => Some(23),
+        "11" => Some(40),
+        "09" => Some(39),
+        "29" => Some(40),
+        "35" => Some(20),
+        "97" => Some(2),
+        _ => None,
+    }
+}
+
+// SIP2 timestamps: YYYYMMDD, four spaces for a local time zone, HHMMSS
+fn sip_time(at: DateTime<Utc>) -> String {
+    at.with_timezone(&Local).format("%Y%m%d    %H%M%S").to_string()
+}
+
+fn yes_no(value: bool) -> char {
+    if value { 'Y' } else { 'N' }
+}
+
+fn checksum(text: &str) -> String {
+    let sum = text.bytes().fold(0u16, |sum, b| sum.wrapping_add(b as u16));
+    format!("{:04X}", sum.wrapping_neg())
+}
+
+// Field values can't contain the field separator
+fn field(id: &str, value: &str) -> String {
+    format!("{}{}|", id, value.replace('|', " "))
+}
+
+struct Request<'a> {
+    code: &'a str,
+    fields: HashMap<&'a str, &'a str>,
+    // Sequence number, when the terminal uses error detection
+    sequence: Option<char>,
+}
+
+impl<'a> Request<'a> {
+    fn parse(line: &'a str) -> Option<Request<'a>> {
+        let code = line.get(..2)?;
+        let rest = line.get(fixed_length(code)?..)?;
+        // "AYnAZxxxx" follows the last field
+        let (body, trailer) = rest.rsplit_once('|').map_or(("", rest), |(body, trailer)| (body, trailer));
+        let fields = body.split('|').filter(|f| f.len() >= 2).map(|f| f.split_at(2)).collect();
+        let sequence = trailer.strip_prefix("AY").and_then(|t| t.chars().next());
+        Some(Request { code, fields, sequence })
+    }
+
+    fn get(&self, id: &str) -> &'a str {
+        self.fields.get(id).copied().unwrap_or("")
+    }
+}
+
+/// A self-check machine or sorter talking to the library over SIP2.
+struct Session {
+    library: Arc<RwLock<Library>>,
+    institution: String,
+    username: String,
+    password: String,
+    // Who is logged in, for the transaction log
+    login: Option<String>,
+    last_response: String,
+}
+
+// Title and due date of a loan just made or renewed. A title bound with
+// others is lent as its host volume, so the loan is under the host's ID.
+fn loan_details(lib: &Library, member_id: u32, item_id: u32) -> Result<(String, String), String> {
+    let item_id = lib.physical_unit(item_id);
+    let inst = lib
+        .members
+        .get(&member_id)
+        .and_then(|m| m.items.get(&item_id))
+        .ok_or_else(|| "Loan not found".to_string())?;
+    Ok((inst.title.to_string(), inst.due_text()))
+}
+
+impl Session {
+    fn respond(&mut self, line: &str) -> Option<String> {
+        let request = Request::parse(line)?;
+        if request.code == "97" {
+            return Some(self.last_response.clone());
+        }
+        let now = self.library.read().unwrap().clock.now();
+        let mut response = match request.code {
+            "93" => self.login(&request),
+            "99" => self.status(now),
+            _ if self.login.is_none() => {
+                tracing::warn!(code = request.code, "SIP2 request before login");
+                return None;
+            }
+            "23" => self.patron_status(&request, now),
+            "11" => self.checkout(&request, now),
+            "09" => self.checkin(&request, now),
+            "29" => self.renew(&request, now),
+            "35" => format!("36Y{}{}{}", sip_time(now), field("AO", &self.institution), field("AA", request.get("AA"))),
+            _ => return None,
+        };
+        if let Some(sequence) = request.sequence {
+            response.push_str(&format!("AY{}AZ", sequence));
+            response.push_str(&checksum(&response));
+        }
+        self.last_response = response.clone();
+        Some(response)
+    }
+
+    fn login(&mut self, request: &Request) -> String {
+        let (user, password) = (request.get("CN"), request.get("CO"));
+        let ok = user == self.username && password == self.password;
+        if ok {
+            self.login = Some(user.to_string());
+            tracing::info!(user, "SIP2 login");
+        } else {
+            tracing::warn!(user, "SIP2 login failed");
+        }
+        format!("94{}", if ok { '1' } else { '0' })
+    }
+
+    fn status(&self, now: DateTime<Utc>) -> String {
+        // Patron status, checkout, checkin, status, resend, login, end
+        // session and renew, in the order the protocol fixes
+        let supported = "YYYNYYYNYNNNNNYN";
+        format!(
+            "98YYYYNN030003{}2.00{}{}",
+            sip_time(now),
+            field("AO", &self.institution),
+            field("BX", supported)
+        )
+    }
+
+    // Runs `change` with the transaction log crediting this terminal
+    fn as_terminal<T>(&self, change: impl FnOnce(&mut Library) -> T) -> T {
+        let mut lib = self.library.write().unwrap();
+        let user = format!("SIP2 {}", self.login.as_deref().unwrap_or(""));
+        let staff = std::mem::replace(&mut lib.current_user, user.trim().to_string());
+        let result = change(&mut *lib);
+        lib.current_user = staff;
+        result
+    }
+
+    fn patron_status(&self, request: &Request, now: DateTime<Utc>) -> String {
+        let patron = request.get("AA");
+        let lib = self.library.read().unwrap();
+        let member = patron.parse::<u32>().ok().and_then(|id| lib.members.get(&id));
+        let expired = member.is_some_and(|m| m.expires_at.is_some_and(|at| at <= now));
+        let fines: u64 = member.map_or(0, |m| {
+            m.fines.iter().filter(|f| f.status == FineStatus::Outstanding).map(|f| f.amount_cents).sum()
+        });
+        // Charge and renewal privileges denied
+        let denied = if expired { "YY" } else { "  " };
+        let mut response = format!("24{:<14}000{}", denied, sip_time(now));
+        response.push_str(&field("AO", &self.institution));
+        response.push_str(&field("AA", patron));
+        response.push_str(&field("AE", member.map_or("", |m| m.name.as_str())));
+        response.push_str(&field("BL", &yes_no(member.is_some()).to_string()));
+        if fines > 0 {
+            response.push_str(&field("BV", &format!("{}.{:02}", fines / 100, fines % 100)));
+        }
+        if member.is_none() {
+            response.push_str(&field("AF", "Unknown patron"));
+        } else if expired {
+            response.push_str(&field("AF", "Membership expired"));
+        }
+        response
+    }
+
+    fn checkout(&self, request: &Request, now: DateTime<Utc>) -> String {
+        let (patron, item) = (request.get("AA"), request.get("AB"));
+        let result = match (patron.parse::<u32>(), item.parse::<u32>()) {
+            (Ok(member_id), Ok(item_id)) => self.as_terminal(|lib| {
+                lib.book_issue(item_id, member_id.to_string())?;
+                loan_details(lib, member_id, item_id)
+            }),
+            // New members can't sign up at a kiosk
+            _ => Err("Unknown patron or item".to_string()),
+        };
+        let ok = result.is_ok();
+        let mut response = format!("12{}NU{}{}", if ok { '1' } else { '0' }, yes_no(ok), sip_time(now));
+        response.push_str(&field("AO", &self.institution));
+        response.push_str(&field("AA", patron));
+        response.push_str(&field("AB", item));
+        match result {
+            Ok((title, due)) => {
+                response.push_str(&field("AJ", &title));
+                response.push_str(&field("AH", &due));
+            }
+            Err(e) => {
+                response.push_str(&field("AJ", ""));
+                response.push_str(&field("AF", &e));
+            }
+        }
+        response
+    }
+
+    // Check-in only names the item, so the loan that has been out longest
+    // is the one returned
+    fn checkin(&self, request: &Request, now: DateTime<Utc>) -> String {
+        let item = request.get("AB");
+        let result = match item.parse::<u32>() {
+            Ok(item_id) => self.as_terminal(|lib| {
+                let item_id = lib.physical_unit(item_id);
+                let member_id = lib
+                    .members
+                    .values()
+                    .filter_map(|m| m.items.get(&item_id).map(|inst| (inst.issued_at, m.id)))
+                    .min()
+                    .map(|(_, id)| id)
+                    .ok_or_else(|| "Item is not checked out".to_string())?;
+                let (item, fine) = lib.book_return(item_id, member_id)?;
+                let location = if item.shelf_location.is_empty() {
+                    item.branch.clone()
+                } else {
+                    format!("{}, {}", item.branch, item.shelf_location)
+                };
+                Ok((item.title.to_string(), location, fine))
+            }),
+            Err(_) => Err("Unknown item".to_string()),
+        };
+        let ok = result.is_ok();
+        let alert = matches!(result, Ok((_, _, Some(_))));
+        let mut response = format!("10{}YU{}{}", if ok { '1' } else { '0' }, yes_no(alert), sip_time(now));
+        response.push_str(&field("AO", &self.institution));
+        response.push_str(&field("AB", item));
+        match result {
+            Ok((title, location, fine)) => {
+                response.push_str(&field("AQ", &location));
+                response.push_str(&field("AJ", &title));
+                if let Some(fine) = fine {
+                    let message = format!("Returned late, fine {}.{:02}", fine.amount_cents / 100, fine.amount_cents % 100);
+                    response.push_str(&field("AF", &message));
+                }
+            }
+            Err(e) => {
+                response.push_str(&field("AQ", ""));
+                response.push_str(&field("AF", &e));
+            }
+        }
+        response
+    }
+
+    fn renew(&self, request: &Request, now: DateTime<Utc>) -> String {
+        let (patron, item) = (request.get("AA"), request.get("AB"));
+        let result = match (patron.parse::<u32>(), item.parse::<u32>()) {
+            (Ok(member_id), Ok(item_id)) => self.as_terminal(|lib| {
+                lib.renew_loan(item_id, member_id)?;
+                loan_details(lib, member_id, item_id)
+            }),
+            _ => Err("Unknown patron or item".to_string()),
+        };
+        let ok = result.is_ok();
+        let mut response = format!("30{}{}U{}{}", if ok { '1' } else { '0' }, yes_no(ok), yes_no(ok), sip_time(now));
+        response.push_str(&field("AO", &self.institution));
+        response.push_str(&field("AA", patron));
+        response.push_str(&field("AB", item));
+        match result {
+            Ok((title, due)) => {
+                response.push_str(&field("AJ", &title));
+                response.push_str(&field("AH", &due));
+            }
+            Err(e) => {
+                response.push_str(&field("AJ", ""));
+                response.push_str(&field("AF", &e));
+            }
+        }
+        response
+    }
+}
+
+fn serve_terminal(stream: TcpStream, mut session: Session) -> std::io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(TERMINATOR, &mut line)? == 0 {
+            return Ok(());
+        }
+        let text = String::from_utf8_lossy(&line);
+        // Some terminals send CR LF
+        let text = text.trim_matches(|c| c == '\r' || c == '\n');
+        if text.is_empty() {
+            continue;
+        }
+        match session.respond(text) {
+            Some(response) => {
+                writer.write_all(response.as_bytes())?;
+                writer.write_all(&[TERMINATOR])?;
+            }
+            None => tracing::warn!(message = text, "Unsupported SIP2 message"),
+        }
+    }
+}
+
+/// Starts the SIP2 server on `config.sip2_port` unless that is zero. Each
+/// terminal gets its own thread and works directly on the shared library.
+/// Terminals must log in, so the server doesn't start without a username
+/// and password to log in with.
+pub fn start(library: Arc<RwLock<Library>>, config: &Config) {
+    if config.sip2_port == 0 {
+        return;
+    }
+    if config.sip2_username.is_empty() || config.sip2_password.is_empty() {
+        tracing::error!(port = config.sip2_port, "SIP2 server not started: set sip2_username and sip2_password first");
+        return;
+    }
+    let address = format!("0.0.0.0:{}", config.sip2_port);
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!(address, "SIP2 server could not start: {}", e);
+            return;
+        }
+    };
+    tracing::info!(address, "SIP2 server listening");
+    let (institution, username, password) =
+        (config.sip2_institution.clone(), config.sip2_username.clone(), config.sip2_password.clone());
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else { continue };
+            let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+            tracing::info!(peer, "SIP2 terminal connected");
+            let session = Session {
+                library: library.clone(),
+                institution: institution.clone(),
+                username: username.clone(),
+                password: password.clone(),
+                login: None,
+                last_response: String::new(),
+            };
+            thread::spawn(move || {
+                if let Err(e) = serve_terminal(stream, session) {
+                    tracing::warn!(peer, "SIP2 connection closed: {}", e);
+                }
+            });
+        }
+    });
+}
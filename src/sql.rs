@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection};
+
+use crate::i18n::tr;
+use crate::pii;
+use crate::Library;
+
+// Most rows a query shows, so a careless join can't hang the window
+pub const MAX_ROWS: usize = 10_000;
+
+const SCHEMA: &str = "
+CREATE TABLE items (
+    id INTEGER PRIMARY KEY, title TEXT, author TEXT, year INTEGER, format TEXT, isbn TEXT,
+    branch TEXT, shelf_location TEXT, call_number TEXT, copies INTEGER, avail_copies INTEGER,
+    added_at TEXT
+);
+CREATE TABLE members (
+    id INTEGER PRIMARY KEY, name TEXT, email TEXT, membership TEXT, joined_at TEXT,
+    expires_at TEXT, anonymized_at TEXT
+);
+CREATE TABLE loans (
+    item_id INTEGER, member_id INTEGER, issued_at TEXT, due_date TEXT, renewals INTEGER
+);
+CREATE TABLE fines (
+    member_id INTEGER, fine_id INTEGER, item_id INTEGER, days_late INTEGER, amount_cents INTEGER,
+    assessed_at TEXT, status TEXT
+);
+CREATE TABLE holds (
+    id INTEGER PRIMARY KEY, item_id INTEGER, member_id INTEGER, placed_at TEXT, status TEXT,
+    shelved_at TEXT, level TEXT
+);
+CREATE TABLE transactions (
+    at TEXT, kind TEXT, item_id INTEGER, member_id INTEGER, user TEXT, amount_cents INTEGER
+);
+";
+
+/// The columns and rows a query returned, as text.
+#[derive(Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    // Set when there were more than MAX_ROWS rows
+    pub truncated: bool,
+}
+
+impl QueryResult {
+    /// Any table can hold member data, so results are exported like other
+    /// member data, encrypted with `passphrase` if given.
+    pub fn export_csv(&self, path: &Path, passphrase: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut wtr = csv::Writer::from_writer(Vec::new());
+        wtr.write_record(&self.columns)?;
+        for row in &self.rows {
+            wtr.write_record(row)?;
+        }
+        pii::write_export(path, &wtr.into_inner().map_err(|e| e.into_error())?, passphrase)
+    }
+}
+
+// Dates are stored as RFC 3339 text, which sorts and compares as it should
+pub(crate) fn date(at: DateTime<Utc>) -> String {
+    at.to_rfc3339()
+}
+
+fn text(value: ValueRef) -> String {
+    match value {
+        ValueRef::Null => String::new(),
+        ValueRef::Integer(n) => n.to_string(),
+        ValueRef::Real(x) => x.to_string(),
+        ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+        ValueRef::Blob(b) => tr!("<{} bytes>", b.len()),
+    }
+}
+
+impl Library {
+    // A throwaway in-memory database holding a copy of the library, so
+    // queries see the state as of now and can't change it
+    pub(crate) fn to_sqlite(&self) -> rusqlite::Result<Connection> {
+        let mut db = Connection::open_in_memory()?;
+        db.execute_batch(SCHEMA)?;
+        let tx = db.transaction()?;
+        {
+            let mut insert = tx.prepare("INSERT INTO items VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)")?;
+            for item in self.items.values() {
+                insert.execute(params![
+                    item.id,
+                    &*item.title,
+                    item.author,
+                    item.year,
+                    item.format,
+                    item.isbn,
+                    item.branch,
+                    item.shelf_location,
+                    item.call_number,
+                    item.copies,
+                    item.avail_copies,
+                    item.added_at.map(date),
+                ])?;
+            }
+            let mut insert = tx.prepare("INSERT INTO members VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+            let mut insert_loan = tx.prepare("INSERT INTO loans VALUES (?1, ?2, ?3, ?4, ?5)")?;
+            let mut insert_fine = tx.prepare("INSERT INTO fines VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+            for member in self.members.values() {
+                insert.execute(params![
+                    member.id,
+                    member.name,
+                    member.email,
+                    member.membership.name(),
+                    member.joined_at.map(date),
+                    member.expires_at.map(date),
+                    member.anonymized_at.map(date),
+                ])?;
+                for inst in member.items.values() {
+                    insert_loan.execute(params![inst.id, member.id, date(inst.issued_at), date(inst.due_date), inst.renewed_count])?;
+                }
+                for fine in &member.fines {
+                    insert_fine.execute(params![
+                        member.id,
+                        fine.id,
+                        fine.item_id,
+                        fine.days_late,
+                        fine.amount_cents as i64,
+                        date(fine.assessed_at),
+                        format!("{:?}", fine.status),
+                    ])?;
+                }
+            }
+            let mut insert = tx.prepare("INSERT INTO holds VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")?;
+            for hold in &self.holds {
+                insert.execute(params![
+                    hold.id,
+                    hold.item_id,
+                    hold.member_id,
+                    date(hold.placed_at),
+                    format!("{:?}", hold.status),
+                    hold.shelved_at.map(date),
+                    format!("{:?}", hold.level),
+                ])?;
+            }
+            let mut insert = tx.prepare("INSERT INTO transactions VALUES (?1, ?2, ?3, ?4, ?5, ?6)")?;
+            for t in &self.transactions {
+                insert.execute(params![date(t.at), format!("{:?}", t.kind), t.item_id, t.member_id, t.user, t.amount_cents as i64])?;
+            }
+        }
+        tx.commit()?;
+        db.pragma_update(None, "query_only", true)?;
+        Ok(db)
+    }
+
+    /// Runs one SELECT statement against a copy of the library. Anything
+    /// that would write is refused, so the console can't get around the
+    /// rules issuing, returning and fines go through.
+    pub fn run_query(&self, sql: &str) -> Result<QueryResult, String> {
+        let sql = sql.trim();
+        if sql.is_empty() {
+            return Err(tr!("Enter a SELECT statement"));
+        }
+        let db = self.to_sqlite().map_err(|e| e.to_string())?;
+        let mut stmt = db.prepare(sql).map_err(|e| e.to_string())?;
+        if !stmt.readonly() {
+            tracing::warn!(user = %self.current_user, "Write statement refused in the SQL console");
+            return Err(tr!("Only statements that read, like SELECT, can be run here"));
+        }
+
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut result = QueryResult { columns, ..QueryResult::default() };
+        let mut rows = stmt.query([]).map_err(|e| e.to_string())?;
+        while let Some(row) = rows.next().map_err(|e| e.to_string())? {
+            if result.rows.len() == MAX_ROWS {
+                result.truncated = true;
+                break;
+            }
+            let values = (0..result.columns.len()).map(|i| row.get_ref(i).map(text)).collect::<Result<Vec<_>, _>>();
+            result.rows.push(values.map_err(|e| e.to_string())?);
+        }
+        tracing::info!(user = %self.current_user, rows = result.rows.len(), "SQL console query: {}", sql);
+        Ok(result)
+    }
+}
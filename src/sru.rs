@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::i18n::tr;
+
+// Namespace of MARCXML records, whichever SRU version wraps them
+pub(crate) const MARC_NS: &str = "http://www.loc.gov/MARC21/slim";
+// Records asked for per search; copy cataloging only needs the first few
+const MAX_RECORDS: &str = "10";
+const TIMEOUT: Duration = Duration::from_secs(20);
+
+/// The fields of a MARC bibliographic record that the catalog keeps.
+#[derive(Clone, Default)]
+pub struct MarcRecord {
+    pub isbn: Option<String>,
+    pub title: String,
+    pub author: Option<String>,
+    pub year: u32,
+    pub edition: String,
+    pub desc: String,
+    pub call_number: String,
+    pub tags: Vec<String>,
+}
+
+impl MarcRecord {
+    /// One line for picking the record out of a list of results.
+    pub fn summary(&self) -> String {
+        let mut text = self.title.clone();
+        if let Some(author) = &self.author {
+            text.push_str(&format!(" / {}", author));
+        }
+        if self.year > 0 {
+            text.push_str(&format!(" ({})", self.year));
+        }
+        if let Some(isbn) = &self.isbn {
+            text.push_str(&format!(", ISBN {}", isbn));
+        }
+        text
+    }
+}
+
+// ISBN-10 or ISBN-13, with or without hyphens and spaces
+fn as_isbn(text: &str) -> Option<String> {
+    let isbn: String = text.chars().filter(|c| !matches!(c, '-' | ' ')).collect();
+    let valid = matches!(isbn.len(), 10 | 13)
+        && isbn.char_indices().all(|(i, c)| c.is_ascii_digit() || (i == 9 && isbn.len() == 10 && c.eq_ignore_ascii_case(&'x')));
+    valid.then_some(isbn)
+}
+
+/// The CQL query for what was typed: an ISBN search when it looks like
+/// one, a title search otherwise.
+fn cql_query(text: &str) -> String {
+    match as_isbn(text) {
+        Some(isbn) => format!("bath.isbn={}", isbn),
+        None => format!("dc.title=\"{}\"", text.trim().replace(['"', '\\'], " ")),
+    }
+}
+
+/// Searches the SRU server at `base_url` by ISBN or title and returns the
+/// MARCXML records it finds. Blocks until the server answers.
+pub fn search(base_url: &str, text: &str) -> Result<Vec<MarcRecord>, Box<dyn std::error::Error + Send + Sync>> {
+    let body = ureq::get(base_url)
+        .timeout(TIMEOUT)
+        .query("operation", "searchRetrieve")
+        .query("version", "1.1")
+        .query("query", &cql_query(text))
+        .query("recordSchema", "marcxml")
+        .query("maximumRecords", MAX_RECORDS)
+        .call()?
+        .into_string()?;
+    parse_marcxml(&body)
+}
+
+/// The MARCXML records in `body`, an SRU response or a MARCXML file.
+pub(crate) fn parse_marcxml(body: &str) -> Result<Vec<MarcRecord>, Box<dyn std::error::Error + Send + Sync>> {
+    let doc = roxmltree::Document::parse(body)?;
+    // The server reports bad queries and unknown schemas as diagnostics
+    if let Some(diagnostic) = doc.descendants().find(|n| n.has_tag_name("diagnostic")) {
+        let message = diagnostic
+            .descendants()
+            .filter(|n| n.has_tag_name("message") || n.has_tag_name("details"))
+            .filter_map(|n| n.text())
+            .collect::<Vec<_>>()
+            .join(": ");
+        return Err(tr!("The catalog server refused the search: {}", message).into());
+    }
+    Ok(doc
+        .descendants()
+        .filter(|n| n.tag_name().name() == "record" && n.tag_name().namespace() == Some(MARC_NS))
+        .map(parse_record)
+        .collect())
+}
+
+// The subfields of every datafield with `tag`, in record order
+fn subfields<'a>(record: roxmltree::Node<'a, 'a>, tag: &'a str, codes: &'a str) -> impl Iterator<Item = Vec<&'a str>> + 'a {
+    record
+        .children()
+        .filter(move |n| n.tag_name().name() == "datafield" && n.attribute("tag") == Some(tag))
+        .map(move |field| {
+            field
+                .children()
+                .filter(|n| n.tag_name().name() == "subfield")
+                .filter(|n| n.attribute("code").is_some_and(|c| codes.contains(c)))
+                .filter_map(|n| n.text())
+                .map(str::trim)
+                .collect()
+        })
+}
+
+fn first(record: roxmltree::Node, tag: &str, codes: &str) -> Option<String> {
+    subfields(record, tag, codes)
+        .map(|parts| parts.join(" "))
+        .find(|text| !text.is_empty())
+}
+
+// MARC ends most fields with ISBD punctuation: "Dune /", "Herbert, Frank,"
+fn clean(text: &str) -> String {
+    text.trim().trim_end_matches([' ', '/', ':', ';', ',', '.', '=']).trim().to_string()
+}
+
+fn parse_record(record: roxmltree::Node) -> MarcRecord {
+    // 020 $a is the ISBN, sometimes followed by a qualifier: "9780441013593 (pbk.)"
+    let isbn = first(record, "020", "a").and_then(|a| a.split_whitespace().next().and_then(as_isbn));
+    let title = first(record, "245", "abnp").map(|t| clean(&t)).unwrap_or_default();
+    let author = first(record, "100", "a").or_else(|| first(record, "110", "a")).map(|a| clean(&a));
+    // The year of publication, from 264 (RDA) or the older 260
+    let year = first(record, "264", "c")
+        .or_else(|| first(record, "260", "c"))
+        .and_then(|date| {
+            let digits: String = date.chars().skip_while(|c| !c.is_ascii_digit()).take(4).collect();
+            digits.parse().ok()
+        })
+        .unwrap_or(0);
+    let edition = first(record, "250", "a").map(|e| clean(&e)).unwrap_or_default();
+    let desc = first(record, "520", "a").unwrap_or_default();
+    // LC classification, or Dewey for libraries that only have that
+    let call_number = first(record, "050", "ab")
+        .or_else(|| first(record, "082", "a"))
+        .map(|c| c.replace('/', ""))
+        .unwrap_or_default();
+    let mut tags: Vec<String> = subfields(record, "650", "a")
+        .chain(subfields(record, "655", "a"))
+        .filter_map(|parts| parts.first().map(|a| clean(a)))
+        .filter(|t| !t.is_empty())
+        .collect();
+    tags.sort();
+    tags.dedup();
+
+    MarcRecord {
+        isbn,
+        title,
+        author,
+        year,
+        edition,
+        desc,
+        call_number,
+        tags,
+    }
+}
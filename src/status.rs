@@ -0,0 +1,146 @@
+use std::cell::Cell;
+
+use chrono::Local;
+use gtk::prelude::*;
+use gtk::{gio, glib, ApplicationWindow, Box as GtkBox, Label, ScrolledWindow};
+
+use crate::i18n::tr;
+
+// Messages kept in the recent-messages popover
+const HISTORY_LEN: usize = 50;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Level {
+    Info,
+    Success,
+    Error,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Info => "info",
+            Level::Success => "success",
+            Level::Error => "error",
+        }
+    }
+
+    fn from_name(name: &str) -> Level {
+        match name {
+            "success" => Level::Success,
+            "error" => Level::Error,
+            _ => Level::Info,
+        }
+    }
+
+    // The theme's style class for the message text
+    fn css_class(self) -> Option<&'static str> {
+        match self {
+            Level::Info => None,
+            Level::Success => Some("success"),
+            Level::Error => Some("error"),
+        }
+    }
+}
+
+pub fn info(widget: &impl IsA<gtk::Widget>, text: &str) {
+    post(widget, Level::Info, text);
+}
+
+pub fn success(widget: &impl IsA<gtk::Widget>, text: &str) {
+    post(widget, Level::Success, text);
+}
+
+pub fn error(widget: &impl IsA<gtk::Widget>, text: &str) {
+    post(widget, Level::Error, text);
+}
+
+// Messages reach the status bar of the widget's window through the
+// "win.status" action, so pages don't need a handle on the bar. Errors are
+// logged as well; the rest only when there is no bar to show them.
+fn post(widget: &impl IsA<gtk::Widget>, level: Level, text: &str) {
+    let shown = widget.activate_action("win.status", Some(&(level.name(), text).to_variant())).is_ok();
+    if level == Level::Error {
+        tracing::warn!("{}", text);
+    } else if !shown {
+        tracing::info!("{}", text);
+    }
+}
+
+fn style(label: &Label, level: Level) {
+    label.remove_css_class("success");
+    label.remove_css_class("error");
+    if let Some(class) = level.css_class() {
+        label.add_css_class(class);
+    }
+}
+
+/// The strip along the bottom of the main window: the latest message with
+/// the time it was posted, and a button listing the recent ones.
+pub fn create_status_bar(window: &ApplicationWindow) -> GtkBox {
+    let label = Label::new(None);
+    label.set_xalign(0.0);
+    label.set_hexpand(true);
+    label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+
+    let history_box = GtkBox::new(gtk::Orientation::Vertical, 4);
+    history_box.set_margin_top(6);
+    history_box.set_margin_bottom(6);
+    history_box.set_margin_start(6);
+    history_box.set_margin_end(6);
+    let empty_label = Label::new(Some(&tr!("No messages yet")));
+    history_box.append(&empty_label);
+
+    let scrolled_window = ScrolledWindow::new();
+    scrolled_window.set_policy(gtk::PolicyType::Never, gtk::PolicyType::Automatic);
+    scrolled_window.set_propagate_natural_height(true);
+    scrolled_window.set_max_content_height(300);
+    scrolled_window.set_min_content_width(350);
+    scrolled_window.set_child(Some(&history_box));
+
+    let popover = gtk::Popover::new();
+    popover.set_child(Some(&scrolled_window));
+    let history_button = gtk::MenuButton::new();
+    history_button.set_icon_name("document-open-recent-symbolic");
+    history_button.set_tooltip_text(Some(&tr!("Recent messages")));
+    history_button.set_popover(Some(&popover));
+
+    let action = gio::SimpleAction::new("status", glib::VariantTy::new("(ss)").ok());
+    let count = Cell::new(0);
+    action.connect_activate(glib::clone!(
+        #[weak]
+        label,
+        #[weak]
+        history_box,
+        move |_, parameter| {
+            let Some((level, text)) = parameter.and_then(|p| p.get::<(String, String)>()) else { return };
+            let level = Level::from_name(&level);
+            let line = format!("{}  {}", Local::now().format("%H:%M:%S"), text);
+            label.set_text(&line);
+            style(&label, level);
+
+            if count.get() == 0 {
+                history_box.remove(&empty_label);
+            }
+            let entry = Label::new(Some(&line));
+            entry.set_xalign(0.0);
+            entry.set_wrap(true);
+            entry.set_selectable(true);
+            style(&entry, level);
+            history_box.prepend(&entry);
+            count.set(count.get() + 1);
+            if count.get() > HISTORY_LEN {
+                if let Some(oldest) = history_box.last_child() {
+                    history_box.remove(&oldest);
+                }
+                count.set(HISTORY_LEN);
+            }
+        }
+    ));
+    window.add_action(&action);
+
+    let status_bar = GtkBox::new(gtk::Orientation::Horizontal, 10);
+    status_bar.append(&label);
+    status_bar.append(&history_button);
+    status_bar
+}
@@ -0,0 +1,174 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::i18n::tr;
+use crate::transactions::TransactionKind;
+use crate::{Library, LiItem};
+
+/// A physical inventory in progress: the shelves being checked and the
+/// item barcodes scanned on them so far. Kept in the library so a count
+/// can run over several days.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Stocktake {
+    pub started_at: DateTime<Utc>,
+    pub branch: String,
+    // Empty for the whole branch
+    pub shelf_location: String,
+    // Item IDs in the order they were scanned, once per copy
+    pub scans: Vec<u32>,
+}
+
+impl Stocktake {
+    fn covers(&self, item: &LiItem) -> bool {
+        item.branch == self.branch && (self.shelf_location.is_empty() || item.shelf_location == self.shelf_location)
+    }
+
+    fn scan_counts(&self) -> BTreeMap<u32, u32> {
+        let mut counts = BTreeMap::new();
+        for &id in &self.scans {
+            *counts.entry(id).or_insert(0) += 1;
+        }
+        counts
+    }
+}
+
+// Where an item is supposed to be shelved
+fn home(item: &LiItem) -> String {
+    if item.shelf_location.is_empty() {
+        item.branch.clone()
+    } else {
+        format!("{}, {}", item.branch, item.shelf_location)
+    }
+}
+
+/// What a stocktake found, as (item ID, number of copies) unless noted.
+#[derive(Default)]
+pub struct StocktakeReport {
+    // Copies that should be on the shelf but weren't scanned
+    pub missing: Vec<(u32, u32)>,
+    // Items scanned here that belong elsewhere, with where they belong
+    pub misshelved: Vec<(u32, String)>,
+    // Copies scanned although they are marked lost
+    pub found_lost: Vec<(u32, u32)>,
+    // More copies scanned than should be on the shelf and none marked
+    // lost, usually a loan that was never checked in
+    pub unexpected: Vec<(u32, u32)>,
+}
+
+impl StocktakeReport {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.misshelved.is_empty() && self.found_lost.is_empty() && self.unexpected.is_empty()
+    }
+}
+
+impl Library {
+    pub fn start_stocktake(&mut self, branch: &str, shelf_location: &str) -> Result<(), String> {
+        self.only_at_server()?;
+        if self.stocktake.is_some() {
+            return Err(tr!("A stocktake is already in progress"));
+        }
+        let branch = branch.trim();
+        if branch.is_empty() {
+            return Err(tr!("Enter the branch to count"));
+        }
+        self.stocktake = Some(Stocktake {
+            started_at: self.clock.now(),
+            branch: branch.to_string(),
+            shelf_location: shelf_location.trim().to_string(),
+            scans: Vec::new(),
+        });
+        tracing::info!(branch, shelf_location, "Stocktake started");
+        self.dirty = true;
+        Ok(())
+    }
+
+    /// Records one scanned copy. Returns the item's title, and where it
+    /// belongs if that isn't on the shelves being counted.
+    pub fn record_scan(&mut self, item_id: u32) -> Result<(Arc<str>, Option<String>), String> {
+        let stocktake = self.stocktake.as_mut().ok_or_else(|| tr!("No stocktake is in progress"))?;
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Unknown barcode {}", item_id))?;
+        stocktake.scans.push(item_id);
+        let misplaced = (!stocktake.covers(item)).then(|| home(item));
+        self.dirty = true;
+        Ok((item.title.clone(), misplaced))
+    }
+
+    /// Takes back the most recent scan, for a copy scanned twice by mistake.
+    pub fn undo_scan(&mut self) -> Result<u32, String> {
+        let stocktake = self.stocktake.as_mut().ok_or_else(|| tr!("No stocktake is in progress"))?;
+        let item_id = stocktake.scans.pop().ok_or_else(|| tr!("Nothing has been scanned yet"))?;
+        self.dirty = true;
+        Ok(item_id)
+    }
+
+    /// Compares the scans with the catalog. Copies on loan aren't expected
+    /// on the shelf.
+    pub fn stocktake_report(&self) -> Result<StocktakeReport, String> {
+        let stocktake = self.stocktake.as_ref().ok_or_else(|| tr!("No stocktake is in progress"))?;
+        let counts = stocktake.scan_counts();
+        let mut report = StocktakeReport::default();
+
+        let mut ids: Vec<u32> = self.items.keys().copied().collect();
+        ids.sort_unstable();
+        for id in ids {
+            let item = &self.items[&id];
+            let scanned = counts.get(&id).copied().unwrap_or(0);
+            if !stocktake.covers(item) {
+                if scanned > 0 {
+                    report.misshelved.push((id, home(item)));
+                }
+                continue;
+            }
+
+            if scanned < item.avail_copies {
+                report.missing.push((id, item.avail_copies - scanned));
+            } else if scanned > item.avail_copies {
+                let extra = scanned - item.avail_copies;
+                let found = extra.min(item.lost_copies);
+                if found > 0 {
+                    report.found_lost.push((id, found));
+                }
+                if extra > found {
+                    report.unexpected.push((id, extra - found));
+                }
+            }
+        }
+        Ok(report)
+    }
+
+    /// Ends the stocktake. With `apply_changes` missing copies are marked
+    /// lost and lost copies that turned up go back on the shelf; otherwise
+    /// the catalog is left as it is. Returns the final report.
+    pub fn finish_stocktake(&mut self, apply_changes: bool) -> Result<StocktakeReport, String> {
+        self.only_at_server()?;
+        let report = self.stocktake_report()?;
+        if apply_changes {
+            for &(id, copies) in &report.missing {
+                let item = self.items.get_mut(&id).unwrap();
+                item.avail_copies -= copies;
+                item.lost_copies += copies;
+                self.log_transaction(TransactionKind::CopyLost, id, 0);
+            }
+            for &(id, copies) in &report.found_lost {
+                let item = self.items.get_mut(&id).unwrap();
+                item.lost_copies -= copies;
+                item.avail_copies += copies;
+                self.log_transaction(TransactionKind::CopyFound, id, 0);
+            }
+        }
+        self.stocktake = None;
+        tracing::info!(
+            apply_changes,
+            missing = report.missing.len(),
+            misshelved = report.misshelved.len(),
+            found = report.found_lost.len(),
+            "Stocktake finished"
+        );
+        self.dirty = true;
+        self.reloaded();
+        Ok(report)
+    }
+}
@@ -0,0 +1,189 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::acquisitions::OrderLine;
+use crate::i18n::tr;
+use crate::Library;
+
+// Where the kiosk leaves suggestions for the staff desk to take in. The
+// kiosk never saves the library, so it can't add them there itself.
+pub const INBOX_PATH: &str = "suggestions.jsonl";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum SuggestionStatus {
+    New,
+    Approved,
+    Rejected,
+}
+
+impl SuggestionStatus {
+    pub fn label(self) -> String {
+        match self {
+            SuggestionStatus::New => tr!("New"),
+            SuggestionStatus::Approved => tr!("Approved"),
+            SuggestionStatus::Rejected => tr!("Rejected"),
+        }
+    }
+}
+
+/// Why a suggestion wasn't bought.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RejectReason {
+    AlreadyOwned,
+    NotAvailable,
+    OutsideCollection,
+    NoBudget,
+}
+
+impl RejectReason {
+    pub const ALL: [RejectReason; 4] =
+        [RejectReason::AlreadyOwned, RejectReason::NotAvailable, RejectReason::OutsideCollection, RejectReason::NoBudget];
+
+    pub fn label(self) -> String {
+        match self {
+            RejectReason::AlreadyOwned => tr!("Already in the catalog"),
+            RejectReason::NotAvailable => tr!("No longer available to buy"),
+            RejectReason::OutsideCollection => tr!("Outside what the library collects"),
+            RejectReason::NoBudget => tr!("No budget left this year"),
+        }
+    }
+}
+
+/// A title a patron asked the library to buy, as submitted at the kiosk.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct NewSuggestion {
+    pub title: String,
+    pub author: Option<String>,
+    pub notes: String,
+    pub member_id: Option<u32>,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Suggestion {
+    pub id: u32,
+    pub title: String,
+    pub author: Option<String>,
+    pub notes: String,
+    pub member_id: Option<u32>,
+    pub at: DateTime<Utc>,
+    pub status: SuggestionStatus,
+    // Set once approved or rejected
+    pub decided_by: Option<String>,
+    pub order_id: Option<u32>,
+    pub reject_reason: Option<RejectReason>,
+}
+
+impl Suggestion {
+    /// E.g. "Order 3" or the reason it was rejected.
+    pub fn decision_text(&self) -> String {
+        match (self.order_id, self.reject_reason) {
+            (Some(order_id), _) => tr!("Order {}", order_id),
+            (None, Some(reason)) => reason.label(),
+            (None, None) => String::new(),
+        }
+    }
+}
+
+/// Adds a suggestion to the inbox at `path`, one JSON line each, for the
+/// staff desk to take in.
+pub fn submit(path: &Path, suggestion: &NewSuggestion) -> Result<(), Box<dyn std::error::Error>> {
+    if suggestion.title.trim().is_empty() {
+        return Err(tr!("Title cannot be empty").into());
+    }
+    let mut line = serde_json::to_string(suggestion)?;
+    line.push('\n');
+    // One write, so lines from two kiosks don't interleave
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    file.write_all(line.as_bytes())?;
+    tracing::info!(member_id = suggestion.member_id, "Purchase suggestion submitted");
+    Ok(())
+}
+
+impl Library {
+    /// Moves the suggestions waiting in the inbox at `path` into the
+    /// library. Returns how many there were.
+    pub fn take_suggestions(&mut self, path: &Path) -> Result<usize, Box<dyn std::error::Error>> {
+        // The sync server takes them, as a client can't add them to its copy
+        if self.sync_client || !path.exists() {
+            return Ok(0);
+        }
+        // Moved aside first, so suggestions submitted meanwhile start a new
+        // inbox instead of being lost
+        let taking = path.with_extension("jsonl.taking");
+        fs::rename(path, &taking)?;
+        let mut taken = 0;
+        for line in BufReader::new(File::open(&taking)?).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let new: NewSuggestion = match serde_json::from_str(&line) {
+                Ok(new) => new,
+                Err(e) => {
+                    tracing::warn!("Skipping a bad suggestion: {}", e);
+                    continue;
+                }
+            };
+            self.suggestions.push(Suggestion {
+                id: self.suggestions.last().map_or(1, |s| s.id + 1),
+                title: new.title,
+                author: new.author,
+                notes: new.notes,
+                member_id: new.member_id,
+                at: new.at,
+                status: SuggestionStatus::New,
+                decided_by: None,
+                order_id: None,
+                reject_reason: None,
+            });
+            taken += 1;
+        }
+        fs::remove_file(&taking)?;
+        if taken > 0 {
+            tracing::info!(count = taken, "Purchase suggestions taken in");
+            self.dirty = true;
+        }
+        Ok(taken)
+    }
+
+    fn new_suggestion(&mut self, id: u32) -> Result<&mut Suggestion, String> {
+        let suggestion = self.suggestions.iter_mut().find(|s| s.id == id).ok_or_else(|| tr!("Suggestion not found"))?;
+        if suggestion.status != SuggestionStatus::New {
+            return Err(tr!("This suggestion has already been decided"));
+        }
+        Ok(suggestion)
+    }
+
+    /// Buys a suggested title: adds `line` to the open order `order_id`
+    /// and marks the suggestion approved.
+    pub fn approve_suggestion(&mut self, id: u32, order_id: u32, line: OrderLine) -> Result<(), String> {
+        self.only_at_server()?;
+        self.new_suggestion(id)?;
+        self.add_order_line(order_id, line)?;
+        let by = self.current_user.clone();
+        let suggestion = self.new_suggestion(id)?;
+        suggestion.status = SuggestionStatus::Approved;
+        suggestion.order_id = Some(order_id);
+        suggestion.decided_by = Some(by);
+        tracing::info!(suggestion_id = id, order_id, "Purchase suggestion approved");
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn reject_suggestion(&mut self, id: u32, reason: RejectReason) -> Result<(), String> {
+        self.only_at_server()?;
+        let by = self.current_user.clone();
+        let suggestion = self.new_suggestion(id)?;
+        suggestion.status = SuggestionStatus::Rejected;
+        suggestion.reject_reason = Some(reason);
+        suggestion.decided_by = Some(by);
+        tracing::info!(suggestion_id = id, "Purchase suggestion rejected: {:?}", reason);
+        self.dirty = true;
+        Ok(())
+    }
+}
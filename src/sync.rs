@@ -0,0 +1,689 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::rc::Rc;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use gtk::glib;
+use gtk::prelude::*;
+use serde::{Deserialize, Serialize};
+use tungstenite::{Message, WebSocket};
+
+use crate::clock::FixedClock;
+use crate::config::Config;
+use crate::fines::{FineStatus, WaiverReason};
+use crate::holds::HoldLevel;
+use crate::i18n::tr;
+use crate::memberships::MembershipType;
+use crate::migrate;
+use crate::{status, Library};
+
+// How often queued messages are passed between the library and the network
+// threads, and how long a socket read waits before checking for outgoing
+// messages
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+const RECONNECT_SECONDS: u64 = 5;
+// Used by a server when no address is configured; only desks on the same
+// computer can connect until an address such as 0.0.0.0:9001 is set
+const DEFAULT_SERVER_ADDRESS: &str = "127.0.0.1:9001";
+// Peer ID a client uses for its server
+const SERVER_ID: u64 = 0;
+
+/// Whether this desk shares its library with others.
+#[derive(Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum SyncMode {
+    #[default]
+    Off,
+    // Holds the authoritative library; other desks connect to it
+    Server,
+    Client,
+}
+
+impl SyncMode {
+    pub const ALL: [SyncMode; 3] = [SyncMode::Off, SyncMode::Server, SyncMode::Client];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SyncMode::Off => "Off",
+            SyncMode::Server => "Server",
+            SyncMode::Client => "Client",
+        }
+    }
+}
+
+/// A change to loans, fines, holds, memberships or members' personal data
+/// that the other desks replay to stay in step. Clients can't make other changes; see
+/// `Library::only_at_server`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub enum LibraryCommand {
+    // `member` is the member's ID as typed at the desk
+    Issue { item_id: u32, member: String },
+    // `member_id` is the ID the member got at that desk, which its later
+    // changes use; None from desks that didn't send it
+    RegisterMember {
+        name: String,
+        #[serde(default)]
+        member_id: Option<u32>,
+    },
+    Return { item_id: u32, member_id: u32 },
+    Renew { item_id: u32, member_id: u32 },
+    // Always `Paid` from this version on; desks that synced before waivers
+    // had reasons could send `Waived`, which is refused
+    SettleFine { member_id: u32, fine_id: u32, status: FineStatus },
+    // `approved_by` is the administrator who approved it at that desk
+    WaiveFine { member_id: u32, fine_id: u32, reason: WaiverReason, approved_by: Option<String> },
+    // `hold_id` is the ID the hold got at that desk
+    PlaceHold { item_id: u32, member_id: u32, level: HoldLevel, hold_id: u32 },
+    CancelHold { hold_id: u32 },
+    RenewMembership { member_id: u32 },
+    SetMembership { member_id: u32, kind: MembershipType },
+    AnonymizeMember { member_id: u32 },
+}
+
+/// A command with when and by whom it was made, so replaying it gives the
+/// same due dates and log entries everywhere.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SyncCommand {
+    pub at: DateTime<Utc>,
+    pub user: String,
+    pub command: LibraryCommand,
+}
+
+impl LibraryCommand {
+    /// What the command does, for staff, e.g. "Return item 12 from member 3".
+    pub fn describe(&self) -> String {
+        match self {
+            LibraryCommand::Issue { item_id, member } => tr!("Issue item {} to member {}", item_id, member),
+            LibraryCommand::RegisterMember { name, .. } => tr!("Register member {}", name),
+            LibraryCommand::Return { item_id, member_id } => tr!("Return item {} from member {}", item_id, member_id),
+            LibraryCommand::Renew { item_id, member_id } => tr!("Renew item {} for member {}", item_id, member_id),
+            LibraryCommand::SettleFine { member_id, fine_id, .. } => tr!("Settle fine {} of member {}", fine_id, member_id),
+            LibraryCommand::WaiveFine { member_id, fine_id, .. } => tr!("Waive fine {} of member {}", fine_id, member_id),
+            LibraryCommand::PlaceHold { item_id, member_id, .. } => {
+                tr!("Place a hold on item {} for member {}", item_id, member_id)
+            }
+            LibraryCommand::CancelHold { hold_id } => tr!("Cancel hold {}", hold_id),
+            LibraryCommand::RenewMembership { member_id } => tr!("Renew the membership of member {}", member_id),
+            LibraryCommand::SetMembership { member_id, .. } => tr!("Change the membership of member {}", member_id),
+            LibraryCommand::AnonymizeMember { member_id } => tr!("Anonymize member {}", member_id),
+        }
+    }
+}
+
+/// A change made at this desk that the server refused, usually because
+/// another desk got there first while this one was offline, e.g. the item
+/// was already returned elsewhere. The server's library wins, so the change
+/// is gone here too until staff retry it or mark it resolved.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct SyncConflict {
+    pub id: u32,
+    pub command: SyncCommand,
+    // The server's error message
+    pub reason: String,
+    pub found_at: DateTime<Utc>,
+    pub resolved_by: Option<String>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    // Set when resolved by sending the change again
+    #[serde(default)]
+    pub retried: bool,
+}
+
+impl SyncConflict {
+    pub fn status_text(&self) -> String {
+        match (&self.resolved_by, self.retried) {
+            (None, _) => tr!("Unresolved"),
+            (Some(_), true) => tr!("Retried"),
+            (Some(_), false) => tr!("Resolved"),
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+enum SyncMessage {
+    // A client's first message. The server reads nothing else from a desk,
+    // and sends it nothing, until it has sent the right secret.
+    Hello { secret: String },
+    Welcome,
+    // Sent instead of Welcome, just before the server hangs up
+    Refused { reason: String },
+    Command(SyncCommand),
+    // Sent back to the desk a command came from when the server couldn't
+    // replay it, with why
+    Rejected { command: SyncCommand, reason: String },
+    // The server's whole library, as saved to disk
+    Snapshot(String),
+    RequestSnapshot,
+}
+
+impl Library {
+    // Queues a change for the other desks. Nothing is queued while sync is
+    // off or a remote change is being replayed.
+    pub(crate) fn record_command(&mut self, command: LibraryCommand) {
+        if self.sync_enabled {
+            self.outbox.push(SyncCommand {
+                at: self.clock.now(),
+                user: self.current_user.clone(),
+                command,
+            });
+        }
+    }
+
+    /// Refuses a change that isn't sent to the other desks while this desk
+    /// is a sync client, as it would be lost the next time the server's
+    /// library replaces this one.
+    pub(crate) fn only_at_server(&self) -> Result<(), String> {
+        if self.sync_client {
+            return Err(tr!("This desk is a sync client, and this change isn't sent to the sync server; make it at the server instead"));
+        }
+        Ok(())
+    }
+
+    /// Replays a change made at another desk, as of when and by whom it was
+    /// made there.
+    pub fn apply_remote(&mut self, remote: &SyncCommand) -> Result<(), String> {
+        self.replay(remote, true)
+    }
+
+    // Makes `command` as of when and by whom it was first made. Changes
+    // from another desk don't post webhooks, as that desk has posted them.
+    fn replay(&mut self, remote: &SyncCommand, from_other_desk: bool) -> Result<(), String> {
+        let clock = std::mem::replace(&mut self.clock, Box::new(FixedClock(remote.at)));
+        let user = std::mem::replace(&mut self.current_user, remote.user.clone());
+        let sync_enabled = std::mem::replace(&mut self.sync_enabled, false);
+        let replaying = std::mem::replace(&mut self.replaying, from_other_desk);
+        let result = match &remote.command {
+            LibraryCommand::Issue { item_id, member } => self.book_issue(*item_id, member.clone()),
+            LibraryCommand::RegisterMember { name, member_id } => match *member_id {
+                // Loans and fines that follow refer to the member by this ID
+                Some(id) if id == self.peek_member_id() => self.register_member(name).map(|_| ()),
+                Some(id) => Err(tr!("The member would get ID {} here, not {}; register them again", self.peek_member_id(), id)),
+                None => Err(tr!("The desk didn't send the new member's ID; register them again")),
+            },
+            LibraryCommand::Return { item_id, member_id } => self.book_return(*item_id, *member_id).map(|_| ()),
+            LibraryCommand::Renew { item_id, member_id } => self.renew_loan(*item_id, *member_id).map(|_| ()),
+            LibraryCommand::SettleFine { status, .. } if *status != FineStatus::Paid => {
+                Err(tr!("Fines can only be waived with a reason; waive it again at this desk"))
+            }
+            LibraryCommand::SettleFine { member_id, fine_id, .. } => self.settle_fine(*member_id, *fine_id).map(|_| ()),
+            LibraryCommand::WaiveFine { member_id, fine_id, reason, approved_by } => {
+                self.waive_fine(*member_id, *fine_id, *reason, approved_by.clone()).map(|_| ())
+            }
+            // Cancelling or collecting it later refers to the hold by this ID
+            LibraryCommand::PlaceHold { item_id, member_id, level, hold_id } => {
+                let id = self.next_hold_id();
+                if *hold_id != id {
+                    Err(tr!("The hold would get ID {} here, not {}; place it again", id, hold_id))
+                } else {
+                    self.place_hold(*item_id, *member_id, *level).map(|_| ())
+                }
+            }
+            LibraryCommand::CancelHold { hold_id } => self.cancel_hold(*hold_id),
+            LibraryCommand::RenewMembership { member_id } => self.renew_membership(*member_id).map(|_| ()),
+            LibraryCommand::SetMembership { member_id, kind } => self.set_membership(*member_id, *kind),
+            LibraryCommand::AnonymizeMember { member_id } => self.anonymize_member(*member_id),
+        };
+        self.clock = clock;
+        self.current_user = user;
+        self.sync_enabled = sync_enabled;
+        self.replaying = replaying;
+        result
+    }
+
+    fn record_conflict(&mut self, command: SyncCommand, reason: String) {
+        tracing::warn!(command = ?command.command, at = %command.at, "Change refused by the sync server: {}", reason);
+        self.sync_conflicts.push(SyncConflict {
+            id: self.sync_conflicts.last().map_or(1, |c| c.id + 1),
+            command,
+            reason,
+            found_at: self.clock.now(),
+            resolved_by: None,
+            resolved_at: None,
+            retried: false,
+        });
+        self.dirty = true;
+    }
+
+    pub fn open_sync_conflicts(&self) -> usize {
+        self.sync_conflicts.iter().filter(|c| c.resolved_by.is_none()).count()
+    }
+
+    fn open_conflict(&mut self, id: u32) -> Result<&mut SyncConflict, String> {
+        let conflict = self.sync_conflicts.iter_mut().find(|c| c.id == id).ok_or_else(|| tr!("Conflict not found"))?;
+        if conflict.resolved_by.is_some() {
+            return Err(tr!("This conflict has already been resolved"));
+        }
+        Ok(conflict)
+    }
+
+    /// Makes a refused change again, as of when it was first made, and
+    /// sends it to the server, once staff have fixed whatever stood in its
+    /// way. Fails, leaving the conflict open, if it still can't be made.
+    pub fn retry_conflict(&mut self, id: u32) -> Result<(), String> {
+        let command = self.open_conflict(id)?.command.clone();
+        // Refused by the server, so made nowhere yet
+        self.replay(&command, false)?;
+        let (now, user) = (self.clock.now(), self.current_user.clone());
+        let conflict = self.open_conflict(id)?;
+        conflict.resolved_by = Some(user);
+        conflict.resolved_at = Some(now);
+        conflict.retried = true;
+        self.outbox.push(command);
+        self.dirty = true;
+        tracing::info!(conflict_id = id, "Sync conflict retried");
+        Ok(())
+    }
+
+    /// Marks a refused change as dealt with, leaving the library as the
+    /// server has it.
+    pub fn resolve_conflict(&mut self, id: u32) -> Result<(), String> {
+        let (now, user) = (self.clock.now(), self.current_user.clone());
+        let conflict = self.open_conflict(id)?;
+        conflict.resolved_by = Some(user);
+        conflict.resolved_at = Some(now);
+        self.dirty = true;
+        tracing::info!(conflict_id = id, "Sync conflict resolved");
+        Ok(())
+    }
+
+    // Takes over the server's library, keeping who is logged in here
+    fn replace_with_snapshot(&mut self, json: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let mut snapshot: Library = migrate::LIBRARY.read(json.as_bytes())?;
+        // What this desk hasn't got through to the server yet
+        snapshot.outbox = std::mem::take(&mut self.outbox);
+        snapshot.sync_conflicts = std::mem::take(&mut self.sync_conflicts);
+        // Each desk has a cash drawer of its own
+        snapshot.cash_sessions = std::mem::take(&mut self.cash_sessions);
+        self.replace_state(snapshot);
+        Ok(())
+    }
+}
+
+enum Event {
+    Connected(u64),
+    Received(u64, String),
+    Disconnected(u64),
+    Failed(String),
+}
+
+type Peers = Arc<Mutex<HashMap<u64, Sender<String>>>>;
+
+// Passes text messages both ways until the connection drops
+fn run_socket<S: Read + Write>(mut socket: WebSocket<S>, id: u64, peers: &Peers, events: &Sender<Event>) {
+    let (sender, outgoing) = mpsc::channel();
+    peers.lock().unwrap().insert(id, sender);
+    let _ = events.send(Event::Connected(id));
+    'connection: loop {
+        // The sender is dropped to hang up, once what it sent has gone
+        loop {
+            match outgoing.try_recv() {
+                Ok(text) => {
+                    if socket.send(Message::text(text)).is_err() {
+                        break 'connection;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => break 'connection,
+            }
+        }
+        match socket.read() {
+            Ok(message) if message.is_text() => {
+                if let Ok(text) = message.to_text() {
+                    let _ = events.send(Event::Received(id, text.to_string()));
+                }
+            }
+            Ok(message) if message.is_close() => break,
+            Ok(_) => {}
+            // The read timeout, so outgoing messages get their turn
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(_) => break,
+        }
+    }
+    peers.lock().unwrap().remove(&id);
+    let _ = events.send(Event::Disconnected(id));
+}
+
+fn serve(address: String, peers: Peers, events: Sender<Event>) {
+    let listener = match TcpListener::bind(&address) {
+        Ok(listener) => listener,
+        Err(e) => {
+            let _ = events.send(Event::Failed(tr!("Sync server could not listen on {}: {}", address, e)));
+            return;
+        }
+    };
+    tracing::info!(address, "Sync server listening");
+    // Client IDs start after SERVER_ID
+    for (id, stream) in (SERVER_ID + 1..).zip(listener.incoming()) {
+        let Ok(stream) = stream else { continue };
+        let (peers, events) = (peers.clone(), events.clone());
+        thread::spawn(move || match tungstenite::accept(stream) {
+            Ok(socket) => {
+                let _ = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL));
+                run_socket(socket, id, &peers, &events);
+            }
+            Err(e) => tracing::warn!("Sync handshake failed: {}", e),
+        });
+    }
+}
+
+// Stays connected to the server, reconnecting whenever the connection drops
+fn connect(address: String, peers: Peers, events: Sender<Event>) {
+    let host = address.trim_start_matches("ws://").trim_end_matches('/').to_string();
+    loop {
+        let socket = TcpStream::connect(&host)
+            .map_err(|e| e.to_string())
+            .and_then(|stream| tungstenite::client(format!("ws://{}/", host), stream).map_err(|e| e.to_string()));
+        match socket {
+            Ok((socket, _)) => {
+                let _ = socket.get_ref().set_read_timeout(Some(POLL_INTERVAL));
+                run_socket(socket, SERVER_ID, &peers, &events);
+            }
+            Err(e) => tracing::debug!(host, "Sync connection failed: {}", e),
+        }
+        thread::sleep(Duration::from_secs(RECONNECT_SECONDS));
+    }
+}
+
+/// A running sync connection. Network threads only pass text around; the
+/// library itself is only touched from the GTK main loop.
+pub struct SyncHandle {
+    mode: SyncMode,
+    peers: Peers,
+    secret: String,
+    // Peers that have sent the secret, or on a client the server once it
+    // has welcomed this desk
+    authenticated: RefCell<HashSet<u64>>,
+    // On a server, desks that had a change refused and haven't asked for
+    // the library since. Their later changes may build on the refused one,
+    // e.g. a loan to a member whose registration was refused, so they are
+    // refused too.
+    out_of_step: RefCell<HashSet<u64>>,
+    // On a client, whether a snapshot has been asked for and not arrived
+    snapshot_requested: Cell<bool>,
+}
+
+// Compares every byte, so how long it takes doesn't tell how much of a
+// guessed secret was right
+fn same_secret(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+impl SyncHandle {
+    fn send(&self, id: u64, message: &SyncMessage) {
+        let Ok(text) = serde_json::to_string(message) else { return };
+        if let Some(peer) = self.peers.lock().unwrap().get(&id) {
+            let _ = peer.send(text);
+        }
+    }
+
+    fn broadcast(&self, text: &str, except: Option<u64>) {
+        let authenticated = self.authenticated.borrow();
+        for (id, peer) in self.peers.lock().unwrap().iter() {
+            if Some(*id) != except && authenticated.contains(id) {
+                let _ = peer.send(text.to_string());
+            }
+        }
+    }
+
+    fn send_snapshot(&self, id: u64, library: &RwLock<Library>) {
+        match migrate::LIBRARY.to_string(&*library.read().unwrap()) {
+            Ok(json) => self.send(id, &SyncMessage::Snapshot(json)),
+            Err(e) => tracing::error!("Failed to serialize the library for sync: {}", e),
+        }
+    }
+
+    // Hangs up on a peer once what was sent to it has gone
+    fn drop_peer(&self, id: u64) {
+        self.peers.lock().unwrap().remove(&id);
+        self.authenticated.borrow_mut().remove(&id);
+        self.out_of_step.borrow_mut().remove(&id);
+    }
+
+    /// Asks the server for its whole library, replacing the one here. Does
+    /// nothing on a server, before the server has let this desk in, or
+    /// while an earlier request is still being answered.
+    pub fn request_snapshot(&self) {
+        if self.mode == SyncMode::Client && self.authenticated.borrow().contains(&SERVER_ID) && !self.snapshot_requested.get() {
+            self.snapshot_requested.set(true);
+            self.send(SERVER_ID, &SyncMessage::RequestSnapshot);
+        }
+    }
+
+    fn send_outbox(&self, library: &RwLock<Library>) {
+        let outbox = std::mem::take(&mut library.write().unwrap().outbox);
+        if self.mode == SyncMode::Client && !outbox.is_empty() {
+            tracing::info!(count = outbox.len(), "Sending queued changes to the sync server");
+        }
+        for command in outbox {
+            if let Ok(text) = serde_json::to_string(&SyncMessage::Command(command)) {
+                self.broadcast(&text, None);
+            }
+        }
+    }
+
+    fn pump(&self, widget: &gtk::Widget, library: &RwLock<Library>, events: &Receiver<Event>) {
+        // A client keeps its changes until the server has let it in. A
+        // server with no clients has nobody to tell.
+        if self.mode == SyncMode::Server || !self.authenticated.borrow().is_empty() {
+            self.send_outbox(library);
+        }
+
+        for event in events.try_iter() {
+            match event {
+                Event::Connected(id) if self.mode == SyncMode::Server => tracing::info!(id, "Desk connected"),
+                Event::Connected(_) => self.send(SERVER_ID, &SyncMessage::Hello { secret: self.secret.clone() }),
+                Event::Disconnected(id) if self.mode == SyncMode::Server => {
+                    self.drop_peer(id);
+                    tracing::info!(id, "Desk disconnected");
+                }
+                Event::Disconnected(_) => {
+                    self.authenticated.borrow_mut().remove(&SERVER_ID);
+                    self.snapshot_requested.set(false);
+                    status::error(widget, &tr!("Lost the connection to the sync server; changes are queued until it is back"))
+                }
+                Event::Failed(e) => status::error(widget, &e),
+                Event::Received(id, text) => self.receive(widget, library, id, &text),
+            }
+        }
+    }
+
+    fn receive(&self, widget: &gtk::Widget, library: &RwLock<Library>, id: u64, text: &str) {
+        let message: SyncMessage = match serde_json::from_str(text) {
+            Ok(message) => message,
+            Err(e) => {
+                tracing::warn!(id, "Ignoring a sync message: {}", e);
+                return;
+            }
+        };
+        if !self.authenticated.borrow().contains(&id) {
+            self.authenticate(widget, library, id, message);
+            return;
+        }
+        match (message, self.mode) {
+            (SyncMessage::Command(command), SyncMode::Server) if self.out_of_step.borrow().contains(&id) => {
+                tracing::warn!(id, command = ?command.command, "Rejected a change from a desk out of step");
+                let reason = tr!("An earlier change from this desk was refused, and this one may depend on it");
+                self.send(id, &SyncMessage::Rejected { command, reason });
+            }
+            (SyncMessage::Command(command), mode) => {
+                let result = library.write().unwrap().apply_remote(&command);
+                match (result, mode) {
+                    (Ok(()), SyncMode::Server) => self.broadcast(text, Some(id)),
+                    (Ok(()), _) => {}
+                    // The desks disagree; the server's library wins. The desk
+                    // asks for it once it hears of the refusal.
+                    (Err(e), SyncMode::Server) => {
+                        tracing::warn!(id, command = ?command.command, "Rejected a change from another desk: {}", e);
+                        self.out_of_step.borrow_mut().insert(id);
+                        self.send(id, &SyncMessage::Rejected { command, reason: e });
+                    }
+                    (Err(e), _) => {
+                        tracing::warn!(command = ?command.command, "Could not replay a change from another desk: {}", e);
+                        self.request_snapshot();
+                    }
+                }
+            }
+            (SyncMessage::Snapshot(json), SyncMode::Client) => {
+                self.snapshot_requested.set(false);
+                match library.write().unwrap().replace_with_snapshot(&json) {
+                    Ok(()) => status::info(widget, &tr!("Library updated from the sync server")),
+                    Err(e) => tracing::error!("Failed to read the library from the sync server: {}", e),
+                }
+            }
+            (SyncMessage::Rejected { command, reason }, SyncMode::Client) => {
+                library.write().unwrap().record_conflict(command, reason);
+                status::error(widget, &tr!("The sync server refused a change made here; see Sync Conflicts"));
+                self.request_snapshot();
+            }
+            // The desk has heard of any refusal by now, so what it sends
+            // after this is taken on its own merits again
+            (SyncMessage::RequestSnapshot, SyncMode::Server) => {
+                self.out_of_step.borrow_mut().remove(&id);
+                self.send_snapshot(id, library);
+            }
+            _ => {}
+        }
+    }
+
+    // The only messages read before a desk is let in: the client's secret
+    // on a server, and the server's answer to it on a client
+    fn authenticate(&self, widget: &gtk::Widget, library: &RwLock<Library>, id: u64, message: SyncMessage) {
+        match (message, self.mode) {
+            (SyncMessage::Hello { secret }, SyncMode::Server) if same_secret(&secret, &self.secret) => {
+                self.authenticated.borrow_mut().insert(id);
+                tracing::info!(id, "Desk let in");
+                self.send(id, &SyncMessage::Welcome);
+            }
+            (SyncMessage::Hello { .. }, SyncMode::Server) => {
+                tracing::warn!(id, "Refused a desk with the wrong sync secret");
+                self.send(id, &SyncMessage::Refused { reason: tr!("The sync secret is wrong") });
+                self.drop_peer(id);
+            }
+            (_, SyncMode::Server) => {
+                tracing::warn!(id, "Refused a desk that didn't send the sync secret");
+                self.drop_peer(id);
+            }
+            // Changes made while offline go out before the snapshot is asked
+            // for, so it already includes them
+            (SyncMessage::Welcome, _) => {
+                self.authenticated.borrow_mut().insert(id);
+                status::info(widget, &tr!("Connected to the sync server"));
+                self.send_outbox(library);
+                self.request_snapshot();
+            }
+            (SyncMessage::Refused { reason }, _) => {
+                tracing::warn!("The sync server refused this desk: {}", reason);
+                status::error(widget, &tr!("The sync server refused this desk: {}", reason));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Starts sharing the library as configured, or returns None when sync is
+/// off or no secret is set. Loans, returns, renewals, fine payments, holds,
+/// memberships and anonymizations made here are sent to the other desks,
+/// and theirs are replayed here; a client refuses other changes, which are made at the
+/// server. Desks must send the shared secret before the server sends them
+/// anything or takes their changes. A client queues its changes while
+/// offline and sends them when it reconnects; any the server refuses become
+/// conflicts. A client takes over the server's library when it connects and
+/// whenever the two disagree.
+pub fn start(widget: &impl IsA<gtk::Widget>, library: Arc<RwLock<Library>>, config: &Config) -> Option<Rc<SyncHandle>> {
+    let address = config.sync_address.trim().to_string();
+    match config.sync_mode {
+        SyncMode::Off => return None,
+        SyncMode::Client if address.is_empty() => {
+            status::error(widget, &tr!("Enter the sync server's address in the settings"));
+            return None;
+        }
+        _ if config.sync_secret.is_empty() => {
+            status::error(widget, &tr!("Enter the sync secret in the settings; the server and its desks need the same one"));
+            return None;
+        }
+        _ => {}
+    }
+
+    {
+        let mut lib = library.write().unwrap();
+        lib.sync_enabled = true;
+        lib.sync_client = config.sync_mode == SyncMode::Client;
+    }
+    let handle = Rc::new(SyncHandle {
+        mode: config.sync_mode,
+        peers: Peers::default(),
+        secret: config.sync_secret.clone(),
+        authenticated: RefCell::default(),
+        out_of_step: RefCell::default(),
+        snapshot_requested: Cell::new(false),
+    });
+    let (events_sender, events) = mpsc::channel();
+    let peers = handle.peers.clone();
+    if config.sync_mode == SyncMode::Server {
+        let address = if address.is_empty() { DEFAULT_SERVER_ADDRESS.to_string() } else { address };
+        thread::spawn(move || serve(address, peers, events_sender));
+    } else {
+        thread::spawn(move || connect(address, peers, events_sender));
+    }
+
+    let widget = widget.clone().upcast::<gtk::Widget>();
+    glib::timeout_add_local(POLL_INTERVAL, glib::clone!(
+        #[weak]
+        widget,
+        #[strong]
+        handle,
+        #[upgrade_or]
+        glib::ControlFlow::Break,
+        move || {
+            handle.pump(&widget, &library, &events);
+            glib::ControlFlow::Continue
+        }
+    ));
+    Some(handle)
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn from_other_desk(command: LibraryCommand) -> SyncCommand {
+        SyncCommand { at: Utc.with_ymd_and_hms(2024, 3, 4, 10, 0, 0).unwrap(), user: "desk2".to_string(), command }
+    }
+
+    fn register(member_id: Option<u32>) -> SyncCommand {
+        from_other_desk(LibraryCommand::RegisterMember { name: "Ada".to_string(), member_id })
+    }
+
+    #[test]
+    fn members_keep_the_id_they_got_at_their_desk() {
+        let mut library = Library::new();
+        library.apply_remote(&register(Some(1))).unwrap();
+        assert_eq!(library.members[&1].name, "Ada");
+
+        // The other desk gave out an ID this library would give someone else
+        assert!(library.apply_remote(&register(Some(3))).is_err());
+        assert!(library.apply_remote(&register(None)).is_err());
+        assert_eq!(library.members.len(), 1);
+
+        library.apply_remote(&register(Some(2))).unwrap();
+        assert!(library.members.contains_key(&2));
+    }
+
+    #[test]
+    fn deleted_members_are_anonymized_from_other_desks() {
+        let mut library = Library::new();
+        library.apply_remote(&register(Some(1))).unwrap();
+        library.delete_member(1).unwrap();
+
+        library.apply_remote(&from_other_desk(LibraryCommand::AnonymizeMember { member_id: 1 })).unwrap();
+        let member = &library.trash.members[&1].record;
+        assert_eq!(member.name, "Anonymized member 1");
+        assert!(member.anonymized_at.is_some());
+    }
+}
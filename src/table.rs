@@ -0,0 +1,304 @@
+use std::cmp::Ordering;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+
+use gtk::prelude::*;
+use gtk::{gio, glib, ColumnView, ColumnViewColumn, Label, SignalListItemFactory};
+
+// What a cell needs to open the table's context menu. The view is held
+// weakly as the cells belong to it.
+#[derive(Clone)]
+struct RowMenu {
+    popover: Rc<RefCell<Option<gtk::PopoverMenu>>>,
+    selection: gtk::SingleSelection,
+    view: glib::WeakRef<ColumnView>,
+}
+
+impl RowMenu {
+    fn attach(&self, cell: &impl IsA<gtk::Widget>, item: &gtk::ListItem) {
+        let gesture = gtk::GestureClick::new();
+        gesture.set_button(gtk::gdk::BUTTON_SECONDARY);
+
+        let menu = self.clone();
+        let cell_ref = cell.upcast_ref::<gtk::Widget>().downgrade();
+        let item = item.downgrade();
+        gesture.connect_pressed(move |_, _, x, y| {
+            let (Some(view), Some(cell), Some(item)) = (menu.view.upgrade(), cell_ref.upgrade(), item.upgrade()) else { return };
+            let Some(popover) = menu.popover.borrow().clone() else { return };
+            menu.selection.set_selected(item.position());
+            if let Some((x, y)) = cell.translate_coordinates(&view, x, y) {
+                popover.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+                popover.popup();
+            }
+        });
+        cell.add_controller(gesture);
+    }
+}
+
+// A sortable GtkColumnView over plain Rust rows. Each row is wrapped in a
+// `BoxedAnyObject` and kept in a `gio::ListStore`; clicking a column header
+// sorts through the column's `CustomSorter`.
+pub struct Table<T> {
+    pub view: ColumnView,
+    pub store: gio::ListStore,
+    pub selection: gtk::SingleSelection,
+    // Shown on right-click once set_context_menu has been called
+    popover: Rc<RefCell<Option<gtk::PopoverMenu>>>,
+    // Set by set_multi_select; the view's model from then on
+    multi: RefCell<Option<gtk::MultiSelection>>,
+    // Each column with its cell text, for copying rows out
+    columns: RefCell<Vec<(ColumnViewColumn, Rc<dyn Fn(&T) -> String>)>>,
+    _row: PhantomData<T>,
+}
+
+impl<T: 'static> Table<T> {
+    pub fn new() -> Self {
+        let store = gio::ListStore::new::<glib::BoxedAnyObject>();
+        let sorted = gtk::SortListModel::new(Some(store.clone()), None::<gtk::Sorter>);
+        let selection = gtk::SingleSelection::new(Some(sorted.clone()));
+        let view = ColumnView::new(Some(selection.clone()));
+        sorted.set_sorter(view.sorter().as_ref());
+
+        Table {
+            view,
+            store,
+            selection,
+            popover: Rc::new(RefCell::new(None)),
+            multi: RefCell::new(None),
+            columns: RefCell::new(Vec::new()),
+            _row: PhantomData,
+        }
+    }
+
+    /// Adds a right-click menu to the rows. Right-clicking selects the row
+    /// first, so `actions` (in the "row" group) work on `selected`.
+    pub fn set_context_menu(&self, menu: &gio::Menu, actions: &gio::SimpleActionGroup) {
+        let popover = gtk::PopoverMenu::from_model(Some(menu));
+        popover.set_parent(&self.view);
+        popover.set_has_arrow(false);
+        popover.set_halign(gtk::Align::Start);
+        self.view.insert_action_group("row", Some(actions));
+        *self.popover.borrow_mut() = Some(popover);
+    }
+
+    /// Lets several rows be selected with Ctrl and Shift. `selection` keeps
+    /// following the first selected row, so actions on one row still work;
+    /// `selected_rows` gives all of them.
+    pub fn set_multi_select(&self) {
+        let multi = gtk::MultiSelection::new(Some(self.selection.clone()));
+        self.view.set_model(Some(&multi));
+
+        let single = self.selection.clone();
+        multi.connect_selection_changed(move |multi, _, _| {
+            if let Some(first) = (0..multi.n_items()).find(|&i| multi.is_selected(i)) {
+                if single.selected() != first {
+                    single.set_selected(first);
+                }
+            }
+        });
+        // Rows selected from code, or right-clicked outside the selection
+        let weak_multi = multi.downgrade();
+        self.selection.connect_selected_notify(move |single| {
+            let Some(multi) = weak_multi.upgrade() else { return };
+            let position = single.selected();
+            if position != gtk::INVALID_LIST_POSITION && !multi.is_selected(position) {
+                multi.select_item(position, true);
+            }
+        });
+        *self.multi.borrow_mut() = Some(multi);
+    }
+
+    fn row_menu(&self) -> RowMenu {
+        RowMenu {
+            popover: self.popover.clone(),
+            selection: self.selection.clone(),
+            view: self.view.downgrade(),
+        }
+    }
+
+    pub fn add_column(
+        &self,
+        title: &str,
+        text: impl Fn(&T) -> String + 'static,
+        cmp: impl Fn(&T, &T) -> Ordering + 'static,
+    ) -> ColumnViewColumn {
+        let text = Rc::new(text);
+        let bind_text = text.clone();
+        let factory = SignalListItemFactory::new();
+        let row_menu = self.row_menu();
+        factory.connect_setup(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let label = Label::new(None);
+            label.set_xalign(0.0);
+            item.set_child(Some(&label));
+            row_menu.attach(&label, item);
+        });
+        factory.connect_bind(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let row = item.item().and_downcast::<glib::BoxedAnyObject>().unwrap();
+            let label = item.child().and_downcast::<Label>().unwrap();
+            label.set_text(&bind_text(&row.borrow::<T>()));
+        });
+
+        self.append(title, factory, text, cmp)
+    }
+
+    /// Like `add_column`, but the cell shows `markup`, e.g. to highlight
+    /// search matches. `text` is still what is sorted on and copied.
+    pub fn add_markup_column(
+        &self,
+        title: &str,
+        text: impl Fn(&T) -> String + 'static,
+        markup: impl Fn(&T) -> String + 'static,
+        cmp: impl Fn(&T, &T) -> Ordering + 'static,
+    ) -> ColumnViewColumn {
+        let factory = SignalListItemFactory::new();
+        let row_menu = self.row_menu();
+        factory.connect_setup(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let label = Label::new(None);
+            label.set_xalign(0.0);
+            item.set_child(Some(&label));
+            row_menu.attach(&label, item);
+        });
+        factory.connect_bind(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let row = item.item().and_downcast::<glib::BoxedAnyObject>().unwrap();
+            let label = item.child().and_downcast::<Label>().unwrap();
+            label.set_markup(&markup(&row.borrow::<T>()));
+        });
+
+        self.append(title, factory, Rc::new(text), cmp)
+    }
+
+    /// Like `add_column`, but the cell can be edited in place. `edit` gets
+    /// the row and the new text; if it fails the cell shows the old value
+    /// again. Reporting the error is up to `edit`.
+    pub fn add_editable_column(
+        &self,
+        title: &str,
+        text: impl Fn(&T) -> String + 'static,
+        cmp: impl Fn(&T, &T) -> Ordering + 'static,
+        edit: impl Fn(&T, &str) -> Result<(), String> + 'static,
+    ) -> ColumnViewColumn {
+        let text = Rc::new(text);
+        let edit = Rc::new(edit);
+
+        let factory = SignalListItemFactory::new();
+        let setup_text = text.clone();
+        let bind_text = text.clone();
+        let row_menu = self.row_menu();
+        factory.connect_setup(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let label = gtk::EditableLabel::new("");
+            item.set_child(Some(&label));
+            row_menu.attach(&label, item);
+
+            let text = setup_text.clone();
+            let edit = edit.clone();
+            let item = item.downgrade();
+            label.connect_editing_notify(move |label| {
+                if label.is_editing() {
+                    return;
+                }
+                let Some(row) = item.upgrade().and_then(|i| i.item()).and_downcast::<glib::BoxedAnyObject>() else { return };
+                let row = row.borrow::<T>();
+                let old = text(&row);
+                let new = label.text();
+                if new != old && edit(&row, &new).is_err() {
+                    label.set_text(&old);
+                }
+            });
+        });
+        factory.connect_bind(move |_, item| {
+            let item = item.downcast_ref::<gtk::ListItem>().unwrap();
+            let row = item.item().and_downcast::<glib::BoxedAnyObject>().unwrap();
+            let label = item.child().and_downcast::<gtk::EditableLabel>().unwrap();
+            label.set_text(&bind_text(&row.borrow::<T>()));
+        });
+
+        self.append(title, factory, text, cmp)
+    }
+
+    fn append(
+        &self,
+        title: &str,
+        factory: SignalListItemFactory,
+        text: Rc<dyn Fn(&T) -> String>,
+        cmp: impl Fn(&T, &T) -> Ordering + 'static,
+    ) -> ColumnViewColumn {
+        let sorter = gtk::CustomSorter::new(move |a, b| {
+            let a = a.downcast_ref::<glib::BoxedAnyObject>().unwrap();
+            let b = b.downcast_ref::<glib::BoxedAnyObject>().unwrap();
+            cmp(&a.borrow::<T>(), &b.borrow::<T>()).into()
+        });
+
+        let column = ColumnViewColumn::new(Some(title), Some(factory));
+        column.set_sorter(Some(&sorter));
+        column.set_resizable(true);
+        self.view.append_column(&column);
+        self.columns.borrow_mut().push((column.clone(), text));
+        column
+    }
+
+    pub fn set_rows(&self, rows: impl IntoIterator<Item = T>) {
+        let rows: Vec<glib::BoxedAnyObject> = rows.into_iter().map(glib::BoxedAnyObject::new).collect();
+        self.store.splice(0, self.store.n_items(), &rows);
+    }
+
+    /// Replaces the rows `update` returns a new version of, leaving the
+    /// others alone. Only the replaced rows are redrawn, and the selection
+    /// stays where it was.
+    pub fn update_rows(&self, update: impl Fn(&T) -> Option<T>) {
+        for position in 0..self.store.n_items() {
+            let Some(row) = self.store.item(position).and_downcast::<glib::BoxedAnyObject>() else { continue };
+            let Some(new) = update(&row.borrow::<T>()) else { continue };
+            *row.borrow_mut::<T>() = new;
+            self.store.items_changed(position, 1, 1);
+        }
+    }
+
+    /// Runs `f` on a row when it is double-clicked or activated with Enter.
+    pub fn connect_row_activated(&self, f: impl Fn(&T) + 'static) {
+        let selection = self.selection.clone();
+        self.view.connect_activate(move |_, position| {
+            if let Some(row) = selection.item(position).and_downcast::<glib::BoxedAnyObject>() {
+                f(&row.borrow::<T>());
+            }
+        });
+    }
+
+    pub fn selected<R>(&self, f: impl FnOnce(&T) -> R) -> Option<R> {
+        let row = self.selection.selected_item().and_downcast::<glib::BoxedAnyObject>()?;
+        let result = f(&row.borrow::<T>());
+        Some(result)
+    }
+
+    /// Runs `f` on every selected row, in the order shown. Without
+    /// set_multi_select that is just the selected row.
+    pub fn selected_rows<R>(&self, f: impl Fn(&T) -> R) -> Vec<R> {
+        let Some(multi) = self.multi.borrow().clone() else {
+            return self.selected(f).into_iter().collect();
+        };
+        (0..multi.n_items())
+            .filter(|&i| multi.is_selected(i))
+            .filter_map(|i| multi.item(i).and_downcast::<glib::BoxedAnyObject>())
+            .map(|row| f(&row.borrow::<T>()))
+            .collect()
+    }
+
+    /// The selected rows as shown, with a header line of the visible
+    /// columns' titles, separated by `delimiter` (b',' for CSV, b'\t' for
+    /// TSV).
+    pub fn selection_as_text(&self, delimiter: u8) -> Result<String, Box<dyn std::error::Error>> {
+        let columns: Vec<_> = self.columns.borrow().iter().filter(|(c, _)| c.is_visible()).cloned().collect();
+        let mut wtr = csv::WriterBuilder::new().delimiter(delimiter).from_writer(Vec::new());
+        wtr.write_record(columns.iter().map(|(c, _)| c.title().map(String::from).unwrap_or_default()))?;
+        for record in self.selected_rows(|row| columns.iter().map(|(_, text)| text(row)).collect::<Vec<_>>()) {
+            wtr.write_record(record)?;
+        }
+        let bytes = wtr.into_inner().map_err(|e| e.into_error())?;
+        Ok(String::from_utf8(bytes)?)
+    }
+}
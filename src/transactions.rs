@@ -0,0 +1,53 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Library;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TransactionKind {
+    Issue,
+    Return,
+    Renew,
+    FinePaid,
+    FineWaived,
+    // What a late return wasn't fined for the grace period, an amnesty or
+    // the fine cap
+    FineForgiven,
+    // Logged with item ID 0
+    MemberAnonymized,
+    // Stocktake results, logged with member ID 0
+    CopyLost,
+    CopyFound,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Transaction {
+    pub at: DateTime<Utc>,
+    pub kind: TransactionKind,
+    pub item_id: u32,
+    pub member_id: u32,
+    // Staff member logged in when the transaction happened
+    pub user: String,
+    // Only set for fine payments, waivers and forgiven fines
+    #[serde(default)]
+    pub amount_cents: u64,
+}
+
+impl Library {
+    pub fn log_transaction(&mut self, kind: TransactionKind, item_id: u32, member_id: u32) {
+        self.log_fine(kind, item_id, member_id, 0);
+    }
+
+    pub fn log_fine(&mut self, kind: TransactionKind, item_id: u32, member_id: u32, amount_cents: u64) {
+        tracing::info!(item_id, member_id, amount_cents, user = %self.current_user, "{:?}", kind);
+        self.transactions.push(Transaction {
+            at: self.clock.now(),
+            kind,
+            item_id,
+            member_id,
+            user: self.current_user.clone(),
+            amount_cents,
+        });
+        self.record_receipt(kind, item_id, member_id, amount_cents);
+    }
+}
@@ -0,0 +1,110 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::Library;
+use crate::copies::CopyStatus;
+use crate::i18n::tr;
+
+// Branch of items imported or saved before branches were tracked
+pub const DEFAULT_BRANCH: &str = "Main";
+
+pub fn default_branch() -> String {
+    DEFAULT_BRANCH.to_string()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TransferStatus {
+    Requested,
+    InTransit,
+    Received,
+}
+
+/// Moving an item record from one branch to another.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Transfer {
+    pub id: u32,
+    pub item_id: u32,
+    pub from: String,
+    pub to: String,
+    pub status: TransferStatus,
+    pub requested_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl Library {
+    pub fn in_transit(&self, item_id: u32) -> bool {
+        self.transfers
+            .iter()
+            .any(|t| t.item_id == item_id && t.status == TransferStatus::InTransit)
+    }
+
+    pub fn request_transfer(&mut self, item_id: u32, from: &str, to: &str) -> Result<u32, String> {
+        self.only_at_server()?;
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        let to = to.trim();
+        if to.is_empty() {
+            return Err(tr!("Enter a destination branch"));
+        }
+        if item.branch != from {
+            return Err(tr!("Item is held at {}, not {}", item.branch, from));
+        }
+        if from == to {
+            return Err(tr!("Item is already at that branch"));
+        }
+        if self.transfers.iter().any(|t| t.item_id == item_id && t.status != TransferStatus::Received) {
+            return Err(tr!("Item already has an open transfer"));
+        }
+
+        let now = self.clock.now();
+        let id = self.transfers.iter().map(|t| t.id).max().unwrap_or(0) + 1;
+        self.transfers.push(Transfer {
+            id,
+            item_id,
+            from: from.to_string(),
+            to: to.to_string(),
+            status: TransferStatus::Requested,
+            requested_at: now,
+            updated_at: now,
+        });
+        tracing::info!(transfer_id = id, item_id, from, to, "Transfer requested");
+        self.dirty = true;
+        Ok(id)
+    }
+
+    /// Moves a transfer to its next status. Sending it takes the copies on
+    /// the shelf along; receiving it puts the item at the destination branch
+    /// and the copies back on the shelf.
+    pub fn advance_transfer(&mut self, transfer_id: u32) -> Result<TransferStatus, String> {
+        self.only_at_server()?;
+        let now = self.clock.now();
+        let transfer = self
+            .transfers
+            .iter_mut()
+            .find(|t| t.id == transfer_id)
+            .ok_or_else(|| tr!("Transfer not found"))?;
+        transfer.status = match transfer.status {
+            TransferStatus::Requested => TransferStatus::InTransit,
+            TransferStatus::InTransit => TransferStatus::Received,
+            TransferStatus::Received => return Err(tr!("Transfer has already been received")),
+        };
+        transfer.updated_at = now;
+
+        let status = transfer.status;
+        tracing::info!(transfer_id, item_id = transfer.item_id, "Transfer {:?}", status);
+        let (item_id, to) = (transfer.item_id, transfer.to.clone());
+        if let Some(item) = self.items.get_mut(&item_id) {
+            if status == TransferStatus::InTransit {
+                item.move_copies(CopyStatus::Available, CopyStatus::InTransit, item.avail_copies)?;
+            } else {
+                item.move_copies(CopyStatus::InTransit, CopyStatus::Available, item.copies_in(CopyStatus::InTransit))?;
+                item.branch = to;
+            }
+        }
+        self.item_changed(item_id);
+        if status == TransferStatus::Received {
+            self.fill_holds(item_id);
+        }
+        self.dirty = true;
+        Ok(status)
+    }
+}
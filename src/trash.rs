@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::changes::LibraryChange;
+use crate::copies::CopyStatus;
+use crate::fines::FineStatus;
+use crate::i18n::tr;
+use crate::transfers::TransferStatus;
+use crate::{Library, LiItem, Member};
+
+/// A deleted record, kept so the transactions and reading histories that
+/// mention it still make sense, and so it can be restored.
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Trashed<T> {
+    pub record: T,
+    pub deleted_at: DateTime<Utc>,
+    // Staff member who deleted it
+    pub deleted_by: String,
+}
+
+/// Deleted items and members, by ID. They are left out of the catalog, the
+/// member list and everything else until restored.
+#[derive(Default, Deserialize, Serialize)]
+pub struct Trash {
+    pub items: BTreeMap<u32, Trashed<LiItem>>,
+    pub members: BTreeMap<u32, Trashed<Member>>,
+}
+
+impl Library {
+    fn trashed<T>(&self, record: T) -> Trashed<T> {
+        Trashed { record, deleted_at: self.clock.now(), deleted_by: self.current_user.clone() }
+    }
+
+    /// An item, even if it has been deleted since, e.g. for the title of a
+    /// past transaction.
+    pub fn item_or_trashed(&self, item_id: u32) -> Option<&LiItem> {
+        self.items.get(&item_id).or_else(|| self.trash.items.get(&item_id).map(|t| &t.record))
+    }
+
+    /// A member, even if they have been deleted since, e.g. to remove their
+    /// personal data from the trash too.
+    pub(crate) fn member_or_trashed_mut(&mut self, member_id: u32) -> Option<&mut Member> {
+        self.members.get_mut(&member_id).or_else(|| self.trash.members.get_mut(&member_id).map(|t| &mut t.record))
+    }
+
+    /// Moves an item to the trash. Items with copies on loan or an open
+    /// transfer can't be deleted; holds on the item are cancelled and its
+    /// course reserves dropped.
+    pub fn delete_item(&mut self, item_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let item = self.items.get(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        if item.copies_in(CopyStatus::OnLoan) > 0 || self.members.values().any(|m| m.items.contains_key(&item_id)) {
+            return Err(tr!("Item has copies on loan"));
+        }
+        if self.transfers.iter().any(|t| t.item_id == item_id && t.status != TransferStatus::Received) {
+            return Err(tr!("Item has an open transfer"));
+        }
+        if !self.bound_in(item_id).is_empty() {
+            return Err(tr!("Other items are bound into this one"));
+        }
+        self.undo_stack = std::mem::take(&mut self.undo_stack).into_iter().filter_map(|e| e.without_item(item_id)).collect();
+        self.reserves.retain(|r| r.item_id != item_id);
+        self.cancel_item_holds(item_id);
+        let item = self.items.remove(&item_id).unwrap();
+        let trashed = self.trashed(item);
+        self.trash.items.insert(item_id, trashed);
+        tracing::info!(item_id, "Item moved to the trash");
+        self.dirty = true;
+        self.changed(LibraryChange::ItemRemoved(item_id));
+        Ok(())
+    }
+
+    /// Moves a member to the trash. Members with loans or unpaid fines
+    /// can't be deleted; their holds are cancelled.
+    pub fn delete_member(&mut self, member_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let member = self.members.get(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        if !member.items.is_empty() {
+            return Err(tr!("Member has items on loan"));
+        }
+        if member.fines.iter().any(|f| f.status == FineStatus::Outstanding) {
+            return Err(tr!("Member has unpaid fines"));
+        }
+        let holds: Vec<u32> = self.holds.iter().filter(|h| h.member_id == member_id && h.is_active()).map(|h| h.id).collect();
+        for hold_id in holds {
+            self.cancel_hold(hold_id)?;
+        }
+        let member = self.members.remove(&member_id).unwrap();
+        let trashed = self.trashed(member);
+        self.trash.members.insert(member_id, trashed);
+        tracing::info!(member_id, "Member moved to the trash");
+        self.dirty = true;
+        self.changed(LibraryChange::MemberRemoved(member_id));
+        Ok(())
+    }
+
+    /// Puts an item from the trash back in the catalog.
+    pub fn restore_item(&mut self, item_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if self.items.contains_key(&item_id) {
+            return Err(tr!("Another item has ID {} now", item_id));
+        }
+        let trashed = self.trash.items.remove(&item_id).ok_or_else(|| tr!("Item not found"))?;
+        self.items.insert(item_id, trashed.record);
+        tracing::info!(item_id, "Item restored from the trash");
+        self.dirty = true;
+        self.changed(LibraryChange::ItemAdded(item_id));
+        Ok(())
+    }
+
+    pub fn restore_member(&mut self, member_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if self.members.contains_key(&member_id) {
+            return Err(tr!("Another member has ID {} now", member_id));
+        }
+        let trashed = self.trash.members.remove(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        self.members.insert(member_id, trashed.record);
+        tracing::info!(member_id, "Member restored from the trash");
+        self.dirty = true;
+        self.changed(LibraryChange::MemberAdded(member_id));
+        Ok(())
+    }
+
+    /// Deletes an item in the trash for good. Not while anything still
+    /// refers to it as current, such as a loan or a transfer.
+    pub fn purge_item(&mut self, item_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        if !self.trash.items.contains_key(&item_id) {
+            return Err(tr!("Item not found"));
+        }
+        if self.members.values().any(|m| m.items.contains_key(&item_id)) {
+            return Err(tr!("Item has copies on loan"));
+        }
+        if self.transfers.iter().any(|t| t.item_id == item_id && t.status != TransferStatus::Received) {
+            return Err(tr!("Item has an open transfer"));
+        }
+        if self.holds.iter().any(|h| h.item_id == item_id && h.is_active()) {
+            return Err(tr!("Item has holds"));
+        }
+        self.trash.items.remove(&item_id);
+        tracing::info!(item_id, "Item purged");
+        self.dirty = true;
+        Ok(())
+    }
+
+    pub fn purge_member(&mut self, member_id: u32) -> Result<(), String> {
+        self.only_at_server()?;
+        let trashed = self.trash.members.get(&member_id).ok_or_else(|| tr!("Member not found"))?;
+        if !trashed.record.items.is_empty() {
+            return Err(tr!("Member has items on loan"));
+        }
+        if self.holds.iter().any(|h| h.member_id == member_id && h.is_active()) {
+            return Err(tr!("Member has holds"));
+        }
+        self.trash.members.remove(&member_id);
+        tracing::info!(member_id, "Member purged");
+        self.dirty = true;
+        Ok(())
+    }
+}
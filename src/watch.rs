@@ -0,0 +1,144 @@
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+use gtk::glib;
+use gtk::prelude::*;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use crate::i18n::tr;
+use crate::import::{CsvSchema, ImportReport};
+use crate::{status, LiItem, Library};
+
+// How often the watcher's events are looked at
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+// Files are usually written in several steps; wait until they have been
+// quiet this long before offering to reload
+const SETTLE_TIME: Duration = Duration::from_secs(2);
+
+impl Library {
+    /// Reads the catalog CSV at `path` again. Bibliographic details and copy
+    /// counts come from the file; what only the library knows (loans,
+    /// reviews, branch and shelving) is kept. Items missing from the file
+    /// stay in the catalog.
+    pub(crate) fn reload_catalog_csv(&mut self, path: &str) -> Result<ImportReport, Box<dyn std::error::Error>> {
+        self.only_at_server()?;
+        let old_items: HashMap<u32, LiItem> = self.items.clone();
+        let report = self.import_csv(path, &CsvSchema::default())?;
+        for (id, old) in old_items {
+            let Some(item) = self.items.get_mut(&id) else {
+                continue;
+            };
+            // Copies on loan or lost can't be in the file's available count
+            let out = old.copies.saturating_sub(old.avail_copies);
+            item.copies = item.copies.max(out);
+            item.avail_copies = item.copies - out;
+            item.lost_copies = old.lost_copies;
+            item.copy_statuses = old.copy_statuses;
+            item.conditions = old.conditions;
+            item.condition_notes = old.condition_notes;
+            item.reviews = old.reviews;
+            if item.tags.is_empty() {
+                item.tags = old.tags;
+            }
+            item.branch = old.branch;
+            item.call_number = old.call_number;
+            item.shelf_location = old.shelf_location;
+        }
+        Ok(report)
+    }
+}
+
+/// Watches the catalog CSV at `path` and, when another program changes it,
+/// offers to reload the catalog from it. Does nothing for an empty path.
+pub fn watch_catalog_csv(window: &gtk::Window, library: Arc<RwLock<Library>>, path: &str) {
+    if path.is_empty() {
+        return;
+    }
+    let path = path.to_string();
+    let file_name = Path::new(&path).file_name().map(|name| name.to_os_string());
+    // The directory is watched rather than the file, as programs often
+    // replace a file instead of writing to it
+    let dir = match Path::new(&path).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => Path::new(".").to_path_buf(),
+    };
+
+    let (sender, changes) = mpsc::channel();
+    let mut watcher = match notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        let Ok(event) = event else {
+            return;
+        };
+        if matches!(event.kind, EventKind::Access(_)) {
+            return;
+        }
+        if event.paths.iter().any(|p| p.file_name().map(|name| name.to_os_string()) == file_name) {
+            let _ = sender.send(());
+        }
+    }) {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            tracing::warn!(path, "Failed to watch the catalog file: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        tracing::warn!(path, "Failed to watch the catalog file: {}", e);
+        return;
+    }
+    tracing::info!(path, "Watching the catalog file");
+
+    let asking = Rc::new(Cell::new(false));
+    let mut last_change: Option<Instant> = None;
+    glib::timeout_add_local(POLL_INTERVAL, glib::clone!(
+        #[weak]
+        window,
+        #[upgrade_or]
+        glib::ControlFlow::Break,
+        move || {
+            // The watcher stops when dropped, so the timer keeps it
+            let _ = &watcher;
+            if changes.try_iter().count() > 0 {
+                last_change = Some(Instant::now());
+            }
+            if !asking.get() && last_change.is_some_and(|at| at.elapsed() >= SETTLE_TIME) {
+                last_change = None;
+                asking.set(true);
+                ask_to_reload(&window, library.clone(), path.clone(), asking.clone());
+            }
+            glib::ControlFlow::Continue
+        }
+    ));
+}
+
+fn ask_to_reload(window: &gtk::Window, library: Arc<RwLock<Library>>, path: String, asking: Rc<Cell<bool>>) {
+    let dialog = gtk::MessageDialog::builder()
+        .transient_for(window)
+        .modal(true)
+        .message_type(gtk::MessageType::Question)
+        .buttons(gtk::ButtonsType::YesNo)
+        .text(tr!("The catalog file changed on disk. Reload it?"))
+        .secondary_text(tr!("{} was changed by another program. Loans and other library records are kept.", path))
+        .build();
+    dialog.connect_response(glib::clone!(
+        #[weak]
+        window,
+        move |dialog, response| {
+            dialog.close();
+            asking.set(false);
+            if response != gtk::ResponseType::Yes {
+                return;
+            }
+            let result = library.write().unwrap().reload_catalog_csv(&path);
+            match result {
+                Ok(report) => status::success(&window, &tr!("Catalog reloaded: {} items read, {} skipped", report.loaded, report.skipped)),
+                Err(e) => status::error(&window, &tr!("Failed to reload the catalog: {}", e)),
+            }
+        }
+    ));
+    dialog.show();
+}
@@ -0,0 +1,201 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use gtk::glib;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::config::Config;
+use crate::i18n::tr;
+use crate::Library;
+
+// Tries per payload before it is given up, waiting twice as long after
+// each failure, starting from RETRY_SECONDS
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_SECONDS: u64 = 5;
+const TIMEOUT_SECONDS: u64 = 10;
+// How often loans are checked for having become overdue
+const OVERDUE_CHECK_SECONDS: u32 = 300;
+// How often the events of finished changes are handed to the queues
+const FLUSH_SECONDS: u32 = 1;
+
+/// Circulation events webhooks can be sent for.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WebhookEvent {
+    Issue,
+    Return,
+    HoldReady,
+    Overdue,
+}
+
+impl WebhookEvent {
+    pub const ALL: [WebhookEvent; 4] = [WebhookEvent::Issue, WebhookEvent::Return, WebhookEvent::HoldReady, WebhookEvent::Overdue];
+
+    // As sent in the payload's "event" field
+    fn name(self) -> &'static str {
+        match self {
+            WebhookEvent::Issue => "issue",
+            WebhookEvent::Return => "return",
+            WebhookEvent::HoldReady => "hold_ready",
+            WebhookEvent::Overdue => "overdue",
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            WebhookEvent::Issue => tr!("Issue"),
+            WebhookEvent::Return => tr!("Return"),
+            WebhookEvent::HoldReady => tr!("Hold ready"),
+            WebhookEvent::Overdue => tr!("Overdue"),
+        }
+    }
+}
+
+/// A queue of payloads for each webhook URL, each posted in order from a
+/// thread of its own, so a slow or failing endpoint holds up neither the
+/// desk nor the other endpoints.
+pub struct Webhooks {
+    queues: Vec<Sender<String>>,
+    events: Vec<WebhookEvent>,
+}
+
+// Server errors, rate limits and network trouble are worth another try;
+// any other refusal won't change
+fn retryable(error: &ureq::Error) -> bool {
+    match error {
+        ureq::Error::Status(code, _) => *code == 429 || *code >= 500,
+        ureq::Error::Transport(_) => true,
+    }
+}
+
+fn deliver(url: &str, body: &str) {
+    let mut wait = Duration::from_secs(RETRY_SECONDS);
+    for attempt in 1..=MAX_ATTEMPTS {
+        let result = ureq::post(url)
+            .timeout(Duration::from_secs(TIMEOUT_SECONDS))
+            .set("Content-Type", "application/json")
+            .send_string(body);
+        match result {
+            Ok(_) => return,
+            Err(e) if retryable(&e) && attempt < MAX_ATTEMPTS => {
+                tracing::warn!(attempt, "Webhook to {} failed, retrying in {:?}: {}", url, wait, e);
+                std::thread::sleep(wait);
+                wait *= 2;
+            }
+            Err(e) => {
+                tracing::error!(attempt, "Webhook to {} failed, giving up: {}", url, e);
+                return;
+            }
+        }
+    }
+}
+
+impl Webhooks {
+    fn start(urls: &[String], events: &[WebhookEvent]) -> Webhooks {
+        let queues = urls
+            .iter()
+            .map(|url| {
+                let (tx, rx) = mpsc::channel::<String>();
+                let url = url.clone();
+                std::thread::spawn(move || {
+                    for body in rx {
+                        deliver(&url, &body);
+                    }
+                });
+                tx
+            })
+            .collect();
+        Webhooks { queues, events: events.to_vec() }
+    }
+}
+
+impl Library {
+    /// Holds back `event` for the webhooks, if any are set up for it, until
+    /// `flush_webhooks`. Only IDs and the title are sent, no member names.
+    /// Changes replayed from another desk post nothing, as that desk posts
+    /// them itself.
+    pub(crate) fn webhook(&mut self, event: WebhookEvent, item_id: u32, member_id: u32) {
+        let Some(webhooks) = &self.webhooks else { return };
+        if !webhooks.events.contains(&event) || self.replaying {
+            return;
+        }
+        let title = self.items.get(&item_id).map(|i| i.title.to_string());
+        let due_date = self.members.get(&member_id).and_then(|m| m.items.get(&item_id)).map(|inst| inst.due_date);
+        let body = json!({
+            "event": event.name(),
+            "at": self.clock.now(),
+            "item_id": item_id,
+            "title": title,
+            "member_id": member_id,
+            "due_date": due_date,
+        })
+        .to_string();
+        self.pending_webhooks.push(body);
+    }
+
+    /// Sends the events held back so far. Called between changes, with the
+    /// library locked, so a change that is rolled back (see
+    /// `book_issue_batch`) has already dropped its events.
+    pub(crate) fn flush_webhooks(&mut self) {
+        let Some(webhooks) = &self.webhooks else { return };
+        for body in self.pending_webhooks.drain(..) {
+            for queue in &webhooks.queues {
+                // The thread only ends with the queue, so this can't fail
+                let _ = queue.send(body.clone());
+            }
+        }
+    }
+}
+
+/// Sends the configured webhooks from now on, and checks every few minutes
+/// for loans that have become overdue since the last check.
+pub fn start(library: Arc<RwLock<Library>>, config: Rc<RefCell<Config>>) {
+    let urls = config.borrow().webhook_urls.clone();
+    if urls.is_empty() {
+        return;
+    }
+    let events = config.borrow().webhook_events.clone();
+    library.write().unwrap().webhooks = Some(Arc::new(Webhooks::start(&urls, &events)));
+    glib::timeout_add_seconds_local(FLUSH_SECONDS, glib::clone!(
+        #[strong]
+        library,
+        move || {
+            library.write().unwrap().flush_webhooks();
+            glib::ControlFlow::Continue
+        }
+    ));
+    if !events.contains(&WebhookEvent::Overdue) {
+        return;
+    }
+
+    let check = move || {
+        let mut lib = library.write().unwrap();
+        let now = lib.clock.now();
+        // Loans already overdue when the webhooks were set up don't count
+        let since = config.borrow().webhook_overdue_checked.unwrap_or(now);
+        let overdue: Vec<(u32, u32)> = lib
+            .members
+            .values()
+            .flat_map(|m| m.items.values().map(move |inst| (m.id, inst)))
+            .filter(|(_, inst)| inst.due_date > since && inst.due_date <= now)
+            .map(|(member_id, inst)| (inst.id, member_id))
+            .collect();
+        for (item_id, member_id) in overdue {
+            lib.webhook(WebhookEvent::Overdue, item_id, member_id);
+        }
+        lib.flush_webhooks();
+        drop(lib);
+        config.borrow_mut().webhook_overdue_checked = Some(now);
+        if let Err(e) = config.borrow().save() {
+            tracing::error!("Failed to save settings: {}", e);
+        }
+    };
+    check();
+    glib::timeout_add_seconds_local(OVERDUE_CHECK_SECONDS, move || {
+        check();
+        glib::ControlFlow::Continue
+    });
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use chrono::{DateTime, Datelike, Months, Utc};
+
+use crate::transactions::TransactionKind;
+use crate::Library;
+
+// Years without a loan before an item is suggested for weeding
+pub const DEFAULT_WEEDING_YEARS: u32 = 3;
+
+/// An item that hasn't circulated recently.
+#[derive(Clone)]
+pub struct WeedingCandidate {
+    pub item_id: u32,
+    pub title: Arc<str>,
+    pub author: Option<String>,
+    // Publication year, 0 when unknown
+    pub year: u32,
+    pub added_at: Option<DateTime<Utc>>,
+    pub copies: u32,
+    pub loans: usize,
+    // None for items that were never borrowed
+    pub last_borrowed: Option<DateTime<Utc>>,
+}
+
+impl WeedingCandidate {
+    /// Years since publication, if the year is known.
+    pub fn age(&self, now: DateTime<Utc>) -> Option<i32> {
+        (self.year > 0).then(|| now.year() - self.year as i32)
+    }
+}
+
+impl Library {
+    /// Items not borrowed in the last `years` years, never borrowed ones
+    /// first and then the longest idle. Items added within that time are
+    /// left out, as they haven't had the chance to circulate yet.
+    pub fn weeding_candidates(&self, years: u32) -> Vec<WeedingCandidate> {
+        let now = self.clock.now();
+        let Some(cutoff) = now.checked_sub_months(Months::new(years * 12)) else { return Vec::new() };
+
+        let mut loans: HashMap<u32, (usize, DateTime<Utc>)> = HashMap::new();
+        for tx in self.transactions.iter().filter(|tx| tx.kind == TransactionKind::Issue) {
+            let entry = loans.entry(tx.item_id).or_insert((0, tx.at));
+            entry.0 += 1;
+            entry.1 = entry.1.max(tx.at);
+        }
+
+        let mut candidates: Vec<WeedingCandidate> = self
+            .items
+            .values()
+            .filter(|item| !item.added_at.is_some_and(|at| at >= cutoff))
+            .filter_map(|item| {
+                let (count, last) = match loans.get(&item.id) {
+                    Some(&(count, last)) => (count, Some(last)),
+                    None => (0, None),
+                };
+                if last.is_some_and(|at| at >= cutoff) {
+                    return None;
+                }
+                Some(WeedingCandidate {
+                    item_id: item.id,
+                    title: item.title.clone(),
+                    author: item.author.clone(),
+                    year: item.year,
+                    added_at: item.added_at,
+                    copies: item.copies,
+                    loans: count,
+                    last_borrowed: last,
+                })
+            })
+            .collect();
+        // None sorts before Some
+        candidates.sort_by(|a, b| a.last_borrowed.cmp(&b.last_borrowed).then(a.item_id.cmp(&b.item_id)));
+        candidates
+    }
+
+    pub fn export_weeding_csv(&self, years: u32, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let now = self.clock.now();
+        let mut wtr = csv::Writer::from_path(path)?;
+        wtr.write_record(["id", "title", "author", "year", "age", "added_at", "copies", "loans", "last_borrowed"])?;
+        for c in self.weeding_candidates(years) {
+            wtr.write_record([
+                c.item_id.to_string(),
+                c.title.to_string(),
+                c.author.clone().unwrap_or_default(),
+                c.year.to_string(),
+                c.age(now).map_or(String::new(), |age| age.to_string()),
+                c.added_at.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+                c.copies.to_string(),
+                c.loans.to_string(),
+                c.last_borrowed.map_or(String::new(), |at| at.format("%Y-%m-%d").to_string()),
+            ])?;
+        }
+        wtr.flush()?;
+        Ok(())
+    }
+}